@@ -273,6 +273,18 @@ pub fn log(target: &str, level: LevelFmt, args: core::fmt::Arguments) -> Result<
         .log(target, level, args)
 }
 
+/// Like [`log`], but returns [`None`] instead of panicking if the logger is not initialized or
+/// its lock is poisoned.
+#[inline(always)]
+pub fn try_log(target: &str, level: LevelFmt, args: core::fmt::Arguments) -> Option<Result<bool>> {
+    Some(LOGGER
+        .get()?
+        .lock()
+        .ok()?
+        .log(target, level, args)
+    )
+}
+
 #[macro_export]
 macro_rules! error {
     ($fmt:expr $(, $arg:expr)* $(,)?) => {
@@ -128,6 +128,10 @@ pub enum ResourceType {
     UniformTexelBuffer,
     /// A storage buffer interpreted as an image.
     StorageTexelBuffer,
+    /// The payload a task shader passes to the mesh shader workgroups it launches.
+    TaskPayload,
+    /// An acceleration structure used for ray tracing and ray queries.
+    AccelerationStructure,
 }
 
 /// A hole created from the declaration of a runtime array.
@@ -692,13 +696,15 @@ impl<'a> Reflector<'a> {
             ResourceType::StorageBuffer => op::StorageClass::STORAGE_BUFFER,
             ResourceType::PushConstant => op::StorageClass::PUSH_CONSTANT,
             ResourceType::AtomicCounter => op::StorageClass::ATOMIC_COUNTER,
+            ResourceType::TaskPayload => op::StorageClass::TASK_PAYLOAD_WORKGROUP_EXT,
             ResourceType::InputAttachment
             | ResourceType::StorageImage
             | ResourceType::CombinedImageSampler
             | ResourceType::SeparateImage
             | ResourceType::SeparateSampler
             | ResourceType::UniformTexelBuffer
-            | ResourceType::StorageTexelBuffer => op::StorageClass::UNIFORM_CONSTANT,
+            | ResourceType::StorageTexelBuffer
+            | ResourceType::AccelerationStructure => op::StorageClass::UNIFORM_CONSTANT,
         };
         Ok(self.variables().map(move |op_variable| {
             let op_variable = op_variable?;
@@ -932,6 +938,21 @@ impl<'a> Reflector<'a> {
                         }
                         Ok(None)
                     },
+                    ResourceType::AccelerationStructure => {
+                        let stream = self.module
+                            .get_result(base_type)
+                            .ok_or(ReflectError::InvalidTypeId(base_type))?;
+                        if matches!(stream.code(), op::Code::TYPE_ACCELERATION_STRUCTURE_KHR) {
+                            Ok(Some(Resource {
+                                type_id: op_variable.result_type,
+                                base_type_id: base_type,
+                                variable_id: op_variable.id_result,
+                                name,
+                                count,
+                                offset: None,
+                            }))
+                        } else { Ok(None) }
+                    },
                     _ => unreachable!()
                 }
             } else {
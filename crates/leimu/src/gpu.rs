@@ -15,6 +15,8 @@ mod handle;
 mod shader;
 mod shader_set;
 mod descriptor;
+mod descriptor_buffer;
+mod bindless;
 mod attributes;
 mod subresource_state;
 mod pipeline;
@@ -26,6 +28,13 @@ mod swapchain;
 mod resources;
 mod commands;
 mod event;
+mod query;
+mod debug_utils;
+mod uploader;
+mod readback;
+mod headless;
+mod sparse;
+mod acceleration_structure;
 
 use core::{
     ops::Deref,
@@ -53,8 +62,9 @@ use nox_threads::{
 
 use crate::{
     error::*,
-    sync::{atomic::AtomicU64, *},
+    sync::{atomic::{self, AtomicU64, AtomicBool}, *},
     log,
+    expand_error,
 };
 
 pub(crate) mod prelude {
@@ -78,23 +88,35 @@ pub(crate) mod prelude {
         buffer::*,
         physical_device::*,
         resources::*,
+        query::*,
+        debug_utils::{DebugUtilsInfo, DEBUG_MESSAGE_TARGET},
         pipeline::*,
         commands::prelude::*,
         nox_proc::VertexInput,
         shader::*,
         super::shader_set::*,
         super::descriptor::*,
+        bindless::*,
         pipeline::vertex_input::*,
         super::memory_binder::*,
         interface::*,
         super::ext,
         surface::VulkanWindow,
+        swapchain::{SwapchainPreferences, SwapchainInfo},
         super::event::Event,
+        super::uploader::{Uploader, Upload},
+        super::readback::{Readback, PendingReadback},
+        super::headless::{HeadlessTarget, HeadlessTargetInfo, HeadlessImage},
+        super::sparse::*,
+        super::acceleration_structure::*,
     };
 
     pub type DeviceName = ([u8; 256], usize);
 
     pub(crate) use surface::Surface;
+    pub(crate) use query::CommandQueries;
+    pub(crate) use debug_utils::CommandLabels;
+    pub(crate) use super::descriptor_buffer::*;
 
     pub(super) use swapchain::Swapchain;
     pub(super) use super::swapchain;
@@ -206,6 +228,10 @@ struct GpuInner {
     images: RwLock<SlotMap<ImageMeta>>,
     timeline_semaphores: RwLock<SlotMap<vk::Semaphore>>,
     draw_commands: RwLock<SlotMap<DrawCommandResource>>,
+    query_pools: RwLock<SlotMap<QueryPoolMeta>>,
+    memory_binders: Mutex<Vec32<(Arc<str>, Weak<dyn MemoryBinder>)>>,
+    readbacks: Mutex<Vec32<readback::ReadbackRequest>>,
+    blit_fallback: BlitFallback,
     tmp_allocs: Arc<TmpAllocs>,
     desired_buffered_frames: u32,
     device: LogicalDevice,
@@ -248,6 +274,7 @@ impl Gpu {
             thread_pool,
             queue_scheduler: OnceLock::new(),
             shader_cache: RwLock::new(ShaderCache::new(device.clone())),
+            blit_fallback: BlitFallback::new(device.clone()),
             surfaces: RwLock::new(SlotMap::new()),
             device,
             pipeline_batches: SwapLock::default(),
@@ -256,6 +283,9 @@ impl Gpu {
             buffers: RwLock::new(SlotMap::new()),
             timeline_semaphores: RwLock::new(SlotMap::new()),
             draw_commands: RwLock::new(SlotMap::new()),
+            query_pools: RwLock::new(SlotMap::new()),
+            memory_binders: Mutex::new(Vec32::new()),
+            readbacks: Mutex::new(Vec32::new()),
             tmp_allocs: Arc::new(TmpAllocs {
                 fallback_alloc: Arc::new(main_tmp_alloc),
                 tmp_allocs,
@@ -313,6 +343,11 @@ impl Gpu {
         self.inner.memory_layout
     }
 
+    #[inline]
+    pub(crate) fn blit_fallback(&self) -> &BlitFallback {
+        &self.inner.blit_fallback
+    }
+
     #[inline]
     pub(crate) fn thread_pool(&self) -> ThreadPool {
         self.inner.thread_pool.clone()
@@ -424,22 +459,229 @@ impl Gpu {
         })
     }
 
+    /// Gets the sparse block layout of each aspect of `format` for sparse residency images.
+    ///
+    /// Returns an empty vector, if the image parameters don't support sparse residency.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetPhysicalDeviceSparseImageFormatProperties2.html>
+    pub fn get_sparse_image_format_properties(
+        &self,
+        format: Format,
+        usage: ImageUsages,
+        samples: MsaaSamples,
+        is_3d: bool,
+    ) -> Vec32<SparseImageFormatProperties>
+    {
+        let format_info = vk::PhysicalDeviceSparseImageFormatInfo2 {
+            format: format.into(),
+            ty: if is_3d {
+                    vk::ImageType::TYPE_3D
+                } else {
+                    vk::ImageType::TYPE_2D
+                },
+            samples: samples.into(),
+            usage: usage.into(),
+            tiling: vk::ImageTiling::OPTIMAL,
+            ..Default::default()
+        };
+        let instance = self.inner.device.instance().ash();
+        let physical_device = self.inner.device.physical_device().handle();
+        unsafe {
+            let mut properties = Vec32::with_len(
+                instance.get_physical_device_sparse_image_format_properties2_len(
+                    physical_device, &format_info,
+                ) as u32,
+                Default::default(),
+            );
+            instance.get_physical_device_sparse_image_format_properties2(
+                physical_device, &format_info, &mut properties,
+            );
+            properties
+                .iter()
+                .map(|p: &vk::SparseImageFormatProperties2| p.properties.into())
+                .collect()
+        }
+    }
+
+    /// Gets the sparse memory requirements of each aspect of a sparse image.
+    ///
+    /// # Valid usage
+    /// - `id` *must* be a valid [`ImageId`] of a sparse image.
+    pub fn get_image_sparse_memory_requirements(
+        &self,
+        id: ImageId,
+    ) -> Result<Vec32<SparseImageMemoryRequirements>>
+    {
+        let images = self.read_images();
+        let requirements = images
+            .get(id)?
+            .sparse_requirements()
+            .ok_or_else(|| Error::just_context(format!(
+                "image {id} is not sparse"
+            )))?;
+        Ok(requirements.iter().copied().collect())
+    }
+
+    /// Gets the [`memory properties`][1] of a sparse buffer.
+    ///
+    /// # Valid usage
+    /// - `id` *must* be a valid [`BufferId`] of a sparse buffer.
+    ///
+    /// [1]: SparseMemoryProperties
+    pub fn get_buffer_sparse_properties(
+        &self,
+        id: BufferId,
+    ) -> Result<SparseMemoryProperties>
+    {
+        self.read_buffers()
+            .get(id)?
+            .sparse_memory()
+            .map(|memory| memory.properties())
+            .ok_or_else(|| Error::just_context(format!(
+                "buffer {id} is not sparse"
+            )))
+    }
+
+    /// Gets the [`memory properties`][1] of a sparse image.
+    ///
+    /// # Valid usage
+    /// - `id` *must* be a valid [`ImageId`] of a sparse image.
+    ///
+    /// [1]: SparseMemoryProperties
+    pub fn get_image_sparse_properties(
+        &self,
+        id: ImageId,
+    ) -> Result<SparseMemoryProperties>
+    {
+        self.read_images()
+            .get(id)?
+            .sparse_memory()
+            .map(|memory| memory.properties())
+            .ok_or_else(|| Error::just_context(format!(
+                "image {id} is not sparse"
+            )))
+    }
+
+    /// Gets the [`format features`][1] supported by `format` for buffers.
+    ///
+    /// [1]: FormatFeatures
+    pub fn get_buffer_format_features(
+        &self,
+        format: Format,
+    ) -> FormatFeatures
+    {
+        let mut format_properties3 = vk::FormatProperties3::default();
+        let mut format_properties = vk::FormatProperties2
+            ::default().push_next(&mut format_properties3);
+        unsafe {
+            self.inner.device.instance().ash().get_physical_device_format_properties2(
+                self.inner.device.physical_device().handle(),
+                format.into(), &mut format_properties,
+            );
+        }
+        FormatFeatures::from_raw(
+            format_properties3.buffer_features.as_raw()
+        )
+    }
+
     pub fn desired_buffered_frames(&self) -> u32 {
         self.inner.desired_buffered_frames
     }
 
+    /// Registers `binder` to be included in [`memory statistics`][1] under `name`.
+    ///
+    /// Only a weak reference is kept, so the binder is removed once it's dropped.
+    ///
+    /// [1]: Self::memory_statistics
+    pub fn register_memory_binder<B: MemoryBinder>(&self, name: &str, binder: &Arc<B>) {
+        let binder: Arc<dyn MemoryBinder> = binder.clone();
+        self.inner.memory_binders
+            .lock()
+            .push((name.into(), Arc::downgrade(&binder)));
+    }
+
+    /// Returns a snapshot of the current memory usage.
+    ///
+    /// Heap usage and budgets come from [`VK_EXT_memory_budget`][1] when it's enabled and from
+    /// the device's own accounting otherwise. See [`LogicalDevice::memory_heap_budgets`].
+    ///
+    /// Binder statistics only include binders [`registered`][2] with this [`Gpu`].
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_memory_budget.html
+    /// [2]: Self::register_memory_binder
+    pub fn memory_statistics(&self) -> MemoryStatistics {
+        let mut binders = self.inner.memory_binders.lock();
+        binders.retain(|(_, binder)| binder.strong_count() != 0);
+        MemoryStatistics {
+            heaps: self.inner.device.memory_heap_budgets(),
+            memory_types: self.inner.device.memory_type_statistics(),
+            binders: binders
+                .iter()
+                .filter_map(|(name, binder)| binder
+                    .upgrade()
+                    .map(|binder| (name.clone(), binder.statistics()))
+                ).collect(),
+        }
+    }
+
+    /// Creates a surface for `window` with the default [`SwapchainPreferences`].
+    #[inline]
     pub fn create_surface<H: VulkanWindow>(
         &self,
         window: Arc<H>,
+    ) -> Result<SurfaceId> {
+        self.create_surface_with_preferences(window, Default::default())
+    }
+
+    /// Creates a surface for `window`, whose swapchains are created with `preferences`.
+    pub fn create_surface_with_preferences<H: VulkanWindow>(
+        &self,
+        window: Arc<H>,
+        preferences: SwapchainPreferences,
     ) -> Result<SurfaceId> {
         let mut surfaces = self.inner.surfaces.write();
         Ok(SurfaceId(surfaces.insert(Surface::new(
             window,
             self.clone(),
             self.inner.desired_buffered_frames,
+            preferences,
         )?)))
     }
 
+    /// Sets the [`SwapchainPreferences`] of a surface.
+    ///
+    /// The swapchain is recreated with the new preferences, when the next image is acquired.
+    pub fn set_swapchain_preferences(
+        &self,
+        surface_id: SurfaceId,
+        preferences: SwapchainPreferences,
+    ) -> Result<()> {
+        self.inner.surfaces
+            .write()
+            .get_mut(surface_id.slot_index())
+            .context_with(|| format!(
+                "invalid surface id {surface_id}"
+            ))?.set_preferences(preferences);
+        Ok(())
+    }
+
+    /// Returns the present mode, format, color space, image count and extent chosen for the
+    /// current swapchain of a surface.
+    ///
+    /// Returns [`None`] if the swapchain has not been created yet.
+    pub fn swapchain_info(
+        &self,
+        surface_id: SurfaceId,
+    ) -> Result<Option<SwapchainInfo>> {
+        Ok(self.inner.surfaces
+            .read()
+            .get(surface_id.slot_index())
+            .context_with(|| format!(
+                "invalid surface id {surface_id}"
+            ))?.swapchain_info())
+    }
+
     pub fn request_swapchain_update(
         &self,
         surface_id: SurfaceId,
@@ -474,6 +716,8 @@ impl Gpu {
     ) -> Result<DrawCommandId>
         where F: FnOnce(&mut DrawCommands) -> EventResult<()>,
     {
+        ext::multiview::validate_view_mask(self.device(), info.view_mask)
+            .context("invalid draw command view mask")?;
         let alloc = Arena
             ::with_fallback(arena_size)
             .context("failed to create arena")?;
@@ -485,6 +729,7 @@ impl Gpu {
             p_color_attachment_formats: info.color_formats.as_ptr().cast(),
             depth_attachment_format: info.depth_format.into(),
             stencil_attachment_format: info.stencil_format.into(),
+            view_mask: info.view_mask,
             rasterization_samples: info.sample_count.into(),
             ..Default::default()
         };
@@ -504,12 +749,15 @@ impl Gpu {
                 .context("failed to command buffer")?;
         }
         let mut storage = DrawCommandStorage::new(
-            self.get_extension_device()
+            self.get_extension_device(),
+            self.get_extension_device(),
+            self.get_extension_device(),
         );
         storage.reinit(
             command_buffer,
             info.color_formats,
             info.depth_format, info.stencil_format, info.sample_count,
+            info.view_mask,
             &alloc
         )?;
         {
@@ -584,22 +832,9 @@ impl Gpu {
         let submits = self.queue_scheduler().record(
             &mut cache.command_cache, &mut event_handler, &cache.arena,
         ).context("failed to record commands")?;
-        for submit in &submits.submits {
-            let submit_info = vk::SubmitInfo2 {
-                wait_semaphore_info_count: submit.wait_semaphore_infos.len(),
-                p_wait_semaphore_infos: submit.wait_semaphore_infos.as_ptr(),
-                command_buffer_info_count: submit.command_buffer_infos.len(),
-                p_command_buffer_infos: submit.command_buffer_infos.as_ptr(),
-                signal_semaphore_info_count: submit.signal_semaphore_infos.len(),
-                p_signal_semaphore_infos: submit.signal_semaphore_infos.as_ptr(),
-                ..Default::default()
-            };
-            cache.submit_cache[submit.device_queue_index as usize]
-                .push(submit_info);
-        }
-        for (i, submits) in cache.submit_cache.iter_mut().enumerate() {
+        let submit_queue = |i: usize, submits: &mut Vec32<vk::SubmitInfo2<'static>>| -> Result<()> {
             if submits.is_empty() {
-                continue
+                return Ok(())
             }
             let queue = &self.inner.device.device_queues()[i];
             unsafe {
@@ -612,6 +847,34 @@ impl Gpu {
                 "failed to submit to queue {queue:?}"
             ))?;
             submits.clear();
+            Ok(())
+        };
+        for submit in &submits.submits {
+            let device_queue_index = submit.device_queue_index as usize;
+            if submit.sparse_binds.is_some() {
+                // earlier submits are submitted first to keep the submission order
+                for (i, submits) in cache.submit_cache.iter_mut().enumerate() {
+                    submit_queue(i, submits)?;
+                }
+                queue_bind_sparse(
+                    self, &self.inner.device.device_queues()[device_queue_index], submit,
+                )?;
+                continue
+            }
+            let submit_info = vk::SubmitInfo2 {
+                wait_semaphore_info_count: submit.wait_semaphore_infos.len(),
+                p_wait_semaphore_infos: submit.wait_semaphore_infos.as_ptr(),
+                command_buffer_info_count: submit.command_buffer_infos.len(),
+                p_command_buffer_infos: submit.command_buffer_infos.as_ptr(),
+                signal_semaphore_info_count: submit.signal_semaphore_infos.len(),
+                p_signal_semaphore_infos: submit.signal_semaphore_infos.as_ptr(),
+                ..Default::default()
+            };
+            cache.submit_cache[device_queue_index]
+                .push(submit_info);
+        }
+        for (i, submits) in cache.submit_cache.iter_mut().enumerate() {
+            submit_queue(i, submits)?;
         }
         for present_submit in &submits.present_submits {
             let mut present_id2 = vk::PresentId2KHR {
@@ -634,7 +897,8 @@ impl Gpu {
                 )
             }.context("queue present failed")?;
         }
-        Ok(())
+        self.poll_readbacks()
+            .context("failed to poll read backs")
     }
 
     pub fn destroy_surface(
@@ -662,7 +926,7 @@ impl Gpu {
     ) -> Result<ShaderSetId>
     {
         self.inner.shader_cache.write().create_shader_set(
-            shaders,
+            Arc::from(shaders),
             attributes,
             self.inner.thread_pool.clone(),
             self.inner.tmp_allocs.clone(),
@@ -685,6 +949,79 @@ impl Gpu {
         self.inner.shader_cache.write().delete_shader_set(id)
     }
 
+    /// Recompiles each shader of a shader set from its original source and rebuilds every
+    /// pipeline created from the shader set.
+    ///
+    /// The reloaded shader set *must* be compatible with the old one, meaning that its
+    /// [`descriptor set layouts`][1] and [`push constant ranges`][2] *must* not change. Otherwise
+    /// an error is returned and the old shader set and pipelines stay in use. The same applies,
+    /// if rebuilding any of the pipelines fails.
+    ///
+    /// Pipelines replaced by the reload are kept alive until every command buffer using them has
+    /// finished executing.
+    ///
+    /// # Valid usage
+    /// - `id` *must* be a valid [`ShaderSetId`].
+    ///
+    /// [1]: DescriptorSetLayout
+    /// [2]: PushConstantRange
+    pub fn reload_shader_set(&self, id: ShaderSetId) -> Result<()> {
+        let reload = self.inner.shader_cache
+            .read()
+            .reload_shader_set(
+                self, id,
+                self.inner.thread_pool.clone(),
+                self.inner.tmp_allocs.clone(),
+            )?;
+        let new = block_on(reload.load())
+            .context_with(|| format!("failed to reload shader set {id}"))?;
+        let old = block_on(self.get_shader_set(id))?;
+        old.check_compatible(&new)
+            .context_with(|| format!(
+                "reloaded shader set {id} is incompatible with the old shader set"
+            ))?;
+        let batches = self.inner.pipeline_batches.load();
+        let mut rebuilds = vec32![];
+        for batch in batches.values() {
+            if let Some(batch) = batch.get() {
+                let rebuild = block_on(batch.rebuild_shader_set_pipelines(self, id, &new))
+                    .context_with(|| format!("failed to rebuild pipelines of shader set {id}"))?;
+                rebuilds.push((batch, rebuild));
+            }
+        }
+        self.inner.shader_cache.write().finish_reload(reload)?;
+        for (batch, rebuild) in rebuilds {
+            block_on(batch.finish_rebuild(rebuild))?;
+        }
+        Ok(())
+    }
+
+    /// Reloads each shader set, which contains a shader created from a
+    /// [`file source`][1] that was modified since the last call.
+    ///
+    /// This *can* be called once per frame to hot reload shaders during development. Errors are
+    /// logged and shader sets, which fail to reload, keep using their old shaders until their
+    /// source is modified again.
+    ///
+    /// Returns the ids of the shader sets, which were reloaded successfully.
+    ///
+    /// See [`Gpu::reload_shader_set`] for more information.
+    ///
+    /// [1]: ShaderAttributes::with_glsl_file
+    pub fn reload_modified_shader_sets(&self) -> Vec32<ShaderSetId> {
+        let modified = self.inner.shader_cache
+            .read()
+            .poll_modified_shader_sets();
+        let mut reloaded = vec32![];
+        for id in modified {
+            match self.reload_shader_set(id) {
+                Ok(()) => reloaded.push(id),
+                Err(err) => { expand_error!(err); },
+            }
+        }
+        reloaded
+    }
+
     pub fn create_descriptor_pool(
         &self,
         pool_sizes: impl IntoIterator<Item = (DescriptorType, u32)>,
@@ -784,6 +1121,8 @@ impl Gpu {
                 "invalid pool id {pool_id}"
             ))?;
         let mut pool = pool.write();
+        // descriptor buffer backed pools write descriptors directly in update and copy_from
+        let uses_descriptor_buffer = pool.descriptor_buffer.is_some();
         let mut unpoison = FixedVec32::with_capacity(
             writes.len() as u32 + copies.len() as u32,
             &tmp_alloc,
@@ -819,6 +1158,9 @@ impl Gpu {
                     "failed to update descriptor set {}",
                     write.set_id,
                 ))?;
+            if uses_descriptor_buffer {
+                continue
+            }
             descriptor_infos.push(infos);
             let last = unsafe {
                 descriptor_infos.last_mut().unwrap_unchecked()
@@ -840,6 +1182,21 @@ impl Gpu {
                     write.descriptor_count = images.len();
                     write.p_image_info = images.as_ptr();
                 },
+                DescriptorUpdateInfos::TexelBuffer(views) => {
+                    write.descriptor_count = views.len();
+                    write.p_texel_buffer_view = views.as_ptr();
+                },
+                DescriptorUpdateInfos::AccelerationStructure(handles, info) => {
+                    write.descriptor_count = handles.len();
+                    *info = vk::WriteDescriptorSetAccelerationStructureKHR {
+                        s_type: vk::StructureType::WRITE_DESCRIPTOR_SET_ACCELERATION_STRUCTURE_KHR,
+                        acceleration_structure_count: handles.len(),
+                        p_acceleration_structures: handles.as_ptr(),
+                        ..Default::default()
+                    };
+                    // Safe because FixedVec doesn't reallocate
+                    write.p_next = info as *const _ as *const core::ffi::c_void;
+                },
                 DescriptorUpdateInfos::InlineUniformBlock(info) => {
                     write.descriptor_count = info.data_size;
                     // Safe because FixedVec doesn't reallocate
@@ -890,6 +1247,9 @@ impl Gpu {
                 "failed to copy source descriptor set {} to destination descriptor set resource {}",
                 copy.src_set_id, copy.dst_set_id,
             ))?; 
+            if uses_descriptor_buffer {
+                continue
+            }
             unpoison.push(dst.into_inner());
             vk_copies.push(vk_copy);
         }
@@ -1009,6 +1369,31 @@ impl Gpu {
         })
     } 
 
+    /// Destroys ray tracing pipelines from a given pipeline batch.
+    ///
+    /// # Valid usage
+    /// - This *should* only be called from the main thread.
+    /// - `batch_id` *must* be a valid [`PipelineBatchId`].
+    /// - Each id in `ids` *must* be a valid [`RayTracingPipelineId`] and *must* have originated
+    ///   from the specified batch.
+    pub fn destroy_ray_tracing_pipelines(
+        &self,
+        batch_id: PipelineBatchId,
+        ids: impl IntoIterator<
+            IntoIter = impl ExactSizeIterator<Item = RayTracingPipelineId>
+        >,
+    ) -> Result<()>
+    {
+        self.inner.pipeline_batches.modify(|batches| {
+            let batch = batches
+                .get_mut(batch_id.slot_index())
+                .context_with(|| format!(
+                    "invalid pipeline batch id {batch_id}"
+                ))?.get().unwrap();
+            block_on(batch.destroy_ray_tracing_pipelines(ids.into_iter()))
+        })
+    }
+
     #[inline]
     pub fn get_pipeline_batch(
         &self,
@@ -1067,6 +1452,25 @@ impl Gpu {
             ))
     }
 
+    pub async fn get_ray_tracing_pipeline<'a>(
+        &self,
+        id: RayTracingPipelineId,
+    ) -> Result<impl Deref<Target = RayTracingPipeline> + use<'a>>
+    {
+        self.inner.pipeline_batches
+            .load()
+            .try_map(|batches| {
+                batches
+                    .get(id.batch_id().slot_index())
+            }).context_with(|| format!(
+                "invalid pipeline batch id {}", id.batch_id()
+            ))?.get().unwrap()
+            .get_ray_tracing_pipeline(id.pipeline_id()).await
+            .context_with(|| format!(
+                "invalid ray tracing pipeline id {id}"
+            ))
+    }
+
     #[inline]
     pub fn is_buffer_valid(&self, id: BufferId) -> bool {
         self.inner.buffers.read().contains(id.0)
@@ -1079,6 +1483,8 @@ impl Gpu {
     /// [`BufferId`]s and [`ImageId`]s are returned to their respective [`BufferCreateInfo`]s and
     /// [`ImageCreateInfo`]s.
     ///
+    /// The memory of sparse resources is not bound here, see [`SparseBindCommands`].
+    ///
     /// # Valid usage
     /// - The valid usage of buffer and image create infos are described in [`ImageCreateInfo`] and
     ///   [`BufferCreateInfo`] respectively.
@@ -1126,7 +1532,9 @@ impl Gpu {
             let mut bind_info = Default::default();
             let buffer_meta = create_info.build(self.device().clone(), &mut bind_info)
                 .context_with(|| format!("failed to create buffer at index {i}"))?;
-            buffer_bind_infos.push(bind_info);
+            if !buffer_meta.is_sparse() {
+                buffer_bind_infos.push(bind_info);
+            }
             let id = BufferId::new(
                 unsafe { &mut *buffers.get() }.insert(buffer_meta)
             );
@@ -1137,7 +1545,9 @@ impl Gpu {
             let mut bind_info = Default::default();
             let image_meta = create_info.build(self.device().clone(), &mut bind_info)
                 .context_with(|| format!("failed to create image at index {i}"))?;
-            image_bind_infos.push(bind_info);
+            if !image_meta.is_sparse() {
+                image_bind_infos.push(bind_info);
+            }
             let id = ImageId::new(
                 unsafe { &mut *images.get() }.insert(image_meta)
             );
@@ -1207,6 +1617,14 @@ impl Gpu {
         Ok(())
     }
 
+    /// Invalidates descriptor sets, which refer to a buffer that was bound to new memory or to
+    /// one of its views.
+    pub(crate) fn buffer_moved(&self, id: BufferId) {
+        for pool in self.inner.descriptor_pools.load().values() {
+            pool.buffer_moved(id);
+        }
+    }
+
     pub fn create_image_view(
         &self,
         image_id: ImageId,
@@ -1222,6 +1640,196 @@ impl Gpu {
             .map(|idx| ImageViewId::new(image_id, idx))
     }
 
+    /// Creates a texel buffer view of a buffer, which *can* be used with
+    /// [`DescriptorInfos::texel_buffers`].
+    ///
+    /// # Valid usage
+    /// - `buffer_id` *must* be a valid [`BufferId`].
+    /// - The buffer *must* have been created with [`BufferUsages::UNIFORM_TEXEL_BUFFER`] or
+    ///   [`BufferUsages::STORAGE_TEXEL_BUFFER`] usage.
+    /// - The [`buffer format features`][1] of the format *must* contain
+    ///   [`FormatFeatures::UNIFORM_TEXEL_BUFFER`] and [`FormatFeatures::STORAGE_TEXEL_BUFFER`] if
+    ///   the buffer has the respective usage.
+    /// - The offset of `range` *must* be a multiple of the min texel buffer offset alignment of
+    ///   the device.
+    /// - The size of `range` *must* be a multiple of the texel block size of the format and the
+    ///   number of texels *must* not exceed the max texel buffer elements of the device.
+    /// - The offset + size of `range` *must* be less than or equal to the size of the buffer.
+    ///
+    /// [1]: Self::get_buffer_format_features
+    pub fn create_buffer_view(
+        &self,
+        buffer_id: BufferId,
+        range: BufferViewRange,
+    ) -> Result<BufferViewId> {
+        let format_features = self.get_buffer_format_features(range.format);
+        self.inner.buffers
+            .write()
+            .get_mut(buffer_id.0)
+            .context_with(|| format!(
+                "invalid buffer id {buffer_id}"
+            ))?
+            .create_view(range, format_features)
+            .map(|idx| BufferViewId::new(buffer_id, idx))
+    }
+
+    /// Destroys a texel buffer view.
+    ///
+    /// Buffer views are also destroyed when their buffer is destroyed.
+    ///
+    /// # Valid usage
+    /// - The view *must* not be in use by a queue.
+    pub fn destroy_buffer_view(
+        &self,
+        id: BufferViewId,
+    ) -> Result<()> {
+        self.inner.buffers
+            .write()
+            .get_mut(id.buffer_id().0)
+            .context_with(|| format!(
+                "invalid buffer id {}", id.buffer_id(),
+            ))?
+            .destroy_view(id)?;
+        for pool in self.inner.descriptor_pools.load().values() {
+            pool.buffer_view_delete(id);
+        }
+        Ok(())
+    }
+
+    /// Creates an acceleration structure stored in a buffer, which *can* be built with
+    /// [`ComputeCommands::build_acceleration_structures`] and used with
+    /// [`DescriptorInfos::acceleration_structures`].
+    ///
+    /// # Valid usage
+    /// - [`ext::acceleration_structure`] *must* be enabled.
+    /// - `buffer_id` *must* be a valid [`BufferId`].
+    /// - The buffer *must* have been created with
+    ///   [`BufferUsages::ACCELERATION_STRUCTURE_STORAGE`] usage.
+    /// - The offset of `range` *must* be a multiple of 256.
+    /// - The offset + size of `range` *must* be less than or equal to the size of the buffer.
+    pub fn create_acceleration_structure(
+        &self,
+        buffer_id: BufferId,
+        range: AccelerationStructureRange,
+    ) -> Result<AccelerationStructureId> {
+        self.inner.buffers
+            .write()
+            .get_mut(buffer_id.0)
+            .context_with(|| format!(
+                "invalid buffer id {buffer_id}"
+            ))?
+            .create_acceleration_structure(range)
+            .map(|idx| AccelerationStructureId::new(buffer_id, idx))
+    }
+
+    /// Destroys an acceleration structure.
+    ///
+    /// Acceleration structures are also destroyed when their buffer is destroyed.
+    ///
+    /// # Valid usage
+    /// - The acceleration structure *must* not be in use by a queue.
+    pub fn destroy_acceleration_structure(
+        &self,
+        id: AccelerationStructureId,
+    ) -> Result<()> {
+        self.inner.buffers
+            .write()
+            .get_mut(id.buffer_id().0)
+            .context_with(|| format!(
+                "invalid buffer id {}", id.buffer_id(),
+            ))?
+            .destroy_acceleration_structure(id)?;
+        for pool in self.inner.descriptor_pools.load().values() {
+            pool.acceleration_structure_delete(id);
+        }
+        Ok(())
+    }
+
+    /// Gets the device address of an acceleration structure, which is used to reference bottom
+    /// level acceleration structures in [`AccelerationStructureInstance`]s.
+    pub fn get_acceleration_structure_device_address(
+        &self,
+        id: AccelerationStructureId,
+    ) -> Result<DeviceAddress> {
+        self.inner.buffers
+            .read()
+            .get(id.buffer_id().0)
+            .context_with(|| format!(
+                "invalid buffer id {}", id.buffer_id(),
+            ))?
+            .get_acceleration_structure(id)
+            .map(|acceleration_structure| acceleration_structure.device_address)
+    }
+
+    /// Gets the device address of a buffer.
+    ///
+    /// # Valid usage
+    /// - [`ext::acceleration_structure`] or [`ext::descriptor_buffer`] *must* be enabled.
+    /// - The buffer *must* have been created with [`BufferUsages::SHADER_DEVICE_ADDRESS`] usage.
+    pub fn get_buffer_device_address(
+        &self,
+        id: BufferId,
+    ) -> Result<DeviceAddress> {
+        self.inner.buffers
+            .read()
+            .get(id.0)
+            .context_with(|| format!(
+                "invalid buffer id {id}"
+            ))?
+            .device_address()
+    }
+
+    /// Gets the size of an acceleration structure and the scratch sizes required to build it
+    /// from `geometries`.
+    ///
+    /// The buffers of `geometries` are not accessed, but the primitive counts are used as the
+    /// maximum primitive counts of later builds.
+    pub fn get_acceleration_structure_build_sizes(
+        &self,
+        ty: AccelerationStructureType,
+        flags: BuildAccelerationStructureFlags,
+        geometries: &[AccelerationStructureGeometry],
+    ) -> Result<AccelerationStructureBuildSizes> {
+        let device = self.get_extension_device::<ext::acceleration_structure::Device>()
+            .ok_or_else(|| Error::just_context("acceleration_structure device extension is not enabled"))?;
+        validate_geometries(ty, geometries, self)?;
+        let tmp_alloc = self.tmp_alloc();
+        let tmp_alloc = tmp_alloc.guard();
+        let mut vk_geometries = FixedVec32::with_capacity(
+            geometries.len() as u32, &tmp_alloc,
+        ).context("alloc failed")?;
+        let mut max_primitive_counts = FixedVec32::with_capacity(
+            geometries.len() as u32, &tmp_alloc,
+        ).context("alloc failed")?;
+        for geometry in geometries {
+            vk_geometries.push(geometry.vk_geometry(|_| Ok(0))?);
+            max_primitive_counts.push(geometry.primitive_count);
+        }
+        let build_info = vk::AccelerationStructureBuildGeometryInfoKHR {
+            s_type: vk::StructureType::ACCELERATION_STRUCTURE_BUILD_GEOMETRY_INFO_KHR,
+            ty: ty.into(),
+            flags: flags.into(),
+            mode: vk::BuildAccelerationStructureModeKHR::BUILD,
+            geometry_count: vk_geometries.len(),
+            p_geometries: vk_geometries.as_ptr(),
+            ..Default::default()
+        };
+        let mut sizes = vk::AccelerationStructureBuildSizesInfoKHR::default();
+        unsafe {
+            device.get_acceleration_structure_build_sizes(
+                vk::AccelerationStructureBuildTypeKHR::DEVICE,
+                &build_info,
+                &max_primitive_counts,
+                &mut sizes,
+            );
+        }
+        Ok(AccelerationStructureBuildSizes {
+            acceleration_structure_size: sizes.acceleration_structure_size,
+            update_scratch_size: sizes.update_scratch_size,
+            build_scratch_size: sizes.build_scratch_size,
+        })
+    }
+
     #[inline]
     pub fn map_buffer(
         &self,
@@ -1233,7 +1841,10 @@ impl Gpu {
             .get_mut(id.0)
             .context_with(|| format!(
                 "invalid buffer id {id}"
-            ))?.memory_mut().map_memory()
+            ))?.memory_mut()
+            .ok_or_else(|| Error::just_context(format!(
+                "buffer {id} is sparse and can't be mapped"
+            )))?.map_memory()
             .context("failed to map memory")
     }
 
@@ -1252,7 +1863,11 @@ impl Gpu {
         let non_coherent_atom_size = self.device_limits().non_coherent_atom_size();
         for range in ranges {
             let buffer = buffers.get(range.buffer_id)?;
-            let memory = buffer.memory();
+            let memory = buffer.memory()
+                .ok_or_else(|| Error::just_context(format!(
+                    "buffer {} is sparse and can't be mapped",
+                    range.buffer_id,
+                )))?;
             if !memory.is_mapped() {
                 return Err(Error::just_context(format!(
                     "buffer {} memory is not mapped",
@@ -1306,7 +1921,11 @@ impl Gpu {
         let non_coherent_atom_size = self.device_limits().non_coherent_atom_size();
         for range in ranges {
             let buffer = buffers.get(range.buffer_id)?;
-            let memory = buffer.memory();
+            let memory = buffer.memory()
+                .ok_or_else(|| Error::just_context(format!(
+                    "buffer {} is sparse and can't be mapped",
+                    range.buffer_id,
+                )))?;
             if !memory.is_mapped() {
                 return Err(Error::just_context(format!(
                     "buffer {} memory is not mapped",
@@ -1345,6 +1964,249 @@ impl Gpu {
         }.context("failed to flush mapped memory ranges")
     }
 
+    /// Reads back `size` bytes of a buffer starting from `offset`.
+    ///
+    /// The copy is scheduled with `scheduler` on a graphics queue, so it's recorded with the
+    /// commands of the next [`tick`][1]. The returned future is resolved by the first
+    /// [`tick`][1], which sees that frame finished executing, so waiting on it never blocks a
+    /// thread. Dropping the future doesn't cancel the read back.
+    ///
+    /// The future resolves to an error, if the frame doesn't finish executing within the
+    /// [`frame timeout`][2] after being submitted.
+    ///
+    /// # Valid usage
+    /// - `buffer_id` *must* be a valid [`BufferId`].
+    /// - The buffer *must* have been created with the [`BufferUsages::TRANSFER_SRC`] bit set.
+    /// - `offset` + `size` *must* be in range of the buffer.
+    ///
+    /// [1]: Gpu::tick
+    /// [2]: DeviceAttributes::with_frame_timeout
+    #[inline]
+    pub fn read_buffer(
+        &self,
+        scheduler: &mut CommandScheduler<'_>,
+        buffer_id: BufferId,
+        offset: DeviceSize,
+        size: DeviceSize,
+    ) -> Result<PendingReadback>
+    {
+        self.read_buffer_after(scheduler, buffer_id, offset, size, &[])
+    }
+
+    /// Same as [`Gpu::read_buffer`], but the copy is recorded after the commands of
+    /// `dependencies`.
+    ///
+    /// Since [`CommandId`]s are only valid until the next [`tick`][1], this is meant for reading
+    /// back what commands scheduled on the same tick wrote.
+    ///
+    /// [1]: Gpu::tick
+    pub fn read_buffer_after(
+        &self,
+        scheduler: &mut CommandScheduler<'_>,
+        buffer_id: BufferId,
+        offset: DeviceSize,
+        size: DeviceSize,
+        dependencies: &[CommandDependency],
+    ) -> Result<PendingReadback>
+    {
+        let buffers = self.read_buffers();
+        let buffer_size = buffers.get(buffer_id)?.properties().size;
+        drop(buffers);
+        if offset + size > buffer_size {
+            return Err(Error::just_context(format!(
+                "read back offset {offset} + size {size} was out of range of buffer {buffer_id} size {buffer_size}"
+            )))
+        }
+        let staging_id = self.create_readback_buffer(size)?;
+        self.read_back(scheduler, staging_id, size, size, None, dependencies, move |cmd| {
+            cmd.copy_buffer(
+                buffer_id,
+                staging_id,
+                &[BufferCopy::new(offset, 0, size)],
+                CommandOrdering::Lenient,
+            )?;
+            Ok(())
+        }).context_with(|| format!("failed to read back buffer {buffer_id}"))
+    }
+
+    /// Reads back the whole mip level of an image subresource.
+    ///
+    /// The texels are tightly packed and [`Readback::row_pitch`] gives the size of a row of
+    /// texel blocks.
+    ///
+    /// The copy is scheduled and resolved like with [`Gpu::read_buffer`].
+    ///
+    /// # Valid usage
+    /// - `image_id` *must* be a valid [`ImageId`].
+    /// - The image *must* have been created with the [`ImageUsages::TRANSFER_SRC`] bit set.
+    /// - `subresource` *must* be a valid subresource of the image.
+    #[inline]
+    pub fn read_image(
+        &self,
+        scheduler: &mut CommandScheduler<'_>,
+        image_id: ImageId,
+        subresource: ImageSubresourceLayers,
+    ) -> Result<PendingReadback>
+    {
+        self.read_image_after(scheduler, image_id, subresource, &[])
+    }
+
+    /// Same as [`Gpu::read_image`], but the copy is recorded after the commands of
+    /// `dependencies`.
+    ///
+    /// Since [`CommandId`]s are only valid until the next [`tick`][1], this is meant for reading
+    /// back what commands scheduled on the same tick wrote, e.g. the frame rendered to a
+    /// [`HeadlessTarget`].
+    ///
+    /// [1]: Gpu::tick
+    pub fn read_image_after(
+        &self,
+        scheduler: &mut CommandScheduler<'_>,
+        image_id: ImageId,
+        subresource: ImageSubresourceLayers,
+        dependencies: &[CommandDependency],
+    ) -> Result<PendingReadback>
+    {
+        let images = self.read_images();
+        let properties = images.get(image_id)?.properties();
+        drop(images);
+        if subresource.mip_level >= properties.mip_levels {
+            return Err(Error::just_context(format!(
+                "mip level {} was out of range of image {image_id} mip level count {}",
+                subresource.mip_level, properties.mip_levels,
+            )))
+        }
+        let extent = properties.dimensions.lod(subresource.mip_level);
+        let region = BufferImageCopy::default()
+            .image_subresource(subresource)
+            .image_extent(extent);
+        let format_class = properties.format.compatibility();
+        let layer_count = subresource
+            .effective(properties.array_layers)
+            .layer_count;
+        let size = region.calculate_buffer_size(
+            format_class,
+            properties.format,
+            subresource.aspect_mask,
+            layer_count,
+        );
+        let block_size =
+            if let Some(plane) = subresource.aspect_mask.plane() {
+                properties.format.plane_formats()[plane as usize].texel_block_size()
+            } else {
+                format_class.texel_block_size()
+            };
+        let row_pitch = extent.width
+            .div_ceil(format_class.texel_block_extent().width) as DeviceSize * block_size;
+        let staging_id = self.create_readback_buffer(size)?;
+        self.read_back(
+            scheduler, staging_id, size, row_pitch, Some(extent), dependencies,
+            move |cmd| {
+                cmd.copy_image_to_buffer(
+                    image_id,
+                    staging_id,
+                    &[region],
+                    CommandOrdering::Lenient,
+                )?;
+                Ok(())
+            },
+        ).context_with(|| format!("failed to read back image {image_id}"))
+    }
+
+    fn create_readback_buffer(&self, size: DeviceSize) -> Result<BufferId> {
+        let binder = GlobalBinder::new(
+            self.device().clone(),
+            MemoryProperties::HOST_VISIBLE | MemoryProperties::HOST_COHERENT | MemoryProperties::HOST_CACHED,
+            MemoryProperties::HOST_VISIBLE | MemoryProperties::HOST_COHERENT,
+        );
+        let mut staging_id = Default::default();
+        self.create_resources(
+            [BufferCreateInfo::new(
+                &mut staging_id,
+                &binder,
+                size,
+                BufferUsages::TRANSFER_DST,
+            ).ok_or_else(|| Error::just_context("read back size is zero"))?],
+            [],
+        ).context("failed to create read back buffer")?;
+        Ok(staging_id)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn read_back<F>(
+        &self,
+        scheduler: &mut CommandScheduler<'_>,
+        staging_id: BufferId,
+        size: DeviceSize,
+        row_pitch: DeviceSize,
+        extent: Option<Dimensions>,
+        dependencies: &[CommandDependency],
+        f: F,
+    ) -> Result<PendingReadback>
+        where F: for<'a, 'b> FnOnce(&mut CopyCommands<'a, 'b>) -> EventResult<()> + Send + Sync + 'static
+    {
+        let res = (|| {
+            let queue = self
+                .any_device_queue(QueueFlags::GRAPHICS)
+                .ok_or_else(|| Error::just_context("failed to find a graphics queue"))?;
+            let failed = Arc::new(AtomicBool::new(false));
+            let record_failed = failed.clone();
+            let frame = scheduler.scheduled_frame();
+            scheduler.new_commands::<NewCopyCommands>(queue, move |cmd| {
+                let res = f(cmd);
+                if res.is_err() {
+                    record_failed.store(true, atomic::Ordering::Release);
+                }
+                res
+            })?.with_dependencies(dependencies.iter().copied());
+            let (request, pending) = readback::ReadbackRequest::new(
+                staging_id, size, row_pitch, extent, frame, failed,
+            );
+            self.inner.readbacks.lock().push(request);
+            Ok(pending)
+        })();
+        if res.is_err() {
+            self.destroy_resources([staging_id], []).ok();
+        }
+        res
+    }
+
+    /// Resolves the read backs, whose frames have finished executing, without blocking.
+    fn poll_readbacks(&self) -> Result<()> {
+        let mut readbacks = self.inner.readbacks.lock();
+        if readbacks.is_empty() {
+            return Ok(())
+        }
+        let (semaphore, submitted_frame) = {
+            let scheduler = self.queue_scheduler().read();
+            (scheduler.get_frame_semaphore_id(), scheduler.get_scheduled_frame() - 1)
+        };
+        let finished_frame = self.get_semaphore_counter_value(semaphore)?;
+        let timeout = Duration::from_nanos(self.device().frame_timeout());
+        readbacks.retain_mut(|request| {
+            !request.poll(self, submitted_frame, finished_frame, timeout)
+        });
+        Ok(())
+    }
+
+    #[inline]
+    pub fn is_buffer_view_valid(&self, id: BufferViewId) -> bool {
+        if let Ok(buffer) = self.inner.buffers.read().get(id.buffer_id().0) {
+            buffer.get_view(id).is_ok()
+        } else {
+            false
+        }
+    }
+
+    #[inline]
+    pub fn is_acceleration_structure_valid(&self, id: AccelerationStructureId) -> bool {
+        if let Ok(buffer) = self.inner.buffers.read().get(id.buffer_id().0) {
+            buffer.get_acceleration_structure(id).is_ok()
+        } else {
+            false
+        }
+    }
+
     #[inline]
     pub fn is_image_valid(&self, id: ImageId) -> bool {
         self.inner.images.read().contains(id.slot_index())
@@ -1503,6 +2365,91 @@ impl Gpu {
             .get(id.0).copied()
             .context("failed to find timeline semaphore")
     }
+
+    /// Creates a query pool with `query_count` queries of type `query_type`.
+    ///
+    /// Queries are written with `write_timestamp`, `begin_query` and `end_query` of
+    /// [`GraphicsCommands`], [`ComputeCommands`] and [`CopyCommands`] and their results can be
+    /// retrieved with [`Gpu::get_query_results`].
+    pub fn create_query_pool(
+        &self,
+        query_type: QueryType,
+        query_count: u32,
+    ) -> Result<QueryPoolId>
+    {
+        let meta = QueryPoolMeta::new(
+            self.device().clone(),
+            query_type,
+            query_count,
+        ).context_with(|| format!(
+            "failed to create {query_type} query pool"
+        ))?;
+        Ok(QueryPoolId(self.inner.query_pools.write().insert(meta)))
+    }
+
+    /// Destroys query pools.
+    ///
+    /// Fails if any of the pools are still in use by a queue.
+    pub fn destroy_query_pools(
+        &self,
+        ids: &[QueryPoolId],
+    ) -> Result<()>
+    {
+        let queue_scheduler = self.queue_scheduler().read();
+        let finished_frame = self.get_semaphore_counter_value(
+            queue_scheduler.get_frame_semaphore_id()
+        )?;
+        let mut pools = self.inner.query_pools.write();
+        for &id in ids {
+            let pool = pools
+                .get(id.0)
+                .context_with(|| format!(
+                    "invalid query pool id {id}"
+                ))?;
+            if pool.last_used_frame() > finished_frame {
+                return Err(Error::just_context(format!(
+                    "attempting to destroy query pool {id} while it is still in use by a queue"
+                )))
+            }
+            pools.remove(id.0).ok();
+        }
+        Ok(())
+    }
+
+    /// Gets the results of the queries of a pool written by the command `command_id`.
+    ///
+    /// Returns [`None`] if the frame the command was submitted on hasn't been signaled yet or if
+    /// the command didn't write any queries in `pool_id`.
+    ///
+    /// Since [`CommandId`]s are reused between frames, the results are always from the latest
+    /// frame the command wrote queries in. Each query only holds the results of its latest write,
+    /// so results should be collected before the queries are written again.
+    pub fn get_query_results(
+        &self,
+        pool_id: QueryPoolId,
+        command_id: CommandId,
+    ) -> Result<Option<QueryResults>>
+    {
+        let queue_scheduler = self.queue_scheduler().read();
+        let finished_frame = self.get_semaphore_counter_value(
+            queue_scheduler.get_frame_semaphore_id()
+        )?;
+        self.inner.query_pools
+            .read()
+            .get(pool_id.0)
+            .context_with(|| format!(
+                "invalid query pool id {pool_id}"
+            ))?
+            .get_results(command_id, finished_frame)
+            .context_with(|| format!(
+                "failed to get results of query pool {pool_id}"
+            ))
+    }
+
+    #[inline]
+    pub(crate) fn write_query_pools(&self) -> ResourceWriteGuard<'_, QueryPoolMeta, QueryPoolId> {
+        ResourceWriteGuard::new(self.inner.query_pools.write())
+    }
 }
 
 impl Drop for GpuInner {
@@ -1510,6 +2457,9 @@ impl Drop for GpuInner {
     fn drop(&mut self) {
         unsafe {
             log::info!("cleaning up GPU");
+            for request in self.readbacks.get_mut().iter() {
+                request.abandon();
+            }
             for &handle in self.timeline_semaphores.write().values() {
                 self.device.destroy_semaphore(handle, None);
             }
@@ -0,0 +1,782 @@
+use nox_ash::{
+    vk,
+    ash_style_enum,
+};
+
+use nox_proc::Display;
+
+use nox_mem::slot_map::SlotIndex;
+
+use crate::{
+    gpu::prelude::*,
+    error::*,
+};
+
+ash_style_enum! {
+
+    /// Specifies additional properties of a geometry in an acceleration structure.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/VkGeometryFlagBitsKHR.html>
+    #[flags(Flags32)]
+    #[default = Self::empty()]
+    pub enum GeometryFlags {
+        /// Specifies that any-hit shaders are not invoked for this geometry.
+        #[display("opaque")]
+        OPAQUE = vk::GeometryFlagsKHR::OPAQUE.as_raw(),
+        /// Specifies that any-hit shaders are invoked at most once per primitive of this geometry.
+        #[display("no duplicate any hit invocation")]
+        NO_DUPLICATE_ANY_HIT_INVOCATION =
+            vk::GeometryFlagsKHR::NO_DUPLICATE_ANY_HIT_INVOCATION.as_raw(),
+    }
+
+    /// Specifies additional parameters of an acceleration structure build.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/VkBuildAccelerationStructureFlagBitsKHR.html>
+    #[flags(Flags32)]
+    #[default = Self::empty()]
+    pub enum BuildAccelerationStructureFlags {
+        /// Specifies that the acceleration structure *can* be updated with
+        /// [`AccelerationStructureBuildInfo::with_update`].
+        #[display("allow update")]
+        ALLOW_UPDATE = vk::BuildAccelerationStructureFlagsKHR::ALLOW_UPDATE.as_raw(),
+        /// Specifies that the acceleration structure *can* be compacted with
+        /// [`ComputeCommands::copy_acceleration_structure`].
+        #[display("allow compaction")]
+        ALLOW_COMPACTION = vk::BuildAccelerationStructureFlagsKHR::ALLOW_COMPACTION.as_raw(),
+        /// Specifies that the build should prioritize trace performance over build time.
+        #[display("prefer fast trace")]
+        PREFER_FAST_TRACE = vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_TRACE.as_raw(),
+        /// Specifies that the build should prioritize build time over trace performance.
+        #[display("prefer fast build")]
+        PREFER_FAST_BUILD = vk::BuildAccelerationStructureFlagsKHR::PREFER_FAST_BUILD.as_raw(),
+        /// Specifies that the build should minimize the size of the acceleration structure and
+        /// its scratch memory.
+        #[display("low memory")]
+        LOW_MEMORY = vk::BuildAccelerationStructureFlagsKHR::LOW_MEMORY.as_raw(),
+    }
+
+    /// Specifies additional properties of an [`AccelerationStructureInstance`].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/VkGeometryInstanceFlagBitsKHR.html>
+    #[flags(Flags32)]
+    #[default = Self::empty()]
+    pub enum GeometryInstanceFlags {
+        /// Disables face culling for the triangles of the instance.
+        #[display("triangle facing cull disable")]
+        TRIANGLE_FACING_CULL_DISABLE =
+            vk::GeometryInstanceFlagsKHR::TRIANGLE_FACING_CULL_DISABLE.as_raw(),
+        /// Flips the facing of the triangles of the instance.
+        #[display("triangle flip facing")]
+        TRIANGLE_FLIP_FACING = vk::GeometryInstanceFlagsKHR::TRIANGLE_FLIP_FACING.as_raw(),
+        /// Treats all geometries of the instance as if they were [`GeometryFlags::OPAQUE`].
+        #[display("force opaque")]
+        FORCE_OPAQUE = vk::GeometryInstanceFlagsKHR::FORCE_OPAQUE.as_raw(),
+        /// Treats all geometries of the instance as if they weren't [`GeometryFlags::OPAQUE`].
+        #[display("force no opaque")]
+        FORCE_NO_OPAQUE = vk::GeometryInstanceFlagsKHR::FORCE_NO_OPAQUE.as_raw(),
+    }
+}
+
+impl From<GeometryFlags> for vk::GeometryFlagsKHR {
+
+    #[inline(always)]
+    fn from(value: GeometryFlags) -> Self {
+        Self::from_raw(value.as_raw())
+    }
+}
+
+impl From<BuildAccelerationStructureFlags> for vk::BuildAccelerationStructureFlagsKHR {
+
+    #[inline(always)]
+    fn from(value: BuildAccelerationStructureFlags) -> Self {
+        Self::from_raw(value.as_raw())
+    }
+}
+
+/// Specifies the type of an acceleration structure.
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkAccelerationStructureTypeKHR.html>
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+pub enum AccelerationStructureType {
+    /// A bottom level acceleration structure, which contains triangle or AABB geometries.
+    #[display("bottom level")]
+    BottomLevel,
+    /// A top level acceleration structure, which contains instances of bottom level
+    /// acceleration structures.
+    #[display("top level")]
+    TopLevel,
+}
+
+impl From<AccelerationStructureType> for vk::AccelerationStructureTypeKHR {
+
+    #[inline(always)]
+    fn from(value: AccelerationStructureType) -> Self {
+        match value {
+            AccelerationStructureType::BottomLevel => Self::BOTTOM_LEVEL,
+            AccelerationStructureType::TopLevel => Self::TOP_LEVEL,
+        }
+    }
+}
+
+/// Specifies how an acceleration structure is copied with
+/// [`ComputeCommands::copy_acceleration_structure`].
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkCopyAccelerationStructureModeKHR.html>
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+pub enum CopyAccelerationStructureMode {
+    /// Copies the acceleration structure as is.
+    ///
+    /// The destination *must* be at least as large as the source.
+    #[display("clone")]
+    Clone,
+    /// Copies the acceleration structure into a more compact form.
+    ///
+    /// The source *must* have been built with [`BuildAccelerationStructureFlags::ALLOW_COMPACTION`]
+    /// and the destination *must* be at least as large as the compacted size, which *can* be
+    /// queried with [`ComputeCommands::write_acceleration_structure_compacted_size`].
+    #[display("compact")]
+    Compact,
+}
+
+impl From<CopyAccelerationStructureMode> for vk::CopyAccelerationStructureModeKHR {
+
+    #[inline(always)]
+    fn from(value: CopyAccelerationStructureMode) -> Self {
+        match value {
+            CopyAccelerationStructureMode::Clone => Self::CLONE,
+            CopyAccelerationStructureMode::Compact => Self::COMPACT,
+        }
+    }
+}
+
+/// Identifies an acceleration structure stored in a buffer.
+///
+/// Acceleration structures are created with [`Gpu::create_acceleration_structure`] and are
+/// destroyed with their buffer.
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkAccelerationStructureKHR.html>
+#[must_use]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+#[display("(buffer id: {0}, acceleration structure id: {1})")]
+pub struct AccelerationStructureId(BufferId, SlotIndex<AccelerationStructure>);
+
+impl AccelerationStructureId {
+
+    #[inline(always)]
+    pub(crate) fn new(
+        buffer_id: BufferId,
+        index: SlotIndex<AccelerationStructure>,
+    ) -> Self {
+        Self(buffer_id, index)
+    }
+
+    /// Gets the [`BufferId`] of the buffer storing the acceleration structure.
+    #[inline(always)]
+    pub fn buffer_id(self) -> BufferId {
+        self.0
+    }
+
+    #[inline(always)]
+    pub(crate) fn acceleration_structure_index(self) -> SlotIndex<AccelerationStructure> {
+        self.1
+    }
+}
+
+/// Specifies the type and the range of the buffer an acceleration structure is stored in.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct AccelerationStructureRange {
+    /// The type of the acceleration structure.
+    pub ty: AccelerationStructureType,
+    /// The offset, in bytes, from the start of the buffer.
+    ///
+    /// This *must* be a multiple of 256.
+    pub offset: DeviceSize,
+    /// The size, in bytes, of the acceleration structure.
+    ///
+    /// The required size *can* be queried with [`Gpu::get_acceleration_structure_build_sizes`].
+    pub size: DeviceSize,
+}
+
+impl AccelerationStructureRange {
+
+    #[inline(always)]
+    pub fn new(
+        ty: AccelerationStructureType,
+        offset: DeviceSize,
+        size: DeviceSize,
+    ) -> Self {
+        Self {
+            ty,
+            offset,
+            size,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct AccelerationStructure {
+    pub handle: vk::AccelerationStructureKHR,
+    pub range: AccelerationStructureRange,
+    pub device_address: DeviceAddress,
+}
+
+/// The layout of a single instance in the instance buffer of a top level acceleration structure.
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkAccelerationStructureInstanceKHR.html>
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AccelerationStructureInstance {
+    /// A row-major 3x4 affine transform matrix.
+    pub transform: [[f32; 4]; 3],
+    custom_index_and_mask: u32,
+    shader_binding_table_record_offset_and_flags: u32,
+    /// The device address of the bottom level acceleration structure.
+    ///
+    /// This *can* be queried with [`Gpu::get_acceleration_structure_device_address`].
+    pub acceleration_structure_reference: DeviceAddress,
+}
+
+impl AccelerationStructureInstance {
+
+    /// The identity transform.
+    pub const IDENTITY_TRANSFORM: [[f32; 4]; 3] = [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+    ];
+
+    /// Creates an instance of a bottom level acceleration structure with an identity transform,
+    /// which is visible to all ray masks.
+    #[inline(always)]
+    pub fn new(acceleration_structure_reference: DeviceAddress) -> Self {
+        Self {
+            transform: Self::IDENTITY_TRANSFORM,
+            custom_index_and_mask: 0xFF << 24,
+            shader_binding_table_record_offset_and_flags: 0,
+            acceleration_structure_reference,
+        }
+    }
+
+    #[inline(always)]
+    pub fn with_transform(mut self, transform: [[f32; 4]; 3]) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    /// Sets the custom index of the instance, which is visible to shaders.
+    ///
+    /// Only the lower 24 bits are used.
+    #[inline(always)]
+    pub fn with_custom_index(mut self, index: u32) -> Self {
+        self.custom_index_and_mask =
+            (self.custom_index_and_mask & 0xFF00_0000) | (index & 0x00FF_FFFF);
+        self
+    }
+
+    /// Sets the visibility mask of the instance, which is combined with the cull mask of rays.
+    #[inline(always)]
+    pub fn with_mask(mut self, mask: u8) -> Self {
+        self.custom_index_and_mask =
+            (self.custom_index_and_mask & 0x00FF_FFFF) | ((mask as u32) << 24);
+        self
+    }
+
+    /// Sets the offset of the hit group records of this instance in the shader binding table.
+    ///
+    /// Only the lower 24 bits are used.
+    #[inline(always)]
+    pub fn with_shader_binding_table_record_offset(mut self, offset: u32) -> Self {
+        self.shader_binding_table_record_offset_and_flags =
+            (self.shader_binding_table_record_offset_and_flags & 0xFF00_0000) |
+            (offset & 0x00FF_FFFF);
+        self
+    }
+
+    #[inline(always)]
+    pub fn with_flags(mut self, flags: GeometryInstanceFlags) -> Self {
+        self.shader_binding_table_record_offset_and_flags =
+            (self.shader_binding_table_record_offset_and_flags & 0x00FF_FFFF) |
+            (flags.as_raw() << 24);
+        self
+    }
+
+    #[inline(always)]
+    pub fn custom_index(&self) -> u32 {
+        self.custom_index_and_mask & 0x00FF_FFFF
+    }
+
+    #[inline(always)]
+    pub fn mask(&self) -> u8 {
+        (self.custom_index_and_mask >> 24) as u8
+    }
+
+    #[inline(always)]
+    pub fn shader_binding_table_record_offset(&self) -> u32 {
+        self.shader_binding_table_record_offset_and_flags & 0x00FF_FFFF
+    }
+
+    #[inline(always)]
+    pub fn flags(&self) -> GeometryInstanceFlags {
+        GeometryInstanceFlags::from_raw(self.shader_binding_table_record_offset_and_flags >> 24)
+    }
+}
+
+/// Specifies where the data of a geometry is read from when building an acceleration structure.
+///
+/// All buffers *must* have been created with
+/// [`BufferUsages::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY`] and
+/// [`BufferUsages::SHADER_DEVICE_ADDRESS`] usage.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GeometryData {
+    /// Triangles of a bottom level acceleration structure.
+    Triangles {
+        /// The buffer containing the vertex positions.
+        vertex_buffer: BufferId,
+        /// The offset of the first vertex in `vertex_buffer`.
+        vertex_offset: DeviceSize,
+        /// The format of the vertex positions.
+        ///
+        /// The [`buffer format features`][1] of the format *must* contain
+        /// [`FormatFeatures::ACCELERATION_STRUCTURE_VERTEX_BUFFER`].
+        ///
+        /// [1]: Gpu::get_buffer_format_features
+        vertex_format: Format,
+        /// The stride, in bytes, between vertices.
+        vertex_stride: DeviceSize,
+        /// The highest vertex index that *can* be addressed.
+        max_vertex: u32,
+        /// The buffer, offset and type of the indices, or [`None`] if the triangles aren't
+        /// indexed.
+        ///
+        /// The index type *must* not be [`IndexType::U8`].
+        index: Option<(BufferId, DeviceSize, IndexType)>,
+        /// The buffer and offset of a row-major 3x4 transform matrix of [`f32`]s, which is
+        /// applied to the vertices.
+        ///
+        /// The offset *must* be a multiple of 16.
+        transform: Option<(BufferId, DeviceSize)>,
+    },
+    /// Axis-aligned bounding boxes of a bottom level acceleration structure, stored as six
+    /// [`f32`]s (min x, y, z and max x, y, z).
+    Aabbs {
+        buffer: BufferId,
+        /// The offset *must* be a multiple of 8.
+        offset: DeviceSize,
+        /// The stride *must* be a multiple of 8.
+        stride: DeviceSize,
+    },
+    /// Tightly packed [`AccelerationStructureInstance`]s of a top level acceleration structure.
+    Instances {
+        buffer: BufferId,
+        /// The offset *must* be a multiple of 16.
+        offset: DeviceSize,
+    },
+}
+
+/// Specifies a single geometry of an acceleration structure.
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkAccelerationStructureGeometryKHR.html>
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct AccelerationStructureGeometry {
+    pub data: GeometryData,
+    /// The number of triangles, AABBs or instances.
+    pub primitive_count: u32,
+    pub flags: GeometryFlags,
+}
+
+impl AccelerationStructureGeometry {
+
+    /// Creates a non-indexed triangle geometry.
+    #[inline(always)]
+    pub fn triangles(
+        vertex_buffer: BufferId,
+        vertex_offset: DeviceSize,
+        vertex_format: Format,
+        vertex_stride: DeviceSize,
+        vertex_count: u32,
+        flags: GeometryFlags,
+    ) -> Self {
+        Self {
+            data: GeometryData::Triangles {
+                vertex_buffer,
+                vertex_offset,
+                vertex_format,
+                vertex_stride,
+                max_vertex: vertex_count.saturating_sub(1),
+                index: None,
+                transform: None,
+            },
+            primitive_count: vertex_count / 3,
+            flags,
+        }
+    }
+
+    /// Creates an indexed triangle geometry.
+    #[allow(clippy::too_many_arguments)]
+    #[inline(always)]
+    pub fn indexed_triangles(
+        vertex_buffer: BufferId,
+        vertex_offset: DeviceSize,
+        vertex_format: Format,
+        vertex_stride: DeviceSize,
+        vertex_count: u32,
+        index_buffer: BufferId,
+        index_offset: DeviceSize,
+        index_type: IndexType,
+        index_count: u32,
+        flags: GeometryFlags,
+    ) -> Self {
+        Self {
+            data: GeometryData::Triangles {
+                vertex_buffer,
+                vertex_offset,
+                vertex_format,
+                vertex_stride,
+                max_vertex: vertex_count.saturating_sub(1),
+                index: Some((index_buffer, index_offset, index_type)),
+                transform: None,
+            },
+            primitive_count: index_count / 3,
+            flags,
+        }
+    }
+
+    #[inline(always)]
+    pub fn aabbs(
+        buffer: BufferId,
+        offset: DeviceSize,
+        stride: DeviceSize,
+        count: u32,
+        flags: GeometryFlags,
+    ) -> Self {
+        Self {
+            data: GeometryData::Aabbs { buffer, offset, stride },
+            primitive_count: count,
+            flags,
+        }
+    }
+
+    #[inline(always)]
+    pub fn instances(
+        buffer: BufferId,
+        offset: DeviceSize,
+        count: u32,
+    ) -> Self {
+        Self {
+            data: GeometryData::Instances { buffer, offset },
+            primitive_count: count,
+            flags: GeometryFlags::empty(),
+        }
+    }
+
+    /// Sets the transform of a triangle geometry.
+    ///
+    /// Does nothing for other geometries.
+    #[inline(always)]
+    pub fn with_transform(mut self, buffer: BufferId, offset: DeviceSize) -> Self {
+        if let GeometryData::Triangles { transform, .. } = &mut self.data {
+            *transform = Some((buffer, offset));
+        }
+        self
+    }
+
+    fn validate(
+        &self,
+        ty: AccelerationStructureType,
+        gpu: &Gpu,
+    ) -> Result<()>
+    {
+        match self.data {
+            GeometryData::Triangles {
+                vertex_format, vertex_stride, vertex_offset,
+                index, transform, ..
+            } => {
+                if ty != AccelerationStructureType::BottomLevel {
+                    return Err(Error::just_context(
+                        "triangle geometries can only be used in bottom level acceleration structures"
+                    ))
+                }
+                let features = gpu.get_buffer_format_features(vertex_format);
+                if !features.contains(FormatFeatures::ACCELERATION_STRUCTURE_VERTEX_BUFFER) {
+                    return Err(Error::just_context(format!(
+                        "format {vertex_format} doesn't support {} buffer format feature",
+                        FormatFeatures::ACCELERATION_STRUCTURE_VERTEX_BUFFER,
+                    )))
+                }
+                let component_size = vertex_format.texel_block_size() / 3;
+                if component_size != 0 && (
+                    !vertex_stride.is_multiple_of(component_size) ||
+                    !vertex_offset.is_multiple_of(component_size)
+                ) {
+                    return Err(Error::just_context(format!(
+                        "vertex stride {vertex_stride} and offset {vertex_offset} must be multiples of the component size {component_size} of format {vertex_format}",
+                    )))
+                }
+                if let Some((_, offset, index_type)) = index {
+                    if index_type == IndexType::U8 {
+                        return Err(Error::just_context(format!(
+                            "index type {index_type} can't be used with acceleration structures",
+                        )))
+                    }
+                    if !offset.is_multiple_of(index_type.index_size()) {
+                        return Err(Error::just_context(format!(
+                            "index offset {offset} must be a multiple of index size {}",
+                            index_type.index_size(),
+                        )))
+                    }
+                }
+                if let Some((_, offset)) = transform && !offset.is_multiple_of(16) {
+                    return Err(Error::just_context(format!(
+                        "transform offset {offset} must be a multiple of 16",
+                    )))
+                }
+            },
+            GeometryData::Aabbs { offset, stride, .. } => {
+                if ty != AccelerationStructureType::BottomLevel {
+                    return Err(Error::just_context(
+                        "AABB geometries can only be used in bottom level acceleration structures"
+                    ))
+                }
+                if !offset.is_multiple_of(8) || !stride.is_multiple_of(8) {
+                    return Err(Error::just_context(format!(
+                        "AABB offset {offset} and stride {stride} must be multiples of 8",
+                    )))
+                }
+            },
+            GeometryData::Instances { offset, .. } => {
+                if ty != AccelerationStructureType::TopLevel {
+                    return Err(Error::just_context(
+                        "instance geometries can only be used in top level acceleration structures"
+                    ))
+                }
+                if !offset.is_multiple_of(16) {
+                    return Err(Error::just_context(format!(
+                        "instance offset {offset} must be a multiple of 16",
+                    )))
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Calls `f` with each buffer range read by the geometry.
+    pub(crate) fn input_ranges(
+        &self,
+        mut f: impl FnMut(BufferId, DeviceSize, DeviceSize) -> Result<()>,
+    ) -> Result<()>
+    {
+        let count = self.primitive_count as DeviceSize;
+        match self.data {
+            GeometryData::Triangles {
+                vertex_buffer, vertex_offset, vertex_format, vertex_stride, max_vertex,
+                index, transform,
+            } => {
+                f(
+                    vertex_buffer, vertex_offset,
+                    max_vertex as DeviceSize * vertex_stride + vertex_format.texel_block_size(),
+                )?;
+                if let Some((buffer, offset, index_type)) = index {
+                    f(buffer, offset, count * 3 * index_type.index_size())?;
+                }
+                if let Some((buffer, offset)) = transform {
+                    f(buffer, offset, size_of::<[[f32; 4]; 3]>() as DeviceSize)?;
+                }
+            },
+            GeometryData::Aabbs { buffer, offset, stride } => {
+                if count != 0 {
+                    f(
+                        buffer, offset,
+                        (count - 1) * stride + size_of::<[f32; 6]>() as DeviceSize,
+                    )?;
+                }
+            },
+            GeometryData::Instances { buffer, offset } => {
+                f(
+                    buffer, offset,
+                    count * size_of::<AccelerationStructureInstance>() as DeviceSize,
+                )?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Creates the Vulkan geometry, getting the device addresses of buffers with `address`.
+    pub(crate) fn vk_geometry(
+        &self,
+        mut address: impl FnMut(BufferId) -> Result<DeviceAddress>,
+    ) -> Result<vk::AccelerationStructureGeometryKHR<'static>>
+    {
+        let (geometry_type, geometry) = match self.data {
+            GeometryData::Triangles {
+                vertex_buffer, vertex_offset, vertex_format, vertex_stride, max_vertex,
+                index, transform,
+            } => {
+                let (index_type, index_data) = match index {
+                    Some((buffer, offset, index_type)) => (
+                        vk::IndexType::from(index_type).into_ash(),
+                        address(buffer)? + offset,
+                    ),
+                    None => (vk::IndexType::NONE_KHR.into_ash(), 0),
+                };
+                let transform_data = match transform {
+                    Some((buffer, offset)) => address(buffer)? + offset,
+                    None => 0,
+                };
+                (vk::GeometryTypeKHR::TRIANGLES, vk::AccelerationStructureGeometryDataKHR {
+                    triangles: vk::AccelerationStructureGeometryTrianglesDataKHR {
+                        s_type: vk::StructureType::ACCELERATION_STRUCTURE_GEOMETRY_TRIANGLES_DATA_KHR,
+                        vertex_format: vertex_format.into(),
+                        vertex_data: vk::DeviceOrHostAddressConstKHR {
+                            device_address: address(vertex_buffer)? + vertex_offset,
+                        },
+                        vertex_stride,
+                        max_vertex,
+                        index_type,
+                        index_data: vk::DeviceOrHostAddressConstKHR {
+                            device_address: index_data,
+                        },
+                        transform_data: vk::DeviceOrHostAddressConstKHR {
+                            device_address: transform_data,
+                        },
+                        ..Default::default()
+                    },
+                })
+            },
+            GeometryData::Aabbs { buffer, offset, stride } => {
+                (vk::GeometryTypeKHR::AABBS, vk::AccelerationStructureGeometryDataKHR {
+                    aabbs: vk::AccelerationStructureGeometryAabbsDataKHR {
+                        s_type: vk::StructureType::ACCELERATION_STRUCTURE_GEOMETRY_AABBS_DATA_KHR,
+                        data: vk::DeviceOrHostAddressConstKHR {
+                            device_address: address(buffer)? + offset,
+                        },
+                        stride,
+                        ..Default::default()
+                    },
+                })
+            },
+            GeometryData::Instances { buffer, offset } => {
+                (vk::GeometryTypeKHR::INSTANCES, vk::AccelerationStructureGeometryDataKHR {
+                    instances: vk::AccelerationStructureGeometryInstancesDataKHR {
+                        s_type: vk::StructureType::ACCELERATION_STRUCTURE_GEOMETRY_INSTANCES_DATA_KHR,
+                        array_of_pointers: vk::FALSE,
+                        data: vk::DeviceOrHostAddressConstKHR {
+                            device_address: address(buffer)? + offset,
+                        },
+                        ..Default::default()
+                    },
+                })
+            },
+        };
+        Ok(vk::AccelerationStructureGeometryKHR {
+            s_type: vk::StructureType::ACCELERATION_STRUCTURE_GEOMETRY_KHR,
+            geometry_type,
+            geometry,
+            flags: self.flags.into(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Validates that `geometries` *can* be built into an acceleration structure of type `ty`.
+pub(crate) fn validate_geometries(
+    ty: AccelerationStructureType,
+    geometries: &[AccelerationStructureGeometry],
+    gpu: &Gpu,
+) -> Result<()>
+{
+    match ty {
+        AccelerationStructureType::BottomLevel => {
+            if geometries.is_empty() {
+                return Err(Error::just_context(
+                    "bottom level acceleration structures must have at least one geometry"
+                ))
+            }
+            let is_triangles = |geometry: &AccelerationStructureGeometry|
+                matches!(geometry.data, GeometryData::Triangles { .. });
+            if geometries.iter().any(is_triangles) && !geometries.iter().all(is_triangles) {
+                return Err(Error::just_context(
+                    "all geometries of a bottom level acceleration structure must have the same type"
+                ))
+            }
+        },
+        AccelerationStructureType::TopLevel => {
+            if geometries.len() != 1 {
+                return Err(Error::just_context(format!(
+                    "top level acceleration structures must have exactly one geometry, found {}",
+                    geometries.len(),
+                )))
+            }
+        },
+    }
+    for (i, geometry) in geometries.iter().enumerate() {
+        geometry.validate(ty, gpu)
+            .context_with(|| format!("invalid geometry at index {i}"))?;
+    }
+    Ok(())
+}
+
+/// The sizes required to build an acceleration structure.
+///
+/// Returned by [`Gpu::get_acceleration_structure_build_sizes`].
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkAccelerationStructureBuildSizesInfoKHR.html>
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct AccelerationStructureBuildSizes {
+    /// The size of the acceleration structure.
+    pub acceleration_structure_size: DeviceSize,
+    /// The size of the scratch memory required by an update.
+    pub update_scratch_size: DeviceSize,
+    /// The size of the scratch memory required by a build.
+    pub build_scratch_size: DeviceSize,
+}
+
+/// Specifies a single acceleration structure build for
+/// [`ComputeCommands::build_acceleration_structures`].
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkAccelerationStructureBuildGeometryInfoKHR.html>
+#[derive(Clone, Copy, Debug)]
+pub struct AccelerationStructureBuildInfo<'a> {
+    /// The acceleration structure to build.
+    pub dst: AccelerationStructureId,
+    /// The acceleration structure to update from, or [`None`] if this is a full build.
+    pub src: Option<AccelerationStructureId>,
+    pub flags: BuildAccelerationStructureFlags,
+    pub geometries: &'a [AccelerationStructureGeometry],
+}
+
+impl<'a> AccelerationStructureBuildInfo<'a> {
+
+    #[inline(always)]
+    pub fn new(
+        dst: AccelerationStructureId,
+        flags: BuildAccelerationStructureFlags,
+        geometries: &'a [AccelerationStructureGeometry],
+    ) -> Self {
+        Self {
+            dst,
+            src: None,
+            flags,
+            geometries,
+        }
+    }
+
+    /// Makes the build an update of `src`.
+    ///
+    /// `src` *must* have been built with [`BuildAccelerationStructureFlags::ALLOW_UPDATE`] and
+    /// the same flags and geometries, and it *can* be the same as `dst`.
+    #[inline(always)]
+    pub fn with_update(mut self, src: AccelerationStructureId) -> Self {
+        self.src = Some(src);
+        self
+    }
+}
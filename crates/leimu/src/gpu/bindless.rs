@@ -0,0 +1,385 @@
+use core::slice;
+
+use nox_proc::Display;
+use nox_mem::vec::Vec32;
+
+use crate::{
+    gpu::prelude::*,
+    error::*,
+};
+
+/// Identifies a descriptor allocated from a [`BindlessHeap`].
+///
+/// The index is the array element of the descriptor in its binding, which shaders use to index
+/// the descriptor array.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+#[display("(binding: {binding}, index: {index})")]
+pub struct BindlessSlot {
+    binding: u32,
+    index: u32,
+}
+
+impl BindlessSlot {
+
+    #[inline(always)]
+    pub fn binding(self) -> u32 {
+        self.binding
+    }
+
+    #[inline(always)]
+    pub fn index(self) -> u32 {
+        self.index
+    }
+}
+
+struct HeapBinding {
+    binding: u32,
+    ty: DescriptorType,
+    capacity: u32,
+    next: u32,
+    free: Vec32<u32>,
+    pending_free: Vec32<(u64, u32)>,
+    allocated: Vec32<bool>,
+}
+
+impl HeapBinding {
+
+    fn reclaim(&mut self, finished_frame: u64) {
+        let free = &mut self.free;
+        self.pending_free.retain(|&(frame, index)| {
+            if frame <= finished_frame {
+                free.push(index);
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+/// A single descriptor set with large descriptor arrays, which are indexed in shaders by
+/// [`slot`][1] indices.
+///
+/// The descriptor set layout *must* have the [`update after bind`][2] and
+/// [`partially bound`][3] flags, so that descriptors *can* be written while the set is in use
+/// and unused slots don't need to be written.
+///
+/// Commands only track the resources of descriptors, which were written before the descriptor
+/// set was bound. Resources written after binding the set *must* be synchronized by the caller.
+///
+/// # Valid usage
+/// - The heap *must* not be dropped while its descriptor set is in use by a queue.
+///
+/// [1]: BindlessSlot
+/// [2]: DescriptorSetLayoutFlags::UPDATE_AFTER_BIND
+/// [3]: DescriptorSetLayoutFlags::PARTIALLY_BOUND
+pub struct BindlessHeap {
+    gpu: Gpu,
+    pool_id: DescriptorPoolId,
+    set_id: DescriptorSetId,
+    bindings: Vec32<HeapBinding>,
+}
+
+impl BindlessHeap {
+
+    /// Creates a new heap from the descriptor set layout at `descriptor_set_index` of
+    /// `shader_set_id`.
+    ///
+    /// The capacity of each binding is the descriptor count of the layout binding, which *can*
+    /// be specified for runtime arrays with [`ShaderSetAttributes::with_runtime_array_count`].
+    ///
+    /// # Valid usage
+    /// - The layout *must* have the [`update after bind`][1] and [`partially bound`][2] flags.
+    /// - Each binding of the layout *must* be a buffer, a texel buffer or an image descriptor.
+    ///
+    /// [1]: DescriptorSetLayoutFlags::UPDATE_AFTER_BIND
+    /// [2]: DescriptorSetLayoutFlags::PARTIALLY_BOUND
+    pub async fn new(
+        gpu: &Gpu,
+        shader_set_id: ShaderSetId,
+        descriptor_set_index: u32,
+    ) -> Result<Self> {
+        let shader_set = gpu
+            .get_shader_set(shader_set_id).await
+            .context_with(|| format!("failed to get shader set {shader_set_id}"))?;
+        let layout = shader_set
+            .descriptor_set_layouts()
+            .get(descriptor_set_index as usize)
+            .ok_or_else(|| Error::just_context(format!(
+                "invalid descriptor set index {descriptor_set_index} for shader set {shader_set_id}"
+            )))?;
+        let required = DescriptorSetLayoutFlags::UPDATE_AFTER_BIND |
+            DescriptorSetLayoutFlags::PARTIALLY_BOUND;
+        if !layout.flags.contains(required) {
+            return Err(Error::just_context(format!(
+                "{}{}",
+                format_args!("descriptor set {descriptor_set_index} of shader set {shader_set_id} "),
+                format_args!("has flags {}, but bindless heaps require {required}", layout.flags),
+            )))
+        }
+        if let Some(binding) = layout.bindings
+            .iter()
+            .find(|b| !b.descriptor_type.is_buffer() && !b.descriptor_type.is_image())
+        {
+            return Err(Error::just_context(format!(
+                "binding {} has descriptor type {}, which can't be used in a bindless heap",
+                binding.binding, binding.descriptor_type,
+            )))
+        }
+        let pool_id = gpu.create_descriptor_pool(
+            layout.bindings
+                .iter()
+                .map(|b| (b.descriptor_type, b.descriptor_count)),
+            1, 0,
+        )?;
+        let mut set_id = DescriptorSetId::default();
+        if let Err(err) = gpu.allocate_descriptor_sets(
+            pool_id,
+            &mut [DescriptorSetInfo::new(&mut set_id, shader_set_id, descriptor_set_index)],
+        ).await {
+            gpu.destroy_descriptor_pool(pool_id);
+            return Err(err)
+        }
+        let bindings = layout.bindings
+            .iter()
+            .map(|b| HeapBinding {
+                binding: b.binding,
+                ty: b.descriptor_type,
+                capacity: b.descriptor_count,
+                next: 0,
+                free: Vec32::default(),
+                pending_free: Vec32::default(),
+                allocated: Vec32::default(),
+            }).collect();
+        Ok(Self {
+            gpu: gpu.clone(),
+            pool_id,
+            set_id,
+            bindings,
+        })
+    }
+
+    /// Gets the id of the descriptor set, which *can* be bound like any other descriptor set.
+    #[inline(always)]
+    pub fn descriptor_set_id(&self) -> DescriptorSetId {
+        self.set_id
+    }
+
+    /// Gets the descriptor count of `binding`.
+    ///
+    /// Returns [`None`] if `binding` is not a binding of the heap.
+    #[inline(always)]
+    pub fn capacity(&self, binding: u32) -> Option<u32> {
+        self.bindings
+            .iter()
+            .find(|b| b.binding == binding)
+            .map(|b| b.capacity)
+    }
+
+    fn get_binding(&mut self, binding: u32) -> Result<&mut HeapBinding> {
+        self.bindings
+            .iter_mut()
+            .find(|b| b.binding == binding)
+            .ok_or_else(|| Error::just_context(format!(
+                "binding {binding} is not a binding of the bindless heap"
+            )))
+    }
+
+    fn finished_frame(&self) -> Result<u64> {
+        self.gpu.get_semaphore_counter_value(
+            self.gpu.queue_scheduler().read().get_frame_semaphore_id()
+        )
+    }
+
+    /// Allocates a slot from `binding`.
+    ///
+    /// Slots freed with [`free`][1] are reused once the frames, which might have used them,
+    /// have finished executing.
+    ///
+    /// [1]: Self::free
+    pub fn allocate(&mut self, binding: u32) -> Result<BindlessSlot> {
+        let finished_frame = self.finished_frame()?;
+        let heap_binding = self.get_binding(binding)?;
+        heap_binding.reclaim(finished_frame);
+        let index =
+            if let Some(index) = heap_binding.free.pop() {
+                index
+            } else if heap_binding.next < heap_binding.capacity {
+                heap_binding.next += 1;
+                heap_binding.allocated.push(false);
+                heap_binding.next - 1
+            } else {
+                return Err(Error::just_context(format!(
+                    "binding {binding} of bindless heap is full with capacity {}",
+                    heap_binding.capacity,
+                )))
+            };
+        heap_binding.allocated[index as usize] = true;
+        Ok(BindlessSlot {
+            binding,
+            index,
+        })
+    }
+
+    fn check_slot(&mut self, slot: BindlessSlot) -> Result<DescriptorType> {
+        let binding = self.get_binding(slot.binding)?;
+        if !binding.allocated
+            .get(slot.index as usize)
+            .copied()
+            .unwrap_or(false)
+        {
+            return Err(Error::just_context(format!(
+                "bindless slot {slot} is not allocated"
+            )))
+        }
+        Ok(binding.ty)
+    }
+
+    /// Writes an image descriptor to `slot`.
+    ///
+    /// # Valid usage
+    /// - `slot` *must* be allocated from this heap.
+    /// - The binding of `slot` *must* be an image descriptor binding.
+    pub fn write_image(
+        &mut self,
+        slot: BindlessSlot,
+        info: DescriptorImageInfo,
+    ) -> Result<()> {
+        let ty = self.check_slot(slot)?;
+        if !ty.is_image() {
+            return Err(Error::just_context(format!(
+                "attempting to write an image to bindless slot {slot} with descriptor type {ty}"
+            )))
+        }
+        self.gpu.update_descriptor_sets(
+            self.pool_id,
+            &[WriteDescriptorSet::new(
+                self.set_id, slot.binding, slot.index,
+                DescriptorInfos::images(slice::from_ref(&info)),
+            )?],
+            &[],
+        )
+    }
+
+    /// Writes a buffer descriptor to `slot`.
+    ///
+    /// # Valid usage
+    /// - `slot` *must* be allocated from this heap.
+    /// - The binding of `slot` *must* be a buffer descriptor binding, which isn't a texel buffer
+    ///   binding.
+    pub fn write_buffer(
+        &mut self,
+        slot: BindlessSlot,
+        info: DescriptorBufferInfo,
+    ) -> Result<()> {
+        let ty = self.check_slot(slot)?;
+        if !ty.is_buffer() || ty.is_texel_buffer() {
+            return Err(Error::just_context(format!(
+                "attempting to write a buffer to bindless slot {slot} with descriptor type {ty}"
+            )))
+        }
+        self.gpu.update_descriptor_sets(
+            self.pool_id,
+            &[WriteDescriptorSet::new(
+                self.set_id, slot.binding, slot.index,
+                DescriptorInfos::buffers(slice::from_ref(&info)),
+            )?],
+            &[],
+        )
+    }
+
+    /// Writes a texel buffer descriptor to `slot`.
+    ///
+    /// # Valid usage
+    /// - `slot` *must* be allocated from this heap.
+    /// - The binding of `slot` *must* be a texel buffer descriptor binding.
+    /// - `buffer_view` *must* be a valid [`BufferViewId`].
+    pub fn write_texel_buffer(
+        &mut self,
+        slot: BindlessSlot,
+        buffer_view: BufferViewId,
+    ) -> Result<()> {
+        let ty = self.check_slot(slot)?;
+        if !ty.is_texel_buffer() {
+            return Err(Error::just_context(format!(
+                "attempting to write a texel buffer to bindless slot {slot} with descriptor type {ty}"
+            )))
+        }
+        self.gpu.update_descriptor_sets(
+            self.pool_id,
+            &[WriteDescriptorSet::new(
+                self.set_id, slot.binding, slot.index,
+                DescriptorInfos::texel_buffers(slice::from_ref(&buffer_view)),
+            )?],
+            &[],
+        )
+    }
+
+    /// Allocates a slot from `binding` and writes an image descriptor to it.
+    pub fn allocate_image(
+        &mut self,
+        binding: u32,
+        info: DescriptorImageInfo,
+    ) -> Result<BindlessSlot> {
+        let slot = self.allocate(binding)?;
+        if let Err(err) = self.write_image(slot, info) {
+            self.free(slot)?;
+            return Err(err)
+        }
+        Ok(slot)
+    }
+
+    /// Allocates a slot from `binding` and writes a buffer descriptor to it.
+    pub fn allocate_buffer(
+        &mut self,
+        binding: u32,
+        info: DescriptorBufferInfo,
+    ) -> Result<BindlessSlot> {
+        let slot = self.allocate(binding)?;
+        if let Err(err) = self.write_buffer(slot, info) {
+            self.free(slot)?;
+            return Err(err)
+        }
+        Ok(slot)
+    }
+
+    /// Allocates a slot from `binding` and writes a texel buffer descriptor to it.
+    pub fn allocate_texel_buffer(
+        &mut self,
+        binding: u32,
+        buffer_view: BufferViewId,
+    ) -> Result<BindlessSlot> {
+        let slot = self.allocate(binding)?;
+        if let Err(err) = self.write_texel_buffer(slot, buffer_view) {
+            self.free(slot)?;
+            return Err(err)
+        }
+        Ok(slot)
+    }
+
+    /// Frees `slot`.
+    ///
+    /// The slot is not reused before every frame, which might have used the descriptor set, has
+    /// finished executing.
+    pub fn free(&mut self, slot: BindlessSlot) -> Result<()> {
+        self.check_slot(slot)?;
+        let last_used_frame = {
+            let pools = self.gpu.get_descriptor_pools();
+            let pool = pools
+                .get(self.pool_id.slot_index())
+                .context_with(|| format!("invalid pool id {}", self.pool_id))?;
+            pool.write().get_descriptor_set(self.set_id)?.last_used_frame()
+        };
+        let binding = self.get_binding(slot.binding)?;
+        binding.allocated[slot.index as usize] = false;
+        binding.pending_free.push((last_used_frame, slot.index));
+        Ok(())
+    }
+}
+
+impl Drop for BindlessHeap {
+
+    fn drop(&mut self) {
+        self.gpu.destroy_descriptor_pool(self.pool_id);
+    }
+}
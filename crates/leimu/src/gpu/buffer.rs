@@ -1,11 +1,13 @@
 mod create_info;
 mod properties;
 mod state;
+mod view;
 
 use nox_ash::vk;
 
 use nox_mem::{
     vec::Vec32,
+    slot_map::{SlotIndex, SlotMap},
     vec32,
 };
 
@@ -19,18 +21,27 @@ use crate::error::*;
 pub use create_info::*;
 pub use properties::BufferProperties;
 pub use state::*;
+pub use view::*;
 
 impl Flags for BufferUsages {
 
     const NAME: &str = "buffer usage";
 }
 
+pub(crate) enum BufferMemory {
+    Bound(DeviceMemoryObj),
+    Sparse(SparseMemory),
+}
+
 pub struct BufferMeta {
     device: LogicalDevice,
     handle: vk::Buffer,
-    memory: DeviceMemoryObj,
+    memory: BufferMemory,
     properties: BufferProperties,
     state: Vec32<BufferRange>,
+    views: SlotMap<BufferView>,
+    acceleration_structures: SlotMap<AccelerationStructure>,
+    acceleration_structure_device: Option<ext::acceleration_structure::Device>,
 }
 
 impl ResourceMeta for BufferMeta {
@@ -69,19 +80,35 @@ impl BufferMeta {
             device
             .get_device_buffer_memory_requirements(&device_mem_requirements, &mut mem_requirements);
         }
-        let memory = unsafe { create_info.memory_binder.alloc(&mem_requirements)
-            .context("failed to allocate GPU memory for buffer")?
-        };
+        let sparse_flags = SparseFlags::from_raw(properties.create_flags.as_raw());
+        let features = device.base_device_features();
+        sparse_flags.validate_features(
+            features,
+            (features.sparse_residency_buffer, "sparse_residency_buffer"),
+        )?;
+        let memory =
+            if sparse_flags.is_empty() {
+                BufferMemory::Bound(unsafe { create_info.memory_binder.alloc(&mem_requirements)
+                    .context("failed to allocate GPU memory for buffer")?
+                })
+            } else {
+                BufferMemory::Sparse(SparseMemory::new(
+                    sparse_flags, mem_requirements.memory_requirements,
+                ))
+            };
         let handle = unsafe {
             device.create_buffer(&vk_create_info, None)
             .context("failed to create Vulkan buffer")?
         };
-        *bind_memory_info = vk::BindBufferMemoryInfo {
-             buffer: handle,
-             memory: <_ as vk::Handle>::from_raw(memory.handle()),
-             memory_offset: memory.offset(),
-             ..Default::default()
-        };
+        device.set_object_name(handle, create_info.name);
+        if let BufferMemory::Bound(memory) = &memory {
+            *bind_memory_info = vk::BindBufferMemoryInfo {
+                 buffer: handle,
+                 memory: <_ as vk::Handle>::from_raw(memory.handle()),
+                 memory_offset: memory.offset(),
+                 ..Default::default()
+            };
+        }
         Ok(Self {
             handle,
             memory,
@@ -96,6 +123,9 @@ impl BufferMeta {
                 offset: 0,
                 size: properties.size,
             }],
+            views: SlotMap::new(),
+            acceleration_structures: SlotMap::new(),
+            acceleration_structure_device: None,
         })
     }
 
@@ -121,6 +151,262 @@ impl BufferMeta {
         )
     } 
 
+    /// Creates a texel buffer view.
+    ///
+    /// `format_features` are the buffer format features of the format of `range`.
+    pub(crate) fn create_view(
+        &mut self,
+        range: BufferViewRange,
+        format_features: FormatFeatures,
+    ) -> Result<SlotIndex<BufferView>> {
+        let usage = self.properties.usage;
+        if !usage.intersects(
+            BufferUsages::UNIFORM_TEXEL_BUFFER |
+            BufferUsages::STORAGE_TEXEL_BUFFER
+        ) {
+            return Err(Error::just_context(format!(
+                "buffer usage {usage} doesn't contain {} or {}",
+                BufferUsages::UNIFORM_TEXEL_BUFFER, BufferUsages::STORAGE_TEXEL_BUFFER,
+            )))
+        }
+        for (usage_bit, feature) in [
+            (BufferUsages::UNIFORM_TEXEL_BUFFER, FormatFeatures::UNIFORM_TEXEL_BUFFER),
+            (BufferUsages::STORAGE_TEXEL_BUFFER, FormatFeatures::STORAGE_TEXEL_BUFFER),
+        ] {
+            if usage.contains(usage_bit) && !format_features.contains(feature) {
+                return Err(Error::just_context(format!(
+                    "format {} doesn't support {feature} buffer format feature",
+                    range.format,
+                )))
+            }
+        }
+        if range.offset + range.size > self.properties.size {
+            return Err(Error::just_context(format!(
+                "buffer view offset {} + size {} is out of range of buffer size {}",
+                range.offset, range.size, self.properties.size,
+            )))
+        }
+        let limits = self.device.physical_device().limits();
+        if !range.offset.is_multiple_of(limits.min_texel_buffer_offset_alignment) {
+            return Err(Error::just_context(format!(
+                "buffer view offset {} is not a multiple of min texel buffer offset alignment {}",
+                range.offset, limits.min_texel_buffer_offset_alignment,
+            )))
+        }
+        let texel_size = range.format.texel_block_size();
+        if texel_size == 0 || !range.size.is_multiple_of(texel_size) {
+            return Err(Error::just_context(format!(
+                "buffer view size {} is not a multiple of the texel size {texel_size} of format {}",
+                range.size, range.format,
+            )))
+        }
+        let texel_count = range.size / texel_size;
+        if texel_count > limits.max_texel_buffer_elements as DeviceSize {
+            return Err(Error::just_context(format!(
+                "buffer view texel count {texel_count} is more than max texel buffer elements {}",
+                limits.max_texel_buffer_elements,
+            )))
+        }
+        let create_info = vk::BufferViewCreateInfo {
+            s_type: vk::StructureType::BUFFER_VIEW_CREATE_INFO,
+            buffer: self.handle,
+            format: range.format.into(),
+            offset: range.offset,
+            range: range.size,
+            ..Default::default()
+        };
+        let handle = unsafe {
+            self.device.create_buffer_view(&create_info, None)
+            .context("failed to create buffer view")?
+        };
+        Ok(self.views.insert(BufferView {
+            handle,
+            range,
+        }))
+    }
+
+    #[inline]
+    pub(crate) fn get_view(
+        &self,
+        id: BufferViewId,
+    ) -> Result<&BufferView>
+    {
+        self.views
+            .get(id.view_index())
+            .context_with(|| format!(
+                "invalid buffer view id {id}"
+            ))
+    }
+
+    pub(crate) fn destroy_view(
+        &mut self,
+        id: BufferViewId,
+    ) -> Result<()>
+    {
+        let view = self.views
+            .remove(id.view_index())
+            .context_with(|| format!(
+                "invalid buffer view id {id}"
+            ))?;
+        unsafe {
+            self.device.destroy_buffer_view(view.handle, None);
+        }
+        Ok(())
+    }
+
+    /// Swaps the Vulkan buffer, memory and state of this buffer with `other`.
+    ///
+    /// The views of this buffer are recreated on the new Vulkan buffer with the same slot
+    /// indices. The old views are moved to `other`, so that they are destroyed with it.
+    ///
+    /// `other` *must* have the same properties as this buffer.
+    pub(crate) fn swap_storage(&mut self, other: &mut Self) -> Result<()> {
+        let mut handles = Vec32::with_capacity(self.views.len());
+        for view in self.views.values() {
+            let create_info = vk::BufferViewCreateInfo {
+                s_type: vk::StructureType::BUFFER_VIEW_CREATE_INFO,
+                buffer: other.handle,
+                format: view.range.format.into(),
+                offset: view.range.offset,
+                range: view.range.size,
+                ..Default::default()
+            };
+            match unsafe {
+                self.device.create_buffer_view(&create_info, None)
+            } {
+                Ok(handle) => handles.push(handle),
+                Err(err) => {
+                    for &handle in handles.iter() {
+                        unsafe {
+                            self.device.destroy_buffer_view(handle, None);
+                        }
+                    }
+                    return Err(Error::new(err, "failed to create buffer view"))
+                },
+            }
+        }
+        core::mem::swap(&mut self.handle, &mut other.handle);
+        core::mem::swap(&mut self.memory, &mut other.memory);
+        core::mem::swap(&mut self.state, &mut other.state);
+        for ((_, view), handle) in self.views.iter_mut().zip(handles) {
+            let old = *view;
+            view.handle = handle;
+            other.views.insert(old);
+        }
+        Ok(())
+    }
+
+    /// Gets the device address of the buffer.
+    pub(crate) fn device_address(&self) -> Result<DeviceAddress> {
+        if !self.device.is_buffer_device_address_enabled() {
+            return Err(Error::just_context(
+                "neither acceleration_structure nor descriptor_buffer device extension is enabled"
+            ))
+        }
+        if let Some(err) = self.validate_usage(BufferUsages::SHADER_DEVICE_ADDRESS) {
+            return Err(Error::new(err, "buffer usage mismatch"))
+        }
+        let info = vk::BufferDeviceAddressInfo {
+            s_type: vk::StructureType::BUFFER_DEVICE_ADDRESS_INFO,
+            buffer: self.handle,
+            ..Default::default()
+        };
+        Ok(unsafe {
+            self.device.get_buffer_device_address(&info)
+        })
+    }
+
+    fn acceleration_structure_device(
+        &mut self,
+    ) -> Result<&ext::acceleration_structure::Device> {
+        if self.acceleration_structure_device.is_none() {
+            self.acceleration_structure_device = Some(self.device
+                .get_extension_device()
+                .ok_or_else(|| Error::just_context("acceleration_structure device extension is not enabled"))?
+            );
+        }
+        Ok(self.acceleration_structure_device.as_ref().unwrap())
+    }
+
+    /// Creates an acceleration structure stored in `range` of the buffer.
+    pub(crate) fn create_acceleration_structure(
+        &mut self,
+        range: AccelerationStructureRange,
+    ) -> Result<SlotIndex<AccelerationStructure>> {
+        if let Some(err) = self.validate_usage(BufferUsages::ACCELERATION_STRUCTURE_STORAGE) {
+            return Err(Error::new(err, "buffer usage mismatch"))
+        }
+        if !range.offset.is_multiple_of(256) {
+            return Err(Error::just_context(format!(
+                "acceleration structure offset {} is not a multiple of 256",
+                range.offset,
+            )))
+        }
+        if range.offset + range.size > self.properties.size {
+            return Err(Error::just_context(format!(
+                "acceleration structure offset {} + size {} is out of range of buffer size {}",
+                range.offset, range.size, self.properties.size,
+            )))
+        }
+        let buffer = self.handle;
+        let device = self.acceleration_structure_device()?;
+        let create_info = vk::AccelerationStructureCreateInfoKHR {
+            s_type: vk::StructureType::ACCELERATION_STRUCTURE_CREATE_INFO_KHR,
+            buffer,
+            offset: range.offset,
+            size: range.size,
+            ty: range.ty.into(),
+            ..Default::default()
+        };
+        let handle = unsafe {
+            device.create_acceleration_structure(&create_info, None)
+            .context("failed to create acceleration structure")?
+        };
+        let address_info = vk::AccelerationStructureDeviceAddressInfoKHR {
+            s_type: vk::StructureType::ACCELERATION_STRUCTURE_DEVICE_ADDRESS_INFO_KHR,
+            acceleration_structure: handle,
+            ..Default::default()
+        };
+        let device_address = unsafe {
+            device.get_acceleration_structure_device_address(&address_info)
+        };
+        Ok(self.acceleration_structures.insert(AccelerationStructure {
+            handle,
+            range,
+            device_address,
+        }))
+    }
+
+    #[inline]
+    pub(crate) fn get_acceleration_structure(
+        &self,
+        id: AccelerationStructureId,
+    ) -> Result<&AccelerationStructure>
+    {
+        self.acceleration_structures
+            .get(id.acceleration_structure_index())
+            .context_with(|| format!(
+                "invalid acceleration structure id {id}"
+            ))
+    }
+
+    pub(crate) fn destroy_acceleration_structure(
+        &mut self,
+        id: AccelerationStructureId,
+    ) -> Result<()>
+    {
+        let acceleration_structure = self.acceleration_structures
+            .remove(id.acceleration_structure_index())
+            .context_with(|| format!(
+                "invalid acceleration structure id {id}"
+            ))?;
+        let device = self.acceleration_structure_device()?;
+        unsafe {
+            device.destroy_acceleration_structure(acceleration_structure.handle, None);
+        }
+        Ok(())
+    }
+
     /// Registers a memory barrier, which *can* be used to perform [`pipeline barrier`][1] with the
     /// [`cache`][2].
     ///
@@ -270,16 +556,84 @@ impl BufferMeta {
         )})
     }
 
+    /// Registers memory barriers, which transfer the ownership of every range of the buffer to
+    /// `queue_family_index` without otherwise accessing it.
+    ///
+    /// The returned [`range`][1] *must* be [`flushed`][2] and recorded, if the range is not empty.
+    ///
+    /// [1]: BufferMemoryBarrierRange
+    /// [2]: BufferMemoryBarrierCache::flush
+    pub(crate) fn ownership_barrier(
+        &mut self,
+        queue_family_index: u32,
+        cache: &mut BufferMemoryBarrierCache,
+    ) -> BufferMemoryBarrierRange
+    {
+        let cache_index = cache.barriers.len();
+        let dst_state = BufferState::new(
+            vk::PipelineStageFlags2::ALL_COMMANDS,
+            vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
+            queue_family_index,
+        );
+        for range in &mut self.state {
+            let src_state = range.state;
+            if src_state.queue_family_index == queue_family_index {
+                continue
+            }
+            if src_state.queue_family_index != vk::QUEUE_FAMILY_IGNORED {
+                cache.barriers.push(BufferRange::memory_barrier(
+                    src_state, dst_state,
+                    range.offset, range.size,
+                ));
+            }
+            range.state = dst_state;
+        }
+        BufferMemoryBarrierRange {
+            handle: self.handle,
+            range_start: cache_index,
+            range_end: cache.barriers.len(),
+        }
+    }
+
+    /// Returns the memory of the buffer or [`None`] if the buffer is sparse.
     #[inline]
-    pub fn memory(&self) -> &DeviceMemoryObj {
-        &self.memory
+    pub fn memory(&self) -> Option<&DeviceMemoryObj> {
+        match &self.memory {
+            BufferMemory::Bound(memory) => Some(memory),
+            BufferMemory::Sparse(_) => None,
+        }
     }
     
+    /// Returns the memory of the buffer or [`None`] if the buffer is sparse.
     #[inline]
-    pub fn memory_mut(&mut self) -> &mut DeviceMemoryObj {
-        &mut self.memory
+    pub fn memory_mut(&mut self) -> Option<&mut DeviceMemoryObj> {
+        match &mut self.memory {
+            BufferMemory::Bound(memory) => Some(memory),
+            BufferMemory::Sparse(_) => None,
+        }
     } 
 
+    #[inline]
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.memory, BufferMemory::Sparse(_))
+    }
+
+    #[inline]
+    pub(crate) fn sparse_memory(&self) -> Option<&SparseMemory> {
+        match &self.memory {
+            BufferMemory::Sparse(memory) => Some(memory),
+            BufferMemory::Bound(_) => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn sparse_memory_mut(&mut self) -> Option<&mut SparseMemory> {
+        match &mut self.memory {
+            BufferMemory::Sparse(memory) => Some(memory),
+            BufferMemory::Bound(_) => None,
+        }
+    }
+
     pub(crate) fn flush_state(&mut self) {
         for range in &mut self.state {
             range.state.stage_mask = vk::PipelineStageFlags2::ALL_COMMANDS;
@@ -292,6 +646,14 @@ impl Drop for BufferMeta {
 
     fn drop(&mut self) {
         unsafe {
+            for view in self.views.values() {
+                self.device.destroy_buffer_view(view.handle, None);
+            }
+            if let Some(device) = &self.acceleration_structure_device {
+                for acceleration_structure in self.acceleration_structures.values() {
+                    device.destroy_acceleration_structure(acceleration_structure.handle, None);
+                }
+            }
             self.device.destroy_buffer(self.handle(), None);
         }
     }
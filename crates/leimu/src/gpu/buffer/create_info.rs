@@ -16,6 +16,7 @@ pub struct BufferCreateInfo<'a> {
     pub(crate) size: NonZeroU64,
     pub(crate) usage: BufferUsages,
     pub(crate) create_flags: vk::BufferCreateFlags,
+    pub(crate) name: &'a str,
 }
 
 impl<'a> BufferCreateInfo<'a> {
@@ -43,9 +44,43 @@ impl<'a> BufferCreateInfo<'a> {
             size: NonZeroU64::new(size)?,
             usage,
             create_flags: vk::BufferCreateFlags::empty(),
+            name: "",
         })
     }
 
+    /// Sets the `name` for the buffer. The default name is an empty string.
+    ///
+    /// The name is given to the buffer as its [`object name`][1].
+    ///
+    /// [1]: LogicalDevice::set_object_name
+    #[inline(always)]
+    pub fn with_name(mut self, name: &'a str) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Makes the buffer sparse with `flags`. The default is [`SparseFlags::empty`].
+    ///
+    /// The memory of a sparse buffer is not allocated with the memory binder of the create info.
+    /// Instead it is bound at page granularity with [`SparseBindCommands`].
+    ///
+    /// # Valid usage
+    /// - [`BaseDeviceFeatures::sparse_binding`] *must* be enabled, if `flags` is not empty.
+    /// - [`BaseDeviceFeatures::sparse_residency_buffer`] *must* be enabled, if `flags` contains
+    ///   [`SparseFlags::RESIDENCY`].
+    /// - [`BaseDeviceFeatures::sparse_residency_aliased`] *must* be enabled, if `flags` contains
+    ///   [`SparseFlags::ALIASED`].
+    #[inline(always)]
+    pub fn with_sparse(mut self, flags: SparseFlags) -> Self {
+        self.create_flags &= !(
+            vk::BufferCreateFlags::SPARSE_BINDING |
+            vk::BufferCreateFlags::SPARSE_RESIDENCY |
+            vk::BufferCreateFlags::SPARSE_ALIASED
+        );
+        self.create_flags |= vk::BufferCreateFlags::from(flags);
+        self
+    }
+
     pub(crate) fn build(
         &self,
         device: LogicalDevice,
@@ -43,6 +43,24 @@ pub(crate) struct BufferMemoryBarrier {
 
 impl BufferMemoryBarrier {
 
+    /// Returns whether the barrier transfers the ownership of the range to another queue family.
+    #[inline(always)]
+    pub fn is_ownership_transfer(&self) -> bool {
+        self.src_queue_family_index != vk::QUEUE_FAMILY_IGNORED &&
+        self.src_queue_family_index != self.dst_queue_family_index
+    }
+
+    /// Returns the release half of a queue family ownership transfer, which *must* be recorded
+    /// on the source queue family.
+    #[inline(always)]
+    pub fn release(self) -> Self {
+        Self {
+            dst_stage_mask: vk::PipelineStageFlags2::NONE,
+            dst_access_mask: vk::AccessFlags2::NONE,
+            ..self
+        }
+    }
+
     #[inline(always)]
     pub fn into_vk(self, handle: vk::Buffer) -> vk::BufferMemoryBarrier2<'static> {
         let dst_queue_family_index =
@@ -131,6 +149,7 @@ impl StateRange for BufferRange {
 #[derive(Default)]
 pub struct BufferMemoryBarrierCache {
     pub(super) barriers: Vec32<BufferMemoryBarrier>,
+    pub(crate) releases: Vec32<(vk::Buffer, BufferMemoryBarrier)>,
 }
 
 #[derive(Default, Clone, Copy)]
@@ -154,6 +173,10 @@ impl BufferMemoryBarrierCache {
         Self::default()
     }
 
+    /// Flushes the barriers of `ranges`.
+    ///
+    /// The barriers, which transfer queue family ownership, are the acquire halves of the
+    /// transfers. Their release halves are collected and recorded by the command scheduler.
     pub fn flush<'a, Alloc>(
         &mut self,
         ranges: &[BufferMemoryBarrierRange],
@@ -171,7 +194,10 @@ impl BufferMemoryBarrierCache {
                 continue
             }
             let handle = barrier.handle;
-            for barrier in &self.barriers[barrier.range_start as usize..barrier.range_end as usize] {
+            for &barrier in &self.barriers[barrier.range_start as usize..barrier.range_end as usize] {
+                if barrier.is_ownership_transfer() {
+                    self.releases.push((handle, barrier.release()));
+                }
                 vk_barriers.push(barrier.into_vk(handle));
             }
         }
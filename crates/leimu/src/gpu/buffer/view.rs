@@ -0,0 +1,67 @@
+use nox_proc::Display;
+use nox_mem::slot_map::SlotIndex;
+
+use super::*;
+
+/// Identifies a [`texel buffer view`][1] of a buffer.
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkBufferView.html
+#[must_use]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+#[display("(buffer id: {0}, view id: {1})")]
+pub struct BufferViewId(BufferId, SlotIndex<BufferView>);
+
+impl BufferViewId {
+
+    #[inline(always)]
+    pub(crate) fn new(
+        buffer_id: BufferId,
+        view_index: SlotIndex<BufferView>,
+    ) -> Self {
+        Self(buffer_id, view_index)
+    }
+
+    /// Gets the [`BufferId`] portion of the id.
+    #[inline(always)]
+    pub fn buffer_id(self) -> BufferId {
+        self.0
+    }
+
+    #[inline(always)]
+    pub(crate) fn view_index(self) -> SlotIndex<BufferView> {
+        self.1
+    }
+}
+
+/// Specifies the range and format of a texel buffer view.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct BufferViewRange {
+    /// The format of the texels in the buffer.
+    pub format: Format,
+    /// The offset, in bytes, from the start of the buffer.
+    pub offset: DeviceSize,
+    /// The size, in bytes, of the view.
+    pub size: DeviceSize,
+}
+
+impl BufferViewRange {
+
+    #[inline(always)]
+    pub fn new(
+        format: Format,
+        offset: DeviceSize,
+        size: DeviceSize,
+    ) -> Self {
+        Self {
+            format,
+            offset,
+            size,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct BufferView {
+    pub handle: vk::BufferView,
+    pub range: BufferViewRange,
+}
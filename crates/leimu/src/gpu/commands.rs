@@ -3,10 +3,14 @@ mod command;
 mod pool;
 pub mod cache;
 mod copy;
+mod blit_fallback;
 mod pipeline;
 mod graphics;
 mod compute;
+mod ownership;
+mod sparse;
 mod dependency_hint;
+mod render_graph;
 pub mod scheduler;
 
 pub mod prelude {
@@ -24,8 +28,13 @@ pub mod prelude {
     pub use pipeline::*;
     pub use graphics::*;
     pub use compute::*;
+    pub use ownership::*;
+    pub use sparse::{SparseBindCommands, NewSparseBindCommands, SparseBinds};
+    pub use render_graph::*;
     pub use super::cache as command_cache;
     pub use command_cache::PushDescriptorBinding;
     pub(crate) use scheduler::{CommandRecorder, QueueSchedulerReadGuard, CommandRecorderCache};
+    pub(crate) use blit_fallback::{BlitFallback, BlitPipeline};
+    pub(crate) use sparse::{SparseBindSubmit, queue_bind_sparse};
 
 }
@@ -0,0 +1,618 @@
+use ahash::AHashMap;
+
+use nox_ash::vk;
+
+use crate::{
+    gpu::prelude::*,
+    sync::*,
+    error::*,
+};
+
+const VERTEX_SHADER: &str = "
+    #version 450
+
+    void main() {
+        vec2 uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+        gl_Position = vec4(uv * 2.0f - 1.0f, 0.0f, 1.0f);
+    }
+";
+
+const FRAGMENT_SHADER: &str = "
+    layout(set = 0, binding = 0) uniform SAMPLER src;
+
+    layout(push_constant) uniform Region {
+        vec2 dst_offset;
+        vec2 dst_extent;
+        vec2 src_offset;
+        vec2 src_extent;
+    } region;
+
+    layout(location = 0) out VEC4 out_color;
+
+    void main() {
+        vec2 t = (gl_FragCoord.xy - region.dst_offset) / region.dst_extent;
+        vec2 texel = region.src_offset + t * region.src_extent;
+    #ifdef INTEGER
+        out_color = texelFetch(src, ivec2(floor(texel)), 0);
+    #else
+        out_color = textureLod(src, texel / vec2(textureSize(src, 0)), 0.0f);
+    #endif
+    }
+";
+
+/// The sample type of a fragment shader variant.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SampleType {
+    Float,
+    Uint,
+    Sint,
+}
+
+impl SampleType {
+
+    fn new(format: Format) -> Option<Self> {
+        let numeric = format.numeric_format_color()?;
+        Some(if numeric.is_floating_point() {
+            Self::Float
+        } else if numeric.is_unsigned_integer() {
+            Self::Uint
+        } else {
+            Self::Sint
+        })
+    }
+
+    #[inline]
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            Self::Float => "#version 450\n#define SAMPLER sampler2D\n#define VEC4 vec4\n",
+            Self::Uint => "#version 450\n#define SAMPLER usampler2D\n#define VEC4 uvec4\n#define INTEGER\n",
+            Self::Sint => "#version 450\n#define SAMPLER isampler2D\n#define VEC4 ivec4\n#define INTEGER\n",
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    samplers: [vk::Sampler; 2],
+    set_layout: vk::DescriptorSetLayout,
+    pipeline_layout: vk::PipelineLayout,
+    vertex_module: vk::ShaderModule,
+    fragment_modules: [vk::ShaderModule; 3],
+    pipelines: AHashMap<Format, vk::Pipeline>,
+}
+
+/// Lazily created objects of the fragment shader path used by [`CopyCommands::blit_image`] and
+/// [`CopyCommands::gen_mip_map`] for formats without blit support.
+pub(crate) struct BlitFallback {
+    device: LogicalDevice,
+    state: Mutex<State>,
+}
+
+/// A pipeline of [`BlitFallback`], which renders a region of a single layer sampled from one image
+/// view to another.
+#[derive(Clone, Copy)]
+pub(crate) struct BlitPipeline {
+    pipeline: vk::Pipeline,
+    layout: vk::PipelineLayout,
+    sampler: vk::Sampler,
+}
+
+impl BlitFallback {
+
+    pub fn new(device: LogicalDevice) -> Self {
+        Self {
+            device,
+            state: Default::default(),
+        }
+    }
+
+    /// Returns whether `format` *can* be blitted with the fallback path given its
+    /// `format_features`.
+    ///
+    /// `filter` is only checked for the source.
+    pub fn is_supported(
+        format: Format,
+        format_features: FormatFeatures,
+        source: bool,
+        filter: Filter,
+    ) -> bool {
+        let Some(sample_type) = SampleType::new(format) else {
+            return false
+        };
+        if source {
+            format_features.contains(FormatFeatures::SAMPLED_IMAGE) &&
+            (filter == Filter::Nearest || (
+                sample_type == SampleType::Float &&
+                format_features.contains(FormatFeatures::SAMPLED_IMAGE_FILTER_LINEAR)
+            ))
+        } else {
+            format_features.contains(FormatFeatures::COLOR_ATTACHMENT)
+        }
+    }
+
+    fn create_module(&self, source: &str, stage: ShaderStage, name: &str) -> Result<vk::ShaderModule> {
+        let compiled = ShaderSource::Glsl(source)
+            .compile(self.device.api_version(), stage, name)?;
+        let spirv = compiled.spirv();
+        let create_info = vk::ShaderModuleCreateInfo {
+            code_size: size_of_val(spirv),
+            p_code: spirv.as_ptr(),
+            ..Default::default()
+        };
+        unsafe {
+            self.device
+                .create_shader_module(&create_info, None)
+                .context("failed to create shader module")
+        }
+    }
+
+    fn init(&self, state: &mut State) -> Result<()> {
+        if state.pipeline_layout != vk::PipelineLayout::null() {
+            return Ok(())
+        }
+        for (sampler, filter) in state.samplers.iter_mut().zip([vk::Filter::NEAREST, vk::Filter::LINEAR]) {
+            let create_info = vk::SamplerCreateInfo {
+                mag_filter: filter,
+                min_filter: filter,
+                mipmap_mode: vk::SamplerMipmapMode::NEAREST,
+                address_mode_u: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+                address_mode_v: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+                address_mode_w: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+                ..Default::default()
+            };
+            *sampler = unsafe {
+                self.device
+                    .create_sampler(&create_info, None)
+                    .context("failed to create sampler")?
+            };
+        }
+        let binding = vk::DescriptorSetLayoutBinding {
+            binding: 0,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            ..Default::default()
+        };
+        let set_layout_info = vk::DescriptorSetLayoutCreateInfo {
+            flags: vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR,
+            binding_count: 1,
+            p_bindings: &binding,
+            ..Default::default()
+        };
+        state.set_layout = unsafe {
+            self.device
+                .create_descriptor_set_layout(&set_layout_info, None)
+                .context("failed to create descriptor set layout")?
+        };
+        let push_constant_range = vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            offset: 0,
+            size: size_of::<[f32; 8]>() as u32,
+        };
+        let layout_info = vk::PipelineLayoutCreateInfo {
+            set_layout_count: 1,
+            p_set_layouts: &state.set_layout,
+            push_constant_range_count: 1,
+            p_push_constant_ranges: &push_constant_range,
+            ..Default::default()
+        };
+        state.pipeline_layout = unsafe {
+            self.device
+                .create_pipeline_layout(&layout_info, None)
+                .context("failed to create pipeline layout")?
+        };
+        state.vertex_module = self.create_module(
+            VERTEX_SHADER, ShaderStage::Vertex, "blit fallback vertex shader",
+        )?;
+        Ok(())
+    }
+
+    /// Gets or creates the pipeline rendering to `dst_format` and sampling with `filter`.
+    pub fn get_pipeline(&self, dst_format: Format, filter: Filter) -> Result<BlitPipeline> {
+        let sample_type = SampleType::new(dst_format)
+            .ok_or_else(|| Error::just_context(format!(
+                "format {dst_format} is not a color format"
+            )))?;
+        let mut state = self.state.lock();
+        self.init(&mut state)
+            .context("failed to initialize blit fallback")?;
+        let sampler = state.samplers[(filter == Filter::Linear) as usize];
+        if let Some(&pipeline) = state.pipelines.get(&dst_format) {
+            return Ok(BlitPipeline {
+                pipeline,
+                layout: state.pipeline_layout,
+                sampler,
+            })
+        }
+        let index = sample_type.index();
+        if state.fragment_modules[index] == vk::ShaderModule::null() {
+            let source = format!("{}{FRAGMENT_SHADER}", sample_type.header());
+            state.fragment_modules[index] = self.create_module(
+                &source, ShaderStage::Fragment, "blit fallback fragment shader",
+            )?;
+        }
+        let stages = [
+            vk::PipelineShaderStageCreateInfo {
+                stage: vk::ShaderStageFlags::VERTEX,
+                module: state.vertex_module,
+                p_name: c"main".as_ptr(),
+                ..Default::default()
+            },
+            vk::PipelineShaderStageCreateInfo {
+                stage: vk::ShaderStageFlags::FRAGMENT,
+                module: state.fragment_modules[index],
+                p_name: c"main".as_ptr(),
+                ..Default::default()
+            },
+        ];
+        let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::default();
+        let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo {
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            ..Default::default()
+        };
+        let viewport_state = vk::PipelineViewportStateCreateInfo {
+            viewport_count: 1,
+            scissor_count: 1,
+            ..Default::default()
+        };
+        let rasterization_state = vk::PipelineRasterizationStateCreateInfo {
+            polygon_mode: vk::PolygonMode::FILL,
+            cull_mode: vk::CullModeFlags::NONE,
+            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+            line_width: 1.0,
+            ..Default::default()
+        };
+        let multisample_state = vk::PipelineMultisampleStateCreateInfo {
+            rasterization_samples: vk::SampleCountFlags::TYPE_1,
+            ..Default::default()
+        };
+        let color_blend_attachment = vk::PipelineColorBlendAttachmentState {
+            color_write_mask: vk::ColorComponentFlags::RGBA,
+            ..Default::default()
+        };
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo {
+            attachment_count: 1,
+            p_attachments: &color_blend_attachment,
+            ..Default::default()
+        };
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state = vk::PipelineDynamicStateCreateInfo {
+            dynamic_state_count: dynamic_states.len() as u32,
+            p_dynamic_states: dynamic_states.as_ptr(),
+            ..Default::default()
+        };
+        let color_format: vk::Format = dst_format.into();
+        let mut rendering_info = vk::PipelineRenderingCreateInfo {
+            color_attachment_count: 1,
+            p_color_attachment_formats: &color_format,
+            ..Default::default()
+        };
+        let create_info = vk::GraphicsPipelineCreateInfo {
+            stage_count: stages.len() as u32,
+            p_stages: stages.as_ptr(),
+            p_vertex_input_state: &vertex_input_state,
+            p_input_assembly_state: &input_assembly_state,
+            p_viewport_state: &viewport_state,
+            p_rasterization_state: &rasterization_state,
+            p_multisample_state: &multisample_state,
+            p_color_blend_state: &color_blend_state,
+            p_dynamic_state: &dynamic_state,
+            layout: state.pipeline_layout,
+            ..Default::default()
+        }.push_next(&mut rendering_info);
+        let mut pipeline = vk::Pipeline::null();
+        unsafe {
+            self.device.create_graphics_pipelines(
+                vk::PipelineCache::null(),
+                &[create_info],
+                None,
+                core::slice::from_mut(&mut pipeline),
+            ).context_with(|| format!(
+                "failed to create blit fallback pipeline for format {dst_format}"
+            ))?;
+        }
+        state.pipelines.insert(dst_format, pipeline);
+        Ok(BlitPipeline {
+            pipeline,
+            layout: state.pipeline_layout,
+            sampler,
+        })
+    }
+}
+
+impl Drop for BlitFallback {
+
+    fn drop(&mut self) {
+        let state = self.state.get_mut();
+        unsafe {
+            for &pipeline in state.pipelines.values() {
+                self.device.destroy_pipeline(pipeline, None);
+            }
+            for &module in state.fragment_modules
+                .iter()
+                .chain(Some(&state.vertex_module))
+            {
+                if module != vk::ShaderModule::null() {
+                    self.device.destroy_shader_module(module, None);
+                }
+            }
+            if state.pipeline_layout != vk::PipelineLayout::null() {
+                self.device.destroy_pipeline_layout(state.pipeline_layout, None);
+                self.device.destroy_descriptor_set_layout(state.set_layout, None);
+                for &sampler in &state.samplers {
+                    self.device.destroy_sampler(sampler, None);
+                }
+            }
+        }
+    }
+}
+
+impl BlitPipeline {
+
+    /// Records a blit from `src_offsets` of `src_view` to `dst_offsets` of `dst_view`.
+    ///
+    /// # Safety
+    /// `src_view` *must* be a single layer, single level view in the
+    /// [`vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL`] layout and `dst_view` *must* be a single
+    /// layer, single level view with dimensions `dst_dimensions` in the
+    /// [`vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL`] layout.
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn record(
+        self,
+        device: &LogicalDevice,
+        push_descriptor_device: &ext::push_descriptor::Device,
+        command_buffer: vk::CommandBuffer,
+        src_view: vk::ImageView,
+        src_offsets: [ImageBlitOffset; 2],
+        dst_view: vk::ImageView,
+        dst_offsets: [ImageBlitOffset; 2],
+        dst_dimensions: Dimensions,
+    ) {
+        let x = dst_offsets[0].x.min(dst_offsets[1].x);
+        let y = dst_offsets[0].y.min(dst_offsets[1].y);
+        let render_area = vk::Rect2D {
+            offset: vk::Offset2D { x: x as i32, y: y as i32 },
+            extent: vk::Extent2D {
+                width: dst_offsets[0].x.abs_diff(dst_offsets[1].x),
+                height: dst_offsets[0].y.abs_diff(dst_offsets[1].y),
+            },
+        };
+        let color_attachment = vk::RenderingAttachmentInfo {
+            image_view: dst_view,
+            image_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            load_op: vk::AttachmentLoadOp::LOAD,
+            store_op: vk::AttachmentStoreOp::STORE,
+            ..Default::default()
+        };
+        let rendering_info = vk::RenderingInfo {
+            render_area,
+            layer_count: 1,
+            color_attachment_count: 1,
+            p_color_attachments: &color_attachment,
+            ..Default::default()
+        };
+        let viewport = vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: dst_dimensions.width as f32,
+            height: dst_dimensions.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        };
+        let image_info = vk::DescriptorImageInfo {
+            sampler: self.sampler,
+            image_view: src_view,
+            image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        };
+        let write = vk::WriteDescriptorSet {
+            dst_binding: 0,
+            descriptor_count: 1,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            p_image_info: &image_info,
+            ..Default::default()
+        };
+        let region = [
+            dst_offsets[0].x as f32,
+            dst_offsets[0].y as f32,
+            dst_offsets[1].x as f32 - dst_offsets[0].x as f32,
+            dst_offsets[1].y as f32 - dst_offsets[0].y as f32,
+            src_offsets[0].x as f32,
+            src_offsets[0].y as f32,
+            src_offsets[1].x as f32 - src_offsets[0].x as f32,
+            src_offsets[1].y as f32 - src_offsets[0].y as f32,
+        ];
+        let mut constants = [0u8; size_of::<[f32; 8]>()];
+        for (bytes, value) in constants.chunks_exact_mut(4).zip(region) {
+            bytes.copy_from_slice(&value.to_ne_bytes());
+        }
+        unsafe {
+            device.cmd_begin_rendering(command_buffer, &rendering_info);
+            device.cmd_bind_pipeline(
+                command_buffer, vk::PipelineBindPoint::GRAPHICS, self.pipeline,
+            );
+            device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+            device.cmd_set_scissor(command_buffer, 0, &[render_area]);
+            push_descriptor_device.cmd_push_descriptor_set(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.layout,
+                0,
+                &[write],
+            );
+            device.cmd_push_constants(
+                command_buffer,
+                self.layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                &constants,
+            );
+            device.cmd_draw(command_buffer, 3, 1, 0, 0);
+            device.cmd_end_rendering(command_buffer);
+        }
+    }
+}
+
+/// Validates and records `regions` with the fallback path of [`BlitFallback`].
+///
+/// # Safety
+/// `src_image` and `dst_image` *must* be valid pointers to images registered for the command
+/// recorded to `command_buffer`. They *may* point to the same image.
+#[allow(clippy::too_many_arguments)]
+pub(crate) unsafe fn record_blits(
+    gpu: &Gpu,
+    command_buffer: vk::CommandBuffer,
+    queue_family_index: u32,
+    barrier_cache: &mut ImageMemoryBarrierCache,
+    src_image: *mut ImageMeta,
+    dst_image: *mut ImageMeta,
+    regions: &[ImageBlitRegion],
+    filter: Filter,
+) -> Result<()> {
+    let push_descriptor_device = gpu
+        .get_extension_device::<ext::push_descriptor::Device>()
+        .ok_or_else(|| Error::just_context(
+            "blitting formats without blit support requires push descriptors to be enabled"
+        ))?;
+    let same_image = core::ptr::eq(src_image, dst_image);
+    let (src_properties, dst_properties) = unsafe {
+        ((*src_image).properties(), (*dst_image).properties())
+    };
+    if !BlitFallback::is_supported(
+        src_properties.format, src_properties.format_features, true, filter,
+    ) {
+        return Err(Error::just_context(format!(
+            "source image format {} supports neither blitting nor sampling with filter {}",
+            src_properties.format, filter,
+        )))
+    }
+    if !BlitFallback::is_supported(
+        dst_properties.format, dst_properties.format_features, false, filter,
+    ) {
+        return Err(Error::just_context(format!(
+            "destination image format {} supports neither blitting nor color attachments",
+            dst_properties.format,
+        )))
+    }
+    if SampleType::new(src_properties.format) != SampleType::new(dst_properties.format) {
+        return Err(Error::just_context(format!(
+            "source format {} and destination format {} must both be either floating point, unsigned integer or signed integer formats",
+            src_properties.format, dst_properties.format,
+        )))
+    }
+    if !src_properties.usage.contains(ImageUsages::SAMPLED) {
+        return Err(Error::just_context(
+            "source image usage must contain sampled usage when its format doesn't support blitting"
+        ))
+    }
+    if !dst_properties.usage.contains(ImageUsages::COLOR_ATTACHMENT) {
+        return Err(Error::just_context(
+            "destination image usage must contain color attachment usage when its format doesn't support blitting"
+        ))
+    }
+    if src_properties.dimensions.depth != 1 || dst_properties.dimensions.depth != 1 {
+        return Err(Error::just_context(
+            "3D images can't be blitted when their format doesn't support blitting"
+        ))
+    }
+    let pipeline = gpu.blit_fallback().get_pipeline(dst_properties.format, filter)?;
+    let src_state = ImageSubresourceState::new(
+        vk::PipelineStageFlags2::FRAGMENT_SHADER,
+        vk::AccessFlags2::SHADER_SAMPLED_READ,
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        queue_family_index,
+    );
+    let dst_state = ImageSubresourceState::new(
+        vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT,
+        vk::AccessFlags2::COLOR_ATTACHMENT_READ | vk::AccessFlags2::COLOR_ATTACHMENT_WRITE,
+        vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        queue_family_index,
+    );
+    let device = gpu.device();
+    let tmp_alloc = gpu.tmp_alloc();
+    let tmp_alloc = tmp_alloc.guard();
+    for region in regions {
+        if region.src_subresource.aspect_mask != ImageAspects::COLOR ||
+            region.dst_subresource.aspect_mask != ImageAspects::COLOR
+        {
+            return Err(Error::just_context(
+                "only color aspects can be blitted when the format doesn't support blitting"
+            ))
+        }
+        if same_image && region.src_subresource.overlaps(region.dst_subresource) {
+            return Err(Error::just_context(
+                "source and destination subresources must not overlap when the format doesn't support blitting"
+            ))
+        }
+        let src_layers = region.src_subresource.effective(src_properties.array_layers);
+        let dst_layers = region.dst_subresource.effective(dst_properties.array_layers);
+        if src_layers.layer_count != dst_layers.layer_count {
+            return Err(Error::just_context(format!(
+                "source layer count {} must match destination layer count {}",
+                src_layers.layer_count, dst_layers.layer_count,
+            )))
+        }
+        let src_lod = src_properties.dimensions.lod(src_layers.mip_level);
+        let dst_lod = dst_properties.dimensions.lod(dst_layers.mip_level);
+        for (offsets, lod, name) in [
+            (region.src_offsets, src_lod, "source"),
+            (region.dst_offsets, dst_lod, "destination"),
+        ] {
+            for offset in offsets {
+                if offset.x > lod.width || offset.y > lod.height || offset.z > 1 {
+                    return Err(Error::just_context(format!(
+                        "{name} region offset {offset} was out of range of image subresource dimensions {lod}",
+                    )))
+                }
+            }
+        }
+        for layer in 0..src_layers.layer_count {
+            let src_range = ImageSubresourceRange::default()
+                .aspect_mask(ImageAspects::COLOR)
+                .base_mip_level(src_layers.mip_level)
+                .level_count(1)
+                .base_array_layer(src_layers.base_array_layer + layer)
+                .layer_count(1);
+            let dst_range = ImageSubresourceRange::default()
+                .aspect_mask(ImageAspects::COLOR)
+                .base_mip_level(dst_layers.mip_level)
+                .level_count(1)
+                .base_array_layer(dst_layers.base_array_layer + layer)
+                .layer_count(1);
+            let (src_view, dst_view, ranges) = unsafe {
+                let src_view = (*src_image).get_or_create_view_handle(src_range)?;
+                let dst_view = (*dst_image).get_or_create_view_handle(dst_range)?;
+                let range1 = (*src_image).memory_barrier(
+                    src_state, src_range, true, barrier_cache,
+                ).context("source image memory barrier failed")?;
+                let range2 = (*dst_image).memory_barrier(
+                    dst_state, dst_range, true, barrier_cache,
+                ).context("destination image memory barrier failed")?;
+                (src_view, dst_view, [range1, range2])
+            };
+            let memory_barriers = barrier_cache.flush(&ranges, &tmp_alloc)?;
+            let dependency_info = vk::DependencyInfo {
+                image_memory_barrier_count: memory_barriers.len(),
+                p_image_memory_barriers: memory_barriers.as_ptr(),
+                ..Default::default()
+            };
+            unsafe {
+                device.cmd_pipeline_barrier2(command_buffer, &dependency_info);
+                pipeline.record(
+                    device,
+                    &push_descriptor_device,
+                    command_buffer,
+                    src_view,
+                    region.src_offsets,
+                    dst_view,
+                    region.dst_offsets,
+                    dst_lod,
+                );
+            }
+        }
+    }
+    Ok(())
+}
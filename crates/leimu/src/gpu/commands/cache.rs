@@ -287,6 +287,11 @@ impl<'a> PushDescriptorBinding<'a> {
     pub fn image_infos(&self) -> Option<&[DescriptorImageInfo]> {
         self.infos.as_images()
     }
+
+    #[inline(always)]
+    pub fn texel_buffer_infos(&self) -> Option<&[BufferViewId]> {
+        self.infos.as_texel_buffers()
+    }
 }
 
 #[derive(Default)]
@@ -383,25 +388,56 @@ pub struct PushDescriptorBindingCall {
     pub images: NonNullVec32<'static, (DescriptorImageInfo, Option<ShaderImageLayout>)>,
 }
 
+/// The descriptor buffer offsets of a single descriptor set, which were set on a command buffer.
+#[derive(Clone, Copy)]
+pub struct BoundDescriptorBufferSet {
+    pub layout: vk::PipelineLayout,
+    pub stage_flags: vk::ShaderStageFlags,
+    pub buffer_index: u32,
+    pub offset: DeviceSize,
+}
+
+/// The descriptor buffers bound to a command buffer.
+///
+/// Binding descriptor buffers invalidates every previously set descriptor buffer offset, so the
+/// offsets are tracked per set number to re-set them after binding.
+#[derive(Default)]
+pub struct BoundDescriptorBuffers {
+    pub command_buffer: vk::CommandBuffer,
+    pub buffers: Vec32<(DeviceAddress, vk::BufferUsageFlags)>,
+    pub sets: Vec32<Option<BoundDescriptorBufferSet>>,
+}
+
 #[derive(Default)]
 pub struct PipelineCommandCache {
     pub push_descriptor_device: Option<ext::push_descriptor::Device>,
+    pub descriptor_buffer_device: Option<ext::descriptor_buffer::Device>,
     pub descriptor_set_binds: Vec32<DescriptorSetBindCall>,
     pub push_descriptor_binding_calls: Vec32<PushDescriptorBindingCall>,
     pub push_descriptor_binding_cache: PushDescriptorBindingsCache,
+    pub descriptor_buffers: BoundDescriptorBuffers,
 }
 
 impl PipelineCommandCache {
 
-    pub fn new(push_descriptor_device: Option<ext::push_descriptor::Device>) -> Self {
+    pub fn new(
+        push_descriptor_device: Option<ext::push_descriptor::Device>,
+        descriptor_buffer_device: Option<ext::descriptor_buffer::Device>,
+    ) -> Self {
         Self {
             push_descriptor_device,
+            descriptor_buffer_device,
             ..Default::default()
         }
     }
 
-    pub(crate) fn init(&mut self, device: Option<ext::push_descriptor::Device>) {
-        self.push_descriptor_device = device;
+    pub(crate) fn init(
+        &mut self,
+        push_descriptor_device: Option<ext::push_descriptor::Device>,
+        descriptor_buffer_device: Option<ext::descriptor_buffer::Device>,
+    ) {
+        self.push_descriptor_device = push_descriptor_device;
+        self.descriptor_buffer_device = descriptor_buffer_device;
     }
 
     /// Resets the cache.
@@ -423,6 +459,9 @@ impl PipelineCommandCache {
             self.descriptor_set_binds.clear();
             self.push_descriptor_binding_calls.clear();
         }
+        self.descriptor_buffers.command_buffer = vk::CommandBuffer::null();
+        self.descriptor_buffers.buffers.clear();
+        self.descriptor_buffers.sets.clear();
     }
 
     /// Prepares [`shader resource cache`][1] with the contents of this cache.
@@ -481,7 +520,15 @@ impl PipelineCommandCache {
                                 bar.barrier_info.access,
                                 Some(bar.barrier_info.access)
                             ))
-                        ).unwrap_or((CommandOrdering::Lenient, ExplicitAccess::SHADER_READ_AND_WRITE, None));
+                        ).unwrap_or_else(|| {
+                            let access =
+                                if binding.ty().is_acceleration_structure() {
+                                    ExplicitAccess::ACCELERATION_STRUCTURE_READ
+                                } else {
+                                    ExplicitAccess::SHADER_READ_AND_WRITE
+                                };
+                            (CommandOrdering::Lenient, access, None)
+                        });
                     for buffer in binding.buffer_descriptors() {
                         if let Some((id, offset, size)) = buffer.buffer {
                             shader_resource_cache.touch_buffer(
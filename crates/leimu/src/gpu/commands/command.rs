@@ -15,7 +15,8 @@ use nox_ash::{vk, ash_style_enum};
 
 use crate::{
     error::*,
-    gpu::prelude::*
+    gpu::prelude::*,
+    sync::Arc,
 };
 
 /// An opaque handle to a [`command buffer`][1].
@@ -30,6 +31,10 @@ pub struct CommandResult<'a, Alloc>
     pub wait_scope: vk::PipelineStageFlags2,
     pub signal_scope: vk::PipelineStageFlags2,
     pub queue: DeviceQueue,
+    /// The sparse binds submitted with [`vkQueueBindSparse`][1] instead of command buffers.
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/vkQueueBindSparse.html
+    pub sparse_binds: Option<SparseBinds<'a, Alloc>>,
 }
 
 /// A builder trait for [`Commands`]
@@ -152,6 +157,11 @@ ash_style_enum!(
         DEPTH_STENCIL_ATTACHMENT =
             vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_READ.as_raw() |
             vk::AccessFlags2::DEPTH_STENCIL_ATTACHMENT_WRITE.as_raw(),
+        /// Specifies that an acceleration structure will be read from in a shader.
+        ///
+        /// This is the default access of acceleration structure descriptors.
+        #[display("acceleration structure read")]
+        ACCELERATION_STRUCTURE_READ = vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR.as_raw(),
     }
 );
 
@@ -374,6 +384,10 @@ pub(crate) enum CommandPoolResetResult {
 pub struct SchedulerWorker {
     pub(super) gpu: Gpu,
     command_pools: AHashMap<DeviceQueue, SchedulerCommandPool>,
+    /// Command pools used for recording secondary command buffers on other threads.
+    ///
+    /// Command pools are externally synchronized, so each thread records with its own pool.
+    parallel_command_pools: AHashMap<DeviceQueue, Vec32<SchedulerCommandPool>>,
     pub(super) semaphore_id: TimelineSemaphoreId,
     pub(super) timeline_value: u64,
     pub(super) last_reset: u64,
@@ -381,6 +395,7 @@ pub struct SchedulerWorker {
     pub(super) present_prep_value: u64,
     pub(super) samplers: AHashSet<Sampler>,
     pub(super) pipelines: AHashSet<PipelineHandle>,
+    pub(super) retired_memory: Vec32<Arc<DeviceMemoryObj>>,
 }
 
 impl SchedulerWorker {
@@ -401,6 +416,7 @@ impl SchedulerWorker {
         }
         Ok(Self {
             command_pools,
+            parallel_command_pools: AHashMap::default(),
             gpu,
             semaphore_id,
             timeline_value: 0,
@@ -409,6 +425,7 @@ impl SchedulerWorker {
             present_prep_value: 0,
             samplers: AHashSet::default(),
             pipelines: AHashSet::default(),
+            retired_memory: Vec32::new(),
         })
     }
 
@@ -440,6 +457,37 @@ impl SchedulerWorker {
             .context_with(|| format!("failed to allocate secondary command buffers for queue {queue}"))
     }
 
+    /// Allocates one secondary command buffer from each of `count` distinct command pools.
+    ///
+    /// The command buffers *can* be recorded concurrently on different threads, as long as each
+    /// command buffer is only recorded on one thread at a time.
+    pub fn allocate_parallel_secondaries(
+        &mut self,
+        queue: &DeviceQueue,
+        count: u32,
+    ) -> Result<Vec32<CommandBuffer>> {
+        let device = self.gpu.device();
+        if !self.command_pools.contains_key(queue) {
+            return Err(Error::just_context(format!("invalid device queue {queue}")))
+        }
+        let pools = self.parallel_command_pools
+            .entry(queue.clone())
+            .or_default();
+        while pools.len() < count {
+            pools.push(SchedulerCommandPool::new(device, queue.family_index())?);
+        }
+        let mut command_buffers = Vec32::with_capacity(count);
+        for pool in &mut pools[0..count as usize] {
+            command_buffers.push(pool
+                .allocate_secondaries(device, 1)
+                .context_with(|| format!(
+                    "failed to allocate parallel secondary command buffers for queue {queue}"
+                ))?[0]
+            );
+        }
+        Ok(command_buffers)
+    }
+
     #[inline(always)]
     pub fn add_sampler(&mut self, sampler: Sampler) {
         self.samplers.insert(sampler);
@@ -459,10 +507,16 @@ impl SchedulerWorker {
                     pool.reset(device)?;
                 }
             }
+            for pool in self.parallel_command_pools.values_mut().flatten() {
+                unsafe {
+                    pool.reset(device)?;
+                }
+            }
             self.timeline_value += 1;
             self.last_reset = current_frame;
             self.samplers.clear();
             self.pipelines.clear();
+            self.retired_memory.clear();
             Ok(CommandPoolResetResult::Ready(self.semaphore_id, self.timeline_value))
         } else {
             Ok(CommandPoolResetResult::Pending(self.last_reset))
@@ -482,6 +536,7 @@ impl SchedulerWorker {
             self.last_reset = current_frame;
             self.samplers.clear();
             self.pipelines.clear();
+            self.retired_memory.clear();
             Ok((self.semaphore_id, self.timeline_value))
         } else {
             Err(Error::just_context(format!(
@@ -499,6 +554,9 @@ impl Drop for SchedulerWorker {
             for pool in self.command_pools.values_mut() {
                 pool.destroy(device);
             }
+            for pool in self.parallel_command_pools.values_mut().flatten() {
+                pool.destroy(device);
+            }
             self.gpu.destroy_timeline_semaphores(&[self.semaphore_id]);
         }
     }
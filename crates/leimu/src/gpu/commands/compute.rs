@@ -29,8 +29,12 @@ pub struct ComputeCommands<'a, 'b> {
     gpu: Gpu,
     queue: DeviceQueue,
     current_pipeline: Option<ComputePipeline>,
+    current_ray_tracing_pipeline: Option<RayTracingPipeline>,
+    ray_tracing_device: Option<ext::ray_tracing_pipeline::Device>,
     primary_command_buffer: vk::CommandBuffer,
     command_id: CommandId,
+    queries: CommandQueries,
+    labels: CommandLabels,
     wait_scope: vk::PipelineStageFlags2,
     signal_scope: vk::PipelineStageFlags2,
 }
@@ -74,8 +78,12 @@ impl NewCommands for NewComputeCommands {
             gpu,
             queue,
             current_pipeline: None,
+            current_ray_tracing_pipeline: None,
+            ray_tracing_device: None,
             primary_command_buffer,
             command_id,
+            queries: Default::default(),
+            labels: Default::default(),
             wait_scope: vk::PipelineStageFlags2::empty(),
             signal_scope: vk::PipelineStageFlags2::empty(),
         })
@@ -107,6 +115,8 @@ unsafe impl<'a, 'b> Commands<'a, 'b> for ComputeCommands<'a, 'b> {
     fn finish<'c, Alloc>(self, alloc: &'c Alloc) -> Result<CommandResult<'c, Alloc>>
         where Alloc: ?Sized + LocalAlloc<Error = arena::Error>
     {
+        self.queries.finish()?;
+        self.labels.finish()?;
         let mut primary_command_buffers = FixedVec32
             ::with_capacity(1, alloc)
             .context("alloc failed")?;
@@ -116,12 +126,138 @@ unsafe impl<'a, 'b> Commands<'a, 'b> for ComputeCommands<'a, 'b> {
             wait_scope: self.wait_scope,
             signal_scope: self.signal_scope,
             queue: self.queue,
+            sparse_binds: None,
         })
     }
 }
 
 impl<'a, 'b> ComputeCommands<'a, 'b> {
 
+    /// Writes a timestamp to `query` of a [`timestamp`][1] query pool once all previous commands
+    /// have reached `stage`.
+    ///
+    /// The result can be retrieved with [`Gpu::get_query_results`] after the frame has finished.
+    ///
+    /// # Valid usage
+    /// - `pool_id` *must* be a valid [`QueryPoolId`] of a [`timestamp`][1] query pool.
+    /// - `query` *must* be less than the query count of the pool and it *must* not be active.
+    /// - The queue family of the command *must* support timestamps.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdWriteTimestamp2.html>
+    ///
+    /// [1]: QueryType::Timestamp
+    pub fn write_timestamp(
+        &mut self,
+        pool_id: QueryPoolId,
+        query: u32,
+        stage: TimestampStage,
+    ) -> Result<()>
+    {
+        let frame = self.recorder.current_frame();
+        unsafe {
+            self.queries.write_timestamp(
+                &self.gpu, self.primary_command_buffer, &self.queue,
+                self.command_id, frame,
+                pool_id, query, stage,
+            )
+        }
+    }
+
+    /// Begins `query` of an [`occlusion`][1] or a [`pipeline statistics`][2] query pool.
+    ///
+    /// Every begun query *must* be ended with [`end_query`][3] before the command finishes.
+    ///
+    /// # Valid usage
+    /// - `pool_id` *must* be a valid [`QueryPoolId`] of a non-timestamp query pool.
+    /// - `query` *must* be less than the query count of the pool.
+    /// - No other query of the same pool *can* be active.
+    /// - If `precise` is true, the pool *must* be an occlusion query pool and
+    ///   [`BaseDeviceFeatures::occlusion_query_precise`] *must* be enabled.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBeginQuery.html>
+    ///
+    /// [1]: QueryType::Occlusion
+    /// [2]: QueryType::PipelineStatistics
+    /// [3]: Self::end_query
+    pub fn begin_query(
+        &mut self,
+        pool_id: QueryPoolId,
+        query: u32,
+        precise: bool,
+    ) -> Result<()>
+    {
+        let frame = self.recorder.current_frame();
+        unsafe {
+            self.queries.begin_query(
+                &self.gpu, self.primary_command_buffer, &self.queue,
+                self.command_id, frame,
+                pool_id, query, precise,
+            )
+        }
+    }
+
+    /// Ends `query` begun with [`begin_query`][1].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdEndQuery.html>
+    ///
+    /// [1]: Self::begin_query
+    pub fn end_query(
+        &mut self,
+        pool_id: QueryPoolId,
+        query: u32,
+    ) -> Result<()>
+    {
+        unsafe {
+            self.queries.end_query(
+                &self.gpu, self.primary_command_buffer,
+                self.command_id,
+                pool_id, query,
+            )
+        }
+    }
+
+    /// Begins a debug label region named `name`, which shows up in debugging tools.
+    ///
+    /// Every label *must* be ended with [`end_label`][1] before the commands finish. Labels can
+    /// be nested.
+    ///
+    /// Does nothing if [`VK_EXT_debug_utils`][2] is not enabled.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBeginDebugUtilsLabelEXT.html>
+    ///
+    /// [1]: Self::end_label
+    /// [2]: Instance::with_debug_utils
+    pub fn begin_label(
+        &mut self,
+        name: &str,
+        color: [f32; 4],
+    ) {
+        unsafe {
+            self.labels.begin(
+                self.gpu.device(), self.primary_command_buffer,
+                name, color,
+            );
+        }
+    }
+
+    /// Ends the innermost label begun with [`begin_label`][1].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdEndDebugUtilsLabelEXT.html>
+    ///
+    /// [1]: Self::begin_label
+    pub fn end_label(&mut self) -> Result<()> {
+        unsafe {
+            self.labels.end(
+                self.gpu.device(), self.primary_command_buffer,
+            )
+        }
+    }
+
     pub fn bind_pipeline<F>(
         &mut self,
         id: ComputePipelineId,
@@ -200,4 +336,705 @@ impl<'a, 'b> ComputeCommands<'a, 'b> {
         }
         Ok(())
     }
+
+    /// Dispatches work with a non-zero base workgroup.
+    ///
+    /// The `WorkgroupId` built-in of each invocation is offset by the base workgroup.
+    ///
+    /// # Valid usage
+    /// - A pipeline *must* be bound.
+    /// - If any of the base workgroups is non-zero, the bound pipeline *must* have been created
+    ///   with [`dispatch base`][1] enabled.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDispatchBase.html>
+    ///
+    /// [1]: ComputePipelineCreateInfo::with_dispatch_base
+    #[inline(always)]
+    pub fn dispatch_base(
+        &mut self,
+        base_group_x: u32,
+        base_group_y: u32,
+        base_group_z: u32,
+        group_count_x: u32,
+        group_count_y: u32,
+        group_count_z: u32,
+    ) -> Result<()>
+    {
+        let Some(pipeline) = &self.current_pipeline else {
+            return Err(Error::just_context("attempting to dispatch with no pipeline bound"))
+        };
+        if (base_group_x != 0 || base_group_y != 0 || base_group_z != 0) &&
+            !pipeline.dispatch_base()
+        {
+            return Err(Error::just_context(
+                "non-zero base workgroup given, but pipeline was not created with dispatch base"
+            ))
+        }
+        unsafe {
+            self.gpu.device().cmd_dispatch_base(
+                self.primary_command_buffer,
+                base_group_x,
+                base_group_y,
+                base_group_z,
+                group_count_x,
+                group_count_y,
+                group_count_z,
+            );
+        }
+        Ok(())
+    }
+
+    /// Dispatches work with the workgroup counts read from `buffer` as a
+    /// [`vk::DispatchIndirectCommand`].
+    ///
+    /// The buffer is automatically synchronized with previous writes, so work sized by earlier
+    /// commands on the GPU doesn't need to be read back to the host.
+    ///
+    /// # Valid usage
+    /// - A pipeline *must* be bound.
+    /// - `buffer` *must* be a valid [`BufferId`].
+    /// - `buffer` *must* have been created with [`BufferUsages::INDIRECT_BUFFER`] bit set.
+    /// - `offset` *must* be a multiple of 4.
+    /// - `offset` + the size of [`vk::DispatchIndirectCommand`] *must* be less than or equal to
+    ///   the size of the buffer.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDispatchIndirect.html>
+    pub fn dispatch_indirect(
+        &mut self,
+        buffer: BufferId,
+        offset: DeviceSize,
+    ) -> Result<()>
+    {
+        if self.current_pipeline.is_none() {
+            return Err(Error::just_context("attempting to dispatch with no pipeline bound"))
+        }
+        if !offset.is_multiple_of(4) {
+            return Err(Error::just_context(format!(
+                "indirect buffer offset {offset} is not a multiple of 4"
+            )))
+        }
+        self.wait_scope |= vk::PipelineStageFlags2::DRAW_INDIRECT;
+        let size = size_of::<vk::DispatchIndirectCommand>() as DeviceSize;
+        let cache = unsafe { &mut *self.recorder.cache().get() };
+        self.recorder.write_resources(|guard| {
+            let buf = guard.register_buffer(buffer)?;
+            let buf_properties = buf.properties();
+            if let Some(err) = buf.validate_usage(BufferUsages::INDIRECT_BUFFER) {
+                return Err(Error::new(err, "indirect buffer has incompatible usage"))
+            }
+            if offset + size > buf_properties.size {
+                return Err(Error::just_context(format!(
+                    "indirect buffer offset {offset} + size {size} is greater than buffer size {}",
+                    buf_properties.size,
+                )))
+            }
+            let dst_state = BufferState::new(
+                vk::PipelineStageFlags2::DRAW_INDIRECT,
+                vk::AccessFlags2::INDIRECT_COMMAND_READ,
+                self.queue.family_index(),
+            );
+            let range = unsafe {
+                buf.memory_barrier_unchecked(
+                    offset, size,
+                    dst_state,
+                    CommandOrdering::Lenient,
+                    &mut cache.shader_resource_cache.buffer_memory_barrier_cache,
+                )
+            };
+            let command_buffer = self.primary_command_buffer;
+            if !range.is_empty() {
+                let tmp_alloc = self.gpu.tmp_alloc();
+                let tmp_alloc = tmp_alloc.guard();
+                let memory_barriers = cache.shader_resource_cache.buffer_memory_barrier_cache.flush(
+                    &[range], &tmp_alloc
+                )?;
+                let dependency_info = vk::DependencyInfo {
+                    buffer_memory_barrier_count: memory_barriers.len(),
+                    p_buffer_memory_barriers: memory_barriers.as_ptr(),
+                    ..Default::default()
+                };
+                unsafe {
+                    self.gpu.device()
+                    .cmd_pipeline_barrier2(command_buffer, &dependency_info);
+                }
+            }
+            unsafe {
+                self.gpu.device().cmd_dispatch_indirect(
+                    command_buffer,
+                    buf.handle(),
+                    offset,
+                );
+            }
+            Ok(())
+        })
+    }
+
+    /// Builds or updates acceleration structures.
+    ///
+    /// The scratch memory of each build is sub-allocated from `scratch_buffer`, starting at
+    /// `scratch_offset`, so the scratch buffer *must* be large enough to hold the scratch sizes
+    /// of all builds, each aligned to
+    /// [`ext::acceleration_structure::Attributes::MIN_ACCELERATION_STRUCTURE_SCRATCH_OFFSET_ALIGNMENT`].
+    /// The scratch sizes *can* be queried with [`Gpu::get_acceleration_structure_build_sizes`].
+    ///
+    /// The input buffers, acceleration structures and the scratch memory are automatically
+    /// synchronized with previous and later commands.
+    ///
+    /// # Valid usage
+    /// - [`ext::acceleration_structure`] *must* be enabled.
+    /// - `scratch_buffer` *must* have been created with [`BufferUsages::STORAGE_BUFFER`] and
+    ///   [`BufferUsages::SHADER_DEVICE_ADDRESS`] usage.
+    /// - Each buffer read by the geometries *must* have been created with
+    ///   [`BufferUsages::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY`] and
+    ///   [`BufferUsages::SHADER_DEVICE_ADDRESS`] usage.
+    /// - The size of each destination *must* be at least the acceleration structure size
+    ///   returned by [`Gpu::get_acceleration_structure_build_sizes`].
+    /// - The instances of a top level acceleration structure *must* only reference bottom level
+    ///   acceleration structures built by earlier commands or an earlier call of this function,
+    ///   not by the same call.
+    /// - If a build is an [`update`][1], the source *must* have been built with
+    ///   [`BuildAccelerationStructureFlags::ALLOW_UPDATE`], and `flags` and the geometries *must*
+    ///   match the ones of its last build except for the geometry data.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBuildAccelerationStructuresKHR.html>
+    ///
+    /// [1]: AccelerationStructureBuildInfo::with_update
+    pub fn build_acceleration_structures(
+        &mut self,
+        scratch_buffer: BufferId,
+        scratch_offset: DeviceSize,
+        infos: &[AccelerationStructureBuildInfo],
+    ) -> Result<()>
+    {
+        if infos.is_empty() {
+            return Ok(())
+        }
+        let device = self.gpu.get_extension_device::<ext::acceleration_structure::Device>()
+            .ok_or_else(|| Error::just_context("acceleration_structure device extension is not enabled"))?;
+        let scratch_alignment = self.gpu
+            .get_device_attribute(
+                ext::acceleration_structure::Attributes::MIN_ACCELERATION_STRUCTURE_SCRATCH_OFFSET_ALIGNMENT
+            ).u32().unwrap_or(1) as DeviceAddress;
+        self.wait_scope |= vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR;
+        self.signal_scope |= vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR;
+        let geometry_count: u32 = infos
+            .iter()
+            .map(|info| info.geometries.len() as u32)
+            .sum();
+        let info_count = infos.len() as u32;
+        let tmp_alloc = self.gpu.tmp_alloc();
+        let tmp_alloc = tmp_alloc.guard();
+        let cache = unsafe { &mut *self.recorder.cache().get() };
+        self.recorder.write_resources(|guard| {
+            let mut vk_geometries = FixedVec32
+                ::with_capacity(geometry_count, &tmp_alloc)
+                .context("alloc failed")?;
+            let mut primitive_counts = FixedVec32
+                ::with_capacity(geometry_count, &tmp_alloc)
+                .context("alloc failed")?;
+            let mut range_infos = FixedVec32
+                ::with_capacity(geometry_count, &tmp_alloc)
+                .context("alloc failed")?;
+            let mut vk_infos = FixedVec32
+                ::with_capacity(info_count, &tmp_alloc)
+                .context("alloc failed")?;
+            let mut p_range_infos = FixedVec32
+                ::with_capacity(info_count, &tmp_alloc)
+                .context("alloc failed")?;
+            let mut memory_barrier_ranges = FixedVec32
+                ::with_capacity(geometry_count * 3 + info_count * 3, &tmp_alloc)
+                .context("alloc failed")?;
+            let queue_family_index = self.queue.family_index();
+            let build_stage = vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR;
+            let scratch: *mut BufferMeta = guard.register_buffer(scratch_buffer)?;
+            let scratch = unsafe { &mut *scratch };
+            if let Some(err) = scratch.validate_usage(BufferUsages::STORAGE_BUFFER) {
+                return Err(Error::new(err, "scratch buffer has incompatible usage"))
+            }
+            let scratch_size = scratch.properties().size;
+            let scratch_address = scratch.device_address()
+                .context("scratch buffer has incompatible usage")?;
+            let mut scratch_end = scratch_address + scratch_offset;
+            for (i, info) in infos.iter().enumerate() {
+                let dst = guard.register_buffer(info.dst.buffer_id())?;
+                let &AccelerationStructure {
+                    handle: dst_handle, range: dst_range, ..
+                } = dst.get_acceleration_structure(info.dst)?;
+                validate_geometries(dst_range.ty, info.geometries, &self.gpu)
+                    .context_with(|| format!("invalid build info at index {i}"))?;
+                let dst_access =
+                    if info.src == Some(info.dst) {
+                        vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR |
+                        vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR
+                    } else {
+                        vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR
+                    };
+                memory_barrier_ranges.push(unsafe { dst.memory_barrier_unchecked(
+                    dst_range.offset, dst_range.size,
+                    BufferState::new(build_stage, dst_access, queue_family_index),
+                    CommandOrdering::Strict,
+                    &mut cache.shader_resource_cache.buffer_memory_barrier_cache,
+                ) });
+                let src_handle =
+                    if let Some(src_id) = info.src {
+                        if !info.flags.contains(BuildAccelerationStructureFlags::ALLOW_UPDATE) {
+                            return Err(Error::just_context(format!(
+                                "update at index {i} doesn't have {} flag",
+                                BuildAccelerationStructureFlags::ALLOW_UPDATE,
+                            )))
+                        }
+                        let src = guard.register_buffer(src_id.buffer_id())?;
+                        let &AccelerationStructure {
+                            handle: src_handle, range: src_range, ..
+                        } = src.get_acceleration_structure(src_id)?;
+                        if src_range.ty != dst_range.ty {
+                            return Err(Error::just_context(format!(
+                                "update source type {} is different from destination type {} at index {i}",
+                                src_range.ty, dst_range.ty,
+                            )))
+                        }
+                        if src_id != info.dst {
+                            memory_barrier_ranges.push(unsafe { src.memory_barrier_unchecked(
+                                src_range.offset, src_range.size,
+                                BufferState::new(
+                                    build_stage,
+                                    vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR,
+                                    queue_family_index,
+                                ),
+                                CommandOrdering::Strict,
+                                &mut cache.shader_resource_cache.buffer_memory_barrier_cache,
+                            ) });
+                        }
+                        src_handle
+                    } else {
+                        vk::AccelerationStructureKHR::null()
+                    };
+                let first_geometry = vk_geometries.len() as usize;
+                for geometry in info.geometries {
+                    geometry.input_ranges(|buffer_id, offset, size| {
+                        let buffer = guard.register_buffer(buffer_id)?;
+                        if let Some(err) = buffer.validate_usage(
+                            BufferUsages::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY
+                        ) {
+                            return Err(Error::new(err, "build input buffer has incompatible usage"))
+                        }
+                        let buffer_size = buffer.properties().size;
+                        if offset + size > buffer_size {
+                            return Err(Error::just_context(format!(
+                                "build input offset {offset} + size {size} is out of range of buffer {buffer_id} size {buffer_size}",
+                            )))
+                        }
+                        memory_barrier_ranges.push(unsafe { buffer.memory_barrier_unchecked(
+                            offset, size,
+                            BufferState::new(
+                                build_stage,
+                                vk::AccessFlags2::SHADER_READ,
+                                queue_family_index,
+                            ),
+                            CommandOrdering::Strict,
+                            &mut cache.shader_resource_cache.buffer_memory_barrier_cache,
+                        ) });
+                        Ok(())
+                    }).context_with(|| format!("invalid build info at index {i}"))?;
+                    vk_geometries.push(geometry.vk_geometry(|buffer_id| {
+                        guard.register_buffer(buffer_id)?.device_address()
+                    }).context_with(|| format!("invalid build info at index {i}"))?);
+                    primitive_counts.push(geometry.primitive_count);
+                    range_infos.push(vk::AccelerationStructureBuildRangeInfoKHR {
+                        primitive_count: geometry.primitive_count,
+                        ..Default::default()
+                    });
+                }
+                let mode =
+                    if info.src.is_some() {
+                        vk::BuildAccelerationStructureModeKHR::UPDATE
+                    } else {
+                        vk::BuildAccelerationStructureModeKHR::BUILD
+                    };
+                // Safe because FixedVec doesn't reallocate
+                let mut vk_info = vk::AccelerationStructureBuildGeometryInfoKHR {
+                    s_type: vk::StructureType::ACCELERATION_STRUCTURE_BUILD_GEOMETRY_INFO_KHR,
+                    ty: dst_range.ty.into(),
+                    flags: info.flags.into(),
+                    mode,
+                    src_acceleration_structure: src_handle,
+                    dst_acceleration_structure: dst_handle,
+                    geometry_count: info.geometries.len() as u32,
+                    p_geometries: vk_geometries[first_geometry..].as_ptr(),
+                    ..Default::default()
+                };
+                let mut sizes = vk::AccelerationStructureBuildSizesInfoKHR::default();
+                unsafe {
+                    device.get_acceleration_structure_build_sizes(
+                        vk::AccelerationStructureBuildTypeKHR::DEVICE,
+                        &vk_info,
+                        &primitive_counts[first_geometry..],
+                        &mut sizes,
+                    );
+                }
+                if sizes.acceleration_structure_size > dst_range.size {
+                    return Err(Error::just_context(format!(
+                        "acceleration structure size {} is greater than destination size {} at index {i}",
+                        sizes.acceleration_structure_size, dst_range.size,
+                    )))
+                }
+                let needed_scratch_size =
+                    if info.src.is_some() {
+                        sizes.update_scratch_size
+                    } else {
+                        sizes.build_scratch_size
+                    };
+                let scratch_start = scratch_end.next_multiple_of(scratch_alignment);
+                let offset = scratch_start - scratch_address;
+                if offset + needed_scratch_size > scratch_size {
+                    return Err(Error::just_context(format!(
+                        "scratch offset {offset} + size {needed_scratch_size} is out of range of scratch buffer size {scratch_size} at index {i}",
+                    )))
+                }
+                if needed_scratch_size != 0 {
+                    memory_barrier_ranges.push(unsafe { scratch.memory_barrier_unchecked(
+                        offset, needed_scratch_size,
+                        BufferState::new(
+                            build_stage,
+                            vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR |
+                            vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
+                            queue_family_index,
+                        ),
+                        CommandOrdering::Strict,
+                        &mut cache.shader_resource_cache.buffer_memory_barrier_cache,
+                    ) });
+                }
+                vk_info.scratch_data = vk::DeviceOrHostAddressKHR {
+                    device_address: scratch_start,
+                };
+                scratch_end = scratch_start + needed_scratch_size;
+                vk_infos.push(vk_info);
+                p_range_infos.push(range_infos[first_geometry..].as_ptr());
+            }
+            let buffer_memory_barriers = cache.shader_resource_cache.buffer_memory_barrier_cache.flush(
+                &memory_barrier_ranges,
+                &tmp_alloc
+            )?;
+            let command_buffer = self.primary_command_buffer;
+            if !buffer_memory_barriers.is_empty() {
+                let dependency_info = vk::DependencyInfo {
+                    buffer_memory_barrier_count: buffer_memory_barriers.len(),
+                    p_buffer_memory_barriers: buffer_memory_barriers.as_ptr(),
+                    ..Default::default()
+                };
+                unsafe {
+                    self.gpu.device()
+                    .cmd_pipeline_barrier2(command_buffer, &dependency_info);
+                }
+            }
+            unsafe {
+                device.cmd_build_acceleration_structures(
+                    command_buffer, &vk_infos, &p_range_infos,
+                );
+                cmd_acceleration_structure_write_barrier(self.gpu.device(), command_buffer);
+            }
+            Ok(())
+        })
+    }
+
+    /// Copies the acceleration structure `src` to `dst`.
+    ///
+    /// Both acceleration structures are automatically synchronized with previous and later
+    /// commands.
+    ///
+    /// # Valid usage
+    /// - [`ext::acceleration_structure`] *must* be enabled.
+    /// - `src` and `dst` *must* be valid [`AccelerationStructureId`]s of the same type.
+    /// - `src` *must* have been built.
+    /// - The valid usage of `mode` described in [`CopyAccelerationStructureMode`] *must* be
+    ///   followed.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdCopyAccelerationStructureKHR.html>
+    pub fn copy_acceleration_structure(
+        &mut self,
+        src: AccelerationStructureId,
+        dst: AccelerationStructureId,
+        mode: CopyAccelerationStructureMode,
+    ) -> Result<()>
+    {
+        if src == dst {
+            return Err(Error::just_context(format!(
+                "attempting to copy acceleration structure {src} to itself"
+            )))
+        }
+        let device = self.gpu.get_extension_device::<ext::acceleration_structure::Device>()
+            .ok_or_else(|| Error::just_context("acceleration_structure device extension is not enabled"))?;
+        self.wait_scope |= vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR;
+        self.signal_scope |= vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR;
+        let tmp_alloc = self.gpu.tmp_alloc();
+        let tmp_alloc = tmp_alloc.guard();
+        let cache = unsafe { &mut *self.recorder.cache().get() };
+        self.recorder.write_resources(|guard| {
+            let queue_family_index = self.queue.family_index();
+            let build_stage = vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR;
+            let src_buffer = guard.register_buffer(src.buffer_id())?;
+            let &AccelerationStructure {
+                handle: src_handle, range: src_range, ..
+            } = src_buffer.get_acceleration_structure(src)?;
+            let range1 = unsafe { src_buffer.memory_barrier_unchecked(
+                src_range.offset, src_range.size,
+                BufferState::new(
+                    build_stage,
+                    vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR,
+                    queue_family_index,
+                ),
+                CommandOrdering::Strict,
+                &mut cache.shader_resource_cache.buffer_memory_barrier_cache,
+            ) };
+            let dst_buffer = guard.register_buffer(dst.buffer_id())?;
+            let &AccelerationStructure {
+                handle: dst_handle, range: dst_range, ..
+            } = dst_buffer.get_acceleration_structure(dst)?;
+            if src_range.ty != dst_range.ty {
+                return Err(Error::just_context(format!(
+                    "source type {} is different from destination type {}",
+                    src_range.ty, dst_range.ty,
+                )))
+            }
+            if mode == CopyAccelerationStructureMode::Clone && dst_range.size < src_range.size {
+                return Err(Error::just_context(format!(
+                    "destination size {} is less than source size {}",
+                    dst_range.size, src_range.size,
+                )))
+            }
+            let range2 = unsafe { dst_buffer.memory_barrier_unchecked(
+                dst_range.offset, dst_range.size,
+                BufferState::new(
+                    build_stage,
+                    vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
+                    queue_family_index,
+                ),
+                CommandOrdering::Strict,
+                &mut cache.shader_resource_cache.buffer_memory_barrier_cache,
+            ) };
+            let buffer_memory_barriers = cache.shader_resource_cache.buffer_memory_barrier_cache.flush(
+                &[range1, range2],
+                &tmp_alloc
+            )?;
+            let command_buffer = self.primary_command_buffer;
+            if !buffer_memory_barriers.is_empty() {
+                let dependency_info = vk::DependencyInfo {
+                    buffer_memory_barrier_count: buffer_memory_barriers.len(),
+                    p_buffer_memory_barriers: buffer_memory_barriers.as_ptr(),
+                    ..Default::default()
+                };
+                unsafe {
+                    self.gpu.device()
+                    .cmd_pipeline_barrier2(command_buffer, &dependency_info);
+                }
+            }
+            let copy_info = vk::CopyAccelerationStructureInfoKHR {
+                s_type: vk::StructureType::COPY_ACCELERATION_STRUCTURE_INFO_KHR,
+                src: src_handle,
+                dst: dst_handle,
+                mode: mode.into(),
+                ..Default::default()
+            };
+            unsafe {
+                device.cmd_copy_acceleration_structure(command_buffer, &copy_info);
+                cmd_acceleration_structure_write_barrier(self.gpu.device(), command_buffer);
+            }
+            Ok(())
+        })
+    }
+
+    /// Writes the compacted size of the acceleration structure `id` to `query` of an
+    /// [`acceleration structure compacted size`][1] query pool.
+    ///
+    /// The result can be retrieved with [`Gpu::get_query_results`] after the frame has finished
+    /// and used as the size of the destination of a [`compacting copy`][2].
+    ///
+    /// # Valid usage
+    /// - `pool_id` *must* be a valid [`QueryPoolId`] of an
+    ///   [`acceleration structure compacted size`][1] query pool.
+    /// - `query` *must* be less than the query count of the pool.
+    /// - The acceleration structure *must* have been built with
+    ///   [`BuildAccelerationStructureFlags::ALLOW_COMPACTION`].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdWriteAccelerationStructuresPropertiesKHR.html>
+    ///
+    /// [1]: QueryType::AccelerationStructureCompactedSize
+    /// [2]: CopyAccelerationStructureMode::Compact
+    pub fn write_acceleration_structure_compacted_size(
+        &mut self,
+        pool_id: QueryPoolId,
+        query: u32,
+        id: AccelerationStructureId,
+    ) -> Result<()>
+    {
+        let device = self.gpu.get_extension_device::<ext::acceleration_structure::Device>()
+            .ok_or_else(|| Error::just_context("acceleration_structure device extension is not enabled"))?;
+        let handle = self.gpu
+            .read_buffers()
+            .get(id.buffer_id())?
+            .get_acceleration_structure(id)?
+            .handle;
+        let frame = self.recorder.current_frame();
+        unsafe {
+            self.queries.write_acceleration_structure_compacted_size(
+                &self.gpu, self.primary_command_buffer,
+                &device, handle,
+                self.command_id, frame,
+                pool_id, query,
+            )
+        }
+    }
+
+    /// Binds a [`RayTracingPipeline`] and records pipeline commands with `f`.
+    ///
+    /// Rays are traced with [`trace_rays`][1] using the shader binding table of the pipeline.
+    ///
+    /// # Valid usage
+    /// - [`ext::ray_tracing_pipeline`] *must* be enabled.
+    /// - `id` *must* be a valid [`RayTracingPipelineId`].
+    ///
+    /// [1]: Self::trace_rays
+    pub fn bind_ray_tracing_pipeline<F>(
+        &mut self,
+        id: RayTracingPipelineId,
+        f: F,
+    ) -> Result<()>
+        where F: FnOnce(&mut PipelineCommands) -> EventResult<()>
+    {
+        if self.ray_tracing_device.is_none() {
+            self.ray_tracing_device = Some(self.gpu
+                .get_extension_device()
+                .ok_or_else(|| Error::just_context("ray_tracing_pipeline device extension is not enabled"))?
+            );
+        }
+        let alloc = self.recorder.stack().clone();
+        let alloc = alloc.guard();
+        let pipeline = self.current_ray_tracing_pipeline.insert(
+            block_on(self.gpu.get_ray_tracing_pipeline(id))?.clone()
+        );
+        let cmd_cache = unsafe {
+            &mut *self.recorder.cache().get()
+        };
+        cmd_cache.compute_command_cache.pipelines.push(pipeline.handle().clone());
+        let command_buffer = self.primary_command_buffer;
+        unsafe {
+            self.gpu.device().cmd_bind_pipeline(
+                command_buffer, vk::PipelineBindPoint::RAY_TRACING_KHR,
+                pipeline.handle().handle(),
+            );
+        }
+        cmd_cache.compute_command_cache.reset();
+        self.recorder.get_current_worker().add_pipeline(pipeline.handle().clone());
+        let buffers = self.gpu.read_buffers();
+        let images = self.gpu.read_images();
+        let mut commands = unsafe { PipelineCommands::new(
+            self.gpu.clone(),
+            command_buffer,
+            pipeline.handle().clone(),
+            &mut cmd_cache.pipeline_cache,
+            &alloc,
+            &buffers,
+            &images,
+        ) };
+        f(&mut commands).context_from_tracked(|orig| format!(
+            "failed to record pipeline commands at {}", orig.or_this(),
+        ))?;
+        unsafe {
+            let tmp_alloc = self.gpu.tmp_alloc();
+            let tmp_alloc = tmp_alloc.guard();
+            cmd_cache.pipeline_cache.prepare_shader_resource_cache(
+                &mut self.recorder,
+                &tmp_alloc,
+            ).context("failed to process pipeline commands")?;
+            cmd_cache.shader_resource_cache.process(
+                &mut self.recorder, command_buffer,
+                self.queue.family_index(),
+                self.command_id,
+                &tmp_alloc,
+            ).context("failed to process pipeline commands")?;
+            cmd_cache.pipeline_cache.reset(&alloc);
+        }
+        Ok(())
+    }
+
+    /// Traces rays with the bound [`RayTracingPipeline`], launching `width` * `height` * `depth`
+    /// ray generation shader invocations.
+    ///
+    /// # Valid usage
+    /// - A ray tracing pipeline *must* be bound with [`bind_ray_tracing_pipeline`][1].
+    /// - `width` * `height` * `depth` *must* be less than or equal to
+    ///   [`ext::ray_tracing_pipeline::Attributes::MAX_RAY_DISPATCH_INVOCATION_COUNT`].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdTraceRaysKHR.html>
+    ///
+    /// [1]: Self::bind_ray_tracing_pipeline
+    pub fn trace_rays(
+        &mut self,
+        width: u32,
+        height: u32,
+        depth: u32,
+    ) -> Result<()>
+    {
+        let (Some(pipeline), Some(device)) = (
+            &self.current_ray_tracing_pipeline,
+            &self.ray_tracing_device,
+        ) else {
+            return Err(Error::just_context("attempting to trace rays with no ray tracing pipeline bound"))
+        };
+        let max_invocations = self.gpu
+            .get_device_attribute(ext::ray_tracing_pipeline::Attributes::MAX_RAY_DISPATCH_INVOCATION_COUNT)
+            .u32().unwrap_or_default() as u64;
+        let invocations = width as u64 * height as u64 * depth as u64;
+        if invocations > max_invocations {
+            return Err(Error::just_context(format!(
+                "ray dispatch invocation count {invocations} is greater than the max ray dispatch invocation count {max_invocations}"
+            )))
+        }
+        let [raygen, miss, hit, callable] = pipeline.shader_binding_table();
+        unsafe {
+            device.cmd_trace_rays(
+                self.primary_command_buffer,
+                raygen, miss, hit, callable,
+                width, height, depth,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Makes acceleration structure writes visible to all later commands.
+///
+/// Bottom level acceleration structures are referenced by device address in instances, so
+/// their reads can't be tracked per buffer like other accesses.
+///
+/// # Safety
+/// `command_buffer` *must* be a valid command buffer in the recording state.
+unsafe fn cmd_acceleration_structure_write_barrier(
+    device: &LogicalDevice,
+    command_buffer: vk::CommandBuffer,
+) {
+    let memory_barrier = vk::MemoryBarrier2 {
+        s_type: vk::StructureType::MEMORY_BARRIER_2,
+        src_stage_mask: vk::PipelineStageFlags2::ACCELERATION_STRUCTURE_BUILD_KHR,
+        src_access_mask: vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
+        dst_stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+        dst_access_mask:
+            vk::AccessFlags2::ACCELERATION_STRUCTURE_READ_KHR |
+            vk::AccessFlags2::ACCELERATION_STRUCTURE_WRITE_KHR,
+        ..Default::default()
+    };
+    let dependency_info = vk::DependencyInfo {
+        memory_barrier_count: 1,
+        p_memory_barriers: &memory_barrier,
+        ..Default::default()
+    };
+    unsafe {
+        device.cmd_pipeline_barrier2(command_buffer, &dependency_info);
+    }
 }
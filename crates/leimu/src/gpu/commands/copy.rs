@@ -1,6 +1,6 @@
 use nox_ash::vk;
 use nox_mem::{
-    vec::{FixedVec32},
+    vec::{FixedVec32, Vec32},
     option::OptionExt,
     arena,
     slice,
@@ -11,6 +11,8 @@ use crate::{
     error::*,
 };
 
+use super::blit_fallback;
+
 /// A wrapper around a [`command buffer`][1], which allows transfer commands to be performed.
 ///
 /// Available through [`CommandScheduler::new_copy_commands`] and
@@ -32,6 +34,8 @@ pub struct CopyCommands<'a, 'b> {
     queue: DeviceQueue,
     command_buffer: vk::CommandBuffer,
     command_id: CommandId,
+    queries: CommandQueries,
+    labels: CommandLabels,
     wait_scope: vk::PipelineStageFlags2,
     signal_scope: vk::PipelineStageFlags2,
 }
@@ -80,6 +84,8 @@ unsafe impl<'a, 'b> Commands<'a, 'b> for CopyCommands<'a, 'b> {
     fn finish<'c, Alloc>(self, alloc: &'c Alloc) -> Result<CommandResult<'c, Alloc>>
         where Alloc: ?Sized + nox_mem::alloc::LocalAlloc<Error = arena::Error>
     {
+        self.queries.finish()?;
+        self.labels.finish()?;
         unsafe {
             self.gpu.device()
                 .end_command_buffer(self.command_buffer)
@@ -93,7 +99,8 @@ unsafe impl<'a, 'b> Commands<'a, 'b> for CopyCommands<'a, 'b> {
             primary_command_buffers,
             wait_scope: self.wait_scope,
             signal_scope: self.signal_scope,
-            queue: self.queue
+            queue: self.queue,
+            sparse_binds: None,
         })
     }
 }
@@ -134,11 +141,138 @@ impl<'a, 'b> CopyCommands<'a, 'b> {
             queue,
             command_buffer,
             command_id,
+            queries: Default::default(),
+            labels: Default::default(),
             wait_scope: vk::PipelineStageFlags2::NONE,
             signal_scope: vk::PipelineStageFlags2::NONE,
         })
     }
 
+    /// Writes a timestamp to `query` of a [`timestamp`][1] query pool once all previous commands
+    /// have reached `stage`.
+    ///
+    /// The result can be retrieved with [`Gpu::get_query_results`] after the frame has finished.
+    ///
+    /// # Valid usage
+    /// - `pool_id` *must* be a valid [`QueryPoolId`] of a [`timestamp`][1] query pool.
+    /// - `query` *must* be less than the query count of the pool and it *must* not be active.
+    /// - The queue family of the command *must* support timestamps.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdWriteTimestamp2.html>
+    ///
+    /// [1]: QueryType::Timestamp
+    pub fn write_timestamp(
+        &mut self,
+        pool_id: QueryPoolId,
+        query: u32,
+        stage: TimestampStage,
+    ) -> Result<()>
+    {
+        let frame = self.recorder.current_frame();
+        unsafe {
+            self.queries.write_timestamp(
+                &self.gpu, self.command_buffer, &self.queue,
+                self.command_id, frame,
+                pool_id, query, stage,
+            )
+        }
+    }
+
+    /// Begins `query` of an [`occlusion`][1] or a [`pipeline statistics`][2] query pool.
+    ///
+    /// Every begun query *must* be ended with [`end_query`][3] before the command finishes.
+    ///
+    /// # Valid usage
+    /// - `pool_id` *must* be a valid [`QueryPoolId`] of a non-timestamp query pool.
+    /// - `query` *must* be less than the query count of the pool.
+    /// - No other query of the same pool *can* be active.
+    /// - If `precise` is true, the pool *must* be an occlusion query pool and
+    ///   [`BaseDeviceFeatures::occlusion_query_precise`] *must* be enabled.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBeginQuery.html>
+    ///
+    /// [1]: QueryType::Occlusion
+    /// [2]: QueryType::PipelineStatistics
+    /// [3]: Self::end_query
+    pub fn begin_query(
+        &mut self,
+        pool_id: QueryPoolId,
+        query: u32,
+        precise: bool,
+    ) -> Result<()>
+    {
+        let frame = self.recorder.current_frame();
+        unsafe {
+            self.queries.begin_query(
+                &self.gpu, self.command_buffer, &self.queue,
+                self.command_id, frame,
+                pool_id, query, precise,
+            )
+        }
+    }
+
+    /// Ends `query` begun with [`begin_query`][1].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdEndQuery.html>
+    ///
+    /// [1]: Self::begin_query
+    pub fn end_query(
+        &mut self,
+        pool_id: QueryPoolId,
+        query: u32,
+    ) -> Result<()>
+    {
+        unsafe {
+            self.queries.end_query(
+                &self.gpu, self.command_buffer,
+                self.command_id,
+                pool_id, query,
+            )
+        }
+    }
+
+    /// Begins a debug label region named `name`, which shows up in debugging tools.
+    ///
+    /// Every label *must* be ended with [`end_label`][1] before the commands finish. Labels can
+    /// be nested.
+    ///
+    /// Does nothing if [`VK_EXT_debug_utils`][2] is not enabled.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBeginDebugUtilsLabelEXT.html>
+    ///
+    /// [1]: Self::end_label
+    /// [2]: Instance::with_debug_utils
+    pub fn begin_label(
+        &mut self,
+        name: &str,
+        color: [f32; 4],
+    ) {
+        unsafe {
+            self.labels.begin(
+                self.gpu.device(), self.command_buffer,
+                name, color,
+            );
+        }
+    }
+
+    /// Ends the innermost label begun with [`begin_label`][1].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdEndDebugUtilsLabelEXT.html>
+    ///
+    /// [1]: Self::begin_label
+    pub fn end_label(&mut self) -> Result<()> {
+        unsafe {
+            self.labels.end(
+                self.gpu.device(), self.command_buffer,
+            )
+        }
+    }
+
     /// Updates a buffer's contents from host memory.
     ///
     /// # Valid usage
@@ -392,11 +526,12 @@ impl<'a, 'b> CopyCommands<'a, 'b> {
             )?;
             let dst_buffer = unsafe { &mut *dst_buffer };
             let dst_properties = dst_buffer.properties();
-            let src_mem = src_buffer.memory();
-            let dst_mem = dst_buffer.memory();
-            let overlap = src_mem.overlaps(dst_mem).then(||
-                (src_mem.offset(), dst_mem.offset())
-            );
+            let overlap = match (src_buffer.memory(), dst_buffer.memory()) {
+                (Some(src_mem), Some(dst_mem)) => src_mem.overlaps(dst_mem).then(||
+                    (src_mem.offset(), dst_mem.offset())
+                ),
+                _ => (src_buffer_id == dst_buffer_id).then_some((0, 0)),
+            };
             if !src_properties.usage.contains(BufferUsages::TRANSFER_SRC) {
                 return Err(Error::just_context(
                     "source buffer usage doesn't contain transfer source usage"
@@ -503,6 +638,95 @@ impl<'a, 'b> CopyCommands<'a, 'b> {
         }) 
     }
 
+    /// Moves buffers allocated by `binder` out of its least used blocks, so that the blocks can
+    /// be freed.
+    ///
+    /// Each moved buffer is copied with [`copy_buffer`][1] to a new allocation and its
+    /// [`BufferId`] is then bound to the new buffer, so ids held by the caller stay valid. Texel
+    /// buffer views of moved buffers are recreated and their [`BufferViewId`]s stay valid as
+    /// well.
+    ///
+    /// Buffers, which weren't allocated by `binder`, lack the [`BufferUsages::TRANSFER_SRC`] bit,
+    /// have the [`BufferUsages::ACCELERATION_STRUCTURE_STORAGE`] or
+    /// [`BufferUsages::SHADER_DEVICE_ADDRESS`] bit or can't be placed in another block are
+    /// skipped. Acceleration structures can't be moved by copying their storage, so they would
+    /// have to be rebuilt. Device addresses of buffers may be stored in descriptor buffers,
+    /// acceleration structure instances, shader binding tables or any user data, none of which
+    /// can be updated when the buffer moves.
+    ///
+    /// Descriptor sets referring to moved buffers or their views are invalidated and *must* be
+    /// updated before they are used again.
+    ///
+    /// Returns the ids of the buffers now holding the old allocations. These *must* be
+    /// [`destroyed`][2] once these commands have finished executing.
+    ///
+    /// # Valid usage
+    /// - Each id in `buffer_ids` *must* be a valid [`BufferId`].
+    ///
+    /// [1]: Self::copy_buffer
+    /// [2]: Gpu::destroy_resources
+    pub fn defragment_buffers(
+        &mut self,
+        binder: &PoolBinder,
+        buffer_ids: &[BufferId],
+    ) -> Result<Vec32<BufferId>>
+    {
+        let mut retired = Vec32::new();
+        for &id in buffer_ids {
+            let (properties, chunk) = {
+                let buffers = self.gpu.read_buffers::<BufferId>();
+                let buffer = buffers.get(id)?;
+                let properties = buffer.properties();
+                (properties,
+                    buffer.memory()
+                        .filter(|_|
+                            properties.usage.contains(BufferUsages::TRANSFER_SRC) &&
+                            !properties.usage.intersects(
+                                BufferUsages::ACCELERATION_STRUCTURE_STORAGE |
+                                BufferUsages::SHADER_DEVICE_ADDRESS
+                            )
+                        )
+                        .and_then(|memory| binder.movable_chunk(memory))
+                )
+            };
+            let Some(chunk) = chunk else {
+                continue
+            };
+            let excluding = binder.excluding(chunk);
+            let mut new_id = BufferId::default();
+            let mut create_info = BufferCreateInfo::new(
+                &mut new_id, &excluding,
+                properties.size,
+                properties.usage | BufferUsages::TRANSFER_DST,
+            ).unwrap();
+            create_info.create_flags = properties.create_flags;
+            if self.gpu.create_resources([create_info], []).is_err() {
+                continue
+            }
+            self.copy_buffer(
+                id, new_id,
+                &[BufferCopy {
+                    src_offset: 0,
+                    dst_offset: 0,
+                    size: properties.size,
+                }],
+                CommandOrdering::Lenient,
+            ).context_with(|| format!(
+                "failed to copy buffer {id} for defragmentation"
+            ))?;
+            self.recorder.write_resources(|guard| {
+                let buffer: *mut BufferMeta = guard.register_buffer(id)?;
+                let new_buffer = guard.register_buffer(new_id)?;
+                unsafe {
+                    (*buffer).swap_storage(new_buffer)
+                }
+            })?;
+            self.gpu.buffer_moved(id);
+            retired.push(new_id);
+        }
+        Ok(retired)
+    }
+
     /// Copies regions of an image to regions of another image.
     ///
     /// # Valid usage
@@ -1634,14 +1858,28 @@ impl<'a, 'b> CopyCommands<'a, 'b> {
     ///
     /// For mip map generation, consider using [`gen_mip_map`][1] for efficiency.
     ///
+    /// If the format features of the source image don't contain [`FormatFeatures::BLIT_SRC`] or
+    /// the format features of the destination image don't contain [`FormatFeatures::BLIT_DST`],
+    /// the blit is performed with a built-in fragment shader instead. Format features can be
+    /// queried in advance with [`Gpu::get_image_format_properties`].
+    ///
     /// # Valid usage
     /// - `src_image_id` and `dst_image_id` *must* be valid [`ImageId`]s.
-    /// - Source image *must* have been created with [`ImageUsages::TRANSFER_SRC`] set.
-    /// - Destination image *must* have been created with [`ImageUsages::TRANSFER_DST`] set.
     /// - Both images *must* have msaa sample count of 1.
-    /// - Format features of the source image *must* contain [`FormatFeatures::BLIT_SRC`] bit.
-    /// - Format features of the destination image *must* contain [`FormatFeatures::BLIT_DST`]
-    ///   bit.
+    /// - If both images support blitting:
+    ///     - Source image *must* have been created with [`ImageUsages::TRANSFER_SRC`] set.
+    ///     - Destination image *must* have been created with [`ImageUsages::TRANSFER_DST`] set.
+    /// - If either image doesn't support blitting:
+    ///     - The push descriptor extension *must* be enabled.
+    ///     - Source image *must* have been created with [`ImageUsages::SAMPLED`] set and its
+    ///       format features *must* contain [`FormatFeatures::SAMPLED_IMAGE`].
+    ///     - Destination image *must* have been created with [`ImageUsages::COLOR_ATTACHMENT`] set
+    ///       and its format features *must* contain [`FormatFeatures::COLOR_ATTACHMENT`].
+    ///     - Both images *must* be 2D color images and both formats *must* be either floating
+    ///       point, unsigned integer or signed integer formats.
+    ///     - If `filter` is [`Filter::Linear`], both formats *must* be floating point formats.
+    ///     - Source and destination subresources *must* not overlap and *must* have the same
+    ///       layer count.
     /// - If the source image format is a depth/stencil format, `filter` *must* be
     ///   [`Filter::Nearest`].
     /// - If `filter` is [`Filter::Linear`], the format features of the source image *must*
@@ -1703,29 +1941,39 @@ impl<'a, 'b> CopyCommands<'a, 'b> {
             let dst_properties = dst_image.properties();
             let src_handle = src_image.handle();
             let dst_handle = dst_image.handle();
-            if !src_properties.usage.contains(ImageUsages::TRANSFER_SRC) {
-                return Err(Error::just_context(
-                    "source image usage must contain transfer source usage"
-                ))
-            }
-            if !dst_properties.usage.contains(ImageUsages::TRANSFER_DST) {
-                return Err(Error::just_context(
-                    "destination image usage must contain transfer destination usage"
-                ))
-            }
             if src_properties.samples != MsaaSamples::X1 ||
                 dst_properties.samples != MsaaSamples::X1
             {
                 return Err(Error::just_context("both source and destination images don't both have msaa sample count of 1"))
             }
-            if !src_properties.format_features.contains(FormatFeatures::BLIT_SRC) {
+            if !src_properties.format_features.contains(FormatFeatures::BLIT_SRC) ||
+                !dst_properties.format_features.contains(FormatFeatures::BLIT_DST)
+            {
+                self.wait_scope |= vk::PipelineStageFlags2::FRAGMENT_SHADER |
+                    vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT;
+                self.signal_scope |= vk::PipelineStageFlags2::FRAGMENT_SHADER |
+                    vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT;
+                return unsafe {
+                    blit_fallback::record_blits(
+                        &self.gpu,
+                        command_buffer,
+                        self.queue.family_index(),
+                        &mut cache.shader_resource_cache.image_memory_barrier_cache,
+                        src_image,
+                        dst_image,
+                        regions,
+                        filter,
+                    )
+                }.context("failed to blit with the fallback path")
+            }
+            if !src_properties.usage.contains(ImageUsages::TRANSFER_SRC) {
                 return Err(Error::just_context(
-                    "source image format features must contain blit source feature"
+                    "source image usage must contain transfer source usage"
                 ))
             }
-            if !dst_properties.format_features.contains(FormatFeatures::BLIT_DST) {
+            if !dst_properties.usage.contains(ImageUsages::TRANSFER_DST) {
                 return Err(Error::just_context(
-                    "source image format features must contain blit destination feature"
+                    "destination image usage must contain transfer destination usage"
                 ))
             }
             if filter == Filter::Linear {
@@ -1842,14 +2090,187 @@ impl<'a, 'b> CopyCommands<'a, 'b> {
         })
     }
 
+    /// Resolves regions of a multisample image to a single sample image.
+    ///
+    /// # Valid usage
+    /// - `src_image_id` and `dst_image_id` *must* be valid [`ImageId`]s.
+    /// - Source image *must* have been created with [`ImageUsages::TRANSFER_SRC`] set.
+    /// - Destination image *must* have been created with [`ImageUsages::TRANSFER_DST`] set.
+    /// - Source image *must* have [`msaa sample count`][1] greater than 1.
+    /// - Destination image *must* have [`msaa sample count`][1] of 1.
+    /// - Both images *must* have the same format.
+    /// - Format features of the destination image *must* contain
+    ///   [`FormatFeatures::COLOR_ATTACHMENT`].
+    /// - Each [`source`][2] and [`destination`][3] aspect mask in `regions` *must* be
+    ///   [`ImageAspects::COLOR`].
+    /// - Each [`extent`][4] in `regions` *must* not be zero.
+    /// - For each [`source offset`][5] + [`extent`][4] in `regions` *must* not be greater than
+    ///   the source image's [`Dimensions`] at that [`mip level`][6].
+    /// - For each [`destination offset`][7] + [`extent`][4] in `regions` *must* not be greater
+    ///   than the destination image's [`Dimensions`] at that [`mip level`][6].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdResolveImage2.html>
+    ///
+    /// [1]: MsaaSamples
+    /// [2]: ImageResolve::src_subresource
+    /// [3]: ImageResolve::dst_subresource
+    /// [4]: ImageResolve::extent
+    /// [5]: ImageResolve::src_offset
+    /// [6]: ImageSubresourceLayers::mip_level
+    /// [7]: ImageResolve::dst_offset
+    pub fn resolve_image(
+        &mut self,
+        src_image_id: ImageId,
+        dst_image_id: ImageId,
+        regions: &[ImageResolve],
+    ) -> Result<()> {
+        self.wait_scope |= vk::PipelineStageFlags2::RESOLVE;
+        self.signal_scope |= vk::PipelineStageFlags2::RESOLVE;
+        let tmp_alloc = self.gpu.tmp_alloc();
+        let tmp_alloc = tmp_alloc.guard();
+        let cache = unsafe { &mut *self.recorder.cache().get() };
+        let command_id = self.command_id;
+        self.recorder.write_resources(|guard| {
+            let src_image: *mut ImageMeta = guard.register_image(
+                src_image_id.slot_index(), command_id.index()
+            )?;
+            let src_image = unsafe { &mut *src_image };
+            let dst_image: *mut ImageMeta = guard.register_image(
+                dst_image_id.slot_index(), command_id.index()
+            )?;
+            let dst_image = unsafe { &mut *dst_image };
+            if let Some(err) = src_image.validate_usage(ImageUsages::TRANSFER_SRC) {
+                return Err(Error::new(err, "source image usage mismatch"))
+            }
+            if let Some(err) = dst_image.validate_usage(ImageUsages::TRANSFER_DST) {
+                return Err(Error::new(err, "destination image usage mismatch"))
+            }
+            let src_properties = src_image.properties();
+            let dst_properties = dst_image.properties();
+            if src_properties.samples == MsaaSamples::X1 {
+                return Err(Error::just_context(
+                    "source image msaa sample count must be greater than 1"
+                ))
+            }
+            if dst_properties.samples != MsaaSamples::X1 {
+                return Err(Error::just_context(format!(
+                    "destination image sample count {} must be 1",
+                    dst_properties.samples,
+                )))
+            }
+            if src_properties.format != dst_properties.format {
+                return Err(Error::just_context(format!(
+                    "source format {} and destination format {} must be the same",
+                    src_properties.format, dst_properties.format,
+                )))
+            }
+            if !dst_properties.format_features.contains(FormatFeatures::COLOR_ATTACHMENT) {
+                return Err(Error::just_context(
+                    "destination image format features must contain color attachment feature"
+                ))
+            }
+            let queue_family_index = self.queue.family_index();
+            let src_state = ImageSubresourceState::new(
+                vk::PipelineStageFlags2::RESOLVE,
+                vk::AccessFlags2::TRANSFER_READ,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                queue_family_index,
+            );
+            let dst_state = ImageSubresourceState::new(
+                vk::PipelineStageFlags2::RESOLVE,
+                vk::AccessFlags2::TRANSFER_WRITE,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                queue_family_index,
+            );
+            let n_regions = regions.len() as u32;
+            let mut vk_regions = FixedVec32
+                ::with_capacity(n_regions, &tmp_alloc)
+                .context("alloc failed")?;
+            let mut memory_barrier_ranges = FixedVec32
+                ::with_capacity(n_regions * 2, &tmp_alloc)
+                .context("alloc failed")?;
+            for &region in regions {
+                if region.src_subresource.aspect_mask != ImageAspects::COLOR ||
+                    region.dst_subresource.aspect_mask != ImageAspects::COLOR
+                {
+                    return Err(Error::just_context(format!(
+                        "source aspect mask {} and destination aspect mask {} must both be {}",
+                        region.src_subresource.aspect_mask, region.dst_subresource.aspect_mask,
+                        ImageAspects::COLOR,
+                    )))
+                }
+                if region.extent.is_zero() {
+                    return Err(Error::just_context(
+                        "region extent was zero"
+                    ))
+                }
+                let src_dim = src_properties.dimensions.lod(region.src_subresource.mip_level);
+                if !region.src_offset.is_in_range(src_dim, region.extent) {
+                    return Err(Error::just_context(format!(
+                        "region source offset {} + extent {} is out of range of image dimensions {}",
+                        region.src_offset, region.extent, src_dim,
+                    )))
+                }
+                let dst_dim = dst_properties.dimensions.lod(region.dst_subresource.mip_level);
+                if !region.dst_offset.is_in_range(dst_dim, region.extent) {
+                    return Err(Error::just_context(format!(
+                        "region destination offset {} + extent {} is out of range of image dimensions {}",
+                        region.dst_offset, region.extent, dst_dim,
+                    )))
+                }
+                let ranges1 = src_image.memory_barrier(
+                    src_state,
+                    region.src_subresource.into_range(),
+                    true,
+                    &mut cache.shader_resource_cache.image_memory_barrier_cache,
+                ).context("source image memory barrier failed")?;
+                let ranges2 = dst_image.memory_barrier(
+                    dst_state,
+                    region.dst_subresource.into_range(),
+                    true,
+                    &mut cache.shader_resource_cache.image_memory_barrier_cache,
+                ).context("destination image memory barrier failed")?;
+                memory_barrier_ranges.fast_append(&[ranges1, ranges2]);
+                vk_regions.push(region.into());
+            }
+            let mem_barriers = cache.shader_resource_cache.image_memory_barrier_cache.flush(
+                &memory_barrier_ranges,
+                &tmp_alloc
+            )?;
+            let dependency_info = vk::DependencyInfo {
+                image_memory_barrier_count: mem_barriers.len(),
+                p_image_memory_barriers: mem_barriers.as_ptr(),
+                ..Default::default()
+            };
+            let command_buffer = self.command_buffer;
+            let info = vk::ResolveImageInfo2 {
+                src_image: src_image.handle(),
+                src_image_layout: src_state.layout,
+                dst_image: dst_image.handle(),
+                dst_image_layout: dst_state.layout,
+                region_count: vk_regions.len(),
+                p_regions: vk_regions.as_ptr(),
+                ..Default::default()
+            };
+            unsafe {
+                self.gpu.device().cmd_pipeline_barrier2(command_buffer, &dependency_info);
+                self.gpu.device().cmd_resolve_image2(command_buffer, &info);
+            }
+            Ok(())
+        })
+    }
+
     /// Generates mip maps from the first mip level of an image.
     ///
+    /// If the format features of the image don't contain both [`FormatFeatures::BLIT_SRC`] and
+    /// [`FormatFeatures::BLIT_DST`], the mip maps are generated with a built-in fragment shader
+    /// instead, see [`blit_image`][3] for the requirements of that path.
+    ///
     /// # Valid usage
     /// - `image_id` *must* be a valid [`ImageId`].
-    /// - The specified usage of the image *must* contain [`ImageUsages::TRANSFER_SRC`] and
-    ///   [`ImageUsages::TRANSFER_DST`] bits.
-    /// - Format features of the image *must* contain [`FormatFeatures::BLIT_SRC`] and
-    ///   [`FormatFeatures::BIT_DST`] bits.
+    /// - If the image format supports blitting, the specified usage of the image *must* contain
+    ///   [`ImageUsages::TRANSFER_SRC`] and [`ImageUsages::TRANSFER_DST`] bits.
     /// - The image *must* have [`msaa sample count`][1] of 1.
     /// - If the image format has a depth/stencil [`aspect`][2], `filter` *must* be [`Filter::Nearest`].
     /// - If `filter` is [`Filter::Linear`], the format features of the image *must* contain
@@ -1860,6 +2281,7 @@ impl<'a, 'b> CopyCommands<'a, 'b> {
     ///
     /// [1]: MsaaSamples
     /// [2]: ImageAspects
+    /// [3]: CopyCommands::blit_image
     pub fn gen_mip_map(
         &mut self,
         image_id: ImageId,
@@ -1879,14 +2301,62 @@ impl<'a, 'b> CopyCommands<'a, 'b> {
                 image_id.slot_index(),
                 command_id.index(),
             )?;
+            let handle = image.handle();
+            let properties = image.properties();
+            if properties.samples != MsaaSamples::X1 {
+                return Err(Error::just_context(format!(
+                    "image sample count {} must be 1", 
+                    properties.samples,
+                )))
+            }
+            if !properties.format_features.contains(FormatFeatures::BLIT_SRC | FormatFeatures::BLIT_DST) {
+                self.wait_scope |= vk::PipelineStageFlags2::FRAGMENT_SHADER |
+                    vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT;
+                self.signal_scope |= vk::PipelineStageFlags2::FRAGMENT_SHADER |
+                    vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT;
+                let mut regions = FixedVec32::with_capacity(
+                    properties.mip_levels.saturating_sub(1), &tmp_alloc
+                ).context("alloc failed")?;
+                for i in 1..properties.mip_levels {
+                    let src_dimensions = properties.dimensions.lod(i - 1);
+                    let dst_dimensions = properties.dimensions.lod(i);
+                    regions.push(ImageBlitRegion {
+                        src_subresource: ImageSubresourceLayers::default()
+                            .aspect_mask(properties.aspect_mask)
+                            .mip_level(i - 1),
+                        src_offsets: [
+                            ImageBlitOffset::new(0, 0, 0),
+                            ImageBlitOffset::new(src_dimensions.width, src_dimensions.height, 1),
+                        ],
+                        dst_subresource: ImageSubresourceLayers::default()
+                            .aspect_mask(properties.aspect_mask)
+                            .mip_level(i),
+                        dst_offsets: [
+                            ImageBlitOffset::new(0, 0, 0),
+                            ImageBlitOffset::new(dst_dimensions.width, dst_dimensions.height, 1),
+                        ],
+                    });
+                }
+                let image: *mut ImageMeta = image;
+                return unsafe {
+                    blit_fallback::record_blits(
+                        &self.gpu,
+                        self.command_buffer,
+                        self.queue.family_index(),
+                        &mut cache.shader_resource_cache.image_memory_barrier_cache,
+                        image,
+                        image,
+                        &regions,
+                        filter,
+                    )
+                }.context("failed to generate mip maps with the fallback path")
+            }
             if let Some(err) = image.validate_usage(
                 ImageUsages::TRANSFER_SRC |
                 ImageUsages::TRANSFER_DST
             ) {
                 return Err(Error::new(err, "image has incompatible usage"))
             }
-            let handle = image.handle();
-            let properties = image.properties();
             if filter == Filter::Linear {
                 if properties.aspect_mask.intersects(ImageAspects::DEPTH | ImageAspects::STENCIL) {
                     return Err(Error::just_context(format!(
@@ -1901,17 +2371,6 @@ impl<'a, 'b> CopyCommands<'a, 'b> {
                     )))
                 }
             }
-            if properties.samples != MsaaSamples::X1 {
-                return Err(Error::just_context(format!(
-                    "image sample count {} must be 1", 
-                    properties.samples,
-                )))
-            }
-            if !properties.format_features.contains(FormatFeatures::BLIT_SRC | FormatFeatures::BLIT_DST) {
-                return Err(Error::just_context(
-                    "image format doesn't support image blitting"
-                ))
-            }
             let mip_levels = properties.mip_levels;
             let mut mip_dimensions = properties.dimensions;
             let queue_family_index = self.queue.family_index();
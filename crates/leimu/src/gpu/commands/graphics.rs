@@ -1,6 +1,14 @@
 mod structs;
 mod draw;
 
+use core::{
+    pin::Pin,
+    ptr::NonNull,
+    mem,
+};
+
+use std::panic::{self, AssertUnwindSafe};
+
 use nox_proc::BuildStructure;
 use nox_mem::{
     alloc::LocalAlloc,
@@ -14,6 +22,10 @@ use nox_ash::vk;
 use crate::{
     error::*,
     gpu::prelude::*,
+    threads::{
+        executor::{SpawnExt, block_on},
+        futures::future::join_all,
+    },
 };
 
 use super::prelude::CommandResult;
@@ -50,9 +62,22 @@ pub struct RenderingInfo {
     ///
     /// The default value is zero.
     ///
+    /// Multiview *can* be used to render e.g. all faces of a cube map or both eyes of a stereo
+    /// image with a single pass, by using an image view with multiple array layers as the
+    /// attachment. Each view renders to the array layer matching its view index.
+    ///
     /// # Valid usage
+    /// - If `view_mask` is not zero, [`multiview`][1] *must* be enabled.
+    /// - The index of the most significant bit of `view_mask` *must* be less than
+    ///   [`max multiview view count`][2].
     /// - If `view_mask` is not zero, each attachment's image view *must* contain at least a number
     ///   of layers greater than the index of the most significant bit of `view_mask`.
+    /// - Each pipeline bound in the pass *must* have been created with the same
+    ///   [`view mask`][3].
+    ///
+    /// [1]: ext::multiview
+    /// [2]: ext::multiview::Attributes::MAX_MULTIVIEW_VIEW_COUNT
+    /// [3]: GraphicsPipelineCreateInfo::with_view_mask
     pub view_mask: u32,
     /// Specifies how many multisample anti-aliasing samples attachments have.
     ///
@@ -86,6 +111,8 @@ pub struct GraphicsCommands<'a, 'b> {
     recorder: CommandRecorder<'a, 'b>,
     primary_command_buffer: vk::CommandBuffer,
     command_id: CommandId,
+    queries: CommandQueries,
+    labels: CommandLabels,
     wait_scope: vk::PipelineStageFlags2,
     signal_scope: vk::PipelineStageFlags2,
 }
@@ -99,9 +126,42 @@ pub struct ActiveRenderPass<'a, 'b, 'c>
     color_formats: NonNullVec32<'static, Format>,
     depth_format: Format,
     stencil_format: Format,
+    view_mask: u32,
     alloc: ArenaGuard<'a, True>,
 }
 
+/// A closure of [`ActiveRenderPass::parallel_draw`] with the state it's recorded with.
+struct ParallelDrawJob<F> {
+    f: F,
+    gpu: Gpu,
+    storage: NonNull<DrawCommandStorage>,
+    alloc: NonNull<ArenaGuard<'static, True>>,
+}
+
+unsafe impl<F: Send> Send for ParallelDrawJob<F> {}
+
+impl<F> ParallelDrawJob<F>
+    where F: FnOnce(&mut DrawCommands) -> Result<()>
+{
+
+    /// # Safety
+    /// `storage` and `alloc` *must* outlive the call and `storage` *must* not be accessed
+    /// anywhere else during the call.
+    unsafe fn record(self) -> Result<()> {
+        let Self { f, gpu, storage, alloc } = self;
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut draw_commands = DrawCommands::new(
+                gpu.clone(),
+                unsafe { &mut *storage.as_ptr() },
+                unsafe { alloc.as_ref() },
+                gpu.read_buffers(),
+                gpu.read_images(),
+            );
+            f(&mut draw_commands)
+        })).unwrap_or_else(|_| Err(Error::just_context("panicked while recording draw commands")))
+    }
+}
+
 pub struct NewGraphicsCommands;
 
 impl NewCommands for NewGraphicsCommands {
@@ -142,6 +202,8 @@ impl NewCommands for NewGraphicsCommands {
             recorder,
             primary_command_buffer: command_buffer,
             command_id,
+            queries: Default::default(),
+            labels: Default::default(),
             wait_scope: vk::PipelineStageFlags2::NONE,
             signal_scope: vk::PipelineStageFlags2::NONE,
         })
@@ -173,6 +235,8 @@ unsafe impl<'a, 'b> Commands<'a, 'b> for GraphicsCommands<'a, 'b> {
     fn finish<'c, Alloc>(self, alloc: &'c Alloc) -> Result<CommandResult<'c, Alloc>>
         where Alloc: ?Sized + LocalAlloc<Error = arena::Error> 
     {
+        self.queries.finish()?;
+        self.labels.finish()?;
         unsafe {
             self.gpu.device()
                 .end_command_buffer(self.primary_command_buffer)
@@ -187,12 +251,138 @@ unsafe impl<'a, 'b> Commands<'a, 'b> for GraphicsCommands<'a, 'b> {
             wait_scope: self.wait_scope,
             signal_scope: self.signal_scope,
             queue: self.queue,
+            sparse_binds: None,
         })
     }
 }
 
 impl<'a, 'b> GraphicsCommands<'a, 'b> { 
 
+    /// Writes a timestamp to `query` of a [`timestamp`][1] query pool once all previous commands
+    /// have reached `stage`.
+    ///
+    /// The result can be retrieved with [`Gpu::get_query_results`] after the frame has finished.
+    ///
+    /// # Valid usage
+    /// - `pool_id` *must* be a valid [`QueryPoolId`] of a [`timestamp`][1] query pool.
+    /// - `query` *must* be less than the query count of the pool and it *must* not be active.
+    /// - The queue family of the command *must* support timestamps.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdWriteTimestamp2.html>
+    ///
+    /// [1]: QueryType::Timestamp
+    pub fn write_timestamp(
+        &mut self,
+        pool_id: QueryPoolId,
+        query: u32,
+        stage: TimestampStage,
+    ) -> Result<()>
+    {
+        let frame = self.recorder.current_frame();
+        unsafe {
+            self.queries.write_timestamp(
+                &self.gpu, self.primary_command_buffer, &self.queue,
+                self.command_id, frame,
+                pool_id, query, stage,
+            )
+        }
+    }
+
+    /// Begins `query` of an [`occlusion`][1] or a [`pipeline statistics`][2] query pool.
+    ///
+    /// Every begun query *must* be ended with [`end_query`][3] before the command finishes.
+    ///
+    /// # Valid usage
+    /// - `pool_id` *must* be a valid [`QueryPoolId`] of a non-timestamp query pool.
+    /// - `query` *must* be less than the query count of the pool.
+    /// - No other query of the same pool *can* be active.
+    /// - If `precise` is true, the pool *must* be an occlusion query pool and
+    ///   [`BaseDeviceFeatures::occlusion_query_precise`] *must* be enabled.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBeginQuery.html>
+    ///
+    /// [1]: QueryType::Occlusion
+    /// [2]: QueryType::PipelineStatistics
+    /// [3]: Self::end_query
+    pub fn begin_query(
+        &mut self,
+        pool_id: QueryPoolId,
+        query: u32,
+        precise: bool,
+    ) -> Result<()>
+    {
+        let frame = self.recorder.current_frame();
+        unsafe {
+            self.queries.begin_query(
+                &self.gpu, self.primary_command_buffer, &self.queue,
+                self.command_id, frame,
+                pool_id, query, precise,
+            )
+        }
+    }
+
+    /// Ends `query` begun with [`begin_query`][1].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdEndQuery.html>
+    ///
+    /// [1]: Self::begin_query
+    pub fn end_query(
+        &mut self,
+        pool_id: QueryPoolId,
+        query: u32,
+    ) -> Result<()>
+    {
+        unsafe {
+            self.queries.end_query(
+                &self.gpu, self.primary_command_buffer,
+                self.command_id,
+                pool_id, query,
+            )
+        }
+    }
+
+    /// Begins a debug label region named `name`, which shows up in debugging tools.
+    ///
+    /// Every label *must* be ended with [`end_label`][1] before the commands finish. Labels can
+    /// be nested.
+    ///
+    /// Does nothing if [`VK_EXT_debug_utils`][2] is not enabled.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBeginDebugUtilsLabelEXT.html>
+    ///
+    /// [1]: Self::end_label
+    /// [2]: Instance::with_debug_utils
+    pub fn begin_label(
+        &mut self,
+        name: &str,
+        color: [f32; 4],
+    ) {
+        unsafe {
+            self.labels.begin(
+                self.gpu.device(), self.primary_command_buffer,
+                name, color,
+            );
+        }
+    }
+
+    /// Ends the innermost label begun with [`begin_label`][1].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdEndDebugUtilsLabelEXT.html>
+    ///
+    /// [1]: Self::begin_label
+    pub fn end_label(&mut self) -> Result<()> {
+        unsafe {
+            self.labels.end(
+                self.gpu.device(), self.primary_command_buffer,
+            )
+        }
+    }
+
     pub fn swapchain_image_view(
         &self,
         surface_id: SurfaceId
@@ -211,6 +401,19 @@ impl<'a, 'b> GraphicsCommands<'a, 'b> {
     {
         let command_id = self.command_id;
         let msaa_samples = rendering_info.msaa_samples;
+        let view_mask = rendering_info.view_mask;
+        ext::multiview::validate_view_mask(self.gpu.device(), view_mask)
+            .context("invalid rendering view mask")?;
+        if view_mask == 0 && rendering_info.layer_count == 0 {
+            return Err(Error::just_context(
+                "rendering layer count must not be zero if view mask is zero"
+            ))
+        }
+        let required_layer_count = if view_mask != 0 {
+            u32::BITS - view_mask.leading_zeros()
+        } else {
+            rendering_info.layer_count
+        };
         let stack = self.recorder.stack().clone();
         let mut vk_color_attachments = NonNullVec32
             ::with_capacity(color_attachments.len() as u32, &stack)
@@ -255,7 +458,7 @@ impl<'a, 'b> GraphicsCommands<'a, 'b> {
                     properties.array_layers
                 );
                 let layer_count = effective_range.layer_count;
-                let mip_levels = effective_range.layer_count;
+                let mip_levels = effective_range.level_count;
                 let format = view.component_info.format;
                 if mip_levels != 1 {
                     return Err(Error::just_context(format!(
@@ -263,10 +466,10 @@ impl<'a, 'b> GraphicsCommands<'a, 'b> {
                         image_view, mip_levels,
                     )))
                 }
-                if layer_count < rendering_info.layer_count {
+                if layer_count < required_layer_count {
                     return Err(Error::just_context(format!(
                         "image view {} layer count {} was less than pass layer count {}",
-                        image_view, layer_count, rendering_info.layer_count,
+                        image_view, layer_count, required_layer_count,
                     )))
                 }
                 let mut attachment_info = vk::RenderingAttachmentInfo {
@@ -433,6 +636,7 @@ impl<'a, 'b> GraphicsCommands<'a, 'b> {
             color_formats,
             depth_format,
             stencil_format,
+            view_mask,
             alloc: stack.guard(),
         };
         f(&mut pass).context_from_tracked(|orig| {
@@ -501,6 +705,12 @@ impl<'a, 'b, 'c> ActiveRenderPass<'a, 'b, 'c>
                 cmd.storage.sample_count, self.sample_count,
             )))
         }
+        if cmd.storage.view_mask != self.view_mask {
+            return Err(Error::just_context(format!(
+                "draw command view mask {:#b} doesn't match pass view mask {:#b}",
+                cmd.storage.view_mask, self.view_mask,
+            )))
+        }
         Ok(())
     }
 
@@ -513,12 +723,149 @@ impl<'a, 'b, 'c> ActiveRenderPass<'a, 'b, 'c>
         let command_buffer = self.cmd.recorder
             .get_current_worker()
             .allocate_secondaries(&self.cmd.queue, 1)?[0];
+        self.begin_secondary(command_buffer)?;
+        let draw_storage = &mut self.next_draw_storages(1)[0];
+        draw_storage.reinit(
+            command_buffer,
+            &self.color_formats,
+            self.depth_format,
+            self.stencil_format,
+            self.sample_count,
+            self.view_mask,
+            &self.alloc,
+        )?;
+        let mut draw_commands = DrawCommands::new(
+            self.cmd.gpu.clone(),
+            draw_storage,
+            &self.alloc,
+            self.cmd.recorder.buffers(),
+            self.cmd.recorder.images(),
+        );
+        f(&mut draw_commands)?;
+        unsafe {
+            self.cmd.gpu.device()
+                .end_command_buffer(command_buffer)
+                .context("failed to end secondary command buffer")?;
+        }
+        Ok(())
+    }
+
+    /// Records each closure of `draws` concurrently into its own secondary command buffer.
+    ///
+    /// The first closure is recorded on the calling thread and the rest are recorded on the
+    /// [`thread pool`][1] of the [`Gpu`]. This blocks until all closures have finished
+    /// recording.
+    ///
+    /// The secondary command buffers inherit the rendering info of the pass and are executed in
+    /// the order of `draws`, after any draws recorded before this call and before any draws
+    /// recorded after it. Resources used by the draws are registered when the pass ends, the same
+    /// way as with [`ActiveRenderPass::dynamic_draw`].
+    ///
+    /// If any closure returns an error, the error of the first failed closure is returned after
+    /// all closures have finished.
+    ///
+    /// # Valid usage
+    /// - This *must* not be called from a thread of the [`thread pool`][1], if the thread pool
+    ///   has no other threads to record the closures with.
+    ///
+    /// [1]: crate::threads::executor::ThreadPool
+    pub fn parallel_draw<I, F>(
+        &mut self,
+        draws: I,
+    ) -> Result<()>
+        where
+            I: IntoIterator<Item = F>,
+            I::IntoIter: ExactSizeIterator,
+            F: FnOnce(&mut DrawCommands) -> Result<()> + Send,
+    {
+        let draws = draws.into_iter();
+        let count = draws.len() as u32;
+        if count == 0 {
+            return Ok(())
+        }
+        let command_buffers = self.cmd.recorder
+            .get_current_worker()
+            .allocate_parallel_secondaries(&self.cmd.queue, count)?;
+        for &command_buffer in &command_buffers {
+            self.begin_secondary(command_buffer)?;
+        }
+        let draw_storages = self.next_draw_storages(count);
+        for (storage, &command_buffer) in draw_storages.iter_mut().zip(&command_buffers) {
+            storage.reinit(
+                command_buffer,
+                &self.color_formats,
+                self.depth_format,
+                self.stencil_format,
+                self.sample_count,
+                self.view_mask,
+                &self.alloc,
+            )?;
+        }
+        let alloc = NonNull::from(&self.alloc).cast::<ArenaGuard<'static, True>>();
+        let gpu = &self.cmd.gpu;
+        let mut jobs = draws
+            .zip(draw_storages.iter_mut())
+            .map(|(f, storage)| ParallelDrawJob {
+                f,
+                gpu: gpu.clone(),
+                storage: NonNull::from(storage),
+                alloc,
+            });
+        let first = jobs.next();
+        let thread_pool = gpu.thread_pool();
+        let mut handles = Vec32::with_capacity(count - 1);
+        let mut spawn_result = Ok(());
+        for job in jobs {
+            let fut: Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> = Box::pin(async move {
+                unsafe { job.record() }
+            });
+            // SAFETY: all spawned jobs are waited for below before the borrowed storages,
+            // allocator or closures go out of scope
+            let fut = unsafe {
+                mem::transmute::<_, Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>>(fut)
+            };
+            match thread_pool
+                .spawn_with_handle(fut)
+                .context("failed to spawn parallel draw recording")
+            {
+                Ok(handle) => handles.push(handle),
+                Err(err) => {
+                    spawn_result = Err(err);
+                    break
+                },
+            }
+        }
+        let first_result = match first {
+            Some(job) if spawn_result.is_ok() => unsafe { job.record() },
+            _ => Ok(()),
+        };
+        let results = block_on(join_all(handles));
+        for &command_buffer in &command_buffers {
+            unsafe {
+                self.cmd.gpu.device()
+                    .end_command_buffer(command_buffer)
+                    .context("failed to end secondary command buffer")?;
+            }
+        }
+        spawn_result?;
+        first_result.context("failed to record parallel draw 0")?;
+        for (i, result) in results.into_iter().enumerate() {
+            result.context_with(|| format!(
+                "failed to record parallel draw {}", i + 1,
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// Begins a secondary command buffer inheriting the rendering info of the pass.
+    fn begin_secondary(&self, command_buffer: vk::CommandBuffer) -> Result<()> {
         let mut rendering_inheritance_info = vk::CommandBufferInheritanceRenderingInfo {
             s_type: vk::StructureType::COMMAND_BUFFER_INHERITANCE_RENDERING_INFO,
             color_attachment_count: self.color_formats.len(),
             p_color_attachment_formats: self.color_formats.as_ptr().cast(),
             depth_attachment_format: self.depth_format.into(),
             stencil_attachment_format: self.stencil_format.into(),
+            view_mask: self.view_mask,
             rasterization_samples: self.sample_count.into(),
             ..Default::default()
         };
@@ -539,38 +886,30 @@ impl<'a, 'b, 'c> ActiveRenderPass<'a, 'b, 'c>
                 .begin_command_buffer(command_buffer, &begin_info)
                 .context("failed to begin secondary command buffer")?;
         }
+        Ok(())
+    }
+
+    /// Takes the next `count` draw command storages of the command cache.
+    fn next_draw_storages<'d>(&mut self, count: u32) -> &'d mut [DrawCommandStorage] {
         let cache = unsafe { &mut *self.cmd.recorder.cache().get() };
         let cache = &mut cache.graphics_command_cache;
-        if cache.next_draw_command_storage >= cache.draw_storages.len() {
+        let next = cache.next_draw_command_storage;
+        if next + count > cache.draw_storages.len() {
             let push_descriptor_device = self.cmd.gpu.get_extension_device();
-            cache.draw_storages.resize_with(cache.next_draw_command_storage + 1, || {
-                DrawCommandStorage::new(push_descriptor_device.clone())
+            let draw_indirect_count_device = self.cmd.gpu.get_extension_device();
+            let mesh_shader_device = self.cmd.gpu.get_extension_device();
+            let descriptor_buffer_device = self.cmd.gpu.get_extension_device();
+            cache.draw_storages.resize_with(next + count, || {
+                DrawCommandStorage::new(
+                    push_descriptor_device.clone(),
+                    draw_indirect_count_device.clone(),
+                    mesh_shader_device.clone(),
+                    descriptor_buffer_device.clone(),
+                )
             });
         }
-        let draw_storage = &mut cache.draw_storages[cache.next_draw_command_storage as usize];
-        cache.next_draw_command_storage += 1;
-        draw_storage.reinit(
-            command_buffer,
-            &self.color_formats,
-            self.depth_format,
-            self.stencil_format,
-            self.sample_count,
-            &self.alloc,
-        )?;
-        let mut draw_commands = DrawCommands::new(
-            self.cmd.gpu.clone(),
-            draw_storage,
-            &self.alloc,
-            self.cmd.recorder.buffers(),
-            self.cmd.recorder.images(),
-        );
-        f(&mut draw_commands)?;
-        unsafe {
-            self.cmd.gpu.device()
-                .end_command_buffer(command_buffer)
-                .context("failed to end secondary command buffer")?;
-        }
-        Ok(())
+        cache.next_draw_command_storage += count;
+        &mut cache.draw_storages[next as usize..(next + count) as usize]
     }
 
     fn finish(
@@ -764,6 +1103,16 @@ impl<'a, 'b, 'c> ActiveRenderPass<'a, 'b, 'c>
                     );
                 }
             }
+            for indirect_buffer in &storage.indirect_buffers {
+                cmd_cache.shader_resource_cache.touch_buffer(
+                    indirect_buffer.id,
+                    indirect_buffer.offset,
+                    unsafe { indirect_buffer.size.unwrap_unchecked().get() },
+                    vk::PipelineStageFlags2::DRAW_INDIRECT,
+                    ExplicitAccess::from_raw(vk::AccessFlags2::INDIRECT_COMMAND_READ.as_raw()),
+                    CommandOrdering::Lenient
+                );
+            }
             if !storage.draw_calls.is_empty() {
                 self.cmd.wait_scope |= vk::PipelineStageFlags2::VERTEX_INPUT;
             }
@@ -806,6 +1155,16 @@ impl<'a, 'b, 'c> ActiveRenderPass<'a, 'b, 'c>
                     );
                 }
             }
+            for indirect_buffer in &storage.indirect_buffers {
+                cmd_cache.shader_resource_cache.touch_buffer(
+                    indirect_buffer.id,
+                    indirect_buffer.offset,
+                    unsafe { indirect_buffer.size.unwrap_unchecked().get() },
+                    vk::PipelineStageFlags2::DRAW_INDIRECT,
+                    ExplicitAccess::from_raw(vk::AccessFlags2::INDIRECT_COMMAND_READ.as_raw()),
+                    CommandOrdering::Lenient
+                );
+            }
             if !storage.draw_calls.is_empty() {
                 self.cmd.wait_scope |= vk::PipelineStageFlags2::VERTEX_INPUT;
             }
@@ -923,6 +1282,7 @@ impl<'a, 'b, 'c> ActiveRenderPass<'a, 'b, 'c>
                     ..Default::default()
                 }).into(),
             layer_count: rendering_info.layer_count,
+            view_mask: rendering_info.view_mask,
             color_attachment_count: color_attachments.len() as u32,
             p_color_attachments: color_attachments.as_ptr(),
             p_depth_attachment: depth_attachment.as_ref().as_ptr(),
@@ -20,7 +20,7 @@ use crate::{
     gpu::{
         prelude::*,
         command_cache::PipelineCommandCache,
-        ext::push_descriptor,
+        ext::{push_descriptor, draw_indirect_count, mesh_shader, descriptor_buffer},
     },
     error::*,
     threads::executor::block_on,
@@ -110,12 +110,16 @@ pub(crate) struct DrawCommandStorage {
     pub(super) pipelines: Vec32<PipelineHandle>,
     pub(super) pipeline_cache: PipelineCommandCache,
     pub(super) draw_calls: Vec32<DrawCall>,
+    pub(super) indirect_buffers: Vec32<DrawBufferRange>,
+    draw_indirect_count_device: Option<draw_indirect_count::Device>,
+    mesh_shader_device: Option<mesh_shader::Device>,
     pub command_buffer: vk::CommandBuffer,
     pub(super) wait_scope: vk::PipelineStageFlags2,
     pub(super) color_formats: NonNullVec32<'static, Format>,
     pub(super) depth_format: Format,
     pub(super) stencil_format: Format,
     pub(super) sample_count: MsaaSamples,
+    pub(super) view_mask: u32,
 }
 
 #[derive(Default, Clone, Copy, BuildStructure)]
@@ -124,6 +128,7 @@ pub struct DrawCommandInfo<'a> {
     pub depth_format: Format,
     pub stencil_format: Format,
     pub sample_count: MsaaSamples,
+    pub view_mask: u32,
 }
 
 impl DrawCommandStorage {
@@ -131,17 +136,27 @@ impl DrawCommandStorage {
     #[inline(always)]
     pub fn new(
         push_descriptor_device: Option<push_descriptor::Device>,
+        draw_indirect_count_device: Option<draw_indirect_count::Device>,
+        mesh_shader_device: Option<mesh_shader::Device>,
+        descriptor_buffer_device: Option<descriptor_buffer::Device>,
     ) -> Self {
         Self {
             pipelines: Default::default(),
-            pipeline_cache: PipelineCommandCache::new(push_descriptor_device),
+            pipeline_cache: PipelineCommandCache::new(
+                push_descriptor_device,
+                descriptor_buffer_device,
+            ),
             draw_calls: Default::default(),
+            indirect_buffers: Default::default(),
+            draw_indirect_count_device,
+            mesh_shader_device,
             command_buffer: Default::default(),
             wait_scope: Default::default(),
             color_formats: NonNullVec32::default(),
             depth_format: Format::Undefined,
             stencil_format: Format::Undefined,
             sample_count: MsaaSamples::X1,
+            view_mask: 0,
         }
     }
 
@@ -153,6 +168,7 @@ impl DrawCommandStorage {
         depth_format: Format,
         stencil_format: Format,
         sample_count: MsaaSamples,
+        view_mask: u32,
         alloc: &Alloc,
     ) -> Result<()>
         where Alloc: LocalAlloc
@@ -167,6 +183,7 @@ impl DrawCommandStorage {
         self.depth_format = depth_format;
         self.stencil_format = stencil_format;
         self.sample_count= sample_count;
+        self.view_mask = view_mask;
         Ok(())
     }
 
@@ -182,6 +199,7 @@ impl DrawCommandStorage {
             }
             self.color_formats.drop_and_free(alloc);
             self.draw_calls.clear();
+            self.indirect_buffers.clear();
             self.pipelines.clear();
         }
     }
@@ -228,6 +246,9 @@ pub struct DrawPipelineCommands<'a, 'b, State = state::Base> {
     pipeline: &'a GraphicsPipeline,
     wait_scope: &'a mut vk::PipelineStageFlags2,
     draw_calls: &'a mut Vec32<DrawCall>,
+    indirect_buffers: &'a mut Vec32<DrawBufferRange>,
+    draw_indirect_count_device: Option<&'a draw_indirect_count::Device>,
+    mesh_shader_device: Option<&'a mesh_shader::Device>,
     draw_info: Option<DrawInfo>,
     indexed_draw_info: Option<IndexedDrawInfo>,
     _marker: PhantomData<State>,
@@ -271,6 +292,8 @@ impl<'a> DrawCommands<'a> {
     ///   when creating the [`draw commands`][1].
     /// - The bound pipeline's depth and stencil formats *must* match the respective formats
     ///   defined when creating the [`draw commands`][1].
+    /// - The bound pipeline's view mask *must* match the view mask defined when creating the
+    ///   [`draw commands`][1].
     /// - The valid usage section of [`DrawCommands::set_multi_viewport`] apply when the number of
     ///   viewports and scissors is not one.
     ///
@@ -308,6 +331,12 @@ impl<'a> DrawCommands<'a> {
                 pipeline.stencil_output_format(), self.storage.stencil_format,
             )))
         }
+        if pipeline.view_mask() != self.storage.view_mask {
+            return Err(Error::just_context(format!(
+                "pipeline view mask {:#b} doesn't match with pass view mask {:#b}",
+                pipeline.view_mask(), self.storage.view_mask,
+            )))
+        }
         let handle = pipeline.handle().clone();
         unsafe {
             self.gpu.device().cmd_bind_pipeline(
@@ -360,6 +389,9 @@ impl<'a> DrawCommands<'a> {
                 pipeline,
                 wait_scope: &mut self.storage.wait_scope,
                 draw_calls: &mut self.storage.draw_calls,
+                indirect_buffers: &mut self.storage.indirect_buffers,
+                draw_indirect_count_device: self.storage.draw_indirect_count_device.as_ref(),
+                mesh_shader_device: self.storage.mesh_shader_device.as_ref(),
                 draw_info: None,
                 indexed_draw_info: None,
                 _marker: PhantomData,
@@ -397,6 +429,77 @@ impl<'a, 'b, State> DrawPipelineCommands<'a, 'b, State> {
         Ok(())
     }
 
+    fn check_indirect_stride(
+        &self,
+        stride: u32,
+        command_size: u32,
+    ) -> Result<()> {
+        if !stride.is_multiple_of(4) || stride < command_size {
+            return Err(Error::just_context(format!(
+                "indirect stride {stride} must be a multiple of 4 and greater than or equal to {command_size}"
+            )))
+        }
+        Ok(())
+    }
+
+    fn register_indirect_buffer(
+        &mut self,
+        id: BufferId,
+        offset: DeviceSize,
+        size: DeviceSize,
+        name: &str,
+    ) -> Result<vk::Buffer> {
+        let buf = self.buffers.get(id)?;
+        if !offset.is_multiple_of(4) {
+            return Err(Error::just_context(format!(
+                "{name} buffer offset {offset} must be a multiple of 4"
+            )))
+        }
+        if offset + size > buf.properties().size {
+            return Err(Error::just_context(format!(
+                "{name} buffer offset {offset} + size {size} is out of range of buffer size {}",
+                buf.properties().size,
+            )))
+        }
+        if let Some(err) = buf.validate_usage(BufferUsages::INDIRECT_BUFFER) {
+            return Err(Error::new(err, format!("{name} buffer has incompatible usage")))
+        }
+        let handle = buf.handle();
+        self.indirect_buffers.push(DrawBufferRange::new(id, offset, size));
+        *self.wait_scope |= vk::PipelineStageFlags2::DRAW_INDIRECT;
+        Ok(handle)
+    }
+
+    fn indirect_draw_count_device(&self) -> Result<draw_indirect_count::Device> {
+        self.draw_indirect_count_device
+            .cloned()
+            .ok_or_else(|| Error::just_context(
+                "draw indirect count device extension is not enabled"
+            ))
+    }
+
+    fn check_vertex_pipeline(&self) -> Result<()> {
+        if self.pipeline.is_mesh_pipeline() {
+            return Err(Error::just_context(
+                "currently bound pipeline has a mesh shader, use draw_mesh_tasks instead"
+            ))
+        }
+        Ok(())
+    }
+
+    fn mesh_shader_device(&self) -> Result<mesh_shader::Device> {
+        if !self.pipeline.is_mesh_pipeline() {
+            return Err(Error::just_context(
+                "currently bound pipeline doesn't have a mesh shader"
+            ))
+        }
+        self.mesh_shader_device
+            .cloned()
+            .ok_or_else(|| Error::just_context(
+                "mesh shader device extension is not enabled"
+            ))
+    }
+
     /// Dynamically sets the line width for subsequent drawing commands.
     ///
     /// # Valid usage
@@ -1017,6 +1120,7 @@ impl<'a, 'b, State> DrawPipelineCommands<'a, 'b, State> {
     /// Binds vertex buffers and allows performing draw calls within the closure.
     ///
     /// # Valid usage
+    /// - The currently bound [`pipeline`][1] *must* not have a mesh shader.
     /// - Each vertex binding's buffer id *must* be a valid [`BufferId`].
     /// - Each vertex buffer *must* have been created with [`BufferUsages::VERTEX_BUFFER`] bit
     ///   set.
@@ -1049,6 +1153,7 @@ impl<'a, 'b, State> DrawPipelineCommands<'a, 'b, State> {
             State: state::CanBeginDraw,
             F: FnOnce(&mut DrawPipelineCommands<state::Draw>) -> EventResult<()>,
     {
+        self.check_vertex_pipeline()?;
         *self.wait_scope |= vk::PipelineStageFlags2::VERTEX_INPUT;
         let n_bindings = vertex_bindings.len() as u32;
         let mut call = DrawCall {
@@ -1164,10 +1269,140 @@ impl<'a, 'b, State> DrawPipelineCommands<'a, 'b, State> {
         Ok(())
     }
 
+    /// Performs an indirect draw call, reading `draw_count` [`vk::DrawIndirectCommand`]
+    /// structures from `buffer`.
+    ///
+    /// This is only usable in a closure passed to [`begin_drawing`][1]. The [`DrawInfo`] passed
+    /// to [`begin_drawing`][1] is ignored.
+    ///
+    /// The argument buffer is automatically synchronized with previous writes, e.g. by a compute
+    /// pass.
+    ///
+    /// # Valid usage
+    /// - All [`dynamic states`][2] of the currently bound [`pipeline`][3] *must* be defined before
+    ///   performing any draw calls.
+    /// - `buffer` *must* be a valid [`BufferId`].
+    /// - `buffer` *must* have been created with [`BufferUsages::INDIRECT_BUFFER`] bit set.
+    /// - `offset` *must* be a multiple of 4.
+    /// - If `draw_count` is greater than one, the [`multi draw indirect`][4] feature *must* be
+    ///   enabled, and `stride` *must* be a multiple of 4 and greater than or equal to the size
+    ///   of [`vk::DrawIndirectCommand`].
+    /// - `offset` + `stride` * (`draw_count` - 1) + the size of [`vk::DrawIndirectCommand`]
+    ///   *must* be less than or equal to the buffer's size.
+    /// - If the [`draw indirect first instance`][5] feature is not enabled, the first instance of
+    ///   each command in the buffer *must* be zero.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawIndirect.html>
+    ///
+    /// [1]: Self::begin_drawing
+    /// [2]: DynamicState
+    /// [3]: GraphicsPipeline
+    /// [4]: BaseDeviceFeatures::multi_draw_indirect
+    /// [5]: BaseDeviceFeatures::draw_indirect_first_instance
+    pub fn draw_indirect(
+        &mut self,
+        buffer: BufferId,
+        offset: DeviceSize,
+        draw_count: u32,
+        stride: u32,
+    ) -> Result<()>
+        where State: state::CanDraw
+    {
+        if draw_count == 0 {
+            return Ok(())
+        }
+        let command_size = size_of::<vk::DrawIndirectCommand>() as u32;
+        if draw_count > 1 {
+            if !self.gpu.enabled_base_features().multi_draw_indirect {
+                return Err(Error::just_context(format!(
+                    "draw count {draw_count} is greater than one, but multi draw indirect is not enabled"
+                )))
+            }
+            self.check_indirect_stride(stride, command_size)?;
+        }
+        let size = stride as DeviceSize * (draw_count - 1) as DeviceSize
+            + command_size as DeviceSize;
+        let handle = self.register_indirect_buffer(buffer, offset, size, "indirect")?;
+        unsafe {
+            self.gpu.device().cmd_draw_indirect(
+                self.command_buffer,
+                handle, offset,
+                draw_count, stride,
+            );
+        }
+        Ok(())
+    }
+
+    /// Performs an indirect draw call, where the draw count is read from `count_buffer`.
+    ///
+    /// This is only usable in a closure passed to [`begin_drawing`][1]. The [`DrawInfo`] passed
+    /// to [`begin_drawing`][1] is ignored.
+    ///
+    /// Both the argument buffer and the count buffer are automatically synchronized with previous
+    /// writes, which allows GPU-driven culling written by a compute pass.
+    ///
+    /// # Valid usage
+    /// - The [`draw indirect count`][2] device extension *must* be enabled.
+    /// - All [`dynamic states`][3] of the currently bound [`pipeline`][4] *must* be defined before
+    ///   performing any draw calls.
+    /// - `buffer` and `count_buffer` *must* be valid [`BufferIds`][5].
+    /// - `buffer` and `count_buffer` *must* have been created with
+    ///   [`BufferUsages::INDIRECT_BUFFER`] bit set.
+    /// - `offset` and `count_offset` *must* be multiples of 4.
+    /// - `stride` *must* be a multiple of 4 and greater than or equal to the size of
+    ///   [`vk::DrawIndirectCommand`].
+    /// - `offset` + `stride` * (`max_draw_count` - 1) + the size of [`vk::DrawIndirectCommand`]
+    ///   *must* be less than or equal to the buffer's size.
+    /// - `count_offset` + 4 *must* be less than or equal to the count buffer's size.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawIndirectCount.html>
+    ///
+    /// [1]: Self::begin_drawing
+    /// [2]: ext::draw_indirect_count
+    /// [3]: DynamicState
+    /// [4]: GraphicsPipeline
+    /// [5]: BufferId
+    pub fn draw_indirect_count(
+        &mut self,
+        buffer: BufferId,
+        offset: DeviceSize,
+        count_buffer: BufferId,
+        count_offset: DeviceSize,
+        max_draw_count: u32,
+        stride: u32,
+    ) -> Result<()>
+        where State: state::CanDraw
+    {
+        let device = self.indirect_draw_count_device()?;
+        let command_size = size_of::<vk::DrawIndirectCommand>() as u32;
+        self.check_indirect_stride(stride, command_size)?;
+        if max_draw_count == 0 {
+            return Ok(())
+        }
+        let size = stride as DeviceSize * (max_draw_count - 1) as DeviceSize
+            + command_size as DeviceSize;
+        let handle = self.register_indirect_buffer(buffer, offset, size, "indirect")?;
+        let count_handle = self.register_indirect_buffer(
+            count_buffer, count_offset, size_of::<u32>() as DeviceSize, "count",
+        )?;
+        unsafe {
+            device.cmd_draw_indirect_count(
+                self.command_buffer,
+                handle, offset,
+                count_handle, count_offset,
+                max_draw_count, stride,
+            );
+        }
+        Ok(())
+    }
+
     /// Binds an index buffer and vertex buffers and allows performing draw calls within the
     /// closure.
     ///
     /// # Valid usage
+    /// - The currently bound [`pipeline`][1] *must* not have a mesh shader.
     /// - If the [`index type uint8`][2] device extension is not enabled, [`index type`][3] *must*
     ///   not be [`IndexType::U8`].
     /// - Index buffer id *must* be a valid [`BufferId`].
@@ -1214,6 +1449,7 @@ impl<'a, 'b, State> DrawPipelineCommands<'a, 'b, State> {
             State: state::CanBeginDraw,
             F: FnOnce(&mut DrawPipelineCommands<state::DrawIndexed>) -> EventResult<()>,
     {
+        self.check_vertex_pipeline()?;
         *self.wait_scope |= vk::PipelineStageFlags2::VERTEX_INPUT;
         let index_buf_size = (draw_info.first_index + draw_info.index_count) as vk::DeviceSize
             * draw_info.index_type.index_size();
@@ -1349,5 +1585,393 @@ impl<'a, 'b, State> DrawPipelineCommands<'a, 'b, State> {
             );
         }
         Ok(())
-    } 
+    }
+
+    /// Performs an indirect indexed draw call, reading `draw_count`
+    /// [`vk::DrawIndexedIndirectCommand`] structures from `buffer`.
+    ///
+    /// This is only usable in a closure passed to [`begin_drawing_indexed`][1]. Only the
+    /// [`index type`][2] of the [`IndexedDrawInfo`] passed to [`begin_drawing_indexed`][1] is
+    /// used, though its index range determines the bound range of the index buffer.
+    ///
+    /// The argument buffer is automatically synchronized with previous writes, e.g. by a compute
+    /// pass.
+    ///
+    /// # Valid usage
+    /// - All [`dynamic states`][3] of the currently bound [`pipeline`][4] *must* be defined before
+    ///   performing any draw calls.
+    /// - `buffer` *must* be a valid [`BufferId`].
+    /// - `buffer` *must* have been created with [`BufferUsages::INDIRECT_BUFFER`] bit set.
+    /// - `offset` *must* be a multiple of 4.
+    /// - If `draw_count` is greater than one, the [`multi draw indirect`][5] feature *must* be
+    ///   enabled, and `stride` *must* be a multiple of 4 and greater than or equal to the size
+    ///   of [`vk::DrawIndexedIndirectCommand`].
+    /// - `offset` + `stride` * (`draw_count` - 1) + the size of
+    ///   [`vk::DrawIndexedIndirectCommand`] *must* be less than or equal to the buffer's size.
+    /// - If the [`draw indirect first instance`][6] feature is not enabled, the first instance of
+    ///   each command in the buffer *must* be zero.
+    /// - Indices read by each command *must* be within the bound index buffer range.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawIndexedIndirect.html>
+    ///
+    /// [1]: Self::begin_drawing_indexed
+    /// [2]: IndexedDrawInfo::index_type
+    /// [3]: DynamicState
+    /// [4]: GraphicsPipeline
+    /// [5]: BaseDeviceFeatures::multi_draw_indirect
+    /// [6]: BaseDeviceFeatures::draw_indirect_first_instance
+    pub fn draw_indexed_indirect(
+        &mut self,
+        buffer: BufferId,
+        offset: DeviceSize,
+        draw_count: u32,
+        stride: u32,
+    ) -> Result<()>
+        where State: state::CanDrawIndexed
+    {
+        if draw_count == 0 {
+            return Ok(())
+        }
+        let command_size = size_of::<vk::DrawIndexedIndirectCommand>() as u32;
+        if draw_count > 1 {
+            if !self.gpu.enabled_base_features().multi_draw_indirect {
+                return Err(Error::just_context(format!(
+                    "draw count {draw_count} is greater than one, but multi draw indirect is not enabled"
+                )))
+            }
+            self.check_indirect_stride(stride, command_size)?;
+        }
+        let size = stride as DeviceSize * (draw_count - 1) as DeviceSize
+            + command_size as DeviceSize;
+        let handle = self.register_indirect_buffer(buffer, offset, size, "indirect")?;
+        unsafe {
+            self.gpu.device().cmd_draw_indexed_indirect(
+                self.command_buffer,
+                handle, offset,
+                draw_count, stride,
+            );
+        }
+        Ok(())
+    }
+
+    /// Performs an indirect indexed draw call, where the draw count is read from `count_buffer`.
+    ///
+    /// This is only usable in a closure passed to [`begin_drawing_indexed`][1]. Only the
+    /// [`index type`][2] of the [`IndexedDrawInfo`] passed to [`begin_drawing_indexed`][1] is
+    /// used, though its index range determines the bound range of the index buffer.
+    ///
+    /// Both the argument buffer and the count buffer are automatically synchronized with previous
+    /// writes, which allows GPU-driven culling written by a compute pass.
+    ///
+    /// # Valid usage
+    /// - The [`draw indirect count`][3] device extension *must* be enabled.
+    /// - All [`dynamic states`][4] of the currently bound [`pipeline`][5] *must* be defined before
+    ///   performing any draw calls.
+    /// - `buffer` and `count_buffer` *must* be valid [`BufferIds`][6].
+    /// - `buffer` and `count_buffer` *must* have been created with
+    ///   [`BufferUsages::INDIRECT_BUFFER`] bit set.
+    /// - `offset` and `count_offset` *must* be multiples of 4.
+    /// - `stride` *must* be a multiple of 4 and greater than or equal to the size of
+    ///   [`vk::DrawIndexedIndirectCommand`].
+    /// - `offset` + `stride` * (`max_draw_count` - 1) + the size of
+    ///   [`vk::DrawIndexedIndirectCommand`] *must* be less than or equal to the buffer's size.
+    /// - `count_offset` + 4 *must* be less than or equal to the count buffer's size.
+    /// - Indices read by each command *must* be within the bound index buffer range.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawIndexedIndirectCount.html>
+    ///
+    /// [1]: Self::begin_drawing_indexed
+    /// [2]: IndexedDrawInfo::index_type
+    /// [3]: ext::draw_indirect_count
+    /// [4]: DynamicState
+    /// [5]: GraphicsPipeline
+    /// [6]: BufferId
+    pub fn draw_indexed_indirect_count(
+        &mut self,
+        buffer: BufferId,
+        offset: DeviceSize,
+        count_buffer: BufferId,
+        count_offset: DeviceSize,
+        max_draw_count: u32,
+        stride: u32,
+    ) -> Result<()>
+        where State: state::CanDrawIndexed
+    {
+        let device = self.indirect_draw_count_device()?;
+        let command_size = size_of::<vk::DrawIndexedIndirectCommand>() as u32;
+        self.check_indirect_stride(stride, command_size)?;
+        if max_draw_count == 0 {
+            return Ok(())
+        }
+        let size = stride as DeviceSize * (max_draw_count - 1) as DeviceSize
+            + command_size as DeviceSize;
+        let handle = self.register_indirect_buffer(buffer, offset, size, "indirect")?;
+        let count_handle = self.register_indirect_buffer(
+            count_buffer, count_offset, size_of::<u32>() as DeviceSize, "count",
+        )?;
+        unsafe {
+            device.cmd_draw_indexed_indirect_count(
+                self.command_buffer,
+                handle, offset,
+                count_handle, count_offset,
+                max_draw_count, stride,
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the first shader stage of the currently bound mesh shader pipeline.
+    fn mesh_wait_scope(&self) -> vk::PipelineStageFlags2 {
+        if self.pipeline.shader_stages().contains(ShaderStageFlags::TASK) {
+            vk::PipelineStageFlags2::TASK_SHADER_EXT
+        } else {
+            vk::PipelineStageFlags2::MESH_SHADER_EXT
+        }
+    }
+
+    /// Performs a mesh shader draw call, launching a grid of task shader workgroups, or mesh
+    /// shader workgroups if the currently bound [`pipeline`][1] has no task shader.
+    ///
+    /// This replaces [`begin_drawing`][2] and [`begin_drawing_indexed`][3] for pipelines with a
+    /// mesh shader, which have no vertex input.
+    ///
+    /// # Valid usage
+    /// - The [`mesh shader`][4] device extension *must* be enabled.
+    /// - The currently bound [`pipeline`][1] *must* have a mesh shader.
+    /// - All [`dynamic states`][5] of the currently bound [`pipeline`][1] *must* be defined before
+    ///   performing any draw calls.
+    /// - `group_count_x`, `group_count_y` and `group_count_z` *must* each be less than or equal
+    ///   to the matching [`max task work group count`][8] if the pipeline has a task shader and
+    ///   [`max mesh work group count`][9] otherwise.
+    /// - The product of `group_count_x`, `group_count_y` and `group_count_z` *must* be less than
+    ///   or equal to [`max task work group total count`][6] if the pipeline has a task shader and
+    ///   [`max mesh work group total count`][7] otherwise.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawMeshTasksEXT.html>
+    ///
+    /// [1]: GraphicsPipeline
+    /// [2]: Self::begin_drawing
+    /// [3]: Self::begin_drawing_indexed
+    /// [4]: ext::mesh_shader
+    /// [5]: DynamicState
+    /// [6]: ext::mesh_shader::Attributes::MAX_TASK_WORK_GROUP_TOTAL_COUNT
+    /// [7]: ext::mesh_shader::Attributes::MAX_MESH_WORK_GROUP_TOTAL_COUNT
+    /// [8]: ext::mesh_shader::Attributes::MAX_TASK_WORK_GROUP_COUNT_X
+    /// [9]: ext::mesh_shader::Attributes::MAX_MESH_WORK_GROUP_COUNT_X
+    pub fn draw_mesh_tasks(
+        &mut self,
+        group_count_x: u32,
+        group_count_y: u32,
+        group_count_z: u32,
+    ) -> Result<()>
+        where State: state::CanBeginDraw
+    {
+        use mesh_shader::Attributes;
+        let device = self.mesh_shader_device()?;
+        let (max_total_count, max_counts) =
+            if self.pipeline.shader_stages().contains(ShaderStageFlags::TASK) {
+                (Attributes::MAX_TASK_WORK_GROUP_TOTAL_COUNT, [
+                    Attributes::MAX_TASK_WORK_GROUP_COUNT_X,
+                    Attributes::MAX_TASK_WORK_GROUP_COUNT_Y,
+                    Attributes::MAX_TASK_WORK_GROUP_COUNT_Z,
+                ])
+            } else {
+                (Attributes::MAX_MESH_WORK_GROUP_TOTAL_COUNT, [
+                    Attributes::MAX_MESH_WORK_GROUP_COUNT_X,
+                    Attributes::MAX_MESH_WORK_GROUP_COUNT_Y,
+                    Attributes::MAX_MESH_WORK_GROUP_COUNT_Z,
+                ])
+            };
+        for (axis, count, name) in [
+            ("x", group_count_x, max_counts[0]),
+            ("y", group_count_y, max_counts[1]),
+            ("z", group_count_z, max_counts[2]),
+        ] {
+            let max_count = self.gpu
+                .get_device_attribute(name)
+                .u32().unwrap_or(0);
+            if count > max_count {
+                return Err(Error::just_context(format!(
+                    "mesh task group count {axis} {count} is greater than max work group count {max_count}"
+                )))
+            }
+        }
+        let max_total_count = self.gpu
+            .get_device_attribute(max_total_count)
+            .u32().unwrap_or(0) as u64;
+        let total_count = group_count_x as u64 * group_count_y as u64 * group_count_z as u64;
+        if total_count > max_total_count {
+            return Err(Error::just_context(format!(
+                "{}{}",
+                format_args!("mesh task group count {group_count_x}x{group_count_y}x{group_count_z} "),
+                format_args!("is greater than max work group total count {max_total_count}"),
+            )))
+        }
+        let wait_scope = self.mesh_wait_scope();
+        *self.wait_scope |= wait_scope;
+        unsafe {
+            device.cmd_draw_mesh_tasks(
+                self.command_buffer,
+                group_count_x,
+                group_count_y,
+                group_count_z,
+            );
+        }
+        Ok(())
+    }
+
+    /// Performs an indirect mesh shader draw call, reading `draw_count`
+    /// [`vk::DrawMeshTasksIndirectCommandEXT`] structures from `buffer`.
+    ///
+    /// The argument buffer is automatically synchronized with previous writes, e.g. by a compute
+    /// pass.
+    ///
+    /// # Valid usage
+    /// - The [`mesh shader`][1] device extension *must* be enabled.
+    /// - The currently bound [`pipeline`][2] *must* have a mesh shader.
+    /// - All [`dynamic states`][3] of the currently bound [`pipeline`][2] *must* be defined before
+    ///   performing any draw calls.
+    /// - `buffer` *must* be a valid [`BufferId`].
+    /// - `buffer` *must* have been created with [`BufferUsages::INDIRECT_BUFFER`] bit set.
+    /// - `offset` *must* be a multiple of 4.
+    /// - If `draw_count` is greater than one, the [`multi draw indirect`][4] feature *must* be
+    ///   enabled, and `stride` *must* be a multiple of 4 and greater than or equal to the size
+    ///   of [`vk::DrawMeshTasksIndirectCommandEXT`].
+    /// - `offset` + `stride` * (`draw_count` - 1) + the size of
+    ///   [`vk::DrawMeshTasksIndirectCommandEXT`] *must* be less than or equal to the buffer's size.
+    /// - The group counts of each command in the buffer *must* each be less than or equal to the
+    ///   matching [`max task work group count`][6] if the pipeline has a task shader and
+    ///   [`max mesh work group count`][7] otherwise, and their product *must* satisfy the total
+    ///   count limits of [`draw_mesh_tasks`][5].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawMeshTasksIndirectEXT.html>
+    ///
+    /// [1]: ext::mesh_shader
+    /// [2]: GraphicsPipeline
+    /// [3]: DynamicState
+    /// [4]: BaseDeviceFeatures::multi_draw_indirect
+    /// [5]: Self::draw_mesh_tasks
+    /// [6]: ext::mesh_shader::Attributes::MAX_TASK_WORK_GROUP_COUNT_X
+    /// [7]: ext::mesh_shader::Attributes::MAX_MESH_WORK_GROUP_COUNT_X
+    pub fn draw_mesh_tasks_indirect(
+        &mut self,
+        buffer: BufferId,
+        offset: DeviceSize,
+        draw_count: u32,
+        stride: u32,
+    ) -> Result<()>
+        where State: state::CanBeginDraw
+    {
+        let device = self.mesh_shader_device()?;
+        if draw_count == 0 {
+            return Ok(())
+        }
+        let command_size = size_of::<vk::DrawMeshTasksIndirectCommandEXT>() as u32;
+        if draw_count > 1 {
+            if !self.gpu.enabled_base_features().multi_draw_indirect {
+                return Err(Error::just_context(format!(
+                    "draw count {draw_count} is greater than one, but multi draw indirect is not enabled"
+                )))
+            }
+            self.check_indirect_stride(stride, command_size)?;
+        }
+        let size = stride as DeviceSize * (draw_count - 1) as DeviceSize
+            + command_size as DeviceSize;
+        let handle = self.register_indirect_buffer(buffer, offset, size, "indirect")?;
+        let wait_scope = self.mesh_wait_scope();
+        *self.wait_scope |= wait_scope;
+        unsafe {
+            device.cmd_draw_mesh_tasks_indirect(
+                self.command_buffer,
+                handle, offset,
+                draw_count, stride,
+            );
+        }
+        Ok(())
+    }
+
+    /// Performs an indirect mesh shader draw call, where the draw count is read from
+    /// `count_buffer`.
+    ///
+    /// Both the argument buffer and the count buffer are automatically synchronized with previous
+    /// writes, which allows GPU-driven meshlet culling written by a compute pass.
+    ///
+    /// # Valid usage
+    /// - The [`mesh shader`][1] and [`draw indirect count`][2] device extensions *must* be
+    ///   enabled.
+    /// - The currently bound [`pipeline`][3] *must* have a mesh shader.
+    /// - All [`dynamic states`][4] of the currently bound [`pipeline`][3] *must* be defined before
+    ///   performing any draw calls.
+    /// - `buffer` and `count_buffer` *must* be valid [`BufferIds`][5].
+    /// - `buffer` and `count_buffer` *must* have been created with
+    ///   [`BufferUsages::INDIRECT_BUFFER`] bit set.
+    /// - `offset` and `count_offset` *must* be multiples of 4.
+    /// - `stride` *must* be a multiple of 4 and greater than or equal to the size of
+    ///   [`vk::DrawMeshTasksIndirectCommandEXT`].
+    /// - `offset` + `stride` * (`max_draw_count` - 1) + the size of
+    ///   [`vk::DrawMeshTasksIndirectCommandEXT`] *must* be less than or equal to the buffer's size.
+    /// - `count_offset` + 4 *must* be less than or equal to the count buffer's size.
+    /// - The group counts of each command in the buffer *must* each be less than or equal to the
+    ///   matching [`max task work group count`][7] if the pipeline has a task shader and
+    ///   [`max mesh work group count`][8] otherwise, and their product *must* satisfy the total
+    ///   count limits of [`draw_mesh_tasks`][6].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawMeshTasksIndirectCountEXT.html>
+    ///
+    /// [1]: ext::mesh_shader
+    /// [2]: ext::draw_indirect_count
+    /// [3]: GraphicsPipeline
+    /// [4]: DynamicState
+    /// [5]: BufferId
+    /// [6]: Self::draw_mesh_tasks
+    /// [7]: ext::mesh_shader::Attributes::MAX_TASK_WORK_GROUP_COUNT_X
+    /// [8]: ext::mesh_shader::Attributes::MAX_MESH_WORK_GROUP_COUNT_X
+    pub fn draw_mesh_tasks_indirect_count(
+        &mut self,
+        buffer: BufferId,
+        offset: DeviceSize,
+        count_buffer: BufferId,
+        count_offset: DeviceSize,
+        max_draw_count: u32,
+        stride: u32,
+    ) -> Result<()>
+        where State: state::CanBeginDraw
+    {
+        let device = self.mesh_shader_device()?;
+        if !self.gpu
+            .get_device_attribute(draw_indirect_count::Attributes::IS_ENABLED)
+            .bool().unwrap_or(false)
+        {
+            return Err(Error::just_context(
+                "draw indirect count device extension is not enabled"
+            ))
+        }
+        let command_size = size_of::<vk::DrawMeshTasksIndirectCommandEXT>() as u32;
+        self.check_indirect_stride(stride, command_size)?;
+        if max_draw_count == 0 {
+            return Ok(())
+        }
+        let size = stride as DeviceSize * (max_draw_count - 1) as DeviceSize
+            + command_size as DeviceSize;
+        let handle = self.register_indirect_buffer(buffer, offset, size, "indirect")?;
+        let count_handle = self.register_indirect_buffer(
+            count_buffer, count_offset, size_of::<u32>() as DeviceSize, "count",
+        )?;
+        let wait_scope = self.mesh_wait_scope();
+        *self.wait_scope |= wait_scope;
+        unsafe {
+            device.cmd_draw_mesh_tasks_indirect_count(
+                self.command_buffer,
+                handle, offset,
+                count_handle, count_offset,
+                max_draw_count, stride,
+            );
+        }
+        Ok(())
+    }
 }
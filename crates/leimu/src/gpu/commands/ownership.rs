@@ -0,0 +1,192 @@
+use nox_ash::vk;
+use nox_mem::{
+    vec::FixedVec32,
+    alloc::LocalAlloc,
+    arena,
+};
+
+use crate::{
+    gpu::prelude::*,
+    error::*,
+};
+
+/// A wrapper around a [`command buffer`][1], which transfers the ownership of resources to the
+/// queue family of its queue.
+///
+/// Available through [`CommandScheduler::transfer_ownership`] or with [`NewOwnershipCommands`].
+///
+/// The scheduler inserts matching release and acquire barriers automatically, when a command
+/// uses a resource last used on another queue family. These commands *can* be used to control
+/// when the transfer happens, e.g. to move a resource uploaded on a dedicated transfer queue to a
+/// graphics queue ahead of the commands using it.
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/spec/latest/chapters/synchronization.html#synchronization-queue-transfers>
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkCommandBuffer.html
+pub struct OwnershipCommands<'a, 'b> {
+    gpu: Gpu,
+    recorder: CommandRecorder<'a, 'b>,
+    queue: DeviceQueue,
+    command_buffer: vk::CommandBuffer,
+    command_id: CommandId,
+}
+
+pub struct NewOwnershipCommands;
+
+impl NewCommands for NewOwnershipCommands {
+
+    const NAME: &'static str = "ownership commands";
+
+    type Target<'a, 'b> = OwnershipCommands<'a, 'b>;
+
+    fn new<'a, 'b>(
+        mut recorder: CommandRecorder<'a, 'b>,
+        command_id: CommandId,
+        queue: DeviceQueue,
+    ) -> Result<Self::Target<'a, 'b>>
+        where Self::Target<'a, 'b>: Commands<'a, 'b>
+    {
+        let command_buffer = recorder
+            .get_current_worker()
+            .allocate_primaries(&queue, 1)?[0];
+        let begin_info = vk::CommandBufferBeginInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
+            flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+            ..Default::default()
+        };
+        let gpu = recorder.gpu().clone();
+        unsafe {
+            gpu.device()
+                .begin_command_buffer(command_buffer, &begin_info)
+                .context("failed to begin primary command buffer")?;
+        }
+        Ok(OwnershipCommands {
+            gpu,
+            recorder,
+            queue,
+            command_buffer,
+            command_id,
+        })
+    }
+}
+
+unsafe impl<'a, 'b> Commands<'a, 'b> for OwnershipCommands<'a, 'b> {
+
+    fn add_signal_semaphore(
+        &mut self,
+        semaphore_id: TimelineSemaphoreId,
+        value: u64,
+    ) {
+        self.recorder.add_signal_semaphore(self.command_id, semaphore_id, value);
+    }
+
+    fn add_wait_semaphore(
+        &mut self,
+        semaphore_id: TimelineSemaphoreId,
+        value: u64,
+        dependency_hint: MemoryDependencyHint,
+    ) {
+        self.recorder.add_wait_semaphore(
+            self.command_id, semaphore_id,
+            value, dependency_hint
+        );
+    }
+
+    fn finish<'c, Alloc>(self, alloc: &'c Alloc) -> Result<CommandResult<'c, Alloc>>
+        where Alloc: ?Sized + LocalAlloc<Error = arena::Error>
+    {
+        unsafe {
+            self.gpu.device()
+                .end_command_buffer(self.command_buffer)
+                .context("failed to end command buffer")?;
+        }
+        let mut primary_command_buffers = FixedVec32
+            ::with_capacity(1, alloc)
+            .context("alloc failed")?;
+        primary_command_buffers.push(self.command_buffer);
+        Ok(CommandResult {
+            primary_command_buffers,
+            wait_scope: vk::PipelineStageFlags2::ALL_COMMANDS,
+            signal_scope: vk::PipelineStageFlags2::ALL_COMMANDS,
+            queue: self.queue,
+            sparse_binds: None,
+        })
+    }
+}
+
+impl<'a, 'b> OwnershipCommands<'a, 'b> {
+
+    /// Acquires the ownership of every range of a buffer for the queue family of the commands.
+    ///
+    /// The release half of the transfer is recorded on the queue, which last used the buffer.
+    ///
+    /// # Valid usage
+    /// - `buffer_id` *must* be a valid [`BufferId`].
+    pub fn acquire_buffer(&mut self, buffer_id: BufferId) -> Result<()> {
+        let cache = unsafe { &mut *self.recorder.cache().get() };
+        let queue_family_index = self.queue.family_index();
+        self.recorder.write_resources(|guard| {
+            let buffer = guard.register_buffer(buffer_id)?;
+            let range = buffer.ownership_barrier(
+                queue_family_index,
+                &mut cache.shader_resource_cache.buffer_memory_barrier_cache,
+            );
+            if !range.is_empty() {
+                let tmp_alloc = self.gpu.tmp_alloc();
+                let tmp_alloc = tmp_alloc.guard();
+                let memory_barriers = cache.shader_resource_cache.buffer_memory_barrier_cache.flush(
+                    &[range], &tmp_alloc
+                )?;
+                let dependency_info = vk::DependencyInfo {
+                    buffer_memory_barrier_count: memory_barriers.len(),
+                    p_buffer_memory_barriers: memory_barriers.as_ptr(),
+                    ..Default::default()
+                };
+                unsafe {
+                    self.gpu.device()
+                    .cmd_pipeline_barrier2(self.command_buffer, &dependency_info);
+                }
+            }
+            Ok(())
+        }).context_with(|| format!("failed to acquire buffer {buffer_id}"))
+    }
+
+    /// Acquires the ownership of every subresource of an image for the queue family of the
+    /// commands.
+    ///
+    /// The layouts of the subresources are preserved and the release half of the transfer is
+    /// recorded on the queue, which last used the image.
+    ///
+    /// # Valid usage
+    /// - `image_id` *must* be a valid [`ImageId`].
+    pub fn acquire_image(&mut self, image_id: ImageId) -> Result<()> {
+        let cache = unsafe { &mut *self.recorder.cache().get() };
+        let queue_family_index = self.queue.family_index();
+        let command_index = self.command_id.index();
+        self.recorder.write_resources(|guard| {
+            let image = guard.register_image(image_id.slot_index(), command_index)?;
+            let range = image.ownership_barrier(
+                queue_family_index,
+                &mut cache.shader_resource_cache.image_memory_barrier_cache,
+            );
+            if !range.is_empty() {
+                let tmp_alloc = self.gpu.tmp_alloc();
+                let tmp_alloc = tmp_alloc.guard();
+                let memory_barriers = cache.shader_resource_cache.image_memory_barrier_cache.flush(
+                    &[range], &tmp_alloc
+                )?;
+                let dependency_info = vk::DependencyInfo {
+                    image_memory_barrier_count: memory_barriers.len(),
+                    p_image_memory_barriers: memory_barriers.as_ptr(),
+                    ..Default::default()
+                };
+                unsafe {
+                    self.gpu.device()
+                    .cmd_pipeline_barrier2(self.command_buffer, &dependency_info);
+                }
+            }
+            Ok(())
+        }).context_with(|| format!("failed to acquire image {image_id}"))
+    }
+}
@@ -1,8 +1,9 @@
 use nox_mem::{
     vec::{FixedVec32, NonNullVec32},
     alloc::LocalAlloc,
-    arena,
+    arena::{self, ArenaGuard},
     option::OptionExt,
+    conditional::True,
 };
 use nox_ash::vk;
 
@@ -88,14 +89,257 @@ impl<'a, 'b> PipelineCommands<'a, 'b> {
         barrier_infos: &[BindingBarrierInfo],
     ) -> Result<&mut Self>
     {
-        {
+        if self.pipeline.shader_set().uses_descriptor_buffers() {
+            return self.bind_descriptor_buffers(first_set, sets, barrier_infos)
+        }
+        if sets.is_empty() {
+            return Ok(self)
+        }
+        let tmp_alloc = self.gpu.tmp_alloc();
+        let tmp_alloc = tmp_alloc.guard();
+        let layout = self.pipeline.shader_set().pipeline_layout();
+        let mut descriptor_sets = FixedVec32::with_capacity(
+            sets.len() as u32, &tmp_alloc,
+        ).context("alloc failed")?;
+        let stage_flags = self.validate_descriptor_sets(
+            first_set, sets, &tmp_alloc,
+            |pool, set_id| {
+                descriptor_sets.push(pool.get_descriptor_set(set_id)?.handle());
+                Ok(())
+            },
+        )?;
+        self.record_descriptor_set_bind(sets, barrier_infos)?;
+        unsafe {
+            let info = vk::BindDescriptorSetsInfo {
+                stage_flags,
+                layout,
+                first_set,
+                descriptor_set_count: descriptor_sets.len(),
+                p_descriptor_sets: descriptor_sets.as_ptr(),
+                ..Default::default()
+            };
+            self.gpu.device().cmd_bind_descriptor_sets2(
+                self.command_buffer,
+                &info,
+            );
+        }
+        Ok(self)
+    }
+
+    /// Binds [`descriptor sets`][1] stored in descriptor buffers for use in subsequent draw
+    /// calls.
+    ///
+    /// This is used instead of [`bind_descriptor_sets`][2] when the [`descriptor_buffer`][3]
+    /// extension is enabled, in which case [`bind_descriptor_sets`][2] forwards to this.
+    ///
+    /// The descriptor buffers of the [`descriptor pools`][4] the sets were allocated from are
+    /// bound, if they aren't already bound, and the descriptor buffer offsets of each set are set
+    /// from `first_set` to `first_set` + `sets.len()` for the currently bound [`pipeline`][5].
+    /// Binding descriptor buffers invalidates the offsets of sets bound by earlier calls, so
+    /// those offsets are set again when a new descriptor buffer gets bound.
+    ///
+    /// # Valid usage
+    /// - The [`shader set`][6] of the currently bound [`pipeline`][5] *must*
+    ///   [`use descriptor buffers`][7].
+    /// - The number of distinct [`descriptor pools`][4] used within the same pipeline scope
+    ///   *must* not exceed [`MAX_DESCRIPTOR_BUFFER_BINDINGS`][8].
+    /// - The number of those pools holding samplers or combined image samplers *must* not exceed
+    ///   [`MAX_SAMPLER_DESCRIPTOR_BUFFER_BINDINGS`][9], and the number of those pools holding
+    ///   other descriptor types *must* not exceed [`MAX_RESOURCE_DESCRIPTOR_BUFFER_BINDINGS`][10].
+    ///   Keeping samplers in a pool of their own keeps the number of sampler descriptor buffers
+    ///   to one.
+    /// - All other valid usage of [`bind_descriptor_sets`][2] applies.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBindDescriptorBuffersEXT.html>
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdSetDescriptorBufferOffsets2EXT.html>
+    ///
+    /// [1]: DescriptorSetId
+    /// [2]: PipelineCommands::bind_descriptor_sets
+    /// [3]: ext::descriptor_buffer
+    /// [4]: Gpu::create_descriptor_pool
+    /// [5]: PipelineHandle
+    /// [6]: ShaderSet
+    /// [7]: ShaderSet::uses_descriptor_buffers
+    /// [8]: ext::descriptor_buffer::Attributes::MAX_DESCRIPTOR_BUFFER_BINDINGS
+    /// [9]: ext::descriptor_buffer::Attributes::MAX_SAMPLER_DESCRIPTOR_BUFFER_BINDINGS
+    /// [10]: ext::descriptor_buffer::Attributes::MAX_RESOURCE_DESCRIPTOR_BUFFER_BINDINGS
+    pub fn bind_descriptor_buffers(
+        &mut self,
+        first_set: u32,
+        sets: &[DescriptorSetId],
+        barrier_infos: &[BindingBarrierInfo],
+    ) -> Result<&mut Self>
+    {
+        let Some(device) = self.cache.descriptor_buffer_device.clone() else {
+            return Err(Error::just_context(
+                "descriptor buffer device extension not enabled"
+            ))
+        };
+        let shader_set = self.pipeline.shader_set();
+        if !shader_set.uses_descriptor_buffers() {
+            return Err(Error::just_context(
+                "the shader set of the bound pipeline doesn't use descriptor buffers"
+            ))
+        }
         if sets.is_empty() {
             return Ok(self)
         }
         let tmp_alloc = self.gpu.tmp_alloc();
         let tmp_alloc = tmp_alloc.guard();
+        let layout = shader_set.pipeline_layout();
+        let n_sets = sets.len() as u32;
+        let mut buffer_indices = FixedVec32
+            ::with_capacity(n_sets, &tmp_alloc)
+            .context("alloc failed")?;
+        let mut offsets = FixedVec32
+            ::with_capacity(n_sets, &tmp_alloc)
+            .context("alloc failed")?;
+        let bound = &mut self.cache.descriptor_buffers;
+        if bound.command_buffer != self.command_buffer {
+            bound.command_buffer = self.command_buffer;
+            bound.buffers.clear();
+            bound.sets.clear();
+        }
+        // on error the taken buffers and sets are dropped, which forces a rebind on the next call
+        let mut buffers = core::mem::take(&mut bound.buffers);
+        let mut bound_sets = core::mem::take(&mut bound.sets);
+        let n_bound = buffers.len();
+        let stage_flags = self.validate_descriptor_sets(
+            first_set, sets, &tmp_alloc,
+            |pool, set_id| {
+                let offset = pool.get_descriptor_set(set_id)?.descriptor_buffer_offset();
+                let Some(buffer) = &pool.descriptor_buffer else {
+                    return Err(Error::just_context(format!(
+                        "descriptor set {set_id} was not allocated from a descriptor buffer backed pool"
+                    )))
+                };
+                let info = buffer.binding_info();
+                let index = buffers
+                    .iter()
+                    .position(|&(address, _)| address == info.address)
+                    .map(|index| index as u32)
+                    .unwrap_or_else(|| {
+                        buffers.push((info.address, info.usage));
+                        buffers.len() - 1
+                    });
+                buffer_indices.push(index);
+                offsets.push(offset);
+                Ok(())
+            },
+        )?;
+        if buffers.len() != n_bound {
+            use ext::descriptor_buffer::Attributes;
+            let count = buffers.len();
+            let sampler_count = buffers
+                .iter()
+                .filter(|(_, usage)| usage.contains(vk::BufferUsageFlags::SAMPLER_DESCRIPTOR_BUFFER_EXT))
+                .count() as u32;
+            let resource_count = buffers
+                .iter()
+                .filter(|(_, usage)| usage.contains(vk::BufferUsageFlags::RESOURCE_DESCRIPTOR_BUFFER_EXT))
+                .count() as u32;
+            for (kind, count, name) in [
+                ("", count, Attributes::MAX_DESCRIPTOR_BUFFER_BINDINGS),
+                ("sampler ", sampler_count, Attributes::MAX_SAMPLER_DESCRIPTOR_BUFFER_BINDINGS),
+                ("resource ", resource_count, Attributes::MAX_RESOURCE_DESCRIPTOR_BUFFER_BINDINGS),
+            ] {
+                let max_bindings = self.gpu
+                    .get_device_attribute(name)
+                    .u32().unwrap_or(0);
+                if count > max_bindings {
+                    return Err(Error::just_context(format!(
+                        "{}{}",
+                        format_args!("binding {count} {kind}descriptor buffers exceeds the max {kind}"),
+                        format_args!("descriptor buffer binding count {max_bindings}"),
+                    )))
+                }
+            }
+            let mut binding_infos = FixedVec32
+                ::with_capacity(count, &tmp_alloc)
+                .context("alloc failed")?;
+            binding_infos.extend(buffers
+                .iter()
+                .map(|&(address, usage)| vk::DescriptorBufferBindingInfoEXT {
+                    s_type: vk::StructureType::DESCRIPTOR_BUFFER_BINDING_INFO_EXT,
+                    address,
+                    usage,
+                    ..Default::default()
+                })
+            );
+            unsafe {
+                device.cmd_bind_descriptor_buffers(self.command_buffer, &binding_infos);
+            }
+            // binding invalidated the offsets of the sets bound before this call
+            for (set, bound_set) in bound_sets.iter().enumerate() {
+                let set = set as u32;
+                let Some(bound_set) = bound_set else {
+                    continue
+                };
+                if (first_set..first_set + n_sets).contains(&set) {
+                    continue
+                }
+                let info = vk::SetDescriptorBufferOffsetsInfoEXT {
+                    s_type: vk::StructureType::SET_DESCRIPTOR_BUFFER_OFFSETS_INFO_EXT,
+                    stage_flags: bound_set.stage_flags,
+                    layout: bound_set.layout,
+                    first_set: set,
+                    set_count: 1,
+                    p_buffer_indices: &bound_set.buffer_index,
+                    p_offsets: &bound_set.offset,
+                    ..Default::default()
+                };
+                unsafe {
+                    device.cmd_set_descriptor_buffer_offsets2(self.command_buffer, &info);
+                }
+            }
+        }
+        let sets_end = first_set + n_sets;
+        if bound_sets.len() < sets_end {
+            bound_sets.resize(sets_end, None);
+        }
+        for (i, (&buffer_index, &offset)) in buffer_indices.iter().zip(offsets.iter()).enumerate() {
+            bound_sets[first_set as usize + i] = Some(BoundDescriptorBufferSet {
+                layout,
+                stage_flags,
+                buffer_index,
+                offset,
+            });
+        }
+        self.cache.descriptor_buffers.buffers = buffers;
+        self.cache.descriptor_buffers.sets = bound_sets;
+        self.record_descriptor_set_bind(sets, barrier_infos)?;
+        unsafe {
+            let info = vk::SetDescriptorBufferOffsetsInfoEXT {
+                s_type: vk::StructureType::SET_DESCRIPTOR_BUFFER_OFFSETS_INFO_EXT,
+                stage_flags,
+                layout,
+                first_set,
+                set_count: n_sets,
+                p_buffer_indices: buffer_indices.as_ptr(),
+                p_offsets: offsets.as_ptr(),
+                ..Default::default()
+            };
+            device.cmd_set_descriptor_buffer_offsets2(self.command_buffer, &info);
+        }
+        Ok(self)
+    }
+
+    /// Validates `sets` against the descriptor set layouts of the bound pipeline starting from
+    /// `first_set` and calls `f` for each set.
+    ///
+    /// Returns the combined stage flags of the validated layouts.
+    fn validate_descriptor_sets<F>(
+        &self,
+        first_set: u32,
+        sets: &[DescriptorSetId],
+        tmp_alloc: &ArenaGuard<'_, True>,
+        mut f: F,
+    ) -> Result<vk::ShaderStageFlags>
+        where F: FnMut(&mut DescriptorPoolWriteGuard<'_>, DescriptorSetId) -> Result<()>
+    {
         let n_sets = sets.len() as u32;
-        let shader_set =    self.pipeline.shader_set();
+        let shader_set = self.pipeline.shader_set();
         let set_layouts = shader_set.descriptor_set_layouts();
         let sets_end = first_set + n_sets;
         if sets_end > set_layouts.len() as u32 {
@@ -105,12 +349,9 @@ impl<'a, 'b> PipelineCommands<'a, 'b> {
                 format_args!("pipeline shader set descriptor set count {}", set_layouts.len())
             )))
         }
-        let mut descriptor_sets = FixedVec32::with_capacity(
-            n_sets, &tmp_alloc,
-        ).context("alloc failed")?;
         let pools = self.gpu.get_descriptor_pools();
         let mut write_cache = FixedVec32
-            ::with_len_with(pools.capacity(), |_| None, &tmp_alloc)
+            ::with_len_with(pools.capacity(), |_| None, tmp_alloc)
             .context("alloc failed")?;
         let mut stage_flags = vk::ShaderStageFlags::empty();
         for (i, set_layout) in set_layouts[first_set as usize..sets_end as usize].iter().enumerate() {
@@ -153,35 +394,30 @@ impl<'a, 'b> PipelineCommands<'a, 'b> {
                     format_args!("with the descriptor set {set_id} bound"),
                 )))
             }
-            descriptor_sets.push(set.handle());
+            f(pool, set_id)?;
         }
+        Ok(stage_flags)
+    }
+
+    /// Records a descriptor set bind for shader resource tracking.
+    fn record_descriptor_set_bind(
+        &mut self,
+        sets: &[DescriptorSetId],
+        barrier_infos: &[BindingBarrierInfo],
+    ) -> Result<()>
+    {
         let mut barriers = NonNullVec32
             ::with_capacity(barrier_infos.len() as u32, self.alloc)
             .context("alloc failed")?
             .into_static();
         barriers.append(barrier_infos);
         let mut set_ids = NonNullVec32
-            ::with_capacity(n_sets, self.alloc)
+            ::with_capacity(sets.len() as u32, self.alloc)
             .context("alloc failed")?
             .into_static();
         set_ids.fast_append(sets);
         self.cache.descriptor_set_binds.push(DescriptorSetBindCall { sets: set_ids, barriers });
-        unsafe {
-            let info = vk::BindDescriptorSetsInfo {
-                stage_flags,
-                layout: shader_set.pipeline_layout(),
-                first_set,
-                descriptor_set_count: descriptor_sets.len(),
-                p_descriptor_sets: descriptor_sets.as_ptr(),
-                ..Default::default()
-            };
-            self.gpu.device().cmd_bind_descriptor_sets2(
-                self.command_buffer,
-                &info,
-            );
-        }
-        }
-        Ok(self)
+        Ok(())
     }
 
     /// Pushes individual [`descriptor bindings`][1] for use in subsequent draw calls.
@@ -211,6 +447,7 @@ impl<'a, 'b> PipelineCommands<'a, 'b> {
             (
                 FixedVec32<vk::DescriptorBufferInfo, _>,
                 FixedVec32<vk::DescriptorImageInfo, _>,
+                FixedVec32<vk::BufferView, _>,
             ),
             _
         >::with_capacity(bindings.len() as u32, &tmp_alloc)
@@ -224,6 +461,76 @@ impl<'a, 'b> PipelineCommands<'a, 'b> {
             let min_uniform_buffer_offset_alignment
                 = self.gpu.device_limits().min_uniform_buffer_offset_alignment();
             match layout_binding.descriptor_type {
+                DescriptorType::UniformTexelBuffer | DescriptorType::StorageTexelBuffer => {
+                    let Some(views) = binding.texel_buffer_infos() else {
+                        return Err(Error::just_context(format!(
+                            "expected texel buffer descriptors for binding {:?}",
+                            binding.binding
+                        )))
+                    };
+                    let n = views.len() as u32;
+                    if binding.starting_index + n > layout_binding.descriptor_count {
+                        return Err(Error::just_context(format!(
+                            "binding {:?} starting index {} + descriptor count {n} was more than layout descriptor count {}",
+                            binding.binding, binding.starting_index, layout_binding.descriptor_count,
+                        )))
+                    }
+                    let buffer_usage = layout_binding.descriptor_type
+                        .buffer_usage()
+                        .unwrap();
+                    let mut call = PushDescriptorBindingCall {
+                        barrier: binding.barrier_info,
+                        stage_flags: layout_binding.stage_flags,
+                        buffers: NonNullVec32
+                            ::with_capacity(n, self.alloc)
+                            .context("alloc failed")?
+                            .into_static(),
+                        images: Default::default(),
+                    };
+                    let mut texel_buffer_views = FixedVec32
+                        ::with_capacity(n, &tmp_alloc)
+                        .context("alloc failed")?;
+                    for (i, &view_id) in views.iter().enumerate() {
+                        let buffer = self.buffers
+                            .get(view_id.buffer_id())
+                            .context_with(|| format!(
+                                "failed to get buffer for binding {:?} at descriptor index {}",
+                                binding.binding, binding.starting_index + i as u32,
+                            ))?;
+                        if let Some(err) = buffer.validate_usage(buffer_usage) {
+                            return Err(Error::new(err, format!(
+                                "binding {:?} buffer usage mismatch at descriptor index {}",
+                                binding.binding, binding.starting_index + i as u32,
+                            )))
+                        }
+                        let view = buffer
+                            .get_view(view_id)
+                            .context_with(|| format!(
+                                "failed to get buffer view for binding {:?} at descriptor index {}",
+                                binding.binding, binding.starting_index + i as u32,
+                            ))?;
+                        call.buffers.push(DescriptorBufferInfo {
+                            buffer_id: view_id.buffer_id(),
+                            offset: view.range.offset,
+                            size: view.range.size,
+                        });
+                        texel_buffer_views.push(view.handle);
+                    }
+                    self.cache.push_descriptor_binding_cache.insert_writes(
+                        set,
+                        layout_binding.stage_flags,
+                        &[vk::WriteDescriptorSet {
+                            dst_binding: layout_binding.binding,
+                            dst_array_element: binding.starting_index,
+                            descriptor_count: texel_buffer_views.len(),
+                            p_texel_buffer_view: texel_buffer_views.as_ptr(),
+                            descriptor_type: layout_binding.descriptor_type.into(),
+                            ..Default::default()
+                        }],
+                    );
+                    infos.push((FixedVec32::new(&tmp_alloc), FixedVec32::new(&tmp_alloc), texel_buffer_views));
+                    self.cache.push_descriptor_binding_calls.push(call);
+                },
                 crate::buffer_descriptor_types!() => {
                     let Some(buffers) = binding.buffer_infos() else {
                         return Err(Error::just_context(format!(
@@ -293,7 +600,7 @@ impl<'a, 'b> PipelineCommands<'a, 'b> {
                             ..Default::default()
                         }],
                     );
-                    infos.push((buffer_infos, FixedVec32::new(&tmp_alloc)));
+                    infos.push((buffer_infos, FixedVec32::new(&tmp_alloc), FixedVec32::new(&tmp_alloc)));
                     self.cache.push_descriptor_binding_calls.push(call);
                 },
                 crate::image_descriptor_types!() => {
@@ -382,7 +689,7 @@ impl<'a, 'b> PipelineCommands<'a, 'b> {
                             ..Default::default()
                         }]
                     );
-                    infos.push((FixedVec32::new(&tmp_alloc), image_infos));
+                    infos.push((FixedVec32::new(&tmp_alloc), image_infos, FixedVec32::new(&tmp_alloc)));
                     self.cache.push_descriptor_binding_calls.push(call);
                 },
                 _ => return Err(Error::just_context(format!(
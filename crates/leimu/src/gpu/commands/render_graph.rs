@@ -0,0 +1,736 @@
+use nox_mem::{
+    vec::Vec32,
+    vec32,
+};
+
+use nox_proc::{Display, BuildStructure};
+
+use crate::{
+    gpu::prelude::*,
+    error::*,
+    sync::*,
+};
+
+/// The minimum size of a single [`device memory`][1] block used for transient images.
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+const TRANSIENT_BLOCK_SIZE: DeviceSize = 1 << 26;
+
+/// A handle to a logical image of a [`render graph`][1].
+///
+/// Only valid for the [`RenderGraphBuilder`] it was created with.
+///
+/// [1]: RenderGraph
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)] #[display("{0}")]
+pub struct GraphImage(u32);
+
+/// A handle to a logical buffer of a [`render graph`][1].
+///
+/// Only valid for the [`RenderGraphBuilder`] it was created with.
+///
+/// [1]: RenderGraph
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)] #[display("{0}")]
+pub struct GraphBuffer(u32);
+
+/// A handle to a pass of a [`render graph`][1].
+///
+/// [1]: RenderGraph
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)] #[display("{0}")]
+pub struct PassId(u32);
+
+/// Either a [`GraphImage`] or a [`GraphBuffer`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+pub enum GraphResource {
+    #[display("image {0}")]
+    Image(GraphImage),
+    #[display("buffer {0}")]
+    Buffer(GraphBuffer),
+}
+
+/// Describes a transient image, whose memory is owned by the [`render graph`][1].
+///
+/// The contents of transient images are *not* preserved between frames and the first access of a
+/// frame *should* be a write.
+///
+/// [1]: RenderGraph
+#[derive(Clone, Copy, PartialEq, Eq, Hash, BuildStructure)]
+pub struct TransientImageInfo {
+    /// Specifies the dimensions of the image.
+    pub dimensions: Dimensions,
+    /// Specifies the format of the image.
+    pub format: Format,
+    /// Specifies the usage of the image.
+    pub usage: ImageUsages,
+    /// Specifies the sample count of the image. The default is [`MsaaSamples::X1`].
+    #[default(MsaaSamples::X1)]
+    pub samples: MsaaSamples,
+    /// Specifies the array layer count of the image. The default is 1.
+    #[default(1)]
+    pub array_layers: u32,
+    /// Specifies the mip level count of the image. The default is 1.
+    #[default(1)]
+    pub mip_levels: u32,
+}
+
+impl TransientImageInfo {
+
+    #[inline]
+    pub fn new<D>(dimensions: D, format: Format, usage: ImageUsages) -> Self
+        where D: Into<Dimensions>
+    {
+        Self {
+            dimensions: dimensions.into(),
+            format,
+            usage,
+            ..Default::default()
+        }
+    }
+}
+
+/// A queue family ownership transfer between two passes, which access the same resource on
+/// different queue families.
+///
+/// The passes are connected by a [`CommandDependency`], so the command scheduler records the
+/// release and acquire barriers of the transfer automatically.
+#[derive(Clone, Copy, Debug)]
+pub struct QueueTransfer {
+    pub resource: GraphResource,
+    pub src_pass: PassId,
+    pub dst_pass: PassId,
+    pub src_queue_family_index: u32,
+    pub dst_queue_family_index: u32,
+}
+
+/// The physical resources of a [`render graph`][1], which are accessible while recording a pass.
+///
+/// [1]: RenderGraph
+pub struct GraphResources {
+    images: Vec32<(ImageId, Option<ImageViewId>)>,
+    buffers: Vec32<BufferId>,
+}
+
+impl GraphResources {
+
+    /// Gets the [`ImageId`] of a [`GraphImage`].
+    ///
+    /// # Panics
+    /// If `image` is not from the same [`RenderGraphBuilder`].
+    #[inline]
+    pub fn image(&self, image: GraphImage) -> ImageId {
+        self.images[image.0 as usize].0
+    }
+
+    /// Gets the whole range [`ImageViewId`] of a [`GraphImage`].
+    ///
+    /// Returns [`None`] if the image was imported, since imported images don't have a view owned
+    /// by the graph.
+    ///
+    /// # Panics
+    /// If `image` is not from the same [`RenderGraphBuilder`].
+    #[inline]
+    pub fn image_view(&self, image: GraphImage) -> Option<ImageViewId> {
+        self.images[image.0 as usize].1
+    }
+
+    /// Gets the [`BufferId`] of a [`GraphBuffer`].
+    ///
+    /// # Panics
+    /// If `buffer` is not from the same [`RenderGraphBuilder`].
+    #[inline]
+    pub fn buffer(&self, buffer: GraphBuffer) -> BufferId {
+        self.buffers[buffer.0 as usize]
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ImageSource {
+    Imported(ImageId),
+    Transient(u32),
+}
+
+#[derive(Clone, Copy)]
+struct Access {
+    resource: GraphResource,
+    write: bool,
+    hint: MemoryDependencyHint,
+}
+
+type RecordPassFn = Box<dyn for<'a, 'b> FnOnce(
+    &'a mut CommandScheduler<'b>,
+    Vec32<CommandDependency>,
+    Arc<GraphResources>,
+) -> Result<CommandId> + Send + Sync>;
+
+struct Pass {
+    name: Box<str>,
+    queue: DeviceQueue,
+    accesses: Vec32<Access>,
+    side_effects: bool,
+    record: RecordPassFn,
+}
+
+/// Used to declare the resource accesses of a pass.
+pub struct PassBuilder<'a> {
+    accesses: &'a mut Vec32<Access>,
+    side_effects: &'a mut bool,
+}
+
+impl<'a> PassBuilder<'a> {
+
+    /// Declares that the pass reads `image`.
+    ///
+    /// `hint` specifies where the read happens, see [`MemoryDependencyHint`].
+    #[inline]
+    pub fn read_image(&mut self, image: GraphImage, hint: MemoryDependencyHint) -> &mut Self {
+        self.accesses.push(Access {
+            resource: GraphResource::Image(image),
+            write: false,
+            hint,
+        });
+        self
+    }
+
+    /// Declares that the pass writes `image`.
+    ///
+    /// `hint` specifies where the write happens, see [`MemoryDependencyHint`].
+    #[inline]
+    pub fn write_image(&mut self, image: GraphImage, hint: MemoryDependencyHint) -> &mut Self {
+        self.accesses.push(Access {
+            resource: GraphResource::Image(image),
+            write: true,
+            hint,
+        });
+        self
+    }
+
+    /// Declares that the pass reads `buffer`.
+    ///
+    /// `hint` specifies where the read happens, see [`MemoryDependencyHint`].
+    #[inline]
+    pub fn read_buffer(&mut self, buffer: GraphBuffer, hint: MemoryDependencyHint) -> &mut Self {
+        self.accesses.push(Access {
+            resource: GraphResource::Buffer(buffer),
+            write: false,
+            hint,
+        });
+        self
+    }
+
+    /// Declares that the pass writes `buffer`.
+    ///
+    /// `hint` specifies where the write happens, see [`MemoryDependencyHint`].
+    #[inline]
+    pub fn write_buffer(&mut self, buffer: GraphBuffer, hint: MemoryDependencyHint) -> &mut Self {
+        self.accesses.push(Access {
+            resource: GraphResource::Buffer(buffer),
+            write: true,
+            hint,
+        });
+        self
+    }
+
+    /// Marks the pass as having side effects not visible to the graph, e.g. presenting to a
+    /// swapchain, which prevents it from being culled.
+    #[inline]
+    pub fn side_effects(&mut self) -> &mut Self {
+        *self.side_effects = true;
+        self
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct TransientKey {
+    info: TransientImageInfo,
+    first: u32,
+    last: u32,
+}
+
+#[derive(Clone, Copy)]
+struct TransientImage {
+    image_id: ImageId,
+    view_id: ImageViewId,
+    allocation: u32,
+}
+
+/// A render graph built on top of [`CommandScheduler`].
+///
+/// Each frame, passes are declared with a [`RenderGraphBuilder`] together with the images and
+/// buffers they read and write. When [`scheduled`][1], the graph
+/// - culls passes, which don't contribute to imported resources or have no side effects,
+/// - works out [`CommandDependency`] edges between passes from read-after-write,
+///   write-after-read and write-after-write hazards,
+/// - detects [`queue family ownership transfers`][2] and
+/// - allocates transient images, whose memory is aliased if their lifetimes don't overlap.
+///
+/// Passes are scheduled in declaration order, which is always a valid order for the edges.
+///
+/// Transient images are cached and only recreated if the transient images or their lifetimes
+/// change. Old transient images are destroyed once the frame they were last used in has finished.
+///
+/// Dropping the graph destroys its transient images immediately, so it *must* not be dropped while
+/// commands using them are pending.
+///
+/// [1]: RenderGraphBuilder::schedule
+/// [2]: QueueTransfer
+pub struct RenderGraph {
+    gpu: Gpu,
+    transient_keys: Vec32<Option<TransientKey>>,
+    transient_images: Vec32<Option<TransientImage>>,
+    binder: Option<AliasingBinder>,
+    retired: Vec32<(u64, Vec32<ImageId>)>,
+}
+
+impl RenderGraph {
+
+    /// Creates a new, empty render graph.
+    #[inline]
+    pub fn new(gpu: Gpu) -> Self {
+        Self {
+            gpu,
+            transient_keys: vec32![],
+            transient_images: vec32![],
+            binder: None,
+            retired: vec32![],
+        }
+    }
+
+    /// Begins declaring the passes of a frame.
+    #[inline]
+    pub fn begin(&mut self) -> RenderGraphBuilder<'_> {
+        RenderGraphBuilder {
+            graph: self,
+            images: vec32![],
+            transients: vec32![],
+            buffers: vec32![],
+            passes: vec32![],
+        }
+    }
+
+    /// Returns the total size of the memory currently allocated for transient images.
+    #[inline]
+    pub fn transient_memory_size(&self) -> DeviceSize {
+        self.binder
+            .as_ref()
+            .map(|binder| binder.memory_size())
+            .unwrap_or_default()
+    }
+
+    fn destroy_retired(&mut self, finished_frame: u64) -> Result<()> {
+        let mut finished = vec32![];
+        self.retired.retain_unordered_mut(|(frame, images)| {
+            if *frame <= finished_frame {
+                finished.append(images);
+                false
+            } else {
+                true
+            }
+        });
+        self.gpu.destroy_resources([], finished)
+            .context("failed to destroy transient images")
+    }
+
+    fn update_transients(
+        &mut self,
+        keys: Vec32<Option<TransientKey>>,
+        last_frame: u64,
+    ) -> Result<()>
+    {
+        if keys == self.transient_keys {
+            return Ok(())
+        }
+        let old: Vec32<ImageId> = core::mem::take(&mut self.transient_images)
+            .into_iter()
+            .flatten()
+            .map(|image| image.image_id)
+            .collect();
+        if !old.is_empty() {
+            self.retired.push((last_frame, old));
+        }
+        self.transient_keys.clear();
+        let binder = AliasingBinder::new(self.gpu.device().clone(), TRANSIENT_BLOCK_SIZE);
+        for (i, key) in keys.iter().enumerate() {
+            let Some(key) = key else {
+                self.transient_images.push(None);
+                continue
+            };
+            binder.set_lifetime(key.first, key.last);
+            let allocation = binder.allocation_count();
+            let mut image_id = Default::default();
+            let info = key.info;
+            self.gpu.create_resources([], [
+                ImageCreateInfo::new(&mut image_id, &binder)
+                    .with_dimensions(info.dimensions)
+                    .with_format(info.format, false)
+                    .with_usage(info.usage)
+                    .with_samples(info.samples)
+                    .with_array_layers(info.array_layers)
+                    .with_mip_levels(info.mip_levels)
+            ]).context_with(|| format!(
+                "failed to create transient image {i}"
+            ))?;
+            let view_id = self.gpu.create_image_view(
+                image_id,
+                ImageRange::whole_range(info.format.aspects()),
+            ).context_with(|| format!(
+                "failed to create view of transient image {i}"
+            ))?;
+            self.transient_images.push(Some(TransientImage {
+                image_id,
+                view_id,
+                allocation,
+            }));
+        }
+        self.transient_keys = keys;
+        self.binder = Some(binder);
+        Ok(())
+    }
+}
+
+impl Drop for RenderGraph {
+
+    fn drop(&mut self) {
+        let images = core::mem::take(&mut self.retired)
+            .into_iter()
+            .flat_map(|(_, images)| images)
+            .chain(core::mem::take(&mut self.transient_images)
+                .into_iter()
+                .flatten()
+                .map(|image| image.image_id)
+            );
+        self.gpu.destroy_resources([], images).ok();
+    }
+}
+
+/// Declares the passes and resources of a single frame of a [`RenderGraph`].
+pub struct RenderGraphBuilder<'a> {
+    graph: &'a mut RenderGraph,
+    images: Vec32<ImageSource>,
+    transients: Vec32<TransientImageInfo>,
+    buffers: Vec32<BufferId>,
+    passes: Vec32<Pass>,
+}
+
+/// The result of [`scheduling`][1] a render graph.
+///
+/// [1]: RenderGraphBuilder::schedule
+pub struct ScheduledRenderGraph {
+    command_ids: Vec32<Option<CommandId>>,
+    queue_transfers: Vec32<QueueTransfer>,
+}
+
+impl ScheduledRenderGraph {
+
+    /// Returns the [`CommandId`] of a pass or [`None`] if the pass was culled.
+    #[inline]
+    pub fn command_id(&self, pass: PassId) -> Option<CommandId> {
+        self.command_ids
+            .get(pass.0 as usize)
+            .copied()
+            .flatten()
+    }
+
+    /// Returns the queue family ownership transfers between scheduled passes.
+    #[inline]
+    pub fn queue_transfers(&self) -> &[QueueTransfer] {
+        &self.queue_transfers
+    }
+}
+
+#[derive(Default, Clone)]
+struct ResourceState {
+    last_writer: Option<u32>,
+    readers: Vec32<u32>,
+    last_queue: Option<(u32, u32)>,
+}
+
+fn add_edge(
+    edges: &mut [Vec32<(u32, MemoryDependencyHint)>],
+    src: u32,
+    dst: u32,
+    hint: MemoryDependencyHint,
+) {
+    if src == dst {
+        return
+    }
+    let edges = &mut edges[dst as usize];
+    if let Some((_, h)) = edges.iter_mut().find(|(pass, _)| *pass == src) {
+        *h |= hint;
+    } else {
+        edges.push((src, hint));
+    }
+}
+
+impl<'a> RenderGraphBuilder<'a> {
+
+    /// Imports an image, whose memory is *not* owned by the graph.
+    ///
+    /// Passes writing to imported images are never culled.
+    #[inline]
+    pub fn import_image(&mut self, image_id: ImageId) -> GraphImage {
+        self.images.push(ImageSource::Imported(image_id));
+        GraphImage(self.images.len() - 1)
+    }
+
+    /// Imports a buffer, whose memory is *not* owned by the graph.
+    ///
+    /// Passes writing to imported buffers are never culled.
+    #[inline]
+    pub fn import_buffer(&mut self, buffer_id: BufferId) -> GraphBuffer {
+        self.buffers.push(buffer_id);
+        GraphBuffer(self.buffers.len() - 1)
+    }
+
+    /// Creates a transient image, whose memory is owned by the graph.
+    ///
+    /// Transient images are only allocated if a scheduled pass accesses them.
+    #[inline]
+    pub fn create_transient_image(&mut self, info: TransientImageInfo) -> GraphImage {
+        self.transients.push(info);
+        self.images.push(ImageSource::Transient(self.transients.len() - 1));
+        GraphImage(self.images.len() - 1)
+    }
+
+    /// Adds a pass to the graph.
+    ///
+    /// `setup` declares the resources accessed by the pass with a [`PassBuilder`] and `record` is
+    /// used to record the commands of the pass, if it is not culled.
+    ///
+    /// Resource accesses *must* be declared in the order passes execute them, which is the order
+    /// they are added in.
+    pub fn add_pass<Cmd, F>(
+        &mut self,
+        name: &str,
+        queue: DeviceQueue,
+        setup: impl FnOnce(&mut PassBuilder<'_>),
+        record: F,
+    ) -> PassId
+        where
+            Cmd: NewCommands + 'static,
+            for<'b, 'c> Cmd::Target<'b, 'c>: Commands<'b, 'c>,
+            F: for<'b, 'c> FnOnce(
+                &mut Cmd::Target<'b, 'c>,
+                &GraphResources,
+            ) -> EventResult<()> + Send + Sync + 'static,
+    {
+        let mut accesses = vec32![];
+        let mut side_effects = false;
+        setup(&mut PassBuilder {
+            accesses: &mut accesses,
+            side_effects: &mut side_effects,
+        });
+        let record_queue = queue.clone();
+        let record: RecordPassFn = Box::new(move |scheduler, dependencies, resources| {
+            Ok(scheduler
+                .new_commands::<Cmd>(record_queue, move |cmd| record(cmd, &resources))?
+                .with_dependencies(dependencies)
+                .id()
+            )
+        });
+        self.passes.push(Pass {
+            name: name.into(),
+            queue,
+            accesses,
+            side_effects,
+            record,
+        });
+        PassId(self.passes.len() - 1)
+    }
+
+    #[inline]
+    fn is_imported(&self, resource: GraphResource) -> bool {
+        match resource {
+            GraphResource::Image(image) => matches!(
+                self.images[image.0 as usize], ImageSource::Imported(_)
+            ),
+            GraphResource::Buffer(_) => true,
+        }
+    }
+
+    #[inline]
+    fn resource_index(&self, resource: GraphResource) -> usize {
+        match resource {
+            GraphResource::Image(image) => image.0 as usize,
+            GraphResource::Buffer(buffer) => self.images.len() as usize + buffer.0 as usize,
+        }
+    }
+
+    /// Compiles the graph and schedules its passes with `scheduler`.
+    pub fn schedule(
+        self,
+        scheduler: &mut CommandScheduler<'_>,
+    ) -> Result<ScheduledRenderGraph>
+    {
+        for pass in &self.passes {
+            for access in &pass.accesses {
+                let valid = match access.resource {
+                    GraphResource::Image(image) => image.0 < self.images.len(),
+                    GraphResource::Buffer(buffer) => buffer.0 < self.buffers.len(),
+                };
+                if !valid {
+                    return Err(Error::just_context(format!(
+                        "invalid graph {} in pass {}", access.resource, pass.name,
+                    )))
+                }
+            }
+        }
+        let n_passes = self.passes.len();
+        let mut kept = vec32![false; n_passes];
+        for (i, pass) in self.passes.iter().enumerate() {
+            kept[i] = pass.side_effects || pass.accesses
+                .iter()
+                .any(|access| access.write && self.is_imported(access.resource));
+        }
+        for i in (0..n_passes as usize).rev() {
+            if !kept[i] {
+                continue
+            }
+            for access in self.passes[i].accesses.iter().filter(|access| !access.write) {
+                for j in 0..i {
+                    if self.passes[j].accesses
+                        .iter()
+                        .any(|other| other.write && other.resource == access.resource)
+                    {
+                        kept[j] = true;
+                    }
+                }
+            }
+        }
+        let n_resources = self.images.len() + self.buffers.len();
+        let mut states = vec32![ResourceState::default(); n_resources];
+        let mut edges: Vec32<Vec32<(u32, MemoryDependencyHint)>> =
+            vec32![Default::default(); n_passes];
+        let mut queue_transfers = vec32![];
+        let mut lifetimes: Vec32<Option<(u32, u32, DeviceQueue)>> =
+            vec32![None; self.transients.len()];
+        let mut aliasable = vec32![true; self.transients.len()];
+        for (i, pass) in self.passes.iter().enumerate() {
+            if !kept[i] {
+                continue
+            }
+            let i = i as u32;
+            let family_index = pass.queue.family_index();
+            for access in &pass.accesses {
+                let state = &mut states[self.resource_index(access.resource)];
+                if let Some((src_pass, src_family)) = state.last_queue &&
+                    src_pass != i && src_family != family_index
+                {
+                    queue_transfers.push(QueueTransfer {
+                        resource: access.resource,
+                        src_pass: PassId(src_pass),
+                        dst_pass: PassId(i),
+                        src_queue_family_index: src_family,
+                        dst_queue_family_index: family_index,
+                    });
+                    add_edge(&mut edges, src_pass, i, access.hint);
+                }
+                state.last_queue = Some((i, family_index));
+                if let Some(writer) = state.last_writer {
+                    add_edge(&mut edges, writer, i, access.hint);
+                }
+                if access.write {
+                    for &reader in &state.readers {
+                        add_edge(&mut edges, reader, i, access.hint);
+                    }
+                    state.readers.clear();
+                    state.last_writer = Some(i);
+                } else {
+                    state.readers.push(i);
+                }
+                if let GraphResource::Image(image) = access.resource &&
+                    let ImageSource::Transient(index) = self.images[image.0 as usize]
+                {
+                    let index = index as usize;
+                    match &mut lifetimes[index] {
+                        Some((_, last, queue)) => {
+                            *last = i;
+                            if *queue != pass.queue {
+                                aliasable[index] = false;
+                            }
+                        },
+                        lifetime => {
+                            *lifetime = Some((i, i, pass.queue.clone()));
+                        },
+                    }
+                }
+            }
+        }
+        let keys: Vec32<_> = lifetimes
+            .iter()
+            .enumerate()
+            .map(|(i, lifetime)| lifetime.as_ref().map(|&(first, last, _)| {
+                let (first, last) =
+                    if aliasable[i] {
+                        (first, last)
+                    } else {
+                        (0, u32::MAX)
+                    };
+                TransientKey {
+                    info: self.transients[i],
+                    first,
+                    last,
+                }
+            })).collect();
+        let graph = self.graph;
+        let scheduled_frame = scheduler.scheduled_frame();
+        let finished_frame = graph.gpu
+            .get_semaphore_counter_value(scheduler.frame_semaphore_id())
+            .context("failed to get frame semaphore value")?;
+        graph.destroy_retired(finished_frame)?;
+        graph.update_transients(keys, scheduled_frame - 1)
+            .context("failed to update transient images")?;
+        if let Some(binder) = &graph.binder {
+            for (a, image_a) in graph.transient_images.iter().enumerate() {
+                let (Some(image_a), Some(key_a)) = (image_a, graph.transient_keys[a]) else {
+                    continue
+                };
+                for (b, image_b) in graph.transient_images.iter().enumerate() {
+                    let (Some(image_b), Some(key_b)) = (image_b, graph.transient_keys[b]) else {
+                        continue
+                    };
+                    if key_a.last < key_b.first &&
+                        binder.aliases(image_a.allocation, image_b.allocation)
+                    {
+                        add_edge(&mut edges, key_a.last, key_b.first, MemoryDependencyHint::NONE);
+                    }
+                }
+            }
+        }
+        let mut images = graph.gpu.write_images::<ImageId>();
+        for image in graph.transient_images.iter().flatten() {
+            images.get_mut(image.image_id)?.discard_subresources();
+        }
+        drop(images);
+        let resources = Arc::new(GraphResources {
+            images: self.images
+                .iter()
+                .map(|&source| match source {
+                    ImageSource::Imported(image_id) => (image_id, None),
+                    ImageSource::Transient(index) => graph.transient_images[index as usize]
+                        .map(|image| (image.image_id, Some(image.view_id)))
+                        .unwrap_or_default(),
+                }).collect(),
+            buffers: self.buffers,
+        });
+        let mut command_ids = vec32![None; n_passes];
+        for (i, pass) in self.passes.into_iter().enumerate() {
+            if !kept[i] {
+                continue
+            }
+            let dependencies = edges[i]
+                .iter()
+                .map(|&(src, hint)| CommandDependency::new(
+                    command_ids[src as usize].unwrap(),
+                    hint,
+                )).collect();
+            command_ids[i] = Some((pass.record)(scheduler, dependencies, resources.clone())
+                .context_with(|| format!(
+                    "failed to schedule pass {}", pass.name,
+                ))?
+            );
+        }
+        Ok(ScheduledRenderGraph {
+            command_ids,
+            queue_transfers,
+        })
+    }
+}
@@ -48,6 +48,26 @@ impl FlushResources {
     }
 }
 
+/// The queues, which last used resources in commands.
+///
+/// Used to find the queue to record the release half of a queue family ownership transfer on.
+#[derive(Default)]
+pub(super) struct QueueOwners {
+    buffers: AHashMap<BufferId, DeviceQueue>,
+    images: AHashMap<ImageIndex, DeviceQueue>,
+}
+
+impl QueueOwners {
+
+    /// Removes the owners of destroyed resources.
+    fn prune(&mut self, gpu: &Gpu) {
+        let buffers = gpu.read_buffers::<BufferId>();
+        self.buffers.retain(|&id, _| buffers.get(id).is_ok());
+        let images = gpu.read_images::<ImageIndex>();
+        self.images.retain(|&id, _| images.get(id).is_ok());
+    }
+}
+
 struct Inner {
     gpu: Gpu,
     frame_semaphore: TimelineSemaphoreId,
@@ -58,6 +78,7 @@ struct Inner {
     stack: Arc<Arena<True>>,
     command_resources: Vec32<CommandResources>,
     flush_resources: FlushResources,
+    queue_owners: QueueOwners,
 }
 
 impl Inner {
@@ -81,6 +102,7 @@ impl Inner {
             ),
             command_resources: vec32![],
             flush_resources: FlushResources::default(),
+            queue_owners: QueueOwners::default(),
             gpu,
         })
     } 
@@ -118,6 +140,23 @@ impl<'a> CommandScheduler<'a> {
         Ok(())
     }
 
+    #[inline]
+    pub(crate) fn gpu(&self) -> &Gpu {
+        unsafe { &(*self.inner.get()).gpu }
+    }
+
+    /// Returns the value the frame semaphore will be signaled with, once the commands scheduled
+    /// here have finished executing.
+    #[inline]
+    pub(crate) fn scheduled_frame(&self) -> u64 {
+        unsafe { (*self.inner.get()).current_frame + 1 }
+    }
+
+    #[inline]
+    pub(crate) fn frame_semaphore_id(&self) -> TimelineSemaphoreId {
+        unsafe { (*self.inner.get()).frame_semaphore }
+    }
+
     #[track_caller]
     pub fn new_commands<Cmd>(
         &mut self,
@@ -165,11 +204,65 @@ impl<'a> CommandScheduler<'a> {
             Ok(CommandBuilder { id: CommandId(idx), frame_resources, resources, })
         }
     }
+
+    /// Schedules [`ownership commands`][1], which transfer the ownership of `buffers` and
+    /// `images` to the queue family of `queue`.
+    ///
+    /// Release and acquire barriers are otherwise inserted automatically, when a command uses a
+    /// resource last used on another queue family. This *can* be used to control where the
+    /// transfer happens, e.g. to move an upload from a dedicated transfer queue ahead of time.
+    ///
+    /// The returned [`CommandBuilder`] *can* be used to add dependencies, like with any other
+    /// command.
+    ///
+    /// [1]: OwnershipCommands
+    #[track_caller]
+    pub fn transfer_ownership(
+        &mut self,
+        queue: DeviceQueue,
+        buffers: &[BufferId],
+        images: &[ImageId],
+    ) -> Result<CommandBuilder<'_>>
+    {
+        let buffers: Vec32<_> = buffers.iter().copied().collect();
+        let images: Vec32<_> = images.iter().copied().collect();
+        self.new_commands::<NewOwnershipCommands>(queue, move |cmd| {
+            for &id in &buffers {
+                cmd.acquire_buffer(id)?;
+            }
+            for &id in &images {
+                cmd.acquire_image(id)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Schedules [`sparse bind commands`][1] on `queue`.
+    ///
+    /// This is the same as calling [`new_commands`][2] with [`NewSparseBindCommands`].
+    ///
+    /// The returned [`CommandBuilder`] *can* be used to add dependencies, like with any other
+    /// command.
+    ///
+    /// [1]: SparseBindCommands
+    /// [2]: Self::new_commands
+    #[track_caller]
+    pub fn bind_sparse(
+        &mut self,
+        queue: DeviceQueue,
+        f: impl for<'b, 'c> FnOnce(
+            &mut SparseBindCommands<'b, 'c>
+        ) -> EventResult<()> + Send + Sync + 'static,
+    ) -> Result<CommandBuilder<'_>>
+    {
+        self.new_commands::<NewSparseBindCommands>(queue, f)
+    }
 }
 
 #[derive(Clone)]
 pub(crate) struct QueueScheduler {
     inner: Arc<RwLock<Inner>>,
+    frame_semaphore: TimelineSemaphoreId,
 }
 
 pub struct QueueSchedulerReadGuard<'a> {
@@ -182,6 +275,13 @@ impl<'a> QueueSchedulerReadGuard<'a> {
     pub fn get_frame_semaphore_id(&self) -> TimelineSemaphoreId {
         self.inner.frame_semaphore
     }
+
+    /// Returns the value the frame semaphore will be signaled with, once the commands of the
+    /// next tick have finished executing.
+    #[inline]
+    pub fn get_scheduled_frame(&self) -> u64 {
+        self.inner.current_frame + 1
+    }
 }
 
 impl QueueScheduler {
@@ -191,11 +291,20 @@ impl QueueScheduler {
         num_workers: u32,
     ) -> Result<Self>
     {
+        let inner = Inner::new(gpu, num_workers)?;
         Ok(Self {
-            inner: Arc::new(RwLock::new(Inner::new(gpu, num_workers)?)),
+            frame_semaphore: inner.frame_semaphore,
+            inner: Arc::new(RwLock::new(inner)),
         })
     }
 
+    /// Returns the id of the frame semaphore without locking the scheduler, so this *can* be
+    /// called while a [`CommandScheduler`] is held.
+    #[inline]
+    pub fn frame_semaphore_id(&self) -> TimelineSemaphoreId {
+        self.frame_semaphore
+    }
+
     #[inline]
     pub fn read(&self) -> QueueSchedulerReadGuard<'_> {
         QueueSchedulerReadGuard { inner: self.inner.read(), }
@@ -277,7 +386,7 @@ pub struct CommandRecorderCache {
 impl CommandRecorderCache {
 
     pub fn init(&mut self, gpu: &Gpu) {
-        self.pipeline_cache.init(gpu.get_extension_device());
+        self.pipeline_cache.init(gpu.get_extension_device(), gpu.get_extension_device());
     }
 }
 
@@ -299,6 +408,13 @@ impl CommandRecorderInner<'_> {
             Alloc: LocalAlloc<Error = arena::Error>,
     {
         self.cache.get_mut().present_submits.clear();
+        {
+            let cache = &mut self.cache.get_mut().shader_resource_cache;
+            cache.buffer_memory_barrier_cache.releases.clear();
+            cache.image_memory_barrier_cache.releases.clear();
+        }
+        let gpu = self.inner.gpu.clone();
+        self.inner.queue_owners.prune(&gpu);
         let surfaces: *mut SlotMap<Surface> = self.surfaces.deref_mut();
         for (id, surface) in unsafe { &mut *surfaces }.iter_mut() {
             let data = surface
@@ -388,9 +504,11 @@ impl CommandRecorderInner<'_> {
                 "topological sort failed"
             ))
         }
+        // each command and the present submit can be preceded by a release submit per queue
+        let n_queues = self.inner.gpu.device().device_queues().len() as u32;
         let mut submits = Submits {
             submits: FixedVec32
-                ::with_capacity(sorted.len() + 2, alloc)
+                ::with_capacity((sorted.len() + 1) * (n_queues + 1) + 1, alloc)
                 .context("alloc error")?,
             present_submits: FixedVec32::new(alloc),
         };
@@ -398,13 +516,11 @@ impl CommandRecorderInner<'_> {
             ::with_capacity(sorted.len() + 1, alloc)
             .context("alloc failed")?;
         let mut swapchain_wait_semaphores = NonNullVec32
-            ::with_capacity(sorted.len(), alloc)
+            ::with_capacity(sorted.len() + 1, alloc)
             .context("alloc failed")?;
         for &idx in &sorted {
             let index = idx.index() as usize;
             let mut command = self.inner.commands.get(idx).unwrap().clone();
-            let cmd_resources = &mut self.inner.command_resources[index];
-            let timeline_value = cmd_resources.timeline_value + 1;
             for &dep in &command.dep {
                 if let Err(err) = self.inner.commands.get(dep.dependency.0) {
                     return Err(Error::new(err, format!(
@@ -435,19 +551,38 @@ impl CommandRecorderInner<'_> {
                 );
                 result
             };
+            let (semaphore_id, last_value) = {
+                let resources = &self.inner.command_resources[index];
+                (resources.semaphore_id, resources.timeline_value)
+            };
+            let signal = unsafe { self.inner.gpu
+                .get_timeline_semaphore(semaphore_id)
+                .unwrap_unchecked()
+            };
+            let release_value = self.submit_ownership_releases(
+                signal, last_value, true,
+                &mut submits.submits, alloc,
+            ).context_with(|| format!(
+                "failed to release queue family ownership for command {}", command.loc,
+            ))?;
+            let timeline_value = release_value + 1;
             {
-                let mut buffers = self.inner.gpu.write_buffers();
-                for &id in &self.inner.flush_resources.flush_buffers {
+                let mut buffers = gpu.write_buffers();
+                let inner = &mut *self.inner;
+                for &id in &inner.flush_resources.flush_buffers {
                     if let Ok(buffer) = buffers.get_mut(id.slot_index()) {
                         buffer.flush_state();
+                        inner.queue_owners.buffers.insert(id, command_result.queue.clone());
                     }
                 }
             }
             {
-                let mut images = self.inner.gpu.write_images();
-                for &id in &self.inner.flush_resources.flush_images {
+                let mut images = gpu.write_images();
+                let inner = &mut *self.inner;
+                for &id in &inner.flush_resources.flush_images {
                     if let Ok(image) = images.get_mut(id) {
                         image.flush_subresources();
+                        inner.queue_owners.images.insert(id, command_result.queue.clone());
                     }
                 }
             }
@@ -455,14 +590,10 @@ impl CommandRecorderInner<'_> {
             let command_resources = unsafe {
                 self.inner.command_resources.get_unchecked(index)
             };
-            let signal = unsafe { self.inner.gpu
-                .get_timeline_semaphore(command_resources.semaphore_id)
-                .unwrap_unchecked()
-            };
             let mut submit_info = SubmitInfo {
                 device_queue_index: command_result.queue.device_queue_index(),
                 wait_semaphore_infos: NonNullVec32::with_capacity(
-                    command_resources.wait_semaphore_cache.len(),
+                    command_resources.wait_semaphore_cache.len() + 1,
                     alloc
                 ).context("alloc failed")?,
                 command_buffer_infos: NonNullVec32::with_capacity(
@@ -472,6 +603,10 @@ impl CommandRecorderInner<'_> {
                 signal_semaphore_infos: NonNullVec32::with_capacity(
                     1 + command_resources.signal_semaphores.len(), alloc
                 ).context("alloc failed")?,
+                sparse_binds: command_result.sparse_binds
+                    .as_ref()
+                    .map(|binds| SparseBindSubmit::new(binds, alloc))
+                    .transpose()?,
                 alloc,
             };
             submit_info.wait_semaphore_infos.try_extend(command_resources.wait_semaphore_cache
@@ -504,6 +639,14 @@ impl CommandRecorderInner<'_> {
                     })
                 })
             )?;
+            if release_value != last_value {
+                submit_info.wait_semaphore_infos.push(vk::SemaphoreSubmitInfo {
+                    semaphore: signal,
+                    value: release_value,
+                    stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+                    ..Default::default()
+                });
+            }
             submit_info.command_buffer_infos.extend(command_result.primary_command_buffers
                 .iter().map(|&command_buffer| {
                     vk::CommandBufferSubmitInfo {
@@ -585,6 +728,20 @@ impl CommandRecorderInner<'_> {
                     command_buffer
                 ).context("failed to end command buffer")?;
             }
+            let present_prep_value = self.inner.workers[free_worker].present_prep_value;
+            let release_value = self.submit_ownership_releases(
+                present_prep_semaphore, present_prep_value, false,
+                &mut submits.submits, alloc,
+            ).context("failed to release queue family ownership of swapchain images")?;
+            if release_value != present_prep_value {
+                swapchain_wait_semaphores.push(vk::SemaphoreSubmitInfo {
+                    semaphore: present_prep_semaphore,
+                    value: release_value,
+                    stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+                    ..Default::default()
+                });
+                self.inner.workers[free_worker].present_prep_value = release_value;
+            }
             let mut submit = SubmitInfo {
                 device_queue_index: queue.device_queue_index(),
                 wait_semaphore_infos: swapchain_wait_semaphores,
@@ -592,6 +749,7 @@ impl CommandRecorderInner<'_> {
                     ::with_capacity(1, alloc)
                     .context("alloc failed")?,
                 signal_semaphore_infos: Default::default(),
+                sparse_binds: None,
                 alloc,
             };
             submit.command_buffer_infos.push(vk::CommandBufferSubmitInfo {
@@ -703,6 +861,7 @@ impl CommandRecorderInner<'_> {
             wait_semaphore_infos: global_wait_semaphore_infos,
             command_buffer_infos: Default::default(),
             signal_semaphore_infos: global_signal_semaphore_infos,
+            sparse_binds: None,
             alloc
         });
         self.inner.workers[free_worker.0].present_prep_value += 1;
@@ -713,6 +872,175 @@ impl CommandRecorderInner<'_> {
     }
 }
 
+impl CommandRecorderInner<'_> {
+
+    /// Submits the release halves of the queue family ownership transfers acquired since the last
+    /// call.
+    ///
+    /// A release is recorded on the queue, which last used the resource in a command, if it
+    /// belongs to the source queue family, and otherwise on any queue of the source queue family.
+    ///
+    /// Each release submit signals the next value of `semaphore` starting from `value`. If
+    /// `wait_value` is true, the first submit waits on `value`, so that the values are signaled in
+    /// order.
+    ///
+    /// Returns the last value signaled, which the acquiring submit *must* wait on, or `value` if
+    /// nothing was released.
+    fn submit_ownership_releases<'a, Alloc>(
+        &mut self,
+        semaphore: vk::Semaphore,
+        mut value: u64,
+        mut wait_value: bool,
+        submits: &mut FixedVec32<'a, SubmitInfo<'a, Alloc>, Alloc>,
+        alloc: &'a Alloc,
+    ) -> Result<u64>
+        where Alloc: LocalAlloc<Error = arena::Error>,
+    {
+        let cache = &mut self.cache.get_mut().shader_resource_cache;
+        let buffer_releases = &mut cache.buffer_memory_barrier_cache.releases;
+        let image_releases = &mut cache.image_memory_barrier_cache.releases;
+        if buffer_releases.is_empty() && image_releases.is_empty() {
+            return Ok(value)
+        }
+        let gpu = self.inner.gpu.clone();
+        let device = gpu.device();
+        let device_queues = device.device_queues();
+        let release_queue = |owner: Option<&DeviceQueue>, family_index: u32| {
+            owner
+                .filter(|queue| queue.family_index() == family_index)
+                .or_else(|| device_queues
+                    .iter()
+                    .find(|queue| queue.family_index() == family_index)
+                ).map(|queue| queue.device_queue_index())
+                .ok_or_else(|| Error::just_context(format!(
+                    "no device queue with queue family index {family_index}"
+                )))
+        };
+        let mut buffer_queues = FixedVec32
+            ::with_capacity(buffer_releases.len(), alloc)
+            .context("alloc failed")?;
+        {
+            let buffers = gpu.read_buffers::<BufferId>();
+            for &(handle, barrier) in buffer_releases.iter() {
+                let owner = self.inner.flush_resources.flush_buffers
+                    .iter()
+                    .find(|&&id| buffers
+                        .get(id)
+                        .is_ok_and(|buffer| buffer.handle() == handle)
+                    ).and_then(|id| self.inner.queue_owners.buffers.get(id));
+                buffer_queues.push(release_queue(owner, barrier.src_queue_family_index)?);
+            }
+        }
+        let mut image_queues = FixedVec32
+            ::with_capacity(image_releases.len(), alloc)
+            .context("alloc failed")?;
+        {
+            let images = gpu.read_images::<ImageIndex>();
+            for &(handle, barrier) in image_releases.iter() {
+                let owner = self.inner.flush_resources.flush_images
+                    .iter()
+                    .find(|&&id| images
+                        .get(id)
+                        .is_ok_and(|image| image.handle() == handle)
+                    ).and_then(|id| self.inner.queue_owners.images.get(id));
+                image_queues.push(release_queue(owner, barrier.src_queue_family_index)?);
+            }
+        }
+        let free_worker = self.inner.free_worker as usize;
+        for queue in device_queues {
+            let queue_index = queue.device_queue_index();
+            let n_buffer_barriers = buffer_queues
+                .iter()
+                .filter(|&&index| index == queue_index)
+                .count() as u32;
+            let n_image_barriers = image_queues
+                .iter()
+                .filter(|&&index| index == queue_index)
+                .count() as u32;
+            if n_buffer_barriers == 0 && n_image_barriers == 0 {
+                continue
+            }
+            let mut buffer_barriers = FixedVec32
+                ::with_capacity(n_buffer_barriers, alloc)
+                .context("alloc failed")?;
+            for (&(handle, barrier), &index) in buffer_releases.iter().zip(buffer_queues.iter()) {
+                if index == queue_index {
+                    buffer_barriers.push(barrier.into_vk(handle));
+                }
+            }
+            let mut image_barriers = FixedVec32
+                ::with_capacity(n_image_barriers, alloc)
+                .context("alloc failed")?;
+            for (&(handle, barrier), &index) in image_releases.iter().zip(image_queues.iter()) {
+                if index == queue_index {
+                    image_barriers.push(barrier.into_vk(handle));
+                }
+            }
+            let command_buffer = self.inner.workers[free_worker]
+                .allocate_primaries(queue, 1)?[0];
+            let begin_info = vk::CommandBufferBeginInfo {
+                s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
+                flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                ..Default::default()
+            };
+            let dependency_info = vk::DependencyInfo {
+                buffer_memory_barrier_count: buffer_barriers.len(),
+                p_buffer_memory_barriers: buffer_barriers.as_ptr(),
+                image_memory_barrier_count: image_barriers.len(),
+                p_image_memory_barriers: image_barriers.as_ptr(),
+                ..Default::default()
+            };
+            unsafe {
+                device
+                    .begin_command_buffer(command_buffer, &begin_info)
+                    .context("failed to begin command buffer")?;
+                device.cmd_pipeline_barrier2(command_buffer, &dependency_info);
+                device
+                    .end_command_buffer(command_buffer)
+                    .context("failed to end command buffer")?;
+            }
+            let mut submit = SubmitInfo {
+                device_queue_index: queue_index,
+                wait_semaphore_infos: NonNullVec32
+                    ::with_capacity(1, alloc)
+                    .context("alloc failed")?,
+                command_buffer_infos: NonNullVec32
+                    ::with_capacity(1, alloc)
+                    .context("alloc failed")?,
+                signal_semaphore_infos: NonNullVec32
+                    ::with_capacity(1, alloc)
+                    .context("alloc failed")?,
+                sparse_binds: None,
+                alloc,
+            };
+            if wait_value {
+                submit.wait_semaphore_infos.push(vk::SemaphoreSubmitInfo {
+                    semaphore,
+                    value,
+                    stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+                    ..Default::default()
+                });
+            }
+            submit.command_buffer_infos.push(vk::CommandBufferSubmitInfo {
+                command_buffer,
+                ..Default::default()
+            });
+            value += 1;
+            submit.signal_semaphore_infos.push(vk::SemaphoreSubmitInfo {
+                semaphore,
+                value,
+                stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+                ..Default::default()
+            });
+            submits.push(submit);
+            wait_value = true;
+        }
+        buffer_releases.clear();
+        image_releases.clear();
+        Ok(value)
+    }
+}
+
 pub struct CommandRecorder<'a, 'b> {
     inner: *mut CommandRecorderInner<'b>,
     _marker: PhantomData<& 'a ()>,
@@ -745,6 +1073,8 @@ pub(crate) struct SubmitInfo<'a, Alloc>
     pub wait_semaphore_infos: NonNullVec32<'a, vk::SemaphoreSubmitInfo<'static>>,
     pub command_buffer_infos: NonNullVec32<'a, vk::CommandBufferSubmitInfo<'static>>,
     pub signal_semaphore_infos: NonNullVec32<'a, vk::SemaphoreSubmitInfo<'static>>,
+    /// If this is [`Some`], the submit is a sparse bind and has no command buffers.
+    pub sparse_binds: Option<SparseBindSubmit<'a>>,
     pub alloc: &'a Alloc,
 }
 
@@ -757,6 +1087,9 @@ impl<'a, Alloc> Drop for SubmitInfo<'a, Alloc>
             self.wait_semaphore_infos.drop_and_free(self.alloc);
             self.command_buffer_infos.drop_and_free(self.alloc);
             self.signal_semaphore_infos.drop_and_free(self.alloc);
+            if let Some(sparse_binds) = &mut self.sparse_binds {
+                sparse_binds.drop_and_free(self.alloc);
+            }
         }
     }
 }
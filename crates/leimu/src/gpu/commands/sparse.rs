@@ -0,0 +1,698 @@
+use nox_ash::vk;
+use nox_mem::{
+    vec::{Vec32, FixedVec32, NonNullVec32},
+    alloc::LocalAlloc,
+    arena,
+};
+
+use crate::{
+    gpu::prelude::*,
+    sync::Arc,
+    error::*,
+};
+
+use super::scheduler::SubmitInfo;
+
+/// Binds and unbinds the memory of sparse buffers and images on a queue.
+///
+/// Available through [`CommandScheduler::bind_sparse`] or with [`NewSparseBindCommands`].
+///
+/// Memory is bound at page granularity. Each bind allocates the memory of the bound pages with a
+/// [`MemoryBinder`], and memory previously bound to the pages is released once the binds of the
+/// frame have finished executing.
+///
+/// The binds are submitted with [`vkQueueBindSparse`][1] in the order of the scheduled commands
+/// and are ordered with other commands with timeline semaphores, like any other command.
+///
+/// # Valid usage
+/// - The queue *must* support [`QueueFlags::SPARSE_BINDING`].
+/// - Pages *must* not be rebound or unbound, while commands accessing them are executing. This
+///   *can* be ensured by adding a dependency to those commands.
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/spec/latest/chapters/sparsemem.html>
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/vkQueueBindSparse.html
+pub struct SparseBindCommands<'a, 'b> {
+    gpu: Gpu,
+    recorder: CommandRecorder<'a, 'b>,
+    queue: DeviceQueue,
+    command_id: CommandId,
+    buffer_binds: Vec32<(vk::Buffer, vk::SparseMemoryBind)>,
+    image_opaque_binds: Vec32<(vk::Image, vk::SparseMemoryBind)>,
+    image_binds: Vec32<(vk::Image, vk::SparseImageMemoryBind)>,
+}
+
+/// The sparse binds of a [`CommandResult`].
+pub struct SparseBinds<'a, Alloc>
+    where Alloc: LocalAlloc + ?Sized,
+{
+    pub buffer_binds: FixedVec32<'a, (vk::Buffer, vk::SparseMemoryBind), Alloc>,
+    pub image_opaque_binds: FixedVec32<'a, (vk::Image, vk::SparseMemoryBind), Alloc>,
+    pub image_binds: FixedVec32<'a, (vk::Image, vk::SparseImageMemoryBind), Alloc>,
+}
+
+pub struct NewSparseBindCommands;
+
+impl NewCommands for NewSparseBindCommands {
+
+    const NAME: &'static str = "sparse bind commands";
+
+    type Target<'a, 'b> = SparseBindCommands<'a, 'b>;
+
+    fn new<'a, 'b>(
+        recorder: CommandRecorder<'a, 'b>,
+        command_id: CommandId,
+        queue: DeviceQueue,
+    ) -> Result<Self::Target<'a, 'b>>
+        where Self::Target<'a, 'b>: Commands<'a, 'b>
+    {
+        if !queue.queue_flags().contains(QueueFlags::SPARSE_BINDING) {
+            return Err(Error::just_context(format!(
+                "queue {queue} doesn't support sparse binding"
+            )))
+        }
+        Ok(SparseBindCommands {
+            gpu: recorder.gpu().clone(),
+            recorder,
+            queue,
+            command_id,
+            buffer_binds: Vec32::new(),
+            image_opaque_binds: Vec32::new(),
+            image_binds: Vec32::new(),
+        })
+    }
+}
+
+unsafe impl<'a, 'b> Commands<'a, 'b> for SparseBindCommands<'a, 'b> {
+
+    fn add_signal_semaphore(
+        &mut self,
+        semaphore_id: TimelineSemaphoreId,
+        value: u64,
+    ) {
+        self.recorder.add_signal_semaphore(self.command_id, semaphore_id, value);
+    }
+
+    fn add_wait_semaphore(
+        &mut self,
+        semaphore_id: TimelineSemaphoreId,
+        value: u64,
+        dependency_hint: MemoryDependencyHint,
+    ) {
+        self.recorder.add_wait_semaphore(
+            self.command_id, semaphore_id,
+            value, dependency_hint
+        );
+    }
+
+    fn finish<'c, Alloc>(self, alloc: &'c Alloc) -> Result<CommandResult<'c, Alloc>>
+        where Alloc: ?Sized + LocalAlloc<Error = arena::Error>
+    {
+        let mut sparse_binds = SparseBinds {
+            buffer_binds: FixedVec32
+                ::with_capacity(self.buffer_binds.len(), alloc)
+                .context("alloc failed")?,
+            image_opaque_binds: FixedVec32
+                ::with_capacity(self.image_opaque_binds.len(), alloc)
+                .context("alloc failed")?,
+            image_binds: FixedVec32
+                ::with_capacity(self.image_binds.len(), alloc)
+                .context("alloc failed")?,
+        };
+        sparse_binds.buffer_binds.fast_append(&self.buffer_binds);
+        sparse_binds.image_opaque_binds.fast_append(&self.image_opaque_binds);
+        sparse_binds.image_binds.fast_append(&self.image_binds);
+        Ok(CommandResult {
+            primary_command_buffers: FixedVec32::new(alloc),
+            wait_scope: vk::PipelineStageFlags2::ALL_COMMANDS,
+            signal_scope: vk::PipelineStageFlags2::ALL_COMMANDS,
+            queue: self.queue,
+            sparse_binds: Some(sparse_binds),
+        })
+    }
+}
+
+impl<'a, 'b> SparseBindCommands<'a, 'b> {
+
+    /// Binds memory allocated with `memory_binder` to a range of a sparse buffer.
+    ///
+    /// Memory previously bound to the range is released.
+    ///
+    /// # Valid usage
+    /// - `buffer_id` *must* be a valid [`BufferId`] of a sparse buffer.
+    /// - `offset` *must* be a multiple of the [`page size`][1] of the buffer.
+    /// - `size` *must* be non-zero and either a multiple of the page size or `offset` + `size`
+    ///   *must* equal the size of the buffer.
+    ///
+    /// [1]: SparseMemoryProperties::page_size
+    pub fn bind_buffer(
+        &mut self,
+        buffer_id: BufferId,
+        offset: DeviceSize,
+        size: DeviceSize,
+        memory_binder: &dyn MemoryBinder,
+    ) -> Result<()>
+    {
+        self.bind_buffer_internal(buffer_id, offset, size, Some(memory_binder))
+            .context_with(|| format!("failed to bind buffer {buffer_id}"))
+    }
+
+    /// Unbinds the memory of a range of a sparse buffer.
+    ///
+    /// The valid usage is the same as with [`bind_buffer`][1].
+    ///
+    /// [1]: Self::bind_buffer
+    pub fn unbind_buffer(
+        &mut self,
+        buffer_id: BufferId,
+        offset: DeviceSize,
+        size: DeviceSize,
+    ) -> Result<()>
+    {
+        self.bind_buffer_internal(buffer_id, offset, size, None)
+            .context_with(|| format!("failed to unbind buffer {buffer_id}"))
+    }
+
+    /// Binds memory allocated with `memory_binder` to a range of the opaque memory of a sparse
+    /// image.
+    ///
+    /// Images without [`SparseFlags::RESIDENCY`] are bound only with this.
+    ///
+    /// # Valid usage
+    /// - `image_id` *must* be a valid [`ImageId`] of a sparse image.
+    /// - `offset` *must* be a multiple of the [`page size`][1] of the image.
+    /// - `size` *must* be non-zero and either a multiple of the page size or `offset` + `size`
+    ///   *must* equal the [`size`][2] of the image.
+    ///
+    /// [1]: SparseMemoryProperties::page_size
+    /// [2]: SparseMemoryProperties::size
+    pub fn bind_image_opaque(
+        &mut self,
+        image_id: ImageId,
+        offset: DeviceSize,
+        size: DeviceSize,
+        memory_binder: &dyn MemoryBinder,
+    ) -> Result<()>
+    {
+        self.bind_image_opaque_internal(image_id, offset, size, Some(memory_binder))
+            .context_with(|| format!("failed to bind image {image_id}"))
+    }
+
+    /// Unbinds the memory of a range of the opaque memory of a sparse image.
+    ///
+    /// The valid usage is the same as with [`bind_image_opaque`][1].
+    ///
+    /// [1]: Self::bind_image_opaque
+    pub fn unbind_image_opaque(
+        &mut self,
+        image_id: ImageId,
+        offset: DeviceSize,
+        size: DeviceSize,
+    ) -> Result<()>
+    {
+        self.bind_image_opaque_internal(image_id, offset, size, None)
+            .context_with(|| format!("failed to unbind image {image_id}"))
+    }
+
+    /// Binds memory allocated with `memory_binder` to the mip tail of an aspect and array layer
+    /// of a sparse image.
+    ///
+    /// The metadata of an image is bound with this by passing the aspect mask of its
+    /// [`requirements`][1].
+    ///
+    /// # Valid usage
+    /// - `image_id` *must* be a valid [`ImageId`] of a sparse image.
+    /// - `aspect_mask` *must* be the aspect mask of one of the [`requirements`][1] of the image.
+    /// - `array_layer` *must* be less than the array layers of the image, and *must* be zero if
+    ///   the image has a [`single mip tail`][2].
+    ///
+    /// [1]: Gpu::get_image_sparse_memory_requirements
+    /// [2]: SparseImageFormatProperties::single_mip_tail
+    pub fn bind_image_mip_tail(
+        &mut self,
+        image_id: ImageId,
+        aspect_mask: ImageAspects,
+        array_layer: u32,
+        memory_binder: &dyn MemoryBinder,
+    ) -> Result<()>
+    {
+        self.bind_image_mip_tail_internal(image_id, aspect_mask, array_layer, Some(memory_binder))
+            .context_with(|| format!("failed to bind mip tail of image {image_id}"))
+    }
+
+    /// Unbinds the memory of the mip tail of an aspect and array layer of a sparse image.
+    ///
+    /// The valid usage is the same as with [`bind_image_mip_tail`][1].
+    ///
+    /// [1]: Self::bind_image_mip_tail
+    pub fn unbind_image_mip_tail(
+        &mut self,
+        image_id: ImageId,
+        aspect_mask: ImageAspects,
+        array_layer: u32,
+    ) -> Result<()>
+    {
+        self.bind_image_mip_tail_internal(image_id, aspect_mask, array_layer, None)
+            .context_with(|| format!("failed to unbind mip tail of image {image_id}"))
+    }
+
+    /// Binds memory allocated with `memory_binder` to a region of sparse blocks of a sparse image.
+    ///
+    /// Each block takes one [`page`][1] of memory.
+    ///
+    /// # Valid usage
+    /// - `image_id` *must* be a valid [`ImageId`] of an image created with
+    ///   [`SparseFlags::RESIDENCY`].
+    /// - `bind` *must* follow the valid usage described in [`SparseImageBind`].
+    ///
+    /// [1]: SparseMemoryProperties::page_size
+    pub fn bind_image(
+        &mut self,
+        image_id: ImageId,
+        bind: SparseImageBind,
+        memory_binder: &dyn MemoryBinder,
+    ) -> Result<()>
+    {
+        self.bind_image_internal(image_id, bind, Some(memory_binder))
+            .context_with(|| format!("failed to bind image {image_id}"))
+    }
+
+    /// Unbinds the memory of a region of sparse blocks of a sparse image.
+    ///
+    /// The valid usage is the same as with [`bind_image`][1].
+    ///
+    /// [1]: Self::bind_image
+    pub fn unbind_image(
+        &mut self,
+        image_id: ImageId,
+        bind: SparseImageBind,
+    ) -> Result<()>
+    {
+        self.bind_image_internal(image_id, bind, None)
+            .context_with(|| format!("failed to unbind image {image_id}"))
+    }
+
+    fn bind_buffer_internal(
+        &mut self,
+        buffer_id: BufferId,
+        offset: DeviceSize,
+        size: DeviceSize,
+        memory_binder: Option<&dyn MemoryBinder>,
+    ) -> Result<()>
+    {
+        let mut buffers = self.gpu.write_buffers();
+        let buffer = buffers.get_mut(buffer_id)?;
+        let handle = buffer.handle();
+        let memory = buffer
+            .sparse_memory_mut()
+            .ok_or_else(|| Error::just_context("buffer is not sparse"))?;
+        let bind = bind_opaque(
+            memory, offset, size, memory_binder,
+            vk::SparseMemoryBindFlags::empty(),
+            &mut self.recorder.get_current_worker().retired_memory,
+        )?;
+        self.buffer_binds.push((handle, bind));
+        Ok(())
+    }
+
+    fn bind_image_opaque_internal(
+        &mut self,
+        image_id: ImageId,
+        offset: DeviceSize,
+        size: DeviceSize,
+        memory_binder: Option<&dyn MemoryBinder>,
+    ) -> Result<()>
+    {
+        let mut images = self.gpu.write_images();
+        let image = images.get_mut(image_id)?;
+        let handle = image.handle();
+        let (memory, _) = image
+            .sparse_memory_mut()
+            .ok_or_else(|| Error::just_context("image is not sparse"))?;
+        let bind = bind_opaque(
+            memory, offset, size, memory_binder,
+            vk::SparseMemoryBindFlags::empty(),
+            &mut self.recorder.get_current_worker().retired_memory,
+        )?;
+        self.image_opaque_binds.push((handle, bind));
+        Ok(())
+    }
+
+    fn bind_image_mip_tail_internal(
+        &mut self,
+        image_id: ImageId,
+        aspect_mask: ImageAspects,
+        array_layer: u32,
+        memory_binder: Option<&dyn MemoryBinder>,
+    ) -> Result<()>
+    {
+        let mut images = self.gpu.write_images();
+        let image = images.get_mut(image_id)?;
+        let handle = image.handle();
+        let array_layers = image.properties().array_layers;
+        let (memory, requirements) = image
+            .sparse_memory_mut()
+            .ok_or_else(|| Error::just_context("image is not sparse"))?;
+        let requirements = *requirements
+            .iter()
+            .find(|r| r.format_properties.aspect_mask == aspect_mask)
+            .ok_or_else(|| Error::just_context(format!(
+                "image has no sparse memory requirements for aspect {aspect_mask}"
+            )))?;
+        if requirements.format_properties.single_mip_tail {
+            if array_layer != 0 {
+                return Err(Error::just_context(format!(
+                    "array layer {array_layer} must be 0 for an image with a single mip tail"
+                )))
+            }
+        } else if array_layer >= array_layers {
+            return Err(Error::just_context(format!(
+                "array layer {array_layer} is out of range of image array layers {array_layers}"
+            )))
+        }
+        let flags =
+            if aspect_mask.as_raw() == vk::ImageAspectFlags::METADATA.as_raw() {
+                vk::SparseMemoryBindFlags::METADATA
+            } else {
+                vk::SparseMemoryBindFlags::empty()
+            };
+        let bind = bind_opaque(
+            memory,
+            requirements.mip_tail_offset + array_layer as DeviceSize * requirements.mip_tail_stride,
+            requirements.mip_tail_size,
+            memory_binder,
+            flags,
+            &mut self.recorder.get_current_worker().retired_memory,
+        )?;
+        self.image_opaque_binds.push((handle, bind));
+        Ok(())
+    }
+
+    fn bind_image_internal(
+        &mut self,
+        image_id: ImageId,
+        bind: SparseImageBind,
+        memory_binder: Option<&dyn MemoryBinder>,
+    ) -> Result<()>
+    {
+        let mut images = self.gpu.write_images();
+        let image = images.get_mut(image_id)?;
+        let handle = image.handle();
+        let properties = image.properties();
+        let (memory, requirements) = image
+            .sparse_memory_mut()
+            .ok_or_else(|| Error::just_context("image is not sparse"))?;
+        if !memory.properties().flags.contains(SparseFlags::RESIDENCY) {
+            return Err(Error::just_context(format!(
+                "image was not created with {} sparse flag", SparseFlags::RESIDENCY,
+            )))
+        }
+        if bind.aspect_mask.count_ones() != 1 {
+            return Err(Error::just_context(format!(
+                "aspect mask {} must contain exactly one aspect", bind.aspect_mask,
+            )))
+        }
+        let requirements = *requirements
+            .iter()
+            .find(|r| r.format_properties.aspect_mask.contains(bind.aspect_mask))
+            .ok_or_else(|| Error::just_context(format!(
+                "image has no sparse memory requirements for aspect {}", bind.aspect_mask,
+            )))?;
+        if bind.mip_level >= requirements.mip_tail_first_lod {
+            return Err(Error::just_context(format!(
+                "mip level {} is in the mip tail starting from mip level {}",
+                bind.mip_level, requirements.mip_tail_first_lod,
+            )))
+        }
+        if bind.array_layer >= properties.array_layers {
+            return Err(Error::just_context(format!(
+                "array layer {} is out of range of image array layers {}",
+                bind.array_layer, properties.array_layers,
+            )))
+        }
+        let granularity = requirements.format_properties.image_granularity;
+        let mip_dimensions = properties.dimensions.lod(bind.mip_level);
+        if bind.extent.is_zero() {
+            return Err(Error::just_context(format!(
+                "extent {} must not be zero", bind.extent,
+            )))
+        }
+        if !bind.offset.is_in_range(mip_dimensions, bind.extent) {
+            return Err(Error::just_context(format!(
+                "offset {} + extent {} is out of range of mip level {} dimensions {mip_dimensions}",
+                bind.offset, bind.extent, bind.mip_level,
+            )))
+        }
+        if !bind.offset.is_multiple_of(granularity) {
+            return Err(Error::just_context(format!(
+                "offset {} is not a multiple of sparse block granularity {granularity}",
+                bind.offset,
+            )))
+        }
+        let end = bind.offset + bind.extent;
+        if !(bind.extent.width.is_multiple_of(granularity.width) ||
+                end.x == mip_dimensions.width) ||
+            !(bind.extent.height.is_multiple_of(granularity.height) ||
+                end.y == mip_dimensions.height) ||
+            !(bind.extent.depth.is_multiple_of(granularity.depth) ||
+                end.z == mip_dimensions.depth)
+        {
+            return Err(Error::just_context(format!(
+                "extent {} is not a multiple of sparse block granularity {granularity} and doesn't reach the edge of the mip level",
+                bind.extent,
+            )))
+        }
+        let first_block = Dimensions::new(
+            bind.offset.x / granularity.width,
+            bind.offset.y / granularity.height,
+            bind.offset.z / granularity.depth,
+        );
+        let block_count = Dimensions::new(
+            bind.extent.width.div_ceil(granularity.width),
+            bind.extent.height.div_ceil(granularity.height),
+            bind.extent.depth.div_ceil(granularity.depth),
+        );
+        let new_memory = memory_binder
+            .map(|binder| memory.alloc(binder, block_count.texel_count()))
+            .transpose()?;
+        let SparseImageBind { aspect_mask, mip_level, array_layer, .. } = bind;
+        memory.bind(
+            (0..block_count.depth).flat_map(|z| {
+                (0..block_count.height).flat_map(move |y| {
+                    (0..block_count.width).map(move |x| SparsePage::Block {
+                        aspect_mask,
+                        mip_level,
+                        array_layer,
+                        x: first_block.width + x,
+                        y: first_block.height + y,
+                        z: first_block.depth + z,
+                    })
+                })
+            }),
+            new_memory.as_ref(),
+            &mut self.recorder.get_current_worker().retired_memory,
+        );
+        self.image_binds.push((handle, vk::SparseImageMemoryBind {
+            subresource: vk::ImageSubresource {
+                aspect_mask: bind.aspect_mask.into(),
+                mip_level: bind.mip_level,
+                array_layer: bind.array_layer,
+            },
+            offset: bind.offset.into(),
+            extent: bind.extent.into(),
+            memory: new_memory
+                .as_ref()
+                .map(|memory| <_ as vk::Handle>::from_raw(memory.handle()))
+                .unwrap_or_default(),
+            memory_offset: new_memory
+                .as_ref()
+                .map(|memory| memory.offset())
+                .unwrap_or_default(),
+            flags: vk::SparseMemoryBindFlags::empty(),
+        }));
+        Ok(())
+    }
+}
+
+/// Binds or unbinds an opaque range of `memory` and returns the bind.
+fn bind_opaque(
+    memory: &mut SparseMemory,
+    offset: DeviceSize,
+    size: DeviceSize,
+    memory_binder: Option<&dyn MemoryBinder>,
+    flags: vk::SparseMemoryBindFlags,
+    retired: &mut Vec32<Arc<DeviceMemoryObj>>,
+) -> Result<vk::SparseMemoryBind>
+{
+    let (first_page, page_count) = memory.opaque_pages(offset, size)?;
+    let new_memory = memory_binder
+        .map(|binder| memory.alloc(binder, page_count))
+        .transpose()?;
+    memory.bind(
+        (first_page..first_page + page_count).map(SparsePage::Opaque),
+        new_memory.as_ref(),
+        retired,
+    );
+    Ok(vk::SparseMemoryBind {
+        resource_offset: offset,
+        size,
+        memory: new_memory
+            .as_ref()
+            .map(|memory| <_ as vk::Handle>::from_raw(memory.handle()))
+            .unwrap_or_default(),
+        memory_offset: new_memory
+            .as_ref()
+            .map(|memory| memory.offset())
+            .unwrap_or_default(),
+        flags,
+    })
+}
+
+/// The sparse binds of a [`SubmitInfo`].
+pub(crate) struct SparseBindSubmit<'a> {
+    pub buffer_binds: NonNullVec32<'a, (vk::Buffer, vk::SparseMemoryBind)>,
+    pub image_opaque_binds: NonNullVec32<'a, (vk::Image, vk::SparseMemoryBind)>,
+    pub image_binds: NonNullVec32<'a, (vk::Image, vk::SparseImageMemoryBind)>,
+}
+
+impl<'a> SparseBindSubmit<'a> {
+
+    pub fn new<Alloc1, Alloc2>(
+        binds: &SparseBinds<'_, Alloc1>,
+        alloc: &'a Alloc2,
+    ) -> Result<Self>
+        where
+            Alloc1: LocalAlloc + ?Sized,
+            Alloc2: LocalAlloc<Error = arena::Error>,
+    {
+        let mut submit = Self {
+            buffer_binds: NonNullVec32
+                ::with_capacity(binds.buffer_binds.len(), alloc)
+                .context("alloc failed")?,
+            image_opaque_binds: NonNullVec32
+                ::with_capacity(binds.image_opaque_binds.len(), alloc)
+                .context("alloc failed")?,
+            image_binds: NonNullVec32
+                ::with_capacity(binds.image_binds.len(), alloc)
+                .context("alloc failed")?,
+        };
+        submit.buffer_binds.fast_append(&binds.buffer_binds);
+        submit.image_opaque_binds.fast_append(&binds.image_opaque_binds);
+        submit.image_binds.fast_append(&binds.image_binds);
+        Ok(submit)
+    }
+
+    /// # Safety
+    /// `alloc` *must* be the allocator `self` was created with.
+    pub unsafe fn drop_and_free<Alloc>(&mut self, alloc: &Alloc)
+        where Alloc: LocalAlloc
+    {
+        unsafe {
+            self.buffer_binds.drop_and_free(alloc);
+            self.image_opaque_binds.drop_and_free(alloc);
+            self.image_binds.drop_and_free(alloc);
+        }
+    }
+}
+
+/// Submits the sparse binds of `submit` to `queue` with [`vkQueueBindSparse`][1].
+///
+/// The wait and signal semaphores of `submit` are passed as timeline semaphores.
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/vkQueueBindSparse.html
+pub(crate) fn queue_bind_sparse<Alloc>(
+    gpu: &Gpu,
+    queue: &DeviceQueue,
+    submit: &SubmitInfo<'_, Alloc>,
+) -> Result<()>
+    where Alloc: LocalAlloc
+{
+    let Some(binds) = &submit.sparse_binds else {
+        return Ok(())
+    };
+    let tmp_alloc = gpu.tmp_alloc();
+    let tmp_alloc = tmp_alloc.guard();
+    let mut wait_semaphores = FixedVec32
+        ::with_capacity(submit.wait_semaphore_infos.len(), &tmp_alloc)
+        .context("alloc failed")?;
+    let mut wait_values = FixedVec32
+        ::with_capacity(submit.wait_semaphore_infos.len(), &tmp_alloc)
+        .context("alloc failed")?;
+    for info in &submit.wait_semaphore_infos {
+        wait_semaphores.push(info.semaphore);
+        wait_values.push(info.value);
+    }
+    let mut signal_semaphores = FixedVec32
+        ::with_capacity(submit.signal_semaphore_infos.len(), &tmp_alloc)
+        .context("alloc failed")?;
+    let mut signal_values = FixedVec32
+        ::with_capacity(submit.signal_semaphore_infos.len(), &tmp_alloc)
+        .context("alloc failed")?;
+    for info in &submit.signal_semaphore_infos {
+        signal_semaphores.push(info.semaphore);
+        signal_values.push(info.value);
+    }
+    let mut buffer_binds = FixedVec32
+        ::with_capacity(binds.buffer_binds.len(), &tmp_alloc)
+        .context("alloc failed")?;
+    for (buffer, bind) in &binds.buffer_binds {
+        buffer_binds.push(vk::SparseBufferMemoryBindInfo {
+            buffer: *buffer,
+            bind_count: 1,
+            p_binds: bind,
+            ..Default::default()
+        });
+    }
+    let mut image_opaque_binds = FixedVec32
+        ::with_capacity(binds.image_opaque_binds.len(), &tmp_alloc)
+        .context("alloc failed")?;
+    for (image, bind) in &binds.image_opaque_binds {
+        image_opaque_binds.push(vk::SparseImageOpaqueMemoryBindInfo {
+            image: *image,
+            bind_count: 1,
+            p_binds: bind,
+            ..Default::default()
+        });
+    }
+    let mut image_binds = FixedVec32
+        ::with_capacity(binds.image_binds.len(), &tmp_alloc)
+        .context("alloc failed")?;
+    for (image, bind) in &binds.image_binds {
+        image_binds.push(vk::SparseImageMemoryBindInfo {
+            image: *image,
+            bind_count: 1,
+            p_binds: bind,
+            ..Default::default()
+        });
+    }
+    let mut timeline_info = vk::TimelineSemaphoreSubmitInfo {
+        wait_semaphore_value_count: wait_values.len(),
+        p_wait_semaphore_values: wait_values.as_ptr(),
+        signal_semaphore_value_count: signal_values.len(),
+        p_signal_semaphore_values: signal_values.as_ptr(),
+        ..Default::default()
+    };
+    let bind_info = vk::BindSparseInfo {
+        wait_semaphore_count: wait_semaphores.len(),
+        p_wait_semaphores: wait_semaphores.as_ptr(),
+        buffer_bind_count: buffer_binds.len(),
+        p_buffer_binds: buffer_binds.as_ptr(),
+        image_opaque_bind_count: image_opaque_binds.len(),
+        p_image_opaque_binds: image_opaque_binds.as_ptr(),
+        image_bind_count: image_binds.len(),
+        p_image_binds: image_binds.as_ptr(),
+        signal_semaphore_count: signal_semaphores.len(),
+        p_signal_semaphores: signal_semaphores.as_ptr(),
+        ..Default::default()
+    }.push_next(&mut timeline_info);
+    unsafe {
+        gpu.device().queue_bind_sparse(
+            queue.handle(),
+            &[bind_info],
+            vk::Fence::null(),
+        )
+    }.context_with(|| format!(
+        "failed to bind sparse memory on queue {queue:?}"
+    ))
+}
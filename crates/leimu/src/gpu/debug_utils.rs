@@ -0,0 +1,304 @@
+//! Support for [`VK_EXT_debug_utils`][1].
+//!
+//! [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_debug_utils.html
+
+use core::ffi::c_void;
+
+use std::{
+    ffi::CString,
+    io::Write,
+};
+
+use nox_ash::{
+    vk,
+    ext::debug_utils,
+};
+
+use nox_proc::BuildStructure;
+
+use crate::{
+    gpu::prelude::*,
+    error::*,
+    log::{self, warn, Level, LevelFmt},
+};
+
+/// The log target debug messages are forwarded to.
+pub const DEBUG_MESSAGE_TARGET: &str = "vulkan";
+
+/// Specifies how the [`debug messenger`][1] of an [`Instance`] is set up.
+///
+/// Messages are forwarded to [`nox_log`][2] with the [`target`][3] `"vulkan"` at the [`Level`]
+/// matching their severity.
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDebugUtilsMessengerEXT.html
+/// [2]: crate::log
+/// [3]: DEBUG_MESSAGE_TARGET
+#[derive(Clone, Copy, BuildStructure)]
+pub struct DebugUtilsInfo {
+    /// Specifies the most verbose [`Level`] of messages forwarded. The default is [`Level::Warn`].
+    ///
+    /// [`Level::Always`] forwards nothing.
+    #[default(Level::Warn)]
+    pub max_level: Level,
+    /// Specifies whether performance warnings are forwarded. The default is `false`.
+    pub performance: bool,
+}
+
+impl DebugUtilsInfo {
+
+    pub(crate) fn messenger_create_info(&self) -> vk::DebugUtilsMessengerCreateInfoEXT<'static> {
+        let mut message_severity = vk::DebugUtilsMessageSeverityFlagsEXT::empty();
+        if self.max_level >= Level::Error {
+            message_severity |= vk::DebugUtilsMessageSeverityFlagsEXT::ERROR;
+        }
+        if self.max_level >= Level::Warn {
+            message_severity |= vk::DebugUtilsMessageSeverityFlagsEXT::WARNING;
+        }
+        if self.max_level >= Level::Info {
+            message_severity |= vk::DebugUtilsMessageSeverityFlagsEXT::INFO;
+        }
+        if self.max_level >= Level::Trace {
+            message_severity |= vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE;
+        }
+        let mut message_type =
+            vk::DebugUtilsMessageTypeFlagsEXT::GENERAL |
+            vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION;
+        if self.performance {
+            message_type |= vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE;
+        }
+        vk::DebugUtilsMessengerCreateInfoEXT {
+            s_type: vk::StructureType::DEBUG_UTILS_MESSENGER_CREATE_INFO_EXT,
+            message_severity,
+            message_type,
+            pfn_user_callback: Some(debug_message_callback),
+            ..Default::default()
+        }
+    }
+}
+
+unsafe extern "system" fn debug_message_callback(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT<'_>,
+    _user_data: *mut c_void,
+) -> vk::Bool32
+{
+    let level =
+        if message_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+            LevelFmt::Error
+        } else if message_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+            LevelFmt::Warn
+        } else if message_severity.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
+            LevelFmt::Info
+        } else {
+            LevelFmt::Trace
+        };
+    let kind =
+        if message_type.contains(vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION) {
+            "validation"
+        } else if message_type.contains(vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE) {
+            "performance"
+        } else {
+            "general"
+        };
+    let (id_name, message) = unsafe {
+        callback_data.as_ref().map(|data| (
+            data.message_id_name_as_c_str().unwrap_or_default(),
+            data.message_as_c_str().unwrap_or_default(),
+        )).unwrap_or_default()
+    };
+    let id_name = id_name.to_string_lossy();
+    let message = message.to_string_lossy();
+    // the callback may run before the logger is initialized and must never unwind into the driver
+    if log::try_log(
+        DEBUG_MESSAGE_TARGET,
+        level,
+        format_args!("{kind} {id_name}: {message}"),
+    ).is_none() {
+        writeln!(
+            std::io::stderr(),
+            "[{DEBUG_MESSAGE_TARGET}] {kind} {id_name}: {message}",
+        ).ok();
+    }
+    vk::FALSE
+}
+
+/// The instance-level state of [`VK_EXT_debug_utils`][1].
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_debug_utils.html
+pub(crate) struct DebugMessenger {
+    instance: debug_utils::Instance,
+    handle: vk::DebugUtilsMessengerEXT,
+}
+
+impl DebugMessenger {
+
+    pub fn new(
+        entry: &nox_ash::Entry,
+        instance: &nox_ash::Instance,
+        info: &DebugUtilsInfo,
+    ) -> Result<Self>
+    {
+        let instance = debug_utils::Instance::new(entry, instance);
+        let handle = unsafe {
+            instance.create_debug_utils_messenger(&info.messenger_create_info(), None)
+        }.context("failed to create debug utils messenger")?;
+        Ok(Self {
+            instance,
+            handle,
+        })
+    }
+
+    /// # Safety
+    /// This *must* be called before the parent instance is destroyed.
+    pub unsafe fn destroy(&mut self) {
+        unsafe {
+            self.instance.destroy_debug_utils_messenger(self.handle, None);
+        }
+    }
+}
+
+fn to_c_string(name: &str) -> CString {
+    CString::new(name
+        .chars()
+        .filter(|&c| c != '\0')
+        .collect::<String>()
+    ).unwrap()
+}
+
+/// Tracks debug labels begun by a command, so that all of them are ended before the command
+/// finishes.
+#[derive(Default)]
+pub(crate) struct CommandLabels {
+    depth: u32,
+}
+
+impl CommandLabels {
+
+    /// # Safety
+    /// `command_buffer` *must* be a valid command buffer in the recording state.
+    pub unsafe fn begin(
+        &mut self,
+        device: &LogicalDevice,
+        command_buffer: vk::CommandBuffer,
+        name: &str,
+        color: [f32; 4],
+    ) {
+        self.depth += 1;
+        unsafe {
+            device.cmd_begin_debug_label(command_buffer, name, color);
+        }
+    }
+
+    /// # Safety
+    /// `command_buffer` *must* be the command buffer used to begin the label.
+    pub unsafe fn end(
+        &mut self,
+        device: &LogicalDevice,
+        command_buffer: vk::CommandBuffer,
+    ) -> Result<()>
+    {
+        if self.depth == 0 {
+            return Err(Error::just_context(
+                "no debug label to end"
+            ))
+        }
+        self.depth -= 1;
+        unsafe {
+            device.cmd_end_debug_label(command_buffer);
+        }
+        Ok(())
+    }
+
+    /// Returns an error if any labels were not ended.
+    pub fn finish(&self) -> Result<()> {
+        if self.depth != 0 {
+            return Err(Error::just_context(format!(
+                "{} debug labels were never ended", self.depth,
+            )))
+        }
+        Ok(())
+    }
+}
+
+impl LogicalDevice {
+
+    /// Gives a Vulkan object a name, which shows up in validation messages and debugging tools.
+    ///
+    /// Does nothing if [`VK_EXT_debug_utils`][1] is not enabled on the [`Instance`] or if `name`
+    /// is empty.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkSetDebugUtilsObjectNameEXT.html>
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_debug_utils.html
+    pub fn set_object_name<T: vk::Handle>(&self, handle: T, name: &str) {
+        let Some(debug_utils) = self.debug_utils_device() else {
+            return
+        };
+        if name.is_empty() {
+            return
+        }
+        let name = to_c_string(name);
+        let name_info = vk::DebugUtilsObjectNameInfoEXT::default()
+            .object_handle(handle)
+            .object_name(&name);
+        if let Err(err) = unsafe {
+            debug_utils.set_debug_utils_object_name(&name_info)
+        } {
+            warn!("failed to set object name {name:?}: {err}");
+        }
+    }
+
+    /// Opens a debug label region in `command_buffer`.
+    ///
+    /// Does nothing if [`VK_EXT_debug_utils`][1] is not enabled on the [`Instance`].
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBeginDebugUtilsLabelEXT.html>
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_debug_utils.html
+    pub unsafe fn cmd_begin_debug_label(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        name: &str,
+        color: [f32; 4],
+    ) {
+        let Some(debug_utils) = self.debug_utils_device() else {
+            return
+        };
+        let name = to_c_string(name);
+        let label = vk::DebugUtilsLabelEXT::default()
+            .label_name(&name)
+            .color(color);
+        unsafe {
+            debug_utils.cmd_begin_debug_utils_label(command_buffer, &label);
+        }
+    }
+
+    /// Closes a debug label region in `command_buffer`.
+    ///
+    /// Does nothing if [`VK_EXT_debug_utils`][1] is not enabled on the [`Instance`].
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdEndDebugUtilsLabelEXT.html>
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_debug_utils.html
+    pub unsafe fn cmd_end_debug_label(
+        &self,
+        command_buffer: vk::CommandBuffer,
+    ) {
+        let Some(debug_utils) = self.debug_utils_device() else {
+            return
+        };
+        unsafe {
+            debug_utils.cmd_end_debug_utils_label(command_buffer);
+        }
+    }
+}
@@ -14,6 +14,7 @@ use super::{
 };
 
 pub type DeviceSize = u64;
+pub type DeviceAddress = u64;
 
 /// Sets which base device features to enable.
 ///
@@ -157,7 +158,7 @@ impl BaseDeviceFeatures {
         if self.sparse_residency_image_2d && (available.sparse_residency_image2_d == 0) {
             return Some(MissingDeviceFeatureError::new("sparse_residency_image_2d"))
         }
-        if self.sparse_residency_image_3d && (available.sparse_residency_image2_d == 0) {
+        if self.sparse_residency_image_3d && (available.sparse_residency_image3_d == 0) {
             return Some(MissingDeviceFeatureError::new("sparse_residency_image_3d"))
         }
         if self.sparse_residency_2_samples && (available.sparse_residency2_samples == 0) {
@@ -1023,6 +1024,34 @@ impl From<ImageCopy> for vk::ImageCopy2<'_> {
     }
 }
 
+/// Specifies a region of a multisample image resolved with [`CopyCommands::resolve_image`].
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkImageResolve2.html>
+#[derive(Clone, Copy, PartialEq, Eq, Hash, BuildStructure)]
+pub struct ImageResolve {
+    pub src_subresource: ImageSubresourceLayers,
+    pub src_offset: ImageCopyOffset,
+    pub dst_subresource: ImageSubresourceLayers,
+    pub dst_offset: ImageCopyOffset,
+    pub extent: Dimensions,
+}
+
+impl From<ImageResolve> for vk::ImageResolve2<'_> {
+
+    #[inline]
+    fn from(value: ImageResolve) -> Self {
+        Self {
+            src_subresource: value.src_subresource.into(),
+            src_offset: value.src_offset.into(),
+            dst_subresource: value.dst_subresource.into(),
+            dst_offset: value.dst_offset.into(),
+            extent: value.extent.into(),
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Default, Clone, Copy, BuildStructure)]
 pub struct BufferImageCopy {
     pub buffer_offset: DeviceSize,
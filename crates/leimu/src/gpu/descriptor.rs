@@ -148,6 +148,7 @@ pub struct DescriptorSetInfo<'a> {
     pub out_id: &'a mut DescriptorSetId,
     pub shader_set_id: ShaderSetId,
     pub descriptor_set_index: u32,
+    pub variable_descriptor_count: Option<u32>,
 }
 
 impl<'a> DescriptorSetInfo<'a> {
@@ -162,13 +163,31 @@ impl<'a> DescriptorSetInfo<'a> {
             out_id,
             shader_set_id,
             descriptor_set_index,
+            variable_descriptor_count: None,
         }
     }
+
+    /// Specifies the descriptor count of the last binding of a set with the
+    /// [`variable descriptor count flag`][1].
+    ///
+    /// If not specified, the descriptor count of the layout is used.
+    ///
+    /// # Valid usage
+    /// - `count` *must* be less than or equal to the descriptor count of the layout binding.
+    ///
+    /// [1]: DescriptorSetLayoutFlags::VARIABLE_DESCRIPTOR_COUNT
+    #[inline(always)]
+    pub fn with_variable_descriptor_count(mut self, count: u32) -> Self {
+        self.variable_descriptor_count = Some(count);
+        self
+    }
 }
 
 #[derive(Clone, Copy)]
 pub(crate) struct DescriptorSetBuffer {
     pub buffer: Option<(BufferId, DeviceSize, DeviceSize)>,
+    pub buffer_view: Option<BufferViewId>,
+    pub acceleration_structure: Option<AccelerationStructureId>,
     pub buffer_track_id: TrackedDescriptorSetId<BufferMeta>,
 }
 
@@ -193,6 +212,8 @@ pub(crate) struct DescriptorSetBinding {
     binding: u32,
     ty: DescriptorType,
     descriptors: DescriptorSetDescriptors,
+    /// The offset of the binding within the descriptor set in a descriptor buffer.
+    offset: DeviceSize,
 }
 
 impl DescriptorSetBinding {
@@ -263,7 +284,10 @@ pub(crate) struct DescriptorSet {
     stage_flags: ShaderStageFlags,
     arena: (NonNull<u8>, usize),
     last_used_frame: u64,
+    layout_flags: DescriptorSetLayoutFlags,
     flags: u32,
+    buffer_offset: DeviceSize,
+    buffer_size: DeviceSize,
 }
 
 unsafe impl Send for DescriptorSet {}
@@ -278,7 +302,9 @@ impl DescriptorSet {
     fn new(
         bindings: NonNullVec32<'static, DescriptorSetBinding>,
         stage_flags: ShaderStageFlags,
+        layout_flags: DescriptorSetLayoutFlags,
         arena: Arena,
+        buffer_size: DeviceSize,
     ) -> Self
     {
         Self {
@@ -287,7 +313,10 @@ impl DescriptorSet {
             stage_flags,
             arena: arena.into_raw_parts(),
             last_used_frame: 0,
+            layout_flags,
             flags: 0,
+            buffer_offset: 0,
+            buffer_size,
         }
     }
 
@@ -296,11 +325,22 @@ impl DescriptorSet {
         self.handle
     }
 
+    /// Returns the offset of the set within the descriptor buffer of its pool.
+    #[inline(always)]
+    pub fn descriptor_buffer_offset(&self) -> DeviceSize {
+        self.buffer_offset
+    }
+
     #[inline(always)]
     pub fn bindings(&self) -> &[DescriptorSetBinding] {
         &self.bindings
     }
 
+    #[inline(always)]
+    pub fn bindings_mut(&mut self) -> &mut [DescriptorSetBinding] {
+        &mut self.bindings
+    }
+
     #[inline(always)]
     pub fn is_maybe_poisoned(&self) -> bool {
         self.flags & Self::MAYBE_POISONED == Self::MAYBE_POISONED
@@ -311,6 +351,43 @@ impl DescriptorSet {
         self.flags & Self::IS_VALID == Self::IS_VALID
     }
 
+    #[inline(always)]
+    pub fn layout_flags(&self) -> DescriptorSetLayoutFlags {
+        self.layout_flags
+    }
+
+    #[inline(always)]
+    pub fn last_used_frame(&self) -> u64 {
+        self.last_used_frame
+    }
+
+    /// Clears descriptors of a partially bound set, which refer to destroyed resources.
+    fn clear_invalid_descriptors(
+        &mut self,
+        gpu: &Gpu,
+    ) {
+        for binding in &mut self.bindings {
+            for buffer in binding.buffer_descriptors_mut() {
+                if buffer.buffer.is_some_and(|(id, _, _)| !gpu.is_buffer_valid(id)) ||
+                    buffer.buffer_view.is_some_and(|id| !gpu.is_buffer_view_valid(id)) ||
+                    buffer.acceleration_structure.is_some_and(|id|
+                        !gpu.is_acceleration_structure_valid(id)
+                    )
+                {
+                    buffer.buffer = None;
+                    buffer.buffer_view = None;
+                    buffer.acceleration_structure = None;
+                }
+            }
+            for image in binding.image_descriptors_mut() {
+                if image.image.is_some_and(|(id, _)| !gpu.is_image_view_valid(id)) {
+                    image.image = None;
+                }
+            }
+        }
+        self.flags |= Self::IS_VALID;
+    }
+
     fn validate_bindings(
         &mut self,
         gpu: &Gpu,
@@ -320,6 +397,8 @@ impl DescriptorSet {
             for binding in &mut self.bindings {
                 for buffer in binding.buffer_descriptors_mut() {
                     buffer.buffer.take();
+                    buffer.buffer_view.take();
+                    buffer.acceleration_structure.take();
                 }
                 for image in binding.image_descriptors_mut() {
                     image.sampler.take();
@@ -329,11 +408,20 @@ impl DescriptorSet {
             self.flags &= !(Self::MAYBE_POISONED | Self::IS_VALID);
             return
         }
+        if self.layout_flags.contains(DescriptorSetLayoutFlags::PARTIALLY_BOUND) {
+            self.clear_invalid_descriptors(gpu);
+            return
+        }
         for binding in &mut self.bindings {
             let ty = binding.ty();
             for buffer in binding.buffer_descriptors() {
                 if let Some((id, _, _)) = buffer.buffer {
-                    if !gpu.is_buffer_valid(id) {
+                    if !gpu.is_buffer_valid(id) ||
+                        buffer.buffer_view.is_some_and(|id| !gpu.is_buffer_view_valid(id)) ||
+                        buffer.acceleration_structure.is_some_and(|id|
+                            !gpu.is_acceleration_structure_valid(id)
+                        )
+                    {
                         self.flags &= !Self::IS_VALID;
                         return
                     }
@@ -476,6 +564,27 @@ impl<'a> DescriptorInfos<'a> {
         Self(DescriptorInfosInner::Image(images))
     }
 
+    /// Texel buffer descriptor writes.
+    ///
+    /// Used with [`DescriptorType::UniformTexelBuffer`] and
+    /// [`DescriptorType::StorageTexelBuffer`] descriptors.
+    ///
+    /// Buffer views are created with [`Gpu::create_buffer_view`].
+    #[inline(always)]
+    pub fn texel_buffers(buffer_views: &'a [BufferViewId]) -> Self {
+        Self(DescriptorInfosInner::TexelBuffer(buffer_views))
+    }
+
+    /// Acceleration structure descriptor writes.
+    ///
+    /// Used with [`DescriptorType::AccelerationStructure`] descriptors.
+    ///
+    /// Acceleration structures are created with [`Gpu::create_acceleration_structure`].
+    #[inline(always)]
+    pub fn acceleration_structures(acceleration_structures: &'a [AccelerationStructureId]) -> Self {
+        Self(DescriptorInfosInner::AccelerationStructure(acceleration_structures))
+    }
+
     /// [`Inline uniform block`][1] write.
     ///
     /// # Valid usage
@@ -509,6 +618,8 @@ impl<'a> DescriptorInfos<'a> {
         match self.0 {
             DescriptorInfosInner::Buffer(b) => b.len() as u32,
             DescriptorInfosInner::Image(i) => i.len() as u32,
+            DescriptorInfosInner::TexelBuffer(t) => t.len() as u32,
+            DescriptorInfosInner::AccelerationStructure(a) => a.len() as u32,
             DescriptorInfosInner::InlineUniformBlock(b) => b.len() as u32,
         }
     }
@@ -523,6 +634,16 @@ impl<'a> DescriptorInfos<'a> {
         matches!(self.0, DescriptorInfosInner::Image(_))
     }
 
+    #[inline(always)]
+    pub fn is_texel_buffers(&self) -> bool {
+        matches!(self.0, DescriptorInfosInner::TexelBuffer(_))
+    }
+
+    #[inline(always)]
+    pub fn is_acceleration_structures(&self) -> bool {
+        matches!(self.0, DescriptorInfosInner::AccelerationStructure(_))
+    }
+
     #[inline(always)]
     pub fn is_inline_uniform_block(&self) -> bool {
         matches!(self.0, DescriptorInfosInner::InlineUniformBlock(_))
@@ -544,6 +665,22 @@ impl<'a> DescriptorInfos<'a> {
         }
     }
 
+    #[inline(always)]
+    pub fn as_texel_buffers(&self) -> Option<&[BufferViewId]> {
+        match self.0 {
+            DescriptorInfosInner::TexelBuffer(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn as_acceleration_structures(&self) -> Option<&[AccelerationStructureId]> {
+        match self.0 {
+            DescriptorInfosInner::AccelerationStructure(a) => Some(a),
+            _ => None,
+        }
+    }
+
     #[inline(always)]
     pub fn as_inline_uniform_block(&self) -> Option<&[u32]> {
         match self.0 {
@@ -557,6 +694,8 @@ impl<'a> DescriptorInfos<'a> {
 enum DescriptorInfosInner<'a> {
     Buffer(&'a [DescriptorBufferInfo]),
     Image(&'a [DescriptorImageInfo]),
+    TexelBuffer(&'a [BufferViewId]),
+    AccelerationStructure(&'a [AccelerationStructureId]),
     InlineUniformBlock(&'a [u32]),
 }
 
@@ -631,6 +770,16 @@ impl<'a> WriteDescriptorSet<'a> {
         self.infos.as_images()
     }
 
+    #[inline(always)]
+    pub fn texel_buffer_infos(&self) -> Option<&[BufferViewId]> {
+        self.infos.as_texel_buffers()
+    }
+
+    #[inline(always)]
+    pub fn acceleration_structure_infos(&self) -> Option<&[AccelerationStructureId]> {
+        self.infos.as_acceleration_structures()
+    }
+
     #[inline(always)]
     pub fn inline_uniform_block(&self) -> Option<&[u32]> {
         self.infos.as_inline_uniform_block()
@@ -669,6 +818,8 @@ mod inner {
         pub tracked_buffers: AHashMap<BufferId, SlotMap<DescriptorSetInnerId>>,
         pub tracked_image_views: AHashMap<ImageViewId, SlotMap<DescriptorSetInnerId>>,
         pub poisoned: bool,
+        /// The descriptor buffer sets are allocated from, if the pool is backed by one.
+        pub descriptor_buffer: Option<DescriptorBuffer>,
     }
 
     impl Inner {
@@ -752,11 +903,32 @@ impl DescriptorPool {
             }
             info = info.push_next(uniform_block_info);
         }
-        let handle = unsafe {
-            device
-                .create_descriptor_pool(&info, None)
-                .context("failed to create descriptor pool")?
-        };
+        let (handle, descriptor_buffer) =
+            if device.get_device_attribute(ext::descriptor_buffer::Attributes::IS_ENABLED)
+                .bool().unwrap_or(false)
+            {
+                let sizes = DescriptorSizes::new(&device);
+                let alignment = device
+                    .get_device_attribute(ext::descriptor_buffer::Attributes::DESCRIPTOR_BUFFER_OFFSET_ALIGNMENT)
+                    .device_size()
+                    .unwrap_or(1);
+                // layouts may pad their bindings, so this is an estimate with room for aligning
+                // each set
+                let size = pools
+                    .iter()
+                    .map(|(&ty, pool)| pool.size as DeviceSize * sizes.get(ty))
+                    .sum::<DeviceSize>() + max_sets as DeviceSize * alignment;
+                let buffer = DescriptorBuffer::new(device.clone(), sizes, pools.keys().copied(), size)
+                    .context("failed to create descriptor buffer")?;
+                (vk::DescriptorPool::null(), Some(buffer))
+            } else {
+                let handle = unsafe {
+                    device
+                        .create_descriptor_pool(&info, None)
+                        .context("failed to create descriptor pool")?
+                };
+                (handle, None)
+            };
         Ok(Self {
             inner: Arc::new(RwLock::new(inner::Inner {
                 device,
@@ -770,6 +942,7 @@ impl DescriptorPool {
                 tracked_image_views: AHashMap::default(),
                 pending_validations: AHashSet::default(),
                 poisoned: false,
+                descriptor_buffer,
             }))
         })
     }
@@ -783,6 +956,48 @@ impl DescriptorPool {
         }
     }
 
+    /// Invalidates descriptors, which refer to a buffer that was bound to new memory.
+    pub fn buffer_moved(&self, id: BufferId) {
+        let mut inner = self.inner.write();
+        let Some(tracked) = inner.tracked_buffers.remove(&id) else {
+            return
+        };
+        for &set_id in tracked.values() {
+            if let Ok(set) = inner.allocated_sets.get_mut(set_id.0) {
+                for binding in set.bindings_mut() {
+                    for buffer in binding.buffer_descriptors_mut() {
+                        if buffer.buffer.is_some_and(|(buffer_id, _, _)| buffer_id == id) {
+                            buffer.buffer = None;
+                            buffer.buffer_view = None;
+                            buffer.acceleration_structure = None;
+                        }
+                    }
+                }
+            }
+            inner.pending_validations.insert(set_id);
+        }
+    }
+
+    pub fn buffer_view_delete(&self, id: BufferViewId) {
+        let mut inner = self.inner.write();
+        let inner = &mut *inner;
+        if let Some(tracked) = inner.tracked_buffers.get(&id.buffer_id()) {
+            for &set_id in tracked.values() {
+                inner.pending_validations.insert(set_id);
+            }
+        }
+    }
+
+    pub fn acceleration_structure_delete(&self, id: AccelerationStructureId) {
+        let mut inner = self.inner.write();
+        let inner = &mut *inner;
+        if let Some(tracked) = inner.tracked_buffers.get(&id.buffer_id()) {
+            for &set_id in tracked.values() {
+                inner.pending_validations.insert(set_id);
+            }
+        }
+    }
+
     pub fn image_view_delete(&self, id: ImageViewId) {
         let mut inner = self.inner.write();
         if let Some(tracked) = inner.tracked_image_views.remove(&id) {
@@ -817,6 +1032,10 @@ impl DescriptorPool {
         let mut sets = FixedVec32
             ::with_capacity(count, tmp_alloc)
             .context("alloc failed")?;
+        let mut variable_counts = FixedVec32
+            ::with_capacity(count, tmp_alloc)
+            .context("alloc failed")?;
+        let mut has_variable_counts = false;
         for (i, info) in set_infos.iter().enumerate() {
             let set = shader_cache.read().get_shader_set(info.shader_set_id);
             let set = set.await
@@ -839,22 +1058,82 @@ impl DescriptorPool {
                     "that has the push descriptor flag set",
                 )))
             }
+            let variable_count = layout.flags
+                .contains(DescriptorSetLayoutFlags::VARIABLE_DESCRIPTOR_COUNT)
+                .then(|| {
+                    let max_count = layout.bindings
+                        .last()
+                        .map(|binding| binding.descriptor_count)
+                        .unwrap_or(0);
+                    let count = info.variable_descriptor_count.unwrap_or(max_count);
+                    if count > max_count {
+                        return Err(Error::just_context(format!(
+                            "{}{}",
+                            format_args!("variable descriptor count {count} of descriptor set with index {} of shader set {} ",
+                                info.descriptor_set_index, info.shader_set_id,
+                            ),
+                            format_args!("is larger than the layout descriptor count {max_count}"),
+                        )))
+                    }
+                    Ok(count)
+                }).transpose()?;
+            if variable_count.is_none() && info.variable_descriptor_count.is_some() {
+                log::warn!(
+                    "variable descriptor count given for descriptor set with index {} of shader set {}, {}",
+                    info.descriptor_set_index, info.shader_set_id,
+                    "but the layout doesn't have the variable descriptor count flag",
+                );
+            }
             set_layouts.push(layout.handle);
-            sets.push((set, info.descriptor_set_index));
+            variable_counts.push(variable_count.unwrap_or(0));
+            has_variable_counts |= variable_count.is_some();
+            sets.push((set, info.descriptor_set_index, variable_count));
         }
         let mut new_sets = FixedVec32
             ::with_capacity(count, tmp_alloc)
             .context("alloc failed")?;
         let mut inner = self.inner.write();
-        for (set, index) in sets {
+        for (set, index, variable_count) in sets {
             let set = &set.descriptor_set_layouts()[index as usize];
+            let last_binding = set.bindings.len().saturating_sub(1);
+            let binding_descriptor_count = |i: u32, binding: &DescriptorSetLayoutBinding| {
+                variable_count
+                    .filter(|_| i == last_binding)
+                    .unwrap_or(binding.descriptor_count)
+            };
+            let buffer_layout =
+                if inner.descriptor_buffer.is_some() {
+                    Some(set.descriptor_buffer_layout
+                        .as_ref()
+                        .ok_or_else(|| Error::just_context(
+                            "descriptor set layout was not created for descriptor buffers"
+                        ))?
+                    )
+                } else {
+                    None
+                };
+            let buffer_size = buffer_layout.map(|layout| {
+                let Some(count) = variable_count else {
+                    return layout.size
+                };
+                let (Some(&offset), Some(binding)) = (layout.binding_offsets.last(), set.bindings.last()) else {
+                    return layout.size
+                };
+                let descriptor_size = inner.descriptor_buffer
+                    .as_ref()
+                    .map(|buffer| buffer.descriptor_size(binding.descriptor_type))
+                    .unwrap_or(0);
+                offset + count as DeviceSize * descriptor_size
+            }).unwrap_or(0);
             let alloc_size: usize = set.bindings
-                .iter().map(|binding|
+                .iter().enumerate().map(|(i, binding)|
                     size_of::<DescriptorSetBinding>() +
-                    if binding.descriptor_type.is_buffer() {
-                        size_of::<DescriptorSetBuffer>() * binding.descriptor_count as usize
+                    if binding.descriptor_type.is_buffer() ||
+                        binding.descriptor_type.is_acceleration_structure()
+                    {
+                        size_of::<DescriptorSetBuffer>() * binding_descriptor_count(i as u32, binding) as usize
                     } else if binding.descriptor_type.is_image() {
-                        size_of::<ImageDescriptor>() * binding.descriptor_count as usize
+                        size_of::<ImageDescriptor>() * binding_descriptor_count(i as u32, binding) as usize
                     } else {
                         0
                     }
@@ -868,7 +1147,9 @@ impl DescriptorPool {
                 .into_static();
             bindings.try_extend(set.bindings
                 .iter()
-                .map(|binding| {
+                .enumerate()
+                .map(|(i, binding)| {
+                    let descriptor_count = binding_descriptor_count(i as u32, binding);
                     if let Some(pool) = inner.pools.get_mut(&binding.descriptor_type) {
                         let used = pool.used + count;
                         if used > pool.size {
@@ -880,26 +1161,29 @@ impl DescriptorPool {
                             pool.used = used;
                             let descriptors =
                                 match binding.descriptor_type {
-                                    crate::buffer_descriptor_types!() => {
+                                    crate::buffer_descriptor_types!() |
+                                    DescriptorType::AccelerationStructure => {
                                         let mut bufs = NonNullVec32::with_capacity(
-                                            binding.descriptor_count,
+                                            descriptor_count,
                                             &alloc,
                                         ).context("alloc failed")?.into_static();
                                         bufs.resize(
-                                            binding.descriptor_count,
+                                            descriptor_count,
                                             DescriptorSetBuffer {
-                                                buffer: None, buffer_track_id: Default::default(),
+                                                buffer: None, buffer_view: None,
+                                                acceleration_structure: None,
+                                                buffer_track_id: Default::default(),
                                             },
                                         );
                                         DescriptorSetDescriptors::Buffers(bufs)
                                     },
                                     crate::image_descriptor_types!() => {
                                         let mut imgs = NonNullVec32::with_capacity(
-                                            binding.descriptor_count,
+                                            descriptor_count,
                                             &alloc
                                         ).context("alloc failed")?.into_static();
                                         imgs.resize(
-                                            binding.descriptor_count,
+                                            descriptor_count,
                                             ImageDescriptor {
                                                 sampler: None,
                                                 image: None, image_track_id: Default::default(),
@@ -926,6 +1210,9 @@ impl DescriptorPool {
                                 binding: binding.binding,
                                 ty: binding.descriptor_type,
                                 descriptors,
+                                offset: buffer_layout
+                                    .map(|layout| layout.binding_offsets[i])
+                                    .unwrap_or(0),
                             })
                         }
                     } else {
@@ -938,23 +1225,49 @@ impl DescriptorPool {
             )?;
             new_sets.push(DescriptorSet::new(
                 bindings, set.stage_flags,
+                set.flags,
                 alloc,
+                buffer_size,
             ));
         }
-        let info = vk::DescriptorSetAllocateInfo {
+        let mut variable_count_info = vk::DescriptorSetVariableDescriptorCountAllocateInfo {
+            s_type: vk::StructureType::DESCRIPTOR_SET_VARIABLE_DESCRIPTOR_COUNT_ALLOCATE_INFO,
+            descriptor_set_count: count,
+            p_descriptor_counts: variable_counts.as_ptr(),
+            ..Default::default()
+        };
+        let mut info = vk::DescriptorSetAllocateInfo {
             s_type: vk::StructureType::DESCRIPTOR_SET_ALLOCATE_INFO,
             descriptor_pool: inner.handle,
             descriptor_set_count: count,
             p_set_layouts: set_layouts.as_ptr(),
             ..Default::default()
         };
+        if has_variable_counts {
+            info = info.push_next(&mut variable_count_info);
+        }
         let mut handles = FixedVec32
             ::with_len(count, Default::default(), tmp_alloc)
             .context("alloc failed")?;
-        unsafe {
-            inner.device.allocate_descriptor_sets(&info, &mut handles)
-            .context("failed to allocate descriptor sets")?
-        };
+        if let Some(descriptor_buffer) = &mut inner.descriptor_buffer {
+            for i in 0..new_sets.len() as usize {
+                let Some(offset) = descriptor_buffer.allocate(new_sets[i].buffer_size) else {
+                    for set in &new_sets[..i] {
+                        descriptor_buffer.free(set.buffer_offset, set.buffer_size);
+                    }
+                    return Err(Error::just_context(format!(
+                        "descriptor buffer was full when allocating {} bytes",
+                        new_sets[i].buffer_size,
+                    )))
+                };
+                new_sets[i].buffer_offset = offset;
+            }
+        } else {
+            unsafe {
+                inner.device.allocate_descriptor_sets(&info, &mut handles)
+                .context("failed to allocate descriptor sets")?
+            };
+        }
         for (i, mut new_set) in new_sets.into_iter().enumerate() {
             let handle = handles[i];
             new_set.handle = handle;
@@ -1007,14 +1320,20 @@ impl DescriptorPool {
                         inner.allocated_inline_uniform_block_bindings -= 1;
                     }
                 }
-                descriptor_sets.push(set.handle);
+                if let Some(descriptor_buffer) = &mut inner.descriptor_buffer {
+                    descriptor_buffer.free(set.buffer_offset, set.buffer_size);
+                } else {
+                    descriptor_sets.push(set.handle);
+                }
             }
         }
-        unsafe {
-            inner.device.free_descriptor_sets(
-                inner.handle,
-                &descriptor_sets,
-            ).context("failed to free descriptor sets")?;
+        if inner.descriptor_buffer.is_none() {
+            unsafe {
+                inner.device.free_descriptor_sets(
+                    inner.handle,
+                    &descriptor_sets,
+                ).context("failed to free descriptor sets")?;
+            }
         }
         Ok(())
     }
@@ -1073,6 +1392,29 @@ impl inner::Inner {
         Ok(())
     } 
 
+    /// Returns the address info of a buffer descriptor, if the pool is backed by a descriptor
+    /// buffer.
+    #[inline(always)]
+    fn descriptor_address_info(
+        &self,
+        buffer: &BufferMeta,
+        offset: DeviceSize,
+        size: DeviceSize,
+        format: vk::Format,
+    ) -> Result<Option<vk::DescriptorAddressInfoEXT<'static>>>
+    {
+        let Some(descriptor_buffer) = &self.descriptor_buffer else {
+            return Ok(None)
+        };
+        Ok(Some(vk::DescriptorAddressInfoEXT {
+            s_type: vk::StructureType::DESCRIPTOR_ADDRESS_INFO_EXT,
+            address: descriptor_buffer.buffer_address(buffer)? + offset,
+            range: size,
+            format,
+            ..Default::default()
+        }))
+    }
+
     #[inline(always)]
     fn track_image(
         &mut self,
@@ -1139,7 +1481,9 @@ impl<'a> DescriptorPoolWriteGuard<'a> {
         let mut handle = self.get_descriptor_set_handle(id)?;
         unsafe {
             let set = handle.get().0;
-            if set.last_used_frame > finished_frame {
+            if set.last_used_frame > finished_frame &&
+                !set.layout_flags.contains(DescriptorSetLayoutFlags::UPDATE_AFTER_BIND)
+            {
                 return Err(Error::just_context(format!(
                     "attempting to update descriptor set {id} while it is still in use by a queue"
                 )))
@@ -1240,6 +1584,11 @@ pub(crate) enum DescriptorUpdateInfos<'c, Alloc>
 {
     Buffer(FixedVec32<'c, vk::DescriptorBufferInfo, Alloc>),
     Image(FixedVec32<'c, vk::DescriptorImageInfo, Alloc>),
+    TexelBuffer(FixedVec32<'c, vk::BufferView, Alloc>),
+    AccelerationStructure(
+        FixedVec32<'c, vk::AccelerationStructureKHR, Alloc>,
+        vk::WriteDescriptorSetAccelerationStructureKHR<'static>,
+    ),
     InlineUniformBlock(vk::WriteDescriptorSetInlineUniformBlock<'static>),
 }
 
@@ -1257,6 +1606,10 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
         self.handle
     }
 
+    /// Updates the descriptor set.
+    ///
+    /// If the pool is backed by a descriptor buffer, descriptors are written to the buffer
+    /// together with their metadata, so a failed update never poisons the pool.
     #[inline(always)]
     pub fn update<'c, Alloc>(
         &mut self,
@@ -1268,14 +1621,19 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
             Alloc: LocalAlloc<Error = arena::Error>,
     {
         if let Some(infos) = write.infos.as_buffers() {
+            let can_poison = self.pool.descriptor_buffer.is_none();
             let mut pool = RaiiHandle::new(&mut *self.pool, |pool| {
-                pool.poisoned = true;
+                if can_poison {
+                    pool.poisoned = true;
+                }
             });
             let (set, id) = unsafe {
                 self.handle.get()
             };
             pool.add_pending_validation(id);
-            set.flags |= DescriptorSet::MAYBE_POISONED;
+            if can_poison {
+                set.flags |= DescriptorSet::MAYBE_POISONED;
+            }
             let Some(binding) = set.bindings.iter_mut().find(|b| b.binding == write.binding) else {
                 return Err(Error::just_context(format!(
                     "invalid buffer write binding {} for descriptor set {}",
@@ -1295,6 +1653,12 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
                 )))
             }
             let ty = binding.ty;
+            if ty.is_texel_buffer() {
+                return Err(Error::just_context(format!(
+                    "texel buffer descriptor type {ty} on buffer write (binding {} for descriptor set {}), use texel buffer writes instead",
+                    write.binding, write.set_id,
+                )))
+            }
             let Some(usage) = ty.buffer_usage() else {
                 return Err(Error::just_context(format!(
                     "non-buffer descriptor on buffer write (binding {} for descriptor set {})",
@@ -1305,14 +1669,12 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
                 ::with_capacity(infos.len() as u32, alloc)
                 .context("alloc failed")?;
             let starting_idx = write.starting_index as usize;
+            let base_offset = set.buffer_offset + binding.offset;
             for (i, descriptor) in binding.buffer_descriptors_mut()[starting_idx..starting_idx + infos.len()]
                 .iter_mut()
                 .enumerate()
             {
                 let info = infos[i];
-                if let Some((id, _, _)) = descriptor.buffer {
-                    pool.untrack_buffer(id, descriptor.buffer_track_id)?;
-                }
                 let buffers = gpu.read_buffers();
                 let buffer = buffers
                     .get(info.buffer_id)
@@ -1339,8 +1701,32 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
                         )
                     )))
                 }
+                let address_info = pool.descriptor_address_info(
+                    buffer, info.offset, info.size, vk::Format::UNDEFINED,
+                ).context_with(|| format!(
+                    "failed to get buffer {} address for descriptor set {} write (binding {}, index {}, type {ty})",
+                    info.buffer_id, write.set_id, write.binding, starting_idx + i,
+                ))?;
+                if let Some((id, _, _)) = descriptor.buffer {
+                    pool.untrack_buffer(id, descriptor.buffer_track_id)?;
+                }
                 descriptor.buffer = Some((info.buffer_id, info.offset, info.size));
+                descriptor.buffer_view = None;
+                descriptor.acceleration_structure = None;
                 descriptor.buffer_track_id = pool.track_buffer(info.buffer_id, id);
+                if let (Some(descriptor_buffer), Some(address_info)) =
+                    (&pool.descriptor_buffer, &address_info)
+                {
+                    unsafe {
+                        descriptor_buffer.write_descriptor(
+                            base_offset, (starting_idx + i) as u32, ty,
+                            DescriptorData::Buffer(address_info),
+                        ).context_with(|| format!(
+                            "failed to write descriptor buffer for descriptor set {} write (binding {}, index {}, type {ty})",
+                            write.set_id, write.binding, starting_idx + i,
+                        ))?;
+                    }
+                }
                 let vk_info = vk::DescriptorBufferInfo {
                     buffer: buffer.handle(),
                     offset: info.offset,
@@ -1351,14 +1737,19 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
             pool.into_inner();
             Ok((ty.into(), DescriptorUpdateInfos::Buffer(vk_infos)))
         } else if let Some(infos) = write.image_infos() {
+            let can_poison = self.pool.descriptor_buffer.is_none();
             let mut pool = RaiiHandle::new(&mut *self.pool, |pool| {
-                pool.poisoned = true;
+                if can_poison {
+                    pool.poisoned = true;
+                }
             });
             let (set, id) = unsafe {
                 self.handle.get()
             };
             pool.add_pending_validation(id);
-            set.flags |= DescriptorSet::MAYBE_POISONED;
+            if can_poison {
+                set.flags |= DescriptorSet::MAYBE_POISONED;
+            }
             let Some(binding) = set.bindings.iter_mut().find(|b| b.binding == write.binding) else {
                 return Err(Error::just_context(format!(
                     "invalid image write binding {} for descriptor set {}",
@@ -1384,12 +1775,13 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
                 ::with_capacity(infos.len() as u32, alloc)
                 .context("alloc failed")?;
             let starting_idx = write.starting_index as usize;
+            let base_offset = set.buffer_offset + binding.offset;
             for (i, descriptor) in binding.image_descriptors_mut()[starting_idx..starting_idx + infos.len()]
                 .iter_mut()
                 .enumerate()
             {
                 let info = infos[i].clone();
-                let sampler =
+                let new_sampler =
                     if ty.requires_sampler() {
                         let Some(sampler) = info.sampler else {
                             return Err(Error::just_context(format!(
@@ -1397,8 +1789,7 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
                                 write.set_id, write.binding, starting_idx + i,
                             )))
                         };
-                        descriptor.sampler = Some(sampler.clone());
-                        sampler.handle().into_inner()
+                        Some(sampler)
                     } else {
                         if info.sampler.is_some() {
                             log::warn!(
@@ -1406,13 +1797,14 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
                                 write.set_id, write.binding,
                             );
                         }
-                        vk::Sampler::null()
+                        None
                     };
+                let sampler = new_sampler
+                    .as_ref()
+                    .map(|sampler| sampler.handle().into_inner())
+                    .unwrap_or_default();
                 let (image_view, layout) =
                     if let Some(layout) = image_layout {
-                        if let Some((view, _)) = descriptor.image {
-                            pool.untrack_image(view, descriptor.image_track_id)?;
-                        }
                         let Some(image_view) = info.image_view else {
                             return Err(Error::just_context(format!(
                                 "descriptor set {} (binding {}, type {ty}) requires an image, but none was given",
@@ -1430,9 +1822,13 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
                                 )
                             ))
                         }
+                        let view = image.get_view(image_view)?.handle;
+                        if let Some((view, _)) = descriptor.image {
+                            pool.untrack_image(view, descriptor.image_track_id)?;
+                        }
                         descriptor.image = Some((image_view, layout));
                         descriptor.image_track_id = pool.track_image(image_view, write.set_id.inner_id());
-                        (image.get_view(image_view)?.handle, layout.into())
+                        (view, layout.into())
                     } else {
                         if info.image_view.is_some() {
                             log::warn!(
@@ -1442,15 +1838,204 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
                         }
                         (vk::ImageView::null(), vk::ImageLayout::UNDEFINED)
                     };
+                if new_sampler.is_some() {
+                    descriptor.sampler = new_sampler;
+                }
                 let vk_info = vk::DescriptorImageInfo {
                     sampler,
                     image_view,
                     image_layout: layout,
                 };
+                if let Some(descriptor_buffer) = &pool.descriptor_buffer {
+                    let data =
+                        if ty == DescriptorType::Sampler {
+                            DescriptorData::Sampler(&vk_info.sampler)
+                        } else {
+                            DescriptorData::Image(&vk_info)
+                        };
+                    unsafe {
+                        descriptor_buffer.write_descriptor(
+                            base_offset, (starting_idx + i) as u32, ty, data,
+                        ).context_with(|| format!(
+                            "failed to write descriptor buffer for descriptor set {} write (binding {}, index {}, type {ty})",
+                            write.set_id, write.binding, starting_idx + i,
+                        ))?;
+                    }
+                }
                 vk_infos.push(vk_info);
             }
             pool.into_inner();
             Ok((ty.into(), DescriptorUpdateInfos::Image(vk_infos)))
+        } else if let Some(views) = write.texel_buffer_infos() {
+            let can_poison = self.pool.descriptor_buffer.is_none();
+            let mut pool = RaiiHandle::new(&mut *self.pool, |pool| {
+                if can_poison {
+                    pool.poisoned = true;
+                }
+            });
+            let (set, id) = unsafe {
+                self.handle.get()
+            };
+            pool.add_pending_validation(id);
+            if can_poison {
+                set.flags |= DescriptorSet::MAYBE_POISONED;
+            }
+            let Some(binding) = set.bindings.iter_mut().find(|b| b.binding == write.binding) else {
+                return Err(Error::just_context(format!(
+                    "invalid texel buffer write binding {} for descriptor set {}",
+                    write.binding, write.set_id,
+                )))
+            };
+            let ty = binding.ty;
+            if !ty.is_texel_buffer() {
+                return Err(Error::just_context(format!(
+                    "non-texel buffer descriptor on texel buffer write (binding {} for descriptor set {})",
+                    write.binding, write.set_id,
+                )))
+            }
+            let usage = ty.buffer_usage().unwrap();
+            let count = binding.buffer_descriptors().len() as u32;
+            if views.len() as u32 + write.starting_index > count {
+                return Err(Error::just_context(format!(
+                    "texel buffer write descriptor starting index {} + count {} is out of range of descriptor binding {} descriptor count {}",
+                    write.starting_index, views.len(), write.binding, count,
+                )))
+            }
+            let mut vk_views = FixedVec32
+                ::with_capacity(views.len() as u32, alloc)
+                .context("alloc failed")?;
+            let starting_idx = write.starting_index as usize;
+            let base_offset = set.buffer_offset + binding.offset;
+            for (i, descriptor) in binding.buffer_descriptors_mut()[starting_idx..starting_idx + views.len()]
+                .iter_mut()
+                .enumerate()
+            {
+                let view_id = views[i];
+                let buffers = gpu.read_buffers();
+                let buffer_id = view_id.buffer_id();
+                let buffer = buffers
+                    .get(buffer_id)
+                    .context_with(|| format!(
+                        "failed to get buffer for descriptor set {} write (binding {}, index {}, type {ty})",
+                        write.set_id, write.binding, starting_idx + i,
+                    ))?;
+                if let Some(err) = buffer.validate_usage(usage) {
+                    return Err(Error::new(err, format!(
+                        "buffer {buffer_id} usage mismatch for descriptor set {}, (binding {}, index {}, type {ty})",
+                        write.set_id, write.binding, starting_idx + i,
+                    )))
+                }
+                let view = buffer.get_view(view_id)?;
+                let address_info = pool.descriptor_address_info(
+                    buffer, view.range.offset, view.range.size, view.range.format.into(),
+                ).context_with(|| format!(
+                    "failed to get buffer {buffer_id} address for descriptor set {} write (binding {}, index {}, type {ty})",
+                    write.set_id, write.binding, starting_idx + i,
+                ))?;
+                if let Some((id, _, _)) = descriptor.buffer {
+                    pool.untrack_buffer(id, descriptor.buffer_track_id)?;
+                }
+                descriptor.buffer = Some((buffer_id, view.range.offset, view.range.size));
+                descriptor.buffer_view = Some(view_id);
+                descriptor.acceleration_structure = None;
+                descriptor.buffer_track_id = pool.track_buffer(buffer_id, id);
+                if let (Some(descriptor_buffer), Some(address_info)) =
+                    (&pool.descriptor_buffer, &address_info)
+                {
+                    unsafe {
+                        descriptor_buffer.write_descriptor(
+                            base_offset, (starting_idx + i) as u32, ty,
+                            DescriptorData::Buffer(address_info),
+                        ).context_with(|| format!(
+                            "failed to write descriptor buffer for descriptor set {} write (binding {}, index {}, type {ty})",
+                            write.set_id, write.binding, starting_idx + i,
+                        ))?;
+                    }
+                }
+                vk_views.push(view.handle);
+            }
+            pool.into_inner();
+            Ok((ty.into(), DescriptorUpdateInfos::TexelBuffer(vk_views)))
+        } else if let Some(acceleration_structures) = write.acceleration_structure_infos() {
+            let can_poison = self.pool.descriptor_buffer.is_none();
+            let mut pool = RaiiHandle::new(&mut *self.pool, |pool| {
+                if can_poison {
+                    pool.poisoned = true;
+                }
+            });
+            let (set, id) = unsafe {
+                self.handle.get()
+            };
+            pool.add_pending_validation(id);
+            if can_poison {
+                set.flags |= DescriptorSet::MAYBE_POISONED;
+            }
+            let Some(binding) = set.bindings.iter_mut().find(|b| b.binding == write.binding) else {
+                return Err(Error::just_context(format!(
+                    "invalid acceleration structure write binding {} for descriptor set {}",
+                    write.binding, write.set_id,
+                )))
+            };
+            let ty = binding.ty;
+            if !ty.is_acceleration_structure() {
+                return Err(Error::just_context(format!(
+                    "non-acceleration structure descriptor on acceleration structure write (binding {} for descriptor set {})",
+                    write.binding, write.set_id,
+                )))
+            }
+            let count = binding.buffer_descriptors().len() as u32;
+            if acceleration_structures.len() as u32 + write.starting_index > count {
+                return Err(Error::just_context(format!(
+                    "acceleration structure write descriptor starting index {} + count {} is out of range of descriptor binding {} descriptor count {}",
+                    write.starting_index, acceleration_structures.len(), write.binding, count,
+                )))
+            }
+            let mut vk_handles = FixedVec32
+                ::with_capacity(acceleration_structures.len() as u32, alloc)
+                .context("alloc failed")?;
+            let starting_idx = write.starting_index as usize;
+            let base_offset = set.buffer_offset + binding.offset;
+            for (i, descriptor) in binding.buffer_descriptors_mut()[
+                    starting_idx..starting_idx + acceleration_structures.len()
+                ].iter_mut()
+                .enumerate()
+            {
+                let acceleration_structure_id = acceleration_structures[i];
+                let buffers = gpu.read_buffers();
+                let buffer_id = acceleration_structure_id.buffer_id();
+                let buffer = buffers
+                    .get(buffer_id)
+                    .context_with(|| format!(
+                        "failed to get buffer for descriptor set {} write (binding {}, index {}, type {ty})",
+                        write.set_id, write.binding, starting_idx + i,
+                    ))?;
+                let acceleration_structure = buffer
+                    .get_acceleration_structure(acceleration_structure_id)?;
+                let range = acceleration_structure.range;
+                if let Some((id, _, _)) = descriptor.buffer {
+                    pool.untrack_buffer(id, descriptor.buffer_track_id)?;
+                }
+                descriptor.buffer = Some((buffer_id, range.offset, range.size));
+                descriptor.buffer_view = None;
+                descriptor.acceleration_structure = Some(acceleration_structure_id);
+                descriptor.buffer_track_id = pool.track_buffer(buffer_id, id);
+                if let Some(descriptor_buffer) = &pool.descriptor_buffer {
+                    unsafe {
+                        descriptor_buffer.write_descriptor(
+                            base_offset, (starting_idx + i) as u32, ty,
+                            DescriptorData::AccelerationStructure(acceleration_structure.device_address),
+                        ).context_with(|| format!(
+                            "failed to write descriptor buffer for descriptor set {} write (binding {}, index {}, type {ty})",
+                            write.set_id, write.binding, starting_idx + i,
+                        ))?;
+                    }
+                }
+                vk_handles.push(acceleration_structure.handle);
+            }
+            pool.into_inner();
+            Ok((ty.into(), DescriptorUpdateInfos::AccelerationStructure(
+                vk_handles, Default::default(),
+            )))
         } else if let Some(data) = write.infos.as_inline_uniform_block() {
             let (set, _) = unsafe {
                 self.handle.get()
@@ -1474,6 +2059,26 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
                     write.starting_index, data.len(), write.binding, binding.descriptor_count(),
                 )))
             }
+            if let Some(descriptor_buffer) = &self.pool.descriptor_buffer {
+                let bytes = unsafe {
+                    core::slice::from_raw_parts(
+                        data.as_ptr().cast::<u8>(),
+                        size_of_val(data),
+                    )
+                };
+                if write.starting_index + bytes.len() as u32 > binding.descriptor_count() {
+                    return Err(Error::just_context(format!(
+                        "inline uniform block write offset {} + size {} is out of range of descriptor binding {} block size {}",
+                        write.starting_index, bytes.len(), write.binding, binding.descriptor_count(),
+                    )))
+                }
+                unsafe {
+                    descriptor_buffer.write_bytes(
+                        set.buffer_offset + binding.offset + write.starting_index as DeviceSize,
+                        bytes,
+                    );
+                }
+            }
             let info = vk::WriteDescriptorSetInlineUniformBlock {
                 data_size: data.len() as u32,
                 p_data: data.as_ptr().cast(),
@@ -1496,8 +2101,11 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
         array_count: u32,
     ) -> Result<vk::CopyDescriptorSet<'c>>
     {
+        let can_poison = self.pool.descriptor_buffer.is_none();
         let mut pool = RaiiHandle::new(&mut *self.pool, |pool| {
-            pool.poisoned = true;
+            if can_poison {
+                pool.poisoned = true;
+            }
         });
         let (from, from_id) = unsafe {
             from.get()
@@ -1506,7 +2114,9 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
             self.handle.get()
         };
         pool.add_pending_validation(to_id);
-        to.flags |= DescriptorSet::MAYBE_POISONED;
+        if can_poison {
+            to.flags |= DescriptorSet::MAYBE_POISONED;
+        }
         let src_binding_id = src_binding;
         let src_binding = from.bindings.iter().find(|b| b.binding == src_binding_id)
             .ok_or_else(|| Error::just_context(format!(
@@ -1517,6 +2127,8 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
             .ok_or_else(|| Error::just_context(format!(
                 "invalid destination binding {}", dst_binding,
             )))?;
+        let src_offset = from.buffer_offset + src_binding.offset;
+        let dst_offset = to.buffer_offset + dst_binding.offset;
         let src_ty = src_binding.ty();
         let dst_ty = dst_binding.ty();
         if src_ty != dst_ty {
@@ -1535,7 +2147,7 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
             )))?;
         }
         let ty = src_ty;
-        if ty.is_buffer() {
+        if ty.is_buffer() || ty.is_acceleration_structure() {
             let src_descriptors = src_binding.buffer_descriptors();
             let dst_descriptors = dst_binding.buffer_descriptors_mut();
             if src_starting_index + array_count > src_descriptors.len() as u32 {
@@ -1564,6 +2176,9 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
                         src_starting_index + i,
                     )))?;
                 dst_descriptor.buffer = Some(new_buffer);
+                dst_descriptor.buffer_view = src_descriptors[src_starting_index + i].buffer_view;
+                dst_descriptor.acceleration_structure =
+                    src_descriptors[src_starting_index + i].acceleration_structure;
                 dst_descriptor.buffer_track_id = pool.track_buffer(new_buffer.0, to_id);
             }
         } else if ty.is_image() {
@@ -1607,6 +2222,29 @@ impl<'a, 'b> DescriptorSetUpdateContext<'a, 'b> {
                 dst_descriptor.sampler = src_descriptor.sampler.clone();
                 dst_descriptor.image = src_descriptor.image;
             }
+        } else if ty.is_inline_uniform_block() {
+            if src_starting_index + array_count > src_binding.descriptor_count() {
+                return Err(Error::just_context(format!(
+                    "descriptor set copy source offset {} + size {} is out of range of source block size {}",
+                    src_starting_index, array_count, src_binding.descriptor_count(),
+                )))
+            }
+            if dst_starting_index + array_count > dst_binding.descriptor_count() {
+                return Err(Error::just_context(format!(
+                    "descriptor set copy destination offset {} + size {} is out of range of destination block size {}",
+                    dst_starting_index, array_count, dst_binding.descriptor_count(),
+                )))
+            }
+        }
+        if let Some(descriptor_buffer) = &pool.descriptor_buffer {
+            let size = descriptor_buffer.descriptor_size(ty);
+            unsafe {
+                descriptor_buffer.copy(
+                    src_offset + src_starting_index as DeviceSize * size,
+                    dst_offset + dst_starting_index as DeviceSize * size,
+                    array_count as DeviceSize * size,
+                );
+            }
         }
         pool.into_inner();
         Ok(vk::CopyDescriptorSet {
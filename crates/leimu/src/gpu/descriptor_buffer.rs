@@ -0,0 +1,499 @@
+use core::ptr::NonNull;
+
+use nox_ash::vk;
+
+use nox_mem::{
+    vec::Vec32,
+    vec32,
+    Display,
+};
+
+use crate::{
+    gpu::prelude::*,
+    error::*,
+    sync::*,
+};
+
+/// Describes where the descriptors of a [`DescriptorSetLayout`] are stored within a descriptor
+/// buffer.
+#[derive(Clone)]
+pub(crate) struct DescriptorBufferLayout {
+    /// The size, in bytes, of a descriptor set with the layout.
+    pub size: DeviceSize,
+    /// The offset, in bytes, of each binding of the layout in binding order.
+    pub binding_offsets: Vec32<DeviceSize>,
+}
+
+/// The sizes of single descriptors of each [`DescriptorType`] in a descriptor buffer.
+#[derive(Clone, Copy)]
+pub(crate) struct DescriptorSizes {
+    sampler: DeviceSize,
+    combined_image_sampler: DeviceSize,
+    sampled_image: DeviceSize,
+    storage_image: DeviceSize,
+    uniform_texel_buffer: DeviceSize,
+    storage_texel_buffer: DeviceSize,
+    uniform_buffer: DeviceSize,
+    storage_buffer: DeviceSize,
+    input_attachment: DeviceSize,
+    acceleration_structure: DeviceSize,
+}
+
+impl DescriptorSizes {
+
+    /// Reads the descriptor sizes from the [`descriptor_buffer`][1] attributes of `device`.
+    ///
+    /// Robust buffer descriptor sizes are used if [`BaseDeviceFeatures::robust_buffer_access`]
+    /// is enabled.
+    ///
+    /// [1]: ext::descriptor_buffer
+    pub fn new(device: &LogicalDevice) -> Self {
+        use ext::descriptor_buffer::Attributes;
+        let get = |name| device
+            .get_device_attribute(name)
+            .device_size()
+            .unwrap_or(0);
+        let robust = device.base_device_features().robust_buffer_access;
+        let robust_or = |robust_name, name| {
+            if robust { get(robust_name) } else { get(name) }
+        };
+        Self {
+            sampler: get(Attributes::SAMPLER_DESCRIPTOR_SIZE),
+            combined_image_sampler: get(Attributes::COMBINED_IMAGE_SAMPLER_DESCRIPTOR_SIZE),
+            sampled_image: get(Attributes::SAMPLED_IMAGE_DESCRIPTOR_SIZE),
+            storage_image: get(Attributes::STORAGE_IMAGE_DESCRIPTOR_SIZE),
+            uniform_texel_buffer: robust_or(
+                Attributes::ROBUST_UNIFORM_TEXEL_BUFFER_DESCRIPTOR_SIZE,
+                Attributes::UNIFORM_TEXEL_BUFFER_DESCRIPTOR_SIZE,
+            ),
+            storage_texel_buffer: robust_or(
+                Attributes::ROBUST_STORAGE_TEXEL_BUFFER_DESCRIPTOR_SIZE,
+                Attributes::STORAGE_TEXEL_BUFFER_DESCRIPTOR_SIZE,
+            ),
+            uniform_buffer: robust_or(
+                Attributes::ROBUST_UNIFORM_BUFFER_DESCRIPTOR_SIZE,
+                Attributes::UNIFORM_BUFFER_DESCRIPTOR_SIZE,
+            ),
+            storage_buffer: robust_or(
+                Attributes::ROBUST_STORAGE_BUFFER_DESCRIPTOR_SIZE,
+                Attributes::STORAGE_BUFFER_DESCRIPTOR_SIZE,
+            ),
+            input_attachment: get(Attributes::INPUT_ATTACHMENT_DESCRIPTOR_SIZE),
+            acceleration_structure: get(Attributes::ACCELERATION_STRUCTURE_DESCRIPTOR_SIZE),
+        }
+    }
+
+    /// Returns the size of a single descriptor of type `ty`.
+    ///
+    /// Inline uniform blocks are stored directly in the descriptor buffer, so their size is one
+    /// byte per descriptor.
+    #[inline(always)]
+    pub fn get(&self, ty: DescriptorType) -> DeviceSize {
+        match ty {
+            DescriptorType::Sampler => self.sampler,
+            DescriptorType::CombinedImageSampler => self.combined_image_sampler,
+            DescriptorType::SampledImage => self.sampled_image,
+            DescriptorType::StorageImage => self.storage_image,
+            DescriptorType::UniformTexelBuffer => self.uniform_texel_buffer,
+            DescriptorType::StorageTexelBuffer => self.storage_texel_buffer,
+            DescriptorType::UniformBuffer => self.uniform_buffer,
+            DescriptorType::StorageBuffer => self.storage_buffer,
+            DescriptorType::InputAttachment => self.input_attachment,
+            DescriptorType::AccelerationStructure => self.acceleration_structure,
+            DescriptorType::InlineUniformBlock => 1,
+            DescriptorType::Unknown => 0,
+        }
+    }
+}
+
+/// Tracks the descriptor buffer address space used by every [`DescriptorBuffer`] of a
+/// [`LogicalDevice`].
+#[derive(Default)]
+pub(crate) struct DescriptorBufferAddressSpace {
+    /// The bytes used by sampler, resource and all descriptor buffers respectively.
+    inner: Mutex<[DeviceSize; 3]>,
+}
+
+impl DescriptorBufferAddressSpace {
+
+    #[inline(always)]
+    fn sizes(usage: BufferUsages, size: DeviceSize) -> [DeviceSize; 3] {
+        let sampler = usage.contains(BufferUsages::SAMPLER_DESCRIPTOR_BUFFER);
+        let resource = usage.contains(BufferUsages::RESOURCE_DESCRIPTOR_BUFFER);
+        [
+            if sampler { size } else { 0 },
+            if resource { size } else { 0 },
+            if sampler || resource { size } else { 0 },
+        ]
+    }
+
+    /// Reserves `size` bytes of address space for a descriptor buffer with `usage`.
+    ///
+    /// Returns an error if the sampler, resource or total descriptor buffer address space
+    /// size of `device` would be exceeded.
+    pub fn reserve(
+        &self,
+        device: &LogicalDevice,
+        usage: BufferUsages,
+        size: DeviceSize,
+    ) -> Result<()>
+    {
+        use ext::descriptor_buffer::Attributes;
+        let mut used = self.inner.lock();
+        let sizes = Self::sizes(usage, size);
+        for (i, (kind, name)) in [
+            ("sampler", Attributes::SAMPLER_DESCRIPTOR_BUFFER_ADDRESS_SPACE_SIZE),
+            ("resource", Attributes::RESOURCE_DESCRIPTOR_BUFFER_ADDRESS_SPACE_SIZE),
+            ("total", Attributes::DESCRIPTOR_BUFFER_ADDRESS_SPACE_SIZE),
+        ].into_iter().enumerate() {
+            let max = device
+                .get_device_attribute(name)
+                .device_size()
+                .unwrap_or(DeviceSize::MAX);
+            let new_used = used[i] + sizes[i];
+            if new_used > max {
+                return Err(Error::just_context(format!(
+                    "{}{}",
+                    format_args!("descriptor buffer of size {size} would use {new_used} bytes of {kind} "),
+                    format_args!("descriptor buffer address space, which is larger than the max {max}"),
+                )))
+            }
+        }
+        for (used, size) in used.iter_mut().zip(sizes) {
+            *used += size;
+        }
+        Ok(())
+    }
+
+    /// Releases address space previously reserved with [`DescriptorBufferAddressSpace::reserve`].
+    pub fn release(&self, usage: BufferUsages, size: DeviceSize) {
+        let mut used = self.inner.lock();
+        for (used, size) in used.iter_mut().zip(Self::sizes(usage, size)) {
+            *used -= size;
+        }
+    }
+}
+
+/// Specifies the resource a single descriptor written with
+/// [`DescriptorBuffer::write_descriptor`] refers to.
+#[derive(Display)]
+pub(crate) enum DescriptorData<'a> {
+    #[display("sampler")]
+    Sampler(&'a vk::Sampler),
+    #[display("image")]
+    Image(&'a vk::DescriptorImageInfo),
+    #[display("buffer")]
+    Buffer(&'a vk::DescriptorAddressInfoEXT<'a>),
+    #[display("acceleration structure")]
+    AccelerationStructure(DeviceAddress),
+}
+
+/// A host visible descriptor buffer, which descriptor sets of a [`DescriptorPool`] are
+/// sub-allocated from.
+///
+/// Descriptors are written directly to the mapped memory of the buffer with
+/// [`vkGetDescriptorEXT`][1].
+///
+/// The buffer only has sampler descriptor buffer usage if its pool holds samplers, so pools
+/// without samplers don't count against the sampler descriptor buffer binding limit.
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/vkGetDescriptorEXT.html
+pub(crate) struct DescriptorBuffer {
+    logical_device: LogicalDevice,
+    device: ext::descriptor_buffer::Device,
+    buffer: BufferMeta,
+    map: NonNull<u8>,
+    address: DeviceAddress,
+    alignment: DeviceSize,
+    max_range: DeviceSize,
+    sizes: DescriptorSizes,
+    free_ranges: Vec32<(DeviceSize, DeviceSize)>,
+}
+
+impl Drop for DescriptorBuffer {
+
+    fn drop(&mut self) {
+        let properties = self.buffer.properties();
+        self.logical_device
+            .descriptor_buffer_address_space()
+            .release(properties.usage, properties.size);
+    }
+}
+
+unsafe impl Send for DescriptorBuffer {}
+unsafe impl Sync for DescriptorBuffer {}
+
+impl DescriptorBuffer {
+
+    /// Creates a descriptor buffer of at least `size` bytes for descriptors of `types`.
+    ///
+    /// The buffer gets sampler descriptor buffer usage if `types` contains samplers or combined
+    /// image samplers, and resource descriptor buffer usage if `types` contains anything other
+    /// than samplers.
+    pub fn new(
+        device: LogicalDevice,
+        sizes: DescriptorSizes,
+        types: impl IntoIterator<Item = DescriptorType>,
+        size: DeviceSize,
+    ) -> Result<Self>
+    {
+        let ext_device: ext::descriptor_buffer::Device = device
+            .get_extension_device()
+            .ok_or_else(|| Error::just_context("descriptor_buffer device extension is not enabled"))?;
+        let alignment = device
+            .get_device_attribute(ext::descriptor_buffer::Attributes::DESCRIPTOR_BUFFER_OFFSET_ALIGNMENT)
+            .device_size()
+            .unwrap_or(1)
+            .max(1);
+        let size = size.next_multiple_of(alignment);
+        let mut usage = BufferUsages::SHADER_DEVICE_ADDRESS;
+        let mut max_range = DeviceSize::MAX;
+        for ty in types {
+            match ty {
+                DescriptorType::Sampler => {
+                    usage |= BufferUsages::SAMPLER_DESCRIPTOR_BUFFER;
+                },
+                DescriptorType::CombinedImageSampler => {
+                    usage |= BufferUsages::SAMPLER_DESCRIPTOR_BUFFER |
+                        BufferUsages::RESOURCE_DESCRIPTOR_BUFFER;
+                },
+                _ => {
+                    usage |= BufferUsages::RESOURCE_DESCRIPTOR_BUFFER;
+                },
+            }
+        }
+        for (usage_bit, name) in [
+            (BufferUsages::SAMPLER_DESCRIPTOR_BUFFER,
+                ext::descriptor_buffer::Attributes::MAX_SAMPLER_DESCRIPTOR_BUFFER_RANGE),
+            (BufferUsages::RESOURCE_DESCRIPTOR_BUFFER,
+                ext::descriptor_buffer::Attributes::MAX_RESOURCE_DESCRIPTOR_BUFFER_RANGE),
+        ] {
+            if usage.contains(usage_bit) {
+                max_range = max_range.min(device
+                    .get_device_attribute(name)
+                    .device_size()
+                    .unwrap_or(DeviceSize::MAX)
+                );
+            }
+        }
+        let binder = GlobalBinder::new(
+            device.clone(),
+            MemoryProperties::HOST_VISIBLE | MemoryProperties::HOST_COHERENT,
+            MemoryProperties::HOST_VISIBLE | MemoryProperties::HOST_COHERENT,
+        );
+        let mut unused_id = Default::default();
+        let create_info = BufferCreateInfo::new(
+            &mut unused_id,
+            &binder,
+            size,
+            usage,
+        ).ok_or_else(|| Error::just_context("descriptor buffer size was zero"))?
+        .with_name("descriptor buffer");
+        device
+            .descriptor_buffer_address_space()
+            .reserve(&device, usage, size)?;
+        let buffer: Result<_> = (|| {
+            let mut bind_info = Default::default();
+            let mut buffer = create_info.build(device.clone(), &mut bind_info)
+                .context("failed to create descriptor buffer")?;
+            unsafe {
+                device.bind_buffer_memory2(&[bind_info])
+                    .context("failed to bind descriptor buffer memory")?;
+            }
+            let address = buffer.device_address()?;
+            let map = buffer
+                .memory_mut()
+                .unwrap()
+                .map_memory()
+                .context("failed to map descriptor buffer memory")?;
+            let map = NonNull::new(map.map)
+                .ok_or_else(|| Error::just_context("descriptor buffer map was null"))?;
+            Ok((buffer, address, map))
+        })();
+        let (buffer, address, map) = buffer.inspect_err(|_| {
+            device
+                .descriptor_buffer_address_space()
+                .release(usage, size);
+        })?;
+        Ok(Self {
+            logical_device: device,
+            device: ext_device,
+            buffer,
+            map,
+            address,
+            alignment,
+            max_range,
+            sizes,
+            free_ranges: vec32![(0, size)],
+        })
+    }
+
+    #[inline(always)]
+    pub fn address(&self) -> DeviceAddress {
+        self.address
+    }
+
+    #[inline(always)]
+    pub fn descriptor_size(&self, ty: DescriptorType) -> DeviceSize {
+        self.sizes.get(ty)
+    }
+
+    /// Returns the binding info used to bind the buffer with
+    /// [`vkCmdBindDescriptorBuffersEXT`][1].
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBindDescriptorBuffersEXT.html
+    #[inline(always)]
+    pub fn binding_info(&self) -> vk::DescriptorBufferBindingInfoEXT<'static> {
+        vk::DescriptorBufferBindingInfoEXT {
+            s_type: vk::StructureType::DESCRIPTOR_BUFFER_BINDING_INFO_EXT,
+            address: self.address,
+            usage: self.buffer.properties().usage.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Gets the device address of `buffer`, which is referred to by a descriptor.
+    pub fn buffer_address(&self, buffer: &BufferMeta) -> Result<DeviceAddress> {
+        if let Some(err) = buffer.validate_usage(BufferUsages::SHADER_DEVICE_ADDRESS) {
+            return Err(Error::new(err, "buffers referred to by descriptor buffers must have shader device address usage"))
+        }
+        buffer.device_address()
+    }
+
+    /// Allocates `size` bytes, returning the offset of the allocation.
+    ///
+    /// The offset is always a multiple of the descriptor buffer offset alignment, and the
+    /// allocation always ends within the max descriptor buffer range of the buffer's usage, so
+    /// that the whole allocation is accessible to shaders.
+    pub fn allocate(&mut self, size: DeviceSize) -> Option<DeviceSize> {
+        let size = size.max(1).next_multiple_of(self.alignment);
+        let max_range = self.max_range;
+        let index = self.free_ranges
+            .iter()
+            .position(|&(offset, free)| free >= size && offset + size <= max_range)?;
+        let (offset, free) = &mut self.free_ranges[index];
+        let allocated = *offset;
+        if *free == size {
+            self.free_ranges.remove(index as u32);
+        } else {
+            *offset += size;
+            *free -= size;
+        }
+        Some(allocated)
+    }
+
+    /// Frees an allocation previously returned from [`DescriptorBuffer::allocate`].
+    pub fn free(&mut self, offset: DeviceSize, size: DeviceSize) {
+        let size = size.max(1).next_multiple_of(self.alignment);
+        let index = self.free_ranges.partition_point(|&(free_offset, _)| free_offset < offset);
+        let merge_prev = index != 0 && {
+            let (prev_offset, prev_size) = self.free_ranges[index - 1];
+            prev_offset + prev_size == offset
+        };
+        let merge_next = self.free_ranges
+            .get(index)
+            .is_some_and(|&(next_offset, _)| offset + size == next_offset);
+        match (merge_prev, merge_next) {
+            (true, true) => {
+                let (_, next_size) = self.free_ranges.remove(index as u32);
+                self.free_ranges[index - 1].1 += size + next_size;
+            },
+            (true, false) => {
+                self.free_ranges[index - 1].1 += size;
+            },
+            (false, true) => {
+                let next = &mut self.free_ranges[index];
+                next.0 = offset;
+                next.1 += size;
+            },
+            (false, false) => {
+                self.free_ranges.insert(index as u32, (offset, size));
+            },
+        }
+    }
+
+    /// Writes a single descriptor of type `ty` to `base_offset` + `index` * the descriptor size
+    /// of `ty`.
+    ///
+    /// Returns an error if `data` doesn't match `ty`.
+    ///
+    /// # Safety
+    /// The written range *must* be within an allocation of this buffer and *must* not be
+    /// accessed by pending commands.
+    pub unsafe fn write_descriptor(
+        &self,
+        base_offset: DeviceSize,
+        index: u32,
+        ty: DescriptorType,
+        data: DescriptorData<'_>,
+    ) -> Result<()> {
+        let data = match (ty, data) {
+            (DescriptorType::Sampler, DescriptorData::Sampler(sampler)) =>
+                vk::DescriptorDataEXT { p_sampler: sampler },
+            (DescriptorType::CombinedImageSampler, DescriptorData::Image(info)) =>
+                vk::DescriptorDataEXT { p_combined_image_sampler: info },
+            (DescriptorType::SampledImage, DescriptorData::Image(info)) =>
+                vk::DescriptorDataEXT { p_sampled_image: info },
+            (DescriptorType::StorageImage, DescriptorData::Image(info)) =>
+                vk::DescriptorDataEXT { p_storage_image: info },
+            (DescriptorType::InputAttachment, DescriptorData::Image(info)) =>
+                vk::DescriptorDataEXT { p_input_attachment_image: info },
+            (DescriptorType::UniformTexelBuffer, DescriptorData::Buffer(info)) =>
+                vk::DescriptorDataEXT { p_uniform_texel_buffer: info },
+            (DescriptorType::StorageTexelBuffer, DescriptorData::Buffer(info)) =>
+                vk::DescriptorDataEXT { p_storage_texel_buffer: info },
+            (DescriptorType::UniformBuffer, DescriptorData::Buffer(info)) =>
+                vk::DescriptorDataEXT { p_uniform_buffer: info },
+            (DescriptorType::StorageBuffer, DescriptorData::Buffer(info)) =>
+                vk::DescriptorDataEXT { p_storage_buffer: info },
+            (DescriptorType::AccelerationStructure, DescriptorData::AccelerationStructure(address)) =>
+                vk::DescriptorDataEXT { acceleration_structure: address },
+            (ty, data) => return Err(Error::just_context(format!(
+                "{data} descriptor data doesn't match descriptor type {ty}",
+            ))),
+        };
+        let size = self.sizes.get(ty);
+        let info = vk::DescriptorGetInfoEXT {
+            s_type: vk::StructureType::DESCRIPTOR_GET_INFO_EXT,
+            ty: ty.into(),
+            data,
+            ..Default::default()
+        };
+        // descriptor buffer memory is host coherent, so the write doesn't need to be flushed
+        unsafe {
+            let dst = core::slice::from_raw_parts_mut(
+                self.map.add((base_offset + index as DeviceSize * size) as usize).as_ptr(),
+                size as usize,
+            );
+            self.device.get_descriptor(&info, dst);
+        }
+        Ok(())
+    }
+
+    /// Writes `data` to `offset`.
+    ///
+    /// # Safety
+    /// The written range *must* be within an allocation of this buffer and *must* not be
+    /// accessed by pending commands.
+    pub unsafe fn write_bytes(&self, offset: DeviceSize, data: &[u8]) {
+        unsafe {
+            data.as_ptr().copy_to_nonoverlapping(
+                self.map.add(offset as usize).as_ptr(),
+                data.len(),
+            );
+        }
+    }
+
+    /// Copies `size` bytes from `src_offset` to `dst_offset`.
+    ///
+    /// # Safety
+    /// Both ranges *must* be within allocations of this buffer and *must* not overlap.
+    pub unsafe fn copy(&self, src_offset: DeviceSize, dst_offset: DeviceSize, size: DeviceSize) {
+        unsafe {
+            self.map.add(src_offset as usize).as_ptr().copy_to_nonoverlapping(
+                self.map.add(dst_offset as usize).as_ptr(),
+                size as usize,
+            );
+        }
+    }
+}
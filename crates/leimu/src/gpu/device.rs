@@ -1,4 +1,5 @@
 mod r#fn;
+mod memory;
 
 use core::ffi;
 
@@ -8,7 +9,10 @@ use nox_mem::{
 
 use nox_log::info;
 
-use nox_ash::vk;
+use nox_ash::{
+    vk,
+    ext::debug_utils,
+};
 
 use super::prelude::*;
 
@@ -18,6 +22,7 @@ use crate::{
 };
 
 pub use r#fn::*;
+pub use memory::*;
 
 struct Inner {
     id: LogicalDeviceId,
@@ -32,6 +37,10 @@ struct Inner {
     supported_depth_resolve_modes: vk::ResolveModeFlags,
     supported_stencil_resolve_modes: vk::ResolveModeFlags,
     instance: Instance,
+    debug_utils: Option<debug_utils::Device>,
+    buffer_device_address_enabled: bool,
+    memory_usage: MemoryUsage,
+    descriptor_buffer_address_space: DescriptorBufferAddressSpace,
     command_workers: u32,
 }
 
@@ -136,7 +145,16 @@ impl LogicalDevice {
                 queue.family_index(), queue.queue_index(),
             );
         }
-        Ok(Self {
+        let buffer_device_address_enabled = enabled_device_extensions
+            .get_attribute(ext::acceleration_structure::Attributes::IS_ENABLED)
+            .bool().unwrap_or(false) ||
+            enabled_device_extensions
+            .get_attribute(ext::descriptor_buffer::Attributes::IS_ENABLED)
+            .bool().unwrap_or(false);
+        let debug_utils = instance
+            .is_debug_utils_enabled()
+            .then(|| debug_utils::Device::new(instance.ash(), &device));
+        let s = Self {
             inner: Arc::new(Inner {
                 id,
                 physical_device: physical_device.clone(),
@@ -150,9 +168,17 @@ impl LogicalDevice {
                 supported_depth_resolve_modes: depth_stencil_resolve_properties.supported_depth_resolve_modes,
                 supported_stencil_resolve_modes: depth_stencil_resolve_properties.supported_stencil_resolve_modes,
                 instance: instance.clone(),
+                debug_utils,
+                buffer_device_address_enabled,
+                memory_usage: Default::default(),
+                descriptor_buffer_address_space: Default::default(),
                 command_workers: suitable.attributes.command_workers,
             })
-        })
+        };
+        for queue in &s.inner.device_queues {
+            s.set_object_name(queue.handle(), queue.name());
+        }
+        Ok(s)
     }
 
     #[inline(always)]
@@ -165,6 +191,11 @@ impl LogicalDevice {
         &self.inner.instance
     }
 
+    #[inline(always)]
+    pub(crate) fn debug_utils_device(&self) -> Option<&debug_utils::Device> {
+        self.inner.debug_utils.as_ref()
+    }
+
     #[inline(always)]
     pub fn api_version(&self) -> Version {
         self.inner.physical_device.api_version()
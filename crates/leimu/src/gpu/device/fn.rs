@@ -4,7 +4,8 @@ use core::{
 };
 
 use nox_ash::{
-    load_fn, prelude::VkResult, vk,
+    load_fn, prelude::VkResult,
+    vk::{self, ptr_chain_iter, TaggedStructure},
     khr,
 };
 
@@ -25,6 +26,9 @@ pub type DeviceFnV1_1 = nox_ash::DeviceFnV1_1;
 ///
 /// # Pre-1.2 extension dependencies
 /// - VK_KHR_timeline_semaphore
+/// - VK_KHR_buffer_device_address, only when [`buffer device address`][1] is enabled
+///
+/// [1]: LogicalDevice::is_buffer_device_address_enabled
 #[derive(Clone)]
 pub struct DeviceFnV1_2 {
     /// VK_KHR_timeline_semaphore
@@ -33,6 +37,8 @@ pub struct DeviceFnV1_2 {
     pub get_semaphore_counter_value: vk::PFN_vkGetSemaphoreCounterValue,
     /// VK_KHR_timeline_semaphore
     pub signal_semaphore: vk::PFN_vkSignalSemaphore,
+    /// VK_KHR_buffer_device_address
+    pub get_buffer_device_address: vk::PFN_vkGetBufferDeviceAddress,
 }
 
 unsafe impl Send for DeviceFnV1_2 {}
@@ -71,6 +77,15 @@ impl DeviceFnV1_2 {
                     c"vkSignalSemaphore",
                     vk::PFN_vkSignalSemaphore,
                 ),
+                get_buffer_device_address: load_fn!(
+                    fn get_buffer_device_address(
+                        vk::Device,
+                        *const vk::BufferDeviceAddressInfo<'_>,
+                    ) -> vk::DeviceAddress,
+                    f,
+                    c"vkGetBufferDeviceAddress",
+                    vk::PFN_vkGetBufferDeviceAddress,
+                ),
             } }
         } else {
             unsafe { Self {
@@ -99,6 +114,15 @@ impl DeviceFnV1_2 {
                     c"vkSignalSemaphoreKHR",
                     vk::PFN_vkSignalSemaphore,
                 ),
+                get_buffer_device_address: load_fn!(
+                    fn get_buffer_device_address(
+                        vk::Device,
+                        *const vk::BufferDeviceAddressInfo<'_>,
+                    ) -> vk::DeviceAddress,
+                    f,
+                    c"vkGetBufferDeviceAddressKHR",
+                    vk::PFN_vkGetBufferDeviceAddress,
+                ),
             } }
         }
     }
@@ -170,6 +194,8 @@ pub struct DeviceFnV1_3 {
     /// VK_KHR_synchronization2
     pub cmd_pipeline_barrier2: vk::PFN_vkCmdPipelineBarrier2,
     /// VK_KHR_synchronization2
+    pub cmd_write_timestamp2: vk::PFN_vkCmdWriteTimestamp2,
+    /// VK_KHR_synchronization2
     pub queue_submit2: vk::PFN_vkQueueSubmit2,
 
     /// VK_KHR_maintenance4
@@ -358,6 +384,17 @@ impl DeviceFnV1_3 {
                     c"vkCmdPipelineBarrier2",
                     vk::PFN_vkCmdPipelineBarrier2,
                 ),
+                cmd_write_timestamp2: load_fn!(
+                    fn cmd_write_timestamp2(
+                        vk::CommandBuffer,
+                        vk::PipelineStageFlags2,
+                        vk::QueryPool,
+                        u32,
+                    ) -> (),
+                    f,
+                    c"vkCmdWriteTimestamp2",
+                    vk::PFN_vkCmdWriteTimestamp2,
+                ),
                 queue_submit2: load_fn!(
                     fn queue_submit2(vk::Queue, u32, *const vk::SubmitInfo2, vk::Fence) -> vk::Result,
                     f,
@@ -568,6 +605,17 @@ impl DeviceFnV1_3 {
                     c"vkCmdPipelineBarrier2KHR",
                     vk::PFN_vkCmdPipelineBarrier2,
                 ),
+                cmd_write_timestamp2: load_fn!(
+                    fn cmd_write_timestamp2(
+                        vk::CommandBuffer,
+                        vk::PipelineStageFlags2,
+                        vk::QueryPool,
+                        u32,
+                    ) -> (),
+                    f,
+                    c"vkCmdWriteTimestamp2KHR",
+                    vk::PFN_vkCmdWriteTimestamp2,
+                ),
                 queue_submit2: load_fn!(
                     fn queue_submit2(vk::Queue, u32, *const vk::SubmitInfo2, vk::Fence) -> vk::Result,
                     f,
@@ -918,37 +966,79 @@ impl super::LogicalDevice {
     ///
     /// Part of Vulkan 1.0 core.
     ///
+    /// The memory is tracked for [`memory statistics`][1].
+    ///
+    /// If [`buffer device address`][2] is enabled, [`vk::MemoryAllocateFlags::DEVICE_ADDRESS`] is
+    /// added to the flags of [`vk::MemoryAllocateFlagsInfo`] in the `p_next` chain or, if the
+    /// chain doesn't contain one, [`vk::MemoryAllocateFlagsInfo`] is prepended to the chain.
+    ///
     /// # Safety
     /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    ///
+    /// [1]: Self::memory_type_statistics
+    /// [2]: Self::is_buffer_device_address_enabled
     #[inline]
     pub unsafe fn allocate_memory(
         &self,
-        allocate_info: &vk::MemoryAllocateInfo<'_>,
+        allocate_info: &mut vk::MemoryAllocateInfo<'_>,
         allocator: Option<&vk::AllocationCallbacks<'_>>,
     ) -> VkResult<vk::DeviceMemory> {
         let mut memory = vk::DeviceMemory::null();
+        let flags_info = vk::MemoryAllocateFlagsInfo {
+            p_next: allocate_info.p_next,
+            flags: vk::MemoryAllocateFlags::DEVICE_ADDRESS,
+            ..Default::default()
+        };
+        let chain = allocate_info;
+        let mut allocate_info = *chain;
+        if self.is_buffer_device_address_enabled() {
+            if let Some(existing) = unsafe { ptr_chain_iter(chain) }
+                .find(|&ptr| unsafe {
+                    (*ptr).s_type == vk::MemoryAllocateFlagsInfo::STRUCTURE_TYPE
+                })
+            {
+                unsafe {
+                    (*existing.cast::<vk::MemoryAllocateFlagsInfo>()).flags |=
+                        vk::MemoryAllocateFlags::DEVICE_ADDRESS;
+                }
+            } else {
+                allocate_info.p_next = &flags_info as *const _ as *const c_void;
+            }
+        }
         unsafe {
             (self.fns().fp_v1_0().allocate_memory)(
                 self.handle(),
-                allocate_info,
+                &allocate_info,
                allocator.as_ptr(),
                 &mut memory
             )
         }.result_with_success(memory)
+        .inspect(|&memory| {
+            self.memory_usage().track_alloc(
+                memory,
+                allocate_info.memory_type_index,
+                allocate_info.allocation_size,
+            );
+        })
     }
 
     /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkFreeMemory.html>
     ///
     /// Part of Vulkan 1.0 core.
     ///
+    /// The memory is tracked for [`memory statistics`][1].
+    ///
     /// # Safety
     /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    ///
+    /// [1]: Self::memory_type_statistics
     #[inline]
     pub unsafe fn free_memory(
         &self,
         memory: vk::DeviceMemory,
         allocator: Option<&vk::AllocationCallbacks<'_>>,
     ) {
+        self.memory_usage().track_free(memory);
         unsafe {
             (self.fns().fp_v1_0().free_memory)(
                 self.handle(),
@@ -3979,6 +4069,26 @@ impl super::LogicalDevice {
         }.result()
     }
 
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetBufferDeviceAddress.html>
+    ///
+    /// Part of Vulkan 1.2 core, otherwise provided by VK_KHR_buffer_device_address device
+    /// extension.
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline]
+    pub unsafe fn get_buffer_device_address(
+        &self,
+        info: &vk::BufferDeviceAddressInfo<'_>,
+    ) -> vk::DeviceAddress {
+        unsafe {
+            (self.fns().fp_v1_2().get_buffer_device_address)(
+                self.handle(),
+                info,
+            )
+        }
+    }
+
     /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBeginRendering.html>
     ///
     /// Part of Vulkan 1.3 core, otherwise provided by VK_KHR_dynamic_rendering device extension.
@@ -4531,6 +4641,30 @@ impl super::LogicalDevice {
         }
     }
 
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdWriteTimestamp2.html>
+    ///
+    /// Part of Vulkan 1.3 core, otherwise provided by VK_KHR_synchronization2 device extension.
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline]
+    pub unsafe fn cmd_write_timestamp2(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        stage: vk::PipelineStageFlags2,
+        query_pool: vk::QueryPool,
+        query: u32,
+    ) {
+        unsafe {
+            (self.fns().fp_v1_3().cmd_write_timestamp2)(
+                command_buffer,
+                stage,
+                query_pool,
+                query,
+            )
+        }
+    }
+
     /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkQueueSubmit2.html>
     ///
     /// Part of Vulkan 1.3 core, otherwise provided by VK_KHR_synchronization2 device extension.
@@ -0,0 +1,233 @@
+use ahash::AHashMap;
+
+use nox_mem::vec::Vec32;
+
+use nox_ash::vk;
+
+use super::*;
+
+/// The fraction of a heap's size reported as its budget, when [`VK_EXT_memory_budget`][1] is not
+/// enabled.
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_memory_budget.html
+const FALLBACK_BUDGET_FRACTION: f64 = 0.8;
+
+/// Usage and budget of a [`memory heap`][1].
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkMemoryHeap.html
+#[derive(Clone, Copy, Debug)]
+pub struct MemoryHeapBudget {
+    /// The index of the heap.
+    pub heap_index: u32,
+    /// The total size of the heap.
+    pub size: DeviceSize,
+    /// Specifies whether the heap is device local.
+    pub device_local: bool,
+    /// The number of bytes currently allocated from the heap.
+    ///
+    /// With [`VK_EXT_memory_budget`][1], this includes allocations of other processes. Otherwise
+    /// this only counts [`device memory`][2] allocated by this device.
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_memory_budget.html
+    /// [2]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+    pub usage: DeviceSize,
+    /// The number of bytes the heap *can* hold before allocations *may* fail or cause
+    /// performance degradation.
+    ///
+    /// Without [`VK_EXT_memory_budget`][1], this is estimated as 80% of the heap's size.
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_memory_budget.html
+    pub budget: DeviceSize,
+    /// Specifies whether `usage` and `budget` were reported by the implementation.
+    pub is_exact: bool,
+}
+
+impl MemoryHeapBudget {
+
+    /// Returns the number of bytes, which *can* still be allocated within budget.
+    #[inline(always)]
+    pub fn available(&self) -> DeviceSize {
+        self.budget.saturating_sub(self.usage)
+    }
+}
+
+/// Statistics of [`device memory`][1] allocated from a memory type.
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+#[derive(Clone, Copy, Debug)]
+pub struct MemoryTypeStatistics {
+    /// The index of the memory type.
+    pub memory_type_index: u32,
+    /// The index of the heap the memory type uses.
+    pub heap_index: u32,
+    /// The properties of the memory type.
+    pub properties: MemoryProperties,
+    /// The number of live [`device memory`][1] objects.
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+    pub allocation_count: u32,
+    /// The total size of the live [`device memory`][1] objects.
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+    pub allocation_bytes: DeviceSize,
+}
+
+#[derive(Default)]
+struct TypeUsage {
+    allocation_count: u32,
+    allocation_bytes: DeviceSize,
+}
+
+/// Tracks every [`device memory`][1] object allocated through a [`LogicalDevice`].
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+#[derive(Default)]
+pub(crate) struct MemoryUsage {
+    inner: Mutex<(AHashMap<vk::DeviceMemory, (u32, DeviceSize)>, Vec32<TypeUsage>)>,
+}
+
+impl MemoryUsage {
+
+    pub fn track_alloc(
+        &self,
+        memory: vk::DeviceMemory,
+        memory_type_index: u32,
+        size: DeviceSize,
+    ) {
+        let mut inner = self.inner.lock();
+        let (allocations, types) = &mut *inner;
+        allocations.insert(memory, (memory_type_index, size));
+        if types.len() <= memory_type_index {
+            types.resize_with(memory_type_index + 1, Default::default);
+        }
+        let usage = &mut types[memory_type_index as usize];
+        usage.allocation_count += 1;
+        usage.allocation_bytes += size;
+    }
+
+    pub fn track_free(&self, memory: vk::DeviceMemory) {
+        let mut inner = self.inner.lock();
+        let (allocations, types) = &mut *inner;
+        if let Some((memory_type_index, size)) = allocations.remove(&memory) {
+            let usage = &mut types[memory_type_index as usize];
+            usage.allocation_count -= 1;
+            usage.allocation_bytes -= size;
+        }
+    }
+
+    fn type_usage(&self, memory_type_index: u32) -> (u32, DeviceSize) {
+        self.inner
+            .lock().1
+            .get(memory_type_index as usize)
+            .map(|usage| (usage.allocation_count, usage.allocation_bytes))
+            .unwrap_or_default()
+    }
+}
+
+impl LogicalDevice {
+
+    /// Returns whether [`memory_budget`][1] is enabled.
+    ///
+    /// [1]: crate::gpu::ext::memory_budget
+    #[inline(always)]
+    pub fn is_memory_budget_enabled(&self) -> bool {
+        self.get_device_attribute(ext::memory_budget::Attributes::IS_ENABLED)
+            .bool().unwrap_or(false)
+    }
+
+    /// Returns whether all device memory is allocated with
+    /// [`vk::MemoryAllocateFlags::DEVICE_ADDRESS`].
+    ///
+    /// This is the case when [`acceleration_structure`][1] or [`descriptor_buffer`][2] is enabled.
+    ///
+    /// [1]: crate::gpu::ext::acceleration_structure
+    /// [2]: crate::gpu::ext::descriptor_buffer
+    #[inline(always)]
+    pub fn is_buffer_device_address_enabled(&self) -> bool {
+        self.inner.buffer_device_address_enabled
+    }
+
+    /// Returns the current usage and budget of each [`memory heap`][1].
+    ///
+    /// Uses [`VK_EXT_memory_budget`][2] when it's enabled and this device's own accounting
+    /// otherwise.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/VkPhysicalDeviceMemoryBudgetPropertiesEXT.html>
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkMemoryHeap.html
+    /// [2]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_memory_budget.html
+    pub fn memory_heap_budgets(&self) -> Vec32<MemoryHeapBudget> {
+        let memory_properties = self.physical_device().memory_properties();
+        let heaps = &memory_properties.memory_heaps[..memory_properties.memory_heap_count as usize];
+        if self.is_memory_budget_enabled() {
+            let mut budget_properties = vk::PhysicalDeviceMemoryBudgetPropertiesEXT::default();
+            let mut properties = vk::PhysicalDeviceMemoryProperties2
+                ::default()
+                .push_next(&mut budget_properties);
+            unsafe {
+                self.instance().ash().get_physical_device_memory_properties2(
+                    self.physical_device().handle(), &mut properties,
+                );
+            }
+            return heaps
+                .iter()
+                .enumerate()
+                .map(|(i, heap)| MemoryHeapBudget {
+                    heap_index: i as u32,
+                    size: heap.size,
+                    device_local: heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL),
+                    usage: budget_properties.heap_usage[i],
+                    budget: budget_properties.heap_budget[i],
+                    is_exact: true,
+                }).collect()
+        }
+        let mut budgets: Vec32<_> = heaps
+            .iter()
+            .enumerate()
+            .map(|(i, heap)| MemoryHeapBudget {
+                heap_index: i as u32,
+                size: heap.size,
+                device_local: heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL),
+                usage: 0,
+                budget: (heap.size as f64 * FALLBACK_BUDGET_FRACTION) as DeviceSize,
+                is_exact: false,
+            }).collect();
+        for statistics in self.memory_type_statistics() {
+            budgets[statistics.heap_index as usize].usage += statistics.allocation_bytes;
+        }
+        budgets
+    }
+
+    /// Returns statistics of the [`device memory`][1] allocated through this device from each
+    /// memory type.
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+    pub fn memory_type_statistics(&self) -> Vec32<MemoryTypeStatistics> {
+        let memory_properties = self.physical_device().memory_properties();
+        memory_properties.memory_types[..memory_properties.memory_type_count as usize]
+            .iter()
+            .enumerate()
+            .map(|(i, memory_type)| {
+                let (allocation_count, allocation_bytes) = self.inner.memory_usage
+                    .type_usage(i as u32);
+                MemoryTypeStatistics {
+                    memory_type_index: i as u32,
+                    heap_index: memory_type.heap_index,
+                    properties: memory_type.property_flags.into(),
+                    allocation_count,
+                    allocation_bytes,
+                }
+            }).collect()
+    }
+
+    #[inline(always)]
+    pub(crate) fn memory_usage(&self) -> &MemoryUsage {
+        &self.inner.memory_usage
+    }
+
+    #[inline(always)]
+    pub(crate) fn descriptor_buffer_address_space(&self) -> &DescriptorBufferAddressSpace {
+        &self.inner.descriptor_buffer_address_space
+    }
+}
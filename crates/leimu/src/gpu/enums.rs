@@ -81,6 +81,46 @@ ash_style_enum! {
         /// Specifies that the buffer *can* be used as in indirect commands.
         #[display("indirect buffer")]
         INDIRECT_BUFFER = vk::BufferUsageFlags::INDIRECT_BUFFER.as_raw(),
+        /// Specifies that the buffer's device address *can* be queried.
+        ///
+        /// Requires [`ext::acceleration_structure`] or [`ext::descriptor_buffer`].
+        #[display("shader device address")]
+        SHADER_DEVICE_ADDRESS = vk::BufferUsageFlags::SHADER_DEVICE_ADDRESS.as_raw(),
+        /// Specifies that the buffer *can* be used as a read-only input of acceleration structure
+        /// builds, such as vertex, index, transform, AABB and instance data.
+        ///
+        /// Requires [`ext::acceleration_structure`].
+        #[display("acceleration structure build input read only")]
+        ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY =
+            vk::BufferUsageFlags::ACCELERATION_STRUCTURE_BUILD_INPUT_READ_ONLY_KHR.as_raw(),
+        /// Specifies that the buffer *can* be used as the backing storage of
+        /// [`acceleration structures`][1].
+        ///
+        /// Requires [`ext::acceleration_structure`].
+        ///
+        /// [1]: AccelerationStructureId
+        #[display("acceleration structure storage")]
+        ACCELERATION_STRUCTURE_STORAGE =
+            vk::BufferUsageFlags::ACCELERATION_STRUCTURE_STORAGE_KHR.as_raw(),
+        /// Specifies that the buffer *can* be used as a shader binding table.
+        ///
+        /// Requires [`ext::ray_tracing_pipeline`].
+        #[display("shader binding table")]
+        SHADER_BINDING_TABLE = vk::BufferUsageFlags::SHADER_BINDING_TABLE_KHR.as_raw(),
+        /// Specifies that the buffer *can* be bound as a descriptor buffer holding sampler and
+        /// combined image sampler descriptors.
+        ///
+        /// Requires [`ext::descriptor_buffer`].
+        #[display("sampler descriptor buffer")]
+        SAMPLER_DESCRIPTOR_BUFFER =
+            vk::BufferUsageFlags::SAMPLER_DESCRIPTOR_BUFFER_EXT.as_raw(),
+        /// Specifies that the buffer *can* be bound as a descriptor buffer holding resource
+        /// descriptors.
+        ///
+        /// Requires [`ext::descriptor_buffer`].
+        #[display("resource descriptor buffer")]
+        RESOURCE_DESCRIPTOR_BUFFER =
+            vk::BufferUsageFlags::RESOURCE_DESCRIPTOR_BUFFER_EXT.as_raw(),
     }
     /// Specifies what an [`Image`] can be used for.
     ///
@@ -194,6 +234,18 @@ ash_style_enum! {
         /// Specifies that the format *can* be used as a vertex attribute format.
         #[display("vertex buffer")]
         VERTEX_BUFFER = vk::FormatFeatureFlags2::VERTEX_BUFFER.as_raw(),
+        /// Specifies that the format *can* be used as the format of a buffer view used as a
+        /// uniform texel buffer.
+        #[display("uniform texel buffer")]
+        UNIFORM_TEXEL_BUFFER = vk::FormatFeatureFlags2::UNIFORM_TEXEL_BUFFER.as_raw(),
+        /// Specifies that the format *can* be used as the format of a buffer view used as a
+        /// storage texel buffer.
+        #[display("storage texel buffer")]
+        STORAGE_TEXEL_BUFFER = vk::FormatFeatureFlags2::STORAGE_TEXEL_BUFFER.as_raw(),
+        /// Specifies that the format *can* be used as the format of a buffer view used as a
+        /// storage texel buffer that supports atomic operations.
+        #[display("storage texel buffer atomic")]
+        STORAGE_TEXEL_BUFFER_ATOMIC = vk::FormatFeatureFlags2::STORAGE_TEXEL_BUFFER_ATOMIC.as_raw(),
         /// Specifies that an image view *can* be used as a color attachment.
         #[display("color attachment")]
         COLOR_ATTACHMENT = vk::FormatFeatureFlags2::COLOR_ATTACHMENT.as_raw(),
@@ -220,6 +272,13 @@ ash_style_enum! {
         /// clear commands.
         #[display("transfer destination")]
         TRANSFER_DST = vk::FormatFeatureFlags2::TRANSFER_DST.as_raw(),
+        /// Specifies that the format *can* be used as the vertex format of
+        /// [`acceleration structure triangle geometry`][1].
+        ///
+        /// [1]: GeometryData::Triangles
+        #[display("acceleration structure vertex buffer")]
+        ACCELERATION_STRUCTURE_VERTEX_BUFFER =
+            vk::FormatFeatureFlags2::ACCELERATION_STRUCTURE_VERTEX_BUFFER_KHR.as_raw(),
     }
 
     #[flags(Flags32)]
@@ -272,6 +331,12 @@ ash_style_enum! {
         /// - [`ComputeCommands`]
         #[display("compute")]
         COMPUTE = vk::QueueFlags::COMPUTE.as_raw(),
+        /// Specifies that the queue supports sparse memory binding.
+        ///
+        /// # Supports commands
+        /// - [`SparseBindCommands`]
+        #[display("sparse binding")]
+        SPARSE_BINDING = vk::QueueFlags::SPARSE_BINDING.as_raw(),
     }
 }
 
@@ -862,6 +927,146 @@ impl BlendOp {
     }
 }
 
+/// Specifies how presented swapchain images are queued for display.
+///
+/// Default value is [`PresentMode::Fifo`].
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkPresentModeKHR.html>
+#[repr(i32)]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+pub enum PresentMode {
+    /// Specifies that images are presented immediately, which *may* cause tearing.
+    #[display("immediate")]
+    Immediate = vk::PresentModeKHR::IMMEDIATE.as_raw(),
+    /// Specifies that the latest image replaces any image waiting for the next vertical blank.
+    ///
+    /// This has low latency and no tearing, but frames are rendered even when they are never
+    /// displayed.
+    #[display("mailbox")]
+    Mailbox = vk::PresentModeKHR::MAILBOX.as_raw(),
+    /// Specifies that images are queued and presented on vertical blanks (vsync).
+    ///
+    /// This is the only present mode, which is always supported.
+    #[default]
+    #[display("fifo")]
+    Fifo = vk::PresentModeKHR::FIFO.as_raw(),
+    /// Like [`PresentMode::Fifo`], except that a late image is presented immediately, which *may*
+    /// cause tearing.
+    #[display("fifo relaxed")]
+    FifoRelaxed = vk::PresentModeKHR::FIFO_RELAXED.as_raw(),
+}
+
+impl PresentMode {
+
+    #[inline]
+    pub fn as_raw(self) -> i32 {
+        self as i32
+    }
+
+    pub(crate) fn from_vk(value: vk::PresentModeKHR) -> Option<Self> {
+        Some(match value {
+            vk::PresentModeKHR::IMMEDIATE => Self::Immediate,
+            vk::PresentModeKHR::MAILBOX => Self::Mailbox,
+            vk::PresentModeKHR::FIFO => Self::Fifo,
+            vk::PresentModeKHR::FIFO_RELAXED => Self::FifoRelaxed,
+            _ => return None,
+        })
+    }
+}
+
+/// Specifies how the presentation engine interprets the values of swapchain images.
+///
+/// Color spaces other than [`ColorSpace::SrgbNonlinear`] are only reported by surfaces, when
+/// [`VK_EXT_swapchain_colorspace`][1] is enabled on the [`Instance`], which is done
+/// automatically when it's present.
+///
+/// Default value is [`ColorSpace::SrgbNonlinear`].
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkColorSpaceKHR.html>
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_swapchain_colorspace.html
+#[repr(i32)]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+pub enum ColorSpace {
+    /// Specifies the sRGB color space with the sRGB transfer function.
+    #[default]
+    #[display("sRGB nonlinear")]
+    SrgbNonlinear = vk::ColorSpaceKHR::SRGB_NONLINEAR.as_raw(),
+    /// Specifies the Display-P3 color space with the sRGB transfer function.
+    #[display("Display-P3 nonlinear")]
+    DisplayP3Nonlinear = vk::ColorSpaceKHR::DISPLAY_P3_NONLINEAR_EXT.as_raw(),
+    /// Specifies the extended sRGB color space with a linear transfer function.
+    ///
+    /// This is typically used with [`Format::R16g16b16a16Sfloat`] for HDR output, where values
+    /// outside of \[0, 1\] extend past the sRGB gamut and brightness.
+    #[display("extended sRGB linear")]
+    ExtendedSrgbLinear = vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT.as_raw(),
+    /// Specifies the extended sRGB color space with the sRGB transfer function.
+    #[display("extended sRGB nonlinear")]
+    ExtendedSrgbNonlinear = vk::ColorSpaceKHR::EXTENDED_SRGB_NONLINEAR_EXT.as_raw(),
+    /// Specifies the Display-P3 color space with a linear transfer function.
+    #[display("Display-P3 linear")]
+    DisplayP3Linear = vk::ColorSpaceKHR::DISPLAY_P3_LINEAR_EXT.as_raw(),
+    /// Specifies the DCI-P3 color space with the DCI-P3 transfer function.
+    #[display("DCI-P3 nonlinear")]
+    DciP3Nonlinear = vk::ColorSpaceKHR::DCI_P3_NONLINEAR_EXT.as_raw(),
+    /// Specifies the BT.709 color space with a linear transfer function.
+    #[display("BT.709 linear")]
+    Bt709Linear = vk::ColorSpaceKHR::BT709_LINEAR_EXT.as_raw(),
+    /// Specifies the BT.709 color space with the BT.709 transfer function.
+    #[display("BT.709 nonlinear")]
+    Bt709Nonlinear = vk::ColorSpaceKHR::BT709_NONLINEAR_EXT.as_raw(),
+    /// Specifies the BT.2020 color space with a linear transfer function.
+    #[display("BT.2020 linear")]
+    Bt2020Linear = vk::ColorSpaceKHR::BT2020_LINEAR_EXT.as_raw(),
+    /// Specifies the HDR10 (BT.2020) color space with the SMPTE ST 2084 perceptual quantizer
+    /// transfer function.
+    ///
+    /// This is typically used with [`Format::A2b10g10r10UnormPack32`].
+    #[display("HDR10 ST 2084")]
+    Hdr10St2084 = vk::ColorSpaceKHR::HDR10_ST2084_EXT.as_raw(),
+    /// Specifies the HDR10 (BT.2020) color space with the hybrid log gamma transfer function.
+    #[display("HDR10 HLG")]
+    Hdr10Hlg = vk::ColorSpaceKHR::HDR10_HLG_EXT.as_raw(),
+    /// Specifies the Adobe RGB color space with a linear transfer function.
+    #[display("Adobe RGB linear")]
+    AdobeRgbLinear = vk::ColorSpaceKHR::ADOBERGB_LINEAR_EXT.as_raw(),
+    /// Specifies the Adobe RGB color space with the gamma 2.2 transfer function.
+    #[display("Adobe RGB nonlinear")]
+    AdobeRgbNonlinear = vk::ColorSpaceKHR::ADOBERGB_NONLINEAR_EXT.as_raw(),
+    /// Specifies that color components are passed to the display as is.
+    #[display("pass through")]
+    PassThrough = vk::ColorSpaceKHR::PASS_THROUGH_EXT.as_raw(),
+}
+
+impl ColorSpace {
+
+    #[inline]
+    pub fn as_raw(self) -> i32 {
+        self as i32
+    }
+
+    pub(crate) fn from_vk(value: vk::ColorSpaceKHR) -> Option<Self> {
+        Some(match value {
+            vk::ColorSpaceKHR::SRGB_NONLINEAR => Self::SrgbNonlinear,
+            vk::ColorSpaceKHR::DISPLAY_P3_NONLINEAR_EXT => Self::DisplayP3Nonlinear,
+            vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT => Self::ExtendedSrgbLinear,
+            vk::ColorSpaceKHR::EXTENDED_SRGB_NONLINEAR_EXT => Self::ExtendedSrgbNonlinear,
+            vk::ColorSpaceKHR::DISPLAY_P3_LINEAR_EXT => Self::DisplayP3Linear,
+            vk::ColorSpaceKHR::DCI_P3_NONLINEAR_EXT => Self::DciP3Nonlinear,
+            vk::ColorSpaceKHR::BT709_LINEAR_EXT => Self::Bt709Linear,
+            vk::ColorSpaceKHR::BT709_NONLINEAR_EXT => Self::Bt709Nonlinear,
+            vk::ColorSpaceKHR::BT2020_LINEAR_EXT => Self::Bt2020Linear,
+            vk::ColorSpaceKHR::HDR10_ST2084_EXT => Self::Hdr10St2084,
+            vk::ColorSpaceKHR::HDR10_HLG_EXT => Self::Hdr10Hlg,
+            vk::ColorSpaceKHR::ADOBERGB_LINEAR_EXT => Self::AdobeRgbLinear,
+            vk::ColorSpaceKHR::ADOBERGB_NONLINEAR_EXT => Self::AdobeRgbNonlinear,
+            vk::ColorSpaceKHR::PASS_THROUGH_EXT => Self::PassThrough,
+            _ => return None,
+        })
+    }
+}
+
 macro_rules! impl_convert_vk {
     ($([$name:ident, vk::$vk:ident]),+ $(,)?) => {
         $(
@@ -901,6 +1106,8 @@ impl_convert_vk! {
     [ResolveModes, vk::ResolveModeFlags],
     [ColorComponents, vk::ColorComponentFlags],
     [CullModes, vk::CullModeFlags],
+    [PresentMode, vk::PresentModeKHR],
+    [ColorSpace, vk::ColorSpaceKHR],
 }
 
 impl From<vk::SampleCountFlags> for MsaaSamples {
@@ -26,6 +26,16 @@
 //! - [`VK_KHR_index_type_uint8`][index_type_uint8]
 //! - [`VK_KHR_robustness2`][robustness2]
 //! - [`VK_EXT_pipeline_robustness`][pipeline_robustness]
+//! - [`VK_KHR_draw_indirect_count`][draw_indirect_count]
+//! - [`VK_EXT_descriptor_indexing`][descriptor_indexing]
+//! - [`VK_KHR_multiview`][multiview]
+//! - [`VK_EXT_mesh_shader`][mesh_shader]
+//! - [`VK_KHR_deferred_host_operations`][deferred_host_operations]
+//! - [`VK_KHR_acceleration_structure`][acceleration_structure]
+//! - [`VK_KHR_ray_tracing_pipeline`][ray_tracing_pipeline]
+//! - [`VK_KHR_ray_query`][ray_query]
+//! - [`VK_EXT_descriptor_buffer`][descriptor_buffer]
+//! - [`VK_EXT_memory_budget`][memory_budget]
 //!
 //! # Future extensions
 //!  *can* be enabled, but doesn't yet have a high level
@@ -55,6 +65,16 @@ pub mod index_type_uint8;
 pub mod robust_image_access;
 pub mod robustness2;
 pub mod pipeline_robustness;
+pub mod draw_indirect_count;
+pub mod descriptor_indexing;
+pub mod multiview;
+pub mod mesh_shader;
+pub mod deferred_host_operations;
+pub mod acceleration_structure;
+pub mod ray_tracing_pipeline;
+pub mod ray_query;
+pub mod descriptor_buffer;
+pub mod memory_budget;
 
 pub(crate) use core::core_extensions;
 
@@ -0,0 +1,433 @@
+//! Provided by [`VK_KHR_acceleration_structure`][1].
+//!
+//! Enables [`acceleration structures`][2], which can be built with
+//! [`ComputeCommands::build_acceleration_structures`][3] and traced against with
+//! [`ray tracing pipelines`][4].
+//!
+//! The [`deferred_host_operations`][5] extension *must* also be enabled. Enabling this extension
+//! also enables the buffer device address feature, and all device memory is allocated with the
+//! device address flag.
+//!
+//! [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_KHR_acceleration_structure.html
+//! [2]: crate::gpu::AccelerationStructureId
+//! [3]: crate::gpu::ComputeCommands::build_acceleration_structures
+//! [4]: super::ray_tracing_pipeline
+//! [5]: super::deferred_host_operations
+
+use {
+    ::core::ffi::{
+        CStr,
+        c_void,
+    },
+    nox_ash::{
+        vk,
+        khr,
+        load_fn,
+        prelude::VkResult,
+    },
+    nox_mem::option::OptionExt,
+    super::*,
+};
+
+/// Attribute names.
+pub struct Attributes;
+
+impl Attributes {
+    /// Attribute type `bool`.
+    pub const IS_ENABLED: ConstName = ConstName::new("acceleration_structure");
+    /// Attribute type `device_size`.
+    pub const MAX_GEOMETRY_COUNT: ConstName
+        = ConstName::new("acceleration_structure max_geometry_count");
+    /// Attribute type `device_size`.
+    pub const MAX_INSTANCE_COUNT: ConstName
+        = ConstName::new("acceleration_structure max_instance_count");
+    /// Attribute type `device_size`.
+    pub const MAX_PRIMITIVE_COUNT: ConstName
+        = ConstName::new("acceleration_structure max_primitive_count");
+    /// Attribute type `u32`.
+    pub const MAX_DESCRIPTOR_SET_ACCELERATION_STRUCTURES: ConstName
+        = ConstName::new("acceleration_structure max_descriptor_set_acceleration_structures");
+    /// Attribute type `u32`.
+    pub const MIN_ACCELERATION_STRUCTURE_SCRATCH_OFFSET_ALIGNMENT: ConstName
+        = ConstName::new("acceleration_structure min_acceleration_structure_scratch_offset_alignment");
+}
+
+/// The extension type.
+#[derive(Clone, Copy)]
+pub struct Extension;
+
+unsafe impl DeviceExtension for Extension {
+
+    fn get_info(&self, _: &DeviceAttributes) -> Option<DeviceExtensionInfo> {
+        Some(DeviceExtensionInfo {
+            name: khr::acceleration_structure::NAME,
+            deprecation_version: Version::MAX,
+            precondition: Precondition::new(|ctx| {
+                if ctx.api_version() < Version::VULKAN_API_VERSION_1_2 {
+                    return Some(MissingDeviceFeatureError::new("Vulkan 1.2"))
+                }
+                let mut features = vk::PhysicalDeviceAccelerationStructureFeaturesKHR::default();
+                ctx.get_features(&mut features);
+                let mut bda_features = vk::PhysicalDeviceBufferDeviceAddressFeatures::default();
+                ctx.get_features(&mut bda_features);
+                if features.acceleration_structure == 0 {
+                    Some(MissingDeviceFeatureError::new("acceleration structure"))
+                } else if bda_features.buffer_device_address == 0 {
+                    Some(MissingDeviceFeatureError::new("buffer device address"))
+                } else {
+                    None
+                }
+            }),
+        })
+    }
+
+    fn register(
+        &self,
+        ctx: &mut PhysicalDeviceContext<'_>,
+    ) -> Option<vk::ExtendsDeviceCreateInfoObj> {
+        let mut properties = vk::PhysicalDeviceAccelerationStructurePropertiesKHR::default();
+        ctx.get_properties(&mut properties);
+        ctx.register_attribute(DeviceAttribute::new_device_size(
+            Attributes::MAX_GEOMETRY_COUNT,
+            properties.max_geometry_count,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_device_size(
+            Attributes::MAX_INSTANCE_COUNT,
+            properties.max_instance_count,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_device_size(
+            Attributes::MAX_PRIMITIVE_COUNT,
+            properties.max_primitive_count,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_DESCRIPTOR_SET_ACCELERATION_STRUCTURES,
+            properties.max_descriptor_set_acceleration_structures,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MIN_ACCELERATION_STRUCTURE_SCRATCH_OFFSET_ALIGNMENT,
+            properties.min_acceleration_structure_scratch_offset_alignment,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_bool(
+            Attributes::IS_ENABLED, true,
+        ));
+        ctx.vulkan_12_features().buffer_device_address = vk::TRUE;
+        Some(create_extends_device_create_info_obj(
+            vk::PhysicalDeviceAccelerationStructureFeaturesKHR
+                ::default()
+                .acceleration_structure(true)
+        ))
+    }
+
+    fn boxed(&self) -> Box<dyn DeviceExtension> {
+        Box::new(*self)
+    }
+}
+
+/// Raw [`acceleration_structure`] device-level function pointers.
+#[derive(Clone)]
+pub struct DeviceFn {
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCreateAccelerationStructureKHR.html>
+    pub create_acceleration_structure: vk::PFN_vkCreateAccelerationStructureKHR,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkDestroyAccelerationStructureKHR.html>
+    pub destroy_acceleration_structure: vk::PFN_vkDestroyAccelerationStructureKHR,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetAccelerationStructureBuildSizesKHR.html>
+    pub get_acceleration_structure_build_sizes: vk::PFN_vkGetAccelerationStructureBuildSizesKHR,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetAccelerationStructureDeviceAddressKHR.html>
+    pub get_acceleration_structure_device_address:
+        vk::PFN_vkGetAccelerationStructureDeviceAddressKHR,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBuildAccelerationStructuresKHR.html>
+    pub cmd_build_acceleration_structures: vk::PFN_vkCmdBuildAccelerationStructuresKHR,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdCopyAccelerationStructureKHR.html>
+    pub cmd_copy_acceleration_structure: vk::PFN_vkCmdCopyAccelerationStructureKHR,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdWriteAccelerationStructuresPropertiesKHR.html>
+    pub cmd_write_acceleration_structures_properties:
+        vk::PFN_vkCmdWriteAccelerationStructuresPropertiesKHR,
+}
+
+unsafe impl Send for DeviceFn {}
+unsafe impl Sync for DeviceFn {}
+
+impl DeviceFn {
+
+    pub fn load<F: FnMut(&CStr) -> *const c_void>(
+        mut f: F,
+    ) -> Self
+    {
+        unsafe { Self {
+            create_acceleration_structure: load_fn!(
+                fn create_acceleration_structure(
+                    vk::Device,
+                    *const vk::AccelerationStructureCreateInfoKHR<'_>,
+                    *const vk::AllocationCallbacks<'_>,
+                    *mut vk::AccelerationStructureKHR,
+                ) -> vk::Result,
+                f,
+                c"vkCreateAccelerationStructureKHR",
+                vk::PFN_vkCreateAccelerationStructureKHR,
+            ),
+            destroy_acceleration_structure: load_fn!(
+                fn destroy_acceleration_structure(
+                    vk::Device,
+                    vk::AccelerationStructureKHR,
+                    *const vk::AllocationCallbacks<'_>,
+                ) -> (),
+                f,
+                c"vkDestroyAccelerationStructureKHR",
+                vk::PFN_vkDestroyAccelerationStructureKHR,
+            ),
+            get_acceleration_structure_build_sizes: load_fn!(
+                fn get_acceleration_structure_build_sizes(
+                    vk::Device,
+                    vk::AccelerationStructureBuildTypeKHR,
+                    *const vk::AccelerationStructureBuildGeometryInfoKHR<'_>,
+                    *const u32,
+                    *mut vk::AccelerationStructureBuildSizesInfoKHR<'_>,
+                ) -> (),
+                f,
+                c"vkGetAccelerationStructureBuildSizesKHR",
+                vk::PFN_vkGetAccelerationStructureBuildSizesKHR,
+            ),
+            get_acceleration_structure_device_address: load_fn!(
+                fn get_acceleration_structure_device_address(
+                    vk::Device,
+                    *const vk::AccelerationStructureDeviceAddressInfoKHR<'_>,
+                ) -> vk::DeviceAddress,
+                f,
+                c"vkGetAccelerationStructureDeviceAddressKHR",
+                vk::PFN_vkGetAccelerationStructureDeviceAddressKHR,
+            ),
+            cmd_build_acceleration_structures: load_fn!(
+                fn cmd_build_acceleration_structures(
+                    vk::CommandBuffer,
+                    u32,
+                    *const vk::AccelerationStructureBuildGeometryInfoKHR<'_>,
+                    *const *const vk::AccelerationStructureBuildRangeInfoKHR,
+                ) -> (),
+                f,
+                c"vkCmdBuildAccelerationStructuresKHR",
+                vk::PFN_vkCmdBuildAccelerationStructuresKHR,
+            ),
+            cmd_copy_acceleration_structure: load_fn!(
+                fn cmd_copy_acceleration_structure(
+                    vk::CommandBuffer,
+                    *const vk::CopyAccelerationStructureInfoKHR<'_>,
+                ) -> (),
+                f,
+                c"vkCmdCopyAccelerationStructureKHR",
+                vk::PFN_vkCmdCopyAccelerationStructureKHR,
+            ),
+            cmd_write_acceleration_structures_properties: load_fn!(
+                fn cmd_write_acceleration_structures_properties(
+                    vk::CommandBuffer,
+                    u32,
+                    *const vk::AccelerationStructureKHR,
+                    vk::QueryType,
+                    vk::QueryPool,
+                    u32,
+                ) -> (),
+                f,
+                c"vkCmdWriteAccelerationStructuresPropertiesKHR",
+                vk::PFN_vkCmdWriteAccelerationStructuresPropertiesKHR,
+            ),
+        } }
+    }
+}
+
+/// [`acceleration_structure`] device-level functions.
+#[derive(Clone)]
+pub struct Device {
+    fp: DeviceFn,
+    handle: vk::Device,
+}
+
+impl Device {
+
+    #[inline(always)]
+    pub fn fp(&self) -> &DeviceFn {
+        &self.fp
+    }
+
+    #[inline(always)]
+    pub fn device(&self) -> vk::Device {
+        self.handle
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCreateAccelerationStructureKHR.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn create_acceleration_structure(
+        &self,
+        create_info: &vk::AccelerationStructureCreateInfoKHR<'_>,
+        allocator: Option<&vk::AllocationCallbacks<'_>>,
+    ) -> VkResult<vk::AccelerationStructureKHR> {
+        let mut acceleration_structure = vk::AccelerationStructureKHR::null();
+        unsafe {
+            (self.fp().create_acceleration_structure)(
+                self.handle,
+                create_info,
+                allocator.as_ptr(),
+                &mut acceleration_structure,
+            )
+        }.result_with_success(acceleration_structure)
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkDestroyAccelerationStructureKHR.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn destroy_acceleration_structure(
+        &self,
+        acceleration_structure: vk::AccelerationStructureKHR,
+        allocator: Option<&vk::AllocationCallbacks<'_>>,
+    ) {
+        unsafe {
+            (self.fp().destroy_acceleration_structure)(
+                self.handle,
+                acceleration_structure,
+                allocator.as_ptr(),
+            )
+        }
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetAccelerationStructureBuildSizesKHR.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn get_acceleration_structure_build_sizes(
+        &self,
+        build_type: vk::AccelerationStructureBuildTypeKHR,
+        build_info: &vk::AccelerationStructureBuildGeometryInfoKHR<'_>,
+        max_primitive_counts: &[u32],
+        size_info: &mut vk::AccelerationStructureBuildSizesInfoKHR<'_>,
+    ) {
+        unsafe {
+            (self.fp().get_acceleration_structure_build_sizes)(
+                self.handle,
+                build_type,
+                build_info,
+                max_primitive_counts.as_ptr(),
+                size_info,
+            )
+        }
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetAccelerationStructureDeviceAddressKHR.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn get_acceleration_structure_device_address(
+        &self,
+        info: &vk::AccelerationStructureDeviceAddressInfoKHR<'_>,
+    ) -> vk::DeviceAddress {
+        unsafe {
+            (self.fp().get_acceleration_structure_device_address)(
+                self.handle,
+                info,
+            )
+        }
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBuildAccelerationStructuresKHR.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn cmd_build_acceleration_structures(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        infos: &[vk::AccelerationStructureBuildGeometryInfoKHR<'_>],
+        build_range_infos: &[*const vk::AccelerationStructureBuildRangeInfoKHR],
+    ) {
+        unsafe {
+            (self.fp().cmd_build_acceleration_structures)(
+                command_buffer,
+                infos.len() as u32,
+                infos.as_ptr(),
+                build_range_infos.as_ptr(),
+            )
+        }
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdCopyAccelerationStructureKHR.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn cmd_copy_acceleration_structure(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        info: &vk::CopyAccelerationStructureInfoKHR<'_>,
+    ) {
+        unsafe {
+            (self.fp().cmd_copy_acceleration_structure)(
+                command_buffer,
+                info,
+            )
+        }
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdWriteAccelerationStructuresPropertiesKHR.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn cmd_write_acceleration_structures_properties(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        acceleration_structures: &[vk::AccelerationStructureKHR],
+        query_type: vk::QueryType,
+        query_pool: vk::QueryPool,
+        first_query: u32,
+    ) {
+        unsafe {
+            (self.fp().cmd_write_acceleration_structures_properties)(
+                command_buffer,
+                acceleration_structures.len() as u32,
+                acceleration_structures.as_ptr(),
+                query_type,
+                query_pool,
+                first_query,
+            )
+        }
+    }
+}
+
+impl AnyExtensionDevice for Device {
+
+    #[inline(always)]
+    fn boxed(&self) -> Box<dyn AnyExtensionDevice> {
+        Box::new(self.clone())
+    }
+}
+
+impl ExtensionDevice for Device {
+
+    const NAME: ConstName = ConstName::new(
+        "acceleration structure device"
+    );
+
+    #[inline(always)]
+    fn precondition<'a, F>(f: F) -> bool
+        where F: Fn(&ConstName) -> Option<&'a DeviceAttribute>
+    {
+        f(&Attributes::IS_ENABLED)
+            .is_some_and(|value| value.bool().is_some_and(|value| value))
+    }
+
+    #[inline(always)]
+    fn new(device: &LogicalDevice) -> Box<Self>
+    {
+        let fp = DeviceFn::load(|name| unsafe {
+            ::core::mem::transmute(device.get_proc_addr(name))
+        });
+        Box::new(Self {
+            fp,
+            handle: device.handle(),
+        })
+    }
+}
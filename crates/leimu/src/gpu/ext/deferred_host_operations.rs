@@ -0,0 +1,52 @@
+//! Provided by [`VK_KHR_deferred_host_operations`][1].
+//!
+//! Required by [`acceleration_structure`][2]. Nox doesn't currently defer any host operations, so
+//! this extension only needs to be enabled alongside it.
+//!
+//! [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_KHR_deferred_host_operations.html
+//! [2]: super::acceleration_structure
+
+use {
+    nox_ash::{
+        vk,
+        khr,
+    },
+    super::*,
+};
+
+/// Attribute names.
+pub struct Attributes;
+
+impl Attributes {
+    /// Attribute type `bool`.
+    pub const IS_ENABLED: ConstName = ConstName::new("deferred_host_operations");
+}
+
+/// The extension type.
+#[derive(Clone, Copy)]
+pub struct Extension;
+
+unsafe impl DeviceExtension for Extension {
+
+    fn get_info(&self, _: &DeviceAttributes) -> Option<DeviceExtensionInfo> {
+        Some(DeviceExtensionInfo {
+            name: khr::deferred_host_operations::NAME,
+            deprecation_version: Version::MAX,
+            precondition: None,
+        })
+    }
+
+    fn register(
+        &self,
+        ctx: &mut PhysicalDeviceContext<'_>,
+    ) -> Option<vk::ExtendsDeviceCreateInfoObj> {
+        ctx.register_attribute(DeviceAttribute::new_bool(
+            Attributes::IS_ENABLED, true,
+        ));
+        None
+    }
+
+    fn boxed(&self) -> Box<dyn DeviceExtension> {
+        Box::new(*self)
+    }
+}
@@ -0,0 +1,491 @@
+//! Provided by [`VK_EXT_descriptor_buffer`][1].
+//!
+//! When enabled, descriptor sets are stored in descriptor buffers instead of Vulkan descriptor
+//! pools. Each [`descriptor pool`][2] is backed by a single host visible descriptor buffer, which
+//! descriptor sets are sub-allocated from and which descriptor writes are written to directly.
+//! The descriptor set layouts of every [`shader set`][3] are created for descriptor buffers and
+//! descriptor sets are bound with [`PipelineCommands::bind_descriptor_buffers`][4].
+//!
+//! Enabling this extension also enables the buffer device address feature, and all device memory
+//! is allocated with the device address flag. Buffers referred to by buffer and texel buffer
+//! descriptors *must* be created with [`BufferUsages::SHADER_DEVICE_ADDRESS`][5].
+//!
+//! [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_descriptor_buffer.html
+//! [2]: crate::gpu::Gpu::create_descriptor_pool
+//! [3]: crate::gpu::ShaderSet
+//! [4]: crate::gpu::PipelineCommands::bind_descriptor_buffers
+//! [5]: crate::gpu::BufferUsages::SHADER_DEVICE_ADDRESS
+
+use {
+    ::core::ffi::{
+        CStr,
+        c_void,
+    },
+    nox_ash::{
+        vk,
+        ext,
+        load_fn,
+    },
+    super::*,
+};
+
+/// Attribute names.
+pub struct Attributes;
+
+impl Attributes {
+    /// Attribute type `bool`.
+    pub const IS_ENABLED: ConstName = ConstName::new("descriptor_buffer");
+    /// Attribute type `bool`.
+    ///
+    /// Whether descriptor set layouts with the [`push descriptor flag`][1] *can* be used in
+    /// shader sets together with descriptor buffers.
+    ///
+    /// [1]: crate::gpu::DescriptorSetLayoutFlags::PUSH_DESCRIPTOR
+    pub const IS_PUSH_DESCRIPTORS_ENABLED: ConstName
+        = ConstName::new("descriptor_buffer push_descriptors");
+    /// Attribute type `device_size`.
+    pub const DESCRIPTOR_BUFFER_OFFSET_ALIGNMENT: ConstName
+        = ConstName::new("descriptor_buffer descriptor_buffer_offset_alignment");
+    /// Attribute type `u32`.
+    ///
+    /// The max number of descriptor buffers that can be bound at once.
+    pub const MAX_DESCRIPTOR_BUFFER_BINDINGS: ConstName
+        = ConstName::new("descriptor_buffer max_descriptor_buffer_bindings");
+    /// Attribute type `u32`.
+    ///
+    /// The max number of descriptor buffers with sampler descriptor buffer usage that can be
+    /// bound at once.
+    pub const MAX_SAMPLER_DESCRIPTOR_BUFFER_BINDINGS: ConstName
+        = ConstName::new("descriptor_buffer max_sampler_descriptor_buffer_bindings");
+    /// Attribute type `u32`.
+    ///
+    /// The max number of descriptor buffers with resource descriptor buffer usage that can be
+    /// bound at once.
+    pub const MAX_RESOURCE_DESCRIPTOR_BUFFER_BINDINGS: ConstName
+        = ConstName::new("descriptor_buffer max_resource_descriptor_buffer_bindings");
+    /// Attribute type `device_size`.
+    ///
+    /// The max range, in bytes, from the start of a bound sampler descriptor buffer that
+    /// descriptor sets can be placed at.
+    pub const MAX_SAMPLER_DESCRIPTOR_BUFFER_RANGE: ConstName
+        = ConstName::new("descriptor_buffer max_sampler_descriptor_buffer_range");
+    /// Attribute type `device_size`.
+    ///
+    /// The max range, in bytes, from the start of a bound resource descriptor buffer that
+    /// descriptor sets can be placed at.
+    pub const MAX_RESOURCE_DESCRIPTOR_BUFFER_RANGE: ConstName
+        = ConstName::new("descriptor_buffer max_resource_descriptor_buffer_range");
+    /// Attribute type `device_size`.
+    ///
+    /// The total size, in bytes, of all buffers with sampler descriptor buffer usage.
+    pub const SAMPLER_DESCRIPTOR_BUFFER_ADDRESS_SPACE_SIZE: ConstName
+        = ConstName::new("descriptor_buffer sampler_descriptor_buffer_address_space_size");
+    /// Attribute type `device_size`.
+    ///
+    /// The total size, in bytes, of all buffers with resource descriptor buffer usage.
+    pub const RESOURCE_DESCRIPTOR_BUFFER_ADDRESS_SPACE_SIZE: ConstName
+        = ConstName::new("descriptor_buffer resource_descriptor_buffer_address_space_size");
+    /// Attribute type `device_size`.
+    ///
+    /// The total size, in bytes, of all buffers with either sampler or resource descriptor
+    /// buffer usage.
+    pub const DESCRIPTOR_BUFFER_ADDRESS_SPACE_SIZE: ConstName
+        = ConstName::new("descriptor_buffer descriptor_buffer_address_space_size");
+    /// Attribute type `device_size`.
+    pub const SAMPLER_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer sampler_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const COMBINED_IMAGE_SAMPLER_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer combined_image_sampler_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const SAMPLED_IMAGE_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer sampled_image_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const STORAGE_IMAGE_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer storage_image_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const UNIFORM_TEXEL_BUFFER_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer uniform_texel_buffer_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const ROBUST_UNIFORM_TEXEL_BUFFER_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer robust_uniform_texel_buffer_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const STORAGE_TEXEL_BUFFER_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer storage_texel_buffer_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const ROBUST_STORAGE_TEXEL_BUFFER_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer robust_storage_texel_buffer_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const UNIFORM_BUFFER_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer uniform_buffer_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const ROBUST_UNIFORM_BUFFER_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer robust_uniform_buffer_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const STORAGE_BUFFER_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer storage_buffer_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const ROBUST_STORAGE_BUFFER_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer robust_storage_buffer_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const INPUT_ATTACHMENT_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer input_attachment_descriptor_size");
+    /// Attribute type `device_size`.
+    pub const ACCELERATION_STRUCTURE_DESCRIPTOR_SIZE: ConstName
+        = ConstName::new("descriptor_buffer acceleration_structure_descriptor_size");
+}
+
+/// The extension type.
+#[derive(Clone, Copy)]
+pub struct Extension;
+
+unsafe impl DeviceExtension for Extension {
+
+    fn get_info(&self, _: &DeviceAttributes) -> Option<DeviceExtensionInfo> {
+        Some(DeviceExtensionInfo {
+            name: ext::descriptor_buffer::NAME,
+            deprecation_version: Version::MAX,
+            precondition: Precondition::new(|ctx| {
+                if ctx.api_version() < Version::VULKAN_API_VERSION_1_2 {
+                    return Some(MissingDeviceFeatureError::new("Vulkan 1.2"))
+                }
+                let mut features = vk::PhysicalDeviceDescriptorBufferFeaturesEXT::default();
+                ctx.get_features(&mut features);
+                let mut bda_features = vk::PhysicalDeviceBufferDeviceAddressFeatures::default();
+                ctx.get_features(&mut bda_features);
+                if features.descriptor_buffer == 0 {
+                    Some(MissingDeviceFeatureError::new("descriptor buffer"))
+                } else if bda_features.buffer_device_address == 0 {
+                    Some(MissingDeviceFeatureError::new("buffer device address"))
+                } else {
+                    None
+                }
+            }),
+        })
+    }
+
+    fn register(
+        &self,
+        ctx: &mut PhysicalDeviceContext<'_>,
+    ) -> Option<vk::ExtendsDeviceCreateInfoObj> {
+        let mut properties = vk::PhysicalDeviceDescriptorBufferPropertiesEXT::default();
+        ctx.get_properties(&mut properties);
+        let mut supported_features = vk::PhysicalDeviceDescriptorBufferFeaturesEXT::default();
+        ctx.get_features(&mut supported_features);
+        ctx.register_attribute(DeviceAttribute::new_device_size(
+            Attributes::DESCRIPTOR_BUFFER_OFFSET_ALIGNMENT,
+            properties.descriptor_buffer_offset_alignment,
+        ));
+        for (name, count) in [
+            (Attributes::MAX_DESCRIPTOR_BUFFER_BINDINGS, properties.max_descriptor_buffer_bindings),
+            (Attributes::MAX_SAMPLER_DESCRIPTOR_BUFFER_BINDINGS,
+                properties.max_sampler_descriptor_buffer_bindings),
+            (Attributes::MAX_RESOURCE_DESCRIPTOR_BUFFER_BINDINGS,
+                properties.max_resource_descriptor_buffer_bindings),
+        ] {
+            ctx.register_attribute(DeviceAttribute::new_u32(name, count));
+        }
+        for (name, size) in [
+            (Attributes::MAX_SAMPLER_DESCRIPTOR_BUFFER_RANGE,
+                properties.max_sampler_descriptor_buffer_range),
+            (Attributes::MAX_RESOURCE_DESCRIPTOR_BUFFER_RANGE,
+                properties.max_resource_descriptor_buffer_range),
+            (Attributes::SAMPLER_DESCRIPTOR_BUFFER_ADDRESS_SPACE_SIZE,
+                properties.sampler_descriptor_buffer_address_space_size),
+            (Attributes::RESOURCE_DESCRIPTOR_BUFFER_ADDRESS_SPACE_SIZE,
+                properties.resource_descriptor_buffer_address_space_size),
+            (Attributes::DESCRIPTOR_BUFFER_ADDRESS_SPACE_SIZE,
+                properties.descriptor_buffer_address_space_size),
+        ] {
+            ctx.register_attribute(DeviceAttribute::new_device_size(name, size));
+        }
+        for (name, size) in [
+            (Attributes::SAMPLER_DESCRIPTOR_SIZE, properties.sampler_descriptor_size),
+            (Attributes::COMBINED_IMAGE_SAMPLER_DESCRIPTOR_SIZE,
+                properties.combined_image_sampler_descriptor_size),
+            (Attributes::SAMPLED_IMAGE_DESCRIPTOR_SIZE, properties.sampled_image_descriptor_size),
+            (Attributes::STORAGE_IMAGE_DESCRIPTOR_SIZE, properties.storage_image_descriptor_size),
+            (Attributes::UNIFORM_TEXEL_BUFFER_DESCRIPTOR_SIZE,
+                properties.uniform_texel_buffer_descriptor_size),
+            (Attributes::ROBUST_UNIFORM_TEXEL_BUFFER_DESCRIPTOR_SIZE,
+                properties.robust_uniform_texel_buffer_descriptor_size),
+            (Attributes::STORAGE_TEXEL_BUFFER_DESCRIPTOR_SIZE,
+                properties.storage_texel_buffer_descriptor_size),
+            (Attributes::ROBUST_STORAGE_TEXEL_BUFFER_DESCRIPTOR_SIZE,
+                properties.robust_storage_texel_buffer_descriptor_size),
+            (Attributes::UNIFORM_BUFFER_DESCRIPTOR_SIZE, properties.uniform_buffer_descriptor_size),
+            (Attributes::ROBUST_UNIFORM_BUFFER_DESCRIPTOR_SIZE,
+                properties.robust_uniform_buffer_descriptor_size),
+            (Attributes::STORAGE_BUFFER_DESCRIPTOR_SIZE, properties.storage_buffer_descriptor_size),
+            (Attributes::ROBUST_STORAGE_BUFFER_DESCRIPTOR_SIZE,
+                properties.robust_storage_buffer_descriptor_size),
+            (Attributes::INPUT_ATTACHMENT_DESCRIPTOR_SIZE,
+                properties.input_attachment_descriptor_size),
+            (Attributes::ACCELERATION_STRUCTURE_DESCRIPTOR_SIZE,
+                properties.acceleration_structure_descriptor_size),
+        ] {
+            ctx.register_attribute(DeviceAttribute::new_device_size(
+                name, size as vk::DeviceSize,
+            ));
+        }
+        ctx.register_attribute(DeviceAttribute::new_bool(
+            Attributes::IS_ENABLED, true,
+        ));
+        let mut features = vk::PhysicalDeviceDescriptorBufferFeaturesEXT
+            ::default()
+            .descriptor_buffer(true);
+        // push descriptor sets don't get a descriptor buffer of their own
+        if supported_features.descriptor_buffer_push_descriptors != 0 &&
+            properties.bufferless_push_descriptors != 0
+        {
+            ctx.register_attribute(DeviceAttribute::new_bool(
+                Attributes::IS_PUSH_DESCRIPTORS_ENABLED, true,
+            ));
+            features.descriptor_buffer_push_descriptors = vk::TRUE;
+        }
+        ctx.vulkan_12_features().buffer_device_address = vk::TRUE;
+        Some(create_extends_device_create_info_obj(features))
+    }
+
+    fn boxed(&self) -> Box<dyn DeviceExtension> {
+        Box::new(*self)
+    }
+}
+
+/// Raw [`descriptor_buffer`] device-level function pointers.
+#[derive(Clone)]
+pub struct DeviceFn {
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetDescriptorSetLayoutSizeEXT.html>
+    pub get_descriptor_set_layout_size: vk::PFN_vkGetDescriptorSetLayoutSizeEXT,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetDescriptorSetLayoutBindingOffsetEXT.html>
+    pub get_descriptor_set_layout_binding_offset: vk::PFN_vkGetDescriptorSetLayoutBindingOffsetEXT,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetDescriptorEXT.html>
+    pub get_descriptor: vk::PFN_vkGetDescriptorEXT,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBindDescriptorBuffersEXT.html>
+    pub cmd_bind_descriptor_buffers: vk::PFN_vkCmdBindDescriptorBuffersEXT,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdSetDescriptorBufferOffsets2EXT.html>
+    ///
+    /// Provided by [`VK_KHR_maintenance6`][1].
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_KHR_maintenance6.html
+    pub cmd_set_descriptor_buffer_offsets2: vk::PFN_vkCmdSetDescriptorBufferOffsets2EXT,
+}
+
+unsafe impl Send for DeviceFn {}
+unsafe impl Sync for DeviceFn {}
+
+impl DeviceFn {
+
+    pub fn load<F: FnMut(&CStr) -> *const c_void>(
+        mut f: F,
+    ) -> Self
+    {
+        unsafe { Self {
+            get_descriptor_set_layout_size: load_fn!(
+                fn get_descriptor_set_layout_size(
+                    vk::Device,
+                    vk::DescriptorSetLayout,
+                    *mut vk::DeviceSize,
+                ) -> (),
+                f,
+                c"vkGetDescriptorSetLayoutSizeEXT",
+                vk::PFN_vkGetDescriptorSetLayoutSizeEXT,
+            ),
+            get_descriptor_set_layout_binding_offset: load_fn!(
+                fn get_descriptor_set_layout_binding_offset(
+                    vk::Device,
+                    vk::DescriptorSetLayout,
+                    u32,
+                    *mut vk::DeviceSize,
+                ) -> (),
+                f,
+                c"vkGetDescriptorSetLayoutBindingOffsetEXT",
+                vk::PFN_vkGetDescriptorSetLayoutBindingOffsetEXT,
+            ),
+            get_descriptor: load_fn!(
+                fn get_descriptor(
+                    vk::Device,
+                    *const vk::DescriptorGetInfoEXT<'_>,
+                    usize,
+                    *mut c_void,
+                ) -> (),
+                f,
+                c"vkGetDescriptorEXT",
+                vk::PFN_vkGetDescriptorEXT,
+            ),
+            cmd_bind_descriptor_buffers: load_fn!(
+                fn cmd_bind_descriptor_buffers(
+                    vk::CommandBuffer,
+                    u32,
+                    *const vk::DescriptorBufferBindingInfoEXT<'_>,
+                ) -> (),
+                f,
+                c"vkCmdBindDescriptorBuffersEXT",
+                vk::PFN_vkCmdBindDescriptorBuffersEXT,
+            ),
+            cmd_set_descriptor_buffer_offsets2: load_fn!(
+                fn cmd_set_descriptor_buffer_offsets2(
+                    vk::CommandBuffer,
+                    *const vk::SetDescriptorBufferOffsetsInfoEXT<'_>,
+                ) -> (),
+                f,
+                c"vkCmdSetDescriptorBufferOffsets2EXT",
+                vk::PFN_vkCmdSetDescriptorBufferOffsets2EXT,
+            ),
+        } }
+    }
+}
+
+/// [`descriptor_buffer`] device-level functions.
+#[derive(Clone)]
+pub struct Device {
+    fp: DeviceFn,
+    handle: vk::Device,
+}
+
+impl Device {
+
+    #[inline(always)]
+    pub fn fp(&self) -> &DeviceFn {
+        &self.fp
+    }
+
+    #[inline(always)]
+    pub fn device(&self) -> vk::Device {
+        self.handle
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetDescriptorSetLayoutSizeEXT.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn get_descriptor_set_layout_size(
+        &self,
+        layout: vk::DescriptorSetLayout,
+    ) -> vk::DeviceSize {
+        let mut size = 0;
+        unsafe {
+            (self.fp().get_descriptor_set_layout_size)(
+                self.handle,
+                layout,
+                &mut size,
+            )
+        }
+        size
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetDescriptorSetLayoutBindingOffsetEXT.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn get_descriptor_set_layout_binding_offset(
+        &self,
+        layout: vk::DescriptorSetLayout,
+        binding: u32,
+    ) -> vk::DeviceSize {
+        let mut offset = 0;
+        unsafe {
+            (self.fp().get_descriptor_set_layout_binding_offset)(
+                self.handle,
+                layout,
+                binding,
+                &mut offset,
+            )
+        }
+        offset
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetDescriptorEXT.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn get_descriptor(
+        &self,
+        info: &vk::DescriptorGetInfoEXT<'_>,
+        descriptor: &mut [u8],
+    ) {
+        unsafe {
+            (self.fp().get_descriptor)(
+                self.handle,
+                info,
+                descriptor.len(),
+                descriptor.as_mut_ptr().cast(),
+            )
+        }
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdBindDescriptorBuffersEXT.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn cmd_bind_descriptor_buffers(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        binding_infos: &[vk::DescriptorBufferBindingInfoEXT<'_>],
+    ) {
+        unsafe {
+            (self.fp().cmd_bind_descriptor_buffers)(
+                command_buffer,
+                binding_infos.len() as u32,
+                binding_infos.as_ptr(),
+            )
+        }
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdSetDescriptorBufferOffsets2EXT.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn cmd_set_descriptor_buffer_offsets2(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        info: &vk::SetDescriptorBufferOffsetsInfoEXT<'_>,
+    ) {
+        unsafe {
+            (self.fp().cmd_set_descriptor_buffer_offsets2)(
+                command_buffer,
+                info,
+            )
+        }
+    }
+}
+
+impl AnyExtensionDevice for Device {
+
+    #[inline(always)]
+    fn boxed(&self) -> Box<dyn AnyExtensionDevice> {
+        Box::new(self.clone())
+    }
+}
+
+impl ExtensionDevice for Device {
+
+    const NAME: ConstName = ConstName::new(
+        "descriptor buffer device"
+    );
+
+    #[inline(always)]
+    fn precondition<'a, F>(f: F) -> bool
+        where F: Fn(&ConstName) -> Option<&'a DeviceAttribute>
+    {
+        f(&Attributes::IS_ENABLED)
+            .is_some_and(|value| value.bool().is_some_and(|value| value))
+    }
+
+    #[inline(always)]
+    fn new(device: &LogicalDevice) -> Box<Self>
+    {
+        let fp = DeviceFn::load(|name| unsafe {
+            ::core::mem::transmute(device.get_proc_addr(name))
+        });
+        Box::new(Self {
+            fp,
+            handle: device.handle(),
+        })
+    }
+}
@@ -0,0 +1,168 @@
+//! Provided by VK_EXT_descriptor_indexing or Vulkan 1.2.
+//!
+//! Enables the [`update after bind`][1], [`partially bound`][2] and
+//! [`variable descriptor count`][3] descriptor set layout flags, runtime descriptor arrays and
+//! non-uniform indexing of sampled image, storage image and storage buffer arrays in shaders.
+//!
+//! [1]: DescriptorSetLayoutFlags::UPDATE_AFTER_BIND
+//! [2]: DescriptorSetLayoutFlags::PARTIALLY_BOUND
+//! [3]: DescriptorSetLayoutFlags::VARIABLE_DESCRIPTOR_COUNT
+
+use {
+    nox_ash::{
+        vk,
+        ext,
+    },
+    super::*,
+};
+
+/// Attribute names.
+pub struct Attributes;
+
+impl Attributes {
+    /// Attribute type `bool`.
+    pub const IS_ENABLED: ConstName = ConstName::new("descriptor_indexing");
+    /// Attribute type `u32`.
+    pub const MAX_UPDATE_AFTER_BIND_DESCRIPTORS_IN_ALL_POOLS: ConstName
+        = ConstName::new("max_update_after_bind_descriptors_in_all_pools");
+    /// Attribute type `u32`.
+    pub const MAX_DESCRIPTOR_SET_UPDATE_AFTER_BIND_SAMPLERS: ConstName
+        = ConstName::new("max_descriptor_set_update_after_bind_samplers");
+    /// Attribute type `u32`.
+    pub const MAX_DESCRIPTOR_SET_UPDATE_AFTER_BIND_SAMPLED_IMAGES: ConstName
+        = ConstName::new("max_descriptor_set_update_after_bind_sampled_images");
+    /// Attribute type `u32`.
+    pub const MAX_DESCRIPTOR_SET_UPDATE_AFTER_BIND_STORAGE_IMAGES: ConstName
+        = ConstName::new("max_descriptor_set_update_after_bind_storage_images");
+    /// Attribute type `u32`.
+    pub const MAX_DESCRIPTOR_SET_UPDATE_AFTER_BIND_STORAGE_BUFFERS: ConstName
+        = ConstName::new("max_descriptor_set_update_after_bind_storage_buffers");
+}
+
+/// The extension type.
+#[derive(Clone, Copy)]
+pub struct Extension;
+
+fn missing_feature(
+    features: &vk::PhysicalDeviceDescriptorIndexingFeatures<'_>,
+) -> Option<MissingDeviceFeatureError> {
+    [
+        (features.runtime_descriptor_array, "runtime descriptor array"),
+        (features.descriptor_binding_partially_bound, "descriptor binding partially bound"),
+        (
+            features.descriptor_binding_variable_descriptor_count,
+            "descriptor binding variable descriptor count",
+        ),
+        (
+            features.descriptor_binding_update_unused_while_pending,
+            "descriptor binding update unused while pending",
+        ),
+        (
+            features.descriptor_binding_sampled_image_update_after_bind,
+            "descriptor binding sampled image update after bind",
+        ),
+        (
+            features.descriptor_binding_storage_image_update_after_bind,
+            "descriptor binding storage image update after bind",
+        ),
+        (
+            features.descriptor_binding_storage_buffer_update_after_bind,
+            "descriptor binding storage buffer update after bind",
+        ),
+        (
+            features.shader_sampled_image_array_non_uniform_indexing,
+            "shader sampled image array non-uniform indexing",
+        ),
+        (
+            features.shader_storage_image_array_non_uniform_indexing,
+            "shader storage image array non-uniform indexing",
+        ),
+        (
+            features.shader_storage_buffer_array_non_uniform_indexing,
+            "shader storage buffer array non-uniform indexing",
+        ),
+    ].into_iter()
+    .find_map(|(supported, name)| (supported == 0).then(||
+        MissingDeviceFeatureError::new(name)
+    ))
+}
+
+unsafe impl DeviceExtension for Extension {
+
+    fn get_info(&self, _attributes: &DeviceAttributes) -> Option<DeviceExtensionInfo> {
+        Some(DeviceExtensionInfo {
+            name: ext::descriptor_indexing::NAME,
+            deprecation_version: Version::VULKAN_API_VERSION_1_2,
+            precondition: Precondition::new(|ctx| {
+                let mut features = vk::PhysicalDeviceDescriptorIndexingFeatures::default();
+                ctx.get_features(&mut features);
+                missing_feature(&features)
+            }),
+        })
+    }
+
+    fn register(
+        &self,
+        ctx: &mut PhysicalDeviceContext<'_>,
+    ) -> Option<vk::ExtendsDeviceCreateInfoObj> {
+        let mut properties = vk::PhysicalDeviceDescriptorIndexingProperties::default();
+        ctx.get_properties(&mut properties);
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_UPDATE_AFTER_BIND_DESCRIPTORS_IN_ALL_POOLS,
+            properties.max_update_after_bind_descriptors_in_all_pools,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_DESCRIPTOR_SET_UPDATE_AFTER_BIND_SAMPLERS,
+            properties.max_descriptor_set_update_after_bind_samplers,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_DESCRIPTOR_SET_UPDATE_AFTER_BIND_SAMPLED_IMAGES,
+            properties.max_descriptor_set_update_after_bind_sampled_images,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_DESCRIPTOR_SET_UPDATE_AFTER_BIND_STORAGE_IMAGES,
+            properties.max_descriptor_set_update_after_bind_storage_images,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_DESCRIPTOR_SET_UPDATE_AFTER_BIND_STORAGE_BUFFERS,
+            properties.max_descriptor_set_update_after_bind_storage_buffers,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_bool(
+            Attributes::IS_ENABLED, true,
+        ));
+        if ctx.api_version() >= Version::VULKAN_API_VERSION_1_2 {
+            let features = ctx.vulkan_12_features();
+            features.descriptor_indexing = vk::TRUE;
+            features.runtime_descriptor_array = vk::TRUE;
+            features.descriptor_binding_partially_bound = vk::TRUE;
+            features.descriptor_binding_variable_descriptor_count = vk::TRUE;
+            features.descriptor_binding_update_unused_while_pending = vk::TRUE;
+            features.descriptor_binding_sampled_image_update_after_bind = vk::TRUE;
+            features.descriptor_binding_storage_image_update_after_bind = vk::TRUE;
+            features.descriptor_binding_storage_buffer_update_after_bind = vk::TRUE;
+            features.shader_sampled_image_array_non_uniform_indexing = vk::TRUE;
+            features.shader_storage_image_array_non_uniform_indexing = vk::TRUE;
+            features.shader_storage_buffer_array_non_uniform_indexing = vk::TRUE;
+            None
+        } else {
+            Some(create_extends_device_create_info_obj(
+                vk::PhysicalDeviceDescriptorIndexingFeatures
+                    ::default()
+                    .runtime_descriptor_array(true)
+                    .descriptor_binding_partially_bound(true)
+                    .descriptor_binding_variable_descriptor_count(true)
+                    .descriptor_binding_update_unused_while_pending(true)
+                    .descriptor_binding_sampled_image_update_after_bind(true)
+                    .descriptor_binding_storage_image_update_after_bind(true)
+                    .descriptor_binding_storage_buffer_update_after_bind(true)
+                    .shader_sampled_image_array_non_uniform_indexing(true)
+                    .shader_storage_image_array_non_uniform_indexing(true)
+                    .shader_storage_buffer_array_non_uniform_indexing(true)
+            ))
+        }
+    }
+
+    fn boxed(&self) -> Box<dyn DeviceExtension> {
+        Box::new(*self)
+    }
+}
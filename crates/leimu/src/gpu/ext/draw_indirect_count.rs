@@ -0,0 +1,263 @@
+//! Provided by VK_KHR_draw_indirect_count or Vulkan 1.2.
+
+use {
+    ::core::ffi::{
+        CStr,
+        c_void,
+    },
+    nox_ash::{
+        vk,
+        load_fn,
+        khr,
+    },
+    super::*,
+};
+
+
+pub struct Attributes;
+
+impl Attributes {
+    /// Attribute type `bool`.
+    pub const IS_ENABLED: ConstName = ConstName::new("draw_indirect_count");
+}
+
+/// The extension type.
+#[derive(Clone, Copy)]
+pub struct Extension;
+
+unsafe impl DeviceExtension for Extension {
+
+    fn get_info(&self, _: &DeviceAttributes) -> Option<DeviceExtensionInfo> {
+        Some(DeviceExtensionInfo {
+            name: khr::draw_indirect_count::NAME,
+            deprecation_version: Version::VULKAN_API_VERSION_1_2,
+            precondition: Precondition::new(|ctx| {
+                if ctx.api_version() >= Version::VULKAN_API_VERSION_1_2 {
+                    let mut features = vk::PhysicalDeviceVulkan12Features::default();
+                    ctx.get_features(&mut features);
+                    (features.draw_indirect_count == 0).then(|| MissingDeviceFeatureError::new(
+                        "draw indirect count"
+                    ))
+                } else {
+                    None
+                }
+            }),
+        })
+    }
+
+    fn register(
+        &self,
+        ctx: &mut PhysicalDeviceContext<'_>,
+    ) -> Option<vk::ExtendsDeviceCreateInfoObj> {
+        ctx.register_attribute(DeviceAttribute::new_bool(
+            Attributes::IS_ENABLED,
+            true,
+        ));
+        if ctx.api_version() >= Version::VULKAN_API_VERSION_1_2 {
+            ctx.vulkan_12_features().draw_indirect_count = vk::TRUE;
+        }
+        None
+    }
+
+    fn boxed(&self) -> Box<dyn DeviceExtension> {
+        Box::new(*self)
+    }
+}
+
+/// Raw [`draw_indirect_count`] device-level function pointers.
+#[derive(Clone)]
+pub struct DeviceFn {
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawIndirectCount.html>
+    pub cmd_draw_indirect_count: vk::PFN_vkCmdDrawIndirectCount,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawIndexedIndirectCount.html>
+    pub cmd_draw_indexed_indirect_count: vk::PFN_vkCmdDrawIndexedIndirectCount,
+}
+
+unsafe impl Send for DeviceFn {}
+unsafe impl Sync for DeviceFn {}
+
+impl DeviceFn {
+
+    pub fn load<F: FnMut(&CStr) -> *const c_void>(
+        api_version: Version,
+        mut f: F,
+    ) -> Self
+    {
+        if api_version >= Version::VULKAN_API_VERSION_1_2 {
+            unsafe { Self {
+                cmd_draw_indirect_count: load_fn!(
+                    fn cmd_draw_indirect_count(
+                        vk::CommandBuffer,
+                        vk::Buffer,
+                        vk::DeviceSize,
+                        vk::Buffer,
+                        vk::DeviceSize,
+                        u32,
+                        u32,
+                    ) -> (),
+                    f,
+                    c"vkCmdDrawIndirectCount",
+                    vk::PFN_vkCmdDrawIndirectCount,
+                ),
+                cmd_draw_indexed_indirect_count: load_fn!(
+                    fn cmd_draw_indexed_indirect_count(
+                        vk::CommandBuffer,
+                        vk::Buffer,
+                        vk::DeviceSize,
+                        vk::Buffer,
+                        vk::DeviceSize,
+                        u32,
+                        u32,
+                    ) -> (),
+                    f,
+                    c"vkCmdDrawIndexedIndirectCount",
+                    vk::PFN_vkCmdDrawIndexedIndirectCount,
+                ),
+            } }
+        } else {
+            unsafe { Self {
+                cmd_draw_indirect_count: load_fn!(
+                    fn cmd_draw_indirect_count(
+                        vk::CommandBuffer,
+                        vk::Buffer,
+                        vk::DeviceSize,
+                        vk::Buffer,
+                        vk::DeviceSize,
+                        u32,
+                        u32,
+                    ) -> (),
+                    f,
+                    c"vkCmdDrawIndirectCountKHR",
+                    vk::PFN_vkCmdDrawIndirectCount,
+                ),
+                cmd_draw_indexed_indirect_count: load_fn!(
+                    fn cmd_draw_indexed_indirect_count(
+                        vk::CommandBuffer,
+                        vk::Buffer,
+                        vk::DeviceSize,
+                        vk::Buffer,
+                        vk::DeviceSize,
+                        u32,
+                        u32,
+                    ) -> (),
+                    f,
+                    c"vkCmdDrawIndexedIndirectCountKHR",
+                    vk::PFN_vkCmdDrawIndexedIndirectCount,
+                ),
+            } }
+        }
+    }
+}
+
+/// [`draw_indirect_count`] device-level functions.
+#[derive(Clone)]
+pub struct Device {
+    fp: DeviceFn,
+    handle: vk::Device,
+}
+
+impl Device {
+
+    #[inline(always)]
+    pub fn fp(&self) -> &DeviceFn {
+        &self.fp
+    }
+
+    #[inline(always)]
+    pub fn device(&self) -> vk::Device {
+        self.handle
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawIndirectCount.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn cmd_draw_indirect_count(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        count_buffer: vk::Buffer,
+        count_buffer_offset: vk::DeviceSize,
+        max_draw_count: u32,
+        stride: u32,
+    ) {
+        unsafe {
+            (self.fp().cmd_draw_indirect_count)(
+                command_buffer,
+                buffer,
+                offset,
+                count_buffer,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            )
+        }
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawIndexedIndirectCount.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn cmd_draw_indexed_indirect_count(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        count_buffer: vk::Buffer,
+        count_buffer_offset: vk::DeviceSize,
+        max_draw_count: u32,
+        stride: u32,
+    ) {
+        unsafe {
+            (self.fp().cmd_draw_indexed_indirect_count)(
+                command_buffer,
+                buffer,
+                offset,
+                count_buffer,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            )
+        }
+    }
+}
+
+impl AnyExtensionDevice for Device {
+
+    #[inline(always)]
+    fn boxed(&self) -> Box<dyn AnyExtensionDevice> {
+        Box::new(self.clone())
+    }
+}
+
+impl ExtensionDevice for Device {
+
+    const NAME: ConstName = ConstName::new(
+        "draw indirect count device"
+    );
+
+    #[inline(always)]
+    fn precondition<'a, F>(f: F) -> bool
+        where F: Fn(&ConstName) -> Option<&'a DeviceAttribute>
+    {
+        f(&Attributes::IS_ENABLED)
+            .is_some_and(|value| value.bool().is_some_and(|value| value))
+    }
+
+    #[inline(always)]
+    fn new(device: &LogicalDevice) -> Box<Self>
+    {
+        let fp = DeviceFn::load(device.api_version(), |name| unsafe {
+            ::core::mem::transmute(device.get_proc_addr(name))
+        });
+        Box::new(Self {
+            fp,
+            handle: device.handle(),
+        })
+    }
+}
@@ -0,0 +1,52 @@
+//! Provided by [`VK_EXT_memory_budget`][1].
+//!
+//! When enabled, [`LogicalDevice::memory_heap_budgets`][2] reports heap usage and budgets from
+//! the driver instead of estimating them.
+//!
+//! [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_memory_budget.html
+//! [2]: crate::gpu::LogicalDevice::memory_heap_budgets
+
+use {
+    nox_ash::{
+        vk,
+        ext,
+    },
+    super::*,
+};
+
+/// Attribute names.
+pub struct Attributes;
+
+impl Attributes {
+    /// Attribute type `bool`.
+    pub const IS_ENABLED: ConstName = ConstName::new("memory_budget");
+}
+
+/// The extension type.
+#[derive(Clone, Copy)]
+pub struct Extension;
+
+unsafe impl DeviceExtension for Extension {
+
+    fn get_info(&self, _: &DeviceAttributes) -> Option<DeviceExtensionInfo> {
+        Some(DeviceExtensionInfo {
+            name: ext::memory_budget::NAME,
+            deprecation_version: Version::MAX,
+            precondition: None,
+        })
+    }
+
+    fn register(
+        &self,
+        ctx: &mut PhysicalDeviceContext<'_>,
+    ) -> Option<vk::ExtendsDeviceCreateInfoObj> {
+        ctx.register_attribute(DeviceAttribute::new_bool(
+            Attributes::IS_ENABLED, true,
+        ));
+        None
+    }
+
+    fn boxed(&self) -> Box<dyn DeviceExtension> {
+        Box::new(*self)
+    }
+}
@@ -0,0 +1,368 @@
+//! Provided by [`VK_EXT_mesh_shader`][1].
+//!
+//! Enables [`task`][2] and [`mesh`][3] shaders, which replace the vertex input stages of
+//! graphics pipelines, and drawing with [`DrawPipelineCommands::draw_mesh_tasks`][4].
+//!
+//! [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_mesh_shader.html
+//! [2]: crate::gpu::ShaderStage::Task
+//! [3]: crate::gpu::ShaderStage::Mesh
+//! [4]: crate::gpu::DrawPipelineCommands::draw_mesh_tasks
+
+use {
+    ::core::ffi::{
+        CStr,
+        c_void,
+    },
+    nox_ash::{
+        vk,
+        load_fn,
+        ext,
+    },
+    super::*,
+};
+
+/// Attribute names.
+pub struct Attributes;
+
+impl Attributes {
+    /// Attribute type `bool`.
+    pub const IS_ENABLED: ConstName = ConstName::new("mesh_shader");
+    /// Attribute type `bool`.
+    pub const IS_TASK_SHADER_ENABLED: ConstName = ConstName::new("mesh_shader task_shader");
+    /// Attribute type `bool`.
+    pub const IS_MULTIVIEW_MESH_SHADER_ENABLED: ConstName
+        = ConstName::new("mesh_shader multiview_mesh_shader");
+    /// Attribute type `u32`.
+    pub const MAX_TASK_WORK_GROUP_TOTAL_COUNT: ConstName
+        = ConstName::new("mesh_shader max_task_work_group_total_count");
+    /// Attribute type `u32`.
+    pub const MAX_MESH_WORK_GROUP_TOTAL_COUNT: ConstName
+        = ConstName::new("mesh_shader max_mesh_work_group_total_count");
+    /// Attribute type `u32`.
+    pub const MAX_TASK_WORK_GROUP_COUNT_X: ConstName
+        = ConstName::new("mesh_shader max_task_work_group_count[0]");
+    /// Attribute type `u32`.
+    pub const MAX_TASK_WORK_GROUP_COUNT_Y: ConstName
+        = ConstName::new("mesh_shader max_task_work_group_count[1]");
+    /// Attribute type `u32`.
+    pub const MAX_TASK_WORK_GROUP_COUNT_Z: ConstName
+        = ConstName::new("mesh_shader max_task_work_group_count[2]");
+    /// Attribute type `u32`.
+    pub const MAX_MESH_WORK_GROUP_COUNT_X: ConstName
+        = ConstName::new("mesh_shader max_mesh_work_group_count[0]");
+    /// Attribute type `u32`.
+    pub const MAX_MESH_WORK_GROUP_COUNT_Y: ConstName
+        = ConstName::new("mesh_shader max_mesh_work_group_count[1]");
+    /// Attribute type `u32`.
+    pub const MAX_MESH_WORK_GROUP_COUNT_Z: ConstName
+        = ConstName::new("mesh_shader max_mesh_work_group_count[2]");
+    /// Attribute type `u32`.
+    pub const MAX_TASK_PAYLOAD_SIZE: ConstName
+        = ConstName::new("mesh_shader max_task_payload_size");
+    /// Attribute type `u32`.
+    pub const MAX_MESH_OUTPUT_VERTICES: ConstName
+        = ConstName::new("mesh_shader max_mesh_output_vertices");
+    /// Attribute type `u32`.
+    pub const MAX_MESH_OUTPUT_PRIMITIVES: ConstName
+        = ConstName::new("mesh_shader max_mesh_output_primitives");
+}
+
+/// The extension type.
+///
+/// Mesh shaders are always enabled, task shaders and multiview mesh shaders are optional.
+#[derive(Clone, Copy)]
+pub struct Extension {
+    /// Specifies whether [`task shaders`][1] are required.
+    ///
+    /// [1]: crate::gpu::ShaderStage::Task
+    pub enable_task_shader: bool,
+    /// Specifies whether mesh shader pipelines with a non-zero view mask are required.
+    ///
+    /// Requires the [`multiview`][1] extension to be enabled.
+    ///
+    /// [1]: super::multiview
+    pub enable_multiview_mesh_shader: bool,
+}
+
+unsafe impl DeviceExtension for Extension {
+
+    fn get_info(&self, _: &DeviceAttributes) -> Option<DeviceExtensionInfo> {
+        let s = *self;
+        Some(DeviceExtensionInfo {
+            name: ext::mesh_shader::NAME,
+            deprecation_version: Version::MAX,
+            precondition: Precondition::new(move |ctx| {
+                let mut features = vk::PhysicalDeviceMeshShaderFeaturesEXT::default();
+                ctx.get_features(&mut features);
+                if features.mesh_shader == 0 {
+                    Some(MissingDeviceFeatureError::new("mesh shader"))
+                } else if s.enable_task_shader && features.task_shader == 0 {
+                    Some(MissingDeviceFeatureError::new("task shader"))
+                } else if s.enable_multiview_mesh_shader && features.multiview_mesh_shader == 0 {
+                    Some(MissingDeviceFeatureError::new("multiview mesh shader"))
+                } else {
+                    None
+                }
+            }),
+        })
+    }
+
+    fn register(
+        &self,
+        ctx: &mut PhysicalDeviceContext<'_>,
+    ) -> Option<vk::ExtendsDeviceCreateInfoObj> {
+        let mut properties = vk::PhysicalDeviceMeshShaderPropertiesEXT::default();
+        ctx.get_properties(&mut properties);
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_TASK_WORK_GROUP_TOTAL_COUNT,
+            properties.max_task_work_group_total_count,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_MESH_WORK_GROUP_TOTAL_COUNT,
+            properties.max_mesh_work_group_total_count,
+        ));
+        for (names, counts) in [
+            ([
+                Attributes::MAX_TASK_WORK_GROUP_COUNT_X,
+                Attributes::MAX_TASK_WORK_GROUP_COUNT_Y,
+                Attributes::MAX_TASK_WORK_GROUP_COUNT_Z,
+            ], properties.max_task_work_group_count),
+            ([
+                Attributes::MAX_MESH_WORK_GROUP_COUNT_X,
+                Attributes::MAX_MESH_WORK_GROUP_COUNT_Y,
+                Attributes::MAX_MESH_WORK_GROUP_COUNT_Z,
+            ], properties.max_mesh_work_group_count),
+        ] {
+            for (name, count) in names.into_iter().zip(counts) {
+                ctx.register_attribute(DeviceAttribute::new_u32(name, count));
+            }
+        }
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_TASK_PAYLOAD_SIZE,
+            properties.max_task_payload_size,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_MESH_OUTPUT_VERTICES,
+            properties.max_mesh_output_vertices,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_MESH_OUTPUT_PRIMITIVES,
+            properties.max_mesh_output_primitives,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_bool(
+            Attributes::IS_ENABLED, true,
+        ));
+        let mut features = vk::PhysicalDeviceMeshShaderFeaturesEXT
+            ::default()
+            .mesh_shader(true);
+        if self.enable_task_shader {
+            ctx.register_attribute(DeviceAttribute::new_bool(
+                Attributes::IS_TASK_SHADER_ENABLED, true,
+            ));
+            features.task_shader = vk::TRUE;
+        }
+        if self.enable_multiview_mesh_shader {
+            ctx.register_attribute(DeviceAttribute::new_bool(
+                Attributes::IS_MULTIVIEW_MESH_SHADER_ENABLED, true,
+            ));
+            features.multiview_mesh_shader = vk::TRUE;
+        }
+        Some(create_extends_device_create_info_obj(features))
+    }
+
+    fn boxed(&self) -> Box<dyn DeviceExtension> {
+        Box::new(*self)
+    }
+}
+
+/// Raw [`mesh_shader`] device-level function pointers.
+#[derive(Clone)]
+pub struct DeviceFn {
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawMeshTasksEXT.html>
+    pub cmd_draw_mesh_tasks: vk::PFN_vkCmdDrawMeshTasksEXT,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawMeshTasksIndirectEXT.html>
+    pub cmd_draw_mesh_tasks_indirect: vk::PFN_vkCmdDrawMeshTasksIndirectEXT,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawMeshTasksIndirectCountEXT.html>
+    pub cmd_draw_mesh_tasks_indirect_count: vk::PFN_vkCmdDrawMeshTasksIndirectCountEXT,
+}
+
+unsafe impl Send for DeviceFn {}
+unsafe impl Sync for DeviceFn {}
+
+impl DeviceFn {
+
+    pub fn load<F: FnMut(&CStr) -> *const c_void>(
+        mut f: F,
+    ) -> Self
+    {
+        unsafe { Self {
+            cmd_draw_mesh_tasks: load_fn!(
+                fn cmd_draw_mesh_tasks(
+                    vk::CommandBuffer,
+                    u32,
+                    u32,
+                    u32,
+                ) -> (),
+                f,
+                c"vkCmdDrawMeshTasksEXT",
+                vk::PFN_vkCmdDrawMeshTasksEXT,
+            ),
+            cmd_draw_mesh_tasks_indirect: load_fn!(
+                fn cmd_draw_mesh_tasks_indirect(
+                    vk::CommandBuffer,
+                    vk::Buffer,
+                    vk::DeviceSize,
+                    u32,
+                    u32,
+                ) -> (),
+                f,
+                c"vkCmdDrawMeshTasksIndirectEXT",
+                vk::PFN_vkCmdDrawMeshTasksIndirectEXT,
+            ),
+            cmd_draw_mesh_tasks_indirect_count: load_fn!(
+                fn cmd_draw_mesh_tasks_indirect_count(
+                    vk::CommandBuffer,
+                    vk::Buffer,
+                    vk::DeviceSize,
+                    vk::Buffer,
+                    vk::DeviceSize,
+                    u32,
+                    u32,
+                ) -> (),
+                f,
+                c"vkCmdDrawMeshTasksIndirectCountEXT",
+                vk::PFN_vkCmdDrawMeshTasksIndirectCountEXT,
+            ),
+        } }
+    }
+}
+
+/// [`mesh_shader`] device-level functions.
+#[derive(Clone)]
+pub struct Device {
+    fp: DeviceFn,
+    handle: vk::Device,
+}
+
+impl Device {
+
+    #[inline(always)]
+    pub fn fp(&self) -> &DeviceFn {
+        &self.fp
+    }
+
+    #[inline(always)]
+    pub fn device(&self) -> vk::Device {
+        self.handle
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawMeshTasksEXT.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn cmd_draw_mesh_tasks(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        group_count_x: u32,
+        group_count_y: u32,
+        group_count_z: u32,
+    ) {
+        unsafe {
+            (self.fp().cmd_draw_mesh_tasks)(
+                command_buffer,
+                group_count_x,
+                group_count_y,
+                group_count_z,
+            )
+        }
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawMeshTasksIndirectEXT.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn cmd_draw_mesh_tasks_indirect(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        unsafe {
+            (self.fp().cmd_draw_mesh_tasks_indirect)(
+                command_buffer,
+                buffer,
+                offset,
+                draw_count,
+                stride,
+            )
+        }
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdDrawMeshTasksIndirectCountEXT.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn cmd_draw_mesh_tasks_indirect_count(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        count_buffer: vk::Buffer,
+        count_buffer_offset: vk::DeviceSize,
+        max_draw_count: u32,
+        stride: u32,
+    ) {
+        unsafe {
+            (self.fp().cmd_draw_mesh_tasks_indirect_count)(
+                command_buffer,
+                buffer,
+                offset,
+                count_buffer,
+                count_buffer_offset,
+                max_draw_count,
+                stride,
+            )
+        }
+    }
+}
+
+impl AnyExtensionDevice for Device {
+
+    #[inline(always)]
+    fn boxed(&self) -> Box<dyn AnyExtensionDevice> {
+        Box::new(self.clone())
+    }
+}
+
+impl ExtensionDevice for Device {
+
+    const NAME: ConstName = ConstName::new(
+        "mesh shader device"
+    );
+
+    #[inline(always)]
+    fn precondition<'a, F>(f: F) -> bool
+        where F: Fn(&ConstName) -> Option<&'a DeviceAttribute>
+    {
+        f(&Attributes::IS_ENABLED)
+            .is_some_and(|value| value.bool().is_some_and(|value| value))
+    }
+
+    #[inline(always)]
+    fn new(device: &LogicalDevice) -> Box<Self>
+    {
+        let fp = DeviceFn::load(|name| unsafe {
+            ::core::mem::transmute(device.get_proc_addr(name))
+        });
+        Box::new(Self {
+            fp,
+            handle: device.handle(),
+        })
+    }
+}
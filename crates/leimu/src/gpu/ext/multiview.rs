@@ -0,0 +1,107 @@
+//! Provided by [`VK_KHR_multiview`][1] or Vulkan 1.1.
+//!
+//! Enables rendering to multiple array layers of the attachments with a single draw by using
+//! [`RenderingInfo::view_mask`][2] and [`GraphicsPipelineCreateInfo::with_view_mask`][3].
+//!
+//! [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_KHR_multiview.html
+//! [2]: crate::gpu::RenderingInfo::view_mask
+//! [3]: crate::gpu::GraphicsPipelineCreateInfo::with_view_mask
+
+use {
+    nox_ash::{
+        vk,
+        khr,
+    },
+    crate::error::*,
+    super::*,
+};
+
+/// Attribute names.
+pub struct Attributes;
+
+impl Attributes {
+    /// Attribute type `bool`.
+    pub const IS_ENABLED: ConstName = ConstName::new("multiview");
+    /// Attribute type `u32`.
+    pub const MAX_MULTIVIEW_VIEW_COUNT: ConstName = ConstName::new("max_multiview_view_count");
+    /// Attribute type `u32`.
+    pub const MAX_MULTIVIEW_INSTANCE_INDEX: ConstName
+        = ConstName::new("max_multiview_instance_index");
+}
+
+/// The extension type.
+#[derive(Clone, Copy)]
+pub struct Extension;
+
+unsafe impl DeviceExtension for Extension {
+
+    fn get_info(&self, _: &DeviceAttributes) -> Option<DeviceExtensionInfo> {
+        Some(DeviceExtensionInfo {
+            name: khr::multiview::NAME,
+            deprecation_version: Version::VULKAN_API_VERSION_1_1,
+            precondition: Precondition::new(|ctx| {
+                let mut features = vk::PhysicalDeviceMultiviewFeatures::default();
+                ctx.get_features(&mut features);
+                (features.multiview == 0).then(||
+                    MissingDeviceFeatureError::new("multiview")
+                )
+            }),
+        })
+    }
+
+    fn register(
+        &self,
+        ctx: &mut PhysicalDeviceContext<'_>,
+    ) -> Option<vk::ExtendsDeviceCreateInfoObj> {
+        let mut properties = vk::PhysicalDeviceMultiviewProperties::default();
+        ctx.get_properties(&mut properties);
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_MULTIVIEW_VIEW_COUNT,
+            properties.max_multiview_view_count,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_MULTIVIEW_INSTANCE_INDEX,
+            properties.max_multiview_instance_index,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_bool(
+            Attributes::IS_ENABLED, true,
+        ));
+        Some(create_extends_device_create_info_obj(
+            vk::PhysicalDeviceMultiviewFeatures
+                ::default()
+                .multiview(true)
+        ))
+    }
+
+    fn boxed(&self) -> Box<dyn DeviceExtension> {
+        Box::new(*self)
+    }
+}
+
+/// Validates `view_mask` against the enabled multiview attributes of `device`.
+///
+/// A `view_mask` of zero disables multiview and is always valid.
+pub(crate) fn validate_view_mask(device: &LogicalDevice, view_mask: u32) -> Result<()> {
+    if view_mask == 0 {
+        return Ok(())
+    }
+    if !device.get_device_attribute(Attributes::IS_ENABLED)
+        .bool().unwrap_or(false)
+    {
+        return Err(Error::just_context(
+            "attempting to use a non-zero view mask without enabling the multiview extension"
+        ))
+    }
+    let max_view_count = device
+        .get_device_attribute(Attributes::MAX_MULTIVIEW_VIEW_COUNT)
+        .u32().unwrap_or(0);
+    let view_count = u32::BITS - view_mask.leading_zeros();
+    if view_count > max_view_count {
+        return Err(Error::just_context(format!(
+            "{}{}",
+            format_args!("view mask {view_mask:#b} uses view index {}, ", view_count - 1),
+            format_args!("but the max multiview view count is {max_view_count}"),
+        )))
+    }
+    Ok(())
+}
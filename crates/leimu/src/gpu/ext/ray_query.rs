@@ -0,0 +1,67 @@
+//! Provided by [`VK_KHR_ray_query`][1].
+//!
+//! Enables inline ray queries (`rayQueryEXT` in Glsl) against
+//! [`acceleration structure descriptors`][2] from any shader stage, including ordinary compute
+//! and fragment shaders, without a [`ray tracing pipeline`][3].
+//!
+//! Requires the [`acceleration_structure`][4] extension to be enabled.
+//!
+//! [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_KHR_ray_query.html
+//! [2]: crate::gpu::DescriptorInfos::acceleration_structures
+//! [3]: super::ray_tracing_pipeline
+//! [4]: super::acceleration_structure
+
+use {
+    nox_ash::{
+        vk,
+        khr,
+    },
+    super::*,
+};
+
+/// Attribute names.
+pub struct Attributes;
+
+impl Attributes {
+    /// Attribute type `bool`.
+    pub const IS_ENABLED: ConstName = ConstName::new("ray_query");
+}
+
+/// The extension type.
+#[derive(Clone, Copy)]
+pub struct Extension;
+
+unsafe impl DeviceExtension for Extension {
+
+    fn get_info(&self, _: &DeviceAttributes) -> Option<DeviceExtensionInfo> {
+        Some(DeviceExtensionInfo {
+            name: khr::ray_query::NAME,
+            deprecation_version: Version::MAX,
+            precondition: Precondition::new(|ctx| {
+                let mut features = vk::PhysicalDeviceRayQueryFeaturesKHR::default();
+                ctx.get_features(&mut features);
+                (features.ray_query == 0).then(||
+                    MissingDeviceFeatureError::new("ray query")
+                )
+            }),
+        })
+    }
+
+    fn register(
+        &self,
+        ctx: &mut PhysicalDeviceContext<'_>,
+    ) -> Option<vk::ExtendsDeviceCreateInfoObj> {
+        ctx.register_attribute(DeviceAttribute::new_bool(
+            Attributes::IS_ENABLED, true,
+        ));
+        Some(create_extends_device_create_info_obj(
+            vk::PhysicalDeviceRayQueryFeaturesKHR
+                ::default()
+                .ray_query(true)
+        ))
+    }
+
+    fn boxed(&self) -> Box<dyn DeviceExtension> {
+        Box::new(*self)
+    }
+}
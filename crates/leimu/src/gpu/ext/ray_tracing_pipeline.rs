@@ -0,0 +1,326 @@
+//! Provided by [`VK_KHR_ray_tracing_pipeline`][1].
+//!
+//! Enables [`ray tracing pipelines`][2], which can be bound with
+//! [`ComputeCommands::bind_ray_tracing_pipeline`][3].
+//!
+//! Requires the [`acceleration_structure`][4] extension to be enabled.
+//!
+//! [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_KHR_ray_tracing_pipeline.html
+//! [2]: crate::gpu::RayTracingPipeline
+//! [3]: crate::gpu::ComputeCommands::bind_ray_tracing_pipeline
+//! [4]: super::acceleration_structure
+
+use {
+    ::core::ffi::{
+        CStr,
+        c_void,
+    },
+    nox_ash::{
+        vk,
+        khr,
+        load_fn,
+        prelude::VkResult,
+    },
+    nox_mem::option::OptionExt,
+    super::*,
+};
+
+/// Attribute names.
+pub struct Attributes;
+
+impl Attributes {
+    /// Attribute type `bool`.
+    pub const IS_ENABLED: ConstName = ConstName::new("ray_tracing_pipeline");
+    /// Attribute type `u32`.
+    pub const SHADER_GROUP_HANDLE_SIZE: ConstName
+        = ConstName::new("ray_tracing_pipeline shader_group_handle_size");
+    /// Attribute type `u32`.
+    pub const SHADER_GROUP_HANDLE_ALIGNMENT: ConstName
+        = ConstName::new("ray_tracing_pipeline shader_group_handle_alignment");
+    /// Attribute type `u32`.
+    pub const SHADER_GROUP_BASE_ALIGNMENT: ConstName
+        = ConstName::new("ray_tracing_pipeline shader_group_base_alignment");
+    /// Attribute type `u32`.
+    pub const MAX_SHADER_GROUP_STRIDE: ConstName
+        = ConstName::new("ray_tracing_pipeline max_shader_group_stride");
+    /// Attribute type `u32`.
+    pub const MAX_RAY_RECURSION_DEPTH: ConstName
+        = ConstName::new("ray_tracing_pipeline max_ray_recursion_depth");
+    /// Attribute type `u32`.
+    pub const MAX_RAY_DISPATCH_INVOCATION_COUNT: ConstName
+        = ConstName::new("ray_tracing_pipeline max_ray_dispatch_invocation_count");
+}
+
+/// The extension type.
+#[derive(Clone, Copy)]
+pub struct Extension;
+
+unsafe impl DeviceExtension for Extension {
+
+    fn get_info(&self, _: &DeviceAttributes) -> Option<DeviceExtensionInfo> {
+        Some(DeviceExtensionInfo {
+            name: khr::ray_tracing_pipeline::NAME,
+            deprecation_version: Version::MAX,
+            precondition: Precondition::new(|ctx| {
+                let mut features = vk::PhysicalDeviceRayTracingPipelineFeaturesKHR::default();
+                ctx.get_features(&mut features);
+                (features.ray_tracing_pipeline == 0).then(||
+                    MissingDeviceFeatureError::new("ray tracing pipeline")
+                )
+            }),
+        })
+    }
+
+    fn register(
+        &self,
+        ctx: &mut PhysicalDeviceContext<'_>,
+    ) -> Option<vk::ExtendsDeviceCreateInfoObj> {
+        let mut properties = vk::PhysicalDeviceRayTracingPipelinePropertiesKHR::default();
+        ctx.get_properties(&mut properties);
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::SHADER_GROUP_HANDLE_SIZE,
+            properties.shader_group_handle_size,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::SHADER_GROUP_HANDLE_ALIGNMENT,
+            properties.shader_group_handle_alignment,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::SHADER_GROUP_BASE_ALIGNMENT,
+            properties.shader_group_base_alignment,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_SHADER_GROUP_STRIDE,
+            properties.max_shader_group_stride,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_RAY_RECURSION_DEPTH,
+            properties.max_ray_recursion_depth,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_u32(
+            Attributes::MAX_RAY_DISPATCH_INVOCATION_COUNT,
+            properties.max_ray_dispatch_invocation_count,
+        ));
+        ctx.register_attribute(DeviceAttribute::new_bool(
+            Attributes::IS_ENABLED, true,
+        ));
+        Some(create_extends_device_create_info_obj(
+            vk::PhysicalDeviceRayTracingPipelineFeaturesKHR
+                ::default()
+                .ray_tracing_pipeline(true)
+        ))
+    }
+
+    fn boxed(&self) -> Box<dyn DeviceExtension> {
+        Box::new(*self)
+    }
+}
+
+/// Raw [`ray_tracing_pipeline`] device-level function pointers.
+#[derive(Clone)]
+pub struct DeviceFn {
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCreateRayTracingPipelinesKHR.html>
+    pub create_ray_tracing_pipelines: vk::PFN_vkCreateRayTracingPipelinesKHR,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetRayTracingShaderGroupHandlesKHR.html>
+    pub get_ray_tracing_shader_group_handles: vk::PFN_vkGetRayTracingShaderGroupHandlesKHR,
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdTraceRaysKHR.html>
+    pub cmd_trace_rays: vk::PFN_vkCmdTraceRaysKHR,
+}
+
+unsafe impl Send for DeviceFn {}
+unsafe impl Sync for DeviceFn {}
+
+impl DeviceFn {
+
+    pub fn load<F: FnMut(&CStr) -> *const c_void>(
+        mut f: F,
+    ) -> Self
+    {
+        unsafe { Self {
+            create_ray_tracing_pipelines: load_fn!(
+                fn create_ray_tracing_pipelines(
+                    vk::Device,
+                    vk::DeferredOperationKHR,
+                    vk::PipelineCache,
+                    u32,
+                    *const vk::RayTracingPipelineCreateInfoKHR<'_>,
+                    *const vk::AllocationCallbacks<'_>,
+                    *mut vk::Pipeline,
+                ) -> vk::Result,
+                f,
+                c"vkCreateRayTracingPipelinesKHR",
+                vk::PFN_vkCreateRayTracingPipelinesKHR,
+            ),
+            get_ray_tracing_shader_group_handles: load_fn!(
+                fn get_ray_tracing_shader_group_handles(
+                    vk::Device,
+                    vk::Pipeline,
+                    u32,
+                    u32,
+                    usize,
+                    *mut c_void,
+                ) -> vk::Result,
+                f,
+                c"vkGetRayTracingShaderGroupHandlesKHR",
+                vk::PFN_vkGetRayTracingShaderGroupHandlesKHR,
+            ),
+            cmd_trace_rays: load_fn!(
+                fn cmd_trace_rays(
+                    vk::CommandBuffer,
+                    *const vk::StridedDeviceAddressRegionKHR,
+                    *const vk::StridedDeviceAddressRegionKHR,
+                    *const vk::StridedDeviceAddressRegionKHR,
+                    *const vk::StridedDeviceAddressRegionKHR,
+                    u32,
+                    u32,
+                    u32,
+                ) -> (),
+                f,
+                c"vkCmdTraceRaysKHR",
+                vk::PFN_vkCmdTraceRaysKHR,
+            ),
+        } }
+    }
+}
+
+/// [`ray_tracing_pipeline`] device-level functions.
+#[derive(Clone)]
+pub struct Device {
+    fp: DeviceFn,
+    handle: vk::Device,
+}
+
+impl Device {
+
+    #[inline(always)]
+    pub fn fp(&self) -> &DeviceFn {
+        &self.fp
+    }
+
+    #[inline(always)]
+    pub fn device(&self) -> vk::Device {
+        self.handle
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCreateRayTracingPipelinesKHR.html>
+    ///
+    /// # Safety
+    /// The lengths of the `create_infos` and `pipelines` *must* match.
+    ///
+    /// This is *not* checked at runtime.
+    ///
+    /// *General safety:*
+    ///
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn create_ray_tracing_pipelines(
+        &self,
+        deferred_operation: vk::DeferredOperationKHR,
+        pipeline_cache: vk::PipelineCache,
+        create_infos: &[vk::RayTracingPipelineCreateInfoKHR<'_>],
+        allocator: Option<&vk::AllocationCallbacks<'_>>,
+        pipelines: &mut [vk::Pipeline],
+    ) -> VkResult<()> {
+        unsafe {
+            (self.fp().create_ray_tracing_pipelines)(
+                self.handle,
+                deferred_operation,
+                pipeline_cache,
+                create_infos.len() as u32,
+                create_infos.as_ptr(),
+                allocator.as_ptr(),
+                pipelines.as_mut_ptr(),
+            )
+        }.result()
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkGetRayTracingShaderGroupHandlesKHR.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    pub unsafe fn get_ray_tracing_shader_group_handles(
+        &self,
+        pipeline: vk::Pipeline,
+        first_group: u32,
+        group_count: u32,
+        data: &mut [u8],
+    ) -> VkResult<()> {
+        unsafe {
+            (self.fp().get_ray_tracing_shader_group_handles)(
+                self.handle,
+                pipeline,
+                first_group,
+                group_count,
+                data.len(),
+                data.as_mut_ptr().cast(),
+            )
+        }.result()
+    }
+
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdTraceRaysKHR.html>
+    ///
+    /// # Safety
+    /// All raw Vulkan calls are inherently unsafe, because no validation of input or usage is applied.
+    #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn cmd_trace_rays(
+        &self,
+        command_buffer: vk::CommandBuffer,
+        raygen_shader_binding_table: &vk::StridedDeviceAddressRegionKHR,
+        miss_shader_binding_table: &vk::StridedDeviceAddressRegionKHR,
+        hit_shader_binding_table: &vk::StridedDeviceAddressRegionKHR,
+        callable_shader_binding_table: &vk::StridedDeviceAddressRegionKHR,
+        width: u32,
+        height: u32,
+        depth: u32,
+    ) {
+        unsafe {
+            (self.fp().cmd_trace_rays)(
+                command_buffer,
+                raygen_shader_binding_table,
+                miss_shader_binding_table,
+                hit_shader_binding_table,
+                callable_shader_binding_table,
+                width,
+                height,
+                depth,
+            )
+        }
+    }
+}
+
+impl AnyExtensionDevice for Device {
+
+    #[inline(always)]
+    fn boxed(&self) -> Box<dyn AnyExtensionDevice> {
+        Box::new(self.clone())
+    }
+}
+
+impl ExtensionDevice for Device {
+
+    const NAME: ConstName = ConstName::new(
+        "ray tracing pipeline device"
+    );
+
+    #[inline(always)]
+    fn precondition<'a, F>(f: F) -> bool
+        where F: Fn(&ConstName) -> Option<&'a DeviceAttribute>
+    {
+        f(&Attributes::IS_ENABLED)
+            .is_some_and(|value| value.bool().is_some_and(|value| value))
+    }
+
+    #[inline(always)]
+    fn new(device: &LogicalDevice) -> Box<Self>
+    {
+        let fp = DeviceFn::load(|name| unsafe {
+            ::core::mem::transmute(device.get_proc_addr(name))
+        });
+        Box::new(Self {
+            fp,
+            handle: device.handle(),
+        })
+    }
+}
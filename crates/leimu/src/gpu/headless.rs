@@ -0,0 +1,347 @@
+use core::{
+    num::NonZeroU32,
+    time::Duration,
+};
+
+use nox_mem::vec::Vec32;
+use nox_proc::BuildStructure;
+use nox_threads::executor::SpawnExt;
+
+use crate::{
+    error::*,
+    gpu::prelude::*,
+};
+
+/// Specifies the images of a [`HeadlessTarget`].
+#[derive(Clone, Copy, BuildStructure)]
+pub struct HeadlessTargetInfo {
+    /// Specifies the dimensions of the images.
+    ///
+    /// The default is `(1, 1)`.
+    #[skip]
+    #[default(Dimensions::new(1, 1, 1))]
+    pub dimensions: Dimensions,
+    /// Specifies the format of the color images.
+    ///
+    /// The default is [`Format::R8g8b8a8Srgb`].
+    #[default(Format::R8g8b8a8Srgb)]
+    pub format: Format,
+    /// Specifies the format of the depth images.
+    ///
+    /// If this is [`None`], no depth images are created.
+    #[skip]
+    #[default(None)]
+    pub depth_format: Option<Format>,
+    /// Specifies the number of images in the ring.
+    ///
+    /// If this is [`None`], the number of buffered frames of the [`Gpu`] is used.
+    #[skip]
+    #[default(None)]
+    pub image_count: Option<NonZeroU32>,
+    /// Specifies additional usages of the color images.
+    ///
+    /// Color images are always created with the [`ImageUsages::COLOR_ATTACHMENT`] and
+    /// [`ImageUsages::TRANSFER_SRC`] usages.
+    #[default(ImageUsages::empty())]
+    pub usage: ImageUsages,
+}
+
+impl HeadlessTargetInfo {
+
+    /// Specifies the dimensions of the images.
+    #[must_use]
+    #[inline]
+    pub fn dimensions(mut self, dimensions: impl Into<Dimensions>) -> Self {
+        self.dimensions = dimensions.into();
+        self
+    }
+
+    /// Specifies the format of the depth images.
+    #[must_use]
+    #[inline]
+    pub fn depth_format(mut self, format: impl Into<Option<Format>>) -> Self {
+        self.depth_format = format.into();
+        self
+    }
+
+    /// Specifies the number of images in the ring.
+    ///
+    /// Passing zero restores the default behavior described in [`image_count`][1].
+    ///
+    /// [1]: Self::image_count
+    #[must_use]
+    #[inline]
+    pub fn image_count(mut self, count: u32) -> Self {
+        self.image_count = NonZeroU32::new(count);
+        self
+    }
+}
+
+/// An image of a [`HeadlessTarget`] returned by [`HeadlessTarget::acquire`].
+#[derive(Clone, Copy, Debug)]
+pub struct HeadlessImage {
+    /// The index of the image in the ring.
+    pub index: u32,
+    /// The view of the whole color image.
+    pub color_view: ImageViewId,
+    /// The view of the whole depth image, if the target has one.
+    pub depth_view: Option<ImageViewId>,
+    /// The format of the color image.
+    pub format: Format,
+    /// The dimensions of the images.
+    pub dimensions: Dimensions,
+}
+
+struct Frame {
+    color_view: ImageViewId,
+    depth_view: Option<ImageViewId>,
+    last_frame: u64,
+}
+
+/// An offscreen render target, which *can* be used in place of a swapchain.
+///
+/// The target owns a ring of color and optionally depth images and follows the same frame pacing
+/// as a swapchain:
+/// 1. [`acquire`][1] the next image, which waits until the frame that last rendered to it has
+///    finished.
+/// 2. Render to the image with commands scheduled on the same tick.
+/// 3. [`present`][2] the image, which reads it back once the commands have finished executing.
+///
+/// Together with [`Instance::headless`] and [`Gpu::standalone`], this allows driving frames with
+/// [`Gpu::tick`] without a display.
+///
+/// [1]: Self::acquire
+/// [2]: Self::present
+pub struct HeadlessTarget {
+    gpu: Gpu,
+    info: HeadlessTargetInfo,
+    frames: Vec32<Frame>,
+    next_index: u32,
+    acquired: Option<u32>,
+}
+
+impl HeadlessTarget {
+
+    /// Creates a new headless target.
+    pub fn new(gpu: Gpu, info: HeadlessTargetInfo) -> Result<Self> {
+        let mut s = Self {
+            gpu,
+            info,
+            frames: Vec32::new(),
+            next_index: 0,
+            acquired: None,
+        };
+        s.create_frames()?;
+        Ok(s)
+    }
+
+    fn create_frames(&mut self) -> Result<()> {
+        let image_count = self.info.image_count
+            .map(|count| count.get())
+            .unwrap_or(self.gpu.desired_buffered_frames());
+        let binder = GlobalBinder::new(
+            self.gpu.device().clone(),
+            MemoryProperties::DEVICE_LOCAL,
+            MemoryProperties::DEVICE_LOCAL,
+        );
+        self.frames.reserve(image_count);
+        for _ in 0..image_count {
+            let (mut color_id, mut depth_id) = (ImageId::default(), ImageId::default());
+            let color_info = ImageCreateInfo
+                ::new(&mut color_id, &binder)
+                .with_dimensions(self.info.dimensions)
+                .with_format(self.info.format, false)
+                .with_usage(
+                    ImageUsages::COLOR_ATTACHMENT |
+                    ImageUsages::TRANSFER_SRC |
+                    self.info.usage
+                );
+            if let Some(depth_format) = self.info.depth_format {
+                self.gpu.create_resources([], [
+                    color_info,
+                    ImageCreateInfo
+                        ::new(&mut depth_id, &binder)
+                        .with_dimensions(self.info.dimensions)
+                        .with_format(depth_format, false)
+                        .with_usage(ImageUsages::DEPTH_STENCIL_ATTACHMENT),
+                ])?;
+            } else {
+                self.gpu.create_resources([], [color_info])?;
+            }
+            let views: Result<_> = (|| {
+                let color_view = self.gpu.create_image_view(
+                    color_id,
+                    ImageRange::whole_range(ImageAspects::COLOR),
+                )?;
+                let depth_view = self.info.depth_format
+                    .map(|format| self.gpu.create_image_view(
+                        depth_id,
+                        ImageRange::whole_range(format.aspects()),
+                    )).transpose()?;
+                Ok((color_view, depth_view))
+            })();
+            let (color_view, depth_view) = match views {
+                Ok(views) => views,
+                Err(err) => {
+                    let images = [Some(color_id), self.info.depth_format.map(|_| depth_id)];
+                    self.gpu.destroy_resources([], images.into_iter().flatten()).ok();
+                    return Err(err)
+                },
+            };
+            self.frames.push(Frame {
+                color_view,
+                depth_view,
+                last_frame: 0,
+            });
+        }
+        self.next_index = 0;
+        self.acquired = None;
+        Ok(())
+    }
+
+    fn wait_for_frame(&self, frame: u64) -> Result<()> {
+        let semaphore = self.gpu.queue_scheduler().frame_semaphore_id();
+        let timeout = self.gpu.device().frame_timeout();
+        if !self.gpu.wait_for_semaphores(&[(semaphore, frame)], Duration::from_nanos(timeout))? {
+            return Err(Error::just_context(format!(
+                "frame timeout {timeout} nanoseconds"
+            )))
+        }
+        Ok(())
+    }
+
+    fn destroy_frames(&mut self) -> Result<()> {
+        let last_frame = self.frames
+            .iter()
+            .map(|frame| frame.last_frame)
+            .max().unwrap_or(0);
+        self.wait_for_frame(last_frame)?;
+        let images: Vec32<_> = self.frames
+            .iter()
+            .flat_map(|frame| [Some(frame.color_view), frame.depth_view])
+            .flatten()
+            .map(|view| view.image_id())
+            .collect();
+        self.frames.clear();
+        self.next_index = 0;
+        self.acquired = None;
+        self.gpu.destroy_resources([], images.iter().copied())
+    }
+
+    /// Returns the info the target was created with.
+    #[inline]
+    pub fn info(&self) -> &HeadlessTargetInfo {
+        &self.info
+    }
+
+    /// Returns the number of images in the ring.
+    #[inline]
+    pub fn image_count(&self) -> u32 {
+        self.frames.len()
+    }
+
+    /// Acquires the next image of the ring.
+    ///
+    /// This waits until the frame, which last rendered to the image, has finished executing and
+    /// fails if that takes longer than the frame timeout of the device.
+    ///
+    /// Fails if the ring has no images, which happens when [`resize`][3] failed to recreate them.
+    ///
+    /// The image is meant to be rendered to by commands scheduled with `scheduler` before the
+    /// next [`tick`][1]. Acquiring again without [`presenting`][2] drops the current image.
+    ///
+    /// [1]: Gpu::tick
+    /// [2]: Self::present
+    /// [3]: Self::resize
+    pub fn acquire(&mut self, scheduler: &CommandScheduler<'_>) -> Result<HeadlessImage> {
+        if self.frames.is_empty() {
+            return Err(Error::just_context(
+                "headless target has no images, which happens when resizing failed"
+            ))
+        }
+        let index = self.next_index;
+        let scheduled_frame = scheduler.scheduled_frame();
+        let frame = &self.frames[index as usize];
+        self.wait_for_frame(frame.last_frame)
+            .context_with(|| format!("failed to acquire headless image {index}"))?;
+        let frame = &mut self.frames[index as usize];
+        frame.last_frame = scheduled_frame;
+        self.next_index = (index + 1) % self.frames.len();
+        self.acquired = Some(index);
+        Ok(HeadlessImage {
+            index,
+            color_view: frame.color_view,
+            depth_view: frame.depth_view,
+            format: self.info.format,
+            dimensions: self.info.dimensions,
+        })
+    }
+
+    /// Presents the acquired image by reading back its color image, once `command_id` has
+    /// finished executing.
+    ///
+    /// `command_id` should be the command, which rendered to the image on the current tick.
+    ///
+    /// The read back is scheduled with `scheduler` and the returned future resolves with the
+    /// tightly packed texels of the image, like with [`Gpu::read_image`].
+    pub fn present(
+        &mut self,
+        scheduler: &mut CommandScheduler<'_>,
+        command_id: CommandId,
+    ) -> Result<PendingReadback>
+    {
+        let index = self.acquired
+            .take()
+            .ok_or_else(|| Error::just_context("no image has been acquired"))?;
+        let image_id = self.frames[index as usize].color_view.image_id();
+        self.gpu.read_image_after(
+            scheduler,
+            image_id,
+            ImageSubresourceLayers::default().aspect_mask(ImageAspects::COLOR),
+            &[CommandDependency::new(command_id, MemoryDependencyHint::TRANSFER)],
+        ).context_with(|| format!("failed to present headless image {index}"))
+    }
+
+    /// Same as [`present`][1], but calls `f` with the read back texels on a worker thread of the
+    /// [`Gpu`].
+    ///
+    /// [1]: Self::present
+    pub fn present_with<F>(
+        &mut self,
+        scheduler: &mut CommandScheduler<'_>,
+        command_id: CommandId,
+        f: F,
+    ) -> Result<()>
+        where F: FnOnce(Result<Readback>) + Send + 'static
+    {
+        let readback = self.present(scheduler, command_id)?;
+        self.gpu.thread_pool()
+            .spawn(async move {
+                f(readback.await)
+            }).context("spawn error")
+    }
+
+    /// Recreates the images with new `dimensions`.
+    ///
+    /// This waits until every image of the ring has been rendered to.
+    ///
+    /// If creating the new images fails, the ring is left without images and resizing *can* be
+    /// retried.
+    pub fn resize(&mut self, dimensions: impl Into<Dimensions>) -> Result<()> {
+        self.destroy_frames()
+            .context("failed to destroy headless images")?;
+        self.info.dimensions = dimensions.into();
+        self.create_frames()
+            .inspect_err(|_| {
+                self.destroy_frames().ok();
+            })
+            .context("failed to create headless images")
+    }
+}
+
+impl Drop for HeadlessTarget {
+
+    fn drop(&mut self) {
+        self.destroy_frames().ok();
+    }
+}
@@ -36,6 +36,11 @@ pub(crate) use properties::ImageProperties;
 
 enum MemorySource {
     Joint { _device_memory: DeviceMemoryObj, arena_size: usize },
+    Sparse {
+        memory: SparseMemory,
+        requirements: Vec32<SparseImageMemoryRequirements>,
+        arena_size: usize,
+    },
     Swapchain,
 }
 
@@ -82,6 +87,32 @@ impl ImageMeta {
         if create_info.mutable_format {
             flags |= vk::ImageCreateFlags::MUTABLE_FORMAT;
         }
+        if !create_info.sparse.is_empty() {
+            let features = device.base_device_features();
+            let residency_feature =
+                if image_type == vk::ImageType::TYPE_3D {
+                    (features.sparse_residency_image_3d, "sparse_residency_image_3d")
+                } else if create_info.samples == MsaaSamples::X1 {
+                    (features.sparse_residency_image_2d, "sparse_residency_image_2d")
+                } else if create_info.samples == MsaaSamples::X2 {
+                    (features.sparse_residency_2_samples, "sparse_residency_2_samples")
+                } else if create_info.samples == MsaaSamples::X4 {
+                    (features.sparse_residency_4_samples, "sparse_residency_4_samples")
+                } else if create_info.samples == MsaaSamples::X8 {
+                    (features.sparse_residency_8_samples, "sparse_residency_8_samples")
+                } else if create_info.samples == MsaaSamples::X16 {
+                    (features.sparse_residency_16_samples, "sparse_residency_16_samples")
+                } else if create_info.sparse.contains(SparseFlags::RESIDENCY) {
+                    return Err(Error::just_context(format!(
+                        "sparse residency is not supported with sample count {}",
+                        create_info.samples,
+                    )))
+                } else {
+                    (false, "")
+                };
+            create_info.sparse.validate_features(features, residency_feature)?;
+            flags |= vk::ImageCreateFlags::from(create_info.sparse);
+        }
         if create_info.cube_map {
             flags |= vk::ImageCreateFlags::CUBE_COMPATIBLE;
             if create_info.dimensions.width != create_info.dimensions.height ||
@@ -204,20 +235,58 @@ impl ImageMeta {
                 &device_mem_requirements, &mut mem_requirements,
             );
         };
-        let memory = unsafe {
-            create_info.memory_binder.alloc(&mem_requirements)
-            .context("failed to allocate GPU memory for image")?
-        };
+        let mut memory =
+            if create_info.sparse.is_empty() {
+                MemorySource::Joint {
+                    _device_memory: unsafe {
+                        create_info.memory_binder.alloc(&mem_requirements)
+                        .context("failed to allocate GPU memory for image")?
+                    },
+                    arena_size: 0,
+                }
+            } else {
+                let sparse_requirements = unsafe {
+                    let device_mem_requirements = vk::DeviceImageMemoryRequirements {
+                        s_type: vk::StructureType::DEVICE_IMAGE_MEMORY_REQUIREMENTS,
+                        p_create_info: &vk_create_info,
+                        ..Default::default()
+                    };
+                    let mut sparse_requirements = Vec32::with_len(
+                        device.get_device_image_sparse_memory_requirements_len(
+                            &device_mem_requirements,
+                        ),
+                        Default::default(),
+                    );
+                    device.get_device_image_sparse_memory_requirements(
+                        &device_mem_requirements,
+                        &mut sparse_requirements,
+                    );
+                    sparse_requirements
+                };
+                MemorySource::Sparse {
+                    memory: SparseMemory::new(
+                        create_info.sparse, mem_requirements.memory_requirements,
+                    ),
+                    requirements: sparse_requirements
+                        .iter()
+                        .map(|r| r.memory_requirements.into())
+                        .collect(),
+                    arena_size: 0,
+                }
+            };
         let handle = unsafe {
             device.create_image(&vk_create_info, None)
             .context("failed to create Vulkan image")?
         };
-        *bind_memory_info = vk::BindImageMemoryInfo {
-            image: handle,
-            memory: <_ as vk::Handle>::from_raw(memory.handle()),
-            memory_offset: memory.offset(),
-            ..Default::default()
-        };
+        device.set_object_name(handle, create_info.name);
+        if let MemorySource::Joint { _device_memory: memory, .. } = &memory {
+            *bind_memory_info = vk::BindImageMemoryInfo {
+                image: handle,
+                memory: <_ as vk::Handle>::from_raw(memory.handle()),
+                memory_offset: memory.offset(),
+                ..Default::default()
+            };
+        }
         let mut format_properties3 = vk::FormatProperties3::default();
         let mut format_properties = vk::FormatProperties2
             ::default()
@@ -267,13 +336,18 @@ impl ImageMeta {
             });
             vec.into_static()
         });
+        if let MemorySource::Joint { arena_size, .. } |
+            MemorySource::Sparse { arena_size, .. } = &mut memory
+        {
+            *arena_size = arena.into_raw_parts().1;
+        }
         Ok(Self {
             device,
             handle,
             image_views: Vec32::with_capacity(1),
             properties,
             states,
-            memory: MemorySource::Joint { _device_memory: memory, arena_size: arena.into_raw_parts().1, },
+            memory,
         })
     }
 
@@ -350,6 +424,39 @@ impl ImageMeta {
         matches!(self.memory, MemorySource::Swapchain)
     }
 
+    #[inline]
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.memory, MemorySource::Sparse { .. })
+    }
+
+    /// Returns the sparse memory requirements of each aspect of the image or [`None`] if the image
+    /// is not sparse.
+    #[inline]
+    pub fn sparse_requirements(&self) -> Option<&[SparseImageMemoryRequirements]> {
+        match &self.memory {
+            MemorySource::Sparse { requirements, .. } => Some(requirements.as_slice()),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn sparse_memory(&self) -> Option<&SparseMemory> {
+        match &self.memory {
+            MemorySource::Sparse { memory, .. } => Some(memory),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn sparse_memory_mut(
+        &mut self,
+    ) -> Option<(&mut SparseMemory, &[SparseImageMemoryRequirements])> {
+        match &mut self.memory {
+            MemorySource::Sparse { memory, requirements, .. } => Some((memory, requirements.as_slice())),
+            _ => None,
+        }
+    }
+
     fn get_states_mut(
         &mut self,
         aspect: ImageAspects,
@@ -485,8 +592,38 @@ impl ImageMeta {
             is_cube_map: range.is_cube_map,
         });
         Ok(id)
-    } 
-    
+    }
+
+    /// Returns the handle of a view with the image format and identity mapping covering exactly
+    /// `subresource_range`, creating one if none exists.
+    ///
+    /// The view lives as long as the image.
+    pub(crate) fn get_or_create_view_handle(
+        &mut self,
+        subresource_range: ImageSubresourceRange,
+    ) -> Result<vk::ImageView> {
+        let component_info = ComponentInfo {
+            component_mapping: ComponentMapping::default(),
+            format: self.properties.format,
+        };
+        if let Some(view) = self.image_views
+            .iter()
+            .find(|view|
+                view.subresource_range == subresource_range &&
+                view.component_info == component_info &&
+                !view.is_cube_map
+            )
+        {
+            return Ok(view.handle)
+        }
+        let index = self.create_view(ImageRange {
+            subresource_range,
+            component_info: None,
+            is_cube_map: false,
+        })?;
+        Ok(self.image_views[index as usize].handle)
+    }
+
     #[inline]
     pub(crate) fn get_view<AnyImageId>(
         &self,
@@ -520,6 +657,27 @@ impl ImageMeta {
         }
     }
 
+    /// Resets the state of every subresource to [`vk::ImageLayout::UNDEFINED`], so that the next
+    /// barrier discards the contents of the image.
+    ///
+    /// Used when the memory of the image *may* have been written through an aliased resource.
+    pub(crate) fn discard_subresources(&mut self) {
+        let layer_count = self.properties.array_layers;
+        for states in &mut self.states {
+            states.clear();
+            states.push(ImageLayerRange {
+                state: ImageSubresourceState {
+                    stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+                    access_mask: vk::AccessFlags2::MEMORY_WRITE,
+                    layout: vk::ImageLayout::UNDEFINED,
+                    queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                },
+                base_array_layer: 0,
+                layer_count,
+            });
+        }
+    }
+
     /// Registers a memory barrier, which *can* be used to perform [`pipeline barrier`][1] with the
     /// [`cache`][2].
     ///
@@ -721,6 +879,70 @@ impl ImageMeta {
             ))
         }
     }
+    /// Registers memory barriers, which transfer the ownership of every subresource of the image
+    /// to `queue_family_index` without otherwise accessing it.
+    ///
+    /// The layouts of the subresources are preserved.
+    ///
+    /// The returned [`range`][1] *must* be [`flushed`][2] and recorded, if the range is not empty.
+    ///
+    /// [1]: ImageMemoryBarrierRange
+    /// [2]: ImageMemoryBarrierCache::flush
+    pub(crate) fn ownership_barrier(
+        &mut self,
+        queue_family_index: u32,
+        cache: &mut ImageMemoryBarrierCache,
+    ) -> ImageMemoryBarrierRange
+    {
+        let cache_index = cache.barriers.len();
+        for aspect in self.properties.aspect_mask.as_raw()
+            .bit_iter()
+            .map(ImageAspects::from_raw)
+        {
+            for level in 0..self.properties.mip_levels {
+                let ranges = self.get_states_mut(aspect, level).unwrap();
+                for range in ranges {
+                    let src_state = range.state;
+                    if src_state.queue_family_index == queue_family_index {
+                        continue
+                    }
+                    let dst_state = ImageSubresourceState {
+                        stage_mask: vk::PipelineStageFlags2::ALL_COMMANDS,
+                        access_mask: vk::AccessFlags2::MEMORY_READ | vk::AccessFlags2::MEMORY_WRITE,
+                        queue_family_index,
+                        ..src_state
+                    };
+                    if src_state.queue_family_index != vk::QUEUE_FAMILY_IGNORED &&
+                        src_state.layout != vk::ImageLayout::UNDEFINED
+                    {
+                        cache.barriers.push(ImageMemoryBarrier {
+                            src_stage_mask: src_state.stage_mask,
+                            src_access_mask: src_state.access_mask,
+                            dst_stage_mask: dst_state.stage_mask,
+                            dst_access_mask: dst_state.access_mask,
+                            old_layout: src_state.layout,
+                            new_layout: dst_state.layout,
+                            src_queue_family_index: src_state.queue_family_index,
+                            dst_queue_family_index: queue_family_index,
+                            subresource_range: vk::ImageSubresourceRange {
+                                aspect_mask: aspect.into(),
+                                base_mip_level: level,
+                                level_count: 1,
+                                base_array_layer: range.base_array_layer,
+                                layer_count: range.layer_count,
+                            },
+                        });
+                    }
+                    range.state = dst_state;
+                }
+            }
+        }
+        ImageMemoryBarrierRange {
+            handle: self.handle,
+            range_start: cache_index,
+            range_end: cache.barriers.len(),
+        }
+    }
 }
 
 impl Drop for ImageMeta {
@@ -730,7 +952,9 @@ impl Drop for ImageMeta {
             for &view in &self.image_views {
                 self.device.destroy_image_view(view.handle, None);
             }
-            if let MemorySource::Joint { _device_memory, arena_size, } = &self.memory {
+            if let MemorySource::Joint { arena_size, .. } |
+                MemorySource::Sparse { arena_size, .. } = &self.memory
+            {
                 self.device.destroy_image(self.handle(), None);
                 StdAlloc.free_raw(
                     NonNull::new_unchecked(self.states.as_mut_ptr()).cast(),
@@ -13,8 +13,10 @@ pub struct ImageCreateInfo<'a> {
     pub(super) mip_levels: u32,
     pub(super) cube_map: bool,
     pub(super) mutable_format: bool,
+    pub(super) sparse: SparseFlags,
     pub(super) resolve_modes: FormatResolveModes,
     pub(super) texel_block_size: DeviceSize,
+    pub(super) name: &'a str,
 }
 
 impl<'a> ImageCreateInfo<'a> {
@@ -43,11 +45,24 @@ impl<'a> ImageCreateInfo<'a> {
             mip_levels: 1,
             cube_map: false,
             mutable_format: false,
+            sparse: SparseFlags::empty(),
             resolve_modes: Default::default(),
             texel_block_size: 0,
+            name: "",
         }
     }
 
+    /// Sets the `name` for the image. The default name is an empty string.
+    ///
+    /// The name is given to the image as its [`object name`][1].
+    ///
+    /// [1]: LogicalDevice::set_object_name
+    #[inline(always)]
+    pub fn with_name(mut self, name: &'a str) -> Self {
+        self.name = name;
+        self
+    }
+
     /// Specifies the images dimensions
     ///
     /// Note that every dimension including `depth` needs to non-zero.
@@ -120,6 +135,29 @@ impl<'a> ImageCreateInfo<'a> {
         self
     }
 
+    /// Makes the image sparse with `flags`. The default is [`SparseFlags::empty`].
+    ///
+    /// The memory of a sparse image is not allocated with the memory binder of the create info.
+    /// Instead it is bound at page or sparse block granularity with [`SparseBindCommands`].
+    ///
+    /// The block layout of the image *can* be queried with
+    /// [`Gpu::get_image_sparse_memory_requirements`].
+    ///
+    /// # Valid usage
+    /// - [`BaseDeviceFeatures::sparse_binding`] *must* be enabled, if `flags` is not empty.
+    /// - If `flags` contains [`SparseFlags::RESIDENCY`], the residency feature matching the image
+    ///   *must* be enabled:
+    ///     - [`BaseDeviceFeatures::sparse_residency_image_3d`] for 3D images.
+    ///     - [`BaseDeviceFeatures::sparse_residency_image_2d`] for single sampled 2D images.
+    ///     - [`BaseDeviceFeatures::sparse_residency_2_samples`] etc. for multisampled 2D images.
+    /// - [`BaseDeviceFeatures::sparse_residency_aliased`] *must* be enabled, if `flags` contains
+    ///   [`SparseFlags::ALIASED`].
+    #[inline(always)]
+    pub fn with_sparse(mut self, flags: SparseFlags) -> Self {
+        self.sparse = flags;
+        self
+    }
+
     #[inline(always)]
     pub(crate) fn build(
         &self,
@@ -57,12 +57,34 @@ pub(crate) struct ImageMemoryBarrier {
 
 impl ImageMemoryBarrier {
 
+    /// Returns whether the barrier transfers the ownership of the subresources to another queue
+    /// family.
+    ///
+    /// Barriers, which discard the contents of the subresources, don't need to transfer ownership.
+    #[inline(always)]
+    pub fn is_ownership_transfer(&self) -> bool {
+        self.src_queue_family_index != vk::QUEUE_FAMILY_IGNORED &&
+        self.src_queue_family_index != self.dst_queue_family_index &&
+        self.old_layout != vk::ImageLayout::UNDEFINED
+    }
+
+    /// Returns the release half of a queue family ownership transfer, which *must* be recorded
+    /// on the source queue family.
+    #[inline(always)]
+    pub fn release(self) -> Self {
+        Self {
+            dst_stage_mask: vk::PipelineStageFlags2::NONE,
+            dst_access_mask: vk::AccessFlags2::NONE,
+            ..self
+        }
+    }
+
     pub fn into_vk(self, image: vk::Image) -> vk::ImageMemoryBarrier2<'static> {
-        let dst_queue_family_index = 
-            if self.src_queue_family_index == vk::QUEUE_FAMILY_IGNORED {
-                vk::QUEUE_FAMILY_IGNORED
+        let (src_queue_family_index, dst_queue_family_index) =
+            if self.is_ownership_transfer() {
+                (self.src_queue_family_index, self.dst_queue_family_index)
             } else {
-                self.dst_queue_family_index
+                (vk::QUEUE_FAMILY_IGNORED, vk::QUEUE_FAMILY_IGNORED)
             };
         vk::ImageMemoryBarrier2 {
             s_type: vk::StructureType::IMAGE_MEMORY_BARRIER_2,
@@ -72,7 +94,7 @@ impl ImageMemoryBarrier {
             dst_access_mask: self.dst_access_mask,
             old_layout: self.old_layout,
             new_layout: self.new_layout,
-            src_queue_family_index: self.src_queue_family_index,
+            src_queue_family_index,
             dst_queue_family_index,
             image,
             subresource_range: self.subresource_range,
@@ -152,6 +174,7 @@ pub struct ImageMemoryBarrierCache {
     pub(super) cache: AHashMap<(ImageAspects, u32, u32), Vec32<ImageMemoryBarrier>>,
     pub(super) touched: Vec32<(ImageAspects, u32, u32)>,
     pub(super) barriers: Vec32<ImageMemoryBarrier>,
+    pub(crate) releases: Vec32<(vk::Image, ImageMemoryBarrier)>,
 }
 
 #[derive(Default, Clone, Copy)]
@@ -198,6 +221,10 @@ impl ImageMemoryBarrierCache {
         });
     }
 
+    /// Flushes the barriers of `ranges`.
+    ///
+    /// The barriers, which transfer queue family ownership, are the acquire halves of the
+    /// transfers. Their release halves are collected and recorded by the command scheduler.
     pub fn flush<'a, Alloc>(
         &mut self,
         ranges: &[ImageMemoryBarrierRange],
@@ -215,7 +242,10 @@ impl ImageMemoryBarrierCache {
                 continue
             }
             let handle = barrier.handle;
-            for barrier in &self.barriers[barrier.range_start as usize..barrier.range_end as usize] {
+            for &barrier in &self.barriers[barrier.range_start as usize..barrier.range_end as usize] {
+                if barrier.is_ownership_transfer() {
+                    self.releases.push((handle, barrier.release()));
+                }
                 vk_barriers.push(barrier.into_vk(handle));
             }
         }
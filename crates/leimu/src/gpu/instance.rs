@@ -23,10 +23,17 @@ use nox_ash::{
         android_surface,
         get_surface_capabilities2,
     },
+    ext::{
+        debug_utils,
+        swapchain_colorspace,
+    },
 };
 
 use crate::{
-    gpu::prelude::*,
+    gpu::{
+        prelude::*,
+        debug_utils::DebugMessenger,
+    },
     error::*,
     log::{info, warn},
     sync::*,
@@ -73,6 +80,7 @@ struct Inner {
     instance: nox_ash::Instance,
     get_surface_capabilities2: get_surface_capabilities2::Instance,
     surface: surface::Instance,
+    debug_messenger: Option<DebugMessenger>,
 }
 
 /// Represents a [`Vulkan instance`][1].
@@ -95,6 +103,56 @@ impl Instance {
         layers: &[InstanceLayer<'_>],
     ) -> Result<Self>
         where H: HasDisplayHandle
+    {
+        Self::with_debug_utils(platform, app_name, app_version, layers, None)
+    }
+
+    /// Creates a new instance, which optionally enables [`VK_EXT_debug_utils`][1].
+    ///
+    /// If `debug_utils` is [`Some`] and the extension is present, a debug messenger forwarding
+    /// messages to [`nox_log`][2] is created, Vulkan objects are given the names specified at
+    /// creation and command debug labels are recorded.
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_debug_utils.html
+    /// [2]: crate::log
+    pub fn with_debug_utils<H>(
+        platform: &H,
+        app_name: &str, 
+        app_version: Version,
+        layers: &[InstanceLayer<'_>],
+        debug_utils: Option<DebugUtilsInfo>,
+    ) -> Result<Self>
+        where H: HasDisplayHandle
+    {
+        let mut extensions = Vec32::<(&CStr, bool)>
+            ::with_capacity(8);
+        get_required_instance_extensions(platform, &mut extensions)?;
+        extensions.push((swapchain_colorspace::NAME, false));
+        Self::create(extensions, app_name, app_version, layers, debug_utils)
+    }
+
+    /// Creates a new instance without any surface extensions.
+    ///
+    /// Surfaces *can't* be created with a headless instance, but everything else works like
+    /// with [`Instance::with_debug_utils`]. Render to a [`HeadlessTarget`] instead, e.g. when
+    /// running without a display.
+    pub fn headless(
+        app_name: &str,
+        app_version: Version,
+        layers: &[InstanceLayer<'_>],
+        debug_utils: Option<DebugUtilsInfo>,
+    ) -> Result<Self>
+    {
+        Self::create(Vec32::with_capacity(2), app_name, app_version, layers, debug_utils)
+    }
+
+    fn create(
+        mut extensions: Vec32<(&CStr, bool)>,
+        app_name: &str, 
+        app_version: Version,
+        layers: &[InstanceLayer<'_>],
+        debug_utils: Option<DebugUtilsInfo>,
+    ) -> Result<Self>
     {
         let entry = unsafe { nox_ash::Entry::load().context("failed to create vulkan entry")? };
         match unsafe { entry.try_enumerate_instance_version() } {
@@ -130,12 +188,12 @@ impl Instance {
             api_version: vk::API_VERSION_1_4,
             ..Default::default()
         };
-        let mut extensions = Vec32::<(&CStr, bool)>
-            ::with_capacity(8);
         let mut found_extensions = Vec32::<*const i8>
             ::with_capacity(8);
         let mut found_extensions_hashed = AHashSet::default();
-        get_required_instance_extensions(platform, &mut extensions)?;
+        if debug_utils.is_some() {
+            extensions.push((debug_utils::NAME, false));
+        }
         let mut found_layers = Vec32::<*const i8>
             ::with_capacity(8);
         let mut found_layers_hashed = AHashSet::default();
@@ -168,7 +226,12 @@ impl Instance {
                 Version(version),
             )))
         }
-        let instance_create_info = vk::InstanceCreateInfo {
+        let debug_utils = debug_utils.filter(|_|
+            found_extensions_hashed.contains(debug_utils::NAME)
+        );
+        let mut messenger_create_info = debug_utils
+            .map(|info| info.messenger_create_info());
+        let mut instance_create_info = vk::InstanceCreateInfo {
             s_type: vk::StructureType::INSTANCE_CREATE_INFO,
             p_application_info: &application_info,
             enabled_extension_count: found_extensions.len(),
@@ -177,6 +240,9 @@ impl Instance {
             pp_enabled_layer_names: found_layers.as_ptr() as _,
             ..Default::default()
         };
+        if let Some(info) = &mut messenger_create_info {
+            instance_create_info = instance_create_info.push_next(info);
+        }
         let instance = unsafe {
             entry
                 .create_instance(&instance_create_info, None)
@@ -186,12 +252,19 @@ impl Instance {
             ::new(&entry, &instance);
         let surface = surface::Instance
             ::new(&entry, &instance);
+        let debug_messenger = debug_utils
+            .map(|info| DebugMessenger::new(&entry, &instance, &info))
+            .transpose()
+            .inspect_err(|_| unsafe {
+                instance.destroy_instance(None);
+            })?;
         Ok(Self {
             inner: Arc::new(Inner {
                 entry,
                 instance,
                 get_surface_capabilities2,
                 surface,
+                debug_messenger,
             }),
         })
     }
@@ -248,6 +321,14 @@ impl Instance {
     pub fn get_surface_capabilities2_instance(&self) -> &get_surface_capabilities2::Instance {
         &self.inner.get_surface_capabilities2
     }
+
+    /// Returns whether [`VK_EXT_debug_utils`][1] is enabled.
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VK_EXT_debug_utils.html
+    #[inline(always)]
+    pub fn is_debug_utils_enabled(&self) -> bool {
+        self.inner.debug_messenger.is_some()
+    }
 }
 
 /// A structure returned by [`enumerate suitable physical devices`][1].
@@ -306,6 +387,9 @@ impl Drop for Inner {
     #[inline(always)]
     fn drop(&mut self) {
         unsafe {
+            if let Some(messenger) = &mut self.debug_messenger {
+                messenger.destroy();
+            }
             self.instance.destroy_instance(None);
         }
     }
@@ -25,4 +25,10 @@ impl DeviceLimits<'_> {
     pub fn non_coherent_atom_size(&self) -> DeviceSize {
         self.limits.non_coherent_atom_size
     }
+
+    /// The number of nanoseconds it takes for a timestamp query value to be incremented by 1.
+    #[inline(always)]
+    pub fn timestamp_period(&self) -> f32 {
+        self.limits.timestamp_period
+    }
 }
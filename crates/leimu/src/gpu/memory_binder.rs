@@ -1,17 +1,23 @@
 mod definitions;
 mod global;
 mod linear;
+mod aliasing;
+mod pool;
 
+use nox_mem::vec::Vec32;
 use nox_ash::vk;
 
 use crate::{
     error::*,
     gpu::prelude::*,
+    sync::Arc,
 };
 
 pub use definitions::*;
 pub use linear::*;
 pub use global::*;
+pub use aliasing::*;
+pub use pool::*;
 
 #[derive(Debug, Error)]
 pub enum MemoryBinderError {
@@ -27,6 +33,85 @@ pub enum MemoryBinderError {
     IncompatibleMemoryRequirements,
 }
 
+/// Statistics of a [`MemoryBinder`].
+#[derive(Default, Clone, Copy, Debug)]
+pub struct MemoryBinderStatistics {
+    /// The number of [`device memory`][1] objects allocated by the binder.
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+    pub block_count: u32,
+    /// The number of live allocations made from the blocks.
+    pub allocation_count: u32,
+    /// The total size of all blocks.
+    pub block_bytes: DeviceSize,
+    /// The total size of all live allocations.
+    pub allocation_bytes: DeviceSize,
+    /// The size of the largest range, which is free in a single block.
+    pub largest_free_range: DeviceSize,
+}
+
+impl MemoryBinderStatistics {
+
+    /// Returns the number of bytes in blocks, which are not used by any allocation.
+    #[inline(always)]
+    pub fn free_bytes(&self) -> DeviceSize {
+        self.block_bytes.saturating_sub(self.allocation_bytes)
+    }
+
+    /// Returns how fragmented the free memory of the binder is, between `0.0` and `1.0`.
+    ///
+    /// This is `0.0`, when all free memory is in a single range, and approaches `1.0` as free
+    /// memory is split into smaller ranges.
+    #[inline(always)]
+    pub fn fragmentation(&self) -> f32 {
+        let free = self.free_bytes();
+        if free == 0 {
+            return 0.0
+        }
+        1.0 - (self.largest_free_range.min(free) as f64 / free as f64) as f32
+    }
+}
+
+impl core::ops::AddAssign for MemoryBinderStatistics {
+
+    #[inline(always)]
+    fn add_assign(&mut self, rhs: Self) {
+        self.block_count += rhs.block_count;
+        self.allocation_count += rhs.allocation_count;
+        self.block_bytes += rhs.block_bytes;
+        self.allocation_bytes += rhs.allocation_bytes;
+        self.largest_free_range = self.largest_free_range.max(rhs.largest_free_range);
+    }
+}
+
+/// A snapshot of the memory usage of a [`Gpu`].
+///
+/// Created with [`Gpu::memory_statistics`].
+#[derive(Clone)]
+pub struct MemoryStatistics {
+    /// The usage and budget of each memory heap.
+    pub heaps: Vec32<MemoryHeapBudget>,
+    /// The allocations made from each memory type.
+    pub memory_types: Vec32<MemoryTypeStatistics>,
+    /// The statistics of each [`registered`][1] binder with its name.
+    ///
+    /// [1]: Gpu::register_memory_binder
+    pub binders: Vec32<(Arc<str>, MemoryBinderStatistics)>,
+}
+
+impl MemoryStatistics {
+
+    /// Returns the total number of bytes allocated from memory types, which have all of
+    /// `properties`.
+    pub fn allocation_bytes_with(&self, properties: MemoryProperties) -> DeviceSize {
+        self.memory_types
+            .iter()
+            .filter(|ty| ty.properties.contains(properties))
+            .map(|ty| ty.allocation_bytes)
+            .sum()
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub enum HostCoherency {
     /// Specifies that the memory is not available for host reads/writes
@@ -198,4 +283,13 @@ pub unsafe trait MemoryBinder: 'static + Send + Sync {
     /// - This *should* never free any [`vk::DeviceMemory`].
     /// - Previous allocations *may* be overwritten by future allocations.
     unsafe fn release_resources(&self);
+
+    /// Returns the current [`statistics`][1] of this binder.
+    ///
+    /// The default implementation returns empty statistics.
+    ///
+    /// [1]: MemoryBinderStatistics
+    fn statistics(&self) -> MemoryBinderStatistics {
+        Default::default()
+    }
 }
@@ -0,0 +1,329 @@
+use nox_mem::vec::Vec32;
+use nox_ash::vk;
+
+use crate::{
+    gpu::prelude::*,
+    sync::*,
+};
+
+use super::*;
+
+use MemoryBinderError::*;
+
+struct Block {
+    device: LogicalDevice,
+    memory: vk::DeviceMemory,
+    size: DeviceSize,
+    memory_type_index: u32,
+}
+
+impl Drop for Block {
+
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .free_memory(self.memory, None);
+        }
+    }
+}
+
+unsafe impl Send for Block {}
+unsafe impl Sync for Block {}
+
+#[derive(Clone, Copy)]
+struct Placement {
+    block: u32,
+    offset: DeviceSize,
+    size: DeviceSize,
+    first: u32,
+    last: u32,
+}
+
+impl Placement {
+
+    #[inline(always)]
+    fn lifetime_overlaps(&self, first: u32, last: u32) -> bool {
+        self.first <= last && first <= self.last
+    }
+
+    #[inline(always)]
+    fn memory_overlaps(&self, other: &Self) -> bool {
+        self.block == other.block &&
+        self.offset < other.offset + other.size &&
+        other.offset < self.offset + self.size
+    }
+}
+
+struct Inner {
+    blocks: Vec32<Arc<Block>>,
+    placements: Vec32<Placement>,
+    lifetime: (u32, u32),
+}
+
+struct Memory {
+    block: Arc<Block>,
+    offset: DeviceSize,
+    size: DeviceSize,
+}
+
+unsafe impl DeviceMemory for Memory {
+
+    fn handle(&self) -> u64 {
+        <_ as vk::Handle>::as_raw(self.block.memory)
+    }
+
+    fn memory_size(&self) -> u64 {
+        self.block.size
+    }
+
+    fn offset(&self) -> DeviceSize {
+        self.offset
+    }
+
+    fn size(&self) -> DeviceSize {
+        self.size
+    }
+
+    fn map_memory(&mut self) -> Result<MemoryMap> {
+        Err(Error::just_context(UnmappableMemory))
+    }
+
+    fn unmap_memory(&mut self) -> Result<()> {
+        Err(Error::just_context(
+            "memory is not mapped"
+        ))
+    }
+
+    fn is_optimal(&self) -> bool {
+        true
+    }
+
+    fn is_mapped(&self) -> bool {
+        false
+    }
+}
+
+/// A [`MemoryBinder`], which places allocations with non-overlapping lifetimes into the same
+/// [`device memory`][1].
+///
+/// Lifetimes are inclusive ranges of arbitrary indices, e.g. pass indices of a
+/// [`render graph`][2], and they are set with [`AliasingBinder::set_lifetime`] before allocating.
+///
+/// Memory allocated by this binder is always device local and never mappable.
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+/// [2]: RenderGraph
+pub struct AliasingBinder {
+    device: LogicalDevice,
+    memory_type_bits: u32,
+    block_size: DeviceSize,
+    inner: Mutex<Inner>,
+}
+
+impl AliasingBinder {
+
+    /// Creates a new aliasing binder, which allocates [`device memory`][1] in blocks of at least
+    /// `block_size` bytes.
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+    pub fn new(
+        device: LogicalDevice,
+        block_size: DeviceSize,
+    ) -> Self
+    {
+        let device_local: vk::MemoryPropertyFlags = MemoryProperties::DEVICE_LOCAL.into();
+        let memory_properties = device.physical_device().memory_properties();
+        let mut memory_type_bits = 0;
+        for (i, memory_type) in memory_properties.memory_types[..memory_properties.memory_type_count as usize]
+            .iter()
+            .enumerate()
+        {
+            if memory_type.property_flags.contains(device_local) {
+                memory_type_bits |= 1 << i;
+            }
+        }
+        Self {
+            device,
+            memory_type_bits,
+            block_size,
+            inner: Mutex::new(Inner {
+                blocks: Default::default(),
+                placements: Default::default(),
+                lifetime: (0, u32::MAX),
+            }),
+        }
+    }
+
+    /// Sets the inclusive lifetime of subsequent allocations.
+    ///
+    /// The default lifetime overlaps with every other lifetime.
+    #[inline(always)]
+    pub fn set_lifetime(&self, first: u32, last: u32) {
+        self.inner.lock().lifetime = (first, last);
+    }
+
+    /// Returns the number of allocations made since creation or the last
+    /// [`release`][MemoryBinder::release_resources].
+    #[inline(always)]
+    pub fn allocation_count(&self) -> u32 {
+        self.inner.lock().placements.len()
+    }
+
+    /// Returns whether the allocations at index `a` and `b` share memory.
+    ///
+    /// Indices are in allocation order.
+    pub fn aliases(&self, a: u32, b: u32) -> bool {
+        let inner = self.inner.lock();
+        let (Some(a), Some(b)) = (
+            inner.placements.get(a as usize),
+            inner.placements.get(b as usize),
+        ) else {
+            return false
+        };
+        a.memory_overlaps(b)
+    }
+
+    /// Returns the total size of the [`device memory`][1] allocated by this binder.
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+    pub fn memory_size(&self) -> DeviceSize {
+        self.inner
+            .lock()
+            .blocks
+            .iter()
+            .map(|block| block.size)
+            .sum()
+    }
+}
+
+unsafe impl MemoryBinder for AliasingBinder {
+
+    #[inline]
+    fn max_alloc_size(&self) -> vk::DeviceSize {
+        self.device.max_memory_allocation_size()
+    }
+
+    #[inline]
+    fn optimal_host_coherency(&self) -> HostCoherency {
+        HostCoherency::None
+    }
+
+    #[inline]
+    fn suboptimal_host_coherency(&self) -> HostCoherency {
+        HostCoherency::None
+    }
+
+    unsafe fn alloc(
+        &self,
+        memory_requirements: &vk::MemoryRequirements2,
+    ) -> Result<DeviceMemoryObj> {
+        let requirements = memory_requirements.memory_requirements;
+        if requirements.size == 0 {
+            return Err(Error::just_context(ZeroSizeAlloc))
+        }
+        let memory_type_bits = self.memory_type_bits & requirements.memory_type_bits;
+        if memory_type_bits == 0 {
+            return Err(Error::just_context(IncompatibleMemoryRequirements))
+        }
+        let align = requirements.alignment.max(1);
+        let mut inner = self.inner.lock();
+        let (first, last) = inner.lifetime;
+        let mut found = None;
+        for (block_index, block) in inner.blocks.iter().enumerate() {
+            if memory_type_bits & (1 << block.memory_type_index) == 0 ||
+                block.size < requirements.size
+            {
+                continue
+            }
+            let block_index = block_index as u32;
+            let conflicts = || inner.placements
+                .iter()
+                .filter(move |p| p.block == block_index && p.lifetime_overlaps(first, last));
+            let candidates = core::iter::once(0)
+                .chain(conflicts().map(|p| p.offset + p.size))
+                .map(|offset| offset.next_multiple_of(align));
+            let mut best: Option<DeviceSize> = None;
+            for offset in candidates {
+                if offset + requirements.size > block.size ||
+                    best.is_some_and(|best| best <= offset)
+                {
+                    continue
+                }
+                if conflicts().all(|p|
+                    offset + requirements.size <= p.offset ||
+                    p.offset + p.size <= offset
+                ) {
+                    best = Some(offset);
+                }
+            }
+            if let Some(offset) = best {
+                found = Some((block_index, offset));
+                break
+            }
+        }
+        let (block_index, offset) = match found {
+            Some(found) => found,
+            None => {
+                let memory_type_index = memory_type_bits.trailing_zeros();
+                let size = self.block_size.max(requirements.size);
+                let mut allocate_info = vk::MemoryAllocateInfo {
+                    s_type: vk::StructureType::MEMORY_ALLOCATE_INFO,
+                    allocation_size: size,
+                    memory_type_index,
+                    ..Default::default()
+                };
+                let memory = unsafe {
+                    self.device.allocate_memory(&mut allocate_info, None)
+                }.context("failed to allocate device memory")?;
+                inner.blocks.push(Arc::new(Block {
+                    device: self.device.clone(),
+                    memory,
+                    size,
+                    memory_type_index,
+                }));
+                (inner.blocks.len() - 1, 0)
+            },
+        };
+        inner.placements.push(Placement {
+            block: block_index,
+            offset,
+            size: requirements.size,
+            first,
+            last,
+        });
+        Ok(DeviceMemoryObj::new(Memory {
+            block: inner.blocks[block_index as usize].clone(),
+            offset,
+            size: requirements.size,
+        }))
+    }
+
+    fn statistics(&self) -> MemoryBinderStatistics {
+        let inner = self.inner.lock();
+        let mut statistics = MemoryBinderStatistics::default();
+        for (block_index, block) in inner.blocks.iter().enumerate() {
+            let end = inner.placements
+                .iter()
+                .filter(|p| p.block == block_index as u32)
+                .map(|p| p.offset + p.size)
+                .max()
+                .unwrap_or(0);
+            statistics.block_count += 1;
+            statistics.block_bytes += block.size;
+            statistics.largest_free_range = statistics.largest_free_range
+                .max(block.size - end);
+        }
+        for placement in &inner.placements {
+            statistics.allocation_count += 1;
+            statistics.allocation_bytes += placement.size;
+        }
+        statistics
+    }
+
+    #[inline]
+    unsafe fn release_resources(&self) {
+        let mut inner = self.inner.lock();
+        inner.placements.clear();
+        inner.lifetime = (0, u32::MAX);
+    }
+}
@@ -46,3 +46,18 @@ impl From<MemoryProperties> for vk::MemoryPropertyFlags {
         Self::from_raw(value.as_raw())
     }
 }
+
+impl From<vk::MemoryPropertyFlags> for MemoryProperties {
+
+    /// Converts known memory property flags, ignoring the rest.
+    #[inline]
+    fn from(value: vk::MemoryPropertyFlags) -> Self {
+        let known =
+            Self::DEVICE_LOCAL |
+            Self::HOST_VISIBLE |
+            Self::HOST_COHERENT |
+            Self::HOST_CACHED |
+            Self::LAZILY_ALLOCATED;
+        Self::from_raw(value.as_raw() & known.as_raw())
+    }
+}
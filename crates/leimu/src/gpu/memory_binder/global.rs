@@ -1,11 +1,23 @@
 use nox_ash::vk;
 
-use crate::gpu::prelude::*;
+use crate::{
+    gpu::prelude::*,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, AtomicU64, Ordering},
+    },
+};
 
 use super::*;
 
 use MemoryBinderError::*;
 
+#[derive(Default)]
+struct Usage {
+    allocation_count: AtomicU32,
+    allocation_bytes: AtomicU64,
+}
+
 #[derive(Clone)]
 pub struct GlobalBinder {
     device: LogicalDevice,
@@ -13,6 +25,7 @@ pub struct GlobalBinder {
     suboptimal_memory_type_bits: u32,
     optimal_host_coherency: HostCoherency,
     suboptimal_host_coherency: HostCoherency,
+    usage: Arc<Usage>,
 }
 
 impl GlobalBinder {
@@ -69,6 +82,7 @@ impl GlobalBinder {
                 } else {
                     HostCoherency::None
                 },
+            usage: Default::default(),
         }
     }
 }
@@ -80,6 +94,7 @@ pub struct Memory {
     map: *mut u8,
     host_coherency: HostCoherency,
     is_optimal: bool,
+    usage: Arc<Usage>,
 }
 
 unsafe impl Send for Memory {}
@@ -159,6 +174,8 @@ impl Drop for Memory {
             self.device
                 .free_memory(self.memory, None);
         }
+        self.usage.allocation_count.fetch_sub(1, Ordering::Relaxed);
+        self.usage.allocation_bytes.fetch_sub(self.size, Ordering::Relaxed);
     }
 }
 
@@ -197,15 +214,17 @@ unsafe impl MemoryBinder for GlobalBinder {
             is_optimal = false;
         }
         let memory_type_index = memory_type_bits.trailing_zeros();
-        let allocate_info = vk::MemoryAllocateInfo {
+        let mut allocate_info = vk::MemoryAllocateInfo {
             s_type: vk::StructureType::MEMORY_ALLOCATE_INFO,
             allocation_size: memory_requirements.memory_requirements.size,
             memory_type_index,
             ..Default::default()
         };
         let memory = unsafe {
-            self.device.allocate_memory(&allocate_info, None)
+            self.device.allocate_memory(&mut allocate_info, None)
         }.context("failed to allocate device memory")?;
+        self.usage.allocation_count.fetch_add(1, Ordering::Relaxed);
+        self.usage.allocation_bytes.fetch_add(allocate_info.allocation_size, Ordering::Relaxed);
         Ok(DeviceMemoryObj::new(Memory {
             device: self.device.clone(),
             memory,
@@ -213,9 +232,22 @@ unsafe impl MemoryBinder for GlobalBinder {
             map: core::ptr::null_mut(),
             host_coherency,
             is_optimal,
+            usage: self.usage.clone(),
         }))
     }
 
     #[inline]
     unsafe fn release_resources(&self) {}
+
+    fn statistics(&self) -> MemoryBinderStatistics {
+        let allocation_count = self.usage.allocation_count.load(Ordering::Relaxed);
+        let allocation_bytes = self.usage.allocation_bytes.load(Ordering::Relaxed);
+        MemoryBinderStatistics {
+            block_count: allocation_count,
+            allocation_count,
+            block_bytes: allocation_bytes,
+            allocation_bytes,
+            largest_free_range: 0,
+        }
+    }
 }
@@ -4,7 +4,7 @@ use nox_mem::vec::Vec32;
 use nox_ash::vk::{self, ptr_chain_iter_const, TaggedStructure};
 
 use crate::{
-    sync::{*, atomic::{self, AtomicU64}},
+    sync::{*, atomic::{self, AtomicU32, AtomicU64}},
 };
 
 use super::*;
@@ -16,6 +16,7 @@ struct Allocation {
     device_memory: nox_ash::prelude::VkResult<vk::DeviceMemory>,
     mapped_pointer: Option<RwLock<*mut ()>>,
     used: AtomicU64,
+    allocation_count: AtomicU32,
 }
 
 impl Allocation {
@@ -112,7 +113,7 @@ impl Block {
     ) -> core::result::Result<Memory, MemoryBinderError>
     {
         let allocation = self.allocation.get_or_init(|| {
-            let allocate_info = vk::MemoryAllocateInfo {
+            let mut allocate_info = vk::MemoryAllocateInfo {
                 s_type: vk::StructureType::MEMORY_ALLOCATE_INFO,
                 allocation_size: block_size,
                 memory_type_index,
@@ -122,9 +123,10 @@ impl Block {
                 device: device.clone(),
                 mapped_pointer: (coherency != HostCoherency::None).then(|| RwLock::new(ptr::null_mut())),
                 device_memory: unsafe {
-                    device.allocate_memory(&allocate_info, None)
+                    device.allocate_memory(&mut allocate_info, None)
                 },
                 used: AtomicU64::new(0),
+                allocation_count: AtomicU32::new(0),
             })
         });
         allocation.device_memory?;
@@ -147,6 +149,7 @@ impl Block {
                 align: memory_requirements.memory_requirements.alignment,
             })
         }
+        allocation.allocation_count.fetch_add(1, atomic::Ordering::Relaxed);
         Ok(Memory::new(
             allocation.clone(),
             offset,
@@ -161,8 +164,25 @@ impl Block {
     unsafe fn reset(&self) {
         if let Some(allocation) = self.allocation.get() {
             allocation.used.store(0, atomic::Ordering::Release);
+            allocation.allocation_count.store(0, atomic::Ordering::Relaxed);
         }
     }
+
+    fn add_statistics(&self, block_size: DeviceSize, statistics: &mut MemoryBinderStatistics) {
+        let Some(allocation) = self.allocation.get() else {
+            return
+        };
+        if allocation.device_memory.is_err() {
+            return
+        }
+        let used = allocation.used.load(atomic::Ordering::Acquire);
+        statistics.block_count += 1;
+        statistics.allocation_count += allocation.allocation_count.load(atomic::Ordering::Relaxed);
+        statistics.block_bytes += block_size;
+        statistics.allocation_bytes += used;
+        statistics.largest_free_range = statistics.largest_free_range
+            .max(block_size.saturating_sub(used));
+    }
 }
 
 pub struct LinearBinder {
@@ -446,4 +466,18 @@ unsafe impl MemoryBinder for LinearBinder {
             self.reset();
         }
     }
+
+    fn statistics(&self) -> MemoryBinderStatistics {
+        let mut statistics = self.fallback.statistics();
+        for (blocks, _, _) in self.optimal_blocks
+            .load()
+            .iter()
+            .chain(self.suboptimal_blocks.load().iter())
+        {
+            for block in blocks.iter() {
+                block.add_statistics(self.block_size, &mut statistics);
+            }
+        }
+        statistics
+    }
 }
@@ -0,0 +1,833 @@
+use core::ptr;
+
+use nox_mem::vec::Vec32;
+use nox_ash::vk::{self, ptr_chain_iter_const, TaggedStructure};
+
+use crate::{
+    gpu::prelude::*,
+    sync::*,
+};
+
+use super::*;
+
+use MemoryBinderError::*;
+
+const SL_BITS: u32 = 4;
+const SL_COUNT: u32 = 1 << SL_BITS;
+const MIN_SIZE_LOG2: u32 = 8;
+const FL_COUNT: u32 = u64::BITS - MIN_SIZE_LOG2 + 1;
+const NONE: u32 = u32::MAX;
+
+/// Returns the first and second level indices of the size class containing `size`.
+#[inline(always)]
+fn mapping(size: DeviceSize) -> (u32, u32) {
+    if size < 1 << MIN_SIZE_LOG2 {
+        (0, (size >> (MIN_SIZE_LOG2 - SL_BITS)) as u32)
+    } else {
+        let log2 = u64::BITS - 1 - size.leading_zeros();
+        (log2 - MIN_SIZE_LOG2 + 1, (size >> (log2 - SL_BITS)) as u32 ^ SL_COUNT)
+    }
+}
+
+/// Returns the first size class, where every free node is at least `size` bytes.
+#[inline(always)]
+fn mapping_round_up(size: DeviceSize) -> (u32, u32) {
+    let round =
+        if size < 1 << MIN_SIZE_LOG2 {
+            (1 << (MIN_SIZE_LOG2 - SL_BITS)) - 1
+        } else {
+            let log2 = u64::BITS - 1 - size.leading_zeros();
+            (1 << (log2 - SL_BITS)) - 1
+        };
+    mapping(size.saturating_add(round))
+}
+
+#[derive(Clone, Copy)]
+struct Node {
+    offset: DeviceSize,
+    size: DeviceSize,
+    chunk: u32,
+    prev_physical: u32,
+    next_physical: u32,
+    prev_free: u32,
+    next_free: u32,
+    is_free: bool,
+}
+
+/// A two-level segregated fit allocator, which manages the ranges of multiple chunks.
+struct Tlsf {
+    nodes: Vec32<Node>,
+    unused_nodes: Vec32<u32>,
+    fl_bitmap: u64,
+    sl_bitmaps: [u32; FL_COUNT as usize],
+    heads: [[u32; SL_COUNT as usize]; FL_COUNT as usize],
+}
+
+impl Tlsf {
+
+    fn new() -> Self {
+        Self {
+            nodes: Default::default(),
+            unused_nodes: Default::default(),
+            fl_bitmap: 0,
+            sl_bitmaps: [0; FL_COUNT as usize],
+            heads: [[NONE; SL_COUNT as usize]; FL_COUNT as usize],
+        }
+    }
+
+    #[inline(always)]
+    fn node(&self, index: u32) -> &Node {
+        &self.nodes[index as usize]
+    }
+
+    #[inline(always)]
+    fn node_mut(&mut self, index: u32) -> &mut Node {
+        &mut self.nodes[index as usize]
+    }
+
+    fn new_node(&mut self, node: Node) -> u32 {
+        if let Some(index) = self.unused_nodes.pop() {
+            *self.node_mut(index) = node;
+            index
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+
+    fn insert_free(&mut self, index: u32) {
+        let (fl, sl) = mapping(self.node(index).size);
+        let head = self.heads[fl as usize][sl as usize];
+        let node = self.node_mut(index);
+        node.is_free = true;
+        node.prev_free = NONE;
+        node.next_free = head;
+        if head != NONE {
+            self.node_mut(head).prev_free = index;
+        }
+        self.heads[fl as usize][sl as usize] = index;
+        self.fl_bitmap |= 1 << fl;
+        self.sl_bitmaps[fl as usize] |= 1 << sl;
+    }
+
+    fn remove_free(&mut self, index: u32) {
+        let node = *self.node(index);
+        let (fl, sl) = mapping(node.size);
+        if node.prev_free != NONE {
+            self.node_mut(node.prev_free).next_free = node.next_free;
+        } else {
+            self.heads[fl as usize][sl as usize] = node.next_free;
+            if node.next_free == NONE {
+                self.sl_bitmaps[fl as usize] &= !(1 << sl);
+                if self.sl_bitmaps[fl as usize] == 0 {
+                    self.fl_bitmap &= !(1 << fl);
+                }
+            }
+        }
+        if node.next_free != NONE {
+            self.node_mut(node.next_free).prev_free = node.prev_free;
+        }
+        self.node_mut(index).is_free = false;
+    }
+
+    /// Adds a new chunk of `size` bytes and returns the node covering it.
+    fn add_chunk(&mut self, chunk: u32, size: DeviceSize) -> u32 {
+        let index = self.new_node(Node {
+            offset: 0,
+            size,
+            chunk,
+            prev_physical: NONE,
+            next_physical: NONE,
+            prev_free: NONE,
+            next_free: NONE,
+            is_free: false,
+        });
+        self.insert_free(index);
+        index
+    }
+
+    /// Returns the size of the largest free node.
+    fn largest_free(&self) -> DeviceSize {
+        if self.fl_bitmap == 0 {
+            return 0
+        }
+        let fl = u64::BITS - 1 - self.fl_bitmap.leading_zeros();
+        let sl = u32::BITS - 1 - self.sl_bitmaps[fl as usize].leading_zeros();
+        let mut largest = 0;
+        let mut index = self.heads[fl as usize][sl as usize];
+        while index != NONE {
+            let node = self.node(index);
+            largest = largest.max(node.size);
+            index = node.next_free;
+        }
+        largest
+    }
+
+    /// Removes a chunk, which is entirely covered by the free node at `index`.
+    fn remove_chunk(&mut self, index: u32) {
+        self.remove_free(index);
+        self.unused_nodes.push(index);
+    }
+
+    /// Allocates a node of `size` bytes aligned to `align`, skipping nodes in chunk `exclude`.
+    fn alloc(
+        &mut self,
+        size: DeviceSize,
+        align: DeviceSize,
+        exclude: Option<u32>,
+    ) -> Option<u32>
+    {
+        let (mut fl, sl) = mapping_round_up(size + align - 1);
+        if fl >= FL_COUNT {
+            return None
+        }
+        let mut sl_map = self.sl_bitmaps[fl as usize] & (!0 << sl);
+        loop {
+            if sl_map == 0 {
+                let fl_map = self.fl_bitmap & (!0u64).checked_shl(fl + 1).unwrap_or(0);
+                if fl_map == 0 {
+                    return None
+                }
+                fl = fl_map.trailing_zeros();
+                sl_map = self.sl_bitmaps[fl as usize];
+            }
+            let sl = sl_map.trailing_zeros();
+            sl_map &= !(1 << sl);
+            let mut index = self.heads[fl as usize][sl as usize];
+            while index != NONE {
+                let node = self.node(index);
+                if Some(node.chunk) != exclude {
+                    return Some(self.split(index, size, align))
+                }
+                index = node.next_free;
+            }
+        }
+    }
+
+    /// Takes the free node at `index` into use, returning its unused front and back to the free
+    /// lists.
+    ///
+    /// The physical neighbours of a free node are never free, so no merging is needed.
+    fn split(&mut self, index: u32, size: DeviceSize, align: DeviceSize) -> u32 {
+        self.remove_free(index);
+        let node = *self.node(index);
+        let offset = node.offset.next_multiple_of(align);
+        let padding = offset - node.offset;
+        if padding != 0 {
+            let front = self.new_node(Node {
+                offset: node.offset,
+                size: padding,
+                chunk: node.chunk,
+                prev_physical: node.prev_physical,
+                next_physical: index,
+                prev_free: NONE,
+                next_free: NONE,
+                is_free: false,
+            });
+            if node.prev_physical != NONE {
+                self.node_mut(node.prev_physical).next_physical = front;
+            }
+            let node = self.node_mut(index);
+            node.prev_physical = front;
+            node.offset = offset;
+            node.size -= padding;
+            self.insert_free(front);
+        }
+        let remaining = self.node(index).size - size;
+        if remaining != 0 {
+            let next_physical = self.node(index).next_physical;
+            let back = self.new_node(Node {
+                offset: offset + size,
+                size: remaining,
+                chunk: node.chunk,
+                prev_physical: index,
+                next_physical,
+                prev_free: NONE,
+                next_free: NONE,
+                is_free: false,
+            });
+            if next_physical != NONE {
+                self.node_mut(next_physical).prev_physical = back;
+            }
+            let node = self.node_mut(index);
+            node.next_physical = back;
+            node.size = size;
+            self.insert_free(back);
+        }
+        index
+    }
+
+    /// Frees the node at `index`, merging it with its free physical neighbours.
+    ///
+    /// Returns the index of the resulting free node.
+    fn free(&mut self, mut index: u32) -> u32 {
+        let prev = self.node(index).prev_physical;
+        if prev != NONE && self.node(prev).is_free {
+            self.remove_free(prev);
+            let node = *self.node(index);
+            let prev_node = self.node_mut(prev);
+            prev_node.size += node.size;
+            prev_node.next_physical = node.next_physical;
+            if node.next_physical != NONE {
+                self.node_mut(node.next_physical).prev_physical = prev;
+            }
+            self.unused_nodes.push(index);
+            index = prev;
+        }
+        let next = self.node(index).next_physical;
+        if next != NONE && self.node(next).is_free {
+            self.remove_free(next);
+            let next_node = *self.node(next);
+            let node = self.node_mut(index);
+            node.size += next_node.size;
+            node.next_physical = next_node.next_physical;
+            if next_node.next_physical != NONE {
+                self.node_mut(next_node.next_physical).prev_physical = index;
+            }
+            self.unused_nodes.push(next);
+        }
+        self.insert_free(index);
+        index
+    }
+}
+
+struct Chunk {
+    device: LogicalDevice,
+    memory: vk::DeviceMemory,
+    size: DeviceSize,
+    mapped_pointer: Option<RwLock<*mut ()>>,
+}
+
+impl Chunk {
+
+    #[inline(always)]
+    fn map(&self) -> Result<*mut u8> {
+        let Some(ptr) = &self.mapped_pointer else {
+            return Err(Error::just_context(UnmappableMemory))
+        };
+        let mut ptr = ptr.upgradable_read();
+        if ptr.is_null() &&
+            let Some(err) = ptr.with_upgraded(|ptr| unsafe
+            {
+                match self.device.map_memory(
+                    self.memory,
+                    0, vk::WHOLE_SIZE,
+                    vk::MemoryMapFlags::empty()
+                ) {
+                    Ok(p) => { *ptr = p; None },
+                    Err(err) => Some(err)
+                }
+            })
+        {
+            return Err(Error::new(err, "failed to map memory"))
+        }
+        Ok(ptr.cast())
+    }
+
+    #[inline(always)]
+    fn unmap(&self) -> Result<()> {
+        let Some(ptr) = &self.mapped_pointer else {
+            return Err(Error::just_context(UnmappableMemory))
+        };
+        let mut guard = ptr.write();
+        if guard.is_null() {
+            return Err(Error::just_context(
+                "memory is not mapped"
+            ))
+        }
+        unsafe {
+            self.device
+                .unmap_memory(self.memory);
+        }
+        *guard = ptr::null_mut();
+        Ok(())
+    }
+}
+
+impl Drop for Chunk {
+
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .free_memory(self.memory, None);
+        }
+    }
+}
+
+unsafe impl Send for Chunk {}
+unsafe impl Sync for Chunk {}
+
+struct ChunkSlot {
+    chunk: Arc<Chunk>,
+    used: DeviceSize,
+    allocations: u32,
+}
+
+struct TypePool {
+    chunks: Vec32<Option<ChunkSlot>>,
+    tlsf: Tlsf,
+}
+
+impl TypePool {
+
+    #[inline(always)]
+    fn chunk_count(&self) -> u32 {
+        self.chunks.iter().filter(|slot| slot.is_some()).count() as u32
+    }
+
+    fn free(&mut self, chunk: u32, node: u32, size: DeviceSize) {
+        let chunk_count = self.chunk_count();
+        let Some(slot) = &mut self.chunks[chunk as usize] else {
+            return
+        };
+        slot.used -= size;
+        slot.allocations -= 1;
+        let node = self.tlsf.free(node);
+        if slot.allocations == 0 && chunk_count > 1 {
+            self.tlsf.remove_chunk(node);
+            self.chunks[chunk as usize] = None;
+        }
+    }
+}
+
+struct Inner {
+    types: Vec32<TypePool>,
+}
+
+/// Identifies a block of a [`PoolBinder`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ChunkId {
+    memory_type_index: u32,
+    chunk_index: u32,
+}
+
+struct Memory {
+    inner: Arc<Mutex<Inner>>,
+    chunk: Arc<Chunk>,
+    memory_type_index: u32,
+    chunk_index: u32,
+    node: u32,
+    offset: DeviceSize,
+    size: DeviceSize,
+    coherency: HostCoherency,
+    is_optimal: bool,
+}
+
+unsafe impl DeviceMemory for Memory {
+
+    fn handle(&self) -> u64 {
+        <_ as vk::Handle>::as_raw(self.chunk.memory)
+    }
+
+    fn memory_size(&self) -> u64 {
+        self.chunk.size
+    }
+
+    fn offset(&self) -> DeviceSize {
+        self.offset
+    }
+
+    fn size(&self) -> DeviceSize {
+        self.size
+    }
+
+    fn map_memory(&mut self) -> Result<MemoryMap> {
+        self.chunk
+            .map()
+            .map(|ptr| unsafe {
+                MemoryMap {
+                    map: ptr.add(self.offset as usize),
+                    size: self.size as usize,
+                    is_coherent: self.coherency == HostCoherency::Coherent,
+                }
+            })
+    }
+
+    fn unmap_memory(&mut self) -> Result<()> {
+        self.chunk.unmap()
+    }
+
+    fn is_optimal(&self) -> bool {
+        self.is_optimal
+    }
+
+    fn is_mapped(&self) -> bool {
+        let Some(ptr) = &self.chunk.mapped_pointer else {
+            return false
+        };
+        !ptr.read().is_null()
+    }
+}
+
+impl Drop for Memory {
+
+    fn drop(&mut self) {
+        self.inner
+            .lock()
+            .types[self.memory_type_index as usize]
+            .free(self.chunk_index, self.node, self.size);
+    }
+}
+
+/// A general purpose [`MemoryBinder`], which sub-allocates [`device memory`][1] blocks with a
+/// two-level segregated fit allocator per memory type.
+///
+/// Unlike [`LinearBinder`], allocations are returned to the pool when the resource using them is
+/// destroyed. Empty blocks are freed, except for the last block of each memory type.
+///
+/// Allocations larger than the block size and allocations, which prefer a
+/// [`dedicated allocation`][2], use a [`GlobalBinder`].
+///
+/// Live buffers *can* be moved out of sparsely used blocks with
+/// [`CopyCommands::defragment_buffers`].
+///
+/// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+/// [2]: https://docs.vulkan.org/refpages/latest/refpages/source/VkMemoryDedicatedRequirements.html
+pub struct PoolBinder {
+    device: LogicalDevice,
+    optimal_memory_type_bits: u32,
+    suboptimal_memory_type_bits: u32,
+    block_size: DeviceSize,
+    fallback: GlobalBinder,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl PoolBinder {
+
+    /// Creates a new pool binder, which allocates [`device memory`][1] in blocks of `block_size`
+    /// bytes.
+    ///
+    /// # Parameters
+    /// - `optimal`: optimal memory properties
+    /// - `suboptimal`: suboptimal memory properties
+    ///
+    /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDeviceMemory.html
+    pub fn new(
+        device: LogicalDevice,
+        block_size: DeviceSize,
+        optimal_properties: MemoryProperties,
+        suboptimal_properties: MemoryProperties,
+    ) -> Self
+    {
+        let optimal = optimal_properties.into();
+        let suboptimal = suboptimal_properties.into();
+        let memory_properties = device.physical_device().memory_properties();
+        let memory_types = &memory_properties.memory_types[..memory_properties.memory_type_count as usize];
+        let mut optimal_memory_type_bits = 0;
+        let mut suboptimal_memory_type_bits = 0;
+        for (i, memory_type) in memory_types.iter().enumerate() {
+            if memory_type.property_flags & optimal == optimal {
+                optimal_memory_type_bits |= 1 << i;
+            }
+            if memory_type.property_flags.contains(suboptimal) {
+                suboptimal_memory_type_bits |= 1 << i;
+            }
+        }
+        let types = Vec32::with_len_with(memory_types.len() as u32, |_| TypePool {
+            chunks: Default::default(),
+            tlsf: Tlsf::new(),
+        });
+        Self {
+            fallback: GlobalBinder::new(device.clone(), optimal_properties, suboptimal_properties),
+            device,
+            optimal_memory_type_bits,
+            suboptimal_memory_type_bits,
+            block_size,
+            inner: Arc::new(Mutex::new(Inner {
+                types,
+            })),
+        }
+    }
+
+    #[inline(always)]
+    pub fn block_size(&self) -> DeviceSize {
+        self.block_size
+    }
+
+    /// Returns the block of `memory`, if `memory` was allocated by this binder, its block is the
+    /// least used block of its memory type and the other blocks have enough free space to hold
+    /// it.
+    ///
+    /// The destination of the move *should* then be allocated with [`excluding`][1].
+    ///
+    /// [1]: Self::excluding
+    pub(crate) fn movable_chunk(&self, memory: &DeviceMemoryObj) -> Option<ChunkId> {
+        let handle = memory.handle();
+        let inner = self.inner.lock();
+        for (memory_type_index, pool) in inner.types.iter().enumerate() {
+            let Some(chunk_index) = pool.chunks
+                .iter()
+                .position(|slot| slot.as_ref().is_some_and(|slot|
+                    <_ as vk::Handle>::as_raw(slot.chunk.memory) == handle
+                )) else {
+                continue
+            };
+            let used = pool.chunks[chunk_index].as_ref().unwrap().used;
+            let mut least_used = true;
+            let mut free_elsewhere = 0;
+            for (i, slot) in pool.chunks.iter().enumerate() {
+                let Some(slot) = slot else {
+                    continue
+                };
+                if i != chunk_index {
+                    least_used &= used <= slot.used;
+                    free_elsewhere += slot.chunk.size - slot.used;
+                }
+            }
+            return (least_used && free_elsewhere >= memory.size()).then_some(ChunkId {
+                memory_type_index: memory_type_index as u32,
+                chunk_index: chunk_index as u32,
+            })
+        }
+        None
+    }
+
+    /// Returns a [`MemoryBinder`], which allocates from this binder without ever using the block
+    /// `chunk` or allocating new blocks.
+    #[inline(always)]
+    pub(crate) fn excluding(&self, chunk: ChunkId) -> ExcludingBinder<'_> {
+        ExcludingBinder {
+            binder: self,
+            chunk,
+        }
+    }
+
+    fn alloc_block(
+        &self,
+        inner: &mut Inner,
+        memory_type_index: u32,
+        coherency: HostCoherency,
+    ) -> Result<()>
+    {
+        let mut allocate_info = vk::MemoryAllocateInfo {
+            s_type: vk::StructureType::MEMORY_ALLOCATE_INFO,
+            allocation_size: self.block_size,
+            memory_type_index,
+            ..Default::default()
+        };
+        let memory = unsafe {
+            self.device.allocate_memory(&mut allocate_info, None)
+        }.context("failed to allocate device memory")?;
+        let chunk = Arc::new(Chunk {
+            device: self.device.clone(),
+            memory,
+            size: self.block_size,
+            mapped_pointer: (coherency != HostCoherency::None).then(|| RwLock::new(ptr::null_mut())),
+        });
+        let pool = &mut inner.types[memory_type_index as usize];
+        let slot = ChunkSlot {
+            chunk,
+            used: 0,
+            allocations: 0,
+        };
+        let chunk_index =
+            if let Some(index) = pool.chunks.iter().position(|slot| slot.is_none()) {
+                pool.chunks[index] = Some(slot);
+                index as u32
+            } else {
+                pool.chunks.push(Some(slot));
+                pool.chunks.len() - 1
+            };
+        pool.tlsf.add_chunk(chunk_index, self.block_size);
+        Ok(())
+    }
+
+    /// Allocates like [`MemoryBinder::alloc`], but never uses the block `exclude` and never
+    /// allocates new blocks if `exclude` is [`Some`].
+    unsafe fn alloc_excluding(
+        &self,
+        memory_requirements: &vk::MemoryRequirements2,
+        exclude: Option<ChunkId>,
+    ) -> Result<DeviceMemoryObj> {
+        let requirements = memory_requirements.memory_requirements;
+        if requirements.size == 0 {
+            return Err(Error::just_context(ZeroSizeAlloc))
+        }
+        unsafe {
+            if let Some(dedicated_requirements) = ptr_chain_iter_const(memory_requirements)
+                .find(|ptr| (**ptr).s_type == vk::MemoryDedicatedRequirements::STRUCTURE_TYPE)
+            {
+                let dedicated_requirements = dedicated_requirements
+                    .cast::<vk::MemoryDedicatedRequirements>()
+                    .as_ref().unwrap();
+                if dedicated_requirements.prefers_dedicated_allocation != 0 {
+                    return self.fallback.alloc(memory_requirements)
+                }
+            }
+        }
+        if self.block_size < requirements.size {
+            return unsafe {
+                self.fallback.alloc(memory_requirements)
+            }
+        }
+        let mut is_optimal = true;
+        let mut coherency = self.optimal_host_coherency();
+        let mut memory_type_bits = self.optimal_memory_type_bits & requirements.memory_type_bits;
+        if memory_type_bits == 0 {
+            memory_type_bits = self.suboptimal_memory_type_bits & requirements.memory_type_bits;
+            if memory_type_bits == 0 {
+                return Err(Error::just_context(IncompatibleMemoryRequirements))
+            }
+            coherency = self.suboptimal_host_coherency();
+            is_optimal = false;
+        }
+        let granularity = self.device
+            .physical_device()
+            .limits().buffer_image_granularity;
+        let align = requirements.alignment.max(granularity).max(1);
+        let mut inner = self.inner.lock();
+        let mut bits = memory_type_bits;
+        while bits != 0 {
+            let memory_type_index = bits.trailing_zeros();
+            bits &= bits - 1;
+            if let Some(memory) = self.try_alloc(
+                &mut inner, memory_type_index,
+                requirements.size, align,
+                coherency, is_optimal,
+                exclude,
+            ) {
+                return Ok(DeviceMemoryObj::new(memory))
+            }
+        }
+        if exclude.is_some() {
+            return Err(Error::just_context(OutOfDeviceMemory {
+                size: requirements.size,
+                align: requirements.alignment,
+            }))
+        }
+        let memory_type_index = memory_type_bits.trailing_zeros();
+        self.alloc_block(&mut inner, memory_type_index, coherency)?;
+        self.try_alloc(
+            &mut inner, memory_type_index,
+            requirements.size, align,
+            coherency, is_optimal,
+            None,
+        ).map(DeviceMemoryObj::new)
+        .ok_or_else(|| Error::just_context(OutOfDeviceMemory {
+            size: requirements.size,
+            align: requirements.alignment,
+        }))
+    }
+
+    fn try_alloc(
+        &self,
+        inner: &mut Inner,
+        memory_type_index: u32,
+        size: DeviceSize,
+        align: DeviceSize,
+        coherency: HostCoherency,
+        is_optimal: bool,
+        exclude: Option<ChunkId>,
+    ) -> Option<Memory>
+    {
+        let exclude = exclude
+            .and_then(|id| (id.memory_type_index == memory_type_index).then_some(id.chunk_index));
+        let pool = &mut inner.types[memory_type_index as usize];
+        let node = pool.tlsf.alloc(size, align, exclude)?;
+        let &Node { offset, chunk, .. } = pool.tlsf.node(node);
+        let slot = pool.chunks[chunk as usize].as_mut().unwrap();
+        slot.used += size;
+        slot.allocations += 1;
+        Some(Memory {
+            inner: self.inner.clone(),
+            chunk: slot.chunk.clone(),
+            memory_type_index,
+            chunk_index: chunk,
+            node,
+            offset,
+            size,
+            coherency,
+            is_optimal,
+        })
+    }
+}
+
+unsafe impl MemoryBinder for PoolBinder {
+
+    #[inline]
+    fn max_alloc_size(&self) -> vk::DeviceSize {
+        self.device.max_memory_allocation_size()
+    }
+
+    #[inline]
+    fn optimal_host_coherency(&self) -> HostCoherency {
+        self.fallback.optimal_host_coherency()
+    }
+
+    #[inline]
+    fn suboptimal_host_coherency(&self) -> HostCoherency {
+        self.fallback.suboptimal_host_coherency()
+    }
+
+    #[inline]
+    unsafe fn alloc(
+        &self,
+        memory_requirements: &vk::MemoryRequirements2,
+    ) -> Result<DeviceMemoryObj> {
+        unsafe {
+            self.alloc_excluding(memory_requirements, None)
+        }
+    }
+
+    /// Allocations are returned to the pool individually, so this does nothing.
+    #[inline]
+    unsafe fn release_resources(&self) {}
+
+    fn statistics(&self) -> MemoryBinderStatistics {
+        let mut statistics = self.fallback.statistics();
+        let inner = self.inner.lock();
+        for pool in inner.types.iter() {
+            for slot in pool.chunks.iter().flatten() {
+                statistics.block_count += 1;
+                statistics.allocation_count += slot.allocations;
+                statistics.block_bytes += slot.chunk.size;
+                statistics.allocation_bytes += slot.used;
+            }
+            statistics.largest_free_range = statistics.largest_free_range
+                .max(pool.tlsf.largest_free());
+        }
+        statistics
+    }
+}
+
+/// A [`MemoryBinder`] returned by [`PoolBinder::excluding`].
+pub(crate) struct ExcludingBinder<'a> {
+    binder: &'a PoolBinder,
+    chunk: ChunkId,
+}
+
+unsafe impl MemoryBinder for ExcludingBinder<'_> {
+
+    #[inline]
+    fn max_alloc_size(&self) -> vk::DeviceSize {
+        self.binder.max_alloc_size()
+    }
+
+    #[inline]
+    fn optimal_host_coherency(&self) -> HostCoherency {
+        self.binder.optimal_host_coherency()
+    }
+
+    #[inline]
+    fn suboptimal_host_coherency(&self) -> HostCoherency {
+        self.binder.suboptimal_host_coherency()
+    }
+
+    #[inline]
+    unsafe fn alloc(
+        &self,
+        memory_requirements: &vk::MemoryRequirements2,
+    ) -> Result<DeviceMemoryObj> {
+        unsafe {
+            self.binder.alloc_excluding(memory_requirements, Some(self.chunk))
+        }
+    }
+
+    #[inline]
+    unsafe fn release_resources(&self) {}
+
+    #[inline]
+    fn statistics(&self) -> MemoryBinderStatistics {
+        self.binder.statistics()
+    }
+}
@@ -18,6 +18,10 @@ pub struct PhysicalDevice {
     handle: vk::PhysicalDevice,
     api_version: Version,
     driver_version: Version,
+    raw_driver_version: u32,
+    vendor_id: u32,
+    device_id: u32,
+    pipeline_cache_uuid: [u8; vk::UUID_SIZE],
     device_type: PhysicalDeviceType,
     limits: vk::PhysicalDeviceLimits,
     memory_properties: vk::PhysicalDeviceMemoryProperties,
@@ -45,6 +49,10 @@ impl PhysicalDevice {
             handle,
             api_version: Version::from_u32(properties.api_version),
             driver_version: Version::from_u32(properties.driver_version),
+            raw_driver_version: properties.driver_version,
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            pipeline_cache_uuid: properties.pipeline_cache_uuid,
             device_type: properties.device_type.into(),
             limits: properties.limits,
             memory_properties,
@@ -68,6 +76,35 @@ impl PhysicalDevice {
         self.driver_version
     }
 
+    /// Returns the driver version as reported by the implementation.
+    ///
+    /// Unlike [`driver_version`][1], this preserves vendor-specific version encodings.
+    ///
+    /// [1]: Self::driver_version
+    #[inline(always)]
+    pub fn raw_driver_version(&self) -> u32 {
+        self.raw_driver_version
+    }
+
+    #[inline(always)]
+    pub fn vendor_id(&self) -> u32 {
+        self.vendor_id
+    }
+
+    #[inline(always)]
+    pub fn device_id(&self) -> u32 {
+        self.device_id
+    }
+
+    /// Returns the UUID identifying pipeline cache compatibility of this device.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/VkPhysicalDeviceProperties.html>
+    #[inline(always)]
+    pub fn pipeline_cache_uuid(&self) -> [u8; vk::UUID_SIZE] {
+        self.pipeline_cache_uuid
+    }
+
     #[inline(always)]
     pub fn device_type(&self) -> PhysicalDeviceType {
         self.device_type
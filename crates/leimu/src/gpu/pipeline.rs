@@ -2,11 +2,13 @@ pub mod vertex_input;
 mod handle;
 mod graphics;
 mod compute;
+mod ray_tracing;
 mod batch;
 mod cache;
 
 pub use graphics::*;
 pub use compute::*;
+pub use ray_tracing::*;
 pub use batch::*;
 pub use cache::PipelineCache;
 
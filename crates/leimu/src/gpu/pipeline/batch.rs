@@ -21,6 +21,8 @@ use crate::{
     log,
 };
 
+use super::ray_tracing::create_ray_tracing_pipelines;
+
 /// An identifier for a pipeline batch.
 ///
 /// This *can* be used to [`destroy an entire pipeline batch`][1] at once.
@@ -92,27 +94,191 @@ impl ComputePipelineId {
     }
 }
 
+/// An identifier for a [`ray tracing pipeline`][1].
+///
+/// [1]: RayTracingPipeline
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+#[display("(batch id: {0}, pipeline index: {1})")]
+pub struct RayTracingPipelineId(PipelineBatchId, u32);
+
+impl RayTracingPipelineId {
+   
+    /// Gets the batch portion of the id.
+    #[inline(always)]
+    pub fn batch_id(self) -> PipelineBatchId {
+        self.0
+    }
+
+    /// Gets the pipeline portion of the id.
+    #[inline(always)]
+    pub fn pipeline_id(self) -> u32 {
+        self.1
+    }
+}
+
 pub(crate) struct PipelineBatchInner {
     id: PipelineBatchId,
     graphics_pipelines: SwapLock<Vec32<Option<GraphicsPipeline>>>,
     compute_pipelines: SwapLock<Vec32<Option<ComputePipeline>>>,
+    ray_tracing_pipelines: SwapLock<Vec32<Option<RayTracingPipeline>>>,
+    graphics_create_infos: Vec32<GraphicsPipelineCreateTemplate>,
+    compute_create_infos: Vec32<ComputePipelineCreateTemplate>,
+    ray_tracing_create_infos: Vec32<RayTracingPipelineCreateTemplate>,
+    cache: Option<PipelineCache>,
 }
 
 impl PipelineBatchInner {
 
     fn new(
         id: PipelineBatchId,
-        graphics_pipelines: Vec32<Option<GraphicsPipeline>>,
-        compute_pipelines: Vec32<Option<ComputePipeline>>
+        graphics: (Vec32<GraphicsPipelineCreateTemplate>, Vec32<GraphicsPipeline>),
+        compute: (Vec32<ComputePipelineCreateTemplate>, Vec32<ComputePipeline>),
+        ray_tracing: (Vec32<RayTracingPipelineCreateTemplate>, Vec32<RayTracingPipeline>),
+        cache: Option<PipelineCache>,
     ) -> Self {
         Self {
             id,
-            graphics_pipelines: SwapLock::new(graphics_pipelines),
-            compute_pipelines: SwapLock::new(compute_pipelines),
+            graphics_pipelines: SwapLock::new(graphics.1.into_iter().map(Some).collect()),
+            compute_pipelines: SwapLock::new(compute.1.into_iter().map(Some).collect()),
+            ray_tracing_pipelines: SwapLock::new(ray_tracing.1.into_iter().map(Some).collect()),
+            graphics_create_infos: graphics.0,
+            compute_create_infos: compute.0,
+            ray_tracing_create_infos: ray_tracing.0,
+            cache,
         }
     }
 }
 
+/// Pipelines recreated with [`PipelineBatch::rebuild_shader_set_pipelines`], which haven't yet
+/// replaced the old pipelines.
+pub(crate) struct PipelineRebuild {
+    graphics: (Vec32<usize>, Vec32<GraphicsPipeline>),
+    compute: (Vec32<usize>, Vec32<ComputePipeline>),
+    ray_tracing: (Vec32<usize>, Vec32<RayTracingPipeline>),
+}
+
+async fn create_graphics_pipelines(
+    gpu: &Gpu,
+    cache: Option<&PipelineCache>,
+    reloaded: Option<&ShaderSet>,
+    create_infos: &[GraphicsPipelineCreateTemplate],
+) -> Result<Vec32<GraphicsPipeline>>
+{
+    let tmp_alloc = gpu.tmp_alloc();
+    let pipeline_count = create_infos.len() as u32;
+    if pipeline_count == 0 {
+        return Ok(Default::default())
+    }
+    let mut prepared_create_infos = FixedVec32
+        ::with_capacity(pipeline_count, &tmp_alloc)
+        .context("alloc failed")?;
+    for info in create_infos {
+        prepared_create_infos.push(info.prepare(gpu, reloaded, &tmp_alloc)
+            .await
+            .context("failed to convert graphics pipeline info")?
+        );
+    }
+    let mut vk_infos = FixedVec32
+        ::with_capacity(pipeline_count, &tmp_alloc)
+        .context("alloc failed")?;
+    for (info, _) in &mut prepared_create_infos {
+        let mut vk_info = info.as_create_info();
+        info.rendering_info.p_next = &info.robustness_info as *const _ as *const core::ffi::c_void;
+        vk_info.p_next = &info.rendering_info as *const _ as *const core::ffi::c_void;
+        vk_infos.push(vk_info);
+    }
+    let mut pipelines = FixedVec32
+        ::with_len(pipeline_count, vk::Pipeline::null(), &tmp_alloc)
+        .context("alloc failed")?;
+    unsafe {
+        let device = gpu.device();
+        let pipeline_cache = cache
+            .map(|cache| cache.handle().into_inner())
+            .unwrap_or(vk::PipelineCache::null());
+        device.create_graphics_pipelines(
+            pipeline_cache,
+            &vk_infos,
+            None,
+            &mut pipelines,
+        ).context_with(|| "failed to create graphics pipelines")?;
+    }
+    let graphics_pipelines: Vec32<_> = pipelines
+        .iter()
+        .copied()
+        .enumerate()
+        .map(|(i, handle)| unsafe {
+            GraphicsPipeline::new(
+                gpu.device().clone(),
+                handle,
+                prepared_create_infos[i].1.clone(),
+                &create_infos[i]
+            )
+        }).collect();
+    unsafe {
+        tmp_alloc.clear();
+    }
+    Ok(graphics_pipelines)
+}
+
+async fn create_compute_pipelines(
+    gpu: &Gpu,
+    cache: Option<&PipelineCache>,
+    reloaded: Option<&ShaderSet>,
+    create_infos: &mut [ComputePipelineCreateTemplate],
+) -> Result<Vec32<ComputePipeline>>
+{
+    let tmp_alloc = gpu.tmp_alloc();
+    let pipeline_count = create_infos.len() as u32;
+    if pipeline_count == 0 {
+        return Ok(Default::default())
+    }
+    let mut vk_infos = FixedVec32
+        ::with_capacity(pipeline_count, &tmp_alloc)
+        .context("alloc failed")?;
+    let mut shader_sets = FixedVec32
+        ::with_capacity(pipeline_count, &tmp_alloc)
+        .context("alloc failed")?;
+    for info in create_infos {
+        let (vk_info, shader_set) = info
+            .prepare(gpu, reloaded)
+            .await
+            .context("failed to convert compute pipeline info")?;
+        shader_sets.push(shader_set);
+        vk_infos.push(vk_info);
+    }
+    let mut pipelines = FixedVec32
+        ::with_len(vk_infos.len(), vk::Pipeline::null(), &tmp_alloc)
+        .context("alloc failed")?;
+    unsafe {
+        let device = gpu.device();
+        let pipeline_cache = cache
+            .map(|cache| cache.handle().into_inner())
+            .unwrap_or(vk::PipelineCache::null());
+        device.create_compute_pipelines(
+            pipeline_cache,
+            &vk_infos,
+            None,
+            &mut pipelines
+        ).context("failed to create compute pipelines")?;
+    };
+    let compute_pipelines: Vec32<_> =
+        shader_sets
+            .into_iter()
+            .enumerate()
+            .map(|(i, shader_set)| {
+                unsafe { ComputePipeline::new(
+                    gpu.device().clone(),
+                    pipelines[i],
+                    shader_set,
+                    vk_infos[i].flags.contains(vk::PipelineCreateFlags::DISPATCH_BASE),
+                )}
+            }).collect();
+    unsafe {
+        tmp_alloc.clear();
+    }
+    Ok(compute_pipelines)
+}
+
 /// Contains the handle of a pipeline batch, which contains the pipelines and metadata about the
 /// batch.
 ///
@@ -166,6 +332,118 @@ impl PipelineBatch {
             
     }
 
+    #[inline(always)]
+    pub(crate) async fn get_ray_tracing_pipeline<'a>(
+        &self,
+        idx: u32,
+    ) -> Result<impl Deref<Target = RayTracingPipeline> + use<'a>>
+    {
+        self.inner
+            .load().await?.ray_tracing_pipelines
+            .load()
+            .try_map(|pipelines| {
+                pipelines
+                    .get(idx as usize)
+                    .ok_or_else(|| Error::just_context("invalid id"))?
+                    .as_ref()
+                    .ok_or_else(|| Error::just_context("ray tracing pipeline is destroyed"))
+            })
+    }
+
+    /// Recreates each live pipeline of this batch, which was created from shader set
+    /// `shader_set_id`, with the `reloaded` shader set.
+    ///
+    /// The reloaded shader set doesn't need to be committed to the shader cache yet, so that the
+    /// reload *can* be dropped if rebuilding fails.
+    ///
+    /// The old pipelines stay in use until the rebuild is [`finished`][1]. Dropping the rebuild
+    /// instead destroys the new pipelines.
+    ///
+    /// [1]: Self::finish_rebuild
+    pub(crate) async fn rebuild_shader_set_pipelines(
+        &self,
+        gpu: &Gpu,
+        shader_set_id: ShaderSetId,
+        reloaded: &ShaderSet,
+    ) -> Result<PipelineRebuild>
+    {
+        let inner = self.inner.load().await?;
+        let live = inner.graphics_pipelines.load();
+        let (graphics_indices, create_infos): (Vec32<_>, Vec32<_>) = inner.graphics_create_infos
+            .iter()
+            .enumerate()
+            .filter(|&(i, info)|
+                info.shader_set_id == shader_set_id && live[i].is_some()
+            ).map(|(i, info)| (i, info.clone()))
+            .unzip();
+        drop(live);
+        let graphics = create_graphics_pipelines(
+            gpu, inner.cache.as_ref(), Some(reloaded), &create_infos,
+        ).await?;
+        let live = inner.compute_pipelines.load();
+        let (compute_indices, mut create_infos): (Vec32<_>, Vec32<_>) = inner.compute_create_infos
+            .iter()
+            .enumerate()
+            .filter(|&(i, info)|
+                info.shader_set_id == shader_set_id && live[i].is_some()
+            ).map(|(i, info)| (i, info.clone()))
+            .unzip();
+        drop(live);
+        let compute = create_compute_pipelines(
+            gpu, inner.cache.as_ref(), Some(reloaded), &mut create_infos,
+        ).await?;
+        let live = inner.ray_tracing_pipelines.load();
+        let (ray_tracing_indices, create_infos): (Vec32<_>, Vec32<_>) = inner.ray_tracing_create_infos
+            .iter()
+            .enumerate()
+            .filter(|&(i, info)|
+                info.shader_set_id == shader_set_id && live[i].is_some()
+            ).map(|(i, info)| (i, info.clone()))
+            .unzip();
+        drop(live);
+        let ray_tracing = create_ray_tracing_pipelines(
+            gpu, inner.cache.as_ref(), Some(reloaded), &create_infos,
+        ).await?;
+        Ok(PipelineRebuild {
+            graphics: (graphics_indices, graphics),
+            compute: (compute_indices, compute),
+            ray_tracing: (ray_tracing_indices, ray_tracing),
+        })
+    }
+
+    /// Replaces the old pipelines of this batch with rebuilt ones.
+    ///
+    /// The old pipelines are dropped from the batch, but command buffers, which still use them,
+    /// keep them alive until they have finished executing.
+    pub(crate) async fn finish_rebuild(&self, rebuild: PipelineRebuild) -> Result<()> {
+        let inner = self.inner.load().await?;
+        let (indices, pipelines) = rebuild.graphics;
+        inner.graphics_pipelines.modify(|live| {
+            for (i, pipeline) in indices.into_iter().zip(pipelines) {
+                if let Some(live) = &mut live[i] {
+                    *live = pipeline;
+                }
+            }
+        });
+        let (indices, pipelines) = rebuild.compute;
+        inner.compute_pipelines.modify(|live| {
+            for (i, pipeline) in indices.into_iter().zip(pipelines) {
+                if let Some(live) = &mut live[i] {
+                    *live = pipeline;
+                }
+            }
+        });
+        let (indices, pipelines) = rebuild.ray_tracing;
+        inner.ray_tracing_pipelines.modify(|live| {
+            for (i, pipeline) in indices.into_iter().zip(pipelines) {
+                if let Some(live) = &mut live[i] {
+                    *live = pipeline;
+                }
+            }
+        });
+        Ok(())
+    }
+
     pub(crate) async fn destroy_graphics_pipelines(
         &self,
         ids: impl ExactSizeIterator<Item = GraphicsPipelineId>
@@ -219,6 +497,33 @@ impl PipelineBatch {
             })?;
         Ok(())
     }
+
+    pub(crate) async fn destroy_ray_tracing_pipelines(
+        &self,
+        ids: impl ExactSizeIterator<Item = RayTracingPipelineId>
+    ) -> Result<()>
+    {
+        if ids.len() == 0 { return Ok(()) }
+        let inner = self.inner.load().await?;
+        let batch_id = inner.id;
+        inner.ray_tracing_pipelines
+            .modify(|pipelines| {
+                for id in ids {
+                    if id.batch_id() != batch_id {
+                        return Err(Error::just_context(format!(
+                            "ray tracing pipeline id {id} batch id is different from this batch id {batch_id}",
+                        )))
+                    }
+                    pipelines
+                        .get_mut(id.1 as usize)
+                        .ok_or_else(|| Error::just_context(format!(
+                            "invalid ray tracing pipeline id {id}"
+                        )))?.take();
+                }
+                Ok(())
+            })?;
+        Ok(())
+    }
 }
 
 pub struct PipelineBatchBuilder {
@@ -226,6 +531,7 @@ pub struct PipelineBatchBuilder {
     gpu: Gpu,
     graphics_create_infos: Option<Vec32<GraphicsPipelineCreateTemplate>>,
     compute_create_infos: Option<Vec32<ComputePipelineCreateTemplate>>,
+    ray_tracing_create_infos: Option<Vec32<RayTracingPipelineCreateTemplate>>,
     cache: Option<PipelineCache>,
     built: bool,
 }
@@ -243,6 +549,7 @@ impl PipelineBatchBuilder {
             gpu,
             graphics_create_infos: Some(vec32![]),
             compute_create_infos: Some(vec32![]),
+            ray_tracing_create_infos: Some(vec32![]),
             cache,
             built: false,
         }
@@ -303,6 +610,34 @@ impl PipelineBatchBuilder {
         );
         self
     }
+
+    /// Appends [`RayTracingPipelineCreateInfo`]s to the batch.
+    ///
+    /// [`RayTracingPipelineId`]s are returned to as described in [`RayTracingPipelineCreateInfo`].
+    ///
+    /// # Valid usage
+    /// - Each create info *must* follow the valid usage described in
+    ///   [`RayTracingPipelineCreateInfo`].
+    #[inline(always)]
+    pub fn with_ray_tracing_pipelines<'a, I>(
+        &mut self,
+        create_infos: I,
+    ) -> &mut Self
+        where I: IntoIterator<Item = RayTracingPipelineCreateInfo<'a>>
+    {
+        let infos = self.ray_tracing_create_infos.as_mut().unwrap();
+        let mut id = infos.len();
+        let batch_id = self.this_id;
+        infos.extend(create_infos
+            .into_iter()
+            .map(|info| {
+                *info.meta = RayTracingPipelineId(batch_id, id);
+                id += 1;
+                info.into_template()
+            })
+        );
+        self
+    }
     
     #[inline]
     pub fn is_empty(&self) -> bool {
@@ -313,6 +648,10 @@ impl PipelineBatchBuilder {
         self.compute_create_infos
             .as_ref()
             .map(|c| c.is_empty())
+            .unwrap() &&
+        self.ray_tracing_create_infos
+            .as_ref()
+            .map(|r| r.is_empty())
             .unwrap()
     }
 
@@ -334,121 +673,38 @@ impl PipelineBatchBuilder {
         let cache = self.cache.clone();
         let this_id = self.this_id;
         let graphics = thread_pool.spawn_with_handle(async move {
-            let tmp_alloc = gpu.tmp_alloc();
-            let pipeline_count = create_infos.len();
-            if pipeline_count == 0 {
-                return Result::Ok(Default::default())
-            }
-            let mut prepared_create_infos = FixedVec32
-                ::with_capacity(pipeline_count, &tmp_alloc)
-                .context("alloc failed")?;
-            for info in &create_infos {
-                prepared_create_infos.push(info.prepare(&gpu, &tmp_alloc)
-                    .await
-                    .context("failed to convert graphics pipeline info")?
-                );
-            }
-            let mut vk_infos = FixedVec32
-                ::with_capacity(pipeline_count, &tmp_alloc)
-                .context("alloc failed")?;
-            for (info, _) in &mut prepared_create_infos {
-                let mut vk_info = info.as_create_info();
-                info.rendering_info.p_next = &info.robustness_info as *const _ as *const core::ffi::c_void;
-                vk_info.p_next = &info.rendering_info as *const _ as *const core::ffi::c_void;
-                vk_infos.push(vk_info);
-            }
-            let mut pipelines = FixedVec32
-                ::with_len(create_infos.len(), vk::Pipeline::null(), &tmp_alloc)
-                .context("alloc failed")?;
-            unsafe {
-                let device = gpu.device();
-                let pipeline_cache = cache 
-                    .map(|cache| cache.handle().into_inner())
-                    .unwrap_or(vk::PipelineCache::null());
-                device.create_graphics_pipelines(
-                    pipeline_cache,
-                    &vk_infos,
-                    None,
-                    &mut pipelines,
-                ).context_with(|| "failed to create graphics pipelines")?;
-            }
-            let graphics_pipelines: Vec32<_> = pipelines
-                .iter()
-                .copied()
-                .enumerate()
-                .map(|(i, handle)| unsafe {
-                    Some(GraphicsPipeline::new(
-                        gpu.device().clone(),
-                        handle,
-                        prepared_create_infos[i].1.clone(),
-                        &create_infos[i]
-                    ))
-                }).collect();
-            unsafe {
-                tmp_alloc.clear();
-            }
-            Ok(graphics_pipelines)
+            let pipelines = create_graphics_pipelines(
+                &gpu, cache.as_ref(), None, &create_infos,
+            ).await?;
+            Result::Ok((create_infos, pipelines))
         }).context("send error")?;
         let gpu = self.gpu.clone();
         let mut create_infos = self.compute_create_infos.take().unwrap();
         let cache = self.cache.clone();
         let compute = thread_pool.spawn_with_handle(async move {
-            let tmp_alloc = gpu.tmp_alloc();
-            let pipeline_count = create_infos.len();
-            if pipeline_count == 0 {
-                return Result::Ok(Default::default())
-            }
-            let mut vk_infos = FixedVec32
-                ::with_capacity(pipeline_count, &tmp_alloc)
-                .context("alloc failed")?;
-            let mut shader_sets = FixedVec32
-                ::with_capacity(pipeline_count, &tmp_alloc)
-                .context("alloc failed")?;
-            for info in &mut create_infos {
-                let (vk_info, shader_set) = info
-                    .prepare(&gpu)
-                    .await
-                    .context("failed to convert compute pipeline info")?;
-                shader_sets.push(shader_set);
-                vk_infos.push(vk_info);
-            }
-            let mut pipelines = FixedVec32
-                ::with_len(vk_infos.len(), vk::Pipeline::null(), &tmp_alloc)
-                .context("alloc failed")?;
-            unsafe {
-                let device = gpu.device();
-                let pipeline_cache = cache
-                    .map(|cache| cache.handle().into_inner())
-                    .unwrap_or(vk::PipelineCache::null());
-                device.create_compute_pipelines(
-                    pipeline_cache,
-                    &vk_infos,
-                    None,
-                    &mut pipelines
-                ).context("failed to create compute pipelines")?;
-            };
-            let compute_pipelines: Vec32<_> =
-                shader_sets 
-                    .into_iter()
-                    .enumerate()
-                    .map(|(i, shader_set)| {
-                        Some(unsafe { ComputePipeline::new(
-                            gpu.device().clone(),
-                            pipelines[i],
-                            shader_set,
-                        )})
-                    }).collect();
-            unsafe {
-                tmp_alloc.clear();
-            }
-            Ok(compute_pipelines)
+            let pipelines = create_compute_pipelines(
+                &gpu, cache.as_ref(), None, &mut create_infos,
+            ).await?;
+            Result::Ok((create_infos, pipelines))
         }).context("send error")?;
+        let gpu = self.gpu.clone();
+        let create_infos = self.ray_tracing_create_infos.take().unwrap();
+        let cache = self.cache.clone();
+        let ray_tracing = thread_pool.spawn_with_handle(async move {
+            let pipelines = create_ray_tracing_pipelines(
+                &gpu, cache.as_ref(), None, &create_infos,
+            ).await?;
+            Result::Ok((create_infos, pipelines))
+        }).context("send error")?;
+        let cache = self.cache.clone();
         let fut = thread_pool.spawn_with_handle(async move {
-            let graphics_pipelines = graphics.await
+            let graphics = graphics.await
                 .context("failed to create graphics pipelines")?;
-            let compute_pipelines = compute.await
+            let compute = compute.await
                 .context("failed to create compute pipelines")?;
-            Ok(PipelineBatchInner::new(this_id, graphics_pipelines, compute_pipelines))
+            let ray_tracing = ray_tracing.await
+                .context("failed to create ray tracing pipelines")?;
+            Ok(PipelineBatchInner::new(this_id, graphics, compute, ray_tracing, cache))
         }).context("send error")?;
         self.gpu.init_pipeline_batch(
             this_id, PipelineBatch::new(fut),
@@ -474,6 +730,7 @@ impl Drop for PipelineBatchBuilder {
                 gpu: self.gpu.clone(),
                 graphics_create_infos: self.graphics_create_infos.clone(),
                 compute_create_infos: self.compute_create_infos.clone(),
+                ray_tracing_create_infos: self.ray_tracing_create_infos.clone(),
                 cache: self.cache.clone(),
                 built: false,
             };
@@ -1,13 +1,145 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    path::Path,
+    fs, io,
+};
 
 use nox_ash::vk;
 
 use nox_error::Context;
 
-use crate::error::Result;
+use nox_proc::Display;
+
+use crate::{
+    error::Result,
+    log::warn,
+};
 
 use crate::gpu::prelude::*;
 
+/// Identifies files written by [`PipelineCache::save_to_file`].
+const MAGIC: [u8; 8] = *b"NOXPCACH";
+/// The version of the file header, incremented whenever the layout changes.
+const HEADER_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 8 + 4 + 4 + 4 + 4 + vk::UUID_SIZE + 8 + 8;
+
+/// The reason a pipeline cache file was discarded.
+#[derive(Display)]
+enum InvalidCache {
+    #[display("file is too small")]
+    TooSmall,
+    #[display("file is not a pipeline cache")]
+    BadMagic,
+    #[display("unsupported header version {0}")]
+    HeaderVersion(u32),
+    #[display("cache was created for a different device")]
+    DeviceMismatch,
+    #[display("cache was created with a different driver version")]
+    DriverMismatch,
+    #[display("pipeline cache UUID does not match")]
+    UuidMismatch,
+    #[display("data size does not match header")]
+    SizeMismatch,
+    #[display("checksum does not match")]
+    ChecksumMismatch,
+}
+
+/// 64-bit FNV-1a.
+fn checksum(data: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// The header written in front of pipeline cache data.
+struct Header {
+    vendor_id: u32,
+    device_id: u32,
+    driver_version: u32,
+    pipeline_cache_uuid: [u8; vk::UUID_SIZE],
+    data_size: u64,
+    checksum: u64,
+}
+
+impl Header {
+
+    fn new(physical_device: &PhysicalDevice, data: &[u8]) -> Self {
+        Self {
+            vendor_id: physical_device.vendor_id(),
+            device_id: physical_device.device_id(),
+            driver_version: physical_device.raw_driver_version(),
+            pipeline_cache_uuid: physical_device.pipeline_cache_uuid(),
+            data_size: data.len() as u64,
+            checksum: checksum(data),
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&HEADER_VERSION.to_le_bytes());
+        out.extend_from_slice(&self.vendor_id.to_le_bytes());
+        out.extend_from_slice(&self.device_id.to_le_bytes());
+        out.extend_from_slice(&self.driver_version.to_le_bytes());
+        out.extend_from_slice(&self.pipeline_cache_uuid);
+        out.extend_from_slice(&self.data_size.to_le_bytes());
+        out.extend_from_slice(&self.checksum.to_le_bytes());
+    }
+
+    /// Validates `bytes` against `physical_device` and returns the cache data following the
+    /// header.
+    fn validate<'a>(
+        physical_device: &PhysicalDevice,
+        bytes: &'a [u8],
+    ) -> core::result::Result<&'a [u8], InvalidCache>
+    {
+        if bytes.len() < HEADER_SIZE {
+            return Err(InvalidCache::TooSmall)
+        }
+        let (header, data) = bytes.split_at(HEADER_SIZE);
+        let (magic, header) = header.split_at(MAGIC.len());
+        if magic != MAGIC {
+            return Err(InvalidCache::BadMagic)
+        }
+        let mut header = header;
+        let mut read_u32 = || {
+            let (value, rest) = header.split_at(4);
+            header = rest;
+            u32::from_le_bytes(value.try_into().unwrap())
+        };
+        let header_version = read_u32();
+        if header_version != HEADER_VERSION {
+            return Err(InvalidCache::HeaderVersion(header_version))
+        }
+        let vendor_id = read_u32();
+        let device_id = read_u32();
+        let driver_version = read_u32();
+        let (pipeline_cache_uuid, header) = header.split_at(vk::UUID_SIZE);
+        let (data_size, header) = header.split_at(8);
+        let data_size = u64::from_le_bytes(data_size.try_into().unwrap());
+        let data_checksum = u64::from_le_bytes(header.try_into().unwrap());
+        let expected = Self::new(physical_device, data);
+        if vendor_id != expected.vendor_id || device_id != expected.device_id {
+            return Err(InvalidCache::DeviceMismatch)
+        }
+        if driver_version != expected.driver_version {
+            return Err(InvalidCache::DriverMismatch)
+        }
+        if pipeline_cache_uuid != expected.pipeline_cache_uuid {
+            return Err(InvalidCache::UuidMismatch)
+        }
+        if data_size != expected.data_size {
+            return Err(InvalidCache::SizeMismatch)
+        }
+        if data_checksum != expected.checksum {
+            return Err(InvalidCache::ChecksumMismatch)
+        }
+        Ok(data)
+    }
+}
+
 struct Inner {
     device: LogicalDevice,
     handle: vk::PipelineCache,
@@ -40,6 +172,65 @@ impl PipelineCache {
         })
     }
 
+    /// Creates a pipeline cache from a file written by [`save_to_file`][1].
+    ///
+    /// The file is validated against the vendor ID, device ID, driver version and
+    /// [`pipeline cache UUID`][2] of `device` and against its checksum. If the file is stale or
+    /// corrupted, a warning is logged and an empty cache is created instead.
+    ///
+    /// An empty cache is also created, if the file doesn't exist.
+    ///
+    /// [1]: Self::save_to_file
+    /// [2]: PhysicalDevice::pipeline_cache_uuid
+    pub fn load_from_file(
+        device: LogicalDevice,
+        path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("failed to read pipeline cache {}: {err}", path.display());
+                }
+                return Self::new(device, None)
+            },
+        };
+        let initial_data = match Header::validate(device.physical_device(), &bytes) {
+            Ok(data) => Some(data),
+            Err(err) => {
+                warn!("discarding pipeline cache {}: {err}", path.display());
+                None
+            },
+        };
+        Self::new(device, initial_data)
+    }
+
+    /// Writes the data of this cache to a file, which *can* be loaded with
+    /// [`load_from_file`][1].
+    ///
+    /// The file is written to a temporary path first and then renamed, so that an interrupted
+    /// write never leaves a truncated cache behind.
+    ///
+    /// [1]: Self::load_from_file
+    pub fn save_to_file(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<()> {
+        let path = path.as_ref();
+        let data = self.retrieve_data()?;
+        let header = Header::new(self.inner.device.physical_device(), &data);
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + data.len());
+        header.write(&mut bytes);
+        bytes.extend_from_slice(&data);
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, &bytes)
+            .context_with(|| format!("failed to write pipeline cache {}", path.display()))?;
+        fs::rename(&tmp_path, path)
+            .context_with(|| format!("failed to write pipeline cache {}", path.display()))
+    }
+
     #[inline]
     pub fn handle(&self) -> TransientHandle<'_, vk::PipelineCache> {
         TransientHandle::new(self.inner.handle)
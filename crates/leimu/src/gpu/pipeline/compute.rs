@@ -7,6 +7,7 @@ use crate::error::*;
 #[derive(Clone)]
 pub struct ComputePipeline {
     handle: PipelineHandle,
+    dispatch_base: bool,
 }
 
 impl ComputePipeline { 
@@ -16,10 +17,12 @@ impl ComputePipeline {
         device: LogicalDevice,
         handle: vk::Pipeline,
         shader_set: ShaderSet,
+        dispatch_base: bool,
     ) -> Self {
         unsafe {
             Self {
                 handle: PipelineHandle::new(device, handle, shader_set),
+                dispatch_base,
             }
         }
     }
@@ -28,6 +31,14 @@ impl ComputePipeline {
     pub fn handle(&self) -> &PipelineHandle {
         &self.handle
     }
+
+    /// Returns whether the pipeline was created with a non-zero base workgroup allowed.
+    ///
+    /// See [`ComputePipelineCreateInfo::with_dispatch_base`].
+    #[inline(always)]
+    pub fn dispatch_base(&self) -> bool {
+        self.dispatch_base
+    }
 }
 
 mod base {
@@ -39,22 +50,29 @@ mod base {
         pub(crate) meta: Meta,
         pub(crate) shader_set_id: ShaderSetId,
         pub(crate) robustness_info: vk::PipelineRobustnessCreateInfo<'static>,
+        pub(crate) dispatch_base: bool,
     }
 
     impl<Meta> Template<Meta> {
 
+        /// Prepares the create info with `reloaded` as the shader set, if it's given, instead of
+        /// the shader set currently identified by the template's shader set id.
         pub(crate) async fn prepare(
             &mut self,
             gpu: &Gpu,
+            reloaded: Option<&ShaderSet>,
         ) -> Result<(vk::ComputePipelineCreateInfo<'_>, ShaderSet)>
         {
-            let shader_set = gpu
-                .get_shader_set(self.shader_set_id)
-                .await
-                .context_with(|| format!(
-                    "failed to get shader set {}",
-                    self.shader_set_id,
-                ))?.clone();
+            let shader_set = match reloaded {
+                Some(shader_set) => shader_set.clone(),
+                None => gpu
+                    .get_shader_set(self.shader_set_id)
+                    .await
+                    .context_with(|| format!(
+                        "failed to get shader set {}",
+                        self.shader_set_id,
+                    ))?.clone(),
+            };
             let module = shader_set.shaders()
                 .iter()
                 .find(|module| module.stage() == ShaderStage::Compute)
@@ -102,8 +120,13 @@ mod base {
                     ))
                 }
             }
+            let mut flags = shader_set.pipeline_create_flags();
+            if self.dispatch_base {
+                flags |= vk::PipelineCreateFlags::DISPATCH_BASE;
+            }
             Ok((vk::ComputePipelineCreateInfo {
                 s_type: vk::StructureType::COMPUTE_PIPELINE_CREATE_INFO,
+                flags,
                 stage: vk::PipelineShaderStageCreateInfo {
                     s_type: vk::StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
                     stage: vk::ShaderStageFlags::COMPUTE,
@@ -139,6 +162,7 @@ impl<'a> ComputePipelineCreateInfo<'a> {
             meta: out_id,
             shader_set_id,
             robustness_info: PipelineRobustnessInfo::default().into(),
+            dispatch_base: false,
         }
     }
 
@@ -148,6 +172,7 @@ impl<'a> ComputePipelineCreateInfo<'a> {
             meta: (),
             shader_set_id: self.shader_set_id,
             robustness_info: self.robustness_info,
+            dispatch_base: self.dispatch_base,
         }
     }
 
@@ -158,5 +183,21 @@ impl<'a> ComputePipelineCreateInfo<'a> {
     ) -> Self {
         self.robustness_info = robustness_info.into();
         self
-    } 
+    }
+
+    /// Sets whether [`ComputeCommands::dispatch_base`] can be used with a non-zero base
+    /// workgroup with this pipeline.
+    ///
+    /// The default is `false`.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/VkPipelineCreateFlagBits.html>
+    #[inline(always)]
+    pub fn with_dispatch_base(
+        mut self,
+        dispatch_base: bool,
+    ) -> Self {
+        self.dispatch_base = dispatch_base;
+        self
+    }
 }
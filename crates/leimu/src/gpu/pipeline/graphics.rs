@@ -31,6 +31,8 @@ pub struct GraphicsPipeline {
     n_color_output_formats: u32,
     depth_output_format: Format,
     stencil_output_format: Format,
+    view_mask: u32,
+    shader_stages: ShaderStageFlags,
 }
 
 impl GraphicsPipeline {
@@ -59,6 +61,12 @@ impl GraphicsPipeline {
                 );
             Arc::from_raw(data)
         };
+        let shader_stages = shader_set
+            .shaders()
+            .iter()
+            .fold(ShaderStageFlags::empty(), |stages, module| {
+                stages | ShaderStageFlags::from(module.stage())
+            });
         Self {
             handle: unsafe {
                 PipelineHandle::new(device, handle, shader_set)
@@ -76,6 +84,8 @@ impl GraphicsPipeline {
             n_color_output_formats: n_color_output_formats as u32,
             depth_output_format: create_info.depth_output_format,
             stencil_output_format: create_info.stencil_output_format,
+            view_mask: create_info.view_mask,
+            shader_stages,
         }
     }
 
@@ -113,6 +123,22 @@ impl GraphicsPipeline {
         self.stencil_output_format
     }
 
+    #[inline(always)]
+    pub fn view_mask(&self) -> u32 {
+        self.view_mask
+    }
+
+    #[inline(always)]
+    pub fn shader_stages(&self) -> ShaderStageFlags {
+        self.shader_stages
+    }
+
+    /// Returns whether the pipeline uses a mesh shader instead of vertex input.
+    #[inline(always)]
+    pub fn is_mesh_pipeline(&self) -> bool {
+        self.shader_stages.contains(ShaderStageFlags::MESH)
+    }
+
     #[inline(always)]
     pub fn dynamic_states(&self) -> &[DynamicState] {
         unsafe {
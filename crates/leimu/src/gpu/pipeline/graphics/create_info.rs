@@ -29,6 +29,7 @@ mod base {
         pub(crate) color_outputs: Vec32<(Format, ColorOutputState)>,
         pub(crate) depth_output_format: Format,
         pub(crate) stencil_output_format: Format,
+        pub(crate) view_mask: u32,
         pub(crate) line_width: f32,
         pub(crate) depth_clamp: bool,
         pub(crate) rasterizer_discard: bool,
@@ -59,7 +60,10 @@ impl<'a> GraphicsPipelineCreateInfo<'a> {
     ///
     /// # Valid usage
     /// - `shader_set_id` *must* be a valid [`ShaderSetId`].
-    /// - The shader set *must* contain a shader with [`ShaderStage::Vertex`].
+    /// - The shader set *must* contain a shader with either [`ShaderStage::Vertex`] or
+    ///   [`ShaderStage::Mesh`], but not both.
+    /// - The shader set *must* only contain a shader with [`ShaderStage::Task`] if it contains
+    ///   a shader with [`ShaderStage::Mesh`].
     /// - If [`GraphicsPipelineCreateInfo::rasterizer_discard`] is false or the dynamic state
     ///   includes [`DynamicState::RasterizerDiscardEnable`], the shader set *must* contain a shader
     ///   with [`ShaderStage::Fragment`].
@@ -78,7 +82,10 @@ impl<'a> GraphicsPipelineCreateInfo<'a> {
     ///
     /// # Valid usage
     /// - [`shader_set_id`][1] *must* be a valid [`ShaderSetId`].
-    /// - The shader set *must* contain a shader with [`ShaderStage::Vertex`].
+    /// - The shader set *must* contain a shader with either [`ShaderStage::Vertex`] or
+    ///   [`ShaderStage::Mesh`], but not both.
+    /// - The shader set *must* only contain a shader with [`ShaderStage::Task`] if it contains
+    ///   a shader with [`ShaderStage::Mesh`].
     /// - If [`GraphicsPipelineCreateInfo::rasterizer_discard`] is false or the dynamic state
     ///   includes [`DynamicState::RasterizerDiscardEnable`], the shader set *must* contain a shader
     ///   with [`ShaderStage::Fragment`].
@@ -121,6 +128,7 @@ impl<Meta: Send + Sync> base::Template<Meta> {
             color_outputs: self.color_outputs,
             depth_output_format: self.depth_output_format,
             stencil_output_format: self.stencil_output_format,
+            view_mask: self.view_mask,
             line_width: self.line_width,
             depth_clamp: self.depth_clamp,
             rasterizer_discard: self.rasterizer_discard,
@@ -146,6 +154,7 @@ impl<Meta: Send + Sync> base::Template<Meta> {
             color_blend_info: ColorBlendInfo::default(),
             depth_output_format: Format::Undefined,
             stencil_output_format: Format::Undefined,
+            view_mask: 0,
             line_width: 1.0,
             depth_clamp: false,
             rasterizer_discard: false,
@@ -159,7 +168,10 @@ impl<Meta: Send + Sync> base::Template<Meta> {
     ///
     /// # Valid usage
     /// - `id` *must* be a valid [`ShaderSetId`].
-    /// - The shader set *must* contain a shader with [`ShaderStage::Vertex`].
+    /// - The shader set *must* contain a shader with either [`ShaderStage::Vertex`] or
+    ///   [`ShaderStage::Mesh`], but not both.
+    /// - The shader set *must* only contain a shader with [`ShaderStage::Task`] if it contains
+    ///   a shader with [`ShaderStage::Mesh`].
     /// - If [`GraphicsPipelineCreateInfo::rasterizer_discard`] is false or the dynamic state
     ///   includes [`DynamicState::RasterizerDiscardEnable`], the shader set *must* contain a shader
     ///   with [`ShaderStage::Fragment`].
@@ -345,6 +357,28 @@ impl<Meta: Send + Sync> base::Template<Meta> {
         self
     }
 
+    /// Sets the view mask of the pipeline.
+    ///
+    /// Each set bit specifies a view the pipeline renders to, with the index of the bit being
+    /// the view index. When rendering with a non-zero view mask, each draw is broadcast to all
+    /// views and the view index is available in shaders as `ViewIndex`.
+    ///
+    /// The view mask *must* match [`RenderingInfo::view_mask`] of the render pass the pipeline
+    /// is used in. The default view mask is zero, which disables multiview.
+    ///
+    /// # Valid usage
+    /// - If `view_mask` is not zero, [`multiview`][1] *must* be enabled.
+    /// - The index of the most significant bit of `view_mask` *must* be less than
+    ///   [`max multiview view count`][2].
+    ///
+    /// [1]: ext::multiview
+    /// [2]: ext::multiview::Attributes::MAX_MULTIVIEW_VIEW_COUNT
+    #[inline]
+    pub fn with_view_mask(mut self, view_mask: u32) -> Self {
+        self.view_mask = view_mask;
+        self
+    }
+
     /// Sets the robustness info of the pipeline.
     ///
     /// # Valid usage
@@ -373,17 +407,87 @@ impl<Meta: Send + Sync> base::Template<Meta> {
         self
     }
 
+    fn validate_mesh_pipeline(
+        &self,
+        gpu: &Gpu,
+        task_shader: Option<&ShaderModule>,
+        mesh_shader: Option<&ShaderModule>,
+        vertex_shader_included: bool,
+    ) -> Result<()>
+    {
+        if vertex_shader_included {
+            return Err(Error::just_context(
+                "vertex shader and mesh shader must not be included in the same graphics pipeline"
+            ))
+        }
+        if !self.vertex_input_bindings.is_empty() || !self.vertex_input_attributes.is_empty() {
+            return Err(Error::just_context(
+                "mesh shader pipelines must not have vertex input"
+            ))
+        }
+        for state in [
+            DynamicState::PrimitiveTopology,
+            DynamicState::PrimitiveRestartEnable,
+            DynamicState::VertexInputBindingStride,
+        ] {
+            if self.dynamic_states.contains(&state) {
+                return Err(Error::just_context(format!(
+                    "mesh shader pipelines must not include dynamic state {state}"
+                )))
+            }
+        }
+        if self.view_mask != 0 &&
+            !gpu
+                .get_device_attribute(ext::mesh_shader::Attributes::IS_MULTIVIEW_MESH_SHADER_ENABLED)
+                .bool().unwrap_or_default()
+        {
+            return Err(Error::just_context(
+                "mesh shader pipelines with a non-zero view mask require multiview mesh shaders to be enabled"
+            ))
+        }
+        let max_task_payload_size = gpu
+            .get_device_attribute(ext::mesh_shader::Attributes::MAX_TASK_PAYLOAD_SIZE)
+            .u32().unwrap_or(0);
+        let task_payload_size = task_shader.and_then(|shader| shader.task_payload_size());
+        if let Some(size) = task_payload_size && size > max_task_payload_size {
+            return Err(Error::just_context(format!(
+                "task payload size {size} is greater than max task payload size {max_task_payload_size}"
+            )))
+        }
+        if let Some(size) = mesh_shader.and_then(|shader| shader.task_payload_size()) {
+            if task_shader.is_none() {
+                return Err(Error::just_context(
+                    "mesh shader reads a task payload, but no task shader is included in pipeline"
+                ))
+            }
+            if task_payload_size != Some(size) {
+                return Err(Error::just_context(format!(
+                    "{}{}",
+                    format_args!("mesh shader task payload size {size} doesn't match "),
+                    format_args!("task shader task payload size {}", task_payload_size.unwrap_or(0)),
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Prepares the create info with `reloaded` as the shader set, if it's given, instead of the
+    /// shader set currently identified by the template's shader set id.
     pub(crate) async fn prepare<'a, Alloc>(
         &self,
         gpu: &Gpu,
+        reloaded: Option<&ShaderSet>,
         alloc: &'a Alloc,
     ) -> Result<(PreparedCreateInfos<'a, Alloc>, ShaderSet)>
         where 
             Alloc: LocalAlloc<Error = arena::Error> + Sync,
     {
-        let shader_set = gpu
-            .get_shader_set(self.shader_set_id)
-            .await?;
+        let shader_set = match reloaded {
+            Some(shader_set) => shader_set.clone(),
+            None => gpu
+                .get_shader_set(self.shader_set_id)
+                .await?,
+        };
 
         let shaders = shader_set.shaders();
         let mut shader_stage_infos = NonNullVec32
@@ -392,6 +496,8 @@ impl<Meta: Send + Sync> base::Template<Meta> {
 
         let mut vertex_shader_included = false;
         let mut fragment_shader_included = false;
+        let mut task_shader = None;
+        let mut mesh_shader = None;
 
         for module in shaders {
             match module.stage() {
@@ -407,10 +513,38 @@ impl<Meta: Send + Sync> base::Template<Meta> {
                     }
                     fragment_shader_included = true;
                 },
+                ShaderStage::Task => {
+                    if task_shader.is_some() {
+                        return Err(Error::just_context("task shader included twice in pipeline"))
+                    }
+                    if !gpu
+                        .get_device_attribute(ext::mesh_shader::Attributes::IS_TASK_SHADER_ENABLED)
+                        .bool().unwrap_or_default()
+                    {
+                        return Err(Error::just_context(
+                            "attempting to use a task shader without enabling task shaders of the mesh shader extension"
+                        ))
+                    }
+                    task_shader = Some(module);
+                },
+                ShaderStage::Mesh => {
+                    if mesh_shader.is_some() {
+                        return Err(Error::just_context("mesh shader included twice in pipeline"))
+                    }
+                    if !gpu
+                        .get_device_attribute(ext::mesh_shader::Attributes::IS_ENABLED)
+                        .bool().unwrap_or_default()
+                    {
+                        return Err(Error::just_context(
+                            "attempting to use a mesh shader without enabling the mesh shader extension"
+                        ))
+                    }
+                    mesh_shader = Some(module);
+                },
                 _ => {
                     return Err(Error::just_context(format!(
                         "{}{}",
-                        format_args!("unsupported shader stage {}, only vertex and fragment shaders", module.stage()),
+                        format_args!("unsupported shader stage {}, only vertex, task, mesh and fragment shaders ", module.stage()),
                         "are supported for graphics pipelines",
                     )))
                 }
@@ -424,8 +558,14 @@ impl<Meta: Send + Sync> base::Template<Meta> {
             });
         }
 
-        if !vertex_shader_included {
-            return Err(Error::just_context("no vertex shader included in graphics pipeline"))
+        let is_mesh_pipeline = mesh_shader.is_some();
+
+        if is_mesh_pipeline {
+            self.validate_mesh_pipeline(gpu, task_shader, mesh_shader, vertex_shader_included)?;
+        } else if task_shader.is_some() {
+            return Err(Error::just_context("task shader included in graphics pipeline without a mesh shader"))
+        } else if !vertex_shader_included {
+            return Err(Error::just_context("no vertex or mesh shader included in graphics pipeline"))
         }
 
         let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo {
@@ -539,9 +679,12 @@ impl<Meta: Send + Sync> base::Template<Meta> {
             .iter().map(|(format, _)| (*format).into())
         );
 
+        ext::multiview::validate_view_mask(gpu.device(), self.view_mask)
+            .context("invalid graphics pipeline view mask")?;
+
         let rendering_info = vk::PipelineRenderingCreateInfo {
             s_type: vk::StructureType::PIPELINE_RENDERING_CREATE_INFO,
-            view_mask: 0,
+            view_mask: self.view_mask,
             color_attachment_count: color_output_formats.len(),
             p_color_attachment_formats: color_output_formats.as_ptr(),
             depth_attachment_format: self.depth_output_format.into(),
@@ -637,7 +780,9 @@ impl<Meta: Send + Sync> base::Template<Meta> {
             dynamic_state,
             rendering_info,
             layout: shader_set.pipeline_layout(),
+            flags: shader_set.pipeline_create_flags(),
             robustness_info: self.robustness_info.into(),
+            is_mesh_pipeline,
             _color_output_formats: color_output_formats,
             _color_blend_attachment_state: color_blend_attachment_states,
             _vertex_input_bindings: vertex_input_bindings,
@@ -663,7 +808,9 @@ pub(crate) struct PreparedCreateInfos<'a, Alloc>
     pub dynamic_state: vk::PipelineDynamicStateCreateInfo<'static>,
     pub rendering_info: vk::PipelineRenderingCreateInfo<'static>,
     pub layout: vk::PipelineLayout,
+    pub flags: vk::PipelineCreateFlags,
     pub robustness_info: vk::PipelineRobustnessCreateInfo<'static>,
+    pub is_mesh_pipeline: bool,
     pub _color_blend_attachment_state: NonNullVec32<'a, vk::PipelineColorBlendAttachmentState>,
     pub _vertex_input_bindings: NonNullVec32<'a, vk::VertexInputBindingDescription>,
     pub _vertex_input_attributes: NonNullVec32<'a, vk::VertexInputAttributeDescription>,
@@ -686,12 +833,23 @@ impl<'a, Alloc> PreparedCreateInfos<'a, Alloc>
 
     #[inline]
     pub fn as_create_info(&self) -> vk::GraphicsPipelineCreateInfo<'static> {
+        // mesh pipelines have no vertex input stages, so their states are ignored
+        let (p_vertex_input_state, p_input_assembly_state) =
+            if self.is_mesh_pipeline {
+                (core::ptr::null(), core::ptr::null())
+            } else {
+                (
+                    &self.vertex_input_state as *const _,
+                    &self.input_assembly_state as *const _,
+                )
+            };
         vk::GraphicsPipelineCreateInfo {
             s_type: vk::StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
+            flags: self.flags,
             stage_count: self.shader_stage_infos.len(),
             p_stages: self.shader_stage_infos.as_ptr(),
-            p_vertex_input_state: &self.vertex_input_state,
-            p_input_assembly_state: &self.input_assembly_state,
+            p_vertex_input_state,
+            p_input_assembly_state,
             p_tessellation_state: &self.tesellation_state,
             p_viewport_state: &self.viewport_state,
             p_rasterization_state: &self.rasterization_state,
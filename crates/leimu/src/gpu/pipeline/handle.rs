@@ -11,6 +11,7 @@ pub(super) struct Inner {
     pub device: LogicalDevice,
     pub handle: vk::Pipeline,
     pub shader_set: ShaderSet,
+    pub _shader_binding_table: Option<BufferMeta>,
 }
 
 impl Drop for Inner {
@@ -40,6 +41,26 @@ impl PipelineHandle {
                 device,
                 handle,
                 shader_set,
+                _shader_binding_table: None,
+            }),
+        }
+    }
+
+    /// Creates a pipeline handle, which keeps the shader binding table of a ray tracing pipeline
+    /// alive for as long as the pipeline.
+    #[inline(always)]
+    pub(super) unsafe fn with_shader_binding_table(
+        device: LogicalDevice,
+        handle: vk::Pipeline,
+        shader_set: ShaderSet,
+        shader_binding_table: BufferMeta,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                device,
+                handle,
+                shader_set,
+                _shader_binding_table: Some(shader_binding_table),
             }),
         }
     }
@@ -0,0 +1,497 @@
+use nox_ash::vk;
+
+use nox_mem::{
+    vec::{FixedVec32, Vec32},
+    alloc::LocalAlloc,
+    arena,
+};
+
+use crate::{
+    gpu::prelude::*,
+    error::*,
+};
+
+/// Contains the handle and the shader binding table of a ray tracing pipeline.
+///
+/// The shader groups of the pipeline are generated from the stages of its [`ShaderSet`]:
+/// - The ray generation shader forms a single group.
+/// - Each miss shader forms its own group, in the order they appear in the shader set.
+/// - Each closest hit shader forms its own hit group together with the any hit and
+///   intersection shaders of the set. If the set contains no closest hit shaders, but contains an
+///   any hit or an intersection shader, a single hit group is formed from them.
+/// - Each callable shader forms its own group, in the order they appear in the shader set.
+///
+/// The hit groups are procedural, when the shader set contains an intersection shader, and
+/// triangle hit groups otherwise.
+///
+/// This is [`Clone`], [`Send`] and [`Sync`].
+#[derive(Clone)]
+pub struct RayTracingPipeline {
+    handle: PipelineHandle,
+    raygen_region: vk::StridedDeviceAddressRegionKHR,
+    miss_region: vk::StridedDeviceAddressRegionKHR,
+    hit_region: vk::StridedDeviceAddressRegionKHR,
+    callable_region: vk::StridedDeviceAddressRegionKHR,
+    miss_group_count: u32,
+    hit_group_count: u32,
+    callable_group_count: u32,
+}
+
+impl RayTracingPipeline {
+
+    #[inline(always)]
+    pub fn handle(&self) -> &PipelineHandle {
+        &self.handle
+    }
+
+    /// Returns the number of miss shader groups of the pipeline.
+    #[inline(always)]
+    pub fn miss_group_count(&self) -> u32 {
+        self.miss_group_count
+    }
+
+    /// Returns the number of hit groups of the pipeline.
+    ///
+    /// The [`shader binding table record offset`][1] of an instance indexes these.
+    ///
+    /// [1]: AccelerationStructureInstance::with_shader_binding_table_record_offset
+    #[inline(always)]
+    pub fn hit_group_count(&self) -> u32 {
+        self.hit_group_count
+    }
+
+    /// Returns the number of callable shader groups of the pipeline.
+    #[inline(always)]
+    pub fn callable_group_count(&self) -> u32 {
+        self.callable_group_count
+    }
+
+    /// Returns the shader binding table regions of the ray generation, miss, hit and callable
+    /// shader groups respectively.
+    #[inline(always)]
+    pub(crate) fn shader_binding_table(&self) -> [&vk::StridedDeviceAddressRegionKHR; 4] {
+        [
+            &self.raygen_region,
+            &self.miss_region,
+            &self.hit_region,
+            &self.callable_region,
+        ]
+    }
+}
+
+mod base {
+
+    use super::*;
+
+    #[derive(Clone)]
+    pub struct Template<Meta> {
+        pub(crate) meta: Meta,
+        pub(crate) shader_set_id: ShaderSetId,
+        pub(crate) max_recursion_depth: u32,
+    }
+}
+
+pub(crate) type RayTracingPipelineCreateTemplate = base::Template<()>;
+
+pub type RayTracingPipelineCreateInfo<'a> = base::Template<&'a mut RayTracingPipelineId>;
+
+impl<'a> RayTracingPipelineCreateInfo<'a> {
+
+    /// Creates new ray tracing pipeline create info.
+    ///
+    /// When added to a [`PipelineBatch`] with [`PipelineBatchBuilder::with_ray_tracing_pipelines`],
+    /// the id of the to be created [`RayTracingPipeline`] is returned to `out_id`.
+    ///
+    /// The shader groups and the shader binding table are generated as described in
+    /// [`RayTracingPipeline`].
+    ///
+    /// # Valid usage
+    /// - [`ext::ray_tracing_pipeline`] *must* be enabled.
+    /// - `shader_set_id` *must* be a valid [`ShaderSetId`] and the shader set *must* contain
+    ///   exactly one [`ShaderStage::RayGeneration`] shader and only ray tracing shaders.
+    /// - The shader set *must* contain at most one [`ShaderStage::AnyHit`] and at most one
+    ///   [`ShaderStage::Intersection`] shader.
+    #[inline(always)]
+    pub fn new(out_id: &'a mut RayTracingPipelineId, shader_set_id: ShaderSetId) -> Self {
+        Self {
+            meta: out_id,
+            shader_set_id,
+            max_recursion_depth: 1,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn into_template(self) -> RayTracingPipelineCreateTemplate {
+        RayTracingPipelineCreateTemplate {
+            meta: (),
+            shader_set_id: self.shader_set_id,
+            max_recursion_depth: self.max_recursion_depth,
+        }
+    }
+
+    /// Sets the maximum depth of recursive `traceRayEXT` calls. The default is 1.
+    ///
+    /// # Valid usage
+    /// - `max_recursion_depth` *must* be less than or equal to
+    ///   [`ext::ray_tracing_pipeline::Attributes::MAX_RAY_RECURSION_DEPTH`].
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/VkRayTracingPipelineCreateInfoKHR.html>
+    #[inline(always)]
+    pub fn with_max_recursion_depth(mut self, max_recursion_depth: u32) -> Self {
+        self.max_recursion_depth = max_recursion_depth;
+        self
+    }
+}
+
+/// The shader group counts of a single pipeline.
+#[derive(Default, Clone, Copy)]
+struct GroupCounts {
+    miss: u32,
+    hit: u32,
+    callable: u32,
+}
+
+impl GroupCounts {
+
+    #[inline(always)]
+    fn total(self) -> u32 {
+        1 + self.miss + self.hit + self.callable
+    }
+}
+
+fn group_counts(
+    shader_set: &ShaderSet,
+    shader_set_id: ShaderSetId,
+) -> Result<GroupCounts>
+{
+    let mut raygen = 0;
+    let mut any_hit = 0;
+    let mut intersection = 0;
+    let mut counts = GroupCounts::default();
+    for module in shader_set.shaders() {
+        match module.stage() {
+            ShaderStage::RayGeneration => raygen += 1,
+            ShaderStage::Miss => counts.miss += 1,
+            ShaderStage::ClosestHit => counts.hit += 1,
+            ShaderStage::AnyHit => any_hit += 1,
+            ShaderStage::Intersection => intersection += 1,
+            ShaderStage::Callable => counts.callable += 1,
+            stage => return Err(Error::just_context(format!(
+                "shader set {shader_set_id} contains a non-ray tracing shader stage {stage}",
+            ))),
+        }
+    }
+    if raygen != 1 {
+        return Err(Error::just_context(format!(
+            "shader set {shader_set_id} must contain exactly one ray generation shader, found {raygen}",
+        )))
+    }
+    if any_hit > 1 || intersection > 1 {
+        return Err(Error::just_context(format!(
+            "shader set {shader_set_id} must contain at most one any hit and intersection shader",
+        )))
+    }
+    if counts.hit == 0 && (any_hit != 0 || intersection != 0) {
+        counts.hit = 1;
+    }
+    Ok(counts)
+}
+
+/// Pushes the shader stages and groups of `shader_set` in the order described in
+/// [`RayTracingPipeline`].
+fn push_groups<'a, Alloc>(
+    shader_set: &ShaderSet,
+    stages: &mut FixedVec32<'a, vk::PipelineShaderStageCreateInfo<'static>, Alloc>,
+    groups: &mut FixedVec32<'a, vk::RayTracingShaderGroupCreateInfoKHR<'static>, Alloc>,
+)
+    where Alloc: ?Sized + LocalAlloc<Error = arena::Error>
+{
+    let first_stage = stages.len();
+    let mut any_hit = vk::SHADER_UNUSED_KHR;
+    let mut intersection = vk::SHADER_UNUSED_KHR;
+    for module in shader_set.shaders() {
+        let stage = module.stage();
+        match stage {
+            ShaderStage::AnyHit => any_hit = stages.len() - first_stage,
+            ShaderStage::Intersection => intersection = stages.len() - first_stage,
+            _ => {},
+        }
+        stages.push(vk::PipelineShaderStageCreateInfo {
+            s_type: vk::StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
+            stage: ShaderStageFlags::from(stage).into(),
+            module: module.handle(),
+            p_name: module.entry_point().as_ptr(),
+            ..Default::default()
+        });
+    }
+    let general = |shader: u32| vk::RayTracingShaderGroupCreateInfoKHR {
+        s_type: vk::StructureType::RAY_TRACING_SHADER_GROUP_CREATE_INFO_KHR,
+        ty: vk::RayTracingShaderGroupTypeKHR::GENERAL,
+        general_shader: shader,
+        closest_hit_shader: vk::SHADER_UNUSED_KHR,
+        any_hit_shader: vk::SHADER_UNUSED_KHR,
+        intersection_shader: vk::SHADER_UNUSED_KHR,
+        ..Default::default()
+    };
+    let hit = |closest_hit: u32| vk::RayTracingShaderGroupCreateInfoKHR {
+        s_type: vk::StructureType::RAY_TRACING_SHADER_GROUP_CREATE_INFO_KHR,
+        ty:
+            if intersection != vk::SHADER_UNUSED_KHR {
+                vk::RayTracingShaderGroupTypeKHR::PROCEDURAL_HIT_GROUP
+            } else {
+                vk::RayTracingShaderGroupTypeKHR::TRIANGLES_HIT_GROUP
+            },
+        general_shader: vk::SHADER_UNUSED_KHR,
+        closest_hit_shader: closest_hit,
+        any_hit_shader: any_hit,
+        intersection_shader: intersection,
+        ..Default::default()
+    };
+    let shaders = || shader_set.shaders()
+        .iter()
+        .enumerate()
+        .map(|(i, module)| (i as u32, module.stage()));
+    for (i, _) in shaders().filter(|&(_, stage)| stage == ShaderStage::RayGeneration) {
+        groups.push(general(i));
+    }
+    for (i, _) in shaders().filter(|&(_, stage)| stage == ShaderStage::Miss) {
+        groups.push(general(i));
+    }
+    let mut has_closest_hit = false;
+    for (i, _) in shaders().filter(|&(_, stage)| stage == ShaderStage::ClosestHit) {
+        groups.push(hit(i));
+        has_closest_hit = true;
+    }
+    if !has_closest_hit && (any_hit != vk::SHADER_UNUSED_KHR || intersection != vk::SHADER_UNUSED_KHR) {
+        groups.push(hit(vk::SHADER_UNUSED_KHR));
+    }
+    for (i, _) in shaders().filter(|&(_, stage)| stage == ShaderStage::Callable) {
+        groups.push(general(i));
+    }
+}
+
+/// Creates the shader binding table of `pipeline` and wraps both in a [`RayTracingPipeline`].
+///
+/// The handles of each group are laid out with a stride of the handle size aligned to the
+/// handle alignment and each region starts at the base alignment.
+fn create_pipeline<Alloc>(
+    gpu: &Gpu,
+    device: &ext::ray_tracing_pipeline::Device,
+    pipeline: vk::Pipeline,
+    shader_set: ShaderSet,
+    counts: GroupCounts,
+    tmp_alloc: &Alloc,
+) -> Result<RayTracingPipeline>
+    where Alloc: ?Sized + LocalAlloc<Error = arena::Error>
+{
+    let handle_size = gpu
+        .get_device_attribute(ext::ray_tracing_pipeline::Attributes::SHADER_GROUP_HANDLE_SIZE)
+        .u32().unwrap_or_default() as DeviceSize;
+    let handle_alignment = gpu
+        .get_device_attribute(ext::ray_tracing_pipeline::Attributes::SHADER_GROUP_HANDLE_ALIGNMENT)
+        .u32().unwrap_or(1) as DeviceSize;
+    let base_alignment = gpu
+        .get_device_attribute(ext::ray_tracing_pipeline::Attributes::SHADER_GROUP_BASE_ALIGNMENT)
+        .u32().unwrap_or(1) as DeviceSize;
+    let stride = handle_size.next_multiple_of(handle_alignment);
+    let group_count = counts.total();
+    let mut handles = FixedVec32::with_len(
+        group_count * handle_size as u32, 0u8, tmp_alloc,
+    ).context("alloc failed")?;
+    unsafe {
+        device.get_ray_tracing_shader_group_handles(
+            pipeline, 0, group_count, &mut handles,
+        ).context("failed to get shader group handles")?;
+    }
+    // (first group, group count, stride, size) of raygen, miss, hit and callable regions
+    let raygen_stride = stride.next_multiple_of(base_alignment);
+    let regions = [
+        (0, 1, raygen_stride, raygen_stride),
+        (1, counts.miss, stride, counts.miss as DeviceSize * stride),
+        (1 + counts.miss, counts.hit, stride, counts.hit as DeviceSize * stride),
+        (1 + counts.miss + counts.hit, counts.callable, stride, counts.callable as DeviceSize * stride),
+    ];
+    let table_size: DeviceSize = regions
+        .iter()
+        .map(|&(_, _, _, size)| size.next_multiple_of(base_alignment))
+        .sum();
+    let binder = GlobalBinder::new(
+        gpu.device().clone(),
+        MemoryProperties::HOST_VISIBLE | MemoryProperties::HOST_COHERENT,
+        MemoryProperties::HOST_VISIBLE | MemoryProperties::HOST_COHERENT,
+    );
+    let mut unused_id = Default::default();
+    // the buffer is over-allocated by the base alignment, so the table can be aligned
+    let create_info = BufferCreateInfo::new(
+        &mut unused_id,
+        &binder,
+        table_size + base_alignment,
+        BufferUsages::SHADER_BINDING_TABLE | BufferUsages::SHADER_DEVICE_ADDRESS,
+    ).ok_or_else(|| Error::just_context("shader binding table size was zero"))?
+    .with_name("shader binding table");
+    let mut bind_info = Default::default();
+    let mut buffer = create_info.build(gpu.device().clone(), &mut bind_info)
+        .context("failed to create shader binding table buffer")?;
+    unsafe {
+        gpu.device().bind_buffer_memory2(&[bind_info])
+            .context("failed to bind shader binding table memory")?;
+    }
+    let buffer_address = buffer.device_address()?;
+    let table_address = buffer_address.next_multiple_of(base_alignment);
+    let map = buffer
+        .memory_mut()
+        .unwrap()
+        .map_memory()
+        .context("failed to map shader binding table memory")?;
+    let mut vk_regions = [vk::StridedDeviceAddressRegionKHR::default(); 4];
+    let mut offset = table_address - buffer_address;
+    for (i, &(first_group, count, stride, size)) in regions.iter().enumerate() {
+        if count == 0 {
+            continue
+        }
+        for group in 0..count {
+            let src = ((first_group + group) as DeviceSize * handle_size) as usize;
+            let dst = (offset + group as DeviceSize * stride) as usize;
+            // shader binding table memory is host coherent, so the write doesn't need to be flushed
+            unsafe {
+                handles[src..src + handle_size as usize].as_ptr()
+                    .copy_to_nonoverlapping(map.map.add(dst), handle_size as usize);
+            }
+        }
+        vk_regions[i] = vk::StridedDeviceAddressRegionKHR {
+            device_address: buffer_address + offset,
+            stride,
+            size,
+        };
+        offset += size.next_multiple_of(base_alignment);
+    }
+    let [raygen_region, miss_region, hit_region, callable_region] = vk_regions;
+    Ok(RayTracingPipeline {
+        handle: unsafe {
+            PipelineHandle::with_shader_binding_table(
+                gpu.device().clone(), pipeline, shader_set, buffer,
+            )
+        },
+        raygen_region,
+        miss_region,
+        hit_region,
+        callable_region,
+        miss_group_count: counts.miss,
+        hit_group_count: counts.hit,
+        callable_group_count: counts.callable,
+    })
+}
+
+pub(super) async fn create_ray_tracing_pipelines(
+    gpu: &Gpu,
+    cache: Option<&PipelineCache>,
+    reloaded: Option<&ShaderSet>,
+    create_infos: &[RayTracingPipelineCreateTemplate],
+) -> Result<Vec32<RayTracingPipeline>>
+{
+    let pipeline_count = create_infos.len() as u32;
+    if pipeline_count == 0 {
+        return Ok(Default::default())
+    }
+    let device = gpu.get_extension_device::<ext::ray_tracing_pipeline::Device>()
+        .ok_or_else(|| Error::just_context("ray_tracing_pipeline device extension is not enabled"))?;
+    let max_recursion_depth = gpu
+        .get_device_attribute(ext::ray_tracing_pipeline::Attributes::MAX_RAY_RECURSION_DEPTH)
+        .u32().unwrap_or_default();
+    let tmp_alloc = gpu.tmp_alloc();
+    let mut shader_sets = FixedVec32
+        ::with_capacity(pipeline_count, &tmp_alloc)
+        .context("alloc failed")?;
+    let mut group_counts_all = FixedVec32
+        ::with_capacity(pipeline_count, &tmp_alloc)
+        .context("alloc failed")?;
+    let mut stage_count = 0;
+    let mut group_count = 0;
+    for info in create_infos {
+        if info.max_recursion_depth > max_recursion_depth {
+            return Err(Error::just_context(format!(
+                "max recursion depth {} is greater than the max ray recursion depth {max_recursion_depth}",
+                info.max_recursion_depth,
+            )))
+        }
+        let shader_set = match reloaded {
+            Some(shader_set) => shader_set.clone(),
+            None => gpu
+                .get_shader_set(info.shader_set_id)
+                .await
+                .context_with(|| format!(
+                    "failed to get shader set {}",
+                    info.shader_set_id,
+                ))?.clone(),
+        };
+        let counts = group_counts(&shader_set, info.shader_set_id)?;
+        stage_count += shader_set.shaders().len() as u32;
+        group_count += counts.total();
+        group_counts_all.push(counts);
+        shader_sets.push(shader_set);
+    }
+    let mut stages = FixedVec32
+        ::with_capacity(stage_count, &tmp_alloc)
+        .context("alloc failed")?;
+    let mut groups = FixedVec32
+        ::with_capacity(group_count, &tmp_alloc)
+        .context("alloc failed")?;
+    let mut vk_infos = FixedVec32
+        ::with_capacity(pipeline_count, &tmp_alloc)
+        .context("alloc failed")?;
+    for (i, info) in create_infos.iter().enumerate() {
+        let shader_set = &shader_sets[i];
+        let first_stage = stages.len();
+        let first_group = groups.len();
+        push_groups(shader_set, &mut stages, &mut groups);
+        // Safe because FixedVec doesn't reallocate
+        vk_infos.push(vk::RayTracingPipelineCreateInfoKHR {
+            s_type: vk::StructureType::RAY_TRACING_PIPELINE_CREATE_INFO_KHR,
+            flags: shader_set.pipeline_create_flags(),
+            stage_count: stages.len() - first_stage,
+            p_stages: stages[first_stage as usize..].as_ptr(),
+            group_count: groups.len() - first_group,
+            p_groups: groups[first_group as usize..].as_ptr(),
+            max_pipeline_ray_recursion_depth: info.max_recursion_depth,
+            layout: shader_set.pipeline_layout(),
+            ..Default::default()
+        });
+    }
+    let mut pipelines = FixedVec32
+        ::with_len(pipeline_count, vk::Pipeline::null(), &tmp_alloc)
+        .context("alloc failed")?;
+    unsafe {
+        let pipeline_cache = cache
+            .map(|cache| cache.handle().into_inner())
+            .unwrap_or(vk::PipelineCache::null());
+        device.create_ray_tracing_pipelines(
+            vk::DeferredOperationKHR::null(),
+            pipeline_cache,
+            &vk_infos,
+            None,
+            &mut pipelines,
+        ).context("failed to create ray tracing pipelines")?;
+    }
+    let mut ray_tracing_pipelines = Vec32::with_capacity(pipeline_count);
+    for (i, shader_set) in shader_sets.into_iter().enumerate() {
+        let pipeline = create_pipeline(
+            gpu, &device, pipelines[i], shader_set, group_counts_all[i], &*tmp_alloc,
+        );
+        match pipeline {
+            Ok(pipeline) => ray_tracing_pipelines.push(pipeline),
+            Err(err) => {
+                unsafe {
+                    for &pipeline in &pipelines[i..] {
+                        gpu.device().destroy_pipeline(pipeline, None);
+                    }
+                }
+                return Err(err)
+            },
+        }
+    }
+    unsafe {
+        tmp_alloc.clear();
+    }
+    Ok(ray_tracing_pipelines)
+}
@@ -0,0 +1,791 @@
+use nox_ash::{
+    vk,
+    ash_style_enum,
+};
+
+use nox_proc::Display;
+
+use nox_mem::{
+    slot_map::SlotIndex,
+    vec::Vec32,
+    vec32,
+};
+
+use crate::{
+    gpu::prelude::*,
+    error::*,
+};
+
+ash_style_enum! {
+
+    /// Specifies which pipeline statistics are counted by a
+    /// [`pipeline statistics`][1] query.
+    ///
+    /// Requires [`BaseDeviceFeatures::pipeline_statistics_query`] to be enabled.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/VkQueryPipelineStatisticFlagBits.html>
+    ///
+    /// [1]: QueryType::PipelineStatistics
+    #[flags(Flags32)]
+    #[default = Self::empty()]
+    pub enum PipelineStatistics {
+        /// Counts the number of vertices processed by the input assembly stage.
+        #[display("input assembly vertices")]
+        INPUT_ASSEMBLY_VERTICES = vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES.as_raw(),
+        /// Counts the number of primitives processed by the input assembly stage.
+        #[display("input assembly primitives")]
+        INPUT_ASSEMBLY_PRIMITIVES = vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES.as_raw(),
+        /// Counts the number of vertex shader invocations.
+        #[display("vertex shader invocations")]
+        VERTEX_SHADER_INVOCATIONS = vk::QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS.as_raw(),
+        /// Counts the number of geometry shader invocations.
+        #[display("geometry shader invocations")]
+        GEOMETRY_SHADER_INVOCATIONS = vk::QueryPipelineStatisticFlags::GEOMETRY_SHADER_INVOCATIONS.as_raw(),
+        /// Counts the number of primitives generated by geometry shader invocations.
+        #[display("geometry shader primitives")]
+        GEOMETRY_SHADER_PRIMITIVES = vk::QueryPipelineStatisticFlags::GEOMETRY_SHADER_PRIMITIVES.as_raw(),
+        /// Counts the number of primitives processed by the primitive clipping stage.
+        #[display("clipping invocations")]
+        CLIPPING_INVOCATIONS = vk::QueryPipelineStatisticFlags::CLIPPING_INVOCATIONS.as_raw(),
+        /// Counts the number of primitives output by the primitive clipping stage.
+        #[display("clipping primitives")]
+        CLIPPING_PRIMITIVES = vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES.as_raw(),
+        /// Counts the number of fragment shader invocations.
+        #[display("fragment shader invocations")]
+        FRAGMENT_SHADER_INVOCATIONS = vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS.as_raw(),
+        /// Counts the number of patches processed by the tessellation control shader.
+        #[display("tessellation control shader patches")]
+        TESSELLATION_CONTROL_SHADER_PATCHES =
+            vk::QueryPipelineStatisticFlags::TESSELLATION_CONTROL_SHADER_PATCHES.as_raw(),
+        /// Counts the number of tessellation evaluation shader invocations.
+        #[display("tessellation evaluation shader invocations")]
+        TESSELLATION_EVALUATION_SHADER_INVOCATIONS =
+            vk::QueryPipelineStatisticFlags::TESSELLATION_EVALUATION_SHADER_INVOCATIONS.as_raw(),
+        /// Counts the number of compute shader invocations.
+        #[display("compute shader invocations")]
+        COMPUTE_SHADER_INVOCATIONS = vk::QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS.as_raw(),
+    }
+
+    /// Specifies the pipeline stage at which a timestamp is written.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/refpages/latest/refpages/source/vkCmdWriteTimestamp2.html>
+    #[flags(Flags64)]
+    pub enum TimestampStage {
+        /// The timestamp is written once all previously submitted commands have started.
+        #[display("top of pipe")]
+        TOP_OF_PIPE = vk::PipelineStageFlags2::TOP_OF_PIPE.as_raw(),
+        /// The stage where vertex and index buffers are consumed.
+        #[display("vertex input")]
+        VERTEX_INPUT = vk::PipelineStageFlags2::VERTEX_INPUT.as_raw(),
+        /// The stage where vertex shaders execute.
+        #[display("vertex shader")]
+        VERTEX_SHADER = vk::PipelineStageFlags2::VERTEX_SHADER.as_raw(),
+        /// The stage where fragment shaders execute.
+        #[display("fragment shader")]
+        FRAGMENT_SHADER = vk::PipelineStageFlags2::FRAGMENT_SHADER.as_raw(),
+        /// The stage where colors are output from a graphics pipeline.
+        #[display("color output")]
+        COLOR_OUTPUT = vk::PipelineStageFlags2::COLOR_ATTACHMENT_OUTPUT.as_raw(),
+        /// The stage where compute shaders execute.
+        #[display("compute shader")]
+        COMPUTE_SHADER = vk::PipelineStageFlags2::COMPUTE_SHADER.as_raw(),
+        /// The stage where all transfer commands execute.
+        #[display("transfer")]
+        TRANSFER = vk::PipelineStageFlags2::TRANSFER.as_raw(),
+        /// The timestamp is written once all previously submitted commands have completed.
+        #[display("bottom of pipe")]
+        BOTTOM_OF_PIPE = vk::PipelineStageFlags2::BOTTOM_OF_PIPE.as_raw(),
+    }
+}
+
+impl Flags for PipelineStatistics {
+
+    const NAME: &str = "pipeline statistics";
+}
+
+impl From<PipelineStatistics> for vk::QueryPipelineStatisticFlags {
+
+    #[inline(always)]
+    fn from(value: PipelineStatistics) -> Self {
+        Self::from_raw(value.as_raw())
+    }
+}
+
+impl From<TimestampStage> for vk::PipelineStageFlags2 {
+
+    #[inline(always)]
+    fn from(value: TimestampStage) -> Self {
+        Self::from_raw(value.as_raw())
+    }
+}
+
+impl PipelineStatistics {
+
+    /// Statistics that are counted by graphics pipelines.
+    pub const GRAPHICS: Self = Self::from_raw(
+        Self::INPUT_ASSEMBLY_VERTICES.as_raw() |
+        Self::INPUT_ASSEMBLY_PRIMITIVES.as_raw() |
+        Self::VERTEX_SHADER_INVOCATIONS.as_raw() |
+        Self::GEOMETRY_SHADER_INVOCATIONS.as_raw() |
+        Self::GEOMETRY_SHADER_PRIMITIVES.as_raw() |
+        Self::CLIPPING_INVOCATIONS.as_raw() |
+        Self::CLIPPING_PRIMITIVES.as_raw() |
+        Self::FRAGMENT_SHADER_INVOCATIONS.as_raw() |
+        Self::TESSELLATION_CONTROL_SHADER_PATCHES.as_raw() |
+        Self::TESSELLATION_EVALUATION_SHADER_INVOCATIONS.as_raw()
+    );
+}
+
+/// Specifies the type of queries in a query pool.
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkQueryType.html>
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+pub enum QueryType {
+    /// Each query stores a single timestamp written with `write_timestamp`.
+    ///
+    /// Timestamps are in device ticks, use [`DeviceLimits::timestamp_period`] to convert them to
+    /// nanoseconds.
+    #[display("timestamp")]
+    Timestamp,
+    /// Each query counts the samples passing the depth and stencil tests between `begin_query`
+    /// and `end_query`.
+    #[display("occlusion")]
+    Occlusion,
+    /// Each query stores one counter per enabled [`PipelineStatistics`] flag, in the order of the
+    /// flag bits.
+    #[display("pipeline statistics {0}")]
+    PipelineStatistics(PipelineStatistics),
+    /// Each query stores the compacted size of an acceleration structure, written with
+    /// [`ComputeCommands::write_acceleration_structure_compacted_size`].
+    ///
+    /// Requires [`ext::acceleration_structure`].
+    #[display("acceleration structure compacted size")]
+    AccelerationStructureCompactedSize,
+}
+
+impl QueryType {
+
+    /// Returns the number of [`u64`] values each query of this type produces.
+    #[inline(always)]
+    pub fn values_per_query(self) -> u32 {
+        match self {
+            Self::Timestamp | Self::Occlusion | Self::AccelerationStructureCompactedSize => 1,
+            Self::PipelineStatistics(statistics) => statistics.as_raw().count_ones(),
+        }
+    }
+}
+
+impl From<QueryType> for vk::QueryType {
+
+    #[inline(always)]
+    fn from(value: QueryType) -> Self {
+        match value {
+            QueryType::Timestamp => Self::TIMESTAMP,
+            QueryType::Occlusion => Self::OCCLUSION,
+            QueryType::PipelineStatistics(_) => Self::PIPELINE_STATISTICS,
+            QueryType::AccelerationStructureCompactedSize =>
+                Self::ACCELERATION_STRUCTURE_COMPACTED_SIZE_KHR,
+        }
+    }
+}
+
+#[must_use]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+#[display("{0}")]
+pub struct QueryPoolId(pub(crate) SlotIndex<QueryPoolMeta>);
+
+impl ResourceId<QueryPoolMeta> for QueryPoolId {
+
+    #[inline(always)]
+    fn slot_index(self) -> SlotIndex<QueryPoolMeta> {
+        self.0
+    }
+}
+
+/// Results of all queries written by a single command.
+///
+/// Returned by [`Gpu::get_query_results`].
+#[derive(Clone, Debug)]
+pub struct QueryResults {
+    frame: u64,
+    values_per_query: u32,
+    queries: Vec32<u32>,
+    values: Vec32<u64>,
+}
+
+impl QueryResults {
+
+    /// Returns the frame the queries were written in.
+    #[inline(always)]
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Returns the number of values each query has.
+    #[inline(always)]
+    pub fn values_per_query(&self) -> u32 {
+        self.values_per_query
+    }
+
+    /// Gets the values of `query`, if it was written by the command.
+    #[inline(always)]
+    pub fn get(&self, query: u32) -> Option<&[u64]> {
+        self.queries
+            .iter()
+            .position(|&q| q == query)
+            .map(|i| {
+                let start = i * self.values_per_query as usize;
+                &self.values[start..start + self.values_per_query as usize]
+            })
+    }
+
+    /// Iterates over query indices and their values in ascending query order.
+    #[inline(always)]
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &[u64])> {
+        self.queries
+            .iter()
+            .copied()
+            .zip(self.values.chunks_exact(self.values_per_query as usize))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct QueryRecord {
+    command_id: CommandId,
+    frame: u64,
+    active: bool,
+}
+
+pub struct QueryPoolMeta {
+    device: LogicalDevice,
+    handle: vk::QueryPool,
+    query_type: QueryType,
+    records: Vec32<Option<QueryRecord>>,
+    last_used_frame: u64,
+}
+
+impl ResourceMeta for QueryPoolMeta {
+
+    const NAME: &str = "query pool";
+}
+
+impl QueryPoolMeta {
+
+    pub(crate) fn new(
+        device: LogicalDevice,
+        query_type: QueryType,
+        query_count: u32,
+    ) -> Result<Self>
+    {
+        if query_count == 0 {
+            return Err(Error::just_context("query count must be greater than zero"))
+        }
+        let features = device.base_device_features();
+        let pipeline_statistics = match query_type {
+            QueryType::PipelineStatistics(statistics) => {
+                if !features.pipeline_statistics_query {
+                    return Err(Error::just_context(
+                        "pipeline statistics queries require pipeline_statistics_query base device feature"
+                    ))
+                }
+                if statistics.is_empty() {
+                    return Err(Error::just_context(
+                        "pipeline statistics query pool must count at least one statistic"
+                    ))
+                }
+                if statistics.intersects(
+                    PipelineStatistics::GEOMETRY_SHADER_INVOCATIONS |
+                    PipelineStatistics::GEOMETRY_SHADER_PRIMITIVES
+                ) && !features.geometry_shader {
+                    return Err(Error::just_context(format!(
+                        "{statistics} require geometry_shader base device feature",
+                    )))
+                }
+                if statistics.intersects(
+                    PipelineStatistics::TESSELLATION_CONTROL_SHADER_PATCHES |
+                    PipelineStatistics::TESSELLATION_EVALUATION_SHADER_INVOCATIONS
+                ) && !features.tessellation_shader {
+                    return Err(Error::just_context(format!(
+                        "{statistics} require tessellation_shader base device feature",
+                    )))
+                }
+                statistics.into()
+            },
+            QueryType::AccelerationStructureCompactedSize => {
+                if !device
+                    .get_device_attribute(ext::acceleration_structure::Attributes::IS_ENABLED)
+                    .bool().unwrap_or(false)
+                {
+                    return Err(Error::just_context(
+                        "acceleration structure compacted size queries require acceleration_structure device extension"
+                    ))
+                }
+                vk::QueryPipelineStatisticFlags::empty()
+            },
+            _ => vk::QueryPipelineStatisticFlags::empty(),
+        };
+        let create_info = vk::QueryPoolCreateInfo {
+            s_type: vk::StructureType::QUERY_POOL_CREATE_INFO,
+            query_type: query_type.into(),
+            query_count,
+            pipeline_statistics,
+            ..Default::default()
+        };
+        let handle = unsafe {
+            device.create_query_pool(&create_info, None)
+                .context("failed to create Vulkan query pool")?
+        };
+        Ok(Self {
+            device,
+            handle,
+            query_type,
+            records: vec32![None; query_count],
+            last_used_frame: 0,
+        })
+    }
+
+    #[inline(always)]
+    pub fn query_type(&self) -> QueryType {
+        self.query_type
+    }
+
+    #[inline(always)]
+    pub fn query_count(&self) -> u32 {
+        self.records.len()
+    }
+
+    #[inline(always)]
+    pub fn last_used_frame(&self) -> u64 {
+        self.last_used_frame
+    }
+
+    fn record_mut(&mut self, query: u32) -> Result<&mut Option<QueryRecord>> {
+        let query_count = self.records.len();
+        self.records
+            .get_mut(query as usize)
+            .ok_or_else(|| Error::just_context(format!(
+                "query {query} is out of range for query pool with {query_count} queries",
+            )))
+    }
+
+    /// Records a timestamp write to `query`.
+    ///
+    /// # Safety
+    /// `command_buffer` *must* be a valid command buffer in the recording state and it *must* be
+    /// submitted on the frame `frame`.
+    pub(crate) unsafe fn cmd_write_timestamp(
+        &mut self,
+        command_buffer: vk::CommandBuffer,
+        queue: &DeviceQueue,
+        stage: TimestampStage,
+        query: u32,
+        command_id: CommandId,
+        frame: u64,
+    ) -> Result<()>
+    {
+        if self.query_type != QueryType::Timestamp {
+            return Err(Error::just_context(format!(
+                "attempting to write timestamp to a {} query pool", self.query_type,
+            )))
+        }
+        if queue.queue_family_properties().timestamp_valid_bits == 0 {
+            return Err(Error::just_context(format!(
+                "queue {queue} doesn't support timestamps"
+            )))
+        }
+        let record = self.record_mut(query)?;
+        if record.is_some_and(|record| record.active) {
+            return Err(Error::just_context(format!(
+                "query {query} is active"
+            )))
+        }
+        *record = Some(QueryRecord {
+            command_id,
+            frame,
+            active: false,
+        });
+        self.last_used_frame = frame;
+        unsafe {
+            self.device.cmd_reset_query_pool(command_buffer, self.handle, query, 1);
+            self.device.cmd_write_timestamp2(
+                command_buffer,
+                stage.into(),
+                self.handle,
+                query,
+            );
+        }
+        Ok(())
+    }
+
+    /// Records a compacted size write of `acceleration_structure` to `query`.
+    ///
+    /// # Safety
+    /// `command_buffer` *must* be a valid command buffer in the recording state, the
+    /// acceleration structure *must* have been built with
+    /// [`BuildAccelerationStructureFlags::ALLOW_COMPACTION`] before this command and it *must* be
+    /// submitted on the frame `frame`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) unsafe fn cmd_write_acceleration_structure_compacted_size(
+        &mut self,
+        command_buffer: vk::CommandBuffer,
+        acceleration_structure_device: &ext::acceleration_structure::Device,
+        acceleration_structure: vk::AccelerationStructureKHR,
+        query: u32,
+        command_id: CommandId,
+        frame: u64,
+    ) -> Result<()>
+    {
+        if self.query_type != QueryType::AccelerationStructureCompactedSize {
+            return Err(Error::just_context(format!(
+                "attempting to write acceleration structure compacted size to a {} query pool",
+                self.query_type,
+            )))
+        }
+        let record = self.record_mut(query)?;
+        if record.is_some_and(|record| record.active) {
+            return Err(Error::just_context(format!(
+                "query {query} is active"
+            )))
+        }
+        *record = Some(QueryRecord {
+            command_id,
+            frame,
+            active: false,
+        });
+        self.last_used_frame = frame;
+        unsafe {
+            self.device.cmd_reset_query_pool(command_buffer, self.handle, query, 1);
+            acceleration_structure_device.cmd_write_acceleration_structures_properties(
+                command_buffer,
+                &[acceleration_structure],
+                self.query_type.into(),
+                self.handle,
+                query,
+            );
+        }
+        Ok(())
+    }
+
+    /// Records the beginning of `query`.
+    ///
+    /// # Safety
+    /// `command_buffer` *must* be a valid command buffer in the recording state, it *must* not be
+    /// inside a render pass instance and it *must* be submitted on the frame `frame`.
+    pub(crate) unsafe fn cmd_begin_query(
+        &mut self,
+        command_buffer: vk::CommandBuffer,
+        queue: &DeviceQueue,
+        query: u32,
+        precise: bool,
+        command_id: CommandId,
+        frame: u64,
+    ) -> Result<()>
+    {
+        let mut flags = vk::QueryControlFlags::empty();
+        match self.query_type {
+            QueryType::Timestamp => {
+                return Err(Error::just_context(
+                    "timestamp queries can't be begun, use write_timestamp instead"
+                ))
+            },
+            QueryType::AccelerationStructureCompactedSize => {
+                return Err(Error::just_context(
+                    "acceleration structure compacted size queries can't be begun, use write_acceleration_structure_compacted_size instead"
+                ))
+            },
+            QueryType::Occlusion => {
+                if !queue.queue_flags().contains(QueueFlags::GRAPHICS) {
+                    return Err(Error::just_context(format!(
+                        "queue {queue} doesn't support occlusion queries"
+                    )))
+                }
+                if precise {
+                    if !self.device.base_device_features().occlusion_query_precise {
+                        return Err(Error::just_context(
+                            "precise occlusion queries require occlusion_query_precise base device feature"
+                        ))
+                    }
+                    flags |= vk::QueryControlFlags::PRECISE;
+                }
+            },
+            QueryType::PipelineStatistics(statistics) => {
+                if precise {
+                    return Err(Error::just_context(
+                        "only occlusion queries can be precise"
+                    ))
+                }
+                if statistics.intersects(PipelineStatistics::GRAPHICS) &&
+                    !queue.queue_flags().contains(QueueFlags::GRAPHICS)
+                {
+                    return Err(Error::just_context(format!(
+                        "queue {queue} doesn't support graphics pipeline statistics"
+                    )))
+                }
+                if statistics.contains(PipelineStatistics::COMPUTE_SHADER_INVOCATIONS) &&
+                    !queue.queue_flags().contains(QueueFlags::COMPUTE)
+                {
+                    return Err(Error::just_context(format!(
+                        "queue {queue} doesn't support compute pipeline statistics"
+                    )))
+                }
+            },
+        }
+        let record = self.record_mut(query)?;
+        if record.is_some_and(|record| record.active) {
+            return Err(Error::just_context(format!(
+                "query {query} is already active"
+            )))
+        }
+        *record = Some(QueryRecord {
+            command_id,
+            frame,
+            active: true,
+        });
+        self.last_used_frame = frame;
+        unsafe {
+            self.device.cmd_reset_query_pool(command_buffer, self.handle, query, 1);
+            self.device.cmd_begin_query(command_buffer, self.handle, query, flags);
+        }
+        Ok(())
+    }
+
+    /// Records the end of `query`.
+    ///
+    /// # Safety
+    /// `command_buffer` *must* be the same command buffer the query was begun in.
+    pub(crate) unsafe fn cmd_end_query(
+        &mut self,
+        command_buffer: vk::CommandBuffer,
+        query: u32,
+        command_id: CommandId,
+    ) -> Result<()>
+    {
+        let record = self.record_mut(query)?;
+        match record {
+            Some(record) if record.active && record.command_id == command_id => {
+                record.active = false;
+            },
+            _ => return Err(Error::just_context(format!(
+                "query {query} was not begun by this command"
+            ))),
+        }
+        unsafe {
+            self.device.cmd_end_query(command_buffer, self.handle, query);
+        }
+        Ok(())
+    }
+
+    /// Gets the results of the queries last written by `command_id`.
+    ///
+    /// Returns [`None`] if the frame the queries were written in hasn't finished yet or if
+    /// `command_id` hasn't written to this pool.
+    pub(crate) fn get_results(
+        &self,
+        command_id: CommandId,
+        finished_frame: u64,
+    ) -> Result<Option<QueryResults>>
+    {
+        let Some(frame) = self.records
+            .iter()
+            .filter_map(|record| record
+                .filter(|record| record.command_id == command_id && !record.active)
+                .map(|record| record.frame)
+            ).max()
+        else {
+            return Ok(None)
+        };
+        if frame > finished_frame {
+            return Ok(None)
+        }
+        let values_per_query = self.query_type.values_per_query();
+        let mut results = QueryResults {
+            frame,
+            values_per_query,
+            queries: vec32![],
+            values: vec32![],
+        };
+        for (query, record) in self.records.iter().enumerate() {
+            if !record.is_some_and(|record|
+                record.command_id == command_id &&
+                record.frame == frame &&
+                !record.active
+            ) {
+                continue
+            }
+            let query = query as u32;
+            let start = results.values.len();
+            results.values.resize(start + values_per_query, 0);
+            let values = &mut results.values[start as usize..];
+            let res = unsafe {
+                self.device.get_query_pool_results(
+                    self.handle,
+                    query, 1,
+                    size_of_val(values),
+                    values.as_mut_ptr().cast(),
+                    size_of::<u64>() as vk::DeviceSize * values_per_query as vk::DeviceSize,
+                    vk::QueryResultFlags::TYPE_64,
+                ).context_with(|| format!(
+                    "failed to get results of query {query}"
+                ))?
+            };
+            if res == vk::Result::NOT_READY {
+                return Ok(None)
+            }
+            results.queries.push(query);
+        }
+        Ok(Some(results))
+    }
+}
+
+impl Drop for QueryPoolMeta {
+
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_query_pool(self.handle, None);
+        }
+    }
+}
+
+/// Tracks queries begun by a command, so that all of them are ended before the command finishes.
+#[derive(Default)]
+pub(crate) struct CommandQueries {
+    active: Vec32<(QueryPoolId, u32)>,
+}
+
+impl CommandQueries {
+
+    /// # Safety
+    /// `command_buffer` *must* be a valid command buffer in the recording state, it *must* belong
+    /// to `command_id` and it *must* be submitted on the frame `frame`.
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn write_timestamp(
+        &mut self,
+        gpu: &Gpu,
+        command_buffer: vk::CommandBuffer,
+        queue: &DeviceQueue,
+        command_id: CommandId,
+        frame: u64,
+        pool_id: QueryPoolId,
+        query: u32,
+        stage: TimestampStage,
+    ) -> Result<()>
+    {
+        let mut pools = gpu.write_query_pools();
+        let pool = pools.get_mut(pool_id)?;
+        unsafe {
+            pool.cmd_write_timestamp(
+                command_buffer, queue, stage, query,
+                command_id, frame,
+            )
+        }.context_with(|| format!(
+            "failed to write timestamp to query pool {pool_id}"
+        ))
+    }
+
+    /// # Safety
+    /// `command_buffer` *must* be a valid command buffer in the recording state, it *must* belong
+    /// to `command_id` and it *must* be submitted on the frame `frame`.
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn write_acceleration_structure_compacted_size(
+        &mut self,
+        gpu: &Gpu,
+        command_buffer: vk::CommandBuffer,
+        acceleration_structure_device: &ext::acceleration_structure::Device,
+        acceleration_structure: vk::AccelerationStructureKHR,
+        command_id: CommandId,
+        frame: u64,
+        pool_id: QueryPoolId,
+        query: u32,
+    ) -> Result<()>
+    {
+        let mut pools = gpu.write_query_pools();
+        let pool = pools.get_mut(pool_id)?;
+        unsafe {
+            pool.cmd_write_acceleration_structure_compacted_size(
+                command_buffer, acceleration_structure_device, acceleration_structure,
+                query, command_id, frame,
+            )
+        }.context_with(|| format!(
+            "failed to write acceleration structure compacted size to query pool {pool_id}"
+        ))
+    }
+
+    /// # Safety
+    /// `command_buffer` *must* be a valid command buffer in the recording state, it *must* belong
+    /// to `command_id`, it *must* not be inside a render pass instance and it *must* be submitted
+    /// on the frame `frame`.
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn begin_query(
+        &mut self,
+        gpu: &Gpu,
+        command_buffer: vk::CommandBuffer,
+        queue: &DeviceQueue,
+        command_id: CommandId,
+        frame: u64,
+        pool_id: QueryPoolId,
+        query: u32,
+        precise: bool,
+    ) -> Result<()>
+    {
+        if self.active.iter().any(|&(id, _)| id == pool_id) {
+            return Err(Error::just_context(format!(
+                "a query of query pool {pool_id} is already active"
+            )))
+        }
+        let mut pools = gpu.write_query_pools();
+        let pool = pools.get_mut(pool_id)?;
+        unsafe {
+            pool.cmd_begin_query(
+                command_buffer, queue, query, precise,
+                command_id, frame,
+            )
+        }.context_with(|| format!(
+            "failed to begin query of query pool {pool_id}"
+        ))?;
+        self.active.push((pool_id, query));
+        Ok(())
+    }
+
+    /// # Safety
+    /// `command_buffer` *must* be the command buffer used to begin the query.
+    pub unsafe fn end_query(
+        &mut self,
+        gpu: &Gpu,
+        command_buffer: vk::CommandBuffer,
+        command_id: CommandId,
+        pool_id: QueryPoolId,
+        query: u32,
+    ) -> Result<()>
+    {
+        let Some(index) = self.active
+            .iter()
+            .position(|&active| active == (pool_id, query))
+        else {
+            return Err(Error::just_context(format!(
+                "query {query} of query pool {pool_id} is not active"
+            )))
+        };
+        let mut pools = gpu.write_query_pools();
+        let pool = pools.get_mut(pool_id)?;
+        unsafe {
+            pool.cmd_end_query(command_buffer, query, command_id)
+        }.context_with(|| format!(
+            "failed to end query of query pool {pool_id}"
+        ))?;
+        self.active.remove(index as u32);
+        Ok(())
+    }
+
+    /// Returns an error if any queries are still active.
+    pub fn finish(&self) -> Result<()> {
+        if let Some(&(pool_id, query)) = self.active.first() {
+            return Err(Error::just_context(format!(
+                "query {query} of query pool {pool_id} was never ended"
+            )))
+        }
+        Ok(())
+    }
+}
@@ -22,6 +22,7 @@ pub struct QueueFamilyProperties {
     pub queue_flags: QueueFlags,
     pub queue_count: u32,
     pub min_image_transfer_granularity: Dimensions,
+    pub timestamp_valid_bits: u32,
 }
 
 #[derive(Clone)]
@@ -111,6 +112,11 @@ impl DeviceQueue {
         self.inner.handle
     }
 
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     #[inline]
     pub fn device_id(&self) -> LogicalDeviceId {
         self.inner.device_id
@@ -189,6 +195,7 @@ impl QueueFamilies {
                     queue_flags: QueueFlags::from_raw(p1.queue_flags.as_raw()),
                     queue_count: p1.queue_count,
                     min_image_transfer_granularity: p1.min_image_transfer_granularity.into(),
+                    timestamp_valid_bits: p1.timestamp_valid_bits,
                 }
             }).collect();
         Self {
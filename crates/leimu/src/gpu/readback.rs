@@ -0,0 +1,222 @@
+use core::{
+    pin::Pin,
+    future::Future,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use std::time::Instant;
+
+use nox_mem::vec::Vec32;
+
+use crate::{
+    gpu::prelude::*,
+    error::*,
+    sync::{atomic::{self, AtomicBool}, *},
+};
+
+/// The contents of a buffer or an image, read back from device memory by [`Gpu::read_buffer`]
+/// or [`Gpu::read_image`].
+pub struct Readback {
+    bytes: Vec<u8>,
+    row_pitch: DeviceSize,
+    extent: Option<Dimensions>,
+}
+
+impl Readback {
+
+    #[inline]
+    pub(crate) fn new(
+        bytes: Vec<u8>,
+        row_pitch: DeviceSize,
+        extent: Option<Dimensions>,
+    ) -> Self {
+        Self {
+            bytes,
+            row_pitch,
+            extent,
+        }
+    }
+
+    /// Returns the bytes read back.
+    ///
+    /// Image texels are tightly packed, with the layout described in
+    /// [`CopyCommands::copy_image_to_buffer`].
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Consumes self and returns the bytes read back.
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the number of bytes between the starts of two consecutive rows of texel blocks.
+    ///
+    /// For buffers, this is the size of the range read back.
+    #[inline]
+    pub fn row_pitch(&self) -> DeviceSize {
+        self.row_pitch
+    }
+
+    /// Returns the extent of the image region read back or [`None`] for buffers.
+    #[inline]
+    pub fn extent(&self) -> Option<Dimensions> {
+        self.extent
+    }
+}
+
+#[derive(Default)]
+struct ReadbackState {
+    result: Option<Result<Readback>>,
+    resolved: bool,
+    wakers: Vec32<Waker>,
+}
+
+impl ReadbackState {
+
+    fn resolve(&mut self, result: Result<Readback>) {
+        if !self.resolved {
+            self.resolved = true;
+            self.result = Some(result);
+        }
+        while let Some(waker) = self.wakers.pop() {
+            waker.wake();
+        }
+    }
+}
+
+/// A read back, which is polled by [`Gpu::tick`] until its frame has finished executing.
+pub(crate) struct ReadbackRequest {
+    staging_id: BufferId,
+    size: DeviceSize,
+    row_pitch: DeviceSize,
+    extent: Option<Dimensions>,
+    frame: u64,
+    failed: Arc<AtomicBool>,
+    submitted_at: Option<Instant>,
+    state: Arc<Mutex<ReadbackState>>,
+}
+
+impl ReadbackRequest {
+
+    /// Creates a request for reading back `size` bytes of `staging_id`, once frame `frame` has
+    /// finished executing.
+    ///
+    /// `failed` is set if recording the copy to the staging buffer fails.
+    pub fn new(
+        staging_id: BufferId,
+        size: DeviceSize,
+        row_pitch: DeviceSize,
+        extent: Option<Dimensions>,
+        frame: u64,
+        failed: Arc<AtomicBool>,
+    ) -> (Self, PendingReadback)
+    {
+        let state = Arc::new(Mutex::new(ReadbackState::default()));
+        (Self {
+            staging_id,
+            size,
+            row_pitch,
+            extent,
+            frame,
+            failed,
+            submitted_at: None,
+            state: state.clone(),
+        }, PendingReadback { state })
+    }
+
+    /// Polls the request without blocking.
+    ///
+    /// `submitted_frame` is the last frame submitted and `finished_frame` is the last frame,
+    /// which has finished executing. The frame timeout is measured from the first poll, which
+    /// sees the frame of the request submitted.
+    ///
+    /// Returns whether the request is done, in which case its staging buffer has been
+    /// destroyed. A request, which timed out, resolves with an error, but keeps its staging
+    /// buffer until the frame has finished, since the copy *can* still be in flight.
+    pub fn poll(
+        &mut self,
+        gpu: &Gpu,
+        submitted_frame: u64,
+        finished_frame: u64,
+        timeout: Duration,
+    ) -> bool
+    {
+        let failed = self.failed.load(atomic::Ordering::Acquire);
+        if failed || finished_frame >= self.frame {
+            let result =
+                if failed {
+                    Err(Error::just_context("failed to record read back copy"))
+                } else {
+                    self.read(gpu)
+                };
+            self.state.lock().resolve(result);
+            gpu.destroy_resources([self.staging_id], []).ok();
+            return true
+        }
+        if submitted_frame >= self.frame {
+            let submitted_at = *self.submitted_at.get_or_insert_with(Instant::now);
+            if submitted_at.elapsed() > timeout {
+                self.state.lock().resolve(Err(Error::just_context(format!(
+                    "read back frame {} didn't finish executing within the frame timeout",
+                    self.frame,
+                ))));
+            }
+        }
+        false
+    }
+
+    /// Resolves the request with an error without destroying its staging buffer.
+    pub fn abandon(&self) {
+        self.state.lock().resolve(Err(Error::just_context("read back was abandoned")));
+    }
+
+    fn read(&self, gpu: &Gpu) -> Result<Readback> {
+        // read back memory is host coherent, so it doesn't need to be invalidated
+        let map = gpu.map_buffer(self.staging_id)?;
+        let bytes = unsafe {
+            core::slice::from_raw_parts(map.map, self.size as usize)
+        }.to_vec();
+        Ok(Readback::new(bytes, self.row_pitch, self.extent))
+    }
+}
+
+/// A [`Future`], which resolves with the contents read back by [`Gpu::read_buffer`] or
+/// [`Gpu::read_image`].
+///
+/// The future is woken when [`Gpu::tick`] notices the frame of the read back has finished
+/// executing, so the [`Gpu`] *must* keep ticking for it to resolve.
+pub struct PendingReadback {
+    state: Arc<Mutex<ReadbackState>>,
+}
+
+impl PendingReadback {
+
+    /// Returns whether the read back has resolved.
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        self.state.lock().resolved
+    }
+}
+
+impl Future for PendingReadback {
+
+    type Output = Result<Readback>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock();
+        if state.resolved {
+            return Poll::Ready(state.result
+                .take()
+                .unwrap_or_else(|| Err(Error::just_context("read back was already resolved")))
+            )
+        }
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
@@ -7,6 +7,12 @@ use core::{
     ptr::NonNull,
 };
 
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+    fs,
+};
+
 use nox_ash::vk;
 
 use nox_spirv::{
@@ -36,16 +42,60 @@ use crate::{
 
 pub use enums::*;
 
+/// Specifies how an `#include` directive was written.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IncludeKind {
+    /// `#include "file"`, which is searched from the directory of the including file first.
+    Relative,
+    /// `#include <file>`, which is only searched from include directories.
+    Standard,
+}
+
+/// A file resolved by an [`include resolver`][1].
+///
+/// [1]: ShaderAttributes::with_include_resolver
+pub struct ResolvedInclude {
+    /// The name of the resolved file.
+    ///
+    /// This is reported in compile errors and relative includes of the file are resolved from it.
+    pub name: String,
+    /// The Glsl source of the resolved file.
+    pub content: String,
+}
+
+/// Resolves `#include` directives.
+///
+/// The parameters are the requested name, the [`kind`][1] of the include and the name of the
+/// including file.
+///
+/// [1]: IncludeKind
+pub type IncludeResolver = Arc<
+    dyn Fn(&str, IncludeKind, &str) -> Option<ResolvedInclude> + Send + Sync
+>;
+
+/// Preprocessor options used when compiling Glsl.
+#[derive(Default, Clone)]
+pub(crate) struct GlslOptions {
+    pub include_dirs: Vec32<PathBuf>,
+    pub include_resolver: Option<IncludeResolver>,
+    pub defines: Vec32<(Box<str>, Box<str>)>,
+}
+
 #[derive(Clone, Copy)]
 pub enum ShaderSource<'a> {
     Spirv(&'a [u32]),
     Glsl(&'a str),
+    /// Glsl read from a file, which is read again each time the shader is [`reloaded`][1].
+    ///
+    /// [1]: Shader::reload
+    GlslFile(&'a Path),
 }
 
 #[derive(Clone)]
 pub enum ShaderSourceOwned {
     Spirv(Box<[u32]>),
     Glsl(Box<str>),
+    GlslFile(Box<Path>),
 }
 
 impl<'a> ShaderSource<'a> {
@@ -56,6 +106,21 @@ impl<'a> ShaderSource<'a> {
         stage: ShaderStage,
         name: &str,
     ) -> Result<ShaderSourceCompiled>
+    {
+        self.compile_with(api_version, stage, name, &Default::default(), &mut Vec::new())
+    }
+
+    /// Compiles the source with Glsl preprocessor `options`.
+    ///
+    /// The paths of all files included by Glsl are pushed to `included_files`.
+    pub(crate) fn compile_with(
+        self,
+        api_version: Version,
+        stage: ShaderStage,
+        name: &str,
+        options: &GlslOptions,
+        included_files: &mut Vec<PathBuf>,
+    ) -> Result<ShaderSourceCompiled>
     {
         match self {
             Self::Spirv(bin) => unsafe {
@@ -72,9 +137,17 @@ impl<'a> ShaderSource<'a> {
                     stage,
                 })
             },
+            Self::GlslFile(path) => {
+                let input = fs::read_to_string(path)
+                    .context_with(|| format!("failed to read {}", path.display()))?;
+                Self::Glsl(&input).compile_with(
+                    api_version, stage, &path.to_string_lossy(), options, included_files,
+                )
+            },
             Self::Glsl(input) => unsafe {
-                let comp = glsl_to_spirv(input, name, stage.into(), api_version)
-                .context("failed to compile glsl to spirv")?;
+                let comp = glsl_to_spirv(
+                    input, name, stage.into(), api_version, options, included_files,
+                ).context("failed to compile glsl to spirv")?;
                 let bin = comp.as_binary();
                 let len = bin.len();
                 assert!(len <= u32::MAX as usize);
@@ -97,8 +170,44 @@ impl<'a> ShaderSource<'a> {
         match *self {
             Self::Spirv(bin) => ShaderSourceOwned::Spirv(Box::from(bin)),
             Self::Glsl(glsl) => ShaderSourceOwned::Glsl(Box::from(glsl)),
+            Self::GlslFile(path) => ShaderSourceOwned::GlslFile(Box::from(path)),
         }
     }
+
+    /// Returns the source file, if this is a [`file`][1] source.
+    ///
+    /// [1]: Self::GlslFile
+    fn path(&self) -> Option<&'a Path> {
+        let Self::GlslFile(path) = self else {
+            return None
+        };
+        Some(path)
+    }
+}
+
+/// The files a [`Shader`] was compiled from, which are polled for modifications.
+#[derive(Default)]
+struct SourceFiles {
+    included: Vec<PathBuf>,
+    modified: Option<SystemTime>,
+}
+
+impl SourceFiles {
+
+    /// Returns the latest modification time of `source` and `included`.
+    fn latest_modified<'a>(
+        source: Option<&Path>,
+        included: impl IntoIterator<Item = &'a PathBuf>,
+    ) -> Option<SystemTime>
+    {
+        source
+            .into_iter()
+            .chain(included.into_iter().map(|path| &**path))
+            .filter_map(|path| fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+            ).max()
+    }
 }
 
 impl ShaderSourceOwned {
@@ -108,6 +217,7 @@ impl ShaderSourceOwned {
         match self {
             ShaderSourceOwned::Spirv(bin) => ShaderSource::Spirv(bin),
             ShaderSourceOwned::Glsl(glsl) => ShaderSource::Glsl(glsl),
+            ShaderSourceOwned::GlslFile(path) => ShaderSource::GlslFile(path),
         }
     }
 }
@@ -173,6 +283,7 @@ pub struct ShaderAttributes<'a> {
     stage: ShaderStage,
     name: &'a str,
     entry_point: &'a CStr,
+    glsl_options: GlslOptions,
 }
 
 pub struct ShaderAttributesOwned {
@@ -180,6 +291,7 @@ pub struct ShaderAttributesOwned {
     stage: ShaderStage,
     name: Arc<str>,
     entry_point: Arc<CStr>,
+    glsl_options: GlslOptions,
 }
 
 impl<'a> ShaderAttributes<'a> {
@@ -207,6 +319,22 @@ impl<'a> ShaderAttributes<'a> {
         self
     }
 
+    /// Sets the source of the shader to a Glsl file at `path`. The file needs to contain valid
+    /// Glsl for Vulkan.
+    ///
+    /// The file is read when the shader is compiled and again each time the shader is
+    /// [`reloaded`][1], which makes it possible to [`hot reload`][2] shaders.
+    ///
+    /// The default source is `None`.
+    ///
+    /// [1]: Shader::reload
+    /// [2]: Gpu::reload_modified_shader_sets
+    #[inline(always)]
+    pub fn with_glsl_file(mut self, path: &'a Path) -> Self {
+        self.source = Some(ShaderSource::GlslFile(path));
+        self
+    }
+
     /// Sets the `stage` of the shader.
     ///
     /// The default stage is [`ShaderStage::Unknown`].
@@ -221,7 +349,10 @@ impl<'a> ShaderAttributes<'a> {
 
     /// Sets the `name` for the shader. The default name is an empty string.
     /// 
-    /// The name is used mainly for debugging.
+    /// The name is used mainly for debugging, and it's given to the shader module as its
+    /// [`object name`][1].
+    ///
+    /// [1]: LogicalDevice::set_object_name
     #[inline(always)]
     pub fn with_name(mut self, name: &'a str) -> Self {
         self.name = name;
@@ -237,6 +368,48 @@ impl<'a> ShaderAttributes<'a> {
         self
     }
 
+    /// Adds directories, which are searched for files included with `#include` in Glsl.
+    ///
+    /// Directories are searched in the order they were added. Relative includes
+    /// (`#include "file"`) are searched from the directory of the including file first.
+    ///
+    /// By default, no directories are searched.
+    #[inline(always)]
+    pub fn with_include_dirs<I>(mut self, dirs: I) -> Self
+        where I: IntoIterator<Item = &'a Path>
+    {
+        self.glsl_options.include_dirs.extend(dirs
+            .into_iter()
+            .map(|dir| dir.to_path_buf())
+        );
+        self
+    }
+
+    /// Sets a resolver for `#include` directives in Glsl, e.g. for a virtual filesystem.
+    ///
+    /// The resolver is called before searching [`include directories`][1]. If it returns
+    /// [`None`], include directories are searched as usual.
+    ///
+    /// The default resolver is `None`.
+    ///
+    /// [1]: Self::with_include_dirs
+    #[inline(always)]
+    pub fn with_include_resolver<F>(mut self, resolver: F) -> Self
+        where F: Fn(&str, IncludeKind, &str) -> Option<ResolvedInclude> + Send + Sync + 'static
+    {
+        self.glsl_options.include_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Defines a preprocessor macro `name` with `value` for Glsl.
+    ///
+    /// If `value` is empty, the macro is defined without a value.
+    #[inline(always)]
+    pub fn with_define(mut self, name: &str, value: &str) -> Self {
+        self.glsl_options.defines.push((name.into(), value.into()));
+        self
+    }
+
     #[inline(always)]
     pub fn to_owned(self) -> ShaderAttributesOwned {
         ShaderAttributesOwned {
@@ -244,6 +417,7 @@ impl<'a> ShaderAttributes<'a> {
             stage: self.stage,
             name: self.name.into(),
             entry_point: self.entry_point.into(),
+            glsl_options: self.glsl_options,
         }
     }
 }
@@ -367,15 +541,22 @@ impl From<PushConstantRange> for vk::PushConstantRange {
 
 #[derive(Clone)]
 pub struct ShaderInner {
+    name: Arc<str>,
     compiled: ShaderSourceCompiled,
     entry_point: Arc<CStr>,
     uniforms: Vec32<Uniform>,
     push_constant_ranges: Vec32<PushConstantRange>,
+    task_payload_size: Option<u32>,
     stage: ShaderStage,
 }
 
 impl ShaderInner {
 
+    #[inline(always)]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     #[inline(always)]
     pub fn source(&self) -> &ShaderSourceCompiled {
         &self.compiled
@@ -400,11 +581,29 @@ impl ShaderInner {
     pub fn push_constant_ranges(&self) -> &[PushConstantRange] {
         &self.push_constant_ranges
     }
+
+    /// Returns the size of the `TaskPayloadWorkgroupEXT` variable of a task or mesh shader.
+    ///
+    /// For task shaders this is the payload written for the mesh shader workgroups it launches
+    /// and for mesh shaders the payload read from the task shader.
+    #[inline(always)]
+    pub fn task_payload_size(&self) -> Option<u32> {
+        self.task_payload_size
+    }
 }
 
+/// A shader, which is compiled and reflected asynchronously on the thread pool of a [`Gpu`].
+///
+/// Shaders keep their [`attributes`][1], so they *can* be [`reloaded`][2] from their original
+/// source.
+///
+/// [1]: ShaderAttributes
+/// [2]: Self::reload
 #[derive(Clone)]
 pub struct Shader {
-    inner: Arc<FutureLock<ShaderInner, RemoteHandle<Result<ShaderInner>>>>
+    inner: Arc<FutureLock<ShaderInner, RemoteHandle<Result<ShaderInner>>>>,
+    attributes: Arc<ShaderAttributesOwned>,
+    source_files: Arc<Mutex<SourceFiles>>,
 }
 
 /// Creates default [`ShaderAttributes`].
@@ -419,6 +618,7 @@ pub fn default_shader_attributes<'a>() -> ShaderAttributes<'a> {
         stage: ShaderStage::Vertex,
         name: "",
         entry_point: c"main",
+        glsl_options: Default::default(),
     }
 }
 
@@ -429,16 +629,81 @@ impl Shader {
         attributes: ShaderAttributes<'_>,
     ) -> Result<Self>
     {
-        let attributes = attributes.to_owned();
+        Self::with_owned_attributes(gpu, Arc::new(attributes.to_owned()))
+    }
+
+    /// Compiles this shader again from its original source and returns the result as a new
+    /// shader.
+    ///
+    /// [`File`][1] sources are read again, other sources are compiled as they were given.
+    ///
+    /// [1]: ShaderAttributes::with_glsl_file
+    pub fn reload(&self, gpu: &Gpu) -> Result<Self> {
+        Self::with_owned_attributes(gpu, self.attributes.clone())
+    }
+
+    /// Returns whether `self` and `other` are the same shader.
+    #[inline(always)]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Returns whether the source file of this shader or any file it includes was modified since
+    /// the shader was compiled or this was last called.
+    ///
+    /// Always returns `false` for shaders, which don't have a [`file source`][1] and don't
+    /// include files.
+    ///
+    /// [1]: ShaderAttributes::with_glsl_file
+    pub fn poll_source_modified(&self) -> bool {
+        let mut source_files = self.source_files.lock();
+        let Some(modified) = SourceFiles::latest_modified(
+            self.attributes.source.as_ref().and_then(|source| source.borrow().path()),
+            &source_files.included,
+        ) else {
+            return false
+        };
+        if source_files.modified.is_some_and(|last| last >= modified) {
+            return false
+        }
+        source_files.modified = Some(modified);
+        true
+    }
+
+    fn with_owned_attributes(
+        gpu: &Gpu,
+        attributes: Arc<ShaderAttributesOwned>,
+    ) -> Result<Self>
+    {
+        let source_files = Arc::new(Mutex::new(SourceFiles {
+            included: Vec::new(),
+            modified: SourceFiles::latest_modified(
+                attributes.source.as_ref().and_then(|source| source.borrow().path()),
+                [],
+            ),
+        }));
         let g = gpu.clone();
+        let fut_attributes = attributes.clone();
+        let fut_source_files = source_files.clone();
         let fut = async move {
-            let Some(source) = attributes.source else {
+            let attributes = fut_attributes;
+            let Some(source) = &attributes.source else {
                 return Err(Error::just_context("no source given for shader"))
             };
+            let mut included = Vec::new();
             let compiled = source
                 .borrow()
-                .compile(g.device().api_version(), attributes.stage, &attributes.name)
-                .context("failed to compile shader")?;
+                .compile_with(
+                    g.device().api_version(), attributes.stage, &attributes.name,
+                    &attributes.glsl_options, &mut included,
+                );
+            let mut source_files = fut_source_files.lock();
+            source_files.modified = source_files.modified.max(
+                SourceFiles::latest_modified(None, &included)
+            );
+            source_files.included = included;
+            drop(source_files);
+            let compiled = compiled.context("failed to compile shader")?;
             let spirv = compiled.spirv();
             let stage = attributes.stage;
             let module = Module
@@ -461,7 +726,8 @@ impl Shader {
                     (ResourceType::SeparateImage, DescriptorType::SampledImage),
                     (ResourceType::SeparateSampler, DescriptorType::Sampler),
                     (ResourceType::UniformTexelBuffer, DescriptorType::UniformTexelBuffer),
-                    (ResourceType::StorageTexelBuffer, DescriptorType::StorageTexelBuffer)
+                    (ResourceType::StorageTexelBuffer, DescriptorType::StorageTexelBuffer),
+                    (ResourceType::AccelerationStructure, DescriptorType::AccelerationStructure)
                 ]
             {
                 for resource in reflector
@@ -537,11 +803,32 @@ impl Shader {
                         .size_hint.declared() as u32,
                 });
             }
+            let mut task_payload_size = None;
+            if matches!(stage, ShaderStage::Task | ShaderStage::Mesh) {
+                for payload in reflector
+                    .resources_for_type(ResourceType::TaskPayload)
+                    .context("failed to reflect TaskPayload")?
+                {
+                    let payload = payload.context("failed to reflect TaskPayload")?;
+                    if task_payload_size.is_some() {
+                        return Err(Error::just_context(format!(
+                            "{stage} shader declares more than one task payload"
+                        )))
+                    }
+                    task_payload_size = Some(reflector
+                        .type_description(payload.base_type_id)
+                        .context("failed to reflect TaskPayload")?
+                        .size_hint.declared() as u32
+                    );
+                }
+            }
             Ok(ShaderInner {
+                name: attributes.name.clone(),
                 compiled,
-                entry_point: attributes.entry_point,
+                entry_point: attributes.entry_point.clone(),
                 uniforms,
                 push_constant_ranges,
+                task_payload_size,
                 stage,
             })
         };
@@ -550,7 +837,9 @@ impl Shader {
                 gpu.thread_pool()
                     .spawn_with_handle(fut)
                     .context("spawn error")?,
-            ))
+            )),
+            attributes,
+            source_files,
         })
     }
 
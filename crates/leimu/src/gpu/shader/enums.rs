@@ -24,11 +24,37 @@ nox_ash::ash_style_enum!(
         FRAGMENT = vk::ShaderStageFlags::FRAGMENT.as_raw(),
         #[display("compute")]
         COMPUTE = vk::ShaderStageFlags::COMPUTE.as_raw(),
+        #[display("task")]
+        TASK = vk::ShaderStageFlags::TASK_EXT.as_raw(),
+        #[display("mesh")]
+        MESH = vk::ShaderStageFlags::MESH_EXT.as_raw(),
+        #[display("ray generation")]
+        RAYGEN = vk::ShaderStageFlags::RAYGEN_KHR.as_raw(),
+        #[display("any hit")]
+        ANY_HIT = vk::ShaderStageFlags::ANY_HIT_KHR.as_raw(),
+        #[display("closest hit")]
+        CLOSEST_HIT = vk::ShaderStageFlags::CLOSEST_HIT_KHR.as_raw(),
+        #[display("miss")]
+        MISS = vk::ShaderStageFlags::MISS_KHR.as_raw(),
+        #[display("intersection")]
+        INTERSECTION = vk::ShaderStageFlags::INTERSECTION_KHR.as_raw(),
+        #[display("callable")]
+        CALLABLE = vk::ShaderStageFlags::CALLABLE_KHR.as_raw(),
     }
 );
 
 impl ShaderStageFlags {
 
+    /// All ray tracing shader stages.
+    pub const RAY_TRACING: Self = Self::from_raw(
+        Self::RAYGEN.as_raw() |
+        Self::ANY_HIT.as_raw() |
+        Self::CLOSEST_HIT.as_raw() |
+        Self::MISS.as_raw() |
+        Self::INTERSECTION.as_raw() |
+        Self::CALLABLE.as_raw()
+    );
+
     #[inline(always)]
     pub fn pipeline_stage_mask(self) -> vk::PipelineStageFlags2 {
         let mut mask = vk::PipelineStageFlags2::empty();
@@ -50,6 +76,15 @@ impl ShaderStageFlags {
         if self.contains(Self::COMPUTE) {
             mask |= vk::PipelineStageFlags2::COMPUTE_SHADER;
         }
+        if self.contains(Self::TASK) {
+            mask |= vk::PipelineStageFlags2::TASK_SHADER_EXT;
+        }
+        if self.contains(Self::MESH) {
+            mask |= vk::PipelineStageFlags2::MESH_SHADER_EXT;
+        }
+        if self.intersects(Self::RAY_TRACING) {
+            mask |= vk::PipelineStageFlags2::RAY_TRACING_SHADER_KHR;
+        }
         mask
     }
 }
@@ -70,6 +105,30 @@ pub enum ShaderStage {
     Fragment = ShaderStageFlags::FRAGMENT.as_raw(),
     #[display("compute")]
     Compute = ShaderStageFlags::COMPUTE.as_raw(),
+    /// Requires [`ext::mesh_shader`] with task shaders enabled.
+    #[display("task")]
+    Task = ShaderStageFlags::TASK.as_raw(),
+    /// Requires [`ext::mesh_shader`].
+    #[display("mesh")]
+    Mesh = ShaderStageFlags::MESH.as_raw(),
+    /// Requires [`ext::ray_tracing_pipeline`].
+    #[display("ray generation")]
+    RayGeneration = ShaderStageFlags::RAYGEN.as_raw(),
+    /// Requires [`ext::ray_tracing_pipeline`].
+    #[display("any hit")]
+    AnyHit = ShaderStageFlags::ANY_HIT.as_raw(),
+    /// Requires [`ext::ray_tracing_pipeline`].
+    #[display("closest hit")]
+    ClosestHit = ShaderStageFlags::CLOSEST_HIT.as_raw(),
+    /// Requires [`ext::ray_tracing_pipeline`].
+    #[display("miss")]
+    Miss = ShaderStageFlags::MISS.as_raw(),
+    /// Requires [`ext::ray_tracing_pipeline`].
+    #[display("intersection")]
+    Intersection = ShaderStageFlags::INTERSECTION.as_raw(),
+    /// Requires [`ext::ray_tracing_pipeline`].
+    #[display("callable")]
+    Callable = ShaderStageFlags::CALLABLE.as_raw(),
 }
 
 impl ShaderStage {
@@ -78,6 +137,12 @@ impl ShaderStage {
     pub fn as_raw(self) -> u32 {
         self as u32
     }
+
+    /// Returns whether this is a ray tracing shader stage.
+    #[inline]
+    pub fn is_ray_tracing(self) -> bool {
+        ShaderStageFlags::from(self).intersects(ShaderStageFlags::RAY_TRACING)
+    }
 }
 
 impl From<ShaderStage> for ShaderStageFlags {
@@ -114,6 +179,14 @@ impl From<ShaderStage> for shaderc::ShaderKind {
             ShaderStage::Geometry => Self::Geometry,
             ShaderStage::Fragment => Self::Fragment,
             ShaderStage::Compute => Self::Compute,
+            ShaderStage::Task => Self::Task,
+            ShaderStage::Mesh => Self::Mesh,
+            ShaderStage::RayGeneration => Self::RayGeneration,
+            ShaderStage::AnyHit => Self::AnyHit,
+            ShaderStage::ClosestHit => Self::ClosestHit,
+            ShaderStage::Miss => Self::Miss,
+            ShaderStage::Intersection => Self::Intersection,
+            ShaderStage::Callable => Self::Callable,
         }
     }
 }
@@ -129,6 +202,14 @@ impl From<ShaderStage> for op::ExecutionModel {
             ShaderStage::Geometry => op::ExecutionModel::GEOMETRY,
             ShaderStage::Fragment => op::ExecutionModel::FRAGMENT,
             ShaderStage::Compute => op::ExecutionModel::KERNEL,
+            ShaderStage::Task => op::ExecutionModel::TASK_EXT,
+            ShaderStage::Mesh => op::ExecutionModel::MESH_EXT,
+            ShaderStage::RayGeneration => op::ExecutionModel::RAY_GENERATION_KHR,
+            ShaderStage::AnyHit => op::ExecutionModel::ANY_HIT_KHR,
+            ShaderStage::ClosestHit => op::ExecutionModel::CLOSEST_HIT_KHR,
+            ShaderStage::Miss => op::ExecutionModel::MISS_KHR,
+            ShaderStage::Intersection => op::ExecutionModel::INTERSECTION_KHR,
+            ShaderStage::Callable => op::ExecutionModel::CALLABLE_KHR,
         }
     }
 }
@@ -212,26 +293,28 @@ pub enum DescriptorType {
     /// A type associated with a buffer and a buffer view that can be used for image sampling
     /// operations.
     ///
-    /// Descriptors with this descriptor type *must* be written through a [`buffer write`][1] that
-    /// contains a [`BufferId`] to a buffer that *can* be used as a [`uniform texel buffer`][2].
+    /// Descriptors with this descriptor type *must* be written through a [`texel buffer write`][1]
+    /// that contains a [`BufferViewId`] to a view of a buffer that *can* be used as a
+    /// [`uniform texel buffer`][2].
     ///
     /// # Shader declarations
     /// Glsl: `uniform samplerBuffer ...`
     ///
-    /// [1]: DescriptorInfos::buffers
+    /// [1]: DescriptorInfos::texel_buffers
     /// [2]: BufferUsages::UNIFORM_TEXEL_BUFFER
     #[display("uniform texel buffer")]
     UniformTexelBuffer = vk::DescriptorType::UNIFORM_TEXEL_BUFFER.as_raw(),
     /// A type associated with a buffer and a buffer view that can be used for image load,
     /// store and atomic operations.
     ///
-    /// Descriptors with this descriptor type *must* be written through a [`buffer write`][1] that
-    /// contains a [`BufferId`] to a buffer that *can* be used as a [`storage texel buffer`][2].
+    /// Descriptors with this descriptor type *must* be written through a [`texel buffer write`][1]
+    /// that contains a [`BufferViewId`] to a view of a buffer that *can* be used as a
+    /// [`storage texel buffer`][2].
     ///
     /// # Shader declarations
     /// Glsl: `uniform imageBuffer ...`
     ///
-    /// [1]: DescriptorInfos::buffers
+    /// [1]: DescriptorInfos::texel_buffers
     /// [2]: BufferUsages::STORAGE_TEXEL_BUFFER
     #[display("storage texel buffer")]
     StorageTexelBuffer = vk::DescriptorType::STORAGE_TEXEL_BUFFER.as_raw(),
@@ -263,6 +346,19 @@ pub enum DescriptorType {
     /// [2]: ImageUsages::INPUT_ATTACHMENT
     #[display("input attachment")]
     InputAttachment = vk::DescriptorType::INPUT_ATTACHMENT.as_raw(),
+    /// A type associated with an [`acceleration structure`][1], which can be traced against with
+    /// ray tracing pipelines or ray queries.
+    ///
+    /// Descriptors with this descriptor type *must* be written through an
+    /// [`acceleration structure write`][2].
+    ///
+    /// # Shader declarations
+    /// Glsl: `uniform accelerationStructureEXT ...`
+    ///
+    /// [1]: AccelerationStructureId
+    /// [2]: DescriptorInfos::acceleration_structures
+    #[display("acceleration structure")]
+    AccelerationStructure = vk::DescriptorType::ACCELERATION_STRUCTURE_KHR.as_raw(),
 }
 
 /// ``` rust
@@ -333,11 +429,24 @@ impl DescriptorType {
         )
     }
 
+    #[inline(always)]
+    pub fn is_texel_buffer(self) -> bool {
+        matches!(self,
+            Self::UniformTexelBuffer |
+            Self::StorageTexelBuffer
+        )
+    }
+
     #[inline(always)]
     pub fn is_inline_uniform_block(self) -> bool {
         matches!(self, Self::InlineUniformBlock)
     }
 
+    #[inline(always)]
+    pub fn is_acceleration_structure(self) -> bool {
+        matches!(self, Self::AccelerationStructure)
+    }
+
     #[inline(always)]
     pub fn is_image(self) -> bool {
         matches!(self, image_descriptor_types!())
@@ -410,6 +519,7 @@ impl From<vk::DescriptorType> for DescriptorType {
             vk::DescriptorType::UNIFORM_TEXEL_BUFFER => Self::UniformTexelBuffer,
             vk::DescriptorType::STORAGE_TEXEL_BUFFER => Self::StorageTexelBuffer,
             vk::DescriptorType::INLINE_UNIFORM_BLOCK => Self::InlineUniformBlock,
+            vk::DescriptorType::ACCELERATION_STRUCTURE_KHR => Self::AccelerationStructure,
             _ => Self::Unknown,
         }
     }
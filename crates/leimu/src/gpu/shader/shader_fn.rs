@@ -1,11 +1,55 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    fs,
+};
+
 use crate::gpu::Version;
 
+use super::{GlslOptions, IncludeKind};
+
+/// Resolves an `#include` directive with the include resolver of `options` or by searching
+/// directories.
+///
+/// Relative includes are searched from the directory of the including file first.
+fn resolve_include(
+    options: &GlslOptions,
+    requested: &str,
+    kind: IncludeKind,
+    requesting: &str,
+) -> Result<(PathBuf, String), String>
+{
+    if let Some(resolver) = &options.include_resolver &&
+        let Some(resolved) = resolver(requested, kind, requesting)
+    {
+        return Ok((resolved.name.into(), resolved.content))
+    }
+    let relative_dir = (kind == IncludeKind::Relative)
+        .then(|| Path::new(requesting).parent())
+        .flatten();
+    for dir in relative_dir
+        .into_iter()
+        .chain(options.include_dirs.iter().map(|dir| &**dir))
+    {
+        let path = dir.join(requested);
+        if let Ok(content) = fs::read_to_string(&path) {
+            return Ok((path, content))
+        }
+    }
+    Err(format!("failed to resolve include {requested:?} from {requesting}"))
+}
+
+/// Compiles Glsl to Spir-V.
+///
+/// The paths of all included files are pushed to `included_files`.
 #[inline]
 pub fn glsl_to_spirv(
     input: &str,
     input_name: &str,
     shader_kind: shaderc::ShaderKind,
     vulkan_version: Version,
+    glsl_options: &GlslOptions,
+    included_files: &mut Vec<PathBuf>,
 ) -> shaderc::Result<shaderc::CompilationArtifact>
 {
     let compiler = shaderc::Compiler::new()?;
@@ -17,11 +61,31 @@ pub fn glsl_to_spirv(
     options.set_source_language(shaderc::SourceLanguage::GLSL);
     options.set_optimization_level(shaderc::OptimizationLevel::Performance);
     options.set_generate_debug_info();
-    compiler.compile_into_spirv(
+    for (name, value) in &glsl_options.defines {
+        options.add_macro_definition(name, (!value.is_empty()).then_some(&**value));
+    }
+    let included = RefCell::new(Vec::new());
+    options.set_include_callback(|requested, include_type, requesting, _depth| {
+        let kind = match include_type {
+            shaderc::IncludeType::Relative => IncludeKind::Relative,
+            shaderc::IncludeType::Standard => IncludeKind::Standard,
+        };
+        let (path, content) = resolve_include(glsl_options, requested, kind, requesting)?;
+        let resolved_name = path.to_string_lossy().into_owned();
+        included.borrow_mut().push(path);
+        Ok(shaderc::ResolvedInclude {
+            resolved_name,
+            content,
+        })
+    });
+    let result = compiler.compile_into_spirv(
         input,
         shader_kind,
         input_name,
         "main",
         Some(&options)
-    )
+    );
+    drop(options);
+    included_files.append(&mut included.into_inner());
+    result
 }
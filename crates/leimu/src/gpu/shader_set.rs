@@ -15,7 +15,7 @@ use nox_mem::{
     option::OptionExt,
     pack_alloc,
     slot_map::{SlotIndex, SlotMap},
-    vec::{ArrayVec, FixedVec32, Pointer, Vec32},
+    vec::{FixedVec32, Pointer, Vec32},
     vec32,
 };
 use nox_threads::{
@@ -50,9 +50,66 @@ nox_ash::ash_style_enum!(
         /// [1]: ext::push_descriptor
         #[display("push descriptor")]
         PUSH_DESCRIPTOR = 0x1,
+        /// Specifies that descriptors of the set *can* be updated after the set is bound and
+        /// while it's in use by a queue, as long as the updated descriptors are not used by
+        /// pending commands.
+        ///
+        /// Sampler, sampled image, storage image and storage buffer bindings *can* be updated
+        /// even when they are used by pending commands.
+        ///
+        /// # Valid usage
+        /// - The [`descriptor_indexing`][1] device extension *must* be enabled.
+        /// - This *must* not be combined with [`PUSH_DESCRIPTOR`][2].
+        ///
+        /// [1]: ext::descriptor_indexing
+        /// [2]: Self::PUSH_DESCRIPTOR
+        #[display("update after bind")]
+        UPDATE_AFTER_BIND = 0x2,
+        /// Specifies that descriptors of the set don't need to be written, unless they are
+        /// dynamically used by shaders.
+        ///
+        /// Descriptors referring to destroyed resources are cleared instead of invalidating the
+        /// whole set.
+        ///
+        /// # Valid usage
+        /// - The [`descriptor_indexing`][1] device extension *must* be enabled.
+        ///
+        /// [1]: ext::descriptor_indexing
+        #[display("partially bound")]
+        PARTIALLY_BOUND = 0x4,
+        /// Specifies that the binding with the largest binding number in the set has a variable
+        /// descriptor count, which is specified when [`allocating`][1] the set.
+        ///
+        /// The descriptor count of the layout is the upper bound of the variable count.
+        ///
+        /// # Valid usage
+        /// - The [`descriptor_indexing`][2] device extension *must* be enabled.
+        /// - This *must* not be combined with [`PUSH_DESCRIPTOR`][3].
+        ///
+        /// [1]: DescriptorSetInfo::with_variable_descriptor_count
+        /// [2]: ext::descriptor_indexing
+        /// [3]: Self::PUSH_DESCRIPTOR
+        #[display("variable descriptor count")]
+        VARIABLE_DESCRIPTOR_COUNT = 0x8,
     }
 );
 
+impl DescriptorSetLayoutFlags {
+
+    /// Returns whether any of the flags provided by the [`descriptor_indexing`][1] device
+    /// extension are set.
+    ///
+    /// [1]: ext::descriptor_indexing
+    #[inline(always)]
+    pub fn requires_descriptor_indexing(self) -> bool {
+        self.intersects(
+            Self::UPDATE_AFTER_BIND |
+            Self::PARTIALLY_BOUND |
+            Self::VARIABLE_DESCRIPTOR_COUNT
+        )
+    }
+}
+
 /// Contains the handle and metadata of a [`descriptor set layout`][1].
 ///
 /// [1]: https://docs.vulkan.org/refpages/latest/refpages/source/VkDescriptorSetLayout.html
@@ -62,6 +119,7 @@ pub struct DescriptorSetLayout {
     pub(crate) bindings: Vec32<DescriptorSetLayoutBinding>,
     pub(crate) stage_flags: ShaderStageFlags,
     pub(crate) flags: DescriptorSetLayoutFlags,
+    pub(crate) descriptor_buffer_layout: Option<DescriptorBufferLayout>,
 }
 
 impl DescriptorSetLayout {
@@ -77,15 +135,23 @@ struct ShaderSetInnerHandle(FutureLock<Arc<ShaderSetInner>, RemoteHandle<Result<
 #[derive(Clone)]
 struct ShaderSetHandle {
     inner: Arc<ShaderSetInnerHandle>,
+    shaders: Arc<[Shader]>,
+    attributes: Arc<ShaderSetAttributes>,
 }
 
 unsafe impl Sync for ShaderSetHandle {}
 
 impl ShaderSetHandle {
 
-    fn new(f: RemoteHandle<Result<Arc<ShaderSetInner>>>) -> Self {
+    fn new(
+        f: RemoteHandle<Result<Arc<ShaderSetInner>>>,
+        shaders: Arc<[Shader]>,
+        attributes: Arc<ShaderSetAttributes>,
+    ) -> Self {
         Self {
-            inner: Arc::new(ShaderSetInnerHandle(FutureLock::new(f)))
+            inner: Arc::new(ShaderSetInnerHandle(FutureLock::new(f))),
+            shaders,
+            attributes,
         }
     }
 
@@ -102,6 +168,7 @@ pub struct ShaderModule {
     handle: vk::ShaderModule,
     spirv: ShaderSourceCompiled,
     entry_point: Arc<CStr>,
+    task_payload_size: Option<u32>,
 }
 
 impl ShaderModule { 
@@ -128,6 +195,12 @@ impl ShaderModule {
     pub fn entry_point(&self) -> &CStr {
         &self.entry_point
     }
+
+    /// Gets the size of the task payload of a task or mesh shader module.
+    #[inline(always)]
+    pub fn task_payload_size(&self) -> Option<u32> {
+        self.task_payload_size
+    }
 }
 
 pub(crate) struct ShaderSetInner {
@@ -142,6 +215,7 @@ pub(crate) struct ShaderSetInner {
     pack_ptr: *mut u8,
     pack_layout: Layout,
     push_descriptor_bindings: AHashMap<Arc<CStr>, (u32, u32)>,
+    uses_descriptor_buffers: bool,
 }
 
 unsafe impl Send for ShaderSetInner {}
@@ -156,6 +230,7 @@ impl ShaderSetInner {
         push_constant_ranges: &[PushConstantRange],
         shaders: impl ExactSizeIterator<Item = ShaderModule>,
         pipeline_layout: vk::PipelineLayout,
+        uses_descriptor_buffers: bool,
     ) -> Self {
         let n_descriptor_set_layouts = descriptor_set_layouts.len() as u32;
         let n_push_constant_ranges = push_constant_ranges.len() as u32;
@@ -215,6 +290,7 @@ impl ShaderSetInner {
             pack_ptr: ptr,
             pack_layout: layout,
             push_descriptor_bindings,
+            uses_descriptor_buffers,
         } }
     } 
 }
@@ -246,6 +322,28 @@ impl ShaderSet {
         self.inner.n_descriptor_set_layouts
     }
 
+    /// Returns whether the descriptor set layouts of the shader set were created for
+    /// descriptor buffers.
+    ///
+    /// This is the case when the [`descriptor_buffer`][1] extension is enabled. Descriptor sets
+    /// of such shader sets are bound with [`PipelineCommands::bind_descriptor_buffers`].
+    ///
+    /// [1]: ext::descriptor_buffer
+    #[inline(always)]
+    pub fn uses_descriptor_buffers(&self) -> bool {
+        self.inner.uses_descriptor_buffers
+    }
+
+    /// Returns the pipeline create flags required by pipelines created with the shader set.
+    #[inline(always)]
+    pub(crate) fn pipeline_create_flags(&self) -> vk::PipelineCreateFlags {
+        if self.inner.uses_descriptor_buffers {
+            vk::PipelineCreateFlags::DESCRIPTOR_BUFFER_EXT
+        } else {
+            vk::PipelineCreateFlags::empty()
+        }
+    }
+
     #[inline(always)]
     pub fn descriptor_set_layouts(
         &self,
@@ -305,6 +403,44 @@ impl ShaderSet {
     }
 }
 
+impl ShaderSet {
+
+    /// Checks that pipelines and descriptor sets created for `self` *can* be used with `other`.
+    pub(crate) fn check_compatible(&self, other: &Self) -> Result<()> {
+        let (layouts, other_layouts) = (
+            self.descriptor_set_layouts(),
+            other.descriptor_set_layouts(),
+        );
+        if layouts.len() != other_layouts.len() {
+            return Err(Error::just_context(format!(
+                "descriptor set count changed from {} to {}",
+                layouts.len(), other_layouts.len(),
+            )))
+        }
+        for (set, (layout, other)) in layouts.iter().zip(other_layouts).enumerate() {
+            if layout.flags != other.flags || layout.bindings != other.bindings {
+                return Err(Error::just_context(format!(
+                    "descriptor set layout bindings of set {set} changed"
+                )))
+            }
+        }
+        let (ranges, other_ranges) = (
+            self.push_constant_ranges(),
+            other.push_constant_ranges(),
+        );
+        if ranges.len() != other_ranges.len() ||
+            ranges.iter().zip(other_ranges).any(|(a, b)|
+                a.stage != b.stage || a.offset != b.offset || a.size != b.size
+            )
+        {
+            return Err(Error::just_context(
+                "push constant ranges changed"
+            ))
+        }
+        Ok(())
+    }
+}
+
 impl Drop for ShaderSetInner {
 
     fn drop(&mut self) {
@@ -324,18 +460,37 @@ impl Drop for ShaderSetInner {
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)] #[display("{0}")]
 pub struct ShaderSetId(SlotIndex<ShaderSetHandle>);
 
+/// A shader set recompiled with [`ShaderCache::reload_shader_set`], which hasn't yet replaced
+/// the old shader set.
+pub(crate) struct ShaderSetReload {
+    id: ShaderSetId,
+    handle: ShaderSetHandle,
+}
+
+impl ShaderSetReload {
+
+    #[inline(always)]
+    pub async fn load(&self) -> Result<ShaderSet> {
+        self.handle.load().await
+            .cloned()
+            .map(|set| ShaderSet { inner: set })
+    }
+}
+
 #[derive(Clone)]
 pub struct ShaderSetAttributes {
     count_spec: AHashMap<(u32, u32), Vec32<SpecializationConstant<u32>>>,
     flags: AHashMap<u32, DescriptorSetLayoutFlags>,
     inline_uniform_blocks: AHashSet<(u32, u32)>,
+    runtime_array_counts: AHashMap<(u32, u32), u32>,
 }
 
 pub fn default_shader_set_attributes() -> ShaderSetAttributes {
     ShaderSetAttributes {
         count_spec: AHashMap::default(),
         flags: AHashMap::default(),
-        inline_uniform_blocks: AHashSet::default()
+        inline_uniform_blocks: AHashSet::default(),
+        runtime_array_counts: AHashMap::default(),
     }
 }
 
@@ -388,11 +543,49 @@ impl ShaderSetAttributes {
         self.inline_uniform_blocks.insert((set, binding));
         self
     }
+
+    /// Specifies the descriptor count of a `binding` in set index `set`, which is declared as a
+    /// runtime array (e.g. `uniform sampler2D textures[]` in Glsl).
+    ///
+    /// Without this, the descriptor count of runtime arrays is the count declared in the shader.
+    ///
+    /// If the set has the [`variable descriptor count flag`][1] and `binding` is its last
+    /// binding, `count` is the upper bound of the count specified at allocation.
+    ///
+    /// # Valid usage
+    /// - The [`descriptor_indexing`][2] device extension *must* be enabled.
+    ///
+    /// [1]: DescriptorSetLayoutFlags::VARIABLE_DESCRIPTOR_COUNT
+    /// [2]: ext::descriptor_indexing
+    #[inline(always)]
+    pub fn with_runtime_array_count(
+        mut self,
+        set: u32,
+        binding: u32,
+        count: u32,
+    ) -> Self {
+        self.runtime_array_counts.insert((set, binding), count);
+        self
+    }
+}
+
+/// Returns whether descriptors of `ty` *can* be updated while in use with the
+/// [`update after bind flag`][1].
+///
+/// [1]: DescriptorSetLayoutFlags::UPDATE_AFTER_BIND
+fn supports_update_after_bind(ty: DescriptorType) -> bool {
+    matches!(ty,
+        DescriptorType::Sampler |
+        DescriptorType::SampledImage |
+        DescriptorType::CombinedImageSampler |
+        DescriptorType::StorageImage |
+        DescriptorType::StorageBuffer
+    )
 }
 
 #[derive(Clone)]
 struct DescriptorSetLayoutKey {
-    _flags: DescriptorSetLayoutFlags,
+    flags: DescriptorSetLayoutFlags,
     bindings: Vec32<DescriptorSetLayoutBinding>,
     hash: u64,
 }
@@ -408,7 +601,7 @@ impl DescriptorSetLayoutKey {
         bindings.hash(&mut hasher);
         let hash = hasher.finish();
         Self {
-            _flags: flags,
+            flags,
             bindings,
             hash,
         }
@@ -419,6 +612,7 @@ impl PartialEq for DescriptorSetLayoutKey {
 
     #[inline(always)]
     fn eq(&self, other: &Self) -> bool {
+        self.flags == other.flags &&
         self.bindings == other.bindings
     }
 }
@@ -456,19 +650,51 @@ impl ShaderCache {
     } 
 
     #[inline(always)]
-    pub fn create_shader_set<const N_SHADERS: usize>(
+    pub fn create_shader_set(
         &mut self,
-        shaders: [Shader; N_SHADERS],
+        shaders: Arc<[Shader]>,
         attributes: ShaderSetAttributes,
         thread_pool: ThreadPool,
         tmp_allocs: Arc<TmpAllocs>,
     ) -> Result<ShaderSetId>
+    {
+        let handle = self.build_shader_set(
+            shaders,
+            Arc::new(attributes),
+            thread_pool,
+            tmp_allocs,
+        )?;
+        Ok(ShaderSetId(self.shader_sets.insert(handle)))
+    }
+
+    /// Compiles `shaders` into a new [`ShaderSetHandle`] on `thread_pool`.
+    fn build_shader_set(
+        &self,
+        shaders: Arc<[Shader]>,
+        attributes: Arc<ShaderSetAttributes>,
+        thread_pool: ThreadPool,
+        tmp_allocs: Arc<TmpAllocs>,
+    ) -> Result<ShaderSetHandle>
     {
         let descriptor_set_layout_cache = self.descriptor_set_layouts.clone();
         let device = self.device.clone();
         let max_push_descriptors = device
             .get_device_attribute(ext::push_descriptor::Attributes::MAX_PUSH_DESCRIPTORS)
             .u32();
+        let descriptor_buffer_device = device
+            .get_extension_device::<ext::descriptor_buffer::Device>();
+        let push_descriptor_buffers = device
+            .get_device_attribute(ext::descriptor_buffer::Attributes::IS_PUSH_DESCRIPTORS_ENABLED)
+            .bool().unwrap_or(false);
+        if (attributes.flags.values().any(|flags| flags.requires_descriptor_indexing()) ||
+            !attributes.runtime_array_counts.is_empty()) &&
+            !device.get_device_attribute(ext::descriptor_indexing::Attributes::IS_ENABLED)
+            .bool().unwrap_or(false)
+        {
+            return Err(Error::just_context(
+                "attempting to use descriptor indexing without enabling the extension"
+            ))
+        }
         if !attributes.inline_uniform_blocks.is_empty() &&
             !device.get_device_attribute(ext::inline_uniform_block::Attributes::IS_ENABLED)
             .bool().unwrap_or(false)
@@ -477,12 +703,16 @@ impl ShaderCache {
                 "attempting to use inline uniform blocks without enabling the extension"
             ))
         }
-        let index = self.shader_sets.insert(ShaderSetHandle::new(
+        let fut_shaders = shaders.clone();
+        let fut_attributes = attributes.clone();
+        Ok(ShaderSetHandle::new(
             thread_pool.spawn_with_handle(async move {
-                let mut shaders_inner = ArrayVec::<_, N_SHADERS>::new();
+                let shaders = fut_shaders;
+                let attributes = fut_attributes;
+                let mut shaders_inner = Vec32::with_capacity(shaders.len() as u32);
                 let mut all_stage_flags = ShaderStageFlags::empty();
                 let mut max_set = 0;
-                for shader in &shaders {
+                for shader in shaders.iter() {
                     let inner = shader
                         .inner()
                         .await
@@ -545,7 +775,14 @@ impl ShaderCache {
                         }
                         bindings.push(uniform.as_layout_binding(
                             inline_uniform_block,
-                            |count| count.declared() as u32,
+                            |count| match count {
+                                DescriptorCount::Static(count) => count as u32,
+                                DescriptorCount::Runtime { declared } => attributes
+                                    .runtime_array_counts
+                                    .get(&(uniform.set, uniform.binding))
+                                    .copied()
+                                    .unwrap_or(declared as u32),
+                            },
                         ).context_with(|| format!(
                             "failed to convert uniform (set {}, binding {})",
                             uniform.set, uniform.binding,
@@ -577,6 +814,10 @@ impl ShaderCache {
                     .get_device_attribute(ext::inline_uniform_block::Attributes
                         ::MAX_DESCRIPTOR_SET_INLINE_UNIFORM_BLOCKS
                     ).u32().unwrap_or(0);
+                let max_descriptor_set_acceleration_structures = device
+                    .get_device_attribute(ext::acceleration_structure::Attributes
+                        ::MAX_DESCRIPTOR_SET_ACCELERATION_STRUCTURES
+                    ).u32();
                 for (i, (mut bindings, stage_flags, flags, inline_ubos)) in sets.into_iter().enumerate() {
                     if inline_ubos > max_descriptor_set_inline_ubos {
                         return Err(Error::just_context(format!(
@@ -592,6 +833,25 @@ impl ShaderCache {
                             "inline uniform buffer block descriptor type can't be used for push descriptors"
                         ))
                     }
+                    let acceleration_structures: u32 = bindings
+                        .iter()
+                        .filter(|binding| binding.descriptor_type == DescriptorType::AccelerationStructure)
+                        .map(|binding| binding.descriptor_count)
+                        .sum();
+                    if acceleration_structures != 0 {
+                        let Some(max) = max_descriptor_set_acceleration_structures else {
+                            return Err(Error::just_context(
+                                "attempting to use acceleration structure descriptors without enabling the extension"
+                            ))
+                        };
+                        if acceleration_structures > max {
+                            return Err(Error::just_context(format!(
+                                "{}{}",
+                                format_args!("set {i} contains {acceleration_structures} acceleration structures, "),
+                                format_args!("but the max descriptor set acceleration structure count is {max}"),
+                            )))
+                        }
+                    }
                     bindings.sort_unstable_by_key(|a| a.binding);
                     let mut prev = bindings.first().map(|b| b.binding).unwrap_or(0);
                     for binding in bindings.iter().skip(1) {
@@ -603,6 +863,31 @@ impl ShaderCache {
                         prev = binding.binding;
                     }
                     let mut layout_flags = vk::DescriptorSetLayoutCreateFlags::empty();
+                    if flags.contains(DescriptorSetLayoutFlags::PUSH_DESCRIPTOR) &&
+                        flags.intersects(
+                            DescriptorSetLayoutFlags::UPDATE_AFTER_BIND |
+                            DescriptorSetLayoutFlags::VARIABLE_DESCRIPTOR_COUNT
+                        )
+                    {
+                        return Err(Error::just_context(format!(
+                            "set {i} has flags {flags}, which can't be combined with push descriptors"
+                        )))
+                    }
+                    if descriptor_buffer_device.is_some() {
+                        // descriptor buffers can always be updated after bind
+                        layout_flags |= vk::DescriptorSetLayoutCreateFlags::DESCRIPTOR_BUFFER_EXT;
+                        if flags.contains(DescriptorSetLayoutFlags::PUSH_DESCRIPTOR) &&
+                            !push_descriptor_buffers
+                        {
+                            return Err(Error::just_context(format!(
+                                "{}{}",
+                                format_args!("set {i} has the push descriptor flag, but push descriptors "),
+                                format_args!("are not supported together with descriptor buffers"),
+                            )))
+                        }
+                    } else if flags.contains(DescriptorSetLayoutFlags::UPDATE_AFTER_BIND) {
+                        layout_flags |= vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL;
+                    }
                     if flags.contains(DescriptorSetLayoutFlags::PUSH_DESCRIPTOR)
                     {
                         layout_flags |= vk::DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR;
@@ -643,13 +928,49 @@ impl ShaderCache {
                                     ..Default::default()
                                 }),
                             );
-                            let create_info = vk::DescriptorSetLayoutCreateInfo {
+                            let mut binding_flags = FixedVec32::with_capacity(
+                                binding_count, &tmp_alloc
+                            ).context("alloc failed")?;
+                            binding_flags.extend(key.bindings
+                                .iter()
+                                .enumerate()
+                                .map(|(j, b)| {
+                                    let mut binding_flags = vk::DescriptorBindingFlags::empty();
+                                    if flags.contains(DescriptorSetLayoutFlags::UPDATE_AFTER_BIND) &&
+                                        descriptor_buffer_device.is_none()
+                                    {
+                                        binding_flags |= vk::DescriptorBindingFlags::UPDATE_UNUSED_WHILE_PENDING;
+                                        if supports_update_after_bind(b.descriptor_type) {
+                                            binding_flags |= vk::DescriptorBindingFlags::UPDATE_AFTER_BIND;
+                                        }
+                                    }
+                                    if flags.contains(DescriptorSetLayoutFlags::PARTIALLY_BOUND) {
+                                        binding_flags |= vk::DescriptorBindingFlags::PARTIALLY_BOUND;
+                                    }
+                                    if flags.contains(DescriptorSetLayoutFlags::VARIABLE_DESCRIPTOR_COUNT) &&
+                                        j as u32 + 1 == binding_count
+                                    {
+                                        binding_flags |= vk::DescriptorBindingFlags::VARIABLE_DESCRIPTOR_COUNT;
+                                    }
+                                    binding_flags
+                                })
+                            );
+                            let mut binding_flags_info = vk::DescriptorSetLayoutBindingFlagsCreateInfo {
+                                s_type: vk::StructureType::DESCRIPTOR_SET_LAYOUT_BINDING_FLAGS_CREATE_INFO,
+                                binding_count,
+                                p_binding_flags: binding_flags.as_ptr(),
+                                ..Default::default()
+                            };
+                            let mut create_info = vk::DescriptorSetLayoutCreateInfo {
                                 s_type: vk::StructureType::DESCRIPTOR_SET_LAYOUT_CREATE_INFO,
                                 flags: layout_flags,
                                 binding_count,
                                 p_bindings: vk_bindings.as_ptr(),
                                 ..Default::default()
                             };
+                            if flags.requires_descriptor_indexing() {
+                                create_info = create_info.push_next(&mut binding_flags_info);
+                            }
                             let handle = unsafe {
                                 device
                                     .create_descriptor_set_layout(&create_info, None)
@@ -657,11 +978,25 @@ impl ShaderCache {
                             cache.insert(key.clone(), handle);
                             Ok(handle)
                         })?;
+                    let descriptor_buffer_layout = descriptor_buffer_device
+                        .as_ref()
+                        .filter(|_| !flags.contains(DescriptorSetLayoutFlags::PUSH_DESCRIPTOR))
+                        .map(|device| unsafe {
+                            DescriptorBufferLayout {
+                                size: device.get_descriptor_set_layout_size(handle),
+                                binding_offsets: key.bindings
+                                    .iter()
+                                    .map(|binding| device.get_descriptor_set_layout_binding_offset(
+                                        handle, binding.binding,
+                                    )).collect(),
+                            }
+                        });
                     descriptor_set_layouts.push(DescriptorSetLayout {
                         handle,
                         bindings: key.bindings,
                         stage_flags,
                         flags,
+                        descriptor_buffer_layout,
                     });
                 }
                 let mut vk_set_layouts = FixedVec32
@@ -690,25 +1025,27 @@ impl ShaderCache {
                     device
                         .create_pipeline_layout(&create_info, None)
                 }.context("failed to create pipeline layout")?;
-                let mut shader_modules = ArrayVec::<_, N_SHADERS>::new();
-                shader_modules.try_extend(
-                    shaders_inner.iter_mut().map(|shader| {
+                let mut shader_modules = Vec32::with_capacity(shaders_inner.len());
+                for shader in shaders_inner.iter() {
+                    shader_modules.push({
                         let info = vk::ShaderModuleCreateInfo {
                             s_type: vk::StructureType::SHADER_MODULE_CREATE_INFO,
                             code_size: size_of_val(shader.source().spirv()),
                             p_code: shader.source().spirv().as_ptr(),
                             ..Default::default()
                         };
-                        Ok(unsafe { RaiiHandle::new(
-                            device
-                                .create_shader_module(&info, None)
-                                .context("failed to create shader module")?,
+                        let module = unsafe {
+                            device.create_shader_module(&info, None)
+                        }.context("failed to create shader module")?;
+                        device.set_object_name(module, shader.name());
+                        unsafe { RaiiHandle::new(
+                            module,
                             |module| {
                                 device.destroy_shader_module(module, None);
                             }
-                        )})
-                    }),
-                )?;
+                        )}
+                    });
+                }
                 Ok(Arc::new(ShaderSetInner::new(
                     device.clone(),
                     &descriptor_set_layouts,
@@ -721,15 +1058,90 @@ impl ShaderCache {
                             ShaderModule {
                                 handle: handle.into_inner(),
                                 spirv: shader.source().clone(),
-                                entry_point: shader.entry_point().into()
+                                entry_point: shader.entry_point().into(),
+                                task_payload_size: shader.task_payload_size(),
                             }
                             
                         }),
                     pipeline_layout,
+                    descriptor_buffer_device.is_some(),
                 )))
-            }).context("failed to spawn")?
-        ));
-        Ok(ShaderSetId(index))
+            }).context("failed to spawn")?,
+            shaders,
+            attributes,
+        ))
+    }
+
+    /// Recompiles each shader of shader set `id` from its original source.
+    ///
+    /// The old shader set stays in use until the reload is [`finished`][1].
+    ///
+    /// [1]: Self::finish_reload
+    pub fn reload_shader_set(
+        &self,
+        gpu: &Gpu,
+        id: ShaderSetId,
+        thread_pool: ThreadPool,
+        tmp_allocs: Arc<TmpAllocs>,
+    ) -> Result<ShaderSetReload>
+    {
+        let handle = self.shader_sets
+            .get(id.0)
+            .context_with(|| format!(
+                "invalid shader set id {id}",
+            ))?;
+        let shaders = handle.shaders
+            .iter()
+            .map(|shader| shader.reload(gpu))
+            .collect::<Result<Arc<[Shader]>>>()?;
+        Ok(ShaderSetReload {
+            id,
+            handle: self.build_shader_set(
+                shaders,
+                handle.attributes.clone(),
+                thread_pool,
+                tmp_allocs,
+            )?,
+        })
+    }
+
+    /// Replaces the old shader set with a reloaded one.
+    pub fn finish_reload(&mut self, reload: ShaderSetReload) -> Result<()> {
+        let id = reload.id;
+        *self.shader_sets
+            .get_mut(id.0)
+            .context_with(|| format!(
+                "invalid shader set id {id}",
+            ))? = reload.handle;
+        Ok(())
+    }
+
+    /// Returns the shader sets, which contain a shader whose source file was modified since
+    /// the last call.
+    pub fn poll_modified_shader_sets(&self) -> Vec32<ShaderSetId> {
+        let mut polled = Vec32::<(Shader, bool)>::new();
+        let mut modified = vec32![];
+        for (index, handle) in self.shader_sets.iter() {
+            let mut is_modified = false;
+            for shader in handle.shaders.iter() {
+                let was_modified = match polled
+                    .iter()
+                    .find(|(polled, _)| polled.ptr_eq(shader))
+                {
+                    Some(&(_, was_modified)) => was_modified,
+                    None => {
+                        let was_modified = shader.poll_source_modified();
+                        polled.push((shader.clone(), was_modified));
+                        was_modified
+                    },
+                };
+                is_modified |= was_modified;
+            }
+            if is_modified {
+                modified.push(ShaderSetId(index));
+            }
+        }
+        modified
     }
 
     #[inline(always)]
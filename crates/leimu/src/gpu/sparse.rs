@@ -0,0 +1,347 @@
+use ahash::AHashMap;
+
+use nox_ash::{
+    vk,
+    ash_style_enum,
+};
+
+use nox_mem::vec::Vec32;
+
+use nox_proc::BuildStructure;
+
+use crate::{
+    gpu::prelude::*,
+    sync::Arc,
+    error::*,
+};
+
+ash_style_enum! {
+
+    /// Specifies how the memory of a sparse buffer or image is bound.
+    ///
+    /// The memory of sparse resources is not bound when they are created. Instead it is bound and
+    /// unbound with [`SparseBindCommands`] at page granularity.
+    ///
+    /// # Vulkan docs
+    /// <https://docs.vulkan.org/spec/latest/chapters/sparsemem.html>
+    #[flags(Flags32)]
+    #[default = Self::empty()]
+    pub enum SparseFlags {
+        /// Specifies that the memory of the resource is bound with [`SparseBindCommands`].
+        ///
+        /// Without [`SparseFlags::RESIDENCY`], the resource *must* be fully bound before it's
+        /// used.
+        ///
+        /// Requires [`BaseDeviceFeatures::sparse_binding`] to be enabled.
+        #[display("binding")]
+        BINDING = vk::BufferCreateFlags::SPARSE_BINDING.as_raw(),
+        /// Specifies that the resource *can* be used while only partially bound.
+        ///
+        /// Requires [`BaseDeviceFeatures::sparse_residency_buffer`] for buffers and the residency
+        /// feature matching the image type and sample count for images.
+        #[display("residency")]
+        RESIDENCY =
+            vk::BufferCreateFlags::SPARSE_BINDING.as_raw() |
+            vk::BufferCreateFlags::SPARSE_RESIDENCY.as_raw(),
+        /// Specifies that the same memory *can* be bound to multiple locations at the same time.
+        ///
+        /// Requires [`BaseDeviceFeatures::sparse_residency_aliased`] to be enabled.
+        #[display("aliased")]
+        ALIASED =
+            vk::BufferCreateFlags::SPARSE_BINDING.as_raw() |
+            vk::BufferCreateFlags::SPARSE_ALIASED.as_raw(),
+    }
+}
+
+impl From<SparseFlags> for vk::BufferCreateFlags {
+
+    #[inline]
+    fn from(value: SparseFlags) -> Self {
+        Self::from_raw(value.as_raw())
+    }
+}
+
+impl From<SparseFlags> for vk::ImageCreateFlags {
+
+    #[inline]
+    fn from(value: SparseFlags) -> Self {
+        Self::from_raw(value.as_raw())
+    }
+}
+
+impl SparseFlags {
+
+    /// Validates that the device features required by `self` are enabled.
+    pub(crate) fn validate_features(
+        self,
+        features: &BaseDeviceFeatures,
+        residency_feature: (bool, &str),
+    ) -> Result<()>
+    {
+        if self.is_empty() {
+            return Ok(())
+        }
+        if !features.sparse_binding {
+            return Err(Error::just_context(
+                "sparse resources require sparse_binding device feature to be enabled"
+            ))
+        }
+        if self.contains(Self::RESIDENCY) && !residency_feature.0 {
+            return Err(Error::just_context(format!(
+                "sparse residency requires {} device feature to be enabled",
+                residency_feature.1,
+            )))
+        }
+        if self.contains(Self::ALIASED) && !features.sparse_residency_aliased {
+            return Err(Error::just_context(
+                "aliased sparse resources require sparse_residency_aliased device feature to be enabled"
+            ))
+        }
+        Ok(())
+    }
+}
+
+/// Describes the sparse block layout of an image format for an image aspect.
+///
+/// Queried with [`Gpu::get_sparse_image_format_properties`].
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkSparseImageFormatProperties.html>
+#[derive(Clone, Copy, Debug)]
+pub struct SparseImageFormatProperties {
+    /// The aspects these properties apply to.
+    pub aspect_mask: ImageAspects,
+    /// The dimensions of a single sparse block in texels.
+    pub image_granularity: Dimensions,
+    /// Specifies whether all array layers share a single mip tail.
+    pub single_mip_tail: bool,
+    /// Specifies whether the mip tail starts at the first mip level, which dimensions aren't
+    /// a multiple of [`image_granularity`][1].
+    ///
+    /// [1]: Self::image_granularity
+    pub aligned_mip_size: bool,
+    /// Specifies whether [`image_granularity`][1] doesn't match the standard sparse block
+    /// dimensions of the format.
+    ///
+    /// [1]: Self::image_granularity
+    pub nonstandard_block_size: bool,
+}
+
+impl From<vk::SparseImageFormatProperties> for SparseImageFormatProperties {
+
+    fn from(value: vk::SparseImageFormatProperties) -> Self {
+        Self {
+            aspect_mask: ImageAspects::from_raw(value.aspect_mask.as_raw()),
+            image_granularity: value.image_granularity.into(),
+            single_mip_tail: value.flags.contains(vk::SparseImageFormatFlags::SINGLE_MIPTAIL),
+            aligned_mip_size: value.flags.contains(vk::SparseImageFormatFlags::ALIGNED_MIP_SIZE),
+            nonstandard_block_size: value.flags.contains(
+                vk::SparseImageFormatFlags::NONSTANDARD_BLOCK_SIZE
+            ),
+        }
+    }
+}
+
+/// The sparse memory requirements of an image for an image aspect.
+///
+/// Queried with [`Gpu::get_image_sparse_memory_requirements`].
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkSparseImageMemoryRequirements.html>
+#[derive(Clone, Copy, Debug)]
+pub struct SparseImageMemoryRequirements {
+    /// The block layout of the aspect.
+    pub format_properties: SparseImageFormatProperties,
+    /// The first mip level of the mip tail.
+    ///
+    /// Mip levels starting from this *must* be bound with
+    /// [`SparseBindCommands::bind_image_mip_tail`].
+    pub mip_tail_first_lod: u32,
+    /// The size of the mip tail of a single array layer, or of the whole image if
+    /// [`single_mip_tail`][1] is set.
+    ///
+    /// [1]: SparseImageFormatProperties::single_mip_tail
+    pub mip_tail_size: DeviceSize,
+    /// The offset of the mip tail in the opaque memory range of the image.
+    pub mip_tail_offset: DeviceSize,
+    /// The offset between the mip tails of consecutive array layers.
+    pub mip_tail_stride: DeviceSize,
+}
+
+impl From<vk::SparseImageMemoryRequirements> for SparseImageMemoryRequirements {
+
+    fn from(value: vk::SparseImageMemoryRequirements) -> Self {
+        Self {
+            format_properties: value.format_properties.into(),
+            mip_tail_first_lod: value.image_mip_tail_first_lod,
+            mip_tail_size: value.image_mip_tail_size,
+            mip_tail_offset: value.image_mip_tail_offset,
+            mip_tail_stride: value.image_mip_tail_stride,
+        }
+    }
+}
+
+/// The memory properties of a sparse buffer or image.
+///
+/// Queried with [`Gpu::get_buffer_sparse_properties`] and [`Gpu::get_image_sparse_properties`].
+#[derive(Clone, Copy, Debug)]
+pub struct SparseMemoryProperties {
+    /// The flags the resource was created with.
+    pub flags: SparseFlags,
+    /// The size of the opaque memory range of the resource.
+    pub size: DeviceSize,
+    /// The size of a single page.
+    ///
+    /// Opaque binds *must* be aligned to this and each sparse image block takes one page.
+    pub page_size: DeviceSize,
+    /// The number of pages currently bound to memory.
+    pub resident_page_count: u32,
+}
+
+/// Specifies a region of sparse blocks of an image bound with [`SparseBindCommands::bind_image`].
+///
+/// # Valid usage
+/// - `aspect_mask` *must* contain exactly one aspect of the image.
+/// - `mip_level` *must* be less than [`SparseImageMemoryRequirements::mip_tail_first_lod`].
+/// - `offset` *must* be a multiple of [`SparseImageFormatProperties::image_granularity`].
+/// - `extent` *must* be a multiple of [`SparseImageFormatProperties::image_granularity`], or
+///   `offset` + `extent` *must* equal the dimensions of the mip level.
+///
+/// # Vulkan docs
+/// <https://docs.vulkan.org/refpages/latest/refpages/source/VkSparseImageMemoryBind.html>
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, BuildStructure)]
+pub struct SparseImageBind {
+    /// Specifies the aspect of the bound blocks.
+    pub aspect_mask: ImageAspects,
+    /// Specifies the mip level of the bound blocks.
+    pub mip_level: u32,
+    /// Specifies the array layer of the bound blocks.
+    pub array_layer: u32,
+    /// Specifies the offset of the region in texels.
+    pub offset: ImageCopyOffset,
+    /// Specifies the extent of the region in texels.
+    pub extent: Dimensions,
+}
+
+/// A page of a sparse resource.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum SparsePage {
+    /// A page of the opaque memory range, indexed by its offset divided by the page size.
+    Opaque(DeviceSize),
+    /// A sparse image block, indexed by its offset divided by the block dimensions.
+    Block {
+        aspect_mask: ImageAspects,
+        mip_level: u32,
+        array_layer: u32,
+        x: u32,
+        y: u32,
+        z: u32,
+    },
+}
+
+/// Tracks the memory bound to the pages of a sparse resource.
+pub(crate) struct SparseMemory {
+    flags: SparseFlags,
+    requirements: vk::MemoryRequirements,
+    pages: AHashMap<SparsePage, Arc<DeviceMemoryObj>>,
+}
+
+impl SparseMemory {
+
+    #[inline]
+    pub fn new(flags: SparseFlags, requirements: vk::MemoryRequirements) -> Self {
+        Self {
+            flags,
+            requirements,
+            pages: AHashMap::default(),
+        }
+    }
+
+    #[inline]
+    pub fn properties(&self) -> SparseMemoryProperties {
+        SparseMemoryProperties {
+            flags: self.flags,
+            size: self.requirements.size,
+            page_size: self.requirements.alignment,
+            resident_page_count: self.pages.len() as u32,
+        }
+    }
+
+    #[inline]
+    pub fn page_size(&self) -> DeviceSize {
+        self.requirements.alignment
+    }
+
+    /// Validates an opaque range and returns its first page and page count.
+    pub fn opaque_pages(
+        &self,
+        offset: DeviceSize,
+        size: DeviceSize,
+    ) -> Result<(DeviceSize, DeviceSize)>
+    {
+        let page_size = self.page_size();
+        if size == 0 {
+            return Err(Error::just_context("sparse bind size must not be zero"))
+        }
+        if !offset.is_multiple_of(page_size) {
+            return Err(Error::just_context(format!(
+                "sparse bind offset {offset} is not a multiple of page size {page_size}",
+            )))
+        }
+        if offset + size > self.requirements.size {
+            return Err(Error::just_context(format!(
+                "sparse bind offset {offset} + size {size} is greater than the sparse memory size {}",
+                self.requirements.size,
+            )))
+        }
+        if !size.is_multiple_of(page_size) && offset + size != self.requirements.size {
+            return Err(Error::just_context(format!(
+                "sparse bind size {size} is not a multiple of page size {page_size}",
+            )))
+        }
+        Ok((offset / page_size, size.div_ceil(page_size)))
+    }
+
+    /// Allocates memory for `page_count` pages with `memory_binder`.
+    pub fn alloc(
+        &self,
+        memory_binder: &dyn MemoryBinder,
+        page_count: DeviceSize,
+    ) -> Result<Arc<DeviceMemoryObj>>
+    {
+        let requirements = vk::MemoryRequirements2 {
+            memory_requirements: vk::MemoryRequirements {
+                size: page_count * self.page_size(),
+                ..self.requirements
+            },
+            ..Default::default()
+        };
+        let memory = unsafe {
+            memory_binder
+                .alloc(&requirements)
+                .context("failed to allocate sparse memory")?
+        };
+        Ok(Arc::new(memory))
+    }
+
+    /// Binds `memory` to `pages` or unbinds them, if `memory` is [`None`].
+    ///
+    /// Memory previously bound to the pages is moved to `retired`, which *must* be kept alive
+    /// until the bind has finished executing.
+    pub fn bind(
+        &mut self,
+        pages: impl IntoIterator<Item = SparsePage>,
+        memory: Option<&Arc<DeviceMemoryObj>>,
+        retired: &mut Vec32<Arc<DeviceMemoryObj>>,
+    ) {
+        for page in pages {
+            let old = match memory {
+                Some(memory) => self.pages.insert(page, memory.clone()),
+                None => self.pages.remove(&page),
+            };
+            if let Some(old) = old {
+                retired.push(old);
+            }
+        }
+    }
+}
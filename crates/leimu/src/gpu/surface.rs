@@ -60,6 +60,7 @@ pub(crate) struct Surface {
     alloc: Arena,
     present_queue: DeviceQueue,
     desired_image_count: u32,
+    preferences: SwapchainPreferences,
 }
 
 impl ResourceMeta for Surface {
@@ -73,6 +74,7 @@ impl Surface {
         window: Arc<dyn VulkanWindow>,
         gpu: Gpu,
         desired_image_count: u32,
+        preferences: SwapchainPreferences,
     ) -> Result<Self>
     {
         let alloc = Arena::new(1 << 10).context("alloc failed")?;
@@ -102,6 +104,7 @@ impl Surface {
                 size: window.inner_size(),
             },
             desired_image_count,
+            preferences,
             window,
             present_queue,
             image_view_ids: Default::default(),
@@ -125,6 +128,25 @@ impl Surface {
         };
     }
 
+    /// Sets the preferences of the swapchain and requests it to be recreated.
+    pub fn set_preferences(
+        &mut self,
+        preferences: SwapchainPreferences,
+    ) {
+        self.preferences = preferences;
+        self.request_swapchain_update(
+            self.desired_image_count,
+            self.window.inner_size(),
+        );
+    }
+
+    #[inline(always)]
+    pub fn swapchain_info(&self) -> Option<SwapchainInfo> {
+        self.swapchain
+            .as_ref()
+            .map(|swapchain| swapchain.info())
+    }
+
     #[inline(always)]
     pub fn current_image_view(&self) -> SwapchainImageViewId<'_> {
         let id = self.image_view_ids[self.image_index as usize];
@@ -159,6 +181,7 @@ impl Surface {
                     self.handle,
                     vk::Extent2D { width: size.0, height: size.1 },
                     desired_image_count.get(),
+                    &self.preferences,
                     &self.alloc,
                     &tmp_alloc,
                 ).context("failed to create swapchain")?);
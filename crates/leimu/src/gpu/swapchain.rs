@@ -1,20 +1,90 @@
 use core::{
     ptr::NonNull,
-    num::NonZeroU64,
+    num::{NonZeroU32, NonZeroU64},
 };
 
 use nox_mem::{
-    vec::{FixedVec32, NonNullVec32},
+    vec::{Vec32, FixedVec32, NonNullVec32},
     alloc::LocalAlloc,
-    arena::{self, Arena}
+    arena::{self, Arena},
+    vec32,
 };
 use nox_ash::vk::{self, SwapchainCreateFlagsKHR2};
 
+use nox_proc::BuildStructure;
+
 use crate::{
     error::*,
     gpu::prelude::*,
 };
 
+/// Specifies the present mode, surface format and image count swapchains of a surface are
+/// created with.
+///
+/// Each list is in order of preference and the first entry supported by the surface is chosen.
+/// The values that were chosen *can* be queried with [`Gpu::swapchain_info`].
+#[derive(Clone, BuildStructure)]
+pub struct SwapchainPreferences {
+    /// Specifies the present modes in order of preference.
+    ///
+    /// [`PresentMode::Fifo`] is chosen, if none of them are supported.
+    ///
+    /// The default is [`PresentMode::Mailbox`] followed by [`PresentMode::Fifo`].
+    #[default(vec32![PresentMode::Mailbox, PresentMode::Fifo])]
+    pub present_modes: Vec32<PresentMode>,
+    /// Specifies the surface formats and color spaces in order of preference.
+    ///
+    /// If none of them are supported, the first supported format with
+    /// [`ColorSpace::SrgbNonlinear`] is chosen.
+    ///
+    /// The default is [`Format::R8g8b8a8Srgb`] followed by [`Format::B8g8r8a8Srgb`], both with
+    /// [`ColorSpace::SrgbNonlinear`].
+    #[default(vec32![
+        (Format::R8g8b8a8Srgb, ColorSpace::SrgbNonlinear),
+        (Format::B8g8r8a8Srgb, ColorSpace::SrgbNonlinear),
+    ])]
+    pub surface_formats: Vec32<(Format, ColorSpace)>,
+    /// Specifies the minimum number of swapchain images.
+    ///
+    /// The count is clamped to the limits of the surface and is at least the number of buffered
+    /// frames of the [`Gpu`]. If this is [`None`], one more than the surface's minimum image
+    /// count is used.
+    #[skip]
+    #[default(None)]
+    pub min_image_count: Option<NonZeroU32>,
+}
+
+impl SwapchainPreferences {
+
+    /// Specifies the minimum number of swapchain images.
+    ///
+    /// Passing zero restores the default behavior described in
+    /// [`min_image_count`][1].
+    ///
+    /// [1]: Self::min_image_count
+    #[must_use]
+    #[inline]
+    pub fn min_image_count(mut self, count: u32) -> Self {
+        self.min_image_count = NonZeroU32::new(count);
+        self
+    }
+}
+
+/// The values chosen for a swapchain from its [`SwapchainPreferences`].
+#[derive(Clone, Copy, Debug)]
+pub struct SwapchainInfo {
+    /// The present mode of the swapchain.
+    pub present_mode: PresentMode,
+    /// The format of the swapchain images.
+    pub format: Format,
+    /// The color space the swapchain images are presented in.
+    pub color_space: ColorSpace,
+    /// The number of swapchain images.
+    pub image_count: u32,
+    /// The extent of the swapchain images.
+    pub extent: (u32, u32),
+}
+
 #[derive(Clone, Copy)]
 pub struct SwapchainFrameData {
     pub image_index: u32,
@@ -30,6 +100,7 @@ pub struct Swapchain {
     handle: vk::SwapchainKHR,
     image_index: u32,
     surface_format: vk::SurfaceFormatKHR,
+    present_mode: PresentMode,
     image_extent: vk::Extent2D,
     image_usage: vk::ImageUsageFlags,
     present_id: Option<NonZeroU64>,
@@ -52,6 +123,7 @@ impl Swapchain {
         surface: vk::SurfaceKHR,
         framebuffer_extent: vk::Extent2D,
         desired_image_count: u32,
+        preferences: &SwapchainPreferences,
         alloc: &Arena,
         tmp_alloc: &impl LocalAlloc<Error = arena::Error>,
     ) -> Result<Self>
@@ -60,10 +132,10 @@ impl Swapchain {
             return Err(Error::just_context("frame buffer size was 0"))
         }
         let surface_format = find_surface_format(
-            device, surface, tmp_alloc,
+            device, surface, &preferences.surface_formats, tmp_alloc,
         )?;
         let present_mode = find_present_mode(
-            device, surface, tmp_alloc,
+            device, surface, &preferences.present_modes, tmp_alloc,
         )?;
         let mut id2 = vk::SurfaceCapabilitiesPresentId2KHR::default();
         let mut wait2 = vk::SurfaceCapabilitiesPresentWait2KHR::default();
@@ -96,8 +168,13 @@ impl Swapchain {
         if image_extent.width == 0 || image_extent.height == 0 {
             return Err(Error::just_context("swapchain extent was 0"));
         }
-        let mut actual_image_count = capabilities.surface_capabilities.min_image_count + 1;
-        actual_image_count = actual_image_count.max(desired_image_count);
+        let mut actual_image_count = match preferences.min_image_count {
+            Some(count) => count.get()
+                .max(capabilities.surface_capabilities.min_image_count)
+                .max(desired_image_count),
+            None => (capabilities.surface_capabilities.min_image_count + 1)
+                .max(desired_image_count),
+        };
         if capabilities.surface_capabilities.max_image_count > 0 &&
             actual_image_count > capabilities.surface_capabilities.max_image_count
         {
@@ -136,7 +213,7 @@ impl Swapchain {
             image_sharing_mode: vk::SharingMode::EXCLUSIVE,
             pre_transform,
             composite_alpha,
-            present_mode,
+            present_mode: present_mode.into(),
             ..Default::default()
         };
         let mut present_id = None;
@@ -187,6 +264,7 @@ impl Swapchain {
             handle,
             image_index: 0,
             surface_format,
+            present_mode,
             image_extent,
             image_usage,
             present_id,
@@ -238,6 +316,19 @@ impl Swapchain {
         }
     }
 
+    pub fn info(&self) -> SwapchainInfo {
+        SwapchainInfo {
+            present_mode: self.present_mode,
+            format: unsafe {
+                Format::from_raw(self.surface_format.format.as_raw())
+            },
+            color_space: ColorSpace::from_vk(self.surface_format.color_space)
+                .unwrap_or_default(),
+            image_count: self.image_count,
+            extent: (self.image_extent.width, self.image_extent.height),
+        }
+    }
+
     pub fn present_id2(&self) -> Option<NonZeroU64> {
         self.present_id
     }
@@ -310,6 +401,7 @@ impl Swapchain {
 fn find_surface_format<Alloc: LocalAlloc<Error = arena::Error>>(
     device: &LogicalDevice,
     surface_handle: vk::SurfaceKHR,
+    preferred: &[(Format, ColorSpace)],
     tmp_alloc: &Alloc,
 ) -> Result<vk::SurfaceFormatKHR>
 {
@@ -327,22 +419,32 @@ fn find_surface_format<Alloc: LocalAlloc<Error = arena::Error>>(
             surface_handle,
             &mut formats,
         ).context("failed to get Vulkan surface formats")?;
-        for &format in &formats {
-            if format.format == vk::Format::R8G8B8A8_SRGB &&
-                format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
-            {
-                return Ok(format);
+        for &(format, color_space) in preferred {
+            let preferred = vk::SurfaceFormatKHR {
+                format: format.into(),
+                color_space: color_space.into(),
+            };
+            if formats.contains(&preferred) {
+                return Ok(preferred)
             }
         }
-        Ok(formats[0])
+        formats
+            .iter()
+            .find(|format| format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR)
+            .or_else(|| formats
+                .iter()
+                .find(|format| ColorSpace::from_vk(format.color_space).is_some())
+            ).copied()
+            .ok_or_else(|| Error::just_context("surface reported no supported formats"))
     }
 }
 
 fn find_present_mode<Alloc: LocalAlloc<Error = arena::Error>>(
     device: &LogicalDevice,
     surface: vk::SurfaceKHR,
+    preferred: &[PresentMode],
     tmp_alloc: &Alloc,
-) -> Result<vk::PresentModeKHR>
+) -> Result<PresentMode>
 {
     unsafe {
         let physical_device = device.physical_device().handle();
@@ -358,11 +460,11 @@ fn find_present_mode<Alloc: LocalAlloc<Error = arena::Error>>(
             surface,
             &mut modes,
         ).context("failed to get Vulkan surface present modes")?;
-        for mode in &modes {
-            if *mode == vk::PresentModeKHR::MAILBOX {
-                return Ok(vk::PresentModeKHR::MAILBOX); // low latency and no tearing
+        for &mode in preferred {
+            if modes.contains(&mode.into()) {
+                return Ok(mode)
             }
         }
-        Ok(vk::PresentModeKHR::FIFO) // always supported
+        Ok(PresentMode::Fifo) // always supported
     }
 }
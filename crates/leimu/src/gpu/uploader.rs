@@ -0,0 +1,482 @@
+use core::{
+    pin::Pin,
+    future::Future,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use nox_mem::vec::Vec32;
+
+use crate::{
+    gpu::prelude::*,
+    error::*,
+    sync::*,
+};
+
+#[derive(Clone, Copy)]
+enum UploadRegion {
+    Buffer {
+        buffer_id: BufferId,
+        offset: DeviceSize,
+        size: DeviceSize,
+    },
+    Image {
+        image_id: ImageId,
+        region: BufferImageCopy,
+    },
+}
+
+#[derive(Clone, Copy)]
+struct UploadRequest {
+    staging_id: BufferId,
+    region: UploadRegion,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum UploadStatus {
+    #[default]
+    Pending,
+    Resident,
+    Failed,
+}
+
+#[derive(Default)]
+struct BatchState {
+    status: UploadStatus,
+    wakers: Vec32<Waker>,
+}
+
+impl BatchState {
+
+    fn resolve(&mut self, status: UploadStatus) {
+        if self.status == UploadStatus::Pending {
+            self.status = status;
+        }
+        while let Some(waker) = self.wakers.pop() {
+            waker.wake();
+        }
+    }
+}
+
+struct Batch {
+    binder: LinearBinder,
+    requests: Vec32<UploadRequest>,
+    state: Arc<Mutex<BatchState>>,
+    value: u64,
+}
+
+impl Batch {
+
+    fn new(binder: LinearBinder, value: u64) -> Self {
+        Self {
+            binder,
+            requests: Vec32::new(),
+            state: Default::default(),
+            value,
+        }
+    }
+
+    fn staging_ids(&self) -> impl Iterator<Item = BufferId> {
+        self.requests.iter().map(|request| request.staging_id)
+    }
+}
+
+struct State {
+    open: Batch,
+    in_flight: Vec32<Batch>,
+    free_binders: Vec32<LinearBinder>,
+    last_value: u64,
+}
+
+struct Inner {
+    gpu: Gpu,
+    queue: DeviceQueue,
+    block_size: DeviceSize,
+    semaphore: TimelineSemaphoreId,
+    state: Mutex<State>,
+}
+
+impl Inner {
+
+    fn new_binder(&self) -> Result<LinearBinder> {
+        LinearBinder::new(
+            self.gpu.device().clone(),
+            self.block_size,
+            MemoryProperties::HOST_VISIBLE | MemoryProperties::HOST_COHERENT,
+            MemoryProperties::HOST_VISIBLE | MemoryProperties::HOST_COHERENT,
+        ).context("failed to create staging memory binder")
+    }
+
+    fn push(&self, bytes: &[u8], region: UploadRegion) -> Result<Upload> {
+        if bytes.is_empty() {
+            return Err(Error::just_context("upload size is zero"))
+        }
+        let size = bytes.len() as DeviceSize;
+        let mut state = self.state.lock();
+        let mut staging_id = Default::default();
+        self.gpu.create_resources(
+            [BufferCreateInfo::new(
+                &mut staging_id,
+                &state.open.binder,
+                size,
+                BufferUsages::TRANSFER_SRC,
+            ).ok_or_else(|| Error::just_context("staging buffer size was zero"))?],
+            [],
+        ).context("failed to create staging buffer")?;
+        let map = self.gpu.map_buffer(staging_id);
+        let map = match map {
+            Ok(map) => map,
+            Err(err) => {
+                self.gpu.destroy_resources([staging_id], []).ok();
+                return Err(err)
+            },
+        };
+        // staging memory is host coherent, so the write doesn't need to be flushed
+        unsafe {
+            bytes.as_ptr().copy_to_nonoverlapping(map.map, bytes.len());
+        }
+        state.open.requests.push(UploadRequest { staging_id, region, });
+        Ok(Upload {
+            gpu: self.gpu.clone(),
+            semaphore: self.semaphore,
+            value: state.open.value,
+            state: state.open.state.clone(),
+        })
+    }
+
+    fn retire(&self, state: &mut State) -> Result<()> {
+        if state.in_flight.is_empty() {
+            return Ok(())
+        }
+        let value = self.gpu.get_semaphore_counter_value(self.semaphore)?;
+        while let Some(batch) = state.in_flight.first() {
+            let status = batch.state.lock().status;
+            if status != UploadStatus::Failed && batch.value > value {
+                break
+            }
+            let batch = state.in_flight.remove(0);
+            self.gpu.destroy_resources(batch.staging_ids(), [])?;
+            batch.state.lock().resolve(UploadStatus::Resident);
+            unsafe {
+                batch.binder.release_resources();
+            }
+            state.free_binders.push(batch.binder);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Inner {
+
+    fn drop(&mut self) {
+        let state = self.state.get_mut();
+        if !state.in_flight.is_empty() {
+            self.gpu.wait_for_semaphores(
+                &[(self.semaphore, state.last_value)],
+                Duration::from_nanos(self.gpu.device().frame_timeout()),
+            ).ok();
+        }
+        for batch in &state.in_flight {
+            self.gpu.destroy_resources(batch.staging_ids(), []).ok();
+            batch.state.lock().resolve(UploadStatus::Resident);
+        }
+        self.gpu.destroy_resources(state.open.staging_ids(), []).ok();
+        state.open.state.lock().resolve(UploadStatus::Failed);
+        self.gpu.destroy_timeline_semaphores(&[self.semaphore]);
+    }
+}
+
+/// An upload service, which copies data from host memory to buffers and images.
+///
+/// Uploads *can* be requested from any thread. The data is written to host visible staging
+/// memory right away and the copies are batched into one [`copy commands`][1] submission on the
+/// queue of the uploader, every time [`Uploader::flush`] is called.
+///
+/// Staging memory of a batch is reused, once the batch has finished executing.
+///
+/// # Valid usage
+/// - The queue of the uploader *must* support graphics operations, like with all
+///   [`copy commands`][1].
+///
+/// [1]: CopyCommands
+#[derive(Clone)]
+pub struct Uploader {
+    inner: Arc<Inner>,
+}
+
+impl Uploader {
+
+    /// Creates a new uploader, which submits its copies to `queue`.
+    ///
+    /// `block_size` is the size of the staging memory blocks. Uploads larger than it get their
+    /// own allocation.
+    pub fn new(
+        gpu: Gpu,
+        queue: DeviceQueue,
+        block_size: DeviceSize,
+    ) -> Result<Self>
+    {
+        if !queue.queue_flags().contains(QueueFlags::GRAPHICS) {
+            return Err(Error::just_context(format!(
+                "queue {queue} doesn't support graphics operations"
+            )))
+        }
+        let mut semaphore = Default::default();
+        gpu.create_timeline_semaphores([(&mut semaphore, 0)])?;
+        let binder = LinearBinder::new(
+            gpu.device().clone(),
+            block_size,
+            MemoryProperties::HOST_VISIBLE | MemoryProperties::HOST_COHERENT,
+            MemoryProperties::HOST_VISIBLE | MemoryProperties::HOST_COHERENT,
+        );
+        let binder = match binder {
+            Ok(binder) => binder,
+            Err(err) => {
+                gpu.destroy_timeline_semaphores(&[semaphore]);
+                return Err(Error::new(err, "failed to create staging memory binder"))
+            },
+        };
+        Ok(Self {
+            inner: Arc::new(Inner {
+                gpu,
+                queue,
+                block_size,
+                semaphore,
+                state: Mutex::new(State {
+                    open: Batch::new(binder, 1),
+                    in_flight: Vec32::new(),
+                    free_binders: Vec32::new(),
+                    last_value: 0,
+                }),
+            }),
+        })
+    }
+
+    /// Returns the queue the copies are submitted to.
+    #[inline]
+    pub fn queue(&self) -> &DeviceQueue {
+        &self.inner.queue
+    }
+
+    /// Returns the timeline semaphore, which is signaled once a batch has finished executing.
+    ///
+    /// The value of an upload is returned by [`Upload::value`].
+    #[inline]
+    pub fn semaphore_id(&self) -> TimelineSemaphoreId {
+        self.inner.semaphore
+    }
+
+    /// Requests `bytes` to be copied to `buffer_id` at `offset`.
+    ///
+    /// # Valid usage
+    /// - `buffer_id` *must* be a valid [`BufferId`].
+    /// - The buffer *must* have been created with the [`BufferUsages::TRANSFER_DST`] bit set.
+    /// - `offset` + the length of `bytes` *must* be in range of the buffer.
+    pub fn upload_buffer(
+        &self,
+        buffer_id: BufferId,
+        offset: DeviceSize,
+        bytes: &[u8],
+    ) -> Result<Upload>
+    {
+        let size = bytes.len() as DeviceSize;
+        let buffers = self.inner.gpu.read_buffers();
+        let buffer_size = buffers.get(buffer_id)?.properties().size;
+        drop(buffers);
+        if offset + size > buffer_size {
+            return Err(Error::just_context(format!(
+                "upload offset {offset} + size {size} was out of range of buffer {buffer_id} size {buffer_size}"
+            )))
+        }
+        self.inner.push(bytes, UploadRegion::Buffer { buffer_id, offset, size, })
+            .context_with(|| format!("failed to upload to buffer {buffer_id}"))
+    }
+
+    /// Requests `bytes` to be copied to a region of `image_id`.
+    ///
+    /// `bytes` *must* be tightly packed texel data of `subresource`, with the layout described
+    /// in [`CopyCommands::copy_buffer_to_image`].
+    ///
+    /// # Valid usage
+    /// - `image_id` *must* be a valid [`ImageId`].
+    /// - The image *must* have been created with the [`ImageUsages::TRANSFER_DST`] bit set.
+    /// - `offset` and `extent` *must* be in range of the image's dimensions at the mip level of
+    ///   `subresource`.
+    pub fn upload_image(
+        &self,
+        image_id: ImageId,
+        subresource: ImageSubresourceLayers,
+        offset: ImageCopyOffset,
+        extent: impl Into<Dimensions>,
+        bytes: &[u8],
+    ) -> Result<Upload>
+    {
+        let region = BufferImageCopy::default()
+            .image_subresource(subresource)
+            .image_offset(offset)
+            .image_extent(extent);
+        let images = self.inner.gpu.read_images();
+        let properties = images.get(image_id)?.properties();
+        let layer_count = subresource
+            .effective(properties.array_layers)
+            .layer_count;
+        let size = region.calculate_buffer_size(
+            properties.format.compatibility(),
+            properties.format,
+            subresource.aspect_mask,
+            layer_count,
+        );
+        drop(images);
+        if (bytes.len() as DeviceSize) < size {
+            return Err(Error::just_context(format!(
+                "upload size {} was less than the calculated size {size} of image {image_id} region",
+                bytes.len(),
+            )))
+        }
+        self.inner.push(&bytes[..size as usize], UploadRegion::Image { image_id, region, })
+            .context_with(|| format!("failed to upload to image {image_id}"))
+    }
+
+    /// Schedules the requested uploads as one [`copy commands`][1] submission and releases the
+    /// staging memory of finished batches.
+    ///
+    /// This should be called once per tick.
+    ///
+    /// Returns the [`CommandId`] of the copies, if there were any uploads, which *can* be used
+    /// to add a [`CommandDependency`] to commands using the uploaded data on the same tick.
+    ///
+    /// If recording the copies fails, every [`Upload`] of the batch resolves with an error.
+    ///
+    /// [1]: CopyCommands
+    pub fn flush(&self, scheduler: &mut CommandScheduler<'_>) -> Result<Option<CommandId>> {
+        let inner = &*self.inner;
+        let mut state = inner.state.lock();
+        inner.retire(&mut state)
+            .context("failed to release staging memory")?;
+        if state.open.requests.is_empty() {
+            return Ok(None)
+        }
+        let binder = match state.free_binders.pop() {
+            Some(binder) => binder,
+            None => inner.new_binder()?,
+        };
+        let requests = state.open.requests.clone();
+        let batch_state = state.open.state.clone();
+        let value = state.open.value;
+        let command_id = scheduler.new_commands::<NewCopyCommands>(
+            inner.queue.clone(),
+            move |cmd| {
+                let res = record_uploads(cmd, &requests);
+                if res.is_err() {
+                    batch_state.lock().resolve(UploadStatus::Failed);
+                }
+                res
+            },
+        );
+        let command_id = match command_id {
+            Ok(builder) => builder
+                .with_signal_semaphore(inner.semaphore, value)
+                .id(),
+            Err(err) => {
+                state.free_binders.push(binder);
+                return Err(err)
+            },
+        };
+        let batch = core::mem::replace(&mut state.open, Batch::new(binder, value + 1));
+        state.in_flight.push(batch);
+        state.last_value = value;
+        Ok(Some(command_id))
+    }
+}
+
+fn record_uploads(
+    cmd: &mut CopyCommands<'_, '_>,
+    requests: &[UploadRequest],
+) -> EventResult<()>
+{
+    for request in requests {
+        match request.region {
+            UploadRegion::Buffer { buffer_id, offset, size, } => {
+                cmd.copy_buffer(
+                    request.staging_id,
+                    buffer_id,
+                    &[BufferCopy::new(0, offset, size)],
+                    CommandOrdering::Lenient,
+                )?;
+            },
+            UploadRegion::Image { image_id, region, } => {
+                cmd.copy_buffer_to_image(
+                    request.staging_id,
+                    image_id,
+                    &[region],
+                    CommandOrdering::Lenient,
+                )?;
+            },
+        }
+    }
+    Ok(())
+}
+
+/// A [`Future`], which resolves once the data of an upload is resident in device memory.
+///
+/// The future is woken when [`Uploader::flush`] notices the batch of the upload has finished,
+/// so the uploader *must* keep being flushed for it to resolve.
+///
+/// The upload *can* also be waited on the GPU with [`Uploader::semaphore_id`] and
+/// [`Upload::value`].
+pub struct Upload {
+    gpu: Gpu,
+    semaphore: TimelineSemaphoreId,
+    value: u64,
+    state: Arc<Mutex<BatchState>>,
+}
+
+impl Upload {
+
+    /// Returns the value [`Uploader::semaphore_id`] is signaled with, once the upload has
+    /// finished.
+    #[inline]
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    fn poll_state(&self, state: &mut BatchState) -> Result<bool> {
+        match state.status {
+            UploadStatus::Resident => Ok(true),
+            UploadStatus::Failed => Err(Error::just_context("upload failed to record")),
+            UploadStatus::Pending => {
+                if self.gpu.get_semaphore_counter_value(self.semaphore)? >= self.value {
+                    state.resolve(UploadStatus::Resident);
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            },
+        }
+    }
+
+    /// Returns whether the data of the upload is resident in device memory.
+    #[inline]
+    pub fn is_resident(&self) -> Result<bool> {
+        self.poll_state(&mut self.state.lock())
+    }
+}
+
+impl Future for Upload {
+
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock();
+        match self.poll_state(&mut state) {
+            Ok(true) => Poll::Ready(Ok(())),
+            Ok(false) => {
+                if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    state.wakers.push(cx.waker().clone());
+                }
+                Poll::Pending
+            },
+            Err(err) => Poll::Ready(Err(err)),
+        }
+    }
+}
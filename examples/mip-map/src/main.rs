@@ -1,3 +1,5 @@
+use core::num::NonZeroU32;
+
 use image::EncodableLayout;
 use nox::{
     Platform,
@@ -5,6 +7,7 @@ use nox::{
     log,
     mem::collections::{EntryExt, HashMap},
     sync::{Arc, SwapLock, atomic::{self, AtomicU64}},
+    threads::executor::{ThreadPool, block_on},
 };
 const BLEND_STATE: gpu::ColorOutputBlendState = gpu::ColorOutputBlendState {
     src_color_blend_factor: gpu::BlendFactor::SrcAlpha,
@@ -15,29 +18,27 @@ const BLEND_STATE: gpu::ColorOutputBlendState = gpu::ColorOutputBlendState {
     alpha_blend_op: gpu::BlendOp::Add,
 };
 
+const HEADLESS_FRAMES: u32 = 3;
+const HEADLESS_EXTENT: (u32, u32) = (800, 600);
+
+/// The texture view, its staging buffer and its dimensions.
+type Texture = (gpu::ImageViewId, gpu::BufferId, u32, u32);
+
 #[inline(always)]
 pub fn load_rgba_image(path: &str) -> ::image::ImageResult<::image::ImageBuffer<::image::Rgba<u8>, Vec<u8>>> {
     let image = ::image::ImageReader::open(path)?.decode()?;
     Ok(image.to_rgba8())
 }
 
-fn main() {
-    let platform = Platform::new();
-    let instance = gpu::Instance::new(
-        &platform,
-        "test",
-        Version::new(1, 0, 0),
-        &[gpu::InstanceLayer::new(
-            gpu::LAYER_KHRONOS_VALIDATION,
-            false,
-        )],
-    ).unwrap();
+fn select_device(
+    instance: &gpu::Instance,
+) -> nox::Result<(gpu::LogicalDevice, gpu::DeviceQueue)> {
     let device_attributes = gpu
         ::default_device_attributes()
         .with_device_extension(ext::push_descriptor::Extension);
     let devices = instance.enumerate_suitable_physical_devices(
         device_attributes
-    ).unwrap();
+    )?;
     let mut idx = 0;
     for (i, device) in devices.iter() {
         if device.device_type() ==
@@ -55,19 +56,344 @@ fn main() {
         .find_map(|(i, properties)|
             (properties.queue_flags.contains(gpu::QueueFlags::GRAPHICS))
             .then_some(i as u32)
-        ).unwrap();
+        ).ok_or_else(|| nox::Error::just_context("failed to find a graphics queue family"))?;
     let queue_create_info = gpu::DeviceQueueCreateInfo::new(
         "graphics queue",
         queue_family_index,
         0
     );
     let logical_device = devices
-        .create_logical_device(idx, &[queue_create_info])
-        .unwrap();
+        .create_logical_device(idx, &[queue_create_info])?;
     log::info!("selected device: {}",
         logical_device.physical_device().device_name()
     );
     let queue = logical_device.device_queues()[0].clone();
+    Ok((logical_device, queue))
+}
+
+fn create_shader_set(gpu: &gpu::Gpu) -> nox::Result<gpu::ShaderSetId> {
+    let vertex_shader = gpu::Shader::new(
+        gpu, gpu
+            ::default_shader_attributes()
+            .with_name("vertex shader")
+            .with_stage(gpu::ShaderStage::Vertex)
+            .with_glsl("
+                #version 450
+
+                vec2 positions[6] = vec2[](
+                    vec2(1.0, 1.0),
+                    vec2(0.0, 1.0),
+                    vec2(0.0, 0.0),
+                    vec2(1.0, 0.0),
+                    vec2(1.0, 1.0),
+                    vec2(0.0, 0.0)
+
+                );
+
+                vec2 uvs[6] = vec2[](
+                    vec2(1.0, 1.0),
+                    vec2(0.0, 1.0),
+                    vec2(0.0, 0.0),
+                    vec2(1.0, 0.0),
+                    vec2(1.0, 1.0),
+                    vec2(0.0, 0.0)
+                );
+
+                layout(location = 0) out vec2 out_uv;
+
+                void main() {
+                    uint idx = gl_VertexIndex;
+                    out_uv = uvs[idx];
+                    const float scale = 0.5;
+                    gl_Position = vec4((positions[idx] - vec2(0.5, 0.5)) * scale, 0.0f, 1.0f);
+                }
+            ")
+    )?;
+    let fragment_shader = gpu::Shader::new(
+        gpu,
+        gpu::default_shader_attributes().with_name("fragment shader")
+            .with_stage(gpu::ShaderStage::Fragment)
+            .with_glsl("
+                #version 450
+
+                layout(location = 0) in vec2 in_uv;
+                layout(location = 0) out vec4 out_color;
+
+                layout(set = 0, binding = 0) uniform sampler2D tex;
+
+                void main() {
+                    out_color = texture(tex, in_uv);
+                }
+            ")
+    )?;
+    gpu.create_shader_set(
+        [vertex_shader, fragment_shader],
+        gpu::default_shader_set_attributes()
+            .with_descriptor_set_layout_flags(
+                0,
+                gpu::DescriptorSetLayoutFlags::PUSH_DESCRIPTOR,
+            )
+    )
+}
+
+fn create_pipeline(
+    gpu: &gpu::Gpu,
+    pipeline_cache: gpu::PipelineCache,
+    shader_set: gpu::ShaderSetId,
+    format: gpu::Format,
+) -> nox::Result<gpu::GraphicsPipelineId> {
+    let mut id = Default::default();
+    let mut batch = gpu.create_pipeline_batch(pipeline_cache)?;
+    batch
+        .with_graphics_pipelines([
+            gpu::GraphicsPipelineCreateInfo
+                ::new(&mut id, shader_set)
+                .with_color_output(
+                    format,
+                    gpu::ColorComponents::default(),
+                    Some(BLEND_STATE),
+                )
+        ]);
+    let _ = batch.build()?;
+    Ok(id)
+}
+
+fn create_texture(gpu: &gpu::Gpu) -> nox::Result<Texture> {
+    let staging_binder = gpu::
+        GlobalBinder::new(
+            gpu.device().clone(),
+            gpu::MemoryProperties::HOST_VISIBLE | gpu::MemoryProperties::HOST_COHERENT,
+            gpu::MemoryProperties::HOST_VISIBLE | gpu::MemoryProperties::HOST_COHERENT,
+        );
+    let view_binder = gpu::
+        GlobalBinder::new(
+            gpu.device().clone(),
+            gpu::MemoryProperties::DEVICE_LOCAL,
+            gpu::MemoryProperties::HOST_VISIBLE,
+        );
+    let new_img = load_rgba_image("ferris.png")
+        .map_err(|err| nox::Error::new(err, "failed to load ferris.png"))?;
+    let (width, height) = new_img.dimensions();
+    let mem_size = (width * height) as gpu::DeviceSize * 4;
+    let mip_levels = 32 - (width | height).leading_zeros();
+    let (mut staging_id, mut image_id) = Default::default();
+    gpu.create_resources(
+        [gpu::BufferCreateInfo::new(
+            &mut staging_id,
+            &staging_binder,
+            mem_size,
+            gpu::BufferUsages::TRANSFER_SRC,
+        ).unwrap()],
+        [gpu::ImageCreateInfo
+            ::new(&mut image_id, &view_binder)
+            .with_dimensions((width, height))
+            .with_format(gpu::Format::R8g8b8a8Srgb, false)
+            .with_usage(
+                gpu::ImageUsages::TRANSFER_DST |
+                gpu::ImageUsages::TRANSFER_SRC |
+                gpu::ImageUsages::SAMPLED)
+            .with_mip_levels(mip_levels)
+        ])?;
+    let mut map = gpu.map_buffer(staging_id)?;
+    unsafe {
+        map.write_bytes(new_img.as_bytes())
+    }
+    let view_id = gpu.create_image_view(
+        image_id,
+        gpu::ImageRange::whole_range(gpu::ImageAspects::COLOR),
+    )?;
+    Ok((view_id, staging_id, width, height))
+}
+
+/// Records the texture upload on the first frame and the textured quad draw to `attachment`.
+fn record_quad(
+    cmd: &mut gpu::GraphicsCommands<'_, '_>,
+    attachment: gpu::PassAttachment,
+    pipeline: gpu::GraphicsPipelineId,
+    (width, height): (u32, u32),
+    (view, staging, texture_width, texture_height): Texture,
+    sampler: gpu::Sampler,
+    upload: bool,
+) -> nox::EventResult<()> {
+    if upload {
+        let mut cmd = cmd.copy_commands();
+        cmd.copy_buffer_to_image(
+            staging,
+            view.image_id(),
+            &[gpu::BufferImageCopy
+                ::default()
+                .image_subresource(gpu::ImageSubresourceLayers
+                    ::default()
+                    .aspect_mask(gpu::ImageAspects::COLOR)
+                ).image_extent((texture_width, texture_height))
+            ],
+            gpu::CommandOrdering::Strict
+        )?;
+        cmd.gen_mip_map(view.image_id(), gpu::Filter::Linear)?;
+    }
+    cmd.render(
+        gpu::RenderingInfo::default(),
+        &[
+            attachment
+                .with_load_op(gpu::AttachmentLoadOp::Clear)
+                .with_clear_value(
+                    gpu::ClearColorValue::Float([0.01, 0.01, 0.01, 0.5])
+                ),
+        ],
+        &gpu::DepthStencilAttachment::None,
+        |pass| {
+            pass.dynamic_draw(|cmd| {
+                let mut pipeline_cmd = cmd.bind_pipeline(
+                    pipeline,
+                    &[gpu::Viewport
+                        ::default()
+                        .width(width as f32)
+                        .height(height as f32)
+                    ],
+                    &[gpu::Scissor
+                        ::default()
+                        .width(width)
+                        .height(height)
+                    ],
+                )?;
+                pipeline_cmd.push_descriptor_bindings(&[
+                    gpu::PushDescriptorBinding::new(
+                        c"tex",
+                        0,
+                        gpu::DescriptorInfos::images(&[
+                            gpu::DescriptorImageInfo {
+                                sampler: Some(sampler.clone()),
+                                image_view: Some(view),
+                            },
+                        ]),
+                        gpu::CommandBarrierInfo::new(
+                            gpu::CommandOrdering::Strict,
+                            gpu::ExplicitAccess::SHADER_READ,
+                        )
+                    )?,
+                ])?;
+                pipeline_cmd.begin_drawing(
+                    gpu::DrawInfo
+                        ::default()
+                        .vertex_count(6),
+                        &[], None,
+                        |cmd| {
+                            cmd.draw()?;
+                            Ok(())
+                        }
+                    )?;
+                Ok(())
+            })?;
+            Ok(())
+        }
+    )?;
+    Ok(())
+}
+
+fn create_sampler(device: &gpu::LogicalDevice) -> nox::Result<gpu::Sampler> {
+    gpu::SamplerCreateInfo
+        ::default()
+        .min_filter(gpu::Filter::Linear)
+        .mag_filter(gpu::Filter::Linear)
+        .max_lod(gpu::Sampler::LOD_CLAMP_NONE)
+        .address_mode(
+            gpu::SamplerAddressMode::ClampToBorder,
+            gpu::SamplerAddressMode::ClampToBorder,
+            gpu::SamplerAddressMode::ClampToBorder,
+        ).build(device.clone())
+}
+
+/// Renders [`HEADLESS_FRAMES`] frames without a window and writes the last one to
+/// `mip-map.png`.
+fn run_headless() -> nox::Result<()> {
+    let instance = gpu::Instance::headless(
+        "test",
+        Version::new(1, 0, 0),
+        &[gpu::InstanceLayer::new(
+            gpu::LAYER_KHRONOS_VALIDATION,
+            false,
+        )],
+        None,
+    )?;
+    let (logical_device, queue) = select_device(&instance)?;
+    let sampler = create_sampler(&logical_device)?;
+    let gpu = gpu::Gpu::standalone(
+        logical_device,
+        ThreadPool::new()?,
+        Default::default(),
+        NonZeroU32::new(2).unwrap(),
+    )?;
+    let mut cache = gpu::create_cache(Default::default());
+    let target_info = gpu::HeadlessTargetInfo
+        ::default()
+        .dimensions(HEADLESS_EXTENT);
+    let mut target = gpu::HeadlessTarget::new(gpu.clone(), target_info)?;
+    let shader_set = create_shader_set(&gpu)?;
+    let pipeline = create_pipeline(
+        &gpu,
+        gpu::PipelineCache::new(gpu.device().clone(), None)?,
+        shader_set,
+        target_info.format,
+    )?;
+    let texture = create_texture(&gpu)?;
+    let mut readback = None;
+    for frame in 0..HEADLESS_FRAMES {
+        let mut commands = gpu.schedule_commands();
+        let image = target.acquire(&commands)?;
+        let extent = (image.dimensions.width, image.dimensions.height);
+        let sampler = sampler.clone();
+        let command_id = commands
+            .new_commands::<gpu::NewGraphicsCommands>(
+                queue.clone(),
+                move |cmd| {
+                    record_quad(
+                        cmd,
+                        gpu::PassAttachment::new(image.color_view),
+                        pipeline,
+                        extent,
+                        texture,
+                        sampler,
+                        frame == 0,
+                    )
+                },
+            )?.id();
+        if frame + 1 == HEADLESS_FRAMES {
+            readback = Some(target.present(&mut commands, command_id)?);
+        }
+        drop(commands);
+        gpu.tick(|_| Ok(()), &mut cache)?;
+    }
+    let readback = readback.unwrap();
+    while !readback.is_ready() {
+        gpu.tick(|_| Ok(()), &mut cache)?;
+    }
+    let readback = block_on(readback)?;
+    let (width, height) = HEADLESS_EXTENT;
+    ::image::RgbaImage
+        ::from_raw(width, height, readback.into_bytes())
+        .ok_or_else(|| nox::Error::just_context("read back image has an invalid size"))?
+        .save("mip-map.png")
+        .map_err(|err| nox::Error::new(err, "failed to write mip-map.png"))?;
+    log::info!("wrote frame {HEADLESS_FRAMES} to mip-map.png");
+    Ok(())
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--headless") {
+        run_headless().unwrap();
+        return
+    }
+    let platform = Platform::new();
+    let instance = gpu::Instance::new(
+        &platform,
+        "test",
+        Version::new(1, 0, 0),
+        &[gpu::InstanceLayer::new(
+            gpu::LAYER_KHRONOS_VALIDATION,
+            false,
+        )],
+    ).unwrap();
+    let (logical_device, queue) = select_device(&instance).unwrap();
     let globals = nox::create_globals();
     let window = globals.add(|event_loop| {
         Ok(event_loop.create_window(
@@ -83,116 +409,10 @@ fn main() {
         )?)
     });
     let shader_set = globals.add(|event_loop| {
-        let vertex_shader = gpu::Shader::new(
-            event_loop.gpu(), gpu
-                ::default_shader_attributes()
-                .with_name("vertex shader")
-                .with_stage(gpu::ShaderStage::Vertex)
-                .with_glsl("
-                    #version 450
-
-                    vec2 positions[6] = vec2[](
-                        vec2(1.0, 1.0),
-                        vec2(0.0, 1.0),
-                        vec2(0.0, 0.0),
-                        vec2(1.0, 0.0),
-                        vec2(1.0, 1.0),
-                        vec2(0.0, 0.0)
-
-                    );
-
-                    vec2 uvs[6] = vec2[](
-                        vec2(1.0, 1.0),
-                        vec2(0.0, 1.0),
-                        vec2(0.0, 0.0),
-                        vec2(1.0, 0.0),
-                        vec2(1.0, 1.0),
-                        vec2(0.0, 0.0)
-                    );
-
-                    layout(location = 0) out vec2 out_uv;
-
-                    void main() {
-                        uint idx = gl_VertexIndex;
-                        out_uv = uvs[idx];
-                        const float scale = 0.5;
-                        gl_Position = vec4((positions[idx] - vec2(0.5, 0.5)) * scale, 0.0f, 1.0f);
-                    }
-                ")
-            )?;
-            let fragment_shader = gpu::Shader::new(
-                event_loop.gpu(),
-                gpu::default_shader_attributes().with_name("fragment shader")
-                    .with_stage(gpu::ShaderStage::Fragment)
-                    .with_glsl("
-                        #version 450
-
-                        layout(location = 0) in vec2 in_uv;
-                        layout(location = 0) out vec4 out_color;
-
-                        layout(set = 0, binding = 0) uniform sampler2D tex;
-
-                        void main() {
-                            out_color = texture(tex, in_uv);
-                        }
-                    ")
-            )?;
-            Ok(event_loop
-                .gpu()
-                .create_shader_set(
-                    [vertex_shader, fragment_shader],
-                    gpu::default_shader_set_attributes()
-                        .with_descriptor_set_layout_flags(
-                            0,
-                            gpu::DescriptorSetLayoutFlags::PUSH_DESCRIPTOR,
-                        )
-                )?)
+        Ok(create_shader_set(event_loop.gpu())?)
     });
     let image = globals.add(|event_loop| {
-        let staging_binder = gpu::
-            GlobalBinder::new(
-                event_loop.gpu().device().clone(),
-                gpu::MemoryProperties::HOST_VISIBLE | gpu::MemoryProperties::HOST_COHERENT,
-                gpu::MemoryProperties::HOST_VISIBLE | gpu::MemoryProperties::HOST_COHERENT,
-            );
-        let view_binder = gpu::
-            GlobalBinder::new(
-                event_loop.gpu().device().clone(),
-                gpu::MemoryProperties::DEVICE_LOCAL,
-                gpu::MemoryProperties::HOST_VISIBLE,
-            );
-        let new_img = load_rgba_image("ferris.png").unwrap();
-        let gpu = event_loop.gpu();
-        let (width, height) = new_img.dimensions();
-        let mem_size = (width * height) as gpu::DeviceSize * 4;
-        let mip_levels = 32 - (width | height).leading_zeros();
-        let (mut staging_id, mut image_id) = Default::default();
-        gpu.create_resources(
-            [gpu::BufferCreateInfo::new(
-                &mut staging_id,
-                &staging_binder,
-                mem_size,
-                gpu::BufferUsages::TRANSFER_SRC,
-            ).unwrap()],
-            [gpu::ImageCreateInfo
-                ::new(&mut image_id, &view_binder)
-                .with_dimensions((width, height))
-                .with_format(gpu::Format::R8g8b8a8Srgb, false)
-                .with_usage(
-                    gpu::ImageUsages::TRANSFER_DST |
-                    gpu::ImageUsages::TRANSFER_SRC |
-                    gpu::ImageUsages::SAMPLED)
-                .with_mip_levels(mip_levels)
-            ])?;
-        let mut map = gpu.map_buffer(staging_id)?;
-        unsafe {
-            map.write_bytes(new_img.as_bytes())
-        }
-        let view_id = gpu.create_image_view(
-            image_id,
-            gpu::ImageRange::whole_range(gpu::ImageAspects::COLOR),
-        )?;
-        Ok((view_id, staging_id, width, height))
+        Ok(create_texture(event_loop.gpu())?)
     });
     let timeline_semaphore = globals.add(|event_loop| {
         let mut id = Default::default();
@@ -208,16 +428,7 @@ fn main() {
         extent: AtomicU64,
     }
     let fb_state: Arc<FrameBufferState> = Default::default();
-    let sampler = gpu::SamplerCreateInfo
-        ::default()
-        .min_filter(gpu::Filter::Linear)
-        .mag_filter(gpu::Filter::Linear)
-        .max_lod(gpu::Sampler::LOD_CLAMP_NONE)
-        .address_mode(
-            gpu::SamplerAddressMode::ClampToBorder,
-            gpu::SamplerAddressMode::ClampToBorder,
-            gpu::SamplerAddressMode::ClampToBorder,
-        ).build(logical_device.clone()).unwrap();
+    let sampler = create_sampler(&logical_device).unwrap();
     nox::Nox::new(
         platform,
         logical_device,
@@ -231,96 +442,34 @@ fn main() {
                         event_loop.exit();
                     }
                     let mut commands = event_loop.gpu().schedule_commands();
-                    let (view, staging, width, height) = *image;
+                    let texture = *image;
                     let fb_state = fb_state.clone();
                     let sampler = sampler.clone();
+                    let upload = timeline_value == 3;
                     commands.new_commands::<gpu::NewGraphicsCommands>(
                         queue.clone(),
                         move |cmd| {
-                            if timeline_value == 3 {
-                                let mut cmd = cmd.copy_commands();
-                                cmd.copy_buffer_to_image(
-                                    staging,
-                                    view.image_id(),
-                                    &[gpu::BufferImageCopy
-                                        ::default()
-                                        .image_subresource(gpu::ImageSubresourceLayers
-                                            ::default()
-                                            .aspect_mask(gpu::ImageAspects::COLOR)
-                                        ).image_extent((width, height))
-                                    ],
-                                    gpu::CommandOrdering::Strict
-                                )?;
-                                cmd.gen_mip_map(view.image_id(), gpu::Filter::Linear)?;
-                            }
                             let (image_view, format) = cmd.swapchain_image_view(
                                 window.surface_id()
                             )?;
-                            cmd.render(
-                                gpu::RenderingInfo::default(),
-                                &[
-                                    gpu::PassAttachment
-                                        ::new(image_view)
-                                        .with_load_op(gpu::AttachmentLoadOp::Clear)
-                                        .with_clear_value(
-                                            gpu::ClearColorValue::Float([0.01, 0.01, 0.01, 0.5])
-                                        ),
-                                ],
-                                &gpu::DepthStencilAttachment::None,
-                                |pass| {
-                                    let frame_buffer_size = fb_state.extent.load(
-                                        atomic::Ordering::Acquire
-                                    );
-                                    let (width, height) = (
-                                        (frame_buffer_size & 0xFFFFFFFF) as u32,
-                                        (frame_buffer_size >> 32) as u32,
-                                    );
-                                    pass.dynamic_draw(|cmd| {
-                                        let mut pipeline_cmd = cmd.bind_pipeline(
-                                            *fb_state.pipelines.load().get(&format).unwrap(),
-                                            &[gpu::Viewport
-                                                ::default()
-                                                .width(width as f32)
-                                                .height(height as f32)
-                                            ],
-                                            &[gpu::Scissor
-                                                ::default()
-                                                .width(width)
-                                                .height(height)
-                                            ],
-                                        )?;
-                                        pipeline_cmd.push_descriptor_bindings(&[
-                                            gpu::PushDescriptorBinding::new(
-                                                c"tex",
-                                                0,
-                                                gpu::DescriptorInfos::images(&[
-                                                    gpu::DescriptorImageInfo {
-                                                        sampler: Some(sampler.clone()),
-                                                        image_view: Some(view),
-                                                    },
-                                                ]),
-                                                gpu::CommandBarrierInfo::new(
-                                                    gpu::CommandOrdering::Strict,
-                                                    gpu::ExplicitAccess::SHADER_READ,
-                                                )
-                                            )?,
-                                        ])?;
-                                        pipeline_cmd.begin_drawing(
-                                            gpu::DrawInfo
-                                                ::default()
-                                                .vertex_count(6),
-                                                &[], None,
-                                                |cmd| {
-                                                    cmd.draw()?;
-                                                    Ok(())
-                                                }
-                                            )?;
-                                        Ok(())
-                                    })?;
-                                    Ok(())
-                                }
-                            )?;
-                            Ok(())
+                            let attachment = gpu::PassAttachment::new(image_view);
+                            let pipeline = *fb_state.pipelines.load().get(&format).unwrap();
+                            let frame_buffer_size = fb_state.extent.load(
+                                atomic::Ordering::Acquire
+                            );
+                            let extent = (
+                                (frame_buffer_size & 0xFFFFFFFF) as u32,
+                                (frame_buffer_size >> 32) as u32,
+                            );
+                            record_quad(
+                                cmd,
+                                attachment,
+                                pipeline,
+                                extent,
+                                texture,
+                                sampler,
+                                upload,
+                            )
                         },
                     )?.with_wait_semaphore(
                         *timeline_semaphore,
@@ -348,26 +497,16 @@ fn main() {
                                     pipelines
                                         .entry(new_format)
                                         .or_try_insert_with_key(|&format| {
-                                            let mut id = Default::default();
-                                            let mut batch = event_loop
-                                                .gpu()
-                                                .create_pipeline_batch(pipeline_cache.clone())?;
-                                            batch 
-                                                .with_graphics_pipelines([
-                                                    gpu::GraphicsPipelineCreateInfo
-                                                        ::new(&mut id, *shader_set)
-                                                        .with_color_output(
-                                                            format,
-                                                            gpu::ColorComponents::default(),
-                                                            Some(BLEND_STATE),
-                                                        )
-                                                ]);
-                                            let _ = batch.build()?;
-                                            nox::error::Result::Ok(id)
+                                            create_pipeline(
+                                                event_loop.gpu(),
+                                                pipeline_cache.clone(),
+                                                *shader_set,
+                                                format,
+                                            )
                                         })?;
                                     nox::Result::Ok(())
                                 })?;
-                            } 
+                            }
                             Ok(())
                         },
                     }
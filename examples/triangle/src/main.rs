@@ -1,13 +1,25 @@
-use core::f32::consts::{PI, TAU, FRAC_PI_3};
+use core::{
+    f32::consts::{PI, TAU, FRAC_PI_3},
+    num::NonZeroU32,
+};
+
+use std::{fs, io::Write};
 
 use nox::{
     Platform,
     gpu::{self, ext},
     mem::collections::{EntryExt, HashMap},
     sync::{Arc, SwapLock, atomic::{self, AtomicU64}},
+    threads::executor::{ThreadPool, block_on},
     log,
 };
 
+const SAT: f32 = 94.0 / 100.0;
+const VAL: f32 = 97.0 / 100.0;
+
+const HEADLESS_FRAMES: u32 = 60;
+const HEADLESS_EXTENT: (u32, u32) = (800, 600);
+
 fn hsva_to_srgb_pack32(hue: f32, sat: f32, val: f32) -> u32 {
     let map = |n: f32| -> f32 {
         let k = (n + hue / FRAC_PI_3) % 6.0;
@@ -27,20 +39,15 @@ fn hsva_to_srgb_pack32(hue: f32, sat: f32, val: f32) -> u32 {
     ])
 }
 
-fn main() {
-    let platform = Platform::new();
-    let instance = gpu::Instance::new(
-        &platform,
-        "test",
-        gpu::Version::new(1, 0, 0),
-        &[gpu::InstanceLayer::new(gpu::LAYER_KHRONOS_VALIDATION, false)],
-    ).unwrap();
+fn select_device(
+    instance: &gpu::Instance,
+) -> nox::Result<(gpu::LogicalDevice, gpu::DeviceQueue)> {
     let device_attributes = gpu
         ::default_device_attributes()
         .with_device_extension(ext::push_descriptor::Extension);
     let devices = instance.enumerate_suitable_physical_devices(
         device_attributes
-    ).unwrap();
+    )?;
     let mut idx = 0;
     for (i, device) in devices.iter() {
         if device.device_type() ==
@@ -58,22 +65,316 @@ fn main() {
         .find_map(|(i, properties)|
             (properties.queue_flags.contains(gpu::QueueFlags::GRAPHICS))
             .then_some(i as u32)
-        ).unwrap();
+        ).ok_or_else(|| nox::Error::just_context("failed to find a graphics queue family"))?;
     let queue_create_info = gpu::DeviceQueueCreateInfo::new(
         "graphics queue",
         queue_family_index,
         0
     );
     let logical_device = devices
-        .create_logical_device(idx, &[queue_create_info])
-        .unwrap();
+        .create_logical_device(idx, &[queue_create_info])?;
     log::info!("selected device: {}",
         logical_device.physical_device().device_name()
     );
-    let min_uniform_buffer_offset_alignment = logical_device
-        .physical_device()
-        .limits().min_uniform_buffer_offset_alignment;
     let queue = logical_device.device_queues()[0].clone();
+    Ok((logical_device, queue))
+}
+
+fn create_shader_set(gpu: &gpu::Gpu) -> nox::Result<gpu::ShaderSetId> {
+    let vertex_shader = gpu::Shader::new(
+        gpu, gpu
+            ::default_shader_attributes()
+            .with_name("vertex shader")
+            .with_stage(gpu::ShaderStage::Vertex)
+            .with_glsl("
+                #version 450
+
+                const vec2 positions[3] = {
+                    vec2(-0.5f, 0.5f),
+                    vec2(0.0f, -0.5f),
+                    vec2(0.5f, 0.5f)
+                };
+
+                layout(set = 0, binding = 0) uniform Colors {
+                    uvec3 data;
+                } colors;
+
+                layout(location = 0) out vec4 out_color;
+
+                vec4 unpack_color(uint color) {
+                    return vec4(
+                        (color & 0xFF) / 255.0f,
+                        ((color >> 8) & 0xFF) / 255.0f,
+                        ((color >> 16) & 0xFF) / 255.0f,
+                        1.0f
+                    );
+                }
+
+                void main() {
+                    uint idx = gl_VertexIndex;
+                    out_color = unpack_color(colors.data[idx]);
+                    gl_Position = vec4(positions[idx], 0.0, 1.0f);
+                }
+            ")
+    )?;
+    let fragment_shader = gpu::Shader::new(
+        gpu,
+        gpu::default_shader_attributes().with_name("fragment shader")
+            .with_stage(gpu::ShaderStage::Fragment)
+            .with_glsl("
+                #version 450
+
+                layout(location = 0) in vec4 in_color;
+                layout(location = 0) out vec4 out_color;
+
+                void main() {
+                    out_color = in_color;
+                }
+            ")
+    )?;
+    gpu.create_shader_set(
+        [vertex_shader, fragment_shader],
+        gpu::default_shader_set_attributes()
+            .with_descriptor_set_layout_flags(
+                0,
+                gpu::DescriptorSetLayoutFlags::PUSH_DESCRIPTOR,
+            )
+    )
+}
+
+fn create_pipeline(
+    gpu: &gpu::Gpu,
+    pipeline_cache: gpu::PipelineCache,
+    shader_set: gpu::ShaderSetId,
+    format: gpu::Format,
+) -> nox::Result<gpu::GraphicsPipelineId> {
+    let mut id = Default::default();
+    let mut batch = gpu.create_pipeline_batch(pipeline_cache)?;
+    batch
+        .with_graphics_pipelines([
+            gpu::GraphicsPipelineCreateInfo
+                ::new(&mut id, shader_set)
+                .with_color_output(
+                    format,
+                    gpu::ColorComponents::default(),
+                    None,
+                )
+        ]);
+    let _ = batch.build()?;
+    Ok(id)
+}
+
+fn create_color_buffer(
+    gpu: &gpu::Gpu,
+    color_stride: gpu::DeviceSize,
+) -> nox::Result<gpu::BufferId> {
+    let mut id = Default::default();
+    let memory_binder = gpu::
+        GlobalBinder::new(
+            gpu.device().clone(),
+            gpu::MemoryProperties::DEVICE_LOCAL,
+            gpu::MemoryProperties::HOST_VISIBLE
+        );
+    gpu.create_resources(
+        [gpu::BufferCreateInfo::new(
+            &mut id,
+            &memory_binder,
+            color_stride * 3,
+            gpu::BufferUsages::UNIFORM_BUFFER |
+            gpu::BufferUsages::TRANSFER_DST,
+        ).unwrap()],
+        [],
+    )?;
+    Ok(id)
+}
+
+/// Records the color update and the triangle draw to `attachment`.
+fn record_triangle(
+    cmd: &mut gpu::GraphicsCommands<'_, '_>,
+    attachment: gpu::PassAttachment,
+    pipeline: gpu::GraphicsPipelineId,
+    (width, height): (u32, u32),
+    buffer_id: gpu::BufferId,
+    buffer_offset: gpu::DeviceSize,
+    colors: [u32; 3],
+) -> nox::EventResult<()> {
+    let mut copy_cmd = cmd.copy_commands();
+    copy_cmd.update_buffer(
+        buffer_id,
+        buffer_offset,
+        &colors,
+        gpu::CommandOrdering::Lenient,
+    )?;
+    cmd.render(
+        gpu::RenderingInfo::default(),
+        &[
+            attachment
+                .with_load_op(gpu::AttachmentLoadOp::Clear)
+                .with_clear_value(
+                    gpu::ClearColorValue::Float([0.01, 0.01, 0.01, 0.5])
+                ),
+        ],
+        &gpu::DepthStencilAttachment::None,
+        |pass| {
+            pass.dynamic_draw(|cmd| {
+                let mut pipeline_cmd = cmd.bind_pipeline(
+                    pipeline,
+                    &[gpu::Viewport
+                        ::default()
+                        .width(width as f32)
+                        .height(height as f32)
+                    ],
+                    &[gpu::Scissor
+                        ::default()
+                        .width(width)
+                        .height(height)
+                    ],
+                )?;
+                pipeline_cmd.push_descriptor_bindings(&[
+                    gpu::PushDescriptorBinding::new(
+                        c"colors",
+                        0,
+                        gpu::DescriptorInfos::buffers(&[
+                            gpu::DescriptorBufferInfo::default()
+                            .buffer_id(buffer_id)
+                            .offset(buffer_offset)
+                            .size(12)
+                        ]),
+                        gpu::CommandBarrierInfo::new(
+                            gpu::CommandOrdering::Strict,
+                            gpu::ExplicitAccess::SHADER_READ,
+                        )
+                    )?,
+                ])?;
+                pipeline_cmd.begin_drawing(
+                    gpu::DrawInfo
+                        ::default()
+                        .vertex_count(3),
+                        &[], None,
+                        |cmd| {
+                            cmd.draw()?;
+                            Ok(())
+                        }
+                    )?;
+                Ok(())
+            })?;
+            Ok(())
+        }
+    )?;
+    Ok(())
+}
+
+/// Renders [`HEADLESS_FRAMES`] frames without a window and writes the last one to
+/// `triangle.ppm`.
+fn run_headless() -> nox::Result<()> {
+    let instance = gpu::Instance::headless(
+        "test",
+        gpu::Version::new(1, 0, 0),
+        &[gpu::InstanceLayer::new(gpu::LAYER_KHRONOS_VALIDATION, false)],
+        None,
+    )?;
+    let (logical_device, queue) = select_device(&instance)?;
+    let color_stride = 12.max(logical_device
+        .physical_device()
+        .limits().min_uniform_buffer_offset_alignment
+    );
+    let gpu = gpu::Gpu::standalone(
+        logical_device,
+        ThreadPool::new()?,
+        Default::default(),
+        NonZeroU32::new(2).unwrap(),
+    )?;
+    let mut cache = gpu::create_cache(Default::default());
+    let target_info = gpu::HeadlessTargetInfo
+        ::default()
+        .dimensions(HEADLESS_EXTENT);
+    let mut target = gpu::HeadlessTarget::new(gpu.clone(), target_info)?;
+    let shader_set = create_shader_set(&gpu)?;
+    let pipeline = create_pipeline(
+        &gpu,
+        gpu::PipelineCache::new(gpu.device().clone(), None)?,
+        shader_set,
+        target_info.format,
+    )?;
+    let buffer_id = create_color_buffer(&gpu, color_stride)?;
+    let mut timeline_semaphore = Default::default();
+    gpu.create_timeline_semaphores([(&mut timeline_semaphore, 3)])?;
+    let mut timeline_value = 3;
+    let mut hues = [0.0, PI / 3.0, 2.0 * PI / 3.0];
+    let mut readback = None;
+    for frame in 0..HEADLESS_FRAMES {
+        let mut commands = gpu.schedule_commands();
+        let image = target.acquire(&commands)?;
+        let colors = hues.map(|hue| hsva_to_srgb_pack32(hue, SAT, VAL));
+        let buffer_offset = timeline_value % 3 * color_stride;
+        let extent = (image.dimensions.width, image.dimensions.height);
+        let command_id = commands
+            .new_commands::<gpu::NewGraphicsCommands>(
+                queue.clone(),
+                move |cmd| {
+                    record_triangle(
+                        cmd,
+                        gpu::PassAttachment::new(image.color_view),
+                        pipeline,
+                        extent,
+                        buffer_id,
+                        buffer_offset,
+                        colors,
+                    )
+                },
+            )?.with_wait_semaphore(
+                timeline_semaphore,
+                timeline_value - 2,
+                gpu::MemoryDependencyHint::TRANSFER,
+            ).with_signal_semaphore(
+                timeline_semaphore,
+                timeline_value + 1,
+            ).id();
+        if frame + 1 == HEADLESS_FRAMES {
+            readback = Some(target.present(&mut commands, command_id)?);
+        }
+        drop(commands);
+        gpu.tick(|_| Ok(()), &mut cache)?;
+        timeline_value += 1;
+        for hue in &mut hues {
+            *hue = (*hue + 1.0 / 60.0) % TAU;
+        }
+    }
+    let readback = readback.unwrap();
+    while !readback.is_ready() {
+        gpu.tick(|_| Ok(()), &mut cache)?;
+    }
+    let readback = block_on(readback)?;
+    let (width, height) = HEADLESS_EXTENT;
+    let mut file = fs::File::create("triangle.ppm")
+        .map_err(|err| nox::Error::new(err, "failed to create triangle.ppm"))?;
+    let mut ppm = format!("P6\n{width} {height}\n255\n").into_bytes();
+    for texel in readback.bytes().chunks_exact(4) {
+        ppm.extend_from_slice(&texel[..3]);
+    }
+    file.write_all(&ppm)
+        .map_err(|err| nox::Error::new(err, "failed to write triangle.ppm"))?;
+    log::info!("wrote frame {HEADLESS_FRAMES} to triangle.ppm");
+    Ok(())
+}
+
+fn main() {
+    if std::env::args().any(|arg| arg == "--headless") {
+        run_headless().unwrap();
+        return
+    }
+    let platform = Platform::new();
+    let instance = gpu::Instance::new(
+        &platform,
+        "test",
+        gpu::Version::new(1, 0, 0),
+        &[gpu::InstanceLayer::new(gpu::LAYER_KHRONOS_VALIDATION, false)],
+    ).unwrap();
+    let (logical_device, queue) = select_device(&instance).unwrap();
+    let color_stride = 12.max(logical_device
+        .physical_device()
+        .limits().min_uniform_buffer_offset_alignment
+    );
     let globals = nox::create_globals();
     let window = globals.add(|event_loop| {
         Ok(event_loop.create_window(
@@ -89,89 +390,10 @@ fn main() {
         )?)
     });
     let shader_set = globals.add(|event_loop| {
-        let vertex_shader = gpu::Shader::new(
-            event_loop.gpu(), gpu
-                ::default_shader_attributes()
-                .with_name("vertex shader")
-                .with_stage(gpu::ShaderStage::Vertex)
-                .with_glsl("
-                    #version 450
-
-                    const vec2 positions[3] = {
-                        vec2(-0.5f, 0.5f),
-                        vec2(0.0f, -0.5f),
-                        vec2(0.5f, 0.5f)
-                    };
-
-                    layout(set = 0, binding = 0) uniform Colors {
-                        uvec3 data;
-                    } colors;
-
-                    layout(location = 0) out vec4 out_color;
-
-                    vec4 unpack_color(uint color) {
-                        return vec4(
-                            (color & 0xFF) / 255.0f,
-                            ((color >> 8) & 0xFF) / 255.0f,
-                            ((color >> 16) & 0xFF) / 255.0f,
-                            1.0f
-                        );
-                    }
-
-                    void main() {
-                        uint idx = gl_VertexIndex;
-                        out_color = unpack_color(colors.data[idx]);
-                        gl_Position = vec4(positions[idx], 0.0, 1.0f);
-                    }
-                ")
-            )?;
-            let fragment_shader = gpu::Shader::new(
-                event_loop.gpu(),
-                gpu::default_shader_attributes().with_name("fragment shader")
-                    .with_stage(gpu::ShaderStage::Fragment)
-                    .with_glsl("
-                        #version 450
-
-                        layout(location = 0) in vec4 in_color;
-                        layout(location = 0) out vec4 out_color;
-
-                        void main() {
-                            out_color = in_color;
-                        }
-                    ")
-            )?;
-            Ok(event_loop
-                .gpu()
-                .create_shader_set(
-                    [vertex_shader, fragment_shader],
-                    gpu::default_shader_set_attributes()
-                        .with_descriptor_set_layout_flags(
-                            0,
-                            gpu::DescriptorSetLayoutFlags::PUSH_DESCRIPTOR,
-                        )
-                )?)
+        Ok(create_shader_set(event_loop.gpu())?)
     });
     let buffer = globals.add(|event_loop| {
-        let mut id = Default::default();
-        let memory_binder = gpu::
-            GlobalBinder::new(
-                event_loop.gpu().device().clone(),
-                gpu::MemoryProperties::DEVICE_LOCAL,
-                gpu::MemoryProperties::HOST_VISIBLE
-            );
-        event_loop
-            .gpu()
-            .create_resources(
-                [gpu::BufferCreateInfo::new(
-                    &mut id, 
-                    &memory_binder,
-                    12.max(min_uniform_buffer_offset_alignment) * 3,
-                    gpu::BufferUsages::UNIFORM_BUFFER |
-                    gpu::BufferUsages::TRANSFER_DST,
-                ).unwrap()],
-                [],
-            )?;
-        Ok(id)
+        Ok(create_color_buffer(event_loop.gpu(), color_stride)?)
     });
     let timeline_semaphore = globals.add(|event_loop| {
         let mut id = Default::default();
@@ -188,8 +410,6 @@ fn main() {
     }
     let fb_state: Arc<FrameBufferState> = Default::default();
     let mut hues = [0.0, PI / 3.0, 2.0 * PI / 3.0];
-    let sat: f32 = 94.0 / 100.0;
-    let val: f32 = 97.0 / 100.0;
     nox::Nox::new(
         platform,
         logical_device,
@@ -205,91 +425,32 @@ fn main() {
                     let mut commands = event_loop.gpu().schedule_commands();
                     let buffer_id = *buffer;
                     let fb_state = fb_state.clone();
+                    let colors = hues.map(|hue| hsva_to_srgb_pack32(hue, SAT, VAL));
+                    let buffer_offset = timeline_value % 3 * color_stride;
                     commands.new_commands::<gpu::NewGraphicsCommands>(
                         queue.clone(),
                         move |cmd| {
-                            let mut copy_cmd = cmd.copy_commands();
-                            let buffer_offset = timeline_value % 3 * 12.max(
-                                min_uniform_buffer_offset_alignment
-                            );
-                            copy_cmd.update_buffer(
-                                buffer_id,
-                                buffer_offset,
-                                &[
-                                    hsva_to_srgb_pack32(hues[0], sat, val),
-                                    hsva_to_srgb_pack32(hues[1], sat, val),
-                                    hsva_to_srgb_pack32(hues[2], sat, val),
-                                ],
-                                gpu::CommandOrdering::Lenient,
-                            )?;
                             let (image_view, format) = cmd.swapchain_image_view(
                                 window.surface_id()
                             )?;
-                            cmd.render(
-                                gpu::RenderingInfo::default(),
-                                &[
-                                    gpu::PassAttachment
-                                        ::new(image_view)
-                                        .with_load_op(gpu::AttachmentLoadOp::Clear)
-                                        .with_clear_value(
-                                            gpu::ClearColorValue::Float([0.01, 0.01, 0.01, 0.5])
-                                        ),
-                                ],
-                                &gpu::DepthStencilAttachment::None,
-                                |pass| {
-                                    let frame_buffer_size = fb_state.extent.load(
-                                        atomic::Ordering::Acquire
-                                    );
-                                    let (width, height) = (
-                                        (frame_buffer_size & 0xFFFFFFFF) as u32,
-                                        (frame_buffer_size >> 32) as u32,
-                                    );
-                                    pass.dynamic_draw(|cmd| {
-                                        let mut pipeline_cmd = cmd.bind_pipeline(
-                                            *fb_state.pipelines.load().get(&format).unwrap(),
-                                            &[gpu::Viewport
-                                                ::default()
-                                                .width(width as f32)
-                                                .height(height as f32)
-                                            ],
-                                            &[gpu::Scissor
-                                                ::default()
-                                                .width(width)
-                                                .height(height)
-                                            ],
-                                        )?;
-                                        pipeline_cmd.push_descriptor_bindings(&[
-                                            gpu::PushDescriptorBinding::new(
-                                                c"colors",
-                                                0,
-                                                gpu::DescriptorInfos::buffers(&[
-                                                    gpu::DescriptorBufferInfo::default()
-                                                    .buffer_id(buffer_id)
-                                                    .offset(buffer_offset)
-                                                    .size(12)
-                                                ]),
-                                                gpu::CommandBarrierInfo::new(
-                                                    gpu::CommandOrdering::Strict,
-                                                    gpu::ExplicitAccess::SHADER_READ,
-                                                )
-                                            )?,
-                                        ])?;
-                                        pipeline_cmd.begin_drawing(
-                                            gpu::DrawInfo
-                                                ::default()
-                                                .vertex_count(3),
-                                                &[], None,
-                                                |cmd| {
-                                                    cmd.draw()?;
-                                                    Ok(())
-                                                }
-                                            )?;
-                                        Ok(())
-                                    })?;
-                                    Ok(())
-                                }
-                            )?;
-                            Ok(())
+                            let attachment = gpu::PassAttachment::new(image_view);
+                            let pipeline = *fb_state.pipelines.load().get(&format).unwrap();
+                            let frame_buffer_size = fb_state.extent.load(
+                                atomic::Ordering::Acquire
+                            );
+                            let extent = (
+                                (frame_buffer_size & 0xFFFFFFFF) as u32,
+                                (frame_buffer_size >> 32) as u32,
+                            );
+                            record_triangle(
+                                cmd,
+                                attachment,
+                                pipeline,
+                                extent,
+                                buffer_id,
+                                buffer_offset,
+                                colors,
+                            )
                         },
                     )?.with_wait_semaphore(
                         *timeline_semaphore,
@@ -319,26 +480,16 @@ fn main() {
                                     pipelines
                                         .entry(new_format)
                                         .or_try_insert_with_key(|&format| {
-                                            let mut id = Default::default();
-                                            let mut batch = event_loop
-                                                .gpu()
-                                                .create_pipeline_batch(pipeline_cache.clone())?;
-                                            batch 
-                                                .with_graphics_pipelines([
-                                                    gpu::GraphicsPipelineCreateInfo
-                                                        ::new(&mut id, *shader_set)
-                                                        .with_color_output(
-                                                            format,
-                                                            gpu::ColorComponents::default(),
-                                                            None,
-                                                        )
-                                                ]);
-                                            let _ = batch.build()?;
-                                            nox::error::Result::Ok(id)
+                                            create_pipeline(
+                                                event_loop.gpu(),
+                                                pipeline_cache.clone(),
+                                                *shader_set,
+                                                format,
+                                            )
                                         })?;
                                     nox::Result::Ok(())
                                 })?;
-                            } 
+                            }
                             Ok(())
                         },
                     }